@@ -0,0 +1,81 @@
+// benches/batch_processing.rs
+use capture_engine::capture_engine::capture::PacketProcessor;
+use capture_engine::capture_engine::protocol::headers::split_headers;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const BATCH_SIZE: usize = 10_000;
+
+/// Ethernet/IPv4/UDP frame with a 1400-byte payload and valid checksums
+fn frame(seed: u32) -> Vec<u8> {
+    let payload: Vec<u8> = (0..1400u32)
+        .map(|i| (i.wrapping_add(seed).wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    let udp_len = 8 + payload.len();
+    let total_len = 20 + udp_len;
+
+    let mut ip = vec![
+        0x45, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00, 10, 0, 0, 1, 10, 0,
+        0, 2,
+    ];
+    ip[2..4].copy_from_slice(&(total_len as u16).to_be_bytes());
+    let ip_checksum = checksum(&[&ip]);
+    ip[10..12].copy_from_slice(&ip_checksum.to_be_bytes());
+
+    let mut udp = vec![0x30, 0x39, 0x1f, 0x90, 0, 0, 0, 0];
+    udp[4..6].copy_from_slice(&(udp_len as u16).to_be_bytes());
+    udp.extend_from_slice(&payload);
+    let pseudo = [&ip[12..20], &[0, 17], &(udp_len as u16).to_be_bytes()].concat();
+    let udp_checksum = checksum(&[&pseudo, &udp]);
+    udp[6..8].copy_from_slice(&udp_checksum.to_be_bytes());
+
+    let mut frame = vec![0u8; 12];
+    frame.extend_from_slice(&[0x08, 0x00]);
+    frame.extend_from_slice(&ip);
+    frame.extend_from_slice(&udp);
+    frame
+}
+
+fn checksum(parts: &[&[u8]]) -> u16 {
+    let mut sum: u32 = parts
+        .iter()
+        .flat_map(|part| part.chunks(2))
+        .map(|word| u32::from(u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)])))
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+fn bench_batch_processing(c: &mut Criterion) {
+    let frames: Vec<Vec<u8>> = (0..BATCH_SIZE as u32).map(frame).collect();
+    let workers = std::thread::available_parallelism().map_or(4, |n| n.get().max(2));
+
+    let mut group = c.benchmark_group("process_batch_10k");
+    for (name, parallelism) in [
+        ("serial".to_string(), 1),
+        (format!("{}_workers", workers), workers),
+    ] {
+        let mut processor = PacketProcessor::new();
+        processor.set_parallelism(parallelism).unwrap();
+        processor.set_min_batch_for_parallel(1);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &frames, |b, frames| {
+            b.iter(|| {
+                processor
+                    .process_batch(frames, |frame| {
+                        let split = split_headers(frame);
+                        let ip = &frame[14..];
+                        Ok(split.header_len
+                            + usize::from(processor.verify_ipv4_checksum(ip) == Some(true))
+                            + usize::from(processor.verify_udp_checksum(ip) == Some(true)))
+                    })
+                    .map(black_box)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch_processing);
+criterion_main!(benches);
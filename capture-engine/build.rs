@@ -0,0 +1,37 @@
+// build.rs
+//! Builds the DPDK shim when the `dpdk` feature is enabled.
+
+fn main() {
+    #[cfg(feature = "dpdk")]
+    dpdk::build();
+}
+
+#[cfg(feature = "dpdk")]
+mod dpdk {
+    use std::process::Command;
+
+    const SHIM: &str = "src/capture_engine/interface/dpdk_shim.c";
+
+    pub fn build() {
+        println!("cargo:rerun-if-changed={}", SHIM);
+        // Emits the link flags for libdpdk
+        if let Err(e) = pkg_config::Config::new().probe("libdpdk") {
+            panic!(
+                "The dpdk feature needs the libdpdk development files: {}",
+                e
+            );
+        }
+
+        // DPDK's headers rely on its cflags, e.g. `-include rte_config.h` and `-march`
+        let cflags = Command::new("pkg-config")
+            .args(["--cflags", "libdpdk"])
+            .output()
+            .expect("pkg-config is required by the dpdk feature");
+        let mut build = cc::Build::new();
+        build.file(SHIM);
+        for flag in String::from_utf8_lossy(&cflags.stdout).split_whitespace() {
+            build.flag(flag);
+        }
+        build.compile("sparktrap_dpdk_shim");
+    }
+}
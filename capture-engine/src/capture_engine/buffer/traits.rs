@@ -1,10 +1,13 @@
 // buffer/traits.rs
 /// The `BufferManager` controls buffer allocation and release.
+use std::ptr::NonNull;
+use std::slice;
+
 use async_trait::async_trait;
 
 use crate::traits::{BufferId, Error, Lifecycle, PressureAware, PressureLevel, PressureStatus};
 /// Represents events specific to buffer management.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum BufferEvent {
     MemoryPressure(PressureLevel),
     BufferReleased(BufferId),
@@ -30,8 +33,53 @@ pub trait BufferManager: Lifecycle + PressureAware + Send + Sync {
 // A handle that can provide &mut [u8] directly, avoiding arc+trait overhead
 pub struct BufferHandle {
     pub buffer_id: BufferId,
-    pub data: *mut u8,
-    pub capacity: usize,
+    data: NonNull<u8>,
+    capacity: usize,
+}
+
+impl BufferHandle {
+    /// Creates a handle over `capacity` bytes starting at `data`.
+    ///
+    /// # Safety
+    /// Caller must ensure, for as long as the handle exists:
+    /// - `data` points to `capacity` initialized bytes in a single allocation
+    /// - The memory is not read or written other than through this handle
+    /// - The memory is not freed or reused
+    ///
+    /// # Panics
+    /// Panics if `data` is null.
+    pub unsafe fn from_raw_parts(buffer_id: BufferId, data: *mut u8, capacity: usize) -> Self {
+        let data = NonNull::new(data).expect("BufferHandle data pointer must not be null");
+        debug_assert!(
+            capacity <= isize::MAX as usize,
+            "BufferHandle capacity exceeds isize::MAX"
+        );
+        Self {
+            buffer_id,
+            data,
+            capacity,
+        }
+    }
+
+    /// Returns the buffer size in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Provides a read-only view of the buffer.
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: `from_raw_parts` requires `data` to address `capacity` initialized bytes
+        // owned exclusively by this handle, and the shared borrow of `self` prevents any
+        // mutable view from coexisting with the returned slice.
+        unsafe { slice::from_raw_parts(self.data.as_ptr(), self.capacity) }
+    }
+
+    /// Provides a mutable view of the buffer.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: as for `as_slice`; the exclusive borrow of `self` makes the returned slice
+        // the only live view of the memory.
+        unsafe { slice::from_raw_parts_mut(self.data.as_ptr(), self.capacity) }
+    }
 }
 
 /// Represents a buffer managed by `BufferManager`.
@@ -78,10 +126,10 @@ pub trait ZeroCopyBuffer {
 
 impl ZeroCopyBuffer for BufferHandle {
     unsafe fn as_ptr(&self) -> *const u8 {
-        self.data
+        self.data.as_ptr()
     }
     unsafe fn as_mut_ptr(&mut self) -> *mut u8 {
-        self.data
+        self.data.as_ptr()
     }
     fn len(&self) -> usize {
         self.capacity
@@ -99,3 +147,48 @@ pub struct BufferMetadata {
     pub buffer_id: BufferId,
     pub capacity: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Leaks a zeroed allocation of `len` bytes, reclaimed by `free`.
+    fn allocate(len: usize) -> *mut u8 {
+        Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+    }
+
+    fn free(handle: BufferHandle) -> Box<[u8]> {
+        let ptr = std::ptr::slice_from_raw_parts_mut(handle.data.as_ptr(), handle.capacity());
+        // SAFETY: the pointer came from `allocate` with the same length and the handle is
+        // consumed, so no other view of the memory remains.
+        unsafe { Box::from_raw(ptr) }
+    }
+
+    #[test]
+    fn test_writes_visible_through_slices() {
+        let mut handle = unsafe { BufferHandle::from_raw_parts(BufferId::new(1), allocate(8), 8) };
+        assert_eq!(handle.as_slice(), &[0u8; 8]);
+
+        handle.as_mut_slice()[..4].copy_from_slice(b"pkt!");
+        handle.as_mut_slice()[7] = 0xff;
+        assert_eq!(&handle.as_slice()[..4], b"pkt!");
+        assert_eq!(handle.len(), 8);
+
+        let memory = free(handle);
+        assert_eq!(&memory[..], b"pkt!\0\0\0\xff");
+    }
+
+    #[test]
+    fn test_empty_buffer() {
+        let handle = unsafe { BufferHandle::from_raw_parts(BufferId::new(2), allocate(0), 0) };
+        assert!(handle.as_slice().is_empty());
+        assert!(handle.is_empty());
+        free(handle);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be null")]
+    fn test_null_pointer_rejected() {
+        let _ = unsafe { BufferHandle::from_raw_parts(BufferId::new(3), std::ptr::null_mut(), 4) };
+    }
+}
@@ -5,45 +5,75 @@
 //!
 //! The engine is built around the following core components:
 //!
+//! - **Adaptive Batch**: Shrinks the pipeline batch size under buffer pressure.
 //! - **Buffer Manager**: Manages the packet buffers used for storing captured packets.
 //! - **Capture Configuration**: Configuration settings for the capture engine.
 //! - **Capture Engine**: The main engine that orchestrates the capture process.
 //! - **Capture Error**: Error types used by the capture engine.
 //! - **Capture Session**: Represents a single capture session.
 //! - **Capture Statistics**: Statistics and metrics for the capture engine.
+//! - **Config Schema**: JSON Schema export of the capture configuration for external tooling.
+//! - **Clock**: Wall-clock source shared by timestamping and scheduled actions.
+//! - **ENI Lifecycle**: Attaches and detaches mirror ENIs, quiescing capture before a detach.
+//! - **Error Rate Monitor**: Raises alerts when error rates by severity exceed thresholds.
 //! - **Health Monitor**: Monitors the health of the capture engine.
+//! - **Health Rollup**: Rolls component health up through hard dependencies.
 //! - **Interface Manager**: Manages the network interfaces used for packet capture.
 //! - **Packet Filter**: Filters packets based on user-defined rules.
+//! - **Packet Layer**: Ordered, user-supplied processing layers between ingestion and output.
 //! - **Packet Processor**: Processes packets captured by the engine.
 //! - **Protocol Filter**: Filters packets based on protocol.
+//! - **Recent Errors**: Bounded, queryable ring of recent errors for diagnostics.
+//! - **Stage Control**: Pauses and resumes individual pipeline stages.
+//! - **Start Barrier**: Holds ingestion until a scheduled start time for synchronized captures.
+//! - **Session Migration**: Moves a running session to a new interface without stopping it.
+//! - **Session Report**: Summary report produced when a capture session stops.
+//! - **Shutdown Drain**: Stops ingest and flushes in-flight packets before the engine stops.
 //! - **State Machine**: A state machine for managing the state of the capture engine.
 //! - **State Recovery**: Manages the recovery of the capture engine state.
 //! - **State Sync**: Synchronizes the state of the capture engine with the control plane.
 //! - **State Validator**: Validates the state of the capture engine.
 //! - **Transaction**: Represents a transaction that modifies the state of the capture engine.
+//! - **Work Stealing**: Balances per-flow work across processing workers.
 
+pub mod adaptive_batch;
 pub mod buffer_manager;
 pub mod capture_config;
 pub mod capture_engine;
 pub mod capture_error;
 pub mod capture_session;
 pub mod capture_statistics;
+pub mod clock;
+pub mod config_schema;
+pub mod eni_lifecycle;
 pub mod error_messages;
+pub mod error_rate_monitor;
 pub mod health_monitor;
+pub mod health_rollup;
 pub mod interface_manager;
+pub mod mmap_ring;
 pub mod packet_filter;
+pub mod packet_layer;
 pub mod packet_processor;
 pub mod protocol_filter;
+pub mod recent_errors;
+pub mod session_migration;
+pub mod session_report;
+pub mod shutdown_drain;
+pub mod stage_control;
+pub mod start_barrier;
 pub mod state_machine;
 pub mod state_recovery;
 pub mod state_sync;
 pub mod state_validator;
 pub mod traits;
 pub mod transaction;
+pub mod work_stealing;
 
+pub use adaptive_batch::{AdaptiveBatchConfig, AdaptiveBatchSizer};
 pub use buffer_manager::{
     Buffer, BufferManager, BufferMemory, BufferMemoryType, BufferMetadata, BufferMetrics,
-    BufferState,
+    BufferPoolAccounting, BufferSnapshot, BufferState, BufferTypeCounts, BufferWatermarks,
 };
 pub use capture_config::{
     CaptureConfiguration, CloudConfiguration, PerformanceConfiguration, SecurityConfiguration,
@@ -51,24 +81,46 @@ pub use capture_config::{
 pub use capture_engine::CaptureEngine;
 pub use capture_error::{CaptureError, CaptureErrorKind, CaptureResult};
 pub use capture_session::{
-    CaptureSession, SessionAction, SessionConfiguration, SessionState, SessionStats,
-    SessionValidationConfig,
+    CaptureSession, QuotaStatus, SessionAction, SessionConfiguration, SessionQuota, SessionState,
+    SessionStats, SessionValidationConfig,
 };
 pub use capture_statistics::{
-    CaptureStatistics, FlowMetrics, StateSyncMetrics, StateTransitionMetrics,
+    CaptureStatistics, DropMetrics, FlowCloseReason, FlowExpired, FlowMetrics, FlowStats,
+    FlowStatsTable, FlowTracker, FlowTuple, StateSyncMetrics, StateTransitionMetrics,
 };
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use config_schema::{capture_configuration_schema, ReloadBehavior};
+pub use eni_lifecycle::{EniBackend, EniCaptureGuard, EniLifecycle, EniState, EniStatus};
+pub use error_rate_monitor::{ErrorRateAlert, ErrorRateMonitor, ErrorRateThresholds};
 pub use health_monitor::{
-    HealthEvent, HealthMetrics, HealthStatus, HealthThresholds, MonitoredComponent,
+    ComponentCheck, HealthEvent, HealthMetrics, HealthStatus, HealthThresholds, HysteresisBand,
+    MonitoredComponent, StatusHysteresis,
 };
+pub use health_rollup::{ComponentHealth, HealthRollup};
 pub use interface_manager::{InterfaceManager, InterfaceState, ManagedInterface};
-pub use packet_filter::{FilterRule, PacketFilter};
-pub use packet_processor::PacketProcessor;
+pub use mmap_ring::{MmapRing, RingFrame, RingMetrics};
+pub use packet_filter::{FilterDirection, FilterRule, PacketFilter, RuleGeneration, RulesetHandle};
+pub use packet_layer::{LayerAction, LayerOutcome, LayerStack, PacketLayer};
+pub use packet_processor::{PacketProcessor, ProcessorStats};
 pub use protocol_filter::ProtocolFilter;
-pub use state_machine::{StateMachine, StateTransition};
+pub use recent_errors::{ErrorQuery, ErrorRecord, RecentErrors};
+pub use session_migration::{ActiveSession, MigrationConfig, MigrationReport};
+pub use session_report::{SessionReport, SessionReportCollector, SessionReportConfig};
+pub use shutdown_drain::ShutdownDrain;
+pub use stage_control::{PausableStage, StageControl, StageSubmit};
+pub use start_barrier::StartBarrier;
+pub use state_machine::{
+    HistoryOverflowPolicy, StateMachine, StateTransition, TransitionGuard, TransitionObserver,
+    TransitionReason,
+};
 pub use state_recovery::{RecoveryPoint, StateRecoveryManager, StateSnapshot};
 pub use state_sync::{StateChangeEvent, StateSync};
-pub use state_validator::{StateValidator, ValidationResult, ValidationRule, ValidationSeverity};
-pub use transaction::{TransactionContext, TransactionOperation, TransactionState};
+pub use state_validator::{
+    StateValidator, ValidationMode, ValidationReport, ValidationResult, ValidationRule,
+    ValidationSeverity,
+};
+pub use transaction::{Savepoint, TransactionContext, TransactionOperation, TransactionState};
+pub use work_stealing::{FlowBatch, WorkStealingConfig, WorkStealingScheduler};
 
 // Prelude module for commonly used types
 pub mod prelude {
@@ -0,0 +1,213 @@
+// capture-engine/src/capture/adaptive_batch.rs
+use std::collections::HashMap;
+
+use crate::capture_engine::capture::buffer_manager::BufferPoolAccounting;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::telemetry::traits::{
+    MetricType, MetricUnit, MetricValue, TelemetryData,
+};
+use crate::traits::PressureLevel;
+
+/// Metric name of the batch size currently in effect
+pub const EFFECTIVE_BATCH_SIZE_METRIC: &str = "capture.batch.effective_size";
+
+/// Bounds and step of pressure-driven batch sizing
+///
+/// # Fields
+/// * `min_batch` - Smallest batch the pipeline shrinks to under pressure
+/// * `max_batch` - Configured batch size, used while pressure is normal
+/// * `step` - Packets removed on elevated pressure and added back when pressure is normal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdaptiveBatchConfig {
+    pub min_batch: usize,
+    pub max_batch: usize,
+    pub step: usize,
+}
+
+impl Default for AdaptiveBatchConfig {
+    fn default() -> Self {
+        Self {
+            min_batch: 8,
+            max_batch: 64,
+            step: 8,
+        }
+    }
+}
+
+impl AdaptiveBatchConfig {
+    /// Validates the configuration
+    ///
+    /// # Returns
+    /// An error if the bounds are zero or inverted, or the step is zero
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        if self.min_batch == 0 || self.min_batch > self.max_batch || self.step == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Adaptive batch sizing needs 0 < min_batch <= max_batch and a positive step",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Batch size that shrinks under buffer pressure and recovers as pressure clears
+///
+/// Elevated pressure removes one step, critical or overflow pressure halves the batch, and
+/// normal pressure adds one step back. The size always stays within the configured bounds,
+/// so fewer buffers are held per batch while the pool is short.
+///
+/// # Fields
+/// * `config` - Bounds and step
+/// * `current` - Batch size in effect
+#[derive(Debug, Clone)]
+pub struct AdaptiveBatchSizer {
+    config: AdaptiveBatchConfig,
+    current: usize,
+}
+
+impl AdaptiveBatchSizer {
+    /// Creates a sizer starting at the configured maximum
+    ///
+    /// # Arguments
+    /// * `config` - Bounds and step
+    ///
+    /// # Returns
+    /// The sizer, or an error if the configuration is invalid
+    pub fn new(config: AdaptiveBatchConfig) -> Result<Self, CaptureError> {
+        config.validate()?;
+        Ok(Self {
+            current: config.max_batch,
+            config,
+        })
+    }
+
+    /// Returns the batch size in effect
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the bounds and step
+    pub fn config(&self) -> &AdaptiveBatchConfig {
+        &self.config
+    }
+
+    /// Adjusts the batch size for a pressure level
+    ///
+    /// # Arguments
+    /// * `level` - Current buffer pressure
+    ///
+    /// # Returns
+    /// The batch size to use next
+    pub fn on_pressure(&mut self, level: &PressureLevel) -> usize {
+        let AdaptiveBatchConfig {
+            min_batch,
+            max_batch,
+            step,
+        } = self.config;
+        self.current = match level {
+            PressureLevel::Normal => self.current.saturating_add(step).min(max_batch),
+            PressureLevel::Elevated => self.current.saturating_sub(step).max(min_batch),
+            PressureLevel::Critical | PressureLevel::Overflow => (self.current / 2).max(min_batch),
+        };
+        self.current
+    }
+
+    /// Adjusts the batch size for the pool's current pressure
+    ///
+    /// # Arguments
+    /// * `pool` - Buffer pool whose pressure drives the batch size
+    ///
+    /// # Returns
+    /// The batch size to use next
+    pub fn next_batch_size(&mut self, pool: &BufferPoolAccounting) -> usize {
+        self.on_pressure(&pool.snapshot().pressure)
+    }
+
+    /// Reports the effective batch size as a gauge
+    pub fn telemetry(&self, timestamp: u64) -> Vec<TelemetryData> {
+        vec![TelemetryData {
+            timestamp,
+            name: EFFECTIVE_BATCH_SIZE_METRIC.to_string(),
+            description: Some("Batch size in effect after pressure adjustment".to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type: MetricType::Gauge,
+            value: MetricValue::Integer(self.current as i64),
+            attributes: HashMap::new(),
+            resource: None,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::buffer_manager::BufferMemoryType;
+
+    fn sizer() -> AdaptiveBatchSizer {
+        AdaptiveBatchSizer::new(AdaptiveBatchConfig {
+            min_batch: 4,
+            max_batch: 64,
+            step: 8,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rejects_invalid_config() {
+        for (min_batch, max_batch, step) in [(0, 64, 8), (65, 64, 8), (4, 64, 0)] {
+            let config = AdaptiveBatchConfig {
+                min_batch,
+                max_batch,
+                step,
+            };
+            assert!(AdaptiveBatchSizer::new(config).is_err());
+        }
+    }
+
+    #[test]
+    fn test_batch_size_tracks_pool_pressure_monotonically() {
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 20);
+        let mut sizer = sizer();
+
+        // Rising pressure: every acquire may only keep or shrink the batch
+        let mut sizes = vec![sizer.next_batch_size(&pool)];
+        for _ in 0..20 {
+            pool.acquire(BufferMemoryType::Heap).unwrap();
+            sizes.push(sizer.next_batch_size(&pool));
+        }
+        assert!(sizes.windows(2).all(|w| w[1] <= w[0]), "{:?}", sizes);
+        assert_eq!(sizes[0], 64);
+        assert_eq!(*sizes.last().unwrap(), 4);
+
+        // Falling pressure: every release may only keep or grow the batch
+        let mut sizes = vec![sizer.current()];
+        for _ in 0..20 {
+            pool.release(BufferMemoryType::Heap).unwrap();
+            sizes.push(sizer.next_batch_size(&pool));
+        }
+        assert!(sizes.windows(2).all(|w| w[1] >= w[0]), "{:?}", sizes);
+        assert_eq!(*sizes.last().unwrap(), 64);
+    }
+
+    #[test]
+    fn test_pressure_levels_adjust_within_bounds() {
+        let mut sizer = sizer();
+        assert_eq!(sizer.on_pressure(&PressureLevel::Normal), 64);
+        assert_eq!(sizer.on_pressure(&PressureLevel::Elevated), 56);
+        assert_eq!(sizer.on_pressure(&PressureLevel::Critical), 28);
+        assert_eq!(sizer.on_pressure(&PressureLevel::Overflow), 14);
+        assert_eq!(sizer.on_pressure(&PressureLevel::Critical), 7);
+        assert_eq!(sizer.on_pressure(&PressureLevel::Critical), 4);
+        assert_eq!(sizer.on_pressure(&PressureLevel::Elevated), 4);
+        assert_eq!(sizer.on_pressure(&PressureLevel::Normal), 12);
+
+        let metrics = sizer.telemetry(7);
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, EFFECTIVE_BATCH_SIZE_METRIC);
+        assert!(matches!(metrics[0].metric_type, MetricType::Gauge));
+        assert!(matches!(metrics[0].value, MetricValue::Integer(12)));
+    }
+}
@@ -2,15 +2,28 @@
 #![allow(unused)]
 #![allow(unused_variables)]
 // capture-engine/src/capture/buffer_manager.rs
+use parking_lot::Mutex;
 use serde::de;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::SystemTime;
 
-use crate::capture_engine::capture::capture_error::{CaptureError, CaptureResult};
+use tokio::sync::broadcast;
+
+use crate::capture_engine::buffer::traits::{BufferEvent, WatermarkType};
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, CaptureResult, ConfigErrorKind, ResourceErrorKind,
+};
+use crate::capture_engine::capture::mmap_ring::{MmapRing, RingFrame, RingMetrics};
 use crate::capture_engine::capture::{StateMachine, StateSync, StateValidator};
+use crate::capture_engine::interface::topology::parse_cpu_list;
+use crate::pressure::{PressureTracker, DEFAULT_HYSTERESIS};
+use crate::traits::{PressureLevel, PressureThresholds};
+
+/// Events retained for a subscriber before the oldest are dropped
+pub const BUFFER_EVENT_CAPACITY: usize = 64;
 
 /// Buffer states in the state machine
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -24,10 +37,15 @@ pub enum BufferState {
     Error,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum BufferMemoryType {
     Heap,
     ZeroCopy,
+    // Frames of `frame_size` bytes carved out of one shared mapping of `region_size` bytes
+    MmapRing {
+        region_size: usize,
+        frame_size: usize,
+    },
 }
 
 /// Direct memory management
@@ -37,6 +55,8 @@ pub enum BufferMemory {
     Heap(Vec<u8>),
     // Zero-copy memory mapping
     ZeroCopy(ZeroCopyRegion),
+    // Frame of a shared mmap'd ring
+    MmapFrame(RingFrame),
 }
 
 /// Buffer metadata for tracking and management
@@ -62,6 +82,835 @@ pub struct BufferManager {
     buffers: HashMap<usize, Arc<Buffer>>,
     state_sync: Arc<StateSync<BufferState>>,
     state_validator: StateValidator<BufferState>,
+    pool: Arc<BufferPoolAccounting>,
+    numa_pools: Option<NumaBufferPools>,
+    ring: Option<MmapRing>,
+}
+
+/// Free and in-use buffer counts for a single memory type
+///
+/// # Fields
+/// * `free` - Buffers available for acquisition
+/// * `in_use` - Buffers currently held by consumers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferTypeCounts {
+    pub free: usize,
+    pub in_use: usize,
+}
+
+impl BufferTypeCounts {
+    /// Gets the total number of buffers of this type
+    ///
+    /// # Returns
+    /// The sum of free and in-use buffers
+    pub fn total(&self) -> usize {
+        self.free + self.in_use
+    }
+}
+
+/// Point-in-time view of the buffer pool
+///
+/// All counts are read together, so `free + in_use == total` always holds for a snapshot.
+///
+/// # Fields
+/// * `total` - Total buffers in the pool
+/// * `free` - Buffers available for acquisition
+/// * `in_use` - Buffers currently held by consumers
+/// * `heap` - Counts for heap-allocated buffers
+/// * `zero_copy` - Counts for zero-copy buffers
+/// * `mmap_ring` - Counts for frames of mmap'd rings
+/// * `nodes` - Counts per NUMA node, for buffers added with node affinity
+/// * `pressure` - Pressure level derived from pool utilization
+/// * `taken_at` - Time the snapshot was taken
+#[derive(Debug, Clone)]
+pub struct BufferSnapshot {
+    pub total: usize,
+    pub free: usize,
+    pub in_use: usize,
+    pub heap: BufferTypeCounts,
+    pub zero_copy: BufferTypeCounts,
+    pub mmap_ring: BufferTypeCounts,
+    pub nodes: BTreeMap<u32, BufferTypeCounts>,
+    pub pressure: PressureLevel,
+    pub taken_at: SystemTime,
+}
+
+impl BufferSnapshot {
+    /// Gets the counts for a memory type
+    ///
+    /// # Arguments
+    /// * `memory_type` - Memory type to look up
+    ///
+    /// # Returns
+    /// The free and in-use counts for the memory type
+    pub fn counts(&self, memory_type: BufferMemoryType) -> BufferTypeCounts {
+        match memory_type {
+            BufferMemoryType::Heap => self.heap,
+            BufferMemoryType::ZeroCopy => self.zero_copy,
+            BufferMemoryType::MmapRing { .. } => self.mmap_ring,
+        }
+    }
+
+    /// Gets the fraction of the pool currently in use
+    ///
+    /// # Returns
+    /// Utilization between 0.0 and 1.0, or 0.0 for an empty pool
+    pub fn utilization(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.in_use as f32 / self.total as f32
+        }
+    }
+
+    /// Gets the fraction of a NUMA node's buffers currently in use
+    ///
+    /// # Arguments
+    /// * `node` - NUMA node to look up
+    ///
+    /// # Returns
+    /// Utilization between 0.0 and 1.0, or 0.0 for a node without buffers
+    pub fn node_utilization(&self, node: u32) -> f32 {
+        match self.nodes.get(&node) {
+            Some(counts) if counts.total() > 0 => counts.in_use as f32 / counts.total() as f32,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Utilization watermarks that raise buffer events when crossed
+///
+/// # Fields
+/// * `low` - Utilization below which the pool is reported as recovered
+/// * `high` - Utilization at which ingest should start throttling
+/// * `critical` - Utilization at which the pool is close to exhaustion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BufferWatermarks {
+    pub low: f32,
+    pub high: f32,
+    pub critical: f32,
+}
+
+impl Default for BufferWatermarks {
+    fn default() -> Self {
+        Self {
+            low: 0.5,
+            high: 0.8,
+            critical: 0.95,
+        }
+    }
+}
+
+impl BufferWatermarks {
+    /// Validates the watermarks
+    ///
+    /// # Returns
+    /// An error unless 0 < low < high <= critical <= 1
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        let ordered = 0.0 < self.low
+            && self.low < self.high
+            && self.high <= self.critical
+            && self.critical <= 1.0;
+        if !ordered {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Buffer watermarks must satisfy 0 < low < high <= critical <= 1",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Edge-triggered watermark state
+///
+/// The pool starts below the low watermark. Rising to `high` or `critical` reports that
+/// watermark; falling below `critical` from above reports `High` again. Dropping below
+/// `low` reports `Low`, and utilization between the two watermarks keeps the previous state,
+/// so the pool does not flap between High and Low.
+#[derive(Debug)]
+struct WatermarkTracker {
+    watermarks: BufferWatermarks,
+    state: WatermarkType,
+}
+
+impl Default for WatermarkTracker {
+    fn default() -> Self {
+        Self {
+            watermarks: BufferWatermarks::default(),
+            state: WatermarkType::Low,
+        }
+    }
+}
+
+impl WatermarkTracker {
+    fn update(&mut self, utilization: f32) -> Option<WatermarkType> {
+        let next = if utilization >= self.watermarks.critical {
+            WatermarkType::Critical
+        } else if utilization >= self.watermarks.high {
+            WatermarkType::High
+        } else if utilization < self.watermarks.low {
+            WatermarkType::Low
+        } else if self.state == WatermarkType::Critical {
+            WatermarkType::High
+        } else {
+            self.state.clone()
+        };
+        if next == self.state {
+            return None;
+        }
+        self.state = next.clone();
+        Some(next)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PoolCounts {
+    heap: BufferTypeCounts,
+    zero_copy: BufferTypeCounts,
+    mmap_ring: BufferTypeCounts,
+    nodes: BTreeMap<u32, BufferTypeCounts>,
+    pressure: PressureTracker,
+    watermark: WatermarkTracker,
+}
+
+impl PoolCounts {
+    fn update_levels(&mut self) -> Option<WatermarkType> {
+        self.update_pressure();
+        let utilization = self.utilization();
+        self.watermark.update(utilization)
+    }
+
+    fn utilization(&self) -> f32 {
+        let total = self.heap.total() + self.zero_copy.total() + self.mmap_ring.total();
+        let in_use = self.heap.in_use + self.zero_copy.in_use + self.mmap_ring.in_use;
+        if total == 0 {
+            0.0
+        } else {
+            in_use as f32 / total as f32
+        }
+    }
+
+    fn update_pressure(&mut self) {
+        let utilization = self.utilization();
+        self.pressure.update(utilization);
+    }
+
+    fn counts_mut(&mut self, memory_type: BufferMemoryType) -> &mut BufferTypeCounts {
+        match memory_type {
+            BufferMemoryType::Heap => &mut self.heap,
+            BufferMemoryType::ZeroCopy => &mut self.zero_copy,
+            BufferMemoryType::MmapRing { .. } => &mut self.mmap_ring,
+        }
+    }
+}
+
+/// Buffer pool accounting shared between the hot path and diagnostics
+///
+/// Every update and snapshot holds the lock only long enough to adjust or copy a few
+/// counters, so readers never observe a half-applied acquire or release. The pressure level is
+/// re-evaluated with hysteresis on every acquire and release.
+///
+/// Buffers added with NUMA node affinity are additionally counted per node, so the node
+/// breakdown always sums to the node-bound part of the per-type counts.
+///
+/// Crossing a watermark publishes a `WatermarkCrossed` event to subscribers. The event
+/// channel is bounded and drops the oldest events when full, so a slow subscriber never
+/// blocks an acquire or release; it sees a lag error and resumes from the retained events.
+///
+/// # Fields
+/// * `counts` - Per-type and per-node free and in-use counts and the pressure and
+///   watermark trackers
+/// * `events` - Sender of buffer events to subscribers
+#[derive(Debug)]
+pub struct BufferPoolAccounting {
+    counts: Mutex<PoolCounts>,
+    events: broadcast::Sender<BufferEvent>,
+}
+
+impl Default for BufferPoolAccounting {
+    fn default() -> Self {
+        Self {
+            counts: Mutex::default(),
+            events: broadcast::channel(BUFFER_EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl BufferPoolAccounting {
+    /// Creates pool accounting with custom pressure thresholds
+    ///
+    /// # Arguments
+    /// * `thresholds` - Utilization thresholds, in ascending order between 0.0 and 1.0
+    /// * `hysteresis` - Margin below a threshold utilization must reach before the level drops
+    ///
+    /// # Returns
+    /// A new BufferPoolAccounting instance or an error if the thresholds are invalid
+    pub fn with_thresholds(
+        thresholds: PressureThresholds,
+        hysteresis: f32,
+    ) -> Result<Self, CaptureError> {
+        let pressure = PressureTracker::new(thresholds, hysteresis).map_err(|_| {
+            *CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Buffer pressure thresholds must be ascending and within (0, 1]",
+            )
+        })?;
+
+        Ok(Self {
+            counts: Mutex::new(PoolCounts {
+                pressure,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Replaces the watermarks, keeping the current watermark state until the next update
+    ///
+    /// # Arguments
+    /// * `watermarks` - Low, high and critical utilization watermarks
+    ///
+    /// # Returns
+    /// An error if the watermarks are not ascending within (0, 1]
+    pub fn set_watermarks(&self, watermarks: BufferWatermarks) -> Result<(), CaptureError> {
+        watermarks.validate()?;
+        self.counts.lock().watermark.watermarks = watermarks;
+        Ok(())
+    }
+
+    /// Subscribes to buffer events
+    ///
+    /// # Returns
+    /// A receiver of events published after the call; it reports a lag if it falls more than
+    /// `BUFFER_EVENT_CAPACITY` events behind
+    pub fn subscribe_events(&self) -> broadcast::Receiver<BufferEvent> {
+        self.events.subscribe()
+    }
+
+    fn refresh(&self, counts: &mut PoolCounts) {
+        if let Some(watermark) = counts.update_levels() {
+            // No subscribers is not an error
+            let _ = self.events.send(BufferEvent::WatermarkCrossed(watermark));
+        }
+    }
+
+    /// Adds free buffers of a memory type to the pool
+    ///
+    /// # Arguments
+    /// * `memory_type` - Memory type of the new buffers
+    /// * `count` - Number of buffers added
+    pub fn add_buffers(&self, memory_type: BufferMemoryType, count: usize) {
+        let mut counts = self.counts.lock();
+        counts.counts_mut(memory_type).free += count;
+        self.refresh(&mut counts);
+    }
+
+    /// Removes free buffers of a memory type from the pool
+    ///
+    /// # Arguments
+    /// * `memory_type` - Memory type of the removed buffers
+    /// * `count` - Number of buffers removed
+    ///
+    /// # Returns
+    /// An error if fewer than `count` buffers are free
+    pub fn remove_buffers(
+        &self,
+        memory_type: BufferMemoryType,
+        count: usize,
+    ) -> Result<(), CaptureError> {
+        let mut counts = self.counts.lock();
+        let entry = counts.counts_mut(memory_type);
+        if entry.free < count {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                "Cannot remove buffers that are in use",
+            ));
+        }
+        entry.free -= count;
+        self.refresh(&mut counts);
+        Ok(())
+    }
+
+    /// Marks a free buffer of a memory type as in use
+    ///
+    /// # Arguments
+    /// * `memory_type` - Memory type of the acquired buffer
+    ///
+    /// # Returns
+    /// An error if no buffer of the type is free
+    pub fn acquire(&self, memory_type: BufferMemoryType) -> Result<(), CaptureError> {
+        let mut counts = self.counts.lock();
+        let entry = counts.counts_mut(memory_type);
+        if entry.free == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable),
+                "No free buffers available",
+            ));
+        }
+        entry.free -= 1;
+        entry.in_use += 1;
+        self.refresh(&mut counts);
+        Ok(())
+    }
+
+    /// Returns an in-use buffer of a memory type to the free pool
+    ///
+    /// # Arguments
+    /// * `memory_type` - Memory type of the released buffer
+    ///
+    /// # Returns
+    /// An error if no buffer of the type is in use
+    pub fn release(&self, memory_type: BufferMemoryType) -> Result<(), CaptureError> {
+        let mut counts = self.counts.lock();
+        let entry = counts.counts_mut(memory_type);
+        if entry.in_use == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                "Released buffer was not in use",
+            ));
+        }
+        entry.in_use -= 1;
+        entry.free += 1;
+        self.refresh(&mut counts);
+        Ok(())
+    }
+
+    /// Adds free buffers bound to a NUMA node to the pool
+    ///
+    /// # Arguments
+    /// * `memory_type` - Memory type of the new buffers
+    /// * `node` - NUMA node the buffers were allocated on
+    /// * `count` - Number of buffers added
+    pub fn add_node_buffers(&self, memory_type: BufferMemoryType, node: u32, count: usize) {
+        let mut counts = self.counts.lock();
+        counts.counts_mut(memory_type).free += count;
+        counts.nodes.entry(node).or_default().free += count;
+        self.refresh(&mut counts);
+    }
+
+    /// Marks a free buffer bound to a NUMA node as in use
+    ///
+    /// # Arguments
+    /// * `memory_type` - Memory type of the acquired buffer
+    /// * `node` - NUMA node the buffer was allocated on
+    ///
+    /// # Returns
+    /// An error if no buffer of the type is free on the node
+    pub fn acquire_on_node(
+        &self,
+        memory_type: BufferMemoryType,
+        node: u32,
+    ) -> Result<(), CaptureError> {
+        let mut counts = self.counts.lock();
+        let node_free = counts.nodes.get(&node).map_or(0, |entry| entry.free);
+        if node_free == 0 || counts.counts_mut(memory_type).free == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable),
+                &format!("No free buffers available on NUMA node {}", node),
+            ));
+        }
+        let entry = counts.counts_mut(memory_type);
+        entry.free -= 1;
+        entry.in_use += 1;
+        let entry = counts.nodes.entry(node).or_default();
+        entry.free -= 1;
+        entry.in_use += 1;
+        self.refresh(&mut counts);
+        Ok(())
+    }
+
+    /// Returns an in-use buffer bound to a NUMA node to the free pool
+    ///
+    /// # Arguments
+    /// * `memory_type` - Memory type of the released buffer
+    /// * `node` - NUMA node the buffer was allocated on
+    ///
+    /// # Returns
+    /// An error if no buffer of the type is in use on the node
+    pub fn release_on_node(
+        &self,
+        memory_type: BufferMemoryType,
+        node: u32,
+    ) -> Result<(), CaptureError> {
+        let mut counts = self.counts.lock();
+        let node_in_use = counts.nodes.get(&node).map_or(0, |entry| entry.in_use);
+        if node_in_use == 0 || counts.counts_mut(memory_type).in_use == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                &format!("Released buffer was not in use on NUMA node {}", node),
+            ));
+        }
+        let entry = counts.counts_mut(memory_type);
+        entry.in_use -= 1;
+        entry.free += 1;
+        let entry = counts.nodes.entry(node).or_default();
+        entry.in_use -= 1;
+        entry.free += 1;
+        self.refresh(&mut counts);
+        Ok(())
+    }
+
+    /// Takes a consistent snapshot of the pool
+    ///
+    /// # Returns
+    /// A snapshot whose counts were all read under the same lock
+    pub fn snapshot(&self) -> BufferSnapshot {
+        let (heap, zero_copy, mmap_ring, nodes, pressure) = {
+            let counts = self.counts.lock();
+            (
+                counts.heap,
+                counts.zero_copy,
+                counts.mmap_ring,
+                counts.nodes.clone(),
+                counts.pressure.level().clone(),
+            )
+        };
+
+        BufferSnapshot {
+            total: heap.total() + zero_copy.total() + mmap_ring.total(),
+            free: heap.free + zero_copy.free + mmap_ring.free,
+            in_use: heap.in_use + zero_copy.in_use + mmap_ring.in_use,
+            heap,
+            zero_copy,
+            mmap_ring,
+            nodes,
+            pressure,
+            taken_at: SystemTime::now(),
+        }
+    }
+}
+
+/// Layout of NUMA-local heap buffer pools
+///
+/// # Fields
+/// * `nodes` - NUMA nodes that get a pool; one node or none means a single default pool
+/// * `buffers_per_node` - Buffers preallocated on each node
+/// * `buffer_size` - Size of each buffer in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumaPoolConfig {
+    pub nodes: Vec<u32>,
+    pub buffers_per_node: usize,
+    pub buffer_size: usize,
+}
+
+impl NumaPoolConfig {
+    /// Builds a configuration with a pool for every online NUMA node
+    ///
+    /// # Arguments
+    /// * `buffers_per_node` - Buffers preallocated on each node
+    /// * `buffer_size` - Size of each buffer in bytes
+    ///
+    /// # Returns
+    /// A configuration listing the nodes in `/sys/devices/system/node/online`, or no nodes
+    /// when the host does not expose NUMA topology
+    pub fn detect(buffers_per_node: usize, buffer_size: usize) -> Self {
+        let nodes = std::fs::read_to_string("/sys/devices/system/node/online")
+            .ok()
+            .and_then(|list| parse_cpu_list(list.trim()))
+            .unwrap_or_default();
+        Self {
+            nodes,
+            buffers_per_node,
+            buffer_size,
+        }
+    }
+}
+
+/// Allocates buffer memory on a NUMA node
+///
+/// `NumaBufferPools` allocates each node's buffers on a dedicated thread, calling `bind` on
+/// that thread before any `allocate` for the node.
+pub trait NodeAllocator: Send + Sync {
+    /// Binds the calling thread's allocations to a node
+    ///
+    /// # Arguments
+    /// * `node` - NUMA node the following allocations should be local to
+    ///
+    /// # Returns
+    /// Whether placement on the node is guaranteed; false leaves placement to the kernel
+    fn bind(&self, node: u32) -> bool {
+        false
+    }
+
+    /// Allocates a zeroed buffer
+    ///
+    /// # Arguments
+    /// * `node` - NUMA node the memory should be local to
+    /// * `size` - Size of the buffer in bytes
+    ///
+    /// # Returns
+    /// The buffer memory
+    fn allocate(&self, node: u32, size: usize) -> Vec<u8>;
+}
+
+/// Allocator relying on the kernel's first-touch placement
+///
+/// `bind` pins the allocating thread to the CPUs of the target node, listed in
+/// `/sys/devices/system/node/node<N>/cpulist`. Every byte is then written by that thread, so
+/// the pages land on the node. Without NUMA topology the thread is left unpinned.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FirstTouchAllocator;
+
+impl NodeAllocator for FirstTouchAllocator {
+    fn bind(&self, node: u32) -> bool {
+        std::fs::read_to_string(format!("/sys/devices/system/node/node{}/cpulist", node))
+            .ok()
+            .and_then(|list| parse_cpu_list(list.trim()))
+            .is_some_and(|cpus| pin_current_thread(&cpus))
+    }
+
+    // `vec![0; size]` maps untouched zero pages, leaving placement to whichever thread writes
+    // first; resizing writes every byte here
+    #[allow(clippy::slow_vector_initialization)]
+    fn allocate(&self, _node: u32, size: usize) -> Vec<u8> {
+        let mut data = Vec::with_capacity(size);
+        data.resize(size, 0);
+        data
+    }
+}
+
+/// Restricts the calling thread to a set of CPUs
+///
+/// # Arguments
+/// * `cpus` - CPUs the thread may run on
+///
+/// # Returns
+/// Whether the affinity was applied
+#[cfg(target_os = "linux")]
+fn pin_current_thread(cpus: &[u32]) -> bool {
+    // SAFETY: `cpu_set_t` is plain data and zeroed is an empty set
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let mut any = false;
+    for &cpu in cpus {
+        if (cpu as usize) < libc::CPU_SETSIZE as usize {
+            // SAFETY: the index is checked against the set size above
+            unsafe { libc::CPU_SET(cpu as usize, &mut set) };
+            any = true;
+        }
+    }
+    // SAFETY: pid 0 is the calling thread and `set` outlives the call
+    any && unsafe { libc::sched_setaffinity(0, std::mem::size_of_val(&set), &set) } == 0
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_cpus: &[u32]) -> bool {
+    false
+}
+
+/// Heap buffer taken from a NUMA-local pool
+///
+/// # Fields
+/// * `node` - NUMA node of the pool the buffer belongs to
+/// * `data` - Buffer memory
+#[derive(Debug)]
+pub struct NodeBuffer {
+    node: u32,
+    data: Vec<u8>,
+}
+
+impl NodeBuffer {
+    /// Gets the NUMA node of the pool the buffer belongs to
+    pub fn node(&self) -> u32 {
+        self.node
+    }
+
+    /// Gets the buffer contents
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Gets the buffer contents for writing
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
+
+/// Heap buffer pools bound to NUMA nodes
+///
+/// Buffers are preallocated per node and handed out only from the requested node's pool, so a
+/// capture thread pinned near its NIC never touches remote memory. With a single node (or no
+/// NUMA topology at all) every request is served from the default pool, whatever node is asked
+/// for. Counts are kept in the shared pool accounting, whose snapshot breaks utilization down
+/// per node.
+///
+/// # Fields
+/// * `pools` - Free buffers and counters per node
+/// * `default_node` - Node serving every request on single-node systems
+/// * `accounting` - Pool accounting shared with the buffer manager
+pub struct NumaBufferPools {
+    pools: BTreeMap<u32, NodePool>,
+    default_node: Option<u32>,
+    accounting: Arc<BufferPoolAccounting>,
+}
+
+/// Free list and counters of one node's pool
+///
+/// # Fields
+/// * `free` - Free buffers
+/// * `buffers` - Buffers preallocated for the node
+/// * `bound` - Whether the allocator guaranteed the buffers are local to the node
+/// * `acquired` - Successful acquisitions
+/// * `exhausted` - Acquisitions refused because the pool was empty
+struct NodePool {
+    free: Mutex<Vec<Vec<u8>>>,
+    buffers: usize,
+    bound: bool,
+    acquired: AtomicU64,
+    exhausted: AtomicU64,
+}
+
+impl NumaBufferPools {
+    /// Preallocates the pools
+    ///
+    /// Each node's buffers are allocated and first touched on a fresh thread bound to that
+    /// node, one node at a time, so placement never depends on the caller's CPU.
+    ///
+    /// # Arguments
+    /// * `config` - Nodes and pool sizes
+    /// * `allocator` - Allocator placing memory on each node
+    /// * `accounting` - Pool accounting to register the buffers with
+    ///
+    /// # Returns
+    /// The pools, or an error if the configuration has no buffers
+    pub fn new(
+        config: &NumaPoolConfig,
+        allocator: &dyn NodeAllocator,
+        accounting: Arc<BufferPoolAccounting>,
+    ) -> Result<Self, CaptureError> {
+        if config.buffers_per_node == 0 || config.buffer_size == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "NUMA buffer pools need a non-zero buffer count and size",
+            ));
+        }
+
+        let mut nodes = config.nodes.clone();
+        nodes.sort_unstable();
+        nodes.dedup();
+        let default_node = match nodes.as_slice() {
+            [] => {
+                nodes.push(0);
+                Some(0)
+            }
+            [node] => Some(*node),
+            _ => None,
+        };
+
+        let pools = nodes
+            .into_iter()
+            .map(|node| {
+                let (bound, buffers) = std::thread::scope(|scope| {
+                    scope
+                        .spawn(|| {
+                            let bound = allocator.bind(node);
+                            let buffers = (0..config.buffers_per_node)
+                                .map(|_| allocator.allocate(node, config.buffer_size))
+                                .collect();
+                            (bound, buffers)
+                        })
+                        .join()
+                        .expect("node allocation thread panicked")
+                });
+                accounting.add_node_buffers(BufferMemoryType::Heap, node, config.buffers_per_node);
+                let pool = NodePool {
+                    free: Mutex::new(buffers),
+                    buffers: config.buffers_per_node,
+                    bound,
+                    acquired: AtomicU64::new(0),
+                    exhausted: AtomicU64::new(0),
+                };
+                (node, pool)
+            })
+            .collect();
+
+        Ok(Self {
+            pools,
+            default_node,
+            accounting,
+        })
+    }
+
+    /// Gets the nodes that have a pool
+    pub fn nodes(&self) -> Vec<u32> {
+        self.pools.keys().copied().collect()
+    }
+
+    /// Whether requests are routed to per-node pools rather than the default pool
+    pub fn is_numa(&self) -> bool {
+        self.default_node.is_none()
+    }
+
+    /// Gets buffer metrics broken down per node
+    pub fn metrics(&self) -> BufferMetrics {
+        BufferMetrics {
+            nodes: self
+                .pools
+                .iter()
+                .map(|(&node, pool)| {
+                    let metrics = NodeBufferMetrics {
+                        buffers: pool.buffers,
+                        bound: pool.bound,
+                        acquired: pool.acquired.load(Ordering::Relaxed),
+                        exhausted: pool.exhausted.load(Ordering::Relaxed),
+                    };
+                    (node, metrics)
+                })
+                .collect(),
+            ..BufferMetrics::default()
+        }
+    }
+
+    /// Takes a buffer from a node's pool
+    ///
+    /// # Arguments
+    /// * `node` - NUMA node the caller runs on
+    ///
+    /// # Returns
+    /// A buffer local to the node, or from the default pool on single-node systems. Fails if
+    /// the node has no pool or its pool is exhausted; other nodes' buffers are never used.
+    pub fn acquire_on_node(&self, node: u32) -> Result<NodeBuffer, CaptureError> {
+        let node = self.default_node.unwrap_or(node);
+        let pool = self.pools.get(&node).ok_or_else(|| {
+            *CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                &format!("No buffer pool for NUMA node {}", node),
+            )
+        })?;
+
+        let mut free = pool.free.lock();
+        if let Err(err) = self
+            .accounting
+            .acquire_on_node(BufferMemoryType::Heap, node)
+        {
+            pool.exhausted.fetch_add(1, Ordering::Relaxed);
+            return Err(err);
+        }
+        pool.acquired.fetch_add(1, Ordering::Relaxed);
+        let data = free.pop().expect("pool accounting matches the free list");
+        Ok(NodeBuffer { node, data })
+    }
+
+    /// Returns a buffer to the pool it was taken from
+    ///
+    /// # Arguments
+    /// * `buffer` - Buffer taken with `acquire_on_node`
+    ///
+    /// # Returns
+    /// An error if the buffer does not belong to these pools
+    pub fn release(&self, buffer: NodeBuffer) -> Result<(), CaptureError> {
+        let pool = self.pools.get(&buffer.node).ok_or_else(|| {
+            *CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                &format!("No buffer pool for NUMA node {}", buffer.node),
+            )
+        })?;
+
+        let mut free = pool.free.lock();
+        self.accounting
+            .release_on_node(BufferMemoryType::Heap, buffer.node)?;
+        free.push(buffer.data);
+        Ok(())
+    }
 }
 
 impl Default for Buffer {
@@ -123,6 +972,104 @@ impl BufferManager {
     pub fn validate_states(&self) -> Result<(), CaptureError> {
         unimplemented!()
     }
+
+    /// Takes a consistent point-in-time snapshot of the buffer pool
+    pub fn snapshot(&self) -> BufferSnapshot {
+        self.pool.snapshot()
+    }
+
+    /// Subscribes to buffer events such as watermark crossings, so ingest can throttle
+    pub fn subscribe_events(&self) -> broadcast::Receiver<BufferEvent> {
+        self.pool.subscribe_events()
+    }
+
+    /// Gets the pool accounting so diagnostics can snapshot without locking the manager
+    pub fn pool_accounting(&self) -> Arc<BufferPoolAccounting> {
+        Arc::clone(&self.pool)
+    }
+
+    /// Preallocates NUMA-local heap buffer pools, registered with the manager's accounting
+    pub fn configure_numa_pools(
+        &mut self,
+        config: &NumaPoolConfig,
+        allocator: &dyn NodeAllocator,
+    ) -> Result<(), CaptureError> {
+        if self.numa_pools.is_some() {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                "NUMA buffer pools are already configured",
+            ));
+        }
+        self.numa_pools = Some(NumaBufferPools::new(
+            config,
+            allocator,
+            Arc::clone(&self.pool),
+        )?);
+        Ok(())
+    }
+
+    /// Acquires a buffer from the pool of a NUMA node, or the default pool on single-node systems
+    pub fn acquire_buffer_on_node(&self, node: u32) -> Result<NodeBuffer, CaptureError> {
+        self.numa_pools()?.acquire_on_node(node)
+    }
+
+    /// Gets per-node metrics of the NUMA buffer pools
+    pub fn numa_metrics(&self) -> Result<BufferMetrics, CaptureError> {
+        Ok(self.numa_pools()?.metrics())
+    }
+
+    /// Returns a buffer acquired with `acquire_buffer_on_node`
+    pub fn release_node_buffer(&self, buffer: NodeBuffer) -> Result<(), CaptureError> {
+        self.numa_pools()?.release(buffer)
+    }
+
+    /// Maps a frame ring of the given `MmapRing` layout, registered with the manager's accounting
+    pub fn configure_mmap_ring(
+        &mut self,
+        memory_type: BufferMemoryType,
+    ) -> Result<(), CaptureError> {
+        if self.ring.is_some() {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                "A frame ring is already configured",
+            ));
+        }
+        self.ring = Some(MmapRing::new(memory_type, Arc::clone(&self.pool))?);
+        Ok(())
+    }
+
+    /// Acquires the next frame of the ring, failing with an overflow if the consumer still holds it
+    pub fn acquire_ring_frame(&self) -> Result<RingFrame, CaptureError> {
+        self.ring()?.acquire()
+    }
+
+    /// Returns a frame to the ring's free list without unmapping the region
+    pub fn release_ring_frame(&self, frame: RingFrame) -> Result<(), CaptureError> {
+        self.ring()?.release(frame)
+    }
+
+    /// Gets frames in flight against total frames of the ring
+    pub fn ring_metrics(&self) -> Option<RingMetrics> {
+        self.ring.as_ref().map(MmapRing::metrics)
+    }
+
+    fn ring(&self) -> Result<&MmapRing, CaptureError> {
+        self.ring.as_ref().ok_or_else(|| {
+            *CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable),
+                "No frame ring is configured",
+            )
+        })
+    }
+
+    fn numa_pools(&self) -> Result<&NumaBufferPools, CaptureError> {
+        self.numa_pools.as_ref().ok_or_else(|| {
+            *CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable),
+                "NUMA buffer pools are not configured",
+            )
+        })
+    }
 }
 
 /// Default buffer state transitions
@@ -179,10 +1126,436 @@ impl ZeroCopyRegion {
 unsafe impl Send for ZeroCopyRegion {}
 unsafe impl Sync for ZeroCopyRegion {}
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone)]
 pub struct BufferMetrics {
     writes: u64,
     reads: u64,
     transitions: u64,
     errors: u64,
+    nodes: BTreeMap<u32, NodeBufferMetrics>,
+}
+
+impl BufferMetrics {
+    /// Gets the metrics of a NUMA node's pool
+    ///
+    /// # Arguments
+    /// * `node` - NUMA node
+    ///
+    /// # Returns
+    /// The node's metrics, or None if it has no pool
+    pub fn node(&self, node: u32) -> Option<&NodeBufferMetrics> {
+        self.nodes.get(&node)
+    }
+
+    /// Gets the metrics of every NUMA node's pool
+    pub fn nodes(&self) -> &BTreeMap<u32, NodeBufferMetrics> {
+        &self.nodes
+    }
+}
+
+/// Metrics of one NUMA node's buffer pool
+///
+/// # Fields
+/// * `buffers` - Buffers preallocated for the node
+/// * `bound` - Whether the buffers are guaranteed local to the node
+/// * `acquired` - Successful acquisitions
+/// * `exhausted` - Acquisitions refused because the pool was empty
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NodeBufferMetrics {
+    pub buffers: usize,
+    pub bound: bool,
+    pub acquired: u64,
+    pub exhausted: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    #[test]
+    fn test_snapshot_of_empty_pool() {
+        let pool = BufferPoolAccounting::default();
+        let snapshot = pool.snapshot();
+        assert_eq!(snapshot.total, 0);
+        assert_eq!(snapshot.utilization(), 0.0);
+        assert_eq!(snapshot.pressure, PressureLevel::Normal);
+    }
+
+    #[test]
+    fn test_snapshot_per_type_breakdown() {
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 4);
+        pool.add_buffers(BufferMemoryType::ZeroCopy, 6);
+        pool.acquire(BufferMemoryType::Heap).unwrap();
+        pool.acquire(BufferMemoryType::ZeroCopy).unwrap();
+        pool.acquire(BufferMemoryType::ZeroCopy).unwrap();
+
+        let snapshot = pool.snapshot();
+        assert_eq!(snapshot.total, 10);
+        assert_eq!(snapshot.in_use, 3);
+        assert_eq!(snapshot.free, 7);
+        assert_eq!(
+            snapshot.counts(BufferMemoryType::Heap),
+            BufferTypeCounts { free: 3, in_use: 1 }
+        );
+        assert_eq!(
+            snapshot.counts(BufferMemoryType::ZeroCopy),
+            BufferTypeCounts { free: 4, in_use: 2 }
+        );
+    }
+
+    #[test]
+    fn test_acquire_and_release_bounds() {
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 1);
+
+        assert!(pool.release(BufferMemoryType::Heap).is_err());
+        pool.acquire(BufferMemoryType::Heap).unwrap();
+        let err = pool.acquire(BufferMemoryType::Heap).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable)
+        ));
+        assert!(pool.remove_buffers(BufferMemoryType::Heap, 1).is_err());
+        pool.release(BufferMemoryType::Heap).unwrap();
+        pool.remove_buffers(BufferMemoryType::Heap, 1).unwrap();
+        assert_eq!(pool.snapshot().total, 0);
+    }
+
+    #[test]
+    fn test_pressure_levels() {
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 20);
+
+        let mut levels = Vec::new();
+        for _ in 0..20 {
+            pool.acquire(BufferMemoryType::Heap).unwrap();
+            levels.push(pool.snapshot().pressure);
+        }
+
+        assert_eq!(levels[0], PressureLevel::Normal);
+        // 14/20 in use reaches the elevated threshold of 0.7
+        assert_eq!(levels[13], PressureLevel::Elevated);
+        // 17/20 in use reaches the critical threshold of 0.85
+        assert_eq!(levels[16], PressureLevel::Critical);
+        assert_eq!(levels[19], PressureLevel::Overflow);
+    }
+
+    fn watermark_events(events: &mut broadcast::Receiver<BufferEvent>) -> Vec<WatermarkType> {
+        let mut crossed = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let BufferEvent::WatermarkCrossed(watermark) = event {
+                crossed.push(watermark);
+            }
+        }
+        crossed
+    }
+
+    #[test]
+    fn test_watermark_events_are_edge_triggered() {
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 20);
+        let mut events = pool.subscribe_events();
+
+        for _ in 0..20 {
+            pool.acquire(BufferMemoryType::Heap).unwrap();
+        }
+        // 16/20 reaches high at 0.8, 19/20 reaches critical at 0.95
+        assert_eq!(
+            watermark_events(&mut events),
+            vec![WatermarkType::High, WatermarkType::Critical]
+        );
+
+        for _ in 0..11 {
+            pool.release(BufferMemoryType::Heap).unwrap();
+        }
+        // 18/20 falls below critical, 9/20 falls below low at 0.5
+        assert_eq!(
+            watermark_events(&mut events),
+            vec![WatermarkType::High, WatermarkType::Low]
+        );
+
+        // Hovering between the low and high watermarks crosses nothing
+        for _ in 0..10 {
+            pool.acquire(BufferMemoryType::Heap).unwrap();
+            pool.release(BufferMemoryType::Heap).unwrap();
+        }
+        assert!(watermark_events(&mut events).is_empty());
+    }
+
+    #[test]
+    fn test_slow_subscriber_drops_oldest_events() {
+        let pool = BufferPoolAccounting::default();
+        pool.set_watermarks(BufferWatermarks {
+            low: 0.4,
+            high: 0.5,
+            critical: 1.0,
+        })
+        .unwrap();
+        pool.add_buffers(BufferMemoryType::Heap, 2);
+        let mut events = pool.subscribe_events();
+
+        for _ in 0..100 {
+            pool.acquire(BufferMemoryType::Heap).unwrap();
+            pool.release(BufferMemoryType::Heap).unwrap();
+        }
+
+        assert!(matches!(
+            events.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(lagged))
+                if lagged == 200 - BUFFER_EVENT_CAPACITY as u64
+        ));
+        let retained = watermark_events(&mut events);
+        assert_eq!(retained.len(), BUFFER_EVENT_CAPACITY);
+        assert_eq!(retained.last(), Some(&WatermarkType::Low));
+    }
+
+    #[test]
+    fn test_invalid_watermarks() {
+        let pool = BufferPoolAccounting::default();
+        for (low, high, critical) in [(0.0, 0.8, 0.9), (0.8, 0.8, 0.9), (0.5, 0.9, 0.8)] {
+            let watermarks = BufferWatermarks {
+                low,
+                high,
+                critical,
+            };
+            assert!(pool.set_watermarks(watermarks).is_err());
+        }
+    }
+
+    #[test]
+    fn test_invalid_thresholds() {
+        let result = BufferPoolAccounting::with_thresholds(
+            PressureThresholds {
+                elevated: 0.9,
+                critical: 0.5,
+                overflow: 1.0,
+            },
+            DEFAULT_HYSTERESIS,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_pressure_does_not_flap_at_threshold() {
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 100);
+        for _ in 0..70 {
+            pool.acquire(BufferMemoryType::Heap).unwrap();
+        }
+        assert_eq!(pool.snapshot().pressure, PressureLevel::Elevated);
+
+        // Utilization oscillates between 0.69 and 0.70 around the elevated threshold
+        for _ in 0..50 {
+            pool.release(BufferMemoryType::Heap).unwrap();
+            assert_eq!(pool.snapshot().pressure, PressureLevel::Elevated);
+            pool.acquire(BufferMemoryType::Heap).unwrap();
+            assert_eq!(pool.snapshot().pressure, PressureLevel::Elevated);
+        }
+
+        // Falling past the hysteresis margin lowers the level
+        for _ in 0..6 {
+            pool.release(BufferMemoryType::Heap).unwrap();
+        }
+        assert_eq!(pool.snapshot().pressure, PressureLevel::Normal);
+    }
+
+    #[test]
+    fn test_snapshot_invariant_under_load() {
+        let pool = Arc::new(BufferPoolAccounting::default());
+        pool.add_buffers(BufferMemoryType::Heap, 64);
+        pool.add_buffers(BufferMemoryType::ZeroCopy, 64);
+        let running = Arc::new(AtomicBool::new(true));
+
+        let workers: Vec<_> = (0..8)
+            .map(|i| {
+                let pool = Arc::clone(&pool);
+                let running = Arc::clone(&running);
+                let memory_type = if i % 2 == 0 {
+                    BufferMemoryType::Heap
+                } else {
+                    BufferMemoryType::ZeroCopy
+                };
+                thread::spawn(move || {
+                    let mut held = 0;
+                    while running.load(Ordering::Relaxed) {
+                        if held < 8 && pool.acquire(memory_type).is_ok() {
+                            held += 1;
+                        } else if held > 0 {
+                            pool.release(memory_type).unwrap();
+                            held -= 1;
+                        }
+                    }
+                    for _ in 0..held {
+                        pool.release(memory_type).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let pool = Arc::clone(&pool);
+                thread::spawn(move || {
+                    for _ in 0..20_000 {
+                        let snapshot = pool.snapshot();
+                        assert_eq!(snapshot.free + snapshot.in_use, snapshot.total);
+                        assert_eq!(snapshot.total, 128);
+                        assert_eq!(snapshot.heap.total(), 64);
+                        assert_eq!(snapshot.zero_copy.total(), 64);
+                    }
+                })
+            })
+            .collect();
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        running.store(false, Ordering::Relaxed);
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let snapshot = pool.snapshot();
+        assert_eq!(snapshot.in_use, 0);
+        assert_eq!(snapshot.free, 128);
+    }
+
+    /// Records which node each allocation was made for
+    #[derive(Default)]
+    struct RecordingAllocator {
+        nodes: Mutex<Vec<u32>>,
+    }
+
+    impl NodeAllocator for RecordingAllocator {
+        fn bind(&self, node: u32) -> bool {
+            node == 1
+        }
+
+        fn allocate(&self, node: u32, size: usize) -> Vec<u8> {
+            self.nodes.lock().push(node);
+            vec![node as u8; size]
+        }
+    }
+
+    fn numa_config(nodes: Vec<u32>) -> NumaPoolConfig {
+        NumaPoolConfig {
+            nodes,
+            buffers_per_node: 2,
+            buffer_size: 64,
+        }
+    }
+
+    #[test]
+    fn test_two_node_pools_serve_local_buffers() {
+        let allocator = RecordingAllocator::default();
+        let accounting = Arc::new(BufferPoolAccounting::default());
+        let pools = NumaBufferPools::new(
+            &numa_config(vec![1, 0]),
+            &allocator,
+            Arc::clone(&accounting),
+        )
+        .unwrap();
+        assert!(pools.is_numa());
+        assert_eq!(pools.nodes(), vec![0, 1]);
+        assert_eq!(*allocator.nodes.lock(), vec![0, 0, 1, 1]);
+
+        let first = pools.acquire_on_node(1).unwrap();
+        let second = pools.acquire_on_node(1).unwrap();
+        assert_eq!((first.node(), second.node()), (1, 1));
+        assert!(first.as_slice().iter().all(|&byte| byte == 1));
+
+        // Node 1 is exhausted; node 0's buffers are not borrowed
+        let err = pools.acquire_on_node(1).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable)
+        ));
+        assert!(pools.acquire_on_node(7).is_err());
+
+        let metrics = pools.metrics();
+        assert_eq!(
+            metrics.node(1),
+            Some(&NodeBufferMetrics {
+                buffers: 2,
+                bound: true,
+                acquired: 2,
+                exhausted: 1,
+            })
+        );
+        assert_eq!(metrics.node(0).map(|node| node.bound), Some(false));
+        assert_eq!(metrics.node(0).map(|node| node.acquired), Some(0));
+
+        let snapshot = accounting.snapshot();
+        assert_eq!(snapshot.in_use, 2);
+        assert_eq!(snapshot.nodes[&0], BufferTypeCounts { free: 2, in_use: 0 });
+        assert_eq!(snapshot.nodes[&1], BufferTypeCounts { free: 0, in_use: 2 });
+        assert_eq!(snapshot.node_utilization(1), 1.0);
+        assert_eq!(snapshot.node_utilization(0), 0.0);
+
+        pools.release(first).unwrap();
+        pools.release(second).unwrap();
+        let snapshot = accounting.snapshot();
+        assert_eq!(snapshot.nodes[&1], BufferTypeCounts { free: 2, in_use: 0 });
+        assert_eq!(snapshot.heap, BufferTypeCounts { free: 4, in_use: 0 });
+    }
+
+    #[test]
+    fn test_single_node_falls_back_to_default_pool() {
+        let accounting = Arc::new(BufferPoolAccounting::default());
+        let pools = NumaBufferPools::new(
+            &numa_config(Vec::new()),
+            &FirstTouchAllocator,
+            Arc::clone(&accounting),
+        )
+        .unwrap();
+        assert!(!pools.is_numa());
+
+        let mut buffer = pools.acquire_on_node(3).unwrap();
+        assert_eq!(buffer.node(), 0);
+        assert_eq!(buffer.as_mut_slice().len(), 64);
+        assert_eq!(accounting.snapshot().nodes[&0].in_use, 1);
+        pools.release(buffer).unwrap();
+        assert_eq!(accounting.snapshot().in_use, 0);
+    }
+
+    /// Records the thread each node's buffers were allocated on
+    #[derive(Default)]
+    struct ThreadRecordingAllocator {
+        threads: Mutex<Vec<(u32, thread::ThreadId)>>,
+    }
+
+    impl NodeAllocator for ThreadRecordingAllocator {
+        fn allocate(&self, node: u32, size: usize) -> Vec<u8> {
+            self.threads.lock().push((node, thread::current().id()));
+            vec![0; size]
+        }
+    }
+
+    #[test]
+    fn test_numa_pools_allocate_off_the_calling_thread() {
+        let allocator = ThreadRecordingAllocator::default();
+        let accounting = Arc::new(BufferPoolAccounting::default());
+        NumaBufferPools::new(&numa_config(vec![0, 1]), &allocator, accounting).unwrap();
+
+        let threads = allocator.threads.lock();
+        let caller = thread::current().id();
+        assert!(threads.iter().all(|&(_, id)| id != caller));
+        // Both buffers of a node come from one thread, distinct from the other node's
+        assert_eq!(threads[0].1, threads[1].1);
+        assert_eq!(threads[2].1, threads[3].1);
+        assert_ne!(threads[0].1, threads[2].1);
+    }
+
+    #[test]
+    fn test_numa_pools_reject_empty_config() {
+        let config = NumaPoolConfig {
+            buffers_per_node: 0,
+            ..numa_config(vec![0, 1])
+        };
+        let accounting = Arc::new(BufferPoolAccounting::default());
+        assert!(NumaBufferPools::new(&config, &FirstTouchAllocator, accounting).is_err());
+    }
 }
@@ -7,6 +7,7 @@ use std::time::Duration;
 
 use crate::capture_engine::capture::capture_error::CaptureError;
 use crate::capture_engine::capture::interface_manager::TimestampConfig;
+use crate::capture_engine::filter::dedup::DedupConfig;
 
 /// Main configuration structure for capture system
 #[derive(Debug, Clone)]
@@ -32,7 +33,8 @@ pub struct CaptureConfiguration {
 pub struct InterfaceConfiguration {
     pub interface_name: String,
     pub promiscuous_mode: bool,
-    pub snaplen: usize,
+    /// Bytes kept per packet, None to keep whole frames
+    pub snaplen: Option<usize>,
     pub buffer_size: usize,
     pub timeout: Duration,
     pub timestamps: TimestampConfig,
@@ -58,6 +60,8 @@ pub struct FilterConfiguration {
     pub custom_filters: Vec<String>,
     pub optimization_level: OptimizationLevel,
     pub hardware_offload: bool,
+    /// Duplicate suppression window and capacity; None keeps duplicate packets
+    pub dedup: Option<DedupConfig>,
 }
 
 /// Cloud-specific configuration
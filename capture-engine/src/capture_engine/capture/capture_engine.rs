@@ -4,13 +4,18 @@
 // capture_engine.rs
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+use crate::capture_engine::capture::adaptive_batch::{AdaptiveBatchConfig, AdaptiveBatchSizer};
 use crate::capture_engine::capture::buffer_manager::BufferManager;
 use crate::capture_engine::capture::capture_config::CaptureConfiguration;
-use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, RuntimeErrorKind,
+};
 use crate::capture_engine::capture::capture_session::{CaptureSession, SessionConfiguration};
 use crate::capture_engine::capture::capture_statistics::CaptureStatistics;
 use crate::capture_engine::capture::interface_manager::InterfaceManager;
+use crate::capture_engine::capture::shutdown_drain::ShutdownDrain;
 use crate::capture_engine::capture::state_machine::{StateMachine, StateTransition};
 use crate::capture_engine::capture::state_recovery::StateSnapshot;
 use crate::capture_engine::capture::state_sync::{StateChangeEvent, StateSync};
@@ -51,6 +56,10 @@ pub struct CaptureEngine {
 
     // Monitoring and statistics
     statistics: Arc<RwLock<CaptureStatistics>>,
+    batch_sizer: AdaptiveBatchSizer,
+
+    // Shutdown
+    shutdown_drain: ShutdownDrain,
 }
 
 impl Default for CaptureEngine {
@@ -109,6 +118,67 @@ impl CaptureEngine {
     pub async fn validate_global_state(&self) -> Result<(), CaptureError> {
         unimplemented!()
     }
+
+    /// Replaces the bounds and step of pressure-driven batch sizing
+    ///
+    /// # Arguments
+    /// * `config` - Bounds and step; the batch restarts at `max_batch`
+    ///
+    /// # Returns
+    /// An error if the configuration is invalid
+    pub fn configure_adaptive_batch(
+        &mut self,
+        config: AdaptiveBatchConfig,
+    ) -> Result<(), CaptureError> {
+        self.batch_sizer = AdaptiveBatchSizer::new(config)?;
+        Ok(())
+    }
+
+    /// Sizes the next pipeline batch from the buffer manager's pressure
+    ///
+    /// # Returns
+    /// The number of packets to take in the next batch
+    pub fn next_batch_size(&mut self) -> Result<usize, CaptureError> {
+        let pool = self
+            .buffer_manager
+            .read()
+            .map_err(|_| {
+                *CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::SyncLockFailure),
+                    "Buffer manager lock poisoned",
+                )
+            })?
+            .pool_accounting();
+        Ok(self.batch_sizer.next_batch_size(&pool))
+    }
+
+    /// Returns the batch sizer, whose telemetry reports the effective batch size
+    pub fn batch_sizer(&self) -> &AdaptiveBatchSizer {
+        &self.batch_sizer
+    }
+
+    /// Returns the drain used on shutdown, for registering ingest sources, stages and outputs
+    pub fn shutdown_drain_mut(&mut self) -> &mut ShutdownDrain {
+        &mut self.shutdown_drain
+    }
+
+    /// Stops ingest and drains in-flight packets before stopping the engine
+    ///
+    /// # Arguments
+    /// * `timeout` - Bound on the whole drain
+    ///
+    /// # Returns
+    /// An error if the drain failed; on timeout the engine stays in `Stopping` and the error
+    /// names the buffers still outstanding
+    pub async fn drain_and_shutdown(&mut self, timeout: Duration) -> Result<(), CaptureError> {
+        self.state_machine.transition_to(
+            EngineState::Stopping,
+            Some("drain and shutdown".to_string()),
+        )?;
+        self.shutdown_drain.drain(timeout).await?;
+        self.state_machine
+            .transition_to(EngineState::Stopped, Some("drain complete".to_string()))
+    }
 }
 
 pub struct CaptureEngineBuilder {
@@ -1,8 +1,9 @@
 // capture-engine/src/capture/capture_error.rs
 /// Error types used by the capture engine.
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt;
-use std::time::SystemTime;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Core error type that represents all possible errors in the capture system
 ///
@@ -37,7 +38,7 @@ pub struct CaptureError {
 /// - `trace_id` - The trace ID for debugging
 /// - `retry_count` - The number of retries attempted
 /// - `severity` - The severity level of the error
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ErrorContext {
     // Cloud context
     instance_id: Option<String>,
@@ -136,7 +137,7 @@ impl ErrorContext {
 /// - `Error` - Standard error conditions
 /// - `Warning` - Non-critical warnings
 /// - `Info` - Informational messages
-#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ErrorSeverity {
     Critical,
     #[default]
@@ -168,7 +169,8 @@ pub type CaptureResult<T> = Result<T, Box<CaptureError>>;
 /// - `Runtime` - Runtime operational errors
 /// - `Cloud` - Cloud-specific errors
 /// - `Security` - Security-related errors
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CaptureErrorKind {
     // Infrastructure errors
     Network(NetworkErrorKind),
@@ -186,6 +188,117 @@ pub enum CaptureErrorKind {
     Security(SecurityErrorKind),
 }
 
+impl CaptureErrorKind {
+    /// Gets the catalog code identifying the error kind
+    ///
+    /// Catalog codes are opaque and never change when a variant is renamed. The part before
+    /// the number names the category, e.g. `CAP-NET`. Descriptions are looked up with
+    /// `error_messages::describe`.
+    ///
+    /// # Returns
+    /// The code of the specific kind, e.g. `CAP-NET-0001`
+    pub fn catalog_code(&self) -> &'static str {
+        match self {
+            CaptureErrorKind::Network(kind) => match kind {
+                NetworkErrorKind::InterfaceNotFound => "CAP-NET-0001",
+                NetworkErrorKind::CaptureFailure => "CAP-NET-0002",
+                NetworkErrorKind::FilterError => "CAP-NET-0003",
+                NetworkErrorKind::Timeout => "CAP-NET-0004",
+                NetworkErrorKind::BufferOverflow => "CAP-NET-0005",
+                NetworkErrorKind::DriverError => "CAP-NET-0006",
+            },
+            CaptureErrorKind::System(kind) => match kind {
+                SystemErrorKind::MemoryError => "CAP-SYS-0001",
+                SystemErrorKind::ThreadError => "CAP-SYS-0002",
+                SystemErrorKind::IoError => "CAP-SYS-0003",
+                SystemErrorKind::TimerError => "CAP-SYS-0004",
+                SystemErrorKind::ResourceExhausted => "CAP-SYS-0005",
+                SystemErrorKind::InitializationFailed => "CAP-SYS-0006",
+            },
+            CaptureErrorKind::Resource(kind) => match kind {
+                ResourceErrorKind::NotAvailable => "CAP-RES-0001",
+                ResourceErrorKind::QuotaExceeded => "CAP-RES-0002",
+                ResourceErrorKind::AllocationFailed => "CAP-RES-0003",
+                ResourceErrorKind::InvalidState => "CAP-RES-0004",
+            },
+            CaptureErrorKind::Configuration(kind) => match kind {
+                ConfigErrorKind::InvalidValue => "CAP-CFG-0001",
+                ConfigErrorKind::MissingRequired => "CAP-CFG-0002",
+                ConfigErrorKind::ValidationFailed => "CAP-CFG-0003",
+                ConfigErrorKind::ParseError => "CAP-CFG-0004",
+            },
+            CaptureErrorKind::Runtime(kind) => match kind {
+                RuntimeErrorKind::EntityNotFound => "CAP-RUN-0001",
+                RuntimeErrorKind::OperationFailed => "CAP-RUN-0002",
+                RuntimeErrorKind::StateError => "CAP-RUN-0003",
+                RuntimeErrorKind::ConcurrencyError => "CAP-RUN-0004",
+                RuntimeErrorKind::Timeout => "CAP-RUN-0005",
+                RuntimeErrorKind::SyncLockFailure => "CAP-RUN-0006",
+            },
+            CaptureErrorKind::Cloud(kind) => match kind {
+                CloudErrorKind::VpcError => "CAP-CLD-0001",
+                CloudErrorKind::EniError => "CAP-CLD-0002",
+                CloudErrorKind::MetadataError => "CAP-CLD-0003",
+                CloudErrorKind::ScalingError => "CAP-CLD-0004",
+                CloudErrorKind::ApiError => "CAP-CLD-0005",
+            },
+            CaptureErrorKind::Security(kind) => match kind {
+                SecurityErrorKind::AccessDenied => "CAP-SEC-0001",
+                SecurityErrorKind::AuthenticationFailed => "CAP-SEC-0002",
+                SecurityErrorKind::EncryptionError => "CAP-SEC-0003",
+                SecurityErrorKind::InvalidCredentials => "CAP-SEC-0004",
+            },
+        }
+    }
+
+    /// Whether an operation failing with this kind may succeed if retried
+    ///
+    /// Timeouts, lock contention, transient unavailability and cloud API failures are
+    /// retryable. Configuration and security errors, and errors describing a state that will
+    /// not change by itself, are not.
+    ///
+    /// # Returns
+    /// True if the failure is transient
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CaptureErrorKind::Network(kind) => match kind {
+                NetworkErrorKind::Timeout
+                | NetworkErrorKind::CaptureFailure
+                | NetworkErrorKind::BufferOverflow => true,
+                NetworkErrorKind::InterfaceNotFound
+                | NetworkErrorKind::FilterError
+                | NetworkErrorKind::DriverError => false,
+            },
+            CaptureErrorKind::System(kind) => match kind {
+                SystemErrorKind::IoError | SystemErrorKind::ResourceExhausted => true,
+                SystemErrorKind::MemoryError
+                | SystemErrorKind::ThreadError
+                | SystemErrorKind::TimerError
+                | SystemErrorKind::InitializationFailed => false,
+            },
+            CaptureErrorKind::Resource(kind) => match kind {
+                ResourceErrorKind::NotAvailable | ResourceErrorKind::AllocationFailed => true,
+                ResourceErrorKind::QuotaExceeded | ResourceErrorKind::InvalidState => false,
+            },
+            CaptureErrorKind::Configuration(_) => false,
+            CaptureErrorKind::Runtime(kind) => match kind {
+                RuntimeErrorKind::OperationFailed
+                | RuntimeErrorKind::ConcurrencyError
+                | RuntimeErrorKind::Timeout
+                | RuntimeErrorKind::SyncLockFailure => true,
+                RuntimeErrorKind::EntityNotFound | RuntimeErrorKind::StateError => false,
+            },
+            CaptureErrorKind::Cloud(kind) => match kind {
+                CloudErrorKind::MetadataError | CloudErrorKind::ApiError => true,
+                CloudErrorKind::VpcError
+                | CloudErrorKind::EniError
+                | CloudErrorKind::ScalingError => false,
+            },
+            CaptureErrorKind::Security(_) => false,
+        }
+    }
+}
+
 /// Network-related errors
 ///
 /// This enum represents the different types of network-related errors that can occur in the system.
@@ -197,7 +310,8 @@ pub enum CaptureErrorKind {
 /// - `Timeout` - A network operation timed out
 /// - `BufferOverflow` - A buffer overflow occurred
 /// - `DriverError` - An error occurred in the network driver
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NetworkErrorKind {
     InterfaceNotFound,
     CaptureFailure,
@@ -217,13 +331,16 @@ pub enum NetworkErrorKind {
 /// - `IoError` - An I/O operation failed
 /// - `TimerError` - An error occurred while managing timers
 /// - `ResourceExhausted` - A system resource was exhausted
-#[derive(Debug)]
+/// - `InitializationFailed` - A process-wide runtime such as the DPDK EAL could not be set up
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SystemErrorKind {
     MemoryError,
     ThreadError,
     IoError,
     TimerError,
     ResourceExhausted,
+    InitializationFailed,
 }
 
 /// Resource management errors
@@ -235,7 +352,8 @@ pub enum SystemErrorKind {
 /// - `QuotaExceeded` - The resource quota was exceeded
 /// - `AllocationFailed` - Resource allocation failed
 /// - `InvalidState` - The resource is in an invalid state
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ResourceErrorKind {
     NotAvailable,
     QuotaExceeded,
@@ -252,7 +370,8 @@ pub enum ResourceErrorKind {
 /// - `MissingRequired` - A required value is missing
 /// - `ValidationFailed` - Validation of a value failed
 /// - `ParseError` - An error occurred while parsing a value
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ConfigErrorKind {
     InvalidValue,
     MissingRequired,
@@ -271,7 +390,8 @@ pub enum ConfigErrorKind {
 /// - `ConcurrencyError` - A concurrency error occurred
 /// - `Timeout` - An operation timed out
 /// - `SyncLockFailure` - A synchronization lock failed
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RuntimeErrorKind {
     EntityNotFound,
     OperationFailed,
@@ -291,7 +411,8 @@ pub enum RuntimeErrorKind {
 /// - `MetadataError` - An error occurred while accessing metadata
 /// - `ScalingError` - An error occurred while scaling resources
 /// - `ApiError` - An error occurred while calling an API
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CloudErrorKind {
     VpcError,
     EniError,
@@ -309,7 +430,8 @@ pub enum CloudErrorKind {
 /// - `AuthenticationFailed` - Authentication failed
 /// - `EncryptionError` - An error occurred while encrypting data
 /// - `InvalidCredentials` - Invalid credentials were provided
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SecurityErrorKind {
     AccessDenied,
     AuthenticationFailed,
@@ -330,6 +452,62 @@ impl From<Box<CaptureError>> for CaptureError {
     }
 }
 
+impl From<std::io::Error> for Box<CaptureError> {
+    /// Converts an I/O error, keeping it as the source
+    ///
+    /// # Arguments
+    /// * `error` - The I/O error to convert
+    ///
+    /// # Returns
+    /// A `Network(Timeout)` error for timed-out I/O, otherwise `System(IoError)`
+    fn from(error: std::io::Error) -> Self {
+        let kind = match error.kind() {
+            std::io::ErrorKind::TimedOut => CaptureErrorKind::Network(NetworkErrorKind::Timeout),
+            _ => CaptureErrorKind::System(SystemErrorKind::IoError),
+        };
+        let message = error.to_string();
+        CaptureError::new(kind, &message).with_source(error).build()
+    }
+}
+
+impl From<std::time::SystemTimeError> for Box<CaptureError> {
+    /// Converts a clock error, such as a time before the epoch, keeping it as the source
+    ///
+    /// # Arguments
+    /// * `error` - The clock error to convert
+    ///
+    /// # Returns
+    /// A `System(TimerError)` error
+    fn from(error: std::time::SystemTimeError) -> Self {
+        let message = error.to_string();
+        CaptureError::new(
+            CaptureErrorKind::System(SystemErrorKind::TimerError),
+            &message,
+        )
+        .with_source(error)
+        .build()
+    }
+}
+
+impl From<tokio::time::error::Elapsed> for Box<CaptureError> {
+    /// Converts an elapsed tokio timeout, keeping it as the source
+    ///
+    /// # Arguments
+    /// * `error` - The elapsed timeout to convert
+    ///
+    /// # Returns
+    /// A `Runtime(Timeout)` error
+    fn from(error: tokio::time::error::Elapsed) -> Self {
+        let message = error.to_string();
+        CaptureError::new(
+            CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout),
+            &message,
+        )
+        .with_source(error)
+        .build()
+    }
+}
+
 impl CaptureError {
     /// Creates a new boxed CaptureError with the specified error kind and message
     ///
@@ -403,6 +581,30 @@ impl CaptureError {
         &self.kind
     }
 
+    /// Gets the error message
+    ///
+    /// # Returns
+    /// Description of the error
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Gets the stable catalog code of the error kind
+    ///
+    /// # Returns
+    /// The code, e.g. `CAP-NET-0001`, for alerting to key off
+    pub fn code(&self) -> &'static str {
+        self.kind.catalog_code()
+    }
+
+    /// Gets the time the error occurred
+    ///
+    /// # Returns
+    /// The time when the error was created
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
     /// Gets the error context
     ///
     /// # Returns
@@ -411,6 +613,64 @@ impl CaptureError {
         &self.context
     }
 
+    /// Whether the failed operation may succeed if retried
+    ///
+    /// # Returns
+    /// True if the error kind is transient
+    pub fn is_retryable(&self) -> bool {
+        self.kind.is_retryable()
+    }
+
+    /// Renders the error as a structured log record
+    ///
+    /// The record holds `kind` as snake_case discriminants (e.g. `{"network": "timeout"}`),
+    /// the catalog `code`, `message`, an RFC 3339 `timestamp`, `severity`, each populated
+    /// context field and `retry_count`, plus a `causes` array with the message of every source
+    /// error, outermost first.
+    ///
+    /// # Returns
+    /// The error as a JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut record = serde_json::Map::new();
+        record.insert(
+            "kind".to_string(),
+            serde_json::to_value(&self.kind).unwrap_or(serde_json::Value::Null),
+        );
+        record.insert("code".to_string(), self.code().into());
+        record.insert("message".to_string(), self.message.clone().into());
+        record.insert("timestamp".to_string(), rfc3339(self.timestamp).into());
+        record.insert(
+            "severity".to_string(),
+            serde_json::to_value(self.context.severity).unwrap_or(serde_json::Value::Null),
+        );
+
+        let context = &self.context;
+        let fields = [
+            ("instance_id", &context.instance_id),
+            ("region", &context.region),
+            ("vpc_id", &context.vpc_id),
+            ("operation", &context.operation),
+            ("component", &context.component),
+            ("resource_id", &context.resource_id),
+            ("trace_id", &context.trace_id),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                record.insert(name.to_string(), value.clone().into());
+            }
+        }
+        record.insert("retry_count".to_string(), context.retry_count.into());
+
+        let mut causes = Vec::new();
+        let mut source = Error::source(self);
+        while let Some(cause) = source {
+            causes.push(serde_json::Value::from(cause.to_string()));
+            source = cause.source();
+        }
+        record.insert("causes".to_string(), causes.into());
+        serde_json::Value::Object(record)
+    }
+
     /// Gets error severity
     ///
     /// # Returns
@@ -444,6 +704,35 @@ impl Default for CaptureError {
     }
 }
 
+/// Formats a time as an RFC 3339 UTC timestamp with millisecond precision
+fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, day_secs) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        day_secs / 3_600,
+        day_secs / 60 % 60,
+        day_secs % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
 impl fmt::Display for CaptureError {
     /// Formats the error for display
     ///
@@ -455,8 +744,11 @@ impl fmt::Display for CaptureError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{} (kind: {:?}, timestamp: {:?})",
-            self.message, self.kind, self.timestamp
+            "[{}] {} (kind: {:?}, timestamp: {:?})",
+            self.code(),
+            self.message,
+            self.kind,
+            self.timestamp
         )
     }
 }
@@ -578,6 +870,30 @@ impl ErrorBuilder {
         self
     }
 
+    /// Sets the component the error occurred in
+    ///
+    /// # Arguments
+    /// * `component` - The component that caused the error
+    ///
+    /// # Returns
+    /// A mutable reference to the ErrorBuilder with the component set
+    pub fn component(mut self, component: &str) -> Self {
+        self.context.component = Some(component.to_string());
+        self
+    }
+
+    /// Sets the operation that failed
+    ///
+    /// # Arguments
+    /// * `operation` - The operation that caused the error
+    ///
+    /// # Returns
+    /// A mutable reference to the ErrorBuilder with the operation set
+    pub fn operation(mut self, operation: &str) -> Self {
+        self.context.operation = Some(operation.to_string());
+        self
+    }
+
     /// Sets the cloud context
     ///
     /// # Arguments
@@ -648,6 +964,360 @@ mod tests {
     use std::time::SystemTime;
 
     // CaptureError Tests
+    #[test]
+    fn test_to_json_structured_record() {
+        let io = std::io::Error::new(std::io::ErrorKind::TimedOut, "socket timed out");
+        let mut error = ErrorBuilder::new()
+            .kind(CaptureErrorKind::Cloud(CloudErrorKind::ApiError))
+            .message("DescribeInstances failed")
+            .severity(ErrorSeverity::Warning)
+            .component("cloud")
+            .operation("describe_instances")
+            .cloud_context("i-0abc", "us-east-1", "vpc-1")
+            .retry_count(2)
+            .source(io)
+            .build()
+            .unwrap();
+        error.timestamp = UNIX_EPOCH + std::time::Duration::from_millis(1_700_000_000_123);
+
+        let json = error.to_json();
+        assert_eq!(json["kind"], serde_json::json!({"cloud": "api_error"}));
+        assert_eq!(json["code"], "CAP-CLD-0005");
+        assert_eq!(json["message"], "DescribeInstances failed");
+        assert_eq!(json["timestamp"], "2023-11-14T22:13:20.123Z");
+        assert_eq!(json["severity"], "Warning");
+        assert_eq!(json["instance_id"], "i-0abc");
+        assert_eq!(json["region"], "us-east-1");
+        assert_eq!(json["vpc_id"], "vpc-1");
+        assert_eq!(json["operation"], "describe_instances");
+        assert_eq!(json["component"], "cloud");
+        assert_eq!(json["retry_count"], 2);
+        assert_eq!(json["causes"], serde_json::json!(["socket timed out"]));
+        assert!(json.get("trace_id").is_none());
+        assert!(json.get("resource_id").is_none());
+    }
+
+    #[test]
+    fn test_to_json_flattens_cause_chain() {
+        let inner = CaptureError::new(
+            CaptureErrorKind::System(SystemErrorKind::IoError),
+            "disk unavailable",
+        )
+        .with_source(std::io::Error::other("EIO"));
+        let outer = CaptureError::new(
+            CaptureErrorKind::Runtime(RuntimeErrorKind::SyncLockFailure),
+            "snapshot failed",
+        )
+        .with_source(inner);
+
+        let json = outer.to_json();
+        assert_eq!(
+            json["kind"],
+            serde_json::json!({"runtime": "sync_lock_failure"})
+        );
+        assert_eq!(json["causes"].as_array().unwrap().len(), 2);
+        assert!(json["causes"][0]
+            .as_str()
+            .unwrap()
+            .starts_with("[CAP-SYS-0003] disk unavailable"));
+        assert_eq!(json["causes"][1], "EIO");
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            rfc3339(UNIX_EPOCH + std::time::Duration::from_secs(951_825_600)),
+            "2000-02-29T12:00:00.000Z"
+        );
+    }
+
+    fn all_kinds() -> Vec<CaptureErrorKind> {
+        use CaptureErrorKind as K;
+        let mut kinds = Vec::new();
+        kinds.extend(
+            [
+                NetworkErrorKind::InterfaceNotFound,
+                NetworkErrorKind::CaptureFailure,
+                NetworkErrorKind::FilterError,
+                NetworkErrorKind::Timeout,
+                NetworkErrorKind::BufferOverflow,
+                NetworkErrorKind::DriverError,
+            ]
+            .map(K::Network),
+        );
+        kinds.extend(
+            [
+                SystemErrorKind::MemoryError,
+                SystemErrorKind::ThreadError,
+                SystemErrorKind::IoError,
+                SystemErrorKind::TimerError,
+                SystemErrorKind::ResourceExhausted,
+                SystemErrorKind::InitializationFailed,
+            ]
+            .map(K::System),
+        );
+        kinds.extend(
+            [
+                ResourceErrorKind::NotAvailable,
+                ResourceErrorKind::QuotaExceeded,
+                ResourceErrorKind::AllocationFailed,
+                ResourceErrorKind::InvalidState,
+            ]
+            .map(K::Resource),
+        );
+        kinds.extend(
+            [
+                ConfigErrorKind::InvalidValue,
+                ConfigErrorKind::MissingRequired,
+                ConfigErrorKind::ValidationFailed,
+                ConfigErrorKind::ParseError,
+            ]
+            .map(K::Configuration),
+        );
+        kinds.extend(
+            [
+                RuntimeErrorKind::EntityNotFound,
+                RuntimeErrorKind::OperationFailed,
+                RuntimeErrorKind::StateError,
+                RuntimeErrorKind::ConcurrencyError,
+                RuntimeErrorKind::Timeout,
+                RuntimeErrorKind::SyncLockFailure,
+            ]
+            .map(K::Runtime),
+        );
+        kinds.extend(
+            [
+                CloudErrorKind::VpcError,
+                CloudErrorKind::EniError,
+                CloudErrorKind::MetadataError,
+                CloudErrorKind::ScalingError,
+                CloudErrorKind::ApiError,
+            ]
+            .map(K::Cloud),
+        );
+        kinds.extend(
+            [
+                SecurityErrorKind::AccessDenied,
+                SecurityErrorKind::AuthenticationFailed,
+                SecurityErrorKind::EncryptionError,
+                SecurityErrorKind::InvalidCredentials,
+            ]
+            .map(K::Security),
+        );
+        kinds
+    }
+
+    #[test]
+    fn test_every_kind_has_unique_described_code() {
+        use crate::capture_engine::capture::error_messages::{describe, ERROR_CATALOG};
+        use std::collections::HashSet;
+
+        let kinds = all_kinds();
+        let mut seen = HashSet::new();
+        for kind in &kinds {
+            let code = kind.catalog_code();
+            assert!(!code.is_empty(), "{:?}", kind);
+            assert!(seen.insert(code), "duplicate code {}", code);
+            assert!(
+                describe(code).is_some_and(|text| !text.is_empty()),
+                "{} has no description",
+                code
+            );
+        }
+
+        // No catalog entry is orphaned or listed twice
+        let catalog: HashSet<&str> = ERROR_CATALOG.iter().map(|(code, _)| *code).collect();
+        assert_eq!(catalog.len(), ERROR_CATALOG.len());
+        assert_eq!(catalog, seen);
+    }
+
+    fn read_config(error: std::io::Error) -> CaptureResult<()> {
+        Err(error)?
+    }
+
+    #[test]
+    fn test_io_errors_convert_with_source() {
+        let error = read_config(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "config missing",
+        ))
+        .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            CaptureErrorKind::System(SystemErrorKind::IoError)
+        ));
+        assert_eq!(error.message(), "config missing");
+        assert_eq!(error.source().unwrap().to_string(), "config missing");
+
+        let error = read_config(std::io::ErrorKind::TimedOut.into()).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            CaptureErrorKind::Network(NetworkErrorKind::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_clock_errors_convert() {
+        let before_epoch = UNIX_EPOCH - std::time::Duration::from_secs(1);
+        let elapsed =
+            || -> CaptureResult<u64> { Ok(before_epoch.duration_since(UNIX_EPOCH)?.as_secs()) };
+        let error = elapsed().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            CaptureErrorKind::System(SystemErrorKind::TimerError)
+        ));
+        assert!(error.source().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_elapsed_timeout_converts() {
+        let wait = async {
+            tokio::time::timeout(
+                std::time::Duration::from_millis(1),
+                std::future::pending::<()>(),
+            )
+            .await?;
+            CaptureResult::Ok(())
+        };
+        let error = wait.await.unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout)
+        ));
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_display_prefixes_code() {
+        let error = CaptureError::new(
+            CaptureErrorKind::Network(NetworkErrorKind::InterfaceNotFound),
+            "eth9 missing",
+        );
+        assert_eq!(error.code(), "CAP-NET-0001");
+        assert!(error.to_string().starts_with("[CAP-NET-0001] eth9 missing"));
+        assert_eq!(
+            crate::capture_engine::capture::error_messages::describe("CAP-NET-9999"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_retryable_classification() {
+        let cases = [
+            (
+                CaptureErrorKind::Network(NetworkErrorKind::InterfaceNotFound),
+                false,
+            ),
+            (
+                CaptureErrorKind::Network(NetworkErrorKind::CaptureFailure),
+                true,
+            ),
+            (
+                CaptureErrorKind::Network(NetworkErrorKind::FilterError),
+                false,
+            ),
+            (CaptureErrorKind::Network(NetworkErrorKind::Timeout), true),
+            (
+                CaptureErrorKind::Network(NetworkErrorKind::BufferOverflow),
+                true,
+            ),
+            (
+                CaptureErrorKind::Network(NetworkErrorKind::DriverError),
+                false,
+            ),
+            (
+                CaptureErrorKind::System(SystemErrorKind::MemoryError),
+                false,
+            ),
+            (
+                CaptureErrorKind::System(SystemErrorKind::ThreadError),
+                false,
+            ),
+            (CaptureErrorKind::System(SystemErrorKind::IoError), true),
+            (CaptureErrorKind::System(SystemErrorKind::TimerError), false),
+            (
+                CaptureErrorKind::System(SystemErrorKind::ResourceExhausted),
+                true,
+            ),
+            (
+                CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable),
+                true,
+            ),
+            (
+                CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded),
+                false,
+            ),
+            (
+                CaptureErrorKind::Resource(ResourceErrorKind::AllocationFailed),
+                true,
+            ),
+            (
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                false,
+            ),
+            (
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                false,
+            ),
+            (
+                CaptureErrorKind::Configuration(ConfigErrorKind::MissingRequired),
+                false,
+            ),
+            (
+                CaptureErrorKind::Configuration(ConfigErrorKind::ValidationFailed),
+                false,
+            ),
+            (
+                CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+                false,
+            ),
+            (
+                CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound),
+                false,
+            ),
+            (
+                CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                true,
+            ),
+            (
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                false,
+            ),
+            (
+                CaptureErrorKind::Runtime(RuntimeErrorKind::ConcurrencyError),
+                true,
+            ),
+            (CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout), true),
+            (
+                CaptureErrorKind::Runtime(RuntimeErrorKind::SyncLockFailure),
+                true,
+            ),
+            (CaptureErrorKind::Cloud(CloudErrorKind::VpcError), false),
+            (CaptureErrorKind::Cloud(CloudErrorKind::EniError), false),
+            (CaptureErrorKind::Cloud(CloudErrorKind::MetadataError), true),
+            (CaptureErrorKind::Cloud(CloudErrorKind::ScalingError), false),
+            (CaptureErrorKind::Cloud(CloudErrorKind::ApiError), true),
+            (
+                CaptureErrorKind::Security(SecurityErrorKind::AccessDenied),
+                false,
+            ),
+            (
+                CaptureErrorKind::Security(SecurityErrorKind::AuthenticationFailed),
+                false,
+            ),
+            (
+                CaptureErrorKind::Security(SecurityErrorKind::EncryptionError),
+                false,
+            ),
+            (
+                CaptureErrorKind::Security(SecurityErrorKind::InvalidCredentials),
+                false,
+            ),
+        ];
+        for (kind, retryable) in cases {
+            let code = kind.catalog_code();
+            let error = CaptureError::new(kind, "test");
+            assert_eq!(error.is_retryable(), retryable, "{}", code);
+        }
+    }
+
     #[test]
     fn test_capture_error_new() {
         let error = CaptureError::new(
@@ -682,7 +1352,7 @@ mod tests {
 
     #[test]
     fn test_capture_error_with_source() {
-        let source_error = std::io::Error::new(std::io::ErrorKind::Other, "Source error");
+        let source_error = std::io::Error::other("Source error");
         let error = CaptureError::new(
             CaptureErrorKind::System(SystemErrorKind::IoError),
             "IO operation failed",
@@ -846,7 +1516,7 @@ mod tests {
 
     #[test]
     fn test_error_chaining() {
-        let base_error = std::io::Error::new(std::io::ErrorKind::Other, "Base error");
+        let base_error = std::io::Error::other("Base error");
 
         let mid_error = CaptureError::new(
             CaptureErrorKind::System(SystemErrorKind::IoError),
@@ -940,8 +1610,10 @@ mod tests {
 
     #[test]
     fn test_error_context_with_max_retries() {
-        let mut context = ErrorContext::default();
-        context.retry_count = u32::MAX;
+        let context = ErrorContext {
+            retry_count: u32::MAX,
+            ..Default::default()
+        };
         assert_eq!(context.retry_count, u32::MAX);
     }
 
@@ -982,7 +1654,7 @@ mod tests {
             .message("Test message")
             .retry_count(u32::MAX)
             .build();
-        assert!(matches!(error, Ok(_)));
+        assert!(error.is_ok());
         let error = error.unwrap();
         assert_eq!(error.context.retry_count, u32::MAX);
     }
@@ -1015,10 +1687,7 @@ mod tests {
             .message("Test message")
             .severity(ErrorSeverity::Warning)
             .retry_count(3)
-            .source(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Source error",
-            ))
+            .source(std::io::Error::other("Source error"))
             .build()
             .unwrap();
 
@@ -1079,7 +1748,7 @@ mod tests {
 
     #[test]
     fn test_error_source_chain() {
-        let source_error = std::io::Error::new(std::io::ErrorKind::Other, "Inner error");
+        let source_error = std::io::Error::other("Inner error");
         let wrapped_error = CaptureError::new(
             CaptureErrorKind::System(SystemErrorKind::IoError),
             "Middle error",
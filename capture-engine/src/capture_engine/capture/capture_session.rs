@@ -7,13 +7,26 @@ use std::time::{Duration, SystemTime};
 
 use crate::capture_engine::capture::buffer_manager::BufferManager;
 use crate::capture_engine::capture::capture_config::CaptureConfiguration;
-use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, RuntimeErrorKind,
+};
+use crate::capture_engine::capture::clock::Clock;
 use crate::capture_engine::capture::interface_manager::ManagedInterface;
 use crate::capture_engine::capture::packet_filter::PacketFilter;
-use crate::capture_engine::capture::state_machine::{StateMachine, StateTransition};
+use crate::capture_engine::capture::session_report::SessionReportConfig;
+use crate::capture_engine::capture::start_barrier::StartBarrier;
+use crate::capture_engine::capture::state_machine::{
+    StateMachine, StateTransition, TransitionReason,
+};
 use crate::capture_engine::capture::state_recovery::{RecoveryPoint, StateSnapshot};
 use crate::capture_engine::capture::state_sync::StateSync;
-use crate::capture_engine::capture::state_validator::{StateValidator, ValidationRule};
+use crate::capture_engine::capture::state_validator::{
+    StateValidator, ValidationRule, ValidatorConfig,
+};
+use crate::capture_engine::filter::sampling::PacketSampler;
+
+/// Transitions kept in a session's state history
+const SESSION_HISTORY: usize = 100;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum SessionAction {
@@ -52,6 +65,150 @@ pub struct SessionStats {
     pub packets_dropped: u64,
     pub packets_filtered: u64,
     pub state_transitions: Vec<StateTransition<SessionState>>,
+    /// Time spent paused in pauses that have ended
+    pub total_paused: Duration,
+    /// Start of the current pause, if the session is paused
+    pub paused_since: Option<SystemTime>,
+}
+
+/// A packet or byte budget that ends a capture session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionQuota {
+    Packets(u64),
+    Bytes(u64),
+}
+
+/// Outcome of counting a packet against a session's quotas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaStatus {
+    /// The packet was counted and budget remains
+    Within,
+    /// The packet was counted and used up the quota; the session must stop
+    Reached(SessionQuota),
+    /// The packet was not counted because it would exceed the quota; the session must stop
+    Exceeded(SessionQuota),
+}
+
+impl QuotaStatus {
+    /// Whether the packet was counted
+    pub fn counted(&self) -> bool {
+        !matches!(self, QuotaStatus::Exceeded(_))
+    }
+
+    /// Whether a quota is used up and the session must stop
+    pub fn quota_reached(&self) -> bool {
+        !matches!(self, QuotaStatus::Within)
+    }
+}
+
+impl SessionStats {
+    /// Counts a captured packet against the session's quotas
+    ///
+    /// Quotas are strict budgets: the packet that brings a count exactly to its limit is
+    /// counted and reaches the quota, while a packet that would take the byte count past its
+    /// limit is not counted.
+    ///
+    /// # Arguments
+    /// * `bytes` - Captured length of the packet
+    /// * `max_packets` - Packet quota, None for no limit
+    /// * `max_bytes` - Byte quota, None for no limit
+    ///
+    /// # Returns
+    /// Whether the packet was counted and whether a quota is used up
+    pub fn record_packet(
+        &mut self,
+        bytes: u64,
+        max_packets: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> QuotaStatus {
+        if let Some(max) = max_packets.filter(|max| self.packets_captured >= *max) {
+            return QuotaStatus::Exceeded(SessionQuota::Packets(max));
+        }
+        if let Some(max) = max_bytes.filter(|max| self.bytes_captured.saturating_add(bytes) > *max)
+        {
+            return QuotaStatus::Exceeded(SessionQuota::Bytes(max));
+        }
+        self.packets_captured += 1;
+        self.bytes_captured += bytes;
+        if let Some(max) = max_packets.filter(|max| self.packets_captured >= *max) {
+            return QuotaStatus::Reached(SessionQuota::Packets(max));
+        }
+        if let Some(max) = max_bytes.filter(|max| self.bytes_captured >= *max) {
+            return QuotaStatus::Reached(SessionQuota::Bytes(max));
+        }
+        QuotaStatus::Within
+    }
+
+    /// Records that the session started
+    ///
+    /// # Arguments
+    /// * `at` - Time the session started; ignored if it has already started
+    pub fn record_start(&mut self, at: SystemTime) {
+        self.start_time.get_or_insert(at);
+    }
+
+    /// Records that the session stopped, ending any pause in progress
+    ///
+    /// # Arguments
+    /// * `at` - Time the session stopped
+    pub fn record_stop(&mut self, at: SystemTime) {
+        self.record_resume(at);
+    }
+
+    /// Records that the session was paused
+    ///
+    /// # Arguments
+    /// * `at` - Time the session paused; ignored if it is already paused
+    pub fn record_pause(&mut self, at: SystemTime) {
+        self.paused_since.get_or_insert(at);
+    }
+
+    /// Records that the session resumed, adding the pause to the total paused time
+    ///
+    /// # Arguments
+    /// * `at` - Time the session resumed; ignored if it is not paused
+    pub fn record_resume(&mut self, at: SystemTime) {
+        if let Some(since) = self.paused_since.take() {
+            self.total_paused += at.duration_since(since).unwrap_or_default();
+        }
+    }
+
+    /// Gets the time spent paused, including the current pause
+    ///
+    /// # Arguments
+    /// * `now` - Current time
+    pub fn paused_duration(&self, now: SystemTime) -> Duration {
+        let current = self
+            .paused_since
+            .and_then(|since| now.duration_since(since).ok())
+            .unwrap_or_default();
+        self.total_paused + current
+    }
+
+    /// Gets the wall-clock time since the session started, whether or not it was capturing
+    ///
+    /// # Arguments
+    /// * `now` - Current time, or the time the session stopped
+    ///
+    /// # Returns
+    /// The elapsed time, zero if the session has not started
+    pub fn wall_duration(&self, now: SystemTime) -> Duration {
+        self.start_time
+            .and_then(|start| now.duration_since(start).ok())
+            .unwrap_or_default()
+    }
+
+    /// Gets the time the session spent capturing, excluding time spent paused
+    ///
+    /// # Arguments
+    /// * `now` - Current time, or the time the session stopped
+    ///
+    /// # Returns
+    /// The active time, zero if the session has not started
+    pub fn active_duration(&self, now: SystemTime) -> Duration {
+        self.wall_duration(now)
+            .saturating_sub(self.paused_duration(now))
+    }
 }
 
 /// Session validation configuration
@@ -69,10 +226,18 @@ pub struct SessionConfiguration {
     pub session_id: String,
     pub capture_config: CaptureConfiguration,
     pub filter: Option<PacketFilter>,
+    /// Packets captured before the session stops itself, None for no limit
     pub max_packets: Option<u64>,
+    /// Bytes captured before the session stops itself, None for no limit
     pub max_bytes: Option<u64>,
     pub duration: Option<Duration>,
     pub validation_config: SessionValidationConfig,
+    /// Absolute wall-clock time at which ingestion begins, for synchronized multi-node captures
+    pub scheduled_start: Option<SystemTime>,
+    /// Report produced when the session stops, None to disable
+    pub report: Option<SessionReportConfig>,
+    /// Seed for sampling and jitter decisions, None to seed from OS entropy
+    pub sampling_seed: Option<u64>,
 }
 
 /// Represents an active packet capture session with enhanced state management
@@ -89,14 +254,102 @@ pub struct CaptureSession {
     end_time: Option<SystemTime>,
 }
 
+impl SessionValidationConfig {
+    /// Checks that a lifecycle action is valid in the session's current state
+    ///
+    /// `Start`, `Pause`, `Resume` and `Stop` must follow the session lifecycle: a session is
+    /// started once, only a running session can be paused, only a paused session can be
+    /// resumed, and a stopped session cannot be stopped again. Other actions leave the state
+    /// unchanged and are not checked here.
+    ///
+    /// # Arguments
+    /// * `state` - Current session state
+    /// * `action` - Action to apply
+    ///
+    /// # Returns
+    /// The state the action moves the session to, or an error if the action is invalid
+    pub fn validate_action(
+        &self,
+        state: &SessionState,
+        action: &SessionAction,
+    ) -> Result<SessionState, CaptureError> {
+        let (next, message) = match action {
+            SessionAction::Start => (
+                matches!(state, SessionState::Created).then_some(SessionState::Running),
+                "Only a newly created session can be started",
+            ),
+            SessionAction::Pause => (
+                matches!(state, SessionState::Running).then_some(SessionState::Paused),
+                "Only a running session can be paused",
+            ),
+            SessionAction::Resume => (
+                matches!(state, SessionState::Paused).then_some(SessionState::Running),
+                "Only a paused session can be resumed",
+            ),
+            SessionAction::Stop => (
+                matches!(state, SessionState::Running | SessionState::Paused)
+                    .then_some(SessionState::Stopped),
+                "Only a running or paused session can be stopped",
+            ),
+            _ => return Ok(state.clone()),
+        };
+        next.ok_or_else(|| {
+            *CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                &format!("{} (state: {:?})", message, state),
+            )
+        })
+    }
+
+    /// Gets the state a used-up quota moves the session to
+    ///
+    /// # Arguments
+    /// * `state` - Current session state
+    ///
+    /// # Returns
+    /// The stopped state for a running or paused session, None for any other state
+    pub fn quota_stop(&self, state: &SessionState) -> Option<SessionState> {
+        self.validate_action(state, &SessionAction::Stop).ok()
+    }
+}
+
 impl Default for SessionConfiguration {
     fn default() -> Self {
         unimplemented!()
     }
 }
 
+impl SessionConfiguration {
+    /// Arms the start barrier for a scheduled start, if one is configured
+    ///
+    /// # Arguments
+    /// * `clock` - Clock used to wait for the scheduled start
+    ///
+    /// # Returns
+    /// The armed barrier, None for an immediate start, or an error if the start time was missed
+    pub fn start_barrier(
+        &self,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Option<StartBarrier>, CaptureError> {
+        self.scheduled_start
+            .map(|start| StartBarrier::arm(start, clock))
+            .transpose()
+    }
+
+    /// Creates the sampler for sampling and jitter decisions in this session
+    ///
+    /// # Returns
+    /// A sampler seeded from `sampling_seed`, or from OS entropy if none is configured
+    pub fn sampler(&self) -> PacketSampler {
+        PacketSampler::new(self.sampling_seed)
+    }
+}
+
 impl CaptureSession {
     /// Creates a new capture session with state management
+    ///
+    /// The session starts in `Created`, with the lifecycle transitions that
+    /// `SessionValidationConfig::validate_action` allows.
     pub fn new(
         session_id: String,
         config: SessionConfiguration,
@@ -104,32 +357,138 @@ impl CaptureSession {
         buffer_manager: Arc<BufferManager>,
         state_sync: Arc<StateSync<SessionState>>,
     ) -> Result<Self, CaptureError> {
-        unimplemented!()
+        let mut state_machine = StateMachine::new(SessionState::Created, SESSION_HISTORY)?;
+        for (from, to) in [
+            (SessionState::Created, SessionState::Running),
+            (SessionState::Running, SessionState::Paused),
+            (SessionState::Paused, SessionState::Running),
+            (SessionState::Running, SessionState::Stopped),
+            (SessionState::Paused, SessionState::Stopped),
+        ] {
+            state_machine.add_transition(from, to);
+        }
+
+        let mut state_validator = StateValidator::new(ValidatorConfig::default());
+        for rule in &config.validation_config.validation_rules {
+            state_validator.add_rule(rule.clone())?;
+        }
+
+        Ok(Self {
+            session_id,
+            config,
+            state_machine,
+            state_validator,
+            state_sync,
+            stats: SessionStats::default(),
+            interface,
+            buffer_manager,
+            start_time: None,
+            end_time: None,
+        })
     }
 
     /// Starts the capture session with state validation
+    ///
+    /// Wall-clock and active capture time are measured from here.
     pub fn start(&mut self) -> Result<(), CaptureError> {
-        unimplemented!()
+        self.apply_action(&SessionAction::Start, SystemTime::now())
     }
 
     /// Stops the capture session with state cleanup
+    ///
+    /// A pause in progress ends at the stop, and durations are fixed at the stop time.
     pub fn stop(&mut self) -> Result<(), CaptureError> {
-        unimplemented!()
+        self.apply_action(&SessionAction::Stop, SystemTime::now())
     }
 
     /// Pauses the capture session
+    ///
+    /// Active capture time stops accruing until the session is resumed.
     pub fn pause(&mut self) -> Result<(), CaptureError> {
-        unimplemented!()
+        self.apply_action(&SessionAction::Pause, SystemTime::now())
     }
 
-    /// Resumes the capture session
+    /// Resumes a paused capture session
     pub fn resume(&mut self) -> Result<(), CaptureError> {
-        unimplemented!()
+        self.apply_action(&SessionAction::Resume, SystemTime::now())
     }
 
     /// Gets the current session state
     pub fn get_state(&self) -> &SessionState {
-        unimplemented!()
+        self.state_machine.current_state()
+    }
+
+    /// Gets the session statistics
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
+    /// Gets the time the session spent capturing, excluding pauses
+    pub fn active_duration(&self) -> Duration {
+        self.stats
+            .active_duration(self.end_time.unwrap_or_else(SystemTime::now))
+    }
+
+    /// Gets the wall-clock time since the session started
+    pub fn wall_duration(&self) -> Duration {
+        self.stats
+            .wall_duration(self.end_time.unwrap_or_else(SystemTime::now))
+    }
+
+    /// Counts a processed packet, stopping the session once a quota is used up
+    ///
+    /// # Arguments
+    /// * `bytes` - Captured length of the packet
+    ///
+    /// # Returns
+    /// Whether the packet is within the session's budget and should be kept; always false
+    /// once the session is stopping or stopped
+    pub fn record_packet(&mut self, bytes: u64) -> Result<bool, CaptureError> {
+        if matches!(
+            self.get_state(),
+            SessionState::Stopping | SessionState::Stopped
+        ) {
+            return Ok(false);
+        }
+        let status =
+            self.stats
+                .record_packet(bytes, self.config.max_packets, self.config.max_bytes);
+        if status.quota_reached() {
+            if let Some(next) = self.config.validation_config.quota_stop(self.get_state()) {
+                self.transition_state(next, TransitionReason::Quota, Some("quota reached"))?;
+                let now = SystemTime::now();
+                self.stats.record_stop(now);
+                self.end_time = Some(now);
+            }
+        }
+        Ok(status.counted())
+    }
+
+    /// Validates a lifecycle action, moves to the resulting state and updates time accounting
+    fn apply_action(
+        &mut self,
+        action: &SessionAction,
+        now: SystemTime,
+    ) -> Result<(), CaptureError> {
+        let next = self
+            .config
+            .validation_config
+            .validate_action(self.get_state(), action)?;
+        self.transition_state(next, TransitionReason::OperatorCommand, None)?;
+        match action {
+            SessionAction::Start => {
+                self.stats.record_start(now);
+                self.start_time = Some(now);
+            }
+            SessionAction::Pause => self.stats.record_pause(now),
+            SessionAction::Resume => self.stats.record_resume(now),
+            SessionAction::Stop => {
+                self.stats.record_stop(now);
+                self.end_time = Some(now);
+            }
+            _ => {}
+        }
+        Ok(())
     }
 
     /// Creates a snapshot of the current session state
@@ -151,8 +510,21 @@ impl CaptureSession {
     }
 
     /// Handles state transition with validation
-    fn transition_state(&mut self, new_state: SessionState) -> Result<(), CaptureError> {
-        unimplemented!()
+    fn transition_state(
+        &mut self,
+        new_state: SessionState,
+        category: TransitionReason,
+        detail: Option<&str>,
+    ) -> Result<(), CaptureError> {
+        self.state_machine.apply_categorized_transition(
+            new_state,
+            category,
+            detail.map(str::to_string),
+        )?;
+        if let Some(transition) = self.state_machine.last_transition() {
+            self.stats.state_transitions.push(transition.clone());
+        }
+        Ok(())
     }
 
     /// Synchronizes session state with distributed components
@@ -200,3 +572,176 @@ impl CaptureSessionBuilder {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validation_config() -> SessionValidationConfig {
+        SessionValidationConfig {
+            validation_rules: Vec::new(),
+            validation_timeout: Duration::from_secs(1),
+            fail_fast: true,
+            recovery_enabled: false,
+        }
+    }
+
+    #[test]
+    fn test_paused_time_excluded_from_active_duration() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut stats = SessionStats {
+            start_time: Some(start),
+            ..SessionStats::default()
+        };
+
+        stats.record_pause(at(10));
+        // Still paused: the open pause counts
+        assert_eq!(stats.active_duration(at(15)), Duration::from_secs(10));
+        assert_eq!(stats.wall_duration(at(15)), Duration::from_secs(15));
+        stats.record_pause(at(18));
+        stats.record_resume(at(30));
+        stats.record_resume(at(35));
+        assert_eq!(stats.total_paused, Duration::from_secs(20));
+
+        stats.record_pause(at(50));
+        stats.record_resume(at(55));
+        assert_eq!(stats.paused_duration(at(60)), Duration::from_secs(25));
+        assert_eq!(stats.active_duration(at(60)), Duration::from_secs(35));
+        assert_eq!(stats.wall_duration(at(60)), Duration::from_secs(60));
+        assert_eq!(
+            SessionStats::default().active_duration(at(60)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_stop_ends_pause_in_progress() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut stats = SessionStats::default();
+
+        stats.record_start(at(0));
+        stats.record_start(at(5));
+        stats.record_pause(at(10));
+        stats.record_stop(at(25));
+        assert_eq!(stats.start_time, Some(at(0)));
+        assert_eq!(stats.paused_since, None);
+        assert_eq!(stats.total_paused, Duration::from_secs(15));
+        assert_eq!(stats.active_duration(at(25)), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_invalid_action_sequences_are_rejected() {
+        let config = validation_config();
+        let mut state = SessionState::Created;
+        for action in [
+            SessionAction::Start,
+            SessionAction::Pause,
+            SessionAction::Resume,
+            SessionAction::Pause,
+            SessionAction::Stop,
+        ] {
+            state = config.validate_action(&state, &action).unwrap();
+        }
+        assert_eq!(state, SessionState::Stopped);
+
+        for (state, action) in [
+            (SessionState::Running, SessionAction::Resume),
+            (SessionState::Created, SessionAction::Resume),
+            (SessionState::Paused, SessionAction::Pause),
+            (SessionState::Created, SessionAction::Pause),
+            (SessionState::Paused, SessionAction::Start),
+            (SessionState::Stopped, SessionAction::Stop),
+        ] {
+            let err = config.validate_action(&state, &action).unwrap_err();
+            assert!(matches!(
+                err.kind(),
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError)
+            ));
+        }
+        assert_eq!(
+            config
+                .validate_action(&SessionState::Paused, &SessionAction::Checkpoint)
+                .unwrap(),
+            SessionState::Paused
+        );
+    }
+
+    #[test]
+    fn test_quota_stops_only_running_or_paused_sessions() {
+        let config = validation_config();
+        let mut stats = SessionStats::default();
+        let mut state = SessionState::Running;
+        for _ in 0..4 {
+            if stats.record_packet(100, Some(2), None).quota_reached() {
+                if let Some(next) = config.quota_stop(&state) {
+                    state = next;
+                }
+            }
+        }
+        // Packets after the stop see no transition, so no error
+        assert_eq!(state, SessionState::Stopped);
+        assert_eq!(stats.packets_captured, 2);
+
+        assert_eq!(
+            config.quota_stop(&SessionState::Paused),
+            Some(SessionState::Stopped)
+        );
+        for state in [
+            SessionState::Created,
+            SessionState::Stopping,
+            SessionState::Stopped,
+        ] {
+            assert_eq!(config.quota_stop(&state), None);
+        }
+    }
+
+    #[test]
+    fn test_packet_quota_reached_on_boundary_packet() {
+        let mut stats = SessionStats::default();
+        for _ in 0..2 {
+            assert_eq!(stats.record_packet(100, Some(3), None), QuotaStatus::Within);
+        }
+        let status = stats.record_packet(100, Some(3), None);
+        assert_eq!(status, QuotaStatus::Reached(SessionQuota::Packets(3)));
+        assert!(status.counted() && status.quota_reached());
+        assert_eq!(stats.packets_captured, 3);
+
+        let status = stats.record_packet(100, Some(3), None);
+        assert_eq!(status, QuotaStatus::Exceeded(SessionQuota::Packets(3)));
+        assert!(!status.counted());
+        assert_eq!(stats.packets_captured, 3);
+    }
+
+    #[test]
+    fn test_byte_quota_reached_on_boundary_packet() {
+        let mut stats = SessionStats::default();
+        assert_eq!(
+            stats.record_packet(600, None, Some(1000)),
+            QuotaStatus::Within
+        );
+        assert_eq!(
+            stats.record_packet(399, None, Some(1000)),
+            QuotaStatus::Within
+        );
+        assert_eq!(
+            stats.record_packet(1, None, Some(1000)),
+            QuotaStatus::Reached(SessionQuota::Bytes(1000))
+        );
+        assert_eq!(stats.bytes_captured, 1000);
+
+        // A packet that would overshoot the budget is not counted
+        let mut stats = SessionStats::default();
+        stats.record_packet(900, None, Some(1000));
+        assert_eq!(
+            stats.record_packet(101, Some(2), Some(1000)),
+            QuotaStatus::Exceeded(SessionQuota::Bytes(1000))
+        );
+        assert_eq!((stats.packets_captured, stats.bytes_captured), (1, 900));
+        assert_eq!(
+            stats.record_packet(100, Some(2), Some(1000)),
+            QuotaStatus::Reached(SessionQuota::Packets(2))
+        );
+    }
+}
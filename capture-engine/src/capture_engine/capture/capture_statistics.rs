@@ -2,12 +2,26 @@
 #![allow(unused)]
 #![allow(unused_variables)]
 // capture-engine/src/capture/capture_statistics.rs
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, AtomicUsize};
-use std::time::{Duration, SystemTime};
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
 
 use crate::capture_engine::capture::capture_error::CaptureError;
 use crate::capture_engine::capture::state_machine::StateTransition;
+use crate::capture_engine::protocol::flow::{EvictedFlow, EvictionReason, FlowTable};
+use crate::capture_engine::protocol::tcp_state::TcpFlags;
+use crate::capture_engine::telemetry::traits::{
+    MetricType, MetricUnit, MetricValue, TelemetryData,
+};
+
+/// Telemetry name for packets dropped deliberately by filter policy
+pub const POLICY_DROPS_METRIC: &str = "capture.packets.dropped.policy";
+/// Telemetry name for packets lost because the engine could not keep up
+pub const LOSS_DROPS_METRIC: &str = "capture.packets.dropped.loss";
 
 /// CPU utilization metrics with state context
 pub struct CpuMetrics {
@@ -35,6 +49,78 @@ pub struct BufferMetrics {
     pub state_transitions: StateTransitionMetrics,
 }
 
+/// Packet drop metrics, separated by cause
+///
+/// Policy drops are packets a filter rule chose to discard. Loss drops are packets the engine
+/// wanted to keep but could not, e.g. because no buffer was available. The two are never
+/// combined so operators can tell intentional filtering apart from capture loss.
+#[derive(Debug, Default)]
+pub struct DropMetrics {
+    policy_drops: AtomicU64,
+    loss_drops: AtomicU64,
+}
+
+impl DropMetrics {
+    /// Records a packet dropped by a filter `Drop` action
+    pub fn record_policy_drop(&self) {
+        self.policy_drops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a packet lost to resource pressure
+    pub fn record_loss_drop(&self) {
+        self.loss_drops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Gets the number of packets dropped by filter policy
+    ///
+    /// # Returns
+    /// The policy drop count
+    pub fn policy_drops(&self) -> u64 {
+        self.policy_drops.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of packets lost to resource pressure
+    ///
+    /// # Returns
+    /// The loss drop count
+    pub fn loss_drops(&self) -> u64 {
+        self.loss_drops.load(Ordering::Relaxed)
+    }
+
+    /// Exports the drop counters as separate telemetry metrics
+    ///
+    /// # Arguments
+    /// * `timestamp` - Timestamp attached to the exported metrics
+    ///
+    /// # Returns
+    /// One counter per drop cause, named `POLICY_DROPS_METRIC` and `LOSS_DROPS_METRIC`
+    pub fn telemetry(&self, timestamp: u64) -> Vec<TelemetryData> {
+        let counter = |name: &str, description: &str, value: u64| TelemetryData {
+            timestamp,
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type: MetricType::Counter,
+            value: MetricValue::Integer(value as i64),
+            attributes: HashMap::new(),
+            resource: None,
+        };
+
+        vec![
+            counter(
+                POLICY_DROPS_METRIC,
+                "Packets dropped by filter policy",
+                self.policy_drops(),
+            ),
+            counter(
+                LOSS_DROPS_METRIC,
+                "Packets lost due to resource pressure",
+                self.loss_drops(),
+            ),
+        ]
+    }
+}
+
 /// Flow tracking metrics
 pub struct FlowMetrics {
     pub active_flows: AtomicUsize,
@@ -43,6 +129,397 @@ pub struct FlowMetrics {
     pub flow_rates: ExponentialMovingAverage,
 }
 
+/// Default number of flows tracked by a `FlowStatsTable`
+pub const DEFAULT_FLOW_STATS_CAPACITY: usize = 65_536;
+
+/// Directional 5-tuple identifying a flow for statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowTuple {
+    pub src_ip: IpAddr,
+    pub dst_ip: IpAddr,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub protocol: u8,
+}
+
+/// Per-flow packet and byte counts with activity timestamps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowStats {
+    pub tuple: FlowTuple,
+    pub packets: u64,
+    pub bytes: u64,
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+}
+
+#[derive(Debug, Default)]
+struct FlowCounters {
+    packets: u64,
+    bytes: u64,
+    /// Timestamp of the flow's first TCP FIN, in nanoseconds
+    fin_at: Option<u64>,
+}
+
+/// Bounded table of per-flow statistics
+///
+/// The table holds at most `capacity` flows. Recording a new flow in a full table evicts the
+/// least recently seen flow, so a spray of unique tuples cannot grow memory without bound.
+#[derive(Debug)]
+pub struct FlowStatsTable {
+    flows: Mutex<FlowTable<FlowCounters, FlowTuple>>,
+    evicted: AtomicU64,
+}
+
+impl Default for FlowStatsTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_FLOW_STATS_CAPACITY)
+    }
+}
+
+impl FlowStatsTable {
+    /// Creates a flow statistics table
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of flows tracked at once
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            flows: Mutex::new(FlowTable::new(capacity, u64::MAX)),
+            evicted: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a packet against its flow
+    ///
+    /// # Arguments
+    /// * `tuple` - Flow the packet belongs to
+    /// * `length` - Packet length in bytes
+    /// * `timestamp` - Packet timestamp
+    pub fn record_packet(&self, tuple: FlowTuple, length: usize, timestamp: SystemTime) {
+        let mut flows = self.flows.lock();
+        let (counters, evicted) =
+            flows.get_or_insert_with(tuple, to_nanos(timestamp), FlowCounters::default);
+        counters.packets += 1;
+        counters.bytes += length as u64;
+        if evicted.is_some() {
+            self.evicted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Gets the flows with the highest byte counts
+    ///
+    /// # Arguments
+    /// * `n` - Maximum number of flows to return
+    ///
+    /// # Returns
+    /// Up to `n` flows ordered by descending byte count
+    pub fn top_flows(&self, n: usize) -> Vec<FlowStats> {
+        let flows = self.flows.lock();
+        let mut stats: Vec<FlowStats> = flows
+            .iter()
+            .map(|(tuple, counters)| FlowStats {
+                tuple: *tuple,
+                packets: counters.packets,
+                bytes: counters.bytes,
+                first_seen: from_nanos(flows.first_seen(tuple).unwrap_or_default()),
+                last_seen: from_nanos(flows.last_seen(tuple).unwrap_or_default()),
+            })
+            .collect();
+        stats.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(b.packets.cmp(&a.packets)));
+        stats.truncate(n);
+        stats
+    }
+
+    /// Removes flows with no packets for longer than `idle`
+    ///
+    /// # Arguments
+    /// * `idle` - Idle time after which a flow expires
+    /// * `now` - Current time
+    ///
+    /// # Returns
+    /// The expired flows
+    pub fn expire_flows(&self, idle: Duration, now: SystemTime) -> Vec<FlowStats> {
+        let idle_ns = duration_nanos(idle);
+        self.flows
+            .lock()
+            .evict_idle_after(to_nanos(now), idle_ns)
+            .into_iter()
+            .map(flow_stats)
+            .collect()
+    }
+
+    /// Gets the number of tracked flows
+    pub fn len(&self) -> usize {
+        self.flows.lock().len()
+    }
+
+    /// Whether no flows are tracked
+    pub fn is_empty(&self) -> bool {
+        self.flows.lock().is_empty()
+    }
+
+    /// Gets the number of flows evicted to stay within capacity
+    pub fn evicted_flows(&self) -> u64 {
+        self.evicted.load(Ordering::Relaxed)
+    }
+}
+
+/// Why a flow's record was closed
+///
+/// # Variants
+/// * `Idle` - No packets for longer than the idle timeout
+/// * `Fin` - The flow sent a TCP FIN
+/// * `Reset` - The flow sent a TCP RST
+/// * `Evicted` - The table was full and this was the least recently seen flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowCloseReason {
+    Idle,
+    Fin,
+    Reset,
+    Evicted,
+}
+
+/// Event emitted when a flow's record is closed
+///
+/// # Fields
+/// * `flow` - Final counters and activity timestamps of the flow
+/// * `reason` - Why the record was closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowExpired {
+    pub flow: FlowStats,
+    pub reason: FlowCloseReason,
+}
+
+/// Default time a flow stays open after its first TCP FIN
+pub const DEFAULT_FIN_LINGER: Duration = Duration::from_secs(1);
+
+/// Flow statistics table that closes flow records for downstream consumers
+///
+/// A flow is closed when it has been idle for longer than the idle timeout, or early when it
+/// ends. A TCP RST closes the flow at once. A TCP FIN starts a linger instead: packets within
+/// `fin_linger` of the FIN, such as the final ACK of the close handshake, still count towards
+/// the flow, and the flow is closed by the first sweep or packet after the linger. Flows are
+/// directional, so each side of a TCP connection closes on its own FIN. A packet arriving after
+/// its flow closed starts a new record.
+///
+/// Idle sweeps walk flows in order of last activity and stop at the first one still active, so
+/// a sweep costs O(expired log n) rather than O(n). Lingering flows are swept in the order
+/// their FINs were seen; at most `capacity` flows linger at once, and the oldest is closed
+/// early beyond that.
+///
+/// # Fields
+/// * `table` - Per-flow counters
+/// * `idle_timeout` - Idle time after which a flow is closed
+/// * `fin_linger` - Time a flow stays open after its first FIN
+/// * `closing` - Flows and FIN timestamps waiting out the linger, oldest first
+#[derive(Debug)]
+pub struct FlowTracker {
+    table: FlowStatsTable,
+    idle_timeout: Duration,
+    fin_linger: Duration,
+    closing: Mutex<VecDeque<(FlowTuple, u64)>>,
+}
+
+impl FlowTracker {
+    /// Creates a flow tracker
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of flows tracked at once
+    /// * `idle_timeout` - Idle time after which a flow is closed
+    pub fn new(capacity: usize, idle_timeout: Duration) -> Self {
+        Self {
+            table: FlowStatsTable::new(capacity),
+            idle_timeout,
+            fin_linger: DEFAULT_FIN_LINGER,
+            closing: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Sets the time a flow stays open after its first FIN
+    ///
+    /// # Arguments
+    /// * `linger` - Linger time; zero closes flows on the FIN itself
+    pub fn with_fin_linger(mut self, linger: Duration) -> Self {
+        self.fin_linger = linger;
+        self
+    }
+
+    /// Gets the time a flow stays open after its first FIN
+    pub fn fin_linger(&self) -> Duration {
+        self.fin_linger
+    }
+
+    /// Gets the idle timeout
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Gets the underlying flow statistics table
+    pub fn table(&self) -> &FlowStatsTable {
+        &self.table
+    }
+
+    /// Records a packet against its flow
+    ///
+    /// # Arguments
+    /// * `tuple` - Flow the packet belongs to
+    /// * `length` - Packet length in bytes
+    /// * `timestamp` - Packet timestamp
+    /// * `tcp_flags` - Flags of a TCP packet, None for other protocols
+    ///
+    /// # Returns
+    /// The flows closed by this packet: its own flow on a RST, or on a FIN with no linger; a
+    /// previous record of the flow whose linger has passed; any flow evicted to make room for
+    /// it; and any lingering flow closed early to bound the lingering flows
+    pub fn record_packet(
+        &self,
+        tuple: FlowTuple,
+        length: usize,
+        timestamp: SystemTime,
+        tcp_flags: Option<TcpFlags>,
+    ) -> Vec<FlowExpired> {
+        let now = to_nanos(timestamp);
+        let linger = duration_nanos(self.fin_linger);
+        let mut flows = self.table.flows.lock();
+        let mut closed = Vec::new();
+
+        // A packet after the linger belongs to a new connection
+        let lingered = flows
+            .get(&tuple)
+            .and_then(|counters| counters.fin_at)
+            .is_some_and(|fin_at| now.saturating_sub(fin_at) > linger);
+        if lingered {
+            closed.extend(close(&mut flows, &tuple, FlowCloseReason::Fin));
+        }
+
+        let (counters, evicted) = flows.get_or_insert_with(tuple, now, FlowCounters::default);
+        counters.packets += 1;
+        counters.bytes += length as u64;
+        let flags = tcp_flags.unwrap_or_default();
+        let first_fin = flags.fin && !flags.rst && counters.fin_at.is_none();
+        if first_fin {
+            counters.fin_at = Some(now);
+        }
+
+        if let Some(flow) = evicted {
+            self.table.evicted.fetch_add(1, Ordering::Relaxed);
+            closed.push(FlowExpired {
+                flow: flow_stats(flow),
+                reason: FlowCloseReason::Evicted,
+            });
+        }
+
+        if flags.rst {
+            closed.extend(close(&mut flows, &tuple, FlowCloseReason::Reset));
+        } else if first_fin && linger == 0 {
+            closed.extend(close(&mut flows, &tuple, FlowCloseReason::Fin));
+        } else if first_fin {
+            let mut closing = self.closing.lock();
+            closing.push_back((tuple, now));
+            while closing.len() > flows.capacity() {
+                if let Some((tuple, fin_at)) = closing.pop_front() {
+                    closed.extend(close_lingering(&mut flows, &tuple, fin_at));
+                }
+            }
+        }
+        closed
+    }
+
+    /// Closes flows whose FIN linger has passed and flows idle for longer than the idle timeout
+    ///
+    /// # Arguments
+    /// * `now` - Current time, normally the latest packet timestamp
+    ///
+    /// # Returns
+    /// The flows closed after a FIN in FIN order, then the idle flows least recently seen first
+    pub fn expire_idle(&self, now: SystemTime) -> Vec<FlowExpired> {
+        let now_ns = to_nanos(now);
+        let linger = duration_nanos(self.fin_linger);
+        let mut expired = Vec::new();
+        {
+            let mut flows = self.table.flows.lock();
+            let mut closing = self.closing.lock();
+            while let Some(&(tuple, fin_at)) = closing.front() {
+                if now_ns.saturating_sub(fin_at) <= linger {
+                    break;
+                }
+                closing.pop_front();
+                expired.extend(close_lingering(&mut flows, &tuple, fin_at));
+            }
+        }
+        expired.extend(
+            self.table
+                .expire_flows(self.idle_timeout, now)
+                .into_iter()
+                .map(|flow| FlowExpired {
+                    flow,
+                    reason: FlowCloseReason::Idle,
+                }),
+        );
+        expired
+    }
+
+    /// Gets the number of open flows
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Whether no flows are open
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+}
+
+fn flow_stats(flow: EvictedFlow<FlowCounters, FlowTuple>) -> FlowStats {
+    FlowStats {
+        tuple: flow.key,
+        packets: flow.value.packets,
+        bytes: flow.value.bytes,
+        first_seen: from_nanos(flow.first_seen),
+        last_seen: from_nanos(flow.last_seen),
+    }
+}
+
+/// Removes a flow that has ended
+fn close(
+    flows: &mut FlowTable<FlowCounters, FlowTuple>,
+    tuple: &FlowTuple,
+    reason: FlowCloseReason,
+) -> Option<FlowExpired> {
+    flows
+        .evict(tuple, EvictionReason::Closed)
+        .map(|flow| FlowExpired {
+            flow: flow_stats(flow),
+            reason,
+        })
+}
+
+/// Closes a flow after its FIN linger, unless the record that sent the FIN is already gone
+fn close_lingering(
+    flows: &mut FlowTable<FlowCounters, FlowTuple>,
+    tuple: &FlowTuple,
+    fin_at: u64,
+) -> Option<FlowExpired> {
+    let counters = flows.get(tuple)?;
+    if counters.fin_at != Some(fin_at) {
+        return None;
+    }
+    close(flows, tuple, FlowCloseReason::Fin)
+}
+
+fn duration_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+fn to_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+fn from_nanos(nanos: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(nanos)
+}
+
 /// State transition metrics
 pub struct StateTransitionMetrics {
     pub transition_counts: HashMap<String, AtomicU64>,
@@ -119,6 +596,8 @@ pub struct CaptureStatistics {
     pub disk_metrics: DiskMetrics,
     pub buffer_metrics: BufferMetrics,
     pub flow_metrics: FlowMetrics,
+    pub flow_table: Arc<FlowStatsTable>,
+    pub drop_metrics: DropMetrics,
 
     // State management metrics
     pub state_transition_metrics: StateTransitionMetrics,
@@ -146,6 +625,28 @@ impl CaptureStatistics {
         unimplemented!()
     }
 
+    /// Records a packet against its flow in the flow table
+    pub fn record_flow_packet(&self, tuple: FlowTuple, length: usize, timestamp: SystemTime) {
+        self.flow_table.record_packet(tuple, length, timestamp);
+        self.flow_metrics
+            .active_flows
+            .store(self.flow_table.len(), Ordering::Relaxed);
+    }
+
+    /// Gets the `n` flows with the highest byte counts
+    pub fn top_flows(&self, n: usize) -> Vec<FlowStats> {
+        self.flow_table.top_flows(n)
+    }
+
+    /// Expires flows idle for longer than `idle` and returns them
+    pub fn expire_flows(&self, idle: Duration, now: SystemTime) -> Vec<FlowStats> {
+        let expired = self.flow_table.expire_flows(idle, now);
+        self.flow_metrics
+            .active_flows
+            .store(self.flow_table.len(), Ordering::Relaxed);
+        expired
+    }
+
     /// Records a state transition
     pub fn record_state_transition<S: Clone>(&self, transition: &StateTransition<S>) {
         unimplemented!()
@@ -234,3 +735,226 @@ impl CaptureStatisticsBuilder {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric_value(metrics: &[TelemetryData], name: &str) -> i64 {
+        match metrics.iter().find(|m| m.name == name).unwrap().value {
+            MetricValue::Integer(value) => value,
+            _ => panic!("drop metrics should be integer counters"),
+        }
+    }
+
+    #[test]
+    fn test_drop_metrics_are_counted_separately() {
+        let drops = DropMetrics::default();
+        drops.record_policy_drop();
+        drops.record_policy_drop();
+        drops.record_loss_drop();
+
+        assert_eq!(drops.policy_drops(), 2);
+        assert_eq!(drops.loss_drops(), 1);
+    }
+
+    #[test]
+    fn test_drop_telemetry_uses_distinct_names() {
+        let drops = DropMetrics::default();
+        drops.record_policy_drop();
+        for _ in 0..3 {
+            drops.record_loss_drop();
+        }
+
+        let metrics = drops.telemetry(42);
+        assert_eq!(metrics.len(), 2);
+        assert_ne!(POLICY_DROPS_METRIC, LOSS_DROPS_METRIC);
+        assert_eq!(metric_value(&metrics, POLICY_DROPS_METRIC), 1);
+        assert_eq!(metric_value(&metrics, LOSS_DROPS_METRIC), 3);
+        assert!(metrics.iter().all(|m| m.timestamp == 42));
+    }
+
+    fn tuple(src_port: u16) -> FlowTuple {
+        FlowTuple {
+            src_ip: "10.0.0.1".parse().unwrap(),
+            dst_ip: "10.0.0.2".parse().unwrap(),
+            src_port,
+            dst_port: 443,
+            protocol: 6,
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_flow_stats_accumulate_per_tuple() {
+        let table = FlowStatsTable::new(16);
+        table.record_packet(tuple(1000), 100, at(10));
+        table.record_packet(tuple(1000), 200, at(12));
+        table.record_packet(tuple(2000), 50, at(11));
+
+        let top = table.top_flows(10);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].tuple, tuple(1000));
+        assert_eq!(top[0].packets, 2);
+        assert_eq!(top[0].bytes, 300);
+        assert_eq!(top[0].first_seen, at(10));
+        assert_eq!(top[0].last_seen, at(12));
+        assert_eq!(top[1].bytes, 50);
+    }
+
+    #[test]
+    fn test_top_flows_orders_by_bytes_and_limits() {
+        let table = FlowStatsTable::new(16);
+        for (port, bytes) in [(1, 10), (2, 300), (3, 20), (4, 1000)] {
+            table.record_packet(tuple(port), bytes, at(1));
+        }
+
+        let top: Vec<u16> = table
+            .top_flows(2)
+            .iter()
+            .map(|flow| flow.tuple.src_port)
+            .collect();
+        assert_eq!(top, vec![4, 2]);
+    }
+
+    #[test]
+    fn test_expire_flows_removes_idle_flows() {
+        let table = FlowStatsTable::new(16);
+        table.record_packet(tuple(1), 10, at(0));
+        table.record_packet(tuple(2), 10, at(50));
+
+        let expired = table.expire_flows(Duration::from_secs(30), at(60));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].tuple, tuple(1));
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.top_flows(1)[0].tuple, tuple(2));
+    }
+
+    #[test]
+    fn test_unique_tuple_spray_stays_within_capacity() {
+        let table = FlowStatsTable::new(64);
+        table.record_packet(tuple(0), 10_000, at(0));
+        for port in 1..=1000u16 {
+            table.record_packet(tuple(port), 1, at(u64::from(port)));
+            table.record_packet(tuple(0), 1, at(u64::from(port)));
+        }
+
+        assert_eq!(table.len(), 64);
+        assert_eq!(table.evicted_flows(), 1000 - 63);
+        assert_eq!(table.top_flows(1)[0].tuple, tuple(0));
+    }
+
+    #[test]
+    fn test_idle_flow_expires_with_final_stats() {
+        let tracker = FlowTracker::new(16, Duration::from_secs(30));
+        for (secs, len) in [(0, 100), (10, 200), (20, 300)] {
+            assert!(tracker
+                .record_packet(tuple(1), len, at(secs), Some(TcpFlags::ACK))
+                .is_empty());
+        }
+        tracker.record_packet(tuple(2), 60, at(40), None);
+
+        // Exactly at the timeout the flow is still open
+        assert!(tracker.expire_idle(at(50)).is_empty());
+        let expired = tracker.expire_idle(at(51));
+        assert_eq!(
+            expired,
+            vec![FlowExpired {
+                flow: FlowStats {
+                    tuple: tuple(1),
+                    packets: 3,
+                    bytes: 600,
+                    first_seen: at(0),
+                    last_seen: at(20),
+                },
+                reason: FlowCloseReason::Idle,
+            }]
+        );
+        assert_eq!(tracker.len(), 1);
+        assert!(tracker.expire_idle(at(51)).is_empty());
+    }
+
+    #[test]
+    fn test_fin_and_rst_close_flow_early() {
+        let tracker =
+            FlowTracker::new(16, Duration::from_secs(300)).with_fin_linger(Duration::ZERO);
+        tracker.record_packet(tuple(1), 60, at(0), Some(TcpFlags::SYN));
+        tracker.record_packet(tuple(1), 1500, at(1), Some(TcpFlags::ACK));
+        let closed = tracker.record_packet(tuple(1), 60, at(2), Some(TcpFlags::FIN_ACK));
+
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].reason, FlowCloseReason::Fin);
+        assert_eq!(closed[0].flow.packets, 3);
+        assert_eq!(closed[0].flow.bytes, 1620);
+        assert_eq!(closed[0].flow.last_seen, at(2));
+        assert!(tracker.is_empty());
+
+        // A segment after the close starts a new record
+        tracker.record_packet(tuple(1), 60, at(3), Some(TcpFlags::ACK));
+        let closed = tracker.record_packet(tuple(1), 40, at(4), Some(TcpFlags::RST));
+        assert_eq!(closed[0].reason, FlowCloseReason::Reset);
+        assert_eq!(closed[0].flow.packets, 2);
+        assert_eq!(closed[0].flow.first_seen, at(3));
+    }
+
+    #[test]
+    fn test_fin_linger_keeps_final_ack_in_flow() {
+        let tracker = FlowTracker::new(16, Duration::from_secs(300));
+        assert_eq!(tracker.fin_linger(), DEFAULT_FIN_LINGER);
+        tracker.record_packet(tuple(1), 1500, at(0), Some(TcpFlags::ACK));
+        assert!(tracker
+            .record_packet(tuple(1), 60, at(10), Some(TcpFlags::FIN_ACK))
+            .is_empty());
+        // The final ACK lands within the linger and joins the flow
+        assert!(tracker
+            .record_packet(tuple(1), 40, at(11), Some(TcpFlags::ACK))
+            .is_empty());
+        assert!(tracker.expire_idle(at(11)).is_empty());
+
+        let closed = tracker.expire_idle(at(12));
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].reason, FlowCloseReason::Fin);
+        assert_eq!(closed[0].flow.packets, 3);
+        assert_eq!(closed[0].flow.last_seen, at(11));
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_packet_after_linger_starts_new_record() {
+        let tracker = FlowTracker::new(16, Duration::from_secs(300));
+        tracker.record_packet(tuple(1), 60, at(0), Some(TcpFlags::FIN_ACK));
+        let closed = tracker.record_packet(tuple(1), 60, at(5), Some(TcpFlags::SYN));
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].reason, FlowCloseReason::Fin);
+        assert_eq!(closed[0].flow.last_seen, at(0));
+        assert_eq!(tracker.len(), 1);
+
+        // The stale linger entry leaves the new record alone
+        assert!(tracker.expire_idle(at(6)).is_empty());
+        assert_eq!(tracker.len(), 1);
+
+        // A reset during the linger closes the flow at once
+        let closed = tracker.record_packet(tuple(1), 60, at(7), Some(TcpFlags::FIN_ACK));
+        assert!(closed.is_empty());
+        let closed = tracker.record_packet(tuple(1), 40, at(7), Some(TcpFlags::RST));
+        assert_eq!(closed[0].reason, FlowCloseReason::Reset);
+        assert_eq!(closed[0].flow.packets, 3);
+        assert!(tracker.expire_idle(at(20)).is_empty());
+    }
+
+    #[test]
+    fn test_tracker_reports_capacity_evictions() {
+        let tracker = FlowTracker::new(2, Duration::from_secs(300));
+        tracker.record_packet(tuple(1), 10, at(0), None);
+        tracker.record_packet(tuple(2), 10, at(1), None);
+        let closed = tracker.record_packet(tuple(3), 10, at(2), None);
+
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].reason, FlowCloseReason::Evicted);
+        assert_eq!(closed[0].flow.tuple, tuple(1));
+        assert_eq!(tracker.table().evicted_flows(), 1);
+    }
+}
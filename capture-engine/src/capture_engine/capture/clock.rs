@@ -0,0 +1,92 @@
+// capture-engine/src/capture/clock.rs
+use async_trait::async_trait;
+use std::fmt::Debug;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+
+/// Wall-clock source used for capture timestamps and scheduled actions
+///
+/// Components take a `Clock` rather than calling `SystemTime::now()` directly so that every
+/// node reads time the same way and tests can drive time explicitly.
+#[async_trait]
+pub trait Clock: Send + Sync + Debug {
+    /// Gets the current wall-clock time
+    fn now(&self) -> SystemTime;
+
+    /// Waits until the clock reaches the given instant
+    ///
+    /// # Arguments
+    /// * `deadline` - Instant to wait for
+    async fn sleep_until(&self, deadline: SystemTime);
+}
+
+/// Clock backed by the operating system wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    async fn sleep_until(&self, deadline: SystemTime) {
+        let remaining = deadline
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO);
+        tokio::time::sleep(remaining).await;
+    }
+}
+
+/// Clock that only moves when explicitly advanced
+///
+/// # Fields
+/// * `now` - Current time, observed by waiters in `sleep_until`
+#[derive(Debug)]
+pub struct ManualClock {
+    now: watch::Sender<SystemTime>,
+}
+
+impl ManualClock {
+    /// Creates a manual clock starting at the given instant
+    ///
+    /// # Arguments
+    /// * `start` - Initial clock time
+    ///
+    /// # Returns
+    /// A new ManualClock instance
+    pub fn new(start: SystemTime) -> Self {
+        let (now, _) = watch::channel(start);
+        Self { now }
+    }
+
+    /// Moves the clock forward, waking any waiters whose deadline has passed
+    ///
+    /// # Arguments
+    /// * `duration` - Amount of time to advance
+    pub fn advance(&self, duration: Duration) {
+        self.now.send_modify(|now| *now += duration);
+    }
+
+    /// Sets the clock to an absolute instant
+    ///
+    /// # Arguments
+    /// * `time` - New clock time
+    pub fn set(&self, time: SystemTime) {
+        self.now.send_replace(time);
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.now.borrow()
+    }
+
+    async fn sleep_until(&self, deadline: SystemTime) {
+        let mut receiver = self.now.subscribe();
+        // The sender lives as long as self, so wait_for can only return once the deadline is
+        // reached
+        let _ = receiver.wait_for(|now| *now >= deadline).await;
+    }
+}
@@ -0,0 +1,530 @@
+// capture-engine/src/capture/config_schema.rs
+use serde_json::{json, Map, Value};
+
+use crate::capture_engine::filter::dedup::DedupConfig;
+
+/// Default snap length, matching the AF_PACKET source; `null` keeps whole frames
+const DEFAULT_SNAPLEN: usize = 65_535;
+
+/// JSON Schema dialect the exported schema declares
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+/// Schema keyword recording whether a field can change without a restart
+pub const RELOAD_KEYWORD: &str = "x-reload";
+
+/// How a configuration change takes effect
+///
+/// # Variants
+/// * `Hot` - Applied to the running engine
+/// * `Restart` - Requires the capture engine to be restarted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadBehavior {
+    Hot,
+    Restart,
+}
+
+impl ReloadBehavior {
+    fn as_str(self) -> &'static str {
+        match self {
+            ReloadBehavior::Hot => "hot",
+            ReloadBehavior::Restart => "restart",
+        }
+    }
+}
+
+use ReloadBehavior::{Hot, Restart};
+
+/// Builds the JSON Schema of `CaptureConfiguration`
+///
+/// The schema describes the serde representation of the configuration: durations are
+/// `{secs, nanos}` objects and enum variants with data are single-key objects. Every field
+/// carries an `x-reload` keyword of `hot` or `restart`. The schema is maintained by hand
+/// alongside the configuration types; a test fails if a field is added without being described.
+///
+/// # Returns
+/// The schema as a JSON value
+pub fn capture_configuration_schema() -> Value {
+    let mut schema = object(
+        "CaptureConfiguration",
+        "Main configuration structure for the capture system",
+        vec![
+            ("interface_config", reference("InterfaceConfiguration")),
+            ("buffer_config", reference("BufferConfiguration")),
+            ("filter_config", reference("FilterConfiguration")),
+            ("cloud_config", reference("CloudConfiguration")),
+            ("performance_config", reference("PerformanceConfiguration")),
+            ("scaling_config", reference("ScalingConfiguration")),
+            ("security_config", reference("SecurityConfiguration")),
+        ],
+    );
+
+    let dedup = DedupConfig::default();
+    let definitions = json!({
+        "InterfaceConfiguration": object(
+            "InterfaceConfiguration",
+            "Network interface configuration",
+            vec![
+                ("interface_name", field(json!({"type": "string", "minLength": 1}), Restart, None)),
+                ("promiscuous_mode", field(json!({"type": "boolean"}), Restart, Some(json!(true)))),
+                ("snaplen", field(
+                    nullable(uint(Some(1), Some(262_144))),
+                    Restart,
+                    Some(json!(DEFAULT_SNAPLEN)),
+                )),
+                ("buffer_size", field(uint(Some(1), None), Restart, None)),
+                ("timeout", field(reference("Duration"), Hot, None)),
+                ("timestamps", field(reference("TimestampConfig"), Restart, None)),
+                ("hardware_acceleration", field(
+                    json!({"type": "boolean"}),
+                    Restart,
+                    Some(json!(false)),
+                )),
+            ],
+        ),
+        "BufferConfiguration": object(
+            "BufferConfiguration",
+            "Buffer management configuration",
+            vec![
+                ("total_size", field(uint(Some(1), None), Restart, None)),
+                ("chunk_size", field(uint(Some(1), None), Restart, None)),
+                ("pre_allocation", field(json!({"type": "boolean"}), Restart, Some(json!(true)))),
+                ("memory_limit", field(nullable(uint(Some(1), None)), Hot, Some(Value::Null))),
+                ("page_size", field(uint(Some(1), None), Restart, Some(json!(4096)))),
+                ("ring_buffer_count", field(uint(Some(1), None), Restart, Some(json!(1)))),
+                ("optimization_level", field(
+                    reference("OptimizationLevel"),
+                    Restart,
+                    Some(json!("Basic")),
+                )),
+            ],
+        ),
+        "FilterConfiguration": object(
+            "FilterConfiguration",
+            "Packet filtering configuration",
+            vec![
+                ("bpf_filter", field(nullable(json!({"type": "string"})), Hot, Some(Value::Null))),
+                ("custom_filters", field(
+                    json!({"type": "array", "items": {"type": "string"}}),
+                    Hot,
+                    Some(json!([])),
+                )),
+                ("optimization_level", field(
+                    reference("OptimizationLevel"),
+                    Hot,
+                    Some(json!("Basic")),
+                )),
+                ("hardware_offload", field(
+                    json!({"type": "boolean"}),
+                    Restart,
+                    Some(json!(false)),
+                )),
+                ("dedup", field(nullable(reference("DedupConfig")), Hot, Some(Value::Null))),
+            ],
+        ),
+        "DedupConfig": object(
+            "DedupConfig",
+            "Duplicate packet suppression",
+            vec![
+                ("window_ns", field(uint(Some(1), None), Hot, Some(json!(dedup.window_ns)))),
+                ("capacity", field(uint(Some(1), None), Hot, Some(json!(dedup.capacity)))),
+            ],
+        ),
+        "CloudConfiguration": object(
+            "CloudConfiguration",
+            "Cloud-specific configuration",
+            vec![
+                ("region", field(json!({"type": "string"}), Restart, None)),
+                ("availability_zone", field(json!({"type": "string"}), Restart, None)),
+                ("vpc_id", field(nullable(json!({"type": "string"})), Restart, Some(Value::Null))),
+                ("subnet_id", field(
+                    nullable(json!({"type": "string"})),
+                    Restart,
+                    Some(Value::Null),
+                )),
+                ("instance_id", field(
+                    nullable(json!({"type": "string"})),
+                    Restart,
+                    Some(Value::Null),
+                )),
+                ("tags", field(
+                    json!({"type": "object", "additionalProperties": {"type": "string"}}),
+                    Hot,
+                    Some(json!({})),
+                )),
+            ],
+        ),
+        "PerformanceConfiguration": object(
+            "PerformanceConfiguration",
+            "Performance tuning configuration",
+            vec![
+                ("cpu_affinity", field(
+                    nullable(json!({
+                        "type": "array",
+                        "items": uint(None, None),
+                        "uniqueItems": true,
+                    })),
+                    Restart,
+                    Some(Value::Null),
+                )),
+                ("numa_node", field(
+                    nullable(json!({"type": "integer", "minimum": 0})),
+                    Restart,
+                    Some(Value::Null),
+                )),
+                ("batch_size", field(uint(Some(1), None), Hot, Some(json!(64)))),
+                ("poll_timeout", field(reference("Duration"), Hot, None)),
+                ("optimization_level", field(
+                    reference("OptimizationLevel"),
+                    Restart,
+                    Some(json!("Basic")),
+                )),
+                ("zero_copy", field(json!({"type": "boolean"}), Restart, Some(json!(false)))),
+                ("use_hugepages", field(json!({"type": "boolean"}), Restart, Some(json!(false)))),
+            ],
+        ),
+        "ScalingConfiguration": object(
+            "ScalingConfiguration",
+            "Auto-scaling configuration",
+            vec![
+                ("min_instances", field(uint(Some(1), None), Hot, Some(json!(1)))),
+                ("max_instances", field(uint(Some(1), None), Hot, None)),
+                ("scale_up_threshold", field(ratio(), Hot, None)),
+                ("scale_down_threshold", field(ratio(), Hot, None)),
+                ("cooldown_period", field(reference("Duration"), Hot, None)),
+                ("target_utilization", field(ratio(), Hot, None)),
+            ],
+        ),
+        "SecurityConfiguration": object(
+            "SecurityConfiguration",
+            "Security and compliance configuration",
+            vec![
+                ("encryption_enabled", field(
+                    json!({"type": "boolean"}),
+                    Restart,
+                    Some(json!(false)),
+                )),
+                ("key_rotation_interval", field(reference("Duration"), Hot, None)),
+                ("audit_logging", field(json!({"type": "boolean"}), Hot, Some(json!(false)))),
+                ("compliance_mode", field(
+                    reference("ComplianceMode"),
+                    Restart,
+                    Some(json!("Standard")),
+                )),
+                ("access_control", reference("AccessControlConfiguration")),
+            ],
+        ),
+        "AccessControlConfiguration": object(
+            "AccessControlConfiguration",
+            "Access control configuration",
+            vec![
+                ("required_roles", field(
+                    json!({"type": "array", "items": {"type": "string"}}),
+                    Hot,
+                    Some(json!([])),
+                )),
+                ("restricted_interfaces", field(
+                    json!({"type": "array", "items": {"type": "string"}}),
+                    Hot,
+                    Some(json!([])),
+                )),
+                ("audit_level", field(reference("AuditLevel"), Hot, Some(json!("Basic")))),
+            ],
+        ),
+        "TimestampConfig": object(
+            "TimestampConfig",
+            "Packet timestamping configuration",
+            vec![
+                ("resolution", json!({"enum": ["Nanosecond", "Microsecond", "Millisecond"]})),
+                ("source", json!({"oneOf": [
+                    {"enum": ["System", "Hardware", "Ptp"]},
+                    tagged("Custom", json!({"type": "string"})),
+                ]})),
+                ("sync", json!({"type": "boolean"})),
+            ],
+        ),
+        "OptimizationLevel": {"oneOf": [
+            {"enum": ["None", "Basic", "Aggressive"]},
+            tagged("Custom", uint(None, Some(255))),
+        ]},
+        "ComplianceMode": {"enum": ["Standard", "HIPAA", "PCI", "Custom"]},
+        "AuditLevel": {"enum": ["None", "Basic", "Detailed", "Debug"]},
+        "Duration": {
+            "type": "object",
+            "properties": {
+                "secs": uint(None, None),
+                "nanos": uint(None, Some(999_999_999)),
+            },
+            "required": ["secs", "nanos"],
+            "additionalProperties": false,
+        },
+    });
+
+    let root = schema.as_object_mut().expect("schema root is an object");
+    root.insert("$schema".to_string(), json!(SCHEMA_DIALECT));
+    root.insert("$defs".to_string(), definitions);
+    schema
+}
+
+/// Gets the reload behavior the schema declares for a field
+///
+/// # Arguments
+/// * `schema` - Schema returned by `capture_configuration_schema`
+/// * `definition` - Name of the configuration struct, e.g. `BufferConfiguration`
+/// * `field` - Field of that struct
+///
+/// # Returns
+/// The declared behavior, or None if the field is unknown or is a nested configuration
+pub fn reload_behavior(schema: &Value, definition: &str, field: &str) -> Option<ReloadBehavior> {
+    let properties = if definition == "CaptureConfiguration" {
+        &schema["properties"]
+    } else {
+        &schema["$defs"][definition]["properties"]
+    };
+    match properties[field][RELOAD_KEYWORD].as_str()? {
+        "hot" => Some(Hot),
+        "restart" => Some(Restart),
+        _ => None,
+    }
+}
+
+fn object(title: &str, description: &str, properties: Vec<(&str, Value)>) -> Value {
+    let required: Vec<&str> = properties.iter().map(|(name, _)| *name).collect();
+    let properties: Map<String, Value> = properties
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect();
+    json!({
+        "title": title,
+        "description": description,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn field(mut schema: Value, reload: ReloadBehavior, default: Option<Value>) -> Value {
+    let map = schema.as_object_mut().expect("field schema is an object");
+    map.insert(RELOAD_KEYWORD.to_string(), json!(reload.as_str()));
+    if let Some(default) = default {
+        map.insert("default".to_string(), default);
+    }
+    schema
+}
+
+fn reference(definition: &str) -> Value {
+    json!({ "$ref": format!("#/$defs/{}", definition) })
+}
+
+fn uint(minimum: Option<u64>, maximum: Option<u64>) -> Value {
+    let mut schema = json!({"type": "integer", "minimum": minimum.unwrap_or(0)});
+    if let Some(maximum) = maximum {
+        schema["maximum"] = json!(maximum);
+    }
+    schema
+}
+
+fn ratio() -> Value {
+    json!({"type": "number", "minimum": 0.0, "maximum": 1.0})
+}
+
+fn nullable(schema: Value) -> Value {
+    json!({"anyOf": [schema, {"type": "null"}]})
+}
+
+fn tagged(variant: &str, schema: Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": { variant: schema },
+        "required": [variant],
+        "additionalProperties": false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_config::*;
+    use crate::capture_engine::capture::interface_manager::TimestampConfig;
+    use crate::capture_engine::filter::dedup::DedupConfig;
+
+    /// Field names of a struct; fails to compile if the list falls out of step with the type
+    macro_rules! fields {
+        ($ty:ident { $($field:ident),* $(,)? }) => {{
+            #[allow(dead_code)]
+            fn exhaustive(value: &$ty) {
+                let $ty { $($field: _),* } = value;
+            }
+            (stringify!($ty), vec![$(stringify!($field)),*])
+        }};
+    }
+
+    fn config_types() -> Vec<(&'static str, Vec<&'static str>)> {
+        vec![
+            fields!(CaptureConfiguration {
+                interface_config,
+                buffer_config,
+                filter_config,
+                cloud_config,
+                performance_config,
+                scaling_config,
+                security_config,
+            }),
+            fields!(InterfaceConfiguration {
+                interface_name,
+                promiscuous_mode,
+                snaplen,
+                buffer_size,
+                timeout,
+                timestamps,
+                hardware_acceleration,
+            }),
+            fields!(BufferConfiguration {
+                total_size,
+                chunk_size,
+                pre_allocation,
+                memory_limit,
+                page_size,
+                ring_buffer_count,
+                optimization_level,
+            }),
+            fields!(FilterConfiguration {
+                bpf_filter,
+                custom_filters,
+                optimization_level,
+                hardware_offload,
+                dedup,
+            }),
+            fields!(DedupConfig {
+                window_ns,
+                capacity
+            }),
+            fields!(CloudConfiguration {
+                region,
+                availability_zone,
+                vpc_id,
+                subnet_id,
+                instance_id,
+                tags,
+            }),
+            fields!(PerformanceConfiguration {
+                cpu_affinity,
+                numa_node,
+                batch_size,
+                poll_timeout,
+                optimization_level,
+                zero_copy,
+                use_hugepages,
+            }),
+            fields!(ScalingConfiguration {
+                min_instances,
+                max_instances,
+                scale_up_threshold,
+                scale_down_threshold,
+                cooldown_period,
+                target_utilization,
+            }),
+            fields!(SecurityConfiguration {
+                encryption_enabled,
+                key_rotation_interval,
+                audit_logging,
+                compliance_mode,
+                access_control,
+            }),
+            fields!(AccessControlConfiguration {
+                required_roles,
+                restricted_interfaces,
+                audit_level,
+            }),
+            fields!(TimestampConfig {
+                resolution,
+                source,
+                sync
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_schema_covers_every_config_field() {
+        let schema = capture_configuration_schema();
+        for (name, fields) in config_types() {
+            let definition = if name == "CaptureConfiguration" {
+                &schema
+            } else {
+                &schema["$defs"][name]
+            };
+            let mut described: Vec<&str> = definition["properties"]
+                .as_object()
+                .unwrap_or_else(|| panic!("{} is not described", name))
+                .keys()
+                .map(String::as_str)
+                .collect();
+            let mut expected = fields.clone();
+            described.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(described, expected, "{} fields", name);
+        }
+    }
+
+    #[test]
+    fn test_leaf_fields_declare_reload_behavior() {
+        let schema = capture_configuration_schema();
+        for (name, fields) in config_types() {
+            // Timestamp settings are covered by the reload behavior of their parent field
+            if name == "TimestampConfig" {
+                continue;
+            }
+            for field in fields {
+                let nested = if name == "CaptureConfiguration" {
+                    &schema["properties"][field]
+                } else {
+                    &schema["$defs"][name]["properties"][field]
+                };
+                let is_struct = nested["$ref"]
+                    .as_str()
+                    .is_some_and(|target| target.ends_with("Configuration"));
+                assert!(
+                    is_struct || reload_behavior(&schema, name, field).is_some(),
+                    "{}.{} has no reload behavior",
+                    name,
+                    field
+                );
+            }
+        }
+        assert_eq!(
+            reload_behavior(&schema, "InterfaceConfiguration", "interface_name"),
+            Some(ReloadBehavior::Restart)
+        );
+        assert_eq!(
+            reload_behavior(&schema, "FilterConfiguration", "bpf_filter"),
+            Some(ReloadBehavior::Hot)
+        );
+    }
+
+    #[test]
+    fn test_dedup_defaults_match_config() {
+        let schema = capture_configuration_schema();
+        let dedup = &schema["$defs"]["DedupConfig"]["properties"];
+        let defaults = DedupConfig::default();
+        assert_eq!(dedup["window_ns"]["default"], json!(defaults.window_ns));
+        assert_eq!(dedup["capacity"]["default"], json!(defaults.capacity));
+        assert_eq!(
+            schema["$defs"]["FilterConfiguration"]["properties"]["dedup"]["default"],
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_references_resolve() {
+        let schema = capture_configuration_schema();
+        let text = schema.to_string();
+        for reference in text.split("\"#/$defs/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(
+                schema["$defs"].get(name).is_some(),
+                "dangling reference {}",
+                name
+            );
+        }
+        assert_eq!(schema["$schema"], SCHEMA_DIALECT);
+    }
+}
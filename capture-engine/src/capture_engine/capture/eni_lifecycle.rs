@@ -0,0 +1,691 @@
+// capture-engine/src/capture/eni_lifecycle.rs
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::{mpsc, Notify};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, RuntimeErrorKind,
+};
+use crate::capture_engine::capture::state_machine::StateMachine;
+use crate::capture_engine::interface::traits::InterfaceEvent;
+
+/// Transitions kept in each ENI's history
+const ENI_HISTORY: usize = 32;
+/// Default bound on waiting for in-flight capture before an ENI is detached
+pub const DEFAULT_QUIESCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Lifecycle state of a mirror ENI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EniState {
+    Attaching,
+    Active,
+    Detaching,
+    Detached,
+    Error,
+}
+
+/// Cloud calls that attach and detach mirror ENIs
+#[async_trait]
+pub trait EniBackend: Send + Sync {
+    /// Attaches the ENI to this instance
+    async fn attach(&self, eni_id: &str) -> Result<(), CaptureError>;
+
+    /// Detaches the ENI from this instance
+    async fn detach(&self, eni_id: &str) -> Result<(), CaptureError>;
+}
+
+/// Point-in-time view of a mirror ENI
+///
+/// # Fields
+/// * `eni_id` - ENI identifier
+/// * `state` - Lifecycle state
+/// * `in_flight` - Captures currently running on the ENI
+/// * `last_error` - Why the ENI last entered the Error state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EniStatus {
+    pub eni_id: String,
+    pub state: EniState,
+    pub in_flight: usize,
+    pub last_error: Option<String>,
+}
+
+/// Tracked state of one ENI
+///
+/// # Fields
+/// * `machine` - Lifecycle state machine
+/// * `in_flight` - Captures currently running on the ENI
+/// * `attaching` - Whether an attach call is still outstanding
+/// * `attached` - Whether the backend reported the ENI attached
+/// * `last_error` - Why the ENI last entered the Error state
+#[derive(Debug)]
+struct EniEntry {
+    machine: StateMachine<EniState>,
+    in_flight: usize,
+    attaching: bool,
+    attached: bool,
+    last_error: Option<String>,
+}
+
+impl EniEntry {
+    fn new() -> Result<Self, CaptureError> {
+        use EniState::*;
+
+        let mut machine = StateMachine::new(Detached, ENI_HISTORY)?;
+        for (from, to) in [
+            (Detached, Attaching),
+            (Attaching, Active),
+            (Attaching, Detaching),
+            (Attaching, Error),
+            (Active, Detaching),
+            (Active, Error),
+            (Detaching, Detached),
+            (Detaching, Error),
+            (Error, Attaching),
+            (Error, Detaching),
+        ] {
+            machine.add_transition(from, to);
+        }
+        Ok(Self {
+            machine,
+            in_flight: 0,
+            attaching: false,
+            attached: false,
+            last_error: None,
+        })
+    }
+
+    fn state(&self) -> EniState {
+        *self.machine.current_state()
+    }
+}
+
+/// ENI table shared with capture guards
+///
+/// # Fields
+/// * `enis` - Tracked ENIs by id; entries are never removed
+/// * `changed` - Woken when a capture finishes or an attach call returns
+#[derive(Debug, Default)]
+struct Shared {
+    enis: Mutex<HashMap<String, EniEntry>>,
+    changed: Notify,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, EniEntry>> {
+        self.enis.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Attach and detach lifecycle of the mirror ENIs auto-scaling adds at runtime
+///
+/// Attaches are idempotent: attaching an ENI that is attaching or active does nothing.
+/// Capture runs under an `EniCaptureGuard`, which only an active ENI hands out. A detach first
+/// moves the ENI to Detaching, so no new capture starts, then waits for running captures and
+/// any outstanding attach call to finish before detaching it. A detach requested while the
+/// ENI is still attaching takes over once the attach call returns, detaching the ENI if the
+/// attach succeeded, so no attached ENI is left behind untracked.
+///
+/// # Fields
+/// * `backend` - Cloud calls
+/// * `shared` - ENI table
+/// * `events` - Receives an interface event for every state change
+/// * `quiesce_timeout` - Bound on waiting for capture to stop before a detach
+pub struct EniLifecycle {
+    backend: Arc<dyn EniBackend>,
+    shared: Arc<Shared>,
+    events: Option<mpsc::UnboundedSender<InterfaceEvent<'static>>>,
+    quiesce_timeout: Duration,
+}
+
+impl std::fmt::Debug for EniLifecycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EniLifecycle")
+            .field("enis", &self.statuses())
+            .field("quiesce_timeout", &self.quiesce_timeout)
+            .finish()
+    }
+}
+
+impl EniLifecycle {
+    /// Creates a lifecycle tracking no ENIs
+    ///
+    /// # Arguments
+    /// * `backend` - Cloud calls that attach and detach ENIs
+    pub fn new(backend: Arc<dyn EniBackend>) -> Self {
+        Self {
+            backend,
+            shared: Arc::new(Shared::default()),
+            events: None,
+            quiesce_timeout: DEFAULT_QUIESCE_TIMEOUT,
+        }
+    }
+
+    /// Sends an `InterfaceEvent::EniStateChange` to `events` on every state change
+    pub fn with_events(mut self, events: mpsc::UnboundedSender<InterfaceEvent<'static>>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Sets the bound on waiting for capture to stop before a detach
+    pub fn set_quiesce_timeout(&mut self, timeout: Duration) {
+        self.quiesce_timeout = timeout;
+    }
+
+    /// Attaches an ENI
+    ///
+    /// # Arguments
+    /// * `eni_id` - ENI to attach
+    ///
+    /// # Returns
+    /// The state once the call is done: Active, Attaching if another attach is still running,
+    /// or Detaching if a detach was requested during the attach. Fails if the ENI is detaching
+    /// or the attach call fails, which leaves the ENI in Error
+    pub async fn attach_eni(&self, eni_id: &str) -> Result<EniState, CaptureError> {
+        {
+            let mut enis = self.shared.lock();
+            if !enis.contains_key(eni_id) {
+                enis.insert(eni_id.to_string(), EniEntry::new()?);
+            }
+            let entry = enis.get_mut(eni_id).ok_or_else(|| not_tracked(eni_id))?;
+            match entry.state() {
+                state @ (EniState::Active | EniState::Attaching) => return Ok(state),
+                EniState::Detaching => {
+                    return Err(*CaptureError::new(
+                        CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                        &format!("ENI {} is detaching", eni_id),
+                    ))
+                }
+                EniState::Detached | EniState::Error => {
+                    entry.attaching = true;
+                    entry.last_error = None;
+                    self.transition(eni_id, entry, EniState::Attaching, "attach requested")?;
+                }
+            }
+        }
+
+        let result = self.backend.attach(eni_id).await;
+
+        let mut enis = self.shared.lock();
+        let entry = enis.get_mut(eni_id).ok_or_else(|| not_tracked(eni_id))?;
+        entry.attaching = false;
+        self.shared.changed.notify_waiters();
+        let taken_over = entry.state() != EniState::Attaching;
+        match result {
+            Ok(()) => {
+                entry.attached = true;
+                if !taken_over {
+                    self.transition(eni_id, entry, EniState::Active, "attached")?;
+                }
+                Ok(entry.state())
+            }
+            // The detach that took over finds nothing to detach
+            Err(_) if taken_over => Ok(entry.state()),
+            Err(e) => {
+                entry.last_error = Some(e.message().to_string());
+                self.transition(eni_id, entry, EniState::Error, "attach failed")?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Detaches an ENI once capture on it has quiesced
+    ///
+    /// Detaching an unknown or detached ENI does nothing; detaching one that is already
+    /// detaching waits for that detach.
+    ///
+    /// # Arguments
+    /// * `eni_id` - ENI to detach
+    ///
+    /// # Returns
+    /// Ok once the ENI is detached. Fails if capture does not stop within the quiesce timeout
+    /// or the detach call fails, which leaves the ENI in Error; the detach may be retried
+    pub async fn detach_eni(&self, eni_id: &str) -> Result<(), CaptureError> {
+        let concurrent = {
+            let mut enis = self.shared.lock();
+            let Some(entry) = enis.get_mut(eni_id) else {
+                return Ok(());
+            };
+            match entry.state() {
+                EniState::Detached => return Ok(()),
+                EniState::Detaching => true,
+                EniState::Attaching | EniState::Active | EniState::Error => {
+                    self.transition(eni_id, entry, EniState::Detaching, "detach requested")?;
+                    false
+                }
+            }
+        };
+        if concurrent {
+            self.wait_until(eni_id, |entry| entry.state() != EniState::Detaching)
+                .await?;
+            return match self.get_eni_status(eni_id).map(|status| status.state) {
+                Some(EniState::Detached) => Ok(()),
+                _ => Err(*CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                    &format!("Concurrent detach of ENI {} failed", eni_id),
+                )),
+            };
+        }
+
+        if let Err(e) = self
+            .wait_until(eni_id, |entry| entry.in_flight == 0 && !entry.attaching)
+            .await
+        {
+            self.fail(eni_id, &e)?;
+            return Err(e);
+        }
+
+        let attached = self
+            .shared
+            .lock()
+            .get(eni_id)
+            .is_some_and(|entry| entry.attached);
+        if attached {
+            if let Err(e) = self.backend.detach(eni_id).await {
+                self.fail(eni_id, &e)?;
+                return Err(e);
+            }
+        }
+
+        let mut enis = self.shared.lock();
+        let entry = enis.get_mut(eni_id).ok_or_else(|| not_tracked(eni_id))?;
+        entry.attached = false;
+        self.transition(eni_id, entry, EniState::Detached, "detached")?;
+        self.shared.changed.notify_waiters();
+        Ok(())
+    }
+
+    /// Gets the status of an ENI, or None if it was never attached
+    pub fn get_eni_status(&self, eni_id: &str) -> Option<EniStatus> {
+        self.shared
+            .lock()
+            .get(eni_id)
+            .map(|entry| status(eni_id, entry))
+    }
+
+    /// Gets the status of every tracked ENI, sorted by id
+    pub fn statuses(&self) -> Vec<EniStatus> {
+        let mut statuses: Vec<EniStatus> = self
+            .shared
+            .lock()
+            .iter()
+            .map(|(eni_id, entry)| status(eni_id, entry))
+            .collect();
+        statuses.sort_by(|a, b| a.eni_id.cmp(&b.eni_id));
+        statuses
+    }
+
+    /// Registers a capture on an ENI
+    ///
+    /// # Arguments
+    /// * `eni_id` - ENI to capture on
+    ///
+    /// # Returns
+    /// A guard the capture holds until it stops, or an error if the ENI is not active
+    pub fn begin_capture(&self, eni_id: &str) -> Result<EniCaptureGuard, CaptureError> {
+        let mut enis = self.shared.lock();
+        let entry = enis.get_mut(eni_id).ok_or_else(|| not_tracked(eni_id))?;
+        if entry.state() != EniState::Active {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                &format!(
+                    "Cannot capture on ENI {} while it is {:?}",
+                    eni_id,
+                    entry.state()
+                ),
+            ));
+        }
+        entry.in_flight += 1;
+        Ok(EniCaptureGuard {
+            shared: Arc::clone(&self.shared),
+            eni_id: eni_id.to_string(),
+        })
+    }
+
+    /// Applies a transition and reports it
+    fn transition(
+        &self,
+        eni_id: &str,
+        entry: &mut EniEntry,
+        to: EniState,
+        reason: &str,
+    ) -> Result<(), CaptureError> {
+        let from = entry.state();
+        entry.machine.transition_to(to, Some(reason.to_string()))?;
+        if let Some(events) = &self.events {
+            // A closed receiver only means nobody listens any more
+            let _ = events.send(InterfaceEvent::EniStateChange {
+                eni_id: eni_id.to_string(),
+                from,
+                to,
+            });
+        }
+        Ok(())
+    }
+
+    /// Moves a detaching ENI to Error
+    fn fail(&self, eni_id: &str, error: &CaptureError) -> Result<(), CaptureError> {
+        let mut enis = self.shared.lock();
+        let entry = enis.get_mut(eni_id).ok_or_else(|| not_tracked(eni_id))?;
+        entry.last_error = Some(error.message().to_string());
+        self.transition(eni_id, entry, EniState::Error, "detach failed")?;
+        self.shared.changed.notify_waiters();
+        Ok(())
+    }
+
+    /// Waits until `ready` holds for an ENI, bounded by the quiesce timeout
+    async fn wait_until(
+        &self,
+        eni_id: &str,
+        ready: impl Fn(&EniEntry) -> bool,
+    ) -> Result<(), CaptureError> {
+        let deadline = tokio::time::Instant::now() + self.quiesce_timeout;
+        loop {
+            let changed = self.shared.changed.notified();
+            tokio::pin!(changed);
+            // Registered before the check so a change in between is not missed
+            changed.as_mut().enable();
+            let done = self.shared.lock().get(eni_id).is_none_or(&ready);
+            if done {
+                return Ok(());
+            }
+            if tokio::time::timeout_at(deadline, changed).await.is_err() {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout),
+                    &format!(
+                        "Capture on ENI {} did not quiesce within {:?}",
+                        eni_id, self.quiesce_timeout
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// A running capture on an ENI; the ENI cannot finish detaching while it is held
+#[derive(Debug)]
+pub struct EniCaptureGuard {
+    shared: Arc<Shared>,
+    eni_id: String,
+}
+
+impl EniCaptureGuard {
+    /// Gets the ENI being captured on
+    pub fn eni_id(&self) -> &str {
+        &self.eni_id
+    }
+}
+
+impl Drop for EniCaptureGuard {
+    fn drop(&mut self) {
+        let mut enis = self.shared.lock();
+        if let Some(entry) = enis.get_mut(&self.eni_id) {
+            entry.in_flight -= 1;
+            if entry.in_flight == 0 {
+                self.shared.changed.notify_waiters();
+            }
+        }
+    }
+}
+
+fn status(eni_id: &str, entry: &EniEntry) -> EniStatus {
+    EniStatus {
+        eni_id: eni_id.to_string(),
+        state: entry.state(),
+        in_flight: entry.in_flight,
+        last_error: entry.last_error.clone(),
+    }
+}
+
+fn not_tracked(eni_id: &str) -> CaptureError {
+    *CaptureError::new(
+        CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound),
+        &format!("ENI {} is not tracked", eni_id),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::CloudErrorKind;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Backend whose attach can be held open and made to fail
+    #[derive(Default)]
+    struct MockBackend {
+        attaches: AtomicUsize,
+        detaches: AtomicUsize,
+        fail_attach: AtomicBool,
+        hold_attach: AtomicBool,
+        release: Notify,
+    }
+
+    #[async_trait]
+    impl EniBackend for MockBackend {
+        async fn attach(&self, _eni_id: &str) -> Result<(), CaptureError> {
+            self.attaches.fetch_add(1, Ordering::SeqCst);
+            if self.hold_attach.load(Ordering::SeqCst) {
+                self.release.notified().await;
+            }
+            if self.fail_attach.load(Ordering::SeqCst) {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Cloud(CloudErrorKind::EniError),
+                    "attachment limit exceeded",
+                ));
+            }
+            Ok(())
+        }
+
+        async fn detach(&self, _eni_id: &str) -> Result<(), CaptureError> {
+            self.detaches.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn lifecycle() -> (
+        Arc<EniLifecycle>,
+        Arc<MockBackend>,
+        mpsc::UnboundedReceiver<InterfaceEvent<'static>>,
+    ) {
+        let backend = Arc::new(MockBackend::default());
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut lifecycle = EniLifecycle::new(backend.clone()).with_events(tx);
+        lifecycle.set_quiesce_timeout(Duration::from_millis(200));
+        (Arc::new(lifecycle), backend, rx)
+    }
+
+    fn transitions(rx: &mut mpsc::UnboundedReceiver<InterfaceEvent<'static>>) -> Vec<EniState> {
+        let mut states = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let InterfaceEvent::EniStateChange { to, .. } = event {
+                states.push(to);
+            }
+        }
+        states
+    }
+
+    async fn wait_for_state(lifecycle: &EniLifecycle, state: EniState) {
+        while lifecycle.get_eni_status("eni-1").map(|s| s.state) != Some(state) {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attach_is_idempotent_and_emits_events() {
+        let (lifecycle, backend, mut rx) = lifecycle();
+        assert_eq!(
+            lifecycle.attach_eni("eni-1").await.unwrap(),
+            EniState::Active
+        );
+        assert_eq!(
+            lifecycle.attach_eni("eni-1").await.unwrap(),
+            EniState::Active
+        );
+
+        assert_eq!(backend.attaches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            transitions(&mut rx),
+            vec![EniState::Attaching, EniState::Active]
+        );
+        assert_eq!(
+            lifecycle.get_eni_status("eni-1"),
+            Some(EniStatus {
+                eni_id: "eni-1".to_string(),
+                state: EniState::Active,
+                in_flight: 0,
+                last_error: None,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capture_requires_active_eni() {
+        let (lifecycle, _backend, _rx) = lifecycle();
+        assert!(lifecycle.begin_capture("eni-1").is_err());
+
+        lifecycle.attach_eni("eni-1").await.unwrap();
+        let guard = lifecycle.begin_capture("eni-1").unwrap();
+        assert_eq!(guard.eni_id(), "eni-1");
+        assert_eq!(lifecycle.get_eni_status("eni-1").unwrap().in_flight, 1);
+        drop(guard);
+
+        lifecycle.detach_eni("eni-1").await.unwrap();
+        let error = lifecycle.begin_capture("eni-1").unwrap_err();
+        assert!(error.message().contains("Detached"));
+    }
+
+    #[tokio::test]
+    async fn test_detach_waits_for_in_flight_capture() {
+        let (lifecycle, backend, mut rx) = lifecycle();
+        lifecycle.attach_eni("eni-1").await.unwrap();
+        let guard = lifecycle.begin_capture("eni-1").unwrap();
+
+        let detach = tokio::spawn({
+            let lifecycle = lifecycle.clone();
+            async move { lifecycle.detach_eni("eni-1").await }
+        });
+        wait_for_state(&lifecycle, EniState::Detaching).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(backend.detaches.load(Ordering::SeqCst), 0);
+        assert!(lifecycle.begin_capture("eni-1").is_err());
+
+        drop(guard);
+        detach.await.unwrap().unwrap();
+        assert_eq!(backend.detaches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            transitions(&mut rx),
+            vec![
+                EniState::Attaching,
+                EniState::Active,
+                EniState::Detaching,
+                EniState::Detached
+            ]
+        );
+        // Detaching again does nothing
+        lifecycle.detach_eni("eni-1").await.unwrap();
+        assert_eq!(backend.detaches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_detach_times_out_into_error_and_can_retry() {
+        let (lifecycle, backend, _rx) = lifecycle();
+        lifecycle.attach_eni("eni-1").await.unwrap();
+        let guard = lifecycle.begin_capture("eni-1").unwrap();
+
+        let error = lifecycle.detach_eni("eni-1").await.unwrap_err();
+        assert!(error.message().contains("did not quiesce"));
+        let status = lifecycle.get_eni_status("eni-1").unwrap();
+        assert_eq!(status.state, EniState::Error);
+        assert!(status.last_error.is_some());
+
+        drop(guard);
+        lifecycle.detach_eni("eni-1").await.unwrap();
+        assert_eq!(backend.detaches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            lifecycle.get_eni_status("eni-1").unwrap().state,
+            EniState::Detached
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detach_while_attaching_detaches_attached_eni() {
+        let (lifecycle, backend, mut rx) = lifecycle();
+        backend.hold_attach.store(true, Ordering::SeqCst);
+        let attach = tokio::spawn({
+            let lifecycle = lifecycle.clone();
+            async move { lifecycle.attach_eni("eni-1").await }
+        });
+        wait_for_state(&lifecycle, EniState::Attaching).await;
+        // A second attach while the first runs does not call the backend again
+        assert_eq!(
+            lifecycle.attach_eni("eni-1").await.unwrap(),
+            EniState::Attaching
+        );
+
+        let detach = tokio::spawn({
+            let lifecycle = lifecycle.clone();
+            async move { lifecycle.detach_eni("eni-1").await }
+        });
+        wait_for_state(&lifecycle, EniState::Detaching).await;
+        backend.release.notify_one();
+
+        assert_eq!(attach.await.unwrap().unwrap(), EniState::Detaching);
+        detach.await.unwrap().unwrap();
+        assert_eq!(backend.attaches.load(Ordering::SeqCst), 1);
+        assert_eq!(backend.detaches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            transitions(&mut rx),
+            vec![EniState::Attaching, EniState::Detaching, EniState::Detached]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detach_while_failed_attach_skips_backend_detach() {
+        let (lifecycle, backend, _rx) = lifecycle();
+        backend.hold_attach.store(true, Ordering::SeqCst);
+        backend.fail_attach.store(true, Ordering::SeqCst);
+        let attach = tokio::spawn({
+            let lifecycle = lifecycle.clone();
+            async move { lifecycle.attach_eni("eni-1").await }
+        });
+        wait_for_state(&lifecycle, EniState::Attaching).await;
+
+        let detach = tokio::spawn({
+            let lifecycle = lifecycle.clone();
+            async move { lifecycle.detach_eni("eni-1").await }
+        });
+        wait_for_state(&lifecycle, EniState::Detaching).await;
+        backend.release.notify_one();
+
+        attach.await.unwrap().unwrap();
+        detach.await.unwrap().unwrap();
+        assert_eq!(backend.detaches.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            lifecycle.get_eni_status("eni-1").unwrap().state,
+            EniState::Detached
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_attach_enters_error_and_retries() {
+        let (lifecycle, backend, _rx) = lifecycle();
+        backend.fail_attach.store(true, Ordering::SeqCst);
+        assert!(lifecycle.attach_eni("eni-1").await.is_err());
+        let status = lifecycle.get_eni_status("eni-1").unwrap();
+        assert_eq!(status.state, EniState::Error);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("attachment limit exceeded")
+        );
+
+        backend.fail_attach.store(false, Ordering::SeqCst);
+        assert_eq!(
+            lifecycle.attach_eni("eni-1").await.unwrap(),
+            EniState::Active
+        );
+        assert_eq!(lifecycle.get_eni_status("eni-1").unwrap().last_error, None);
+        assert_eq!(lifecycle.statuses().len(), 1);
+    }
+}
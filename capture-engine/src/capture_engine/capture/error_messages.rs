@@ -1,3 +1,59 @@
 // src/capture/error_messages.rs
 pub const ERR_INVALID_STATE_TRANSITION: &str =
     "Invalid transition from current state to target state";
+
+/// Catalog of stable error codes and their human-readable descriptions
+///
+/// Codes never change meaning once shipped, so alerting can key off them. A retired kind
+/// keeps its entry and new kinds take the next free number in their category.
+pub const ERROR_CATALOG: &[(&str, &str)] = &[
+    ("CAP-NET-0001", "Network interface not found"),
+    ("CAP-NET-0002", "Packet capture failed"),
+    ("CAP-NET-0003", "Capture filter could not be applied"),
+    ("CAP-NET-0004", "Network operation timed out"),
+    ("CAP-NET-0005", "Capture buffer overflowed"),
+    ("CAP-NET-0006", "Network driver reported an error"),
+    ("CAP-SYS-0001", "Memory management failed"),
+    ("CAP-SYS-0002", "Thread management failed"),
+    ("CAP-SYS-0003", "I/O operation failed"),
+    ("CAP-SYS-0004", "Timer management failed"),
+    ("CAP-SYS-0005", "System resource exhausted"),
+    ("CAP-SYS-0006", "System runtime initialization failed"),
+    ("CAP-RES-0001", "Resource not available"),
+    ("CAP-RES-0002", "Resource quota exceeded"),
+    ("CAP-RES-0003", "Resource allocation failed"),
+    ("CAP-RES-0004", "Resource in an invalid state"),
+    ("CAP-CFG-0001", "Configuration value is invalid"),
+    ("CAP-CFG-0002", "Required configuration value is missing"),
+    ("CAP-CFG-0003", "Configuration validation failed"),
+    ("CAP-CFG-0004", "Configuration could not be parsed"),
+    ("CAP-RUN-0001", "Entity not found"),
+    ("CAP-RUN-0002", "Operation failed"),
+    ("CAP-RUN-0003", "Operation not allowed in the current state"),
+    ("CAP-RUN-0004", "Concurrent operations conflicted"),
+    ("CAP-RUN-0005", "Operation timed out"),
+    ("CAP-RUN-0006", "Synchronization lock failed"),
+    ("CAP-CLD-0001", "VPC operation failed"),
+    ("CAP-CLD-0002", "Network interface (ENI) operation failed"),
+    ("CAP-CLD-0003", "Instance metadata unavailable"),
+    ("CAP-CLD-0004", "Scaling operation failed"),
+    ("CAP-CLD-0005", "Cloud API call failed"),
+    ("CAP-SEC-0001", "Access denied"),
+    ("CAP-SEC-0002", "Authentication failed"),
+    ("CAP-SEC-0003", "Encryption failed"),
+    ("CAP-SEC-0004", "Credentials are invalid"),
+];
+
+/// Looks up the human-readable description of an error code
+///
+/// # Arguments
+/// * `code` - Catalog code, e.g. `CAP-NET-0001`
+///
+/// # Returns
+/// The description, or None if the code is not in the catalog
+pub fn describe(code: &str) -> Option<&'static str> {
+    ERROR_CATALOG
+        .iter()
+        .find(|(entry, _)| *entry == code)
+        .map(|(_, description)| *description)
+}
@@ -0,0 +1,489 @@
+// capture-engine/src/capture/error_rate_monitor.rs
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, ErrorSeverity,
+};
+use crate::capture_engine::capture::clock::Clock;
+use crate::capture_engine::capture::health_monitor::{
+    HealthEvent, HealthMetrics, HealthStatus, MonitoredComponent,
+};
+
+/// Largest accepted alert threshold; the monitor keeps up to this many errors per severity
+pub const MAX_ERROR_THRESHOLD: u64 = 100_000;
+
+/// Thresholds for error-rate alerts
+///
+/// A threshold of None disables alerting for that severity.
+///
+/// # Fields
+/// * `window` - Sliding window errors are counted over
+/// * `critical` - Critical errors within the window that raise an alert
+/// * `error` - Errors of severity `Error` within the window that raise an alert
+#[derive(Debug, Clone)]
+pub struct ErrorRateThresholds {
+    pub window: Duration,
+    pub critical: Option<u64>,
+    pub error: Option<u64>,
+}
+
+impl Default for ErrorRateThresholds {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            critical: Some(1),
+            error: Some(50),
+        }
+    }
+}
+
+impl ErrorRateThresholds {
+    /// Validates the thresholds
+    ///
+    /// # Returns
+    /// An error if the window or a threshold is zero, or a threshold exceeds
+    /// `MAX_ERROR_THRESHOLD`
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        if self.window.is_zero() {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Error-rate window must be greater than zero",
+            ));
+        }
+        let in_range = |threshold: Option<u64>| {
+            threshold.is_none_or(|threshold| (1..=MAX_ERROR_THRESHOLD).contains(&threshold))
+        };
+        if !in_range(self.critical) || !in_range(self.error) {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                &format!(
+                    "Error-rate thresholds must be between 1 and {}",
+                    MAX_ERROR_THRESHOLD
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn threshold(&self, severity: ErrorSeverity) -> Option<u64> {
+        match severity {
+            ErrorSeverity::Critical => self.critical,
+            ErrorSeverity::Error => self.error,
+            ErrorSeverity::Warning | ErrorSeverity::Info => None,
+        }
+    }
+}
+
+/// Alert raised when errors of a severity exceed their threshold
+///
+/// # Fields
+/// * `severity` - Severity whose threshold was crossed
+/// * `count` - Errors of that severity within the window
+/// * `threshold` - Configured threshold
+/// * `window` - Window the errors were counted over
+/// * `error_codes` - Codes of the errors in the window with their counts, most frequent first
+/// * `raised_at` - When the alert was raised
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorRateAlert {
+    pub severity: ErrorSeverity,
+    pub count: u64,
+    pub threshold: u64,
+    pub window: Duration,
+    pub error_codes: Vec<(String, u64)>,
+    pub raised_at: SystemTime,
+}
+
+impl ErrorRateAlert {
+    /// Converts the alert into a health event for the global component
+    ///
+    /// # Returns
+    /// A HealthEvent marking the engine critical for critical errors and degraded otherwise
+    pub fn health_event(&self) -> HealthEvent {
+        let new_status = match self.severity {
+            ErrorSeverity::Critical => HealthStatus::Critical,
+            _ => HealthStatus::Degraded,
+        };
+        let codes = self
+            .error_codes
+            .iter()
+            .map(|(code, count)| format!("{} x{}", code, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        HealthEvent {
+            timestamp: self.raised_at,
+            component: MonitoredComponent::Global,
+            previous_status: HealthStatus::Healthy,
+            new_status: new_status.clone(),
+            message: format!(
+                "{} {:?} errors in {:?} exceeds threshold of {}: {}",
+                self.count, self.severity, self.window, self.threshold, codes
+            ),
+            metrics: HealthMetrics {
+                component: MonitoredComponent::Global,
+                status: new_status,
+                last_check: self.raised_at,
+                error_count: self.count,
+                warning_count: 0,
+                latency_ms: 0,
+                custom_metrics: HashMap::new(),
+            },
+        }
+    }
+}
+
+/// An error observation held in the sliding window
+#[derive(Debug)]
+struct ErrorObservation {
+    at: SystemTime,
+    code: String,
+}
+
+/// Most recent errors of one alerting severity
+///
+/// Only the newest `threshold` errors are kept: that is enough to tell whether the threshold is
+/// reached, and at the crossing they are exactly the errors in the window.
+///
+/// # Fields
+/// * `threshold` - Errors within the window that raise an alert
+/// * `observations` - Newest errors within the window, oldest first
+/// * `codes` - Count of each error code among the observations
+/// * `alerting` - Whether an alert was raised and the count has not dropped below threshold
+#[derive(Debug)]
+struct SeverityWindow {
+    threshold: u64,
+    observations: VecDeque<ErrorObservation>,
+    codes: HashMap<String, u64>,
+    alerting: bool,
+}
+
+impl SeverityWindow {
+    fn new(threshold: u64) -> Self {
+        Self {
+            threshold,
+            observations: VecDeque::new(),
+            codes: HashMap::new(),
+            alerting: false,
+        }
+    }
+
+    fn count(&self) -> u64 {
+        self.observations.len() as u64
+    }
+
+    fn push(&mut self, observation: ErrorObservation) {
+        if self.count() >= self.threshold {
+            self.pop_oldest();
+        }
+        *self.codes.entry(observation.code.clone()).or_default() += 1;
+        self.observations.push_back(observation);
+    }
+
+    fn pop_oldest(&mut self) {
+        let Some(oldest) = self.observations.pop_front() else {
+            return;
+        };
+        if let Some(count) = self.codes.get_mut(&oldest.code) {
+            *count -= 1;
+            if *count == 0 {
+                self.codes.remove(&oldest.code);
+            }
+        }
+    }
+
+    /// Drops errors older than the window and re-arms the alert once back under threshold
+    fn prune(&mut self, now: SystemTime, window: Duration) {
+        while let Some(oldest) = self.observations.front() {
+            let age = now.duration_since(oldest.at).unwrap_or(Duration::ZERO);
+            if age < window {
+                break;
+            }
+            self.pop_oldest();
+        }
+        if self.count() < self.threshold {
+            self.alerting = false;
+        }
+    }
+
+    fn codes(&self) -> Vec<(String, u64)> {
+        let mut codes: Vec<(String, u64)> = self
+            .codes
+            .iter()
+            .map(|(code, count)| (code.clone(), *count))
+            .collect();
+        codes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        codes
+    }
+}
+
+/// Counts errors by severity over a sliding window and raises alerts on threshold crossings
+///
+/// Alerts are edge-triggered: once a severity is alerting, further errors do not raise new
+/// alerts until its count has dropped back below the threshold. Only severities with a
+/// threshold are tracked, and each keeps at most its threshold's worth of errors, so memory is
+/// bounded however fast errors arrive.
+///
+/// # Fields
+/// * `thresholds` - Alert thresholds
+/// * `clock` - Time source for the window
+/// * `windows` - Recent errors and alert state of each alerting severity
+#[derive(Debug)]
+pub struct ErrorRateMonitor {
+    thresholds: ErrorRateThresholds,
+    clock: Arc<dyn Clock>,
+    windows: Mutex<HashMap<ErrorSeverity, SeverityWindow>>,
+}
+
+impl ErrorRateMonitor {
+    /// Creates a monitor
+    ///
+    /// # Arguments
+    /// * `thresholds` - Alert thresholds
+    /// * `clock` - Time source for the window
+    ///
+    /// # Returns
+    /// A new ErrorRateMonitor, or an error if the thresholds are invalid
+    pub fn new(
+        thresholds: ErrorRateThresholds,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, CaptureError> {
+        thresholds.validate()?;
+        let windows = [ErrorSeverity::Critical, ErrorSeverity::Error]
+            .into_iter()
+            .filter_map(|severity| {
+                let threshold = thresholds.threshold(severity)?;
+                Some((severity, SeverityWindow::new(threshold)))
+            })
+            .collect();
+        Ok(Self {
+            thresholds,
+            clock,
+            windows: Mutex::new(windows),
+        })
+    }
+
+    /// Records an error
+    ///
+    /// # Arguments
+    /// * `error` - Error that occurred
+    ///
+    /// # Returns
+    /// An alert if this error pushed its severity over the threshold
+    pub fn record(&self, error: &CaptureError) -> Option<ErrorRateAlert> {
+        let now = self.clock.now();
+        let severity = error.severity();
+        let mut windows = self.windows.lock();
+        let window = windows.get_mut(&severity)?;
+        window.prune(now, self.thresholds.window);
+        window.push(ErrorObservation {
+            at: now,
+            code: error.code().to_string(),
+        });
+        if window.count() < window.threshold || window.alerting {
+            return None;
+        }
+        window.alerting = true;
+
+        Some(ErrorRateAlert {
+            severity,
+            count: window.count(),
+            threshold: window.threshold,
+            window: self.thresholds.window,
+            error_codes: window.codes(),
+            raised_at: now,
+        })
+    }
+
+    /// Gets the number of errors of a severity within the current window
+    ///
+    /// Counts stop at the severity's threshold, and severities without one are not counted.
+    ///
+    /// # Arguments
+    /// * `severity` - Severity to count
+    pub fn count(&self, severity: ErrorSeverity) -> u64 {
+        self.with_window(severity, SeverityWindow::count)
+            .unwrap_or(0)
+    }
+
+    /// Checks whether a severity is currently alerting
+    ///
+    /// # Arguments
+    /// * `severity` - Severity to check
+    pub fn is_alerting(&self, severity: ErrorSeverity) -> bool {
+        self.with_window(severity, |window| window.alerting)
+            .unwrap_or(false)
+    }
+
+    /// Reads a severity's window after dropping errors that have left it
+    fn with_window<T>(
+        &self,
+        severity: ErrorSeverity,
+        read: impl FnOnce(&SeverityWindow) -> T,
+    ) -> Option<T> {
+        let mut windows = self.windows.lock();
+        let window = windows.get_mut(&severity)?;
+        window.prune(self.clock.now(), self.thresholds.window);
+        Some(read(window))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::{
+        ErrorBuilder, NetworkErrorKind, ResourceErrorKind, RuntimeErrorKind,
+    };
+    use crate::capture_engine::capture::clock::ManualClock;
+
+    fn error(kind: CaptureErrorKind, severity: ErrorSeverity) -> CaptureError {
+        ErrorBuilder::new()
+            .kind(kind)
+            .message("test error")
+            .severity(severity)
+            .build()
+            .unwrap()
+    }
+
+    fn monitor(critical: u64, error: u64) -> (ErrorRateMonitor, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let thresholds = ErrorRateThresholds {
+            window: Duration::from_secs(10),
+            critical: Some(critical),
+            error: Some(error),
+        };
+        (
+            ErrorRateMonitor::new(thresholds, clock.clone()).unwrap(),
+            clock,
+        )
+    }
+
+    #[test]
+    fn test_critical_rate_past_threshold_raises_alert() {
+        let (monitor, clock) = monitor(3, 100);
+        let overflow = || {
+            error(
+                CaptureErrorKind::Network(NetworkErrorKind::BufferOverflow),
+                ErrorSeverity::Critical,
+            )
+        };
+
+        assert!(monitor.record(&overflow()).is_none());
+        clock.advance(Duration::from_secs(1));
+        assert!(monitor
+            .record(&error(
+                CaptureErrorKind::Resource(ResourceErrorKind::AllocationFailed),
+                ErrorSeverity::Critical,
+            ))
+            .is_none());
+        clock.advance(Duration::from_secs(1));
+        let alert = monitor.record(&overflow()).expect("threshold crossed");
+
+        assert_eq!(alert.severity, ErrorSeverity::Critical);
+        assert_eq!(alert.count, 3);
+        assert_eq!(
+            alert.error_codes,
+            vec![
+                ("CAP-NET-0005".to_string(), 2),
+                ("CAP-RES-0003".to_string(), 1),
+            ]
+        );
+        let event = alert.health_event();
+        assert_eq!(event.new_status, HealthStatus::Critical);
+        assert!(event.message.contains("CAP-NET-0005 x2"));
+
+        // Edge-triggered: staying over the threshold does not alert again
+        assert!(monitor.record(&overflow()).is_none());
+        assert!(monitor.is_alerting(ErrorSeverity::Critical));
+    }
+
+    #[test]
+    fn test_low_rate_does_not_alert() {
+        let (monitor, clock) = monitor(3, 100);
+        for _ in 0..10 {
+            let alert = monitor.record(&error(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout),
+                ErrorSeverity::Critical,
+            ));
+            assert!(alert.is_none());
+            // Two errors per window never reach three
+            clock.advance(Duration::from_secs(6));
+        }
+        assert!(!monitor.is_alerting(ErrorSeverity::Critical));
+    }
+
+    #[test]
+    fn test_alert_rearms_after_window_clears() {
+        let (monitor, clock) = monitor(100, 2);
+        let timeout = || {
+            error(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout),
+                ErrorSeverity::Error,
+            )
+        };
+
+        monitor.record(&timeout());
+        assert!(monitor.record(&timeout()).is_some());
+        clock.advance(Duration::from_secs(11));
+        assert_eq!(monitor.count(ErrorSeverity::Error), 0);
+        assert!(!monitor.is_alerting(ErrorSeverity::Error));
+
+        monitor.record(&timeout());
+        let alert = monitor.record(&timeout()).unwrap();
+        assert_eq!(alert.health_event().new_status, HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn test_warnings_never_alert() {
+        let (monitor, _clock) = monitor(1, 1);
+        let warning = error(
+            CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout),
+            ErrorSeverity::Warning,
+        );
+        assert!(monitor.record(&warning).is_none());
+        // Warnings can never alert, so they are not kept at all
+        assert_eq!(monitor.count(ErrorSeverity::Warning), 0);
+    }
+
+    #[test]
+    fn test_window_keeps_at_most_threshold_errors() {
+        let (monitor, clock) = monitor(100, 3);
+        let mut alerts = 0;
+        for i in 0..1000 {
+            let kind = match i {
+                0..998 => CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout),
+                _ => CaptureErrorKind::Resource(ResourceErrorKind::AllocationFailed),
+            };
+            alerts += monitor.record(&error(kind, ErrorSeverity::Error)).is_some() as u32;
+        }
+        assert_eq!(alerts, 1);
+        assert_eq!(monitor.count(ErrorSeverity::Error), 3);
+        let windows = monitor.windows.lock();
+        assert_eq!(windows[&ErrorSeverity::Error].codes().len(), 2);
+        drop(windows);
+
+        clock.advance(Duration::from_secs(11));
+        assert_eq!(monitor.count(ErrorSeverity::Error), 0);
+        assert!(monitor.windows.lock()[&ErrorSeverity::Error]
+            .codes
+            .is_empty());
+    }
+
+    #[test]
+    fn test_zero_threshold_is_rejected() {
+        let thresholds = ErrorRateThresholds {
+            critical: Some(0),
+            ..Default::default()
+        };
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        assert!(ErrorRateMonitor::new(thresholds, clock.clone()).is_err());
+
+        let thresholds = ErrorRateThresholds {
+            error: Some(MAX_ERROR_THRESHOLD + 1),
+            ..Default::default()
+        };
+        assert!(ErrorRateMonitor::new(thresholds, clock).is_err());
+    }
+}
@@ -10,8 +10,11 @@ use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
 
 use crate::capture_engine::capture::buffer_manager::BufferManager;
-use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, RuntimeErrorKind,
+};
 use crate::capture_engine::capture::capture_statistics::CaptureStatistics;
+use crate::capture_engine::capture::health_rollup::{severity, ComponentHealth, HealthRollup};
 use crate::capture_engine::capture::interface_manager::InterfaceManager;
 use crate::capture_engine::capture::state_machine::{StateMachine, StateTransition};
 use crate::capture_engine::capture::transaction::TransactionMetrics;
@@ -24,7 +27,7 @@ pub enum HealthStatus {
     Unknown,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum MonitoredComponent {
     Buffer,
     Interface,
@@ -32,8 +35,12 @@ pub enum MonitoredComponent {
     Session,
     StateSync,
     Global,
+    Custom(String),
 }
 
+/// Health check of a registered component
+pub type ComponentCheck = Arc<dyn Fn() -> HealthStatus + Send + Sync>;
+
 #[derive(Debug, Clone)]
 pub struct HealthMetrics {
     pub component: MonitoredComponent,
@@ -45,6 +52,26 @@ pub struct HealthMetrics {
     pub custom_metrics: HashMap<String, f64>,
 }
 
+/// Load levels at which a status is entered and left
+///
+/// Leaving at a lower level than entering keeps a load hovering around one boundary from
+/// flapping the status.
+///
+/// # Fields
+/// * `enter` - Load at or above which the status is entered
+/// * `exit` - Load below which the status is left
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HysteresisBand {
+    pub enter: f64,
+    pub exit: f64,
+}
+
+/// Health check thresholds
+///
+/// # Fields
+/// * `degraded` - Load band for `Degraded`, as a fraction of capacity
+/// * `critical` - Load band for `Critical`, as a fraction of capacity
+/// * `min_dwell` - Time a new status must persist before it is emitted; `Critical` is exempt
 #[derive(Debug, Clone)]
 pub struct HealthThresholds {
     pub error_threshold: u64,
@@ -52,6 +79,140 @@ pub struct HealthThresholds {
     pub max_latency_ms: u64,
     pub check_interval: Duration,
     pub recovery_threshold: u64,
+    pub degraded: HysteresisBand,
+    pub critical: HysteresisBand,
+    pub min_dwell: Duration,
+}
+
+impl HealthThresholds {
+    /// Validates the hysteresis bands
+    ///
+    /// # Returns
+    /// An error if a band exits above its entry, or `Critical` is entered below `Degraded`
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        let ordered = |band: &HysteresisBand| band.exit <= band.enter;
+        if !ordered(&self.degraded)
+            || !ordered(&self.critical)
+            || self.critical.enter < self.degraded.enter
+        {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Health bands need exit <= enter and critical.enter >= degraded.enter",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Status a load calls for, given the status currently in effect
+    ///
+    /// # Arguments
+    /// * `load` - Current load as a fraction of capacity
+    /// * `current` - Status in effect
+    ///
+    /// # Returns
+    /// The highest status whose band the load is in; a status already in effect is kept until
+    /// the load falls below its exit level
+    pub fn classify(&self, load: f64, current: &HealthStatus) -> HealthStatus {
+        let holding = |status: HealthStatus, band: &HysteresisBand| {
+            load >= band.enter || (severity(current) >= severity(&status) && load >= band.exit)
+        };
+        if holding(HealthStatus::Critical, &self.critical) {
+            HealthStatus::Critical
+        } else if holding(HealthStatus::Degraded, &self.degraded) {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+}
+
+/// Debounced status of one component
+///
+/// A change to a new status is held as the candidate until it has persisted for the minimum
+/// dwell time, so a brief excursion is never emitted. A change to `Critical` is emitted at once.
+///
+/// # Fields
+/// * `thresholds` - Hysteresis bands and dwell time
+/// * `effective` - Status last emitted
+/// * `candidate` - Status waiting out the dwell time, and when it was first seen
+#[derive(Debug, Clone)]
+pub struct StatusHysteresis {
+    thresholds: HealthThresholds,
+    effective: HealthStatus,
+    candidate: Option<(HealthStatus, SystemTime)>,
+}
+
+impl StatusHysteresis {
+    /// Creates a debouncer starting at `Unknown`
+    ///
+    /// # Arguments
+    /// * `thresholds` - Hysteresis bands and dwell time
+    ///
+    /// # Returns
+    /// An error if the bands fail validation
+    pub fn new(thresholds: HealthThresholds) -> Result<Self, CaptureError> {
+        thresholds.validate()?;
+        Ok(Self {
+            thresholds,
+            effective: HealthStatus::Unknown,
+            candidate: None,
+        })
+    }
+
+    /// Replaces the thresholds, keeping the effective status
+    ///
+    /// # Arguments
+    /// * `thresholds` - Hysteresis bands and dwell time
+    ///
+    /// # Returns
+    /// An error if the bands fail validation; the current thresholds are then kept
+    pub fn set_thresholds(&mut self, thresholds: HealthThresholds) -> Result<(), CaptureError> {
+        thresholds.validate()?;
+        self.thresholds = thresholds;
+        Ok(())
+    }
+
+    /// Gets the status last emitted
+    pub fn effective(&self) -> &HealthStatus {
+        &self.effective
+    }
+
+    /// Gets the status waiting out the dwell time, if any
+    pub fn candidate(&self) -> Option<&HealthStatus> {
+        self.candidate.as_ref().map(|(status, _)| status)
+    }
+
+    /// Feeds a load observation
+    ///
+    /// # Arguments
+    /// * `load` - Current load as a fraction of capacity
+    /// * `now` - Time of the observation
+    ///
+    /// # Returns
+    /// The previous and new status if the effective status changed
+    pub fn observe(&mut self, load: f64, now: SystemTime) -> Option<(HealthStatus, HealthStatus)> {
+        let target = self.thresholds.classify(load, &self.effective);
+        if target == self.effective {
+            self.candidate = None;
+            return None;
+        }
+
+        // The first reading and escalation to Critical are not debounced
+        let prompt = self.effective == HealthStatus::Unknown || target == HealthStatus::Critical;
+        let since = match &self.candidate {
+            Some((candidate, since)) if *candidate == target => *since,
+            _ => now,
+        };
+        let dwelt = now.duration_since(since).unwrap_or_default() >= self.thresholds.min_dwell;
+        if !(prompt || dwelt) {
+            self.candidate = Some((target, since));
+            return None;
+        }
+
+        self.candidate = None;
+        let previous = std::mem::replace(&mut self.effective, target.clone());
+        Some((previous, target))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -90,11 +251,14 @@ pub struct HealthMonitor {
     health_checks: Vec<Box<dyn HealthCheck>>,
     global_status: Arc<RwLock<HealthStatus>>,
     component_status: HashMap<MonitoredComponent, HealthStatus>,
+    dependencies: HealthRollup,
     metrics_history: Vec<HealthMetrics>,
     event_handlers: Vec<Box<dyn HealthEventHandler>>,
     is_running: Arc<AtomicBool>,
     check_interval: Duration,
     max_history_size: usize,
+    hysteresis: HashMap<MonitoredComponent, StatusHysteresis>,
+    custom_checks: Vec<(String, ComponentCheck)>,
 }
 
 #[async_trait::async_trait]
@@ -104,21 +268,140 @@ pub trait HealthEventHandler: Send + Sync {
 
 impl Default for HealthThresholds {
     fn default() -> Self {
-        unimplemented!()
+        Self {
+            error_threshold: 10,
+            warning_threshold: 5,
+            max_latency_ms: 100,
+            check_interval: Duration::from_secs(5),
+            recovery_threshold: 3,
+            degraded: HysteresisBand {
+                enter: 0.8,
+                exit: 0.7,
+            },
+            critical: HysteresisBand {
+                enter: 0.95,
+                exit: 0.9,
+            },
+            min_dwell: Duration::from_secs(10),
+        }
     }
 }
 
 impl HealthMonitor {
     pub fn new(check_interval: Duration, max_history_size: usize) -> Self {
-        unimplemented!()
+        Self {
+            health_checks: Vec::new(),
+            global_status: Arc::new(RwLock::new(HealthStatus::Unknown)),
+            component_status: HashMap::new(),
+            dependencies: HealthRollup::new(),
+            metrics_history: Vec::new(),
+            event_handlers: Vec::new(),
+            is_running: Arc::new(AtomicBool::new(false)),
+            check_interval,
+            max_history_size,
+            hysteresis: HashMap::new(),
+            custom_checks: Vec::new(),
+        }
     }
 
     pub fn add_health_check(&mut self, check: Box<dyn HealthCheck>) {
-        unimplemented!()
+        self.health_checks.push(check);
     }
 
     pub fn add_event_handler(&mut self, handler: Box<dyn HealthEventHandler>) {
-        unimplemented!()
+        self.event_handlers.push(handler);
+    }
+
+    /// Registers a named component checked on every evaluation pass
+    ///
+    /// Registering a name again replaces its check.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the component, reported as `MonitoredComponent::Custom(name)`
+    /// * `check` - Returns the component's current status
+    pub fn register_component(&mut self, name: &str, check: ComponentCheck) {
+        match self
+            .custom_checks
+            .iter_mut()
+            .find(|(existing, _)| existing == name)
+        {
+            Some((_, existing)) => *existing = check,
+            None => self.custom_checks.push((name.to_string(), check)),
+        }
+    }
+
+    /// Runs every registered component check
+    ///
+    /// # Arguments
+    /// * `now` - Time of the pass, recorded in the events
+    ///
+    /// # Returns
+    /// One event per component whose status changed, in registration order
+    pub fn evaluate_components(&mut self, now: SystemTime) -> Vec<HealthEvent> {
+        let mut events = Vec::new();
+        for (name, check) in &self.custom_checks {
+            let component = MonitoredComponent::Custom(name.clone());
+            let status = check();
+            let previous_status = self
+                .component_status
+                .insert(component.clone(), status.clone())
+                .unwrap_or(HealthStatus::Unknown);
+            if previous_status == status {
+                continue;
+            }
+            events.push(HealthEvent {
+                timestamp: now,
+                component: component.clone(),
+                message: format!("{} {:?} -> {:?}", name, previous_status, status),
+                previous_status,
+                new_status: status.clone(),
+                metrics: HealthMetrics {
+                    component,
+                    status,
+                    last_check: now,
+                    error_count: 0,
+                    warning_count: 0,
+                    latency_ms: 0,
+                    custom_metrics: HashMap::new(),
+                },
+            });
+        }
+
+        let overall = self.overall_status();
+        *self
+            .global_status
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = overall;
+        events
+    }
+
+    /// Runs every registered component check and hands the changes to the event handlers
+    ///
+    /// # Returns
+    /// The events emitted, or the first handler error once all handlers have been called
+    pub async fn run_evaluation_pass(&mut self) -> Result<Vec<HealthEvent>, CaptureError> {
+        let events = self.evaluate_components(SystemTime::now());
+        let mut result = Ok(());
+        for event in &events {
+            for handler in &self.event_handlers {
+                if let Err(e) = handler.handle_event(event.clone()).await {
+                    result = result.and(Err(e));
+                }
+            }
+        }
+        result.map(|_| events)
+    }
+
+    /// Last status of a registered component, None if it has not been evaluated
+    pub fn component_status(&self, name: &str) -> Option<HealthStatus> {
+        self.component_status
+            .get(&MonitoredComponent::Custom(name.to_string()))
+            .cloned()
+    }
+
+    /// Worst rolled-up status across all components; `Unknown` if none has reported
+    pub fn overall_status(&self) -> HealthStatus {
+        self.dependencies.aggregate(&self.component_status)
     }
 
     pub async fn start_monitoring(&self) -> Result<(), CaptureError> {
@@ -130,20 +413,127 @@ impl HealthMonitor {
     }
 
     pub fn get_current_status(&self) -> Result<HealthStatus, CaptureError> {
-        unimplemented!()
+        Ok(self
+            .global_status
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
     }
 
     pub fn get_component_status(
         &self,
         component: MonitoredComponent,
     ) -> Result<HealthStatus, CaptureError> {
-        unimplemented!()
+        self.component_status
+            .get(&component)
+            .cloned()
+            .ok_or_else(|| {
+                *CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound),
+                    &format!("No health status for {:?}", component),
+                )
+            })
+    }
+
+    /// Effective component health after rolling failures up through hard dependencies
+    pub fn get_rolled_up_status(&self) -> HashMap<MonitoredComponent, ComponentHealth> {
+        self.dependencies.rollup(&self.component_status)
     }
 
     pub fn get_metrics_history(&self) -> Result<Vec<HealthMetrics>, CaptureError> {
         unimplemented!()
     }
 
+    /// Feeds a component's load through its hysteresis and dwell time
+    ///
+    /// The component's thresholds come from its health check, or the defaults if it has none;
+    /// the observation time is `metrics.last_check`.
+    ///
+    /// # Arguments
+    /// * `metrics` - Latest check result for the component
+    /// * `load` - Current load as a fraction of capacity
+    ///
+    /// # Returns
+    /// The event to emit if the component's effective status changed, or an error if the
+    /// component's thresholds fail validation
+    pub fn observe_load(
+        &mut self,
+        mut metrics: HealthMetrics,
+        load: f64,
+    ) -> Result<Option<HealthEvent>, CaptureError> {
+        let component = metrics.component.clone();
+        let hysteresis = match self.hysteresis.entry(component.clone()) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let thresholds = self
+                    .health_checks
+                    .iter()
+                    .find(|check| check.get_component() == component)
+                    .map(|check| check.get_thresholds().clone())
+                    .unwrap_or_default();
+                entry.insert(StatusHysteresis::new(thresholds)?)
+            }
+        };
+        let Some((previous_status, new_status)) = hysteresis.observe(load, metrics.last_check)
+        else {
+            return Ok(None);
+        };
+
+        self.component_status
+            .insert(component.clone(), new_status.clone());
+        metrics.status = new_status.clone();
+        Ok(Some(HealthEvent {
+            timestamp: metrics.last_check,
+            component,
+            message: format!(
+                "{:?} -> {:?} at load {:.2}",
+                previous_status, new_status, load
+            ),
+            previous_status,
+            new_status,
+            metrics,
+        }))
+    }
+
+    /// Sets the thresholds a component's load is classified against
+    ///
+    /// # Arguments
+    /// * `component` - Component to configure
+    /// * `thresholds` - Hysteresis bands and dwell time
+    ///
+    /// # Returns
+    /// An error if the bands fail validation; the component's thresholds are then unchanged
+    pub fn set_thresholds(
+        &mut self,
+        component: MonitoredComponent,
+        thresholds: HealthThresholds,
+    ) -> Result<(), CaptureError> {
+        match self.hysteresis.entry(component) {
+            std::collections::hash_map::Entry::Occupied(entry) => {
+                entry.into_mut().set_thresholds(thresholds)
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(StatusHysteresis::new(thresholds)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Status last emitted for a component, `Unknown` before its first observation
+    pub fn effective_status(&self, component: &MonitoredComponent) -> HealthStatus {
+        self.hysteresis
+            .get(component)
+            .map(|hysteresis| hysteresis.effective().clone())
+            .unwrap_or(HealthStatus::Unknown)
+    }
+
+    /// Status a component is moving to but has not yet held for the dwell time
+    pub fn candidate_status(&self, component: &MonitoredComponent) -> Option<HealthStatus> {
+        self.hysteresis
+            .get(component)
+            .and_then(|hysteresis| hysteresis.candidate().cloned())
+    }
+
     async fn check_all_components(&self) -> Result<(), CaptureError> {
         unimplemented!()
     }
@@ -165,6 +555,7 @@ pub struct HealthMonitorBuilder {
     max_history_size: Option<usize>,
     health_checks: Vec<Box<dyn HealthCheck>>,
     event_handlers: Vec<Box<dyn HealthEventHandler>>,
+    dependencies: HealthRollup,
 }
 
 impl HealthMonitorBuilder {
@@ -174,6 +565,7 @@ impl HealthMonitorBuilder {
             max_history_size: None,
             health_checks: Vec::new(),
             event_handlers: Vec::new(),
+            dependencies: HealthRollup::new(),
         }
     }
 
@@ -197,8 +589,25 @@ impl HealthMonitorBuilder {
         self
     }
 
+    /// Declares a hard dependency; fails if it would create a cycle
+    pub fn with_dependency(
+        mut self,
+        component: MonitoredComponent,
+        dependency: MonitoredComponent,
+    ) -> Result<Self, CaptureError> {
+        self.dependencies.add_dependency(component, dependency)?;
+        Ok(self)
+    }
+
     pub fn build(self) -> Result<HealthMonitor, CaptureError> {
-        unimplemented!()
+        let mut monitor = HealthMonitor::new(
+            self.check_interval.unwrap_or(Duration::from_secs(5)),
+            self.max_history_size.unwrap_or(100),
+        );
+        monitor.health_checks = self.health_checks;
+        monitor.event_handlers = self.event_handlers;
+        monitor.dependencies = self.dependencies;
+        Ok(monitor)
     }
 }
 
@@ -247,3 +656,241 @@ impl HealthCheck for TransactionHealthCheck {
         &self.thresholds
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hysteresis() -> (StatusHysteresis, SystemTime) {
+        let mut hysteresis = StatusHysteresis::new(HealthThresholds::default()).unwrap();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        assert_eq!(
+            hysteresis.observe(0.5, start),
+            Some((HealthStatus::Unknown, HealthStatus::Healthy))
+        );
+        (hysteresis, start)
+    }
+
+    fn secs(start: SystemTime, secs: u64) -> SystemTime {
+        start + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_bands_keep_status_until_exit() {
+        let thresholds = HealthThresholds::default();
+        assert_eq!(
+            thresholds.classify(0.75, &HealthStatus::Healthy),
+            HealthStatus::Healthy
+        );
+        assert_eq!(
+            thresholds.classify(0.75, &HealthStatus::Degraded),
+            HealthStatus::Degraded
+        );
+        assert_eq!(
+            thresholds.classify(0.69, &HealthStatus::Degraded),
+            HealthStatus::Healthy
+        );
+        assert_eq!(
+            thresholds.classify(0.92, &HealthStatus::Critical),
+            HealthStatus::Critical
+        );
+        assert_eq!(
+            thresholds.classify(0.85, &HealthStatus::Critical),
+            HealthStatus::Degraded
+        );
+    }
+
+    #[test]
+    fn test_oscillation_around_boundary_does_not_flap() {
+        let (mut hysteresis, start) = hysteresis();
+        // Crosses into Degraded, then hovers in the band without emitting anything
+        assert_eq!(hysteresis.observe(0.82, secs(start, 1)), None);
+        assert_eq!(hysteresis.candidate(), Some(&HealthStatus::Degraded));
+        assert_eq!(hysteresis.observe(0.78, secs(start, 2)), None);
+        assert_eq!(hysteresis.observe(0.81, secs(start, 3)), None);
+        assert_eq!(hysteresis.effective(), &HealthStatus::Healthy);
+
+        // Dropping back below the entry level before the dwell time cancels the candidate
+        assert_eq!(hysteresis.observe(0.6, secs(start, 4)), None);
+        assert_eq!(hysteresis.candidate(), None);
+
+        assert_eq!(hysteresis.observe(0.85, secs(start, 5)), None);
+        assert_eq!(
+            hysteresis.observe(0.83, secs(start, 15)),
+            Some((HealthStatus::Healthy, HealthStatus::Degraded))
+        );
+        // Oscillating between 0.72 and 0.79 stays Degraded
+        for (i, load) in [0.72, 0.79, 0.72, 0.79].into_iter().enumerate() {
+            assert_eq!(hysteresis.observe(load, secs(start, 16 + i as u64)), None);
+            assert_eq!(hysteresis.candidate(), None);
+        }
+    }
+
+    #[test]
+    fn test_critical_emitted_without_dwell() {
+        let (mut hysteresis, start) = hysteresis();
+        assert_eq!(
+            hysteresis.observe(0.97, secs(start, 1)),
+            Some((HealthStatus::Healthy, HealthStatus::Critical))
+        );
+
+        // Recovery from Critical still waits out the dwell time
+        assert_eq!(hysteresis.observe(0.5, secs(start, 2)), None);
+        assert_eq!(hysteresis.candidate(), Some(&HealthStatus::Healthy));
+        assert_eq!(
+            hysteresis.observe(0.5, secs(start, 12)),
+            Some((HealthStatus::Critical, HealthStatus::Healthy))
+        );
+    }
+
+    /// Check whose status can be changed by the test
+    fn switchable(status: HealthStatus) -> (ComponentCheck, Arc<RwLock<HealthStatus>>) {
+        let current = Arc::new(RwLock::new(status));
+        let reader = current.clone();
+        (Arc::new(move || reader.read().unwrap().clone()), current)
+    }
+
+    struct RecordingHandler(Arc<RwLock<Vec<MonitoredComponent>>>);
+
+    #[async_trait::async_trait]
+    impl HealthEventHandler for RecordingHandler {
+        async fn handle_event(&self, event: HealthEvent) -> Result<(), CaptureError> {
+            self.0.write().unwrap().push(event.component);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_registered_components_aggregate_worst_wins() {
+        let mut monitor = HealthMonitorBuilder::new().build().unwrap();
+        let (buffers, buffers_status) = switchable(HealthStatus::Healthy);
+        let (interfaces, _) = switchable(HealthStatus::Healthy);
+        monitor.register_component("buffers", buffers);
+        monitor.register_component("interfaces", interfaces);
+        assert_eq!(monitor.component_status("buffers"), None);
+        assert_eq!(monitor.overall_status(), HealthStatus::Unknown);
+
+        let now = SystemTime::now();
+        let events = monitor.evaluate_components(now);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].previous_status, HealthStatus::Unknown);
+        assert_eq!(monitor.overall_status(), HealthStatus::Healthy);
+
+        // Unchanged components emit nothing
+        assert!(monitor.evaluate_components(now).is_empty());
+
+        *buffers_status.write().unwrap() = HealthStatus::Critical;
+        let events = monitor.evaluate_components(now);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].component,
+            MonitoredComponent::Custom("buffers".to_string())
+        );
+        assert_eq!(events[0].new_status, HealthStatus::Critical);
+        assert_eq!(
+            monitor.component_status("buffers"),
+            Some(HealthStatus::Critical)
+        );
+        assert_eq!(
+            monitor.component_status("interfaces"),
+            Some(HealthStatus::Healthy)
+        );
+        assert_eq!(monitor.overall_status(), HealthStatus::Critical);
+        assert_eq!(
+            monitor.get_current_status().unwrap(),
+            HealthStatus::Critical
+        );
+    }
+
+    #[test]
+    fn test_unknown_component_outranks_healthy() {
+        let mut monitor = HealthMonitorBuilder::new().build().unwrap();
+        let (healthy, _) = switchable(HealthStatus::Healthy);
+        let (pending, pending_status) = switchable(HealthStatus::Unknown);
+        monitor.register_component("healthy", healthy);
+        monitor.register_component("pending", pending);
+
+        monitor.evaluate_components(SystemTime::now());
+        assert_eq!(monitor.overall_status(), HealthStatus::Unknown);
+        assert_eq!(monitor.get_current_status().unwrap(), HealthStatus::Unknown);
+
+        *pending_status.write().unwrap() = HealthStatus::Degraded;
+        monitor.evaluate_components(SystemTime::now());
+        assert_eq!(monitor.overall_status(), HealthStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn test_evaluation_pass_notifies_handlers() {
+        let seen = Arc::new(RwLock::new(Vec::new()));
+        let mut monitor = HealthMonitorBuilder::new()
+            .add_event_handler(Box::new(RecordingHandler(seen.clone())))
+            .build()
+            .unwrap();
+        let (check, status) = switchable(HealthStatus::Healthy);
+        monitor.register_component("exporter", check);
+
+        monitor.run_evaluation_pass().await.unwrap();
+        *status.write().unwrap() = HealthStatus::Degraded;
+        monitor.run_evaluation_pass().await.unwrap();
+        monitor.run_evaluation_pass().await.unwrap();
+        assert_eq!(seen.read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_thresholds_rejected_on_construction_and_update() {
+        let mut invalid = HealthThresholds::default();
+        invalid.critical.enter = 0.5;
+        assert!(StatusHysteresis::new(invalid.clone()).is_err());
+
+        let mut monitor = HealthMonitorBuilder::new().build().unwrap();
+        assert!(monitor
+            .set_thresholds(MonitoredComponent::Buffer, invalid.clone())
+            .is_err());
+        monitor
+            .set_thresholds(MonitoredComponent::Buffer, HealthThresholds::default())
+            .unwrap();
+        assert!(monitor
+            .set_thresholds(MonitoredComponent::Buffer, invalid)
+            .is_err());
+    }
+
+    #[test]
+    fn test_overall_status_rolls_up_dependencies() {
+        let mut monitor = HealthMonitorBuilder::new()
+            .with_dependency(
+                MonitoredComponent::Custom("exporter".to_string()),
+                MonitoredComponent::Custom("network".to_string()),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let (exporter, _) = switchable(HealthStatus::Healthy);
+        let (network, network_status) = switchable(HealthStatus::Healthy);
+        monitor.register_component("exporter", exporter);
+        monitor.register_component("network", network);
+        monitor.evaluate_components(SystemTime::now());
+        assert_eq!(monitor.overall_status(), HealthStatus::Healthy);
+
+        *network_status.write().unwrap() = HealthStatus::Critical;
+        monitor.evaluate_components(SystemTime::now());
+        assert_eq!(monitor.overall_status(), HealthStatus::Critical);
+        let rolled_up = monitor.get_rolled_up_status();
+        assert_eq!(
+            rolled_up[&MonitoredComponent::Custom("exporter".to_string())].status,
+            HealthStatus::Degraded
+        );
+    }
+
+    #[test]
+    fn test_invalid_bands_rejected() {
+        let mut thresholds = HealthThresholds::default();
+        assert!(thresholds.validate().is_ok());
+        thresholds.degraded.exit = 0.9;
+        assert!(thresholds.validate().is_err());
+
+        let mut thresholds = HealthThresholds::default();
+        thresholds.critical.enter = 0.5;
+        thresholds.critical.exit = 0.4;
+        assert!(thresholds.validate().is_err());
+    }
+}
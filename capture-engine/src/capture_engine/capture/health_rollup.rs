@@ -0,0 +1,265 @@
+// capture-engine/src/capture/health_rollup.rs
+use std::collections::HashMap;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::capture::health_monitor::{HealthStatus, MonitoredComponent};
+
+/// Health of a component after accounting for its dependencies
+///
+/// # Fields
+/// * `component` - Component the status applies to
+/// * `status` - Own status, raised to at least `Degraded` when a hard dependency is failing
+/// * `reason` - Chain of dependencies leading to the failing component, if one affected the status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentHealth {
+    pub component: MonitoredComponent,
+    pub status: HealthStatus,
+    pub reason: Option<String>,
+}
+
+/// Hard dependencies between monitored components and the health rollup over them
+///
+/// A component whose hard dependency is `Critical`, or is itself degraded by a failing
+/// dependency, is reported at least `Degraded` even when its own check passes. The dependency
+/// graph is kept acyclic so the rollup always terminates.
+#[derive(Debug, Clone, Default)]
+pub struct HealthRollup {
+    dependencies: HashMap<MonitoredComponent, Vec<MonitoredComponent>>,
+}
+
+impl HealthRollup {
+    /// Creates a rollup with no dependencies
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares that `component` cannot work without `dependency`
+    ///
+    /// # Arguments
+    /// * `component` - Dependent component
+    /// * `dependency` - Component it relies on
+    ///
+    /// # Returns
+    /// An error if the dependency would create a cycle
+    pub fn add_dependency(
+        &mut self,
+        component: MonitoredComponent,
+        dependency: MonitoredComponent,
+    ) -> Result<(), CaptureError> {
+        if component == dependency || self.reaches(&dependency, &component) {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ValidationFailed),
+                &format!(
+                    "Dependency of {:?} on {:?} would create a cycle",
+                    component, dependency
+                ),
+            ));
+        }
+        let dependencies = self.dependencies.entry(component).or_default();
+        if !dependencies.contains(&dependency) {
+            dependencies.push(dependency);
+        }
+        Ok(())
+    }
+
+    /// Hard dependencies declared for a component, in declaration order
+    pub fn dependencies(&self, component: &MonitoredComponent) -> &[MonitoredComponent] {
+        self.dependencies
+            .get(component)
+            .map_or(&[], |dependencies| dependencies.as_slice())
+    }
+
+    /// Rolls component statuses up through the dependency graph
+    ///
+    /// # Arguments
+    /// * `statuses` - Each component's own status; components without one are `Unknown`
+    ///
+    /// # Returns
+    /// The effective health of every component in `statuses` or the dependency graph
+    pub fn rollup(
+        &self,
+        statuses: &HashMap<MonitoredComponent, HealthStatus>,
+    ) -> HashMap<MonitoredComponent, ComponentHealth> {
+        let mut resolved = HashMap::new();
+        let components = statuses
+            .keys()
+            .chain(self.dependencies.keys())
+            .chain(self.dependencies.values().flatten());
+        for component in components {
+            self.resolve(component, statuses, &mut resolved);
+        }
+        resolved
+    }
+
+    /// Worst effective status across all components
+    pub fn aggregate(&self, statuses: &HashMap<MonitoredComponent, HealthStatus>) -> HealthStatus {
+        self.rollup(statuses)
+            .into_values()
+            .map(|health| health.status)
+            .max_by_key(severity)
+            .unwrap_or(HealthStatus::Unknown)
+    }
+
+    fn resolve(
+        &self,
+        component: &MonitoredComponent,
+        statuses: &HashMap<MonitoredComponent, HealthStatus>,
+        resolved: &mut HashMap<MonitoredComponent, ComponentHealth>,
+    ) -> ComponentHealth {
+        if let Some(health) = resolved.get(component) {
+            return health.clone();
+        }
+
+        let mut health = ComponentHealth {
+            component: component.clone(),
+            status: statuses
+                .get(component)
+                .cloned()
+                .unwrap_or(HealthStatus::Unknown),
+            reason: None,
+        };
+        for dependency in self.dependencies(component) {
+            let dependency_health = self.resolve(dependency, statuses, resolved);
+            let failing = dependency_health.status == HealthStatus::Critical
+                || dependency_health.reason.is_some();
+            if !failing {
+                continue;
+            }
+            let reason = match dependency_health.reason {
+                Some(inner) => format!("depends on {:?}, which {}", dependency, inner),
+                None => format!(
+                    "depends on {:?} ({:?})",
+                    dependency, dependency_health.status
+                ),
+            };
+            if severity(&health.status) < severity(&HealthStatus::Degraded) {
+                health.status = HealthStatus::Degraded;
+            }
+            health.reason = Some(match health.reason.take() {
+                Some(existing) => format!("{}; {}", existing, reason),
+                None => reason,
+            });
+        }
+
+        resolved.insert(component.clone(), health.clone());
+        health
+    }
+
+    fn reaches(&self, from: &MonitoredComponent, to: &MonitoredComponent) -> bool {
+        let mut stack = vec![from];
+        let mut seen = Vec::new();
+        while let Some(current) = stack.pop() {
+            if current == to {
+                return true;
+            }
+            if seen.contains(&current) {
+                continue;
+            }
+            seen.push(current);
+            stack.extend(self.dependencies(current));
+        }
+        false
+    }
+}
+
+/// Orders statuses by how bad they are; `Unknown` ranks between `Healthy` and `Degraded`
+pub(crate) fn severity(status: &HealthStatus) -> u8 {
+    match status {
+        HealthStatus::Healthy => 0,
+        HealthStatus::Unknown => 1,
+        HealthStatus::Degraded => 2,
+        HealthStatus::Critical => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses(
+        entries: &[(MonitoredComponent, HealthStatus)],
+    ) -> HashMap<MonitoredComponent, HealthStatus> {
+        entries.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_healthy_component_degraded_by_unhealthy_dependency() {
+        let mut rollup = HealthRollup::new();
+        rollup
+            .add_dependency(MonitoredComponent::Session, MonitoredComponent::Interface)
+            .unwrap();
+        let health = rollup.rollup(&statuses(&[
+            (MonitoredComponent::Session, HealthStatus::Healthy),
+            (MonitoredComponent::Interface, HealthStatus::Critical),
+        ]));
+
+        let session = &health[&MonitoredComponent::Session];
+        assert_eq!(session.status, HealthStatus::Degraded);
+        assert!(session.reason.as_deref().unwrap().contains("Interface"));
+        assert_eq!(
+            health[&MonitoredComponent::Interface].status,
+            HealthStatus::Critical
+        );
+    }
+
+    #[test]
+    fn test_failure_propagates_transitively() {
+        let mut rollup = HealthRollup::new();
+        rollup
+            .add_dependency(MonitoredComponent::Global, MonitoredComponent::Session)
+            .unwrap();
+        rollup
+            .add_dependency(MonitoredComponent::Session, MonitoredComponent::Buffer)
+            .unwrap();
+        let health = rollup.rollup(&statuses(&[
+            (MonitoredComponent::Global, HealthStatus::Healthy),
+            (MonitoredComponent::Session, HealthStatus::Healthy),
+            (MonitoredComponent::Buffer, HealthStatus::Critical),
+        ]));
+
+        let global = &health[&MonitoredComponent::Global];
+        assert_eq!(global.status, HealthStatus::Degraded);
+        assert_eq!(
+            global.reason.as_deref(),
+            Some("depends on Session, which depends on Buffer (Critical)")
+        );
+    }
+
+    #[test]
+    fn test_degraded_dependency_does_not_propagate() {
+        let mut rollup = HealthRollup::new();
+        rollup
+            .add_dependency(MonitoredComponent::Session, MonitoredComponent::Buffer)
+            .unwrap();
+        let states = statuses(&[
+            (MonitoredComponent::Session, HealthStatus::Healthy),
+            (MonitoredComponent::Buffer, HealthStatus::Degraded),
+        ]);
+
+        let session = &rollup.rollup(&states)[&MonitoredComponent::Session];
+        assert_eq!(session.status, HealthStatus::Healthy);
+        assert_eq!(session.reason, None);
+        assert_eq!(rollup.aggregate(&states), HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn test_cycles_rejected() {
+        let mut rollup = HealthRollup::new();
+        rollup
+            .add_dependency(MonitoredComponent::Session, MonitoredComponent::Interface)
+            .unwrap();
+        rollup
+            .add_dependency(MonitoredComponent::Interface, MonitoredComponent::Buffer)
+            .unwrap();
+
+        assert!(rollup
+            .add_dependency(MonitoredComponent::Buffer, MonitoredComponent::Session)
+            .is_err());
+        assert!(rollup
+            .add_dependency(MonitoredComponent::Buffer, MonitoredComponent::Buffer)
+            .is_err());
+        assert!(rollup.dependencies(&MonitoredComponent::Buffer).is_empty());
+    }
+}
@@ -4,14 +4,21 @@
 // capture-engine/src/capture/interface_manager.rs
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::capture_engine::capture::capture_config::CaptureConfiguration;
 use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::eni_lifecycle::{
+    EniCaptureGuard, EniLifecycle, EniState, EniStatus,
+};
 use crate::capture_engine::capture::state_machine::{StateMachine, StateTransition};
 use crate::capture_engine::capture::state_recovery::{RecoveryPoint, StateSnapshot};
 use crate::capture_engine::capture::state_sync::StateSync;
 use crate::capture_engine::capture::state_validator::StateValidator;
+use crate::capture_engine::interface::batch::CaptureBatchResult;
+use crate::capture_engine::interface::rate_limit::{CaptureRate, CaptureRateLimiter};
+use crate::capture_engine::interface::source::SourceRunner;
+use crate::capture_engine::interface::traits::RxStats;
 
 /// Defines the direction of packet capture
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -73,6 +80,8 @@ pub struct ManagedInterface {
     capabilities: InterfaceCapabilities,
     config: CaptureConfiguration,
     recovery_points: Vec<RecoveryPoint>,
+    rate_limiter: Option<CaptureRateLimiter>,
+    rx_stats: RxStats,
 }
 
 #[derive(Debug, Clone)]
@@ -113,6 +122,7 @@ pub struct InterfaceManager {
     state_sync: Arc<StateSync<InterfaceState>>,
     state_validator: StateValidator<InterfaceState>,
     recovery_config: InterfaceRecoveryConfig,
+    enis: EniLifecycle,
 }
 
 impl Default for InterfaceState {
@@ -149,6 +159,59 @@ impl ManagedInterface {
     ) -> Result<(), CaptureError> {
         unimplemented!()
     }
+
+    /// Sets the capture rate, or removes it with None; the buckets start full
+    pub fn set_capture_rate(&mut self, rate: Option<CaptureRate>) -> Result<(), CaptureError> {
+        self.rate_limiter = rate
+            .map(|rate| CaptureRateLimiter::new(rate, Instant::now()))
+            .transpose()?;
+        Ok(())
+    }
+
+    /// Sets a packets-per-second capture limit, or removes it with None
+    ///
+    /// # Arguments
+    /// * `limit` - Packets per second, allowing one second of packets as a burst
+    pub fn set_capture_rate_limit(&mut self, limit: Option<u64>) -> Result<(), CaptureError> {
+        self.set_capture_rate(limit.map(CaptureRate::packets))
+    }
+
+    /// Gets the enforced capture rate
+    pub fn capture_rate(&self) -> Option<&CaptureRate> {
+        self.rate_limiter.as_ref().map(CaptureRateLimiter::rate)
+    }
+
+    /// Drops the packets of a captured batch beyond the capture rate
+    pub fn limit_batch<'a>(&mut self, batch: CaptureBatchResult<'a>) -> CaptureBatchResult<'a> {
+        match &mut self.rate_limiter {
+            Some(limiter) => limiter.limit_batch(batch, Instant::now(), &mut self.rx_stats),
+            None => batch,
+        }
+    }
+
+    /// Polls one batch from the interface's source, dropping packets beyond the capture rate
+    ///
+    /// # Arguments
+    /// * `runner` - Runner over the interface's packet source
+    /// * `handler` - Receives the batch after rate limiting
+    ///
+    /// # Returns
+    /// The number of packets delivered to `handler`
+    pub fn capture_batch<F>(
+        &mut self,
+        runner: &mut SourceRunner,
+        handler: F,
+    ) -> Result<usize, CaptureError>
+    where
+        F: FnMut(&CaptureBatchResult<'_>) -> Result<(), CaptureError>,
+    {
+        runner.run_limited_batch(|batch| self.limit_batch(batch), handler)
+    }
+
+    /// Gets the receive counters, including rate-limited drops
+    pub fn rx_stats(&self) -> &RxStats {
+        &self.rx_stats
+    }
 }
 
 impl Default for InterfaceManager {
@@ -232,6 +295,26 @@ impl InterfaceManager {
     pub fn recover_interface(&mut self, name: &str) -> Result<(), CaptureError> {
         unimplemented!()
     }
+
+    /// Attaches a mirror ENI; attaching an attaching or active ENI does nothing
+    pub async fn attach_eni(&self, eni_id: &str) -> Result<EniState, CaptureError> {
+        self.enis.attach_eni(eni_id).await
+    }
+
+    /// Detaches a mirror ENI once capture on it has quiesced
+    pub async fn detach_eni(&self, eni_id: &str) -> Result<(), CaptureError> {
+        self.enis.detach_eni(eni_id).await
+    }
+
+    /// Gets the lifecycle status of a mirror ENI
+    pub fn get_eni_status(&self, eni_id: &str) -> Option<EniStatus> {
+        self.enis.get_eni_status(eni_id)
+    }
+
+    /// Registers a capture on a mirror ENI, rejected unless the ENI is active
+    pub fn begin_eni_capture(&self, eni_id: &str) -> Result<EniCaptureGuard, CaptureError> {
+        self.enis.begin_capture(eni_id)
+    }
 }
 
 /// Builder for InterfaceManager
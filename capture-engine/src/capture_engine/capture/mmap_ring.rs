@@ -0,0 +1,439 @@
+// capture-engine/src/capture/mmap_ring.rs
+use parking_lot::Mutex;
+use std::fmt;
+use std::io;
+use std::ptr::{self, NonNull};
+use std::sync::Arc;
+
+use crate::capture_engine::capture::buffer_manager::{BufferMemoryType, BufferPoolAccounting};
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, NetworkErrorKind, ResourceErrorKind,
+};
+
+/// Anonymous shared mapping holding every frame of a ring
+///
+/// # Fields
+/// * `ptr` - Start of the mapping
+/// * `len` - Length of the mapping in bytes
+struct MmapRegion {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// The mapping is only reached through frames, and each frame is handed to one owner at a time
+unsafe impl Send for MmapRegion {}
+unsafe impl Sync for MmapRegion {}
+
+impl MmapRegion {
+    fn new(len: usize) -> Result<Self, CaptureError> {
+        // SAFETY: a fresh anonymous mapping aliases no existing memory
+        let addr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::AllocationFailed),
+                &format!("Failed to map a {} byte frame ring", len),
+            )
+            .with_source(io::Error::last_os_error()));
+        }
+        Ok(Self {
+            ptr: NonNull::new(addr.cast()).expect("mmap returned a null mapping"),
+            len,
+        })
+    }
+}
+
+impl Drop for MmapRegion {
+    fn drop(&mut self) {
+        // SAFETY: the mapping was created by `new` with this length, and frames keep the
+        // region alive, so nothing refers to it any more
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), self.len);
+        }
+    }
+}
+
+/// Mapping and slot bookkeeping shared by a ring and its outstanding frames
+///
+/// # Fields
+/// * `region` - Mapping holding every frame
+/// * `memory_type` - Memory type the frames are accounted under
+/// * `state` - Next frame to produce and which frames are in flight
+/// * `accounting` - Pool accounting shared with the buffer manager
+struct RingShared {
+    region: MmapRegion,
+    memory_type: BufferMemoryType,
+    state: Mutex<RingState>,
+    accounting: Arc<BufferPoolAccounting>,
+}
+
+impl RingShared {
+    /// Clears the in-flight mark of a frame and returns it to the pool accounting
+    fn free(&self, index: usize) {
+        let mut state = self.state.lock();
+        // A frame is only constructed by `acquire` and frees its slot once, so the slot is
+        // always marked in flight and counted in use here
+        state.in_flight[index] = false;
+        state.frames_in_flight -= 1;
+        let _ = self.accounting.release(self.memory_type);
+    }
+}
+
+/// Frame carved out of a ring's shared mapping
+///
+/// A frame is owned by one consumer until it is released back to its ring or dropped; either
+/// hands its slot back. It keeps the mapping alive, so its memory stays valid even if the ring
+/// is dropped first.
+///
+/// # Fields
+/// * `ring` - Mapping the frame points into and the slots it is tracked in
+/// * `index` - Position of the frame in the ring
+/// * `len` - Size of the frame in bytes
+pub struct RingFrame {
+    ring: Arc<RingShared>,
+    index: usize,
+    len: usize,
+}
+
+impl RingFrame {
+    /// Gets the position of the frame in its ring
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Gets the size of the frame in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the frame has no bytes, which a validated ring never produces
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the frame contents
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: the frame lies within the live mapping and no other frame overlaps it
+        unsafe { std::slice::from_raw_parts(self.start(), self.len) }
+    }
+
+    /// Gets the frame contents for writing
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: as for `as_slice`, and the ring hands the frame to a single owner
+        unsafe { std::slice::from_raw_parts_mut(self.start(), self.len) }
+    }
+
+    fn start(&self) -> *mut u8 {
+        // SAFETY: index * len is within the mapping, as checked when the ring was created
+        unsafe { self.ring.region.ptr.as_ptr().add(self.index * self.len) }
+    }
+}
+
+impl Drop for RingFrame {
+    fn drop(&mut self) {
+        self.ring.free(self.index);
+    }
+}
+
+impl fmt::Debug for RingFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RingFrame")
+            .field("index", &self.index)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// Frame occupancy of a ring
+///
+/// # Fields
+/// * `total_frames` - Frames carved out of the mapping
+/// * `frames_in_flight` - Frames handed out and not yet released
+/// * `overflows` - Acquisitions refused because the producer caught up with the consumer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingMetrics {
+    pub total_frames: usize,
+    pub frames_in_flight: usize,
+    pub overflows: u64,
+}
+
+#[derive(Debug)]
+struct RingState {
+    head: usize,
+    in_flight: Vec<bool>,
+    frames_in_flight: usize,
+    overflows: u64,
+}
+
+/// Fixed-size frames carved out of one contiguous mmap'd region
+///
+/// Frames are produced strictly in ring order, as with an AF_PACKET RX ring. When the producer
+/// wraps around to a frame the consumer still holds, acquisition fails with a buffer overflow
+/// instead of overwriting live data; it succeeds again once that frame is released. Releasing
+/// a frame returns it to the ring without unmapping anything. Frames are counted in the shared
+/// pool accounting under their `MmapRing` memory type.
+///
+/// # Fields
+/// * `shared` - Mapping, slot state and accounting shared with outstanding frames
+/// * `frame_size` - Size of each frame in bytes
+pub struct MmapRing {
+    shared: Arc<RingShared>,
+    frame_size: usize,
+}
+
+impl MmapRing {
+    /// Maps the ring region and registers its frames
+    ///
+    /// # Arguments
+    /// * `memory_type` - `MmapRing` layout of the region
+    /// * `accounting` - Pool accounting to register the frames with
+    ///
+    /// # Returns
+    /// The ring, or an error if the layout is not an `MmapRing` whose region is a non-zero
+    /// multiple of the frame size, or the region cannot be mapped
+    pub fn new(
+        memory_type: BufferMemoryType,
+        accounting: Arc<BufferPoolAccounting>,
+    ) -> Result<Self, CaptureError> {
+        let BufferMemoryType::MmapRing {
+            region_size,
+            frame_size,
+        } = memory_type
+        else {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "A frame ring needs the MmapRing memory type",
+            ));
+        };
+        if frame_size == 0 || region_size < frame_size || region_size % frame_size != 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Ring region size must be a non-zero multiple of the frame size",
+            ));
+        }
+
+        let region = MmapRegion::new(region_size)?;
+        let frame_count = region_size / frame_size;
+        accounting.add_buffers(memory_type, frame_count);
+        Ok(Self {
+            shared: Arc::new(RingShared {
+                region,
+                memory_type,
+                state: Mutex::new(RingState {
+                    head: 0,
+                    in_flight: vec![false; frame_count],
+                    frames_in_flight: 0,
+                    overflows: 0,
+                }),
+                accounting,
+            }),
+            frame_size,
+        })
+    }
+
+    /// Gets the number of frames in the ring
+    pub fn total_frames(&self) -> usize {
+        self.shared.region.len / self.frame_size
+    }
+
+    /// Takes the next frame in ring order
+    ///
+    /// # Returns
+    /// The frame, or a `BufferOverflow` error if the consumer still holds it
+    pub fn acquire(&self) -> Result<RingFrame, CaptureError> {
+        let mut state = self.shared.state.lock();
+        let index = state.head;
+        if state.in_flight[index] {
+            state.overflows += 1;
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Network(NetworkErrorKind::BufferOverflow),
+                &format!(
+                    "Frame ring exhausted: frame {} has not been released by the consumer",
+                    index
+                ),
+            ));
+        }
+        self.shared.accounting.acquire(self.shared.memory_type)?;
+
+        state.in_flight[index] = true;
+        state.frames_in_flight += 1;
+        state.head = (index + 1) % state.in_flight.len();
+        Ok(RingFrame {
+            ring: Arc::clone(&self.shared),
+            index,
+            len: self.frame_size,
+        })
+    }
+
+    /// Returns a frame to the ring, keeping the mapping in place
+    ///
+    /// Dropping the frame has the same effect; this also checks where the frame came from.
+    ///
+    /// # Arguments
+    /// * `frame` - Frame taken from this ring
+    ///
+    /// # Returns
+    /// An error if the frame belongs to another ring; the frame still goes back to its own ring
+    pub fn release(&self, frame: RingFrame) -> Result<(), CaptureError> {
+        if !Arc::ptr_eq(&frame.ring, &self.shared) {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                "Released frame belongs to a different ring",
+            ));
+        }
+        drop(frame);
+        Ok(())
+    }
+
+    /// Gets the frame occupancy of the ring
+    pub fn metrics(&self) -> RingMetrics {
+        let state = self.shared.state.lock();
+        RingMetrics {
+            total_frames: state.in_flight.len(),
+            frames_in_flight: state.frames_in_flight,
+            overflows: state.overflows,
+        }
+    }
+}
+
+impl fmt::Debug for MmapRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapRing")
+            .field("memory_type", &self.shared.memory_type)
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_ring(frames: usize) -> (MmapRing, Arc<BufferPoolAccounting>) {
+        let accounting = Arc::new(BufferPoolAccounting::default());
+        let ring = MmapRing::new(
+            BufferMemoryType::MmapRing {
+                region_size: frames * 2048,
+                frame_size: 2048,
+            },
+            Arc::clone(&accounting),
+        )
+        .unwrap();
+        (ring, accounting)
+    }
+
+    #[test]
+    fn test_frames_are_disjoint_views_of_the_region() {
+        let (ring, _) = new_ring(4);
+        let mut first = ring.acquire().unwrap();
+        let mut second = ring.acquire().unwrap();
+        assert_eq!((first.index(), second.index()), (0, 1));
+        assert_eq!(first.len(), 2048);
+
+        first.as_mut_slice().fill(0xaa);
+        second.as_mut_slice().fill(0x55);
+        assert!(first.as_slice().iter().all(|&byte| byte == 0xaa));
+        assert_eq!(
+            second.as_slice().as_ptr() as usize - first.as_slice().as_ptr() as usize,
+            2048
+        );
+    }
+
+    #[test]
+    fn test_wraparound_signals_overflow_instead_of_overwriting() {
+        let (ring, accounting) = new_ring(3);
+        let frames: Vec<_> = (0..3).map(|_| ring.acquire().unwrap()).collect();
+
+        let err = ring.acquire().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Network(NetworkErrorKind::BufferOverflow)
+        ));
+        assert_eq!(
+            ring.metrics(),
+            RingMetrics {
+                total_frames: 3,
+                frames_in_flight: 3,
+                overflows: 1,
+            }
+        );
+        assert_eq!(accounting.snapshot().utilization(), 1.0);
+
+        // Releasing a later frame does not free the head; the consumer must release frame 0
+        let mut frames = frames.into_iter();
+        let head = frames.next().unwrap();
+        ring.release(frames.next().unwrap()).unwrap();
+        assert!(ring.acquire().is_err());
+        ring.release(head).unwrap();
+
+        let reused = ring.acquire().unwrap();
+        assert_eq!(reused.index(), 0);
+        assert_eq!(ring.metrics().frames_in_flight, 2);
+    }
+
+    #[test]
+    fn test_release_keeps_mapping_and_updates_accounting() {
+        let (ring, accounting) = new_ring(2);
+        let mut frame = ring.acquire().unwrap();
+        frame.as_mut_slice()[0] = 7;
+        ring.release(frame).unwrap();
+
+        let snapshot = accounting.snapshot();
+        assert_eq!(snapshot.mmap_ring.free, 2);
+        assert_eq!(snapshot.in_use, 0);
+
+        // Frame 1 is next, then the ring wraps to the frame written above
+        ring.release(ring.acquire().unwrap()).unwrap();
+        let frame = ring.acquire().unwrap();
+        assert_eq!(frame.index(), 0);
+        assert_eq!(frame.as_slice()[0], 7);
+    }
+
+    #[test]
+    fn test_dropped_frames_return_to_the_ring() {
+        let (ring, accounting) = new_ring(2);
+        for _ in 0..5 {
+            let first = ring.acquire().unwrap();
+            let second = ring.acquire().unwrap();
+            assert!(ring.acquire().is_err());
+            drop(first);
+            drop(second);
+        }
+        assert_eq!(ring.metrics().frames_in_flight, 0);
+        assert_eq!(accounting.snapshot().in_use, 0);
+
+        // A frame outliving its ring still frees its slot without touching a dead mapping
+        let frame = ring.acquire().unwrap();
+        drop(ring);
+        assert_eq!(frame.len(), 2048);
+        drop(frame);
+        assert_eq!(accounting.snapshot().in_use, 0);
+    }
+
+    #[test]
+    fn test_foreign_frame_rejected() {
+        let (ring, _) = new_ring(2);
+        let (other, _) = new_ring(2);
+        let frame = other.acquire().unwrap();
+        assert!(ring.release(frame).is_err());
+    }
+
+    #[test]
+    fn test_invalid_layout_rejected() {
+        let accounting = Arc::new(BufferPoolAccounting::default());
+        let layout = BufferMemoryType::MmapRing {
+            region_size: 3000,
+            frame_size: 2048,
+        };
+        assert!(MmapRing::new(layout, Arc::clone(&accounting)).is_err());
+        assert!(MmapRing::new(BufferMemoryType::Heap, accounting).is_err());
+    }
+}
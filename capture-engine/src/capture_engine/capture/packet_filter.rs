@@ -2,14 +2,56 @@
 #![allow(unused)]
 #![allow(unused_variables)]
 // capture-engine/src/capture/capture_config.rs
-use crate::capture_engine::capture::capture_error::CaptureError;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::SystemTime;
 
-#[derive(Debug, Clone)]
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, CaptureResult, ConfigErrorKind,
+};
+use crate::capture_engine::control::traits::FilterAction;
+use crate::capture_engine::filter::bpf_expression;
+use crate::capture_engine::filter::ruleset::{
+    self, CompiledRuleset, FilterRuleset, OffloadCapabilities, PacketFields, RulesetReport,
+};
+use crate::capture_engine::filter::sampling;
+use crate::capture_engine::filter::stats::{RuleId, RuleMatchStats};
+
+/// Which end of a connection an address or port is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterDirection {
+    Src,
+    Dst,
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum FilterRule {
     Protocol(String),
     Port(u16),
     Host(String),
     Custom(String),
+    // Ethernet frame type, e.g. 0x0800 for IPv4
+    EtherType(u16),
+    // Address prefix; a full-length prefix matches a single host
+    Net {
+        direction: FilterDirection,
+        addr: IpAddr,
+        prefix_len: u8,
+    },
+    // Inclusive port range; equal bounds match a single port
+    PortRange {
+        direction: FilterDirection,
+        start: u16,
+        end: u16,
+    },
+    // TCP flags under `mask`, equal to `equals`, or with any bit set when `equals` is None
+    TcpFlags {
+        mask: u8,
+        equals: Option<u8>,
+    },
     And(Box<FilterRule>, Box<FilterRule>),
     Or(Box<FilterRule>, Box<FilterRule>),
     Not(Box<FilterRule>),
@@ -20,6 +62,117 @@ pub struct PacketFilter {
     rules: Vec<FilterRule>,
     compiled_expression: Option<String>,
     is_optimized: bool,
+    active: RulesetHandle,
+}
+
+/// A published rule set and the generation it was published as
+///
+/// # Fields
+/// * `generation` - Number of swaps before this set went live, starting at 0
+/// * `rules` - Rules of the set
+/// * `ruleset` - Compiled control-plane ruleset packets are evaluated against, if one is active
+#[derive(Debug)]
+pub struct RuleGeneration {
+    generation: u64,
+    rules: Vec<FilterRule>,
+    ruleset: Option<Arc<CompiledRuleset>>,
+}
+
+impl RuleGeneration {
+    /// Gets the generation number
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Gets the rules of this generation
+    pub fn rules(&self) -> &[FilterRule] {
+        &self.rules
+    }
+
+    /// Gets the compiled ruleset of this generation, None if no ruleset is active
+    pub fn ruleset(&self) -> Option<&Arc<CompiledRuleset>> {
+        self.ruleset.as_ref()
+    }
+}
+
+/// Shared view of a filter's live rule set
+///
+/// Matchers load the current generation once per packet and keep using it while they process
+/// the packet, so a concurrent swap never changes the rules under them. The lock is held only
+/// to clone or replace an `Arc`, never while rules are evaluated.
+///
+/// # Fields
+/// * `current` - Generation new packets are matched against
+#[derive(Debug, Clone)]
+pub struct RulesetHandle {
+    current: Arc<RwLock<Arc<RuleGeneration>>>,
+}
+
+impl RulesetHandle {
+    fn new(rules: Vec<FilterRule>) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(RuleGeneration {
+                generation: 0,
+                rules,
+                ruleset: None,
+            }))),
+        }
+    }
+
+    /// Gets the live generation
+    ///
+    /// # Returns
+    /// The generation, which stays valid after later swaps
+    pub fn load(&self) -> Arc<RuleGeneration> {
+        Arc::clone(&self.current.read())
+    }
+
+    /// Finds the action for a packet under the live generation's compiled ruleset
+    ///
+    /// # Arguments
+    /// * `packet` - Packet fields
+    /// * `at` - Capture time of the packet, recorded as the matching rule's last match
+    ///
+    /// # Returns
+    /// The action to apply, or None if no ruleset is active
+    pub fn evaluate(&self, packet: &PacketFields, at: SystemTime) -> Option<FilterAction> {
+        self.load()
+            .ruleset
+            .as_ref()
+            .map(|ruleset| ruleset.evaluate(packet, at).clone())
+    }
+
+    /// Finds the action for a packet under the live generation, deciding flow sampling
+    ///
+    /// A matched `SampleFlow` action is returned as `Accept` or `Drop`, the same decision for
+    /// every packet of the flow in either direction. Other actions are returned unchanged.
+    ///
+    /// # Arguments
+    /// * `packet` - Packet fields
+    /// * `at` - Capture time of the packet
+    ///
+    /// # Returns
+    /// The action to apply, or None if no ruleset is active
+    pub fn evaluate_flow(&self, packet: &PacketFields, at: SystemTime) -> Option<FilterAction> {
+        self.evaluate(packet, at)
+            .map(|action| sampling::resolve_flow(&action, packet))
+    }
+
+    /// Publishes a new generation, keeping whichever of the rules and compiled ruleset is None
+    fn publish(
+        &self,
+        rules: Option<Vec<FilterRule>>,
+        ruleset: Option<Arc<CompiledRuleset>>,
+    ) -> u64 {
+        let mut current = self.current.write();
+        let generation = current.generation + 1;
+        *current = Arc::new(RuleGeneration {
+            generation,
+            rules: rules.unwrap_or_else(|| current.rules.clone()),
+            ruleset: ruleset.or_else(|| current.ruleset.clone()),
+        });
+        generation
+    }
 }
 
 impl Default for PacketFilter {
@@ -68,6 +221,203 @@ impl PacketFilter {
     pub fn validate(&self) -> Result<(), CaptureError> {
         unimplemented!()
     }
+
+    /// Builds a filter from a tcpdump-style expression
+    ///
+    /// # Arguments
+    /// * `expr` - Expression such as `tcp port 443 and not host 10.0.0.1`
+    ///
+    /// # Returns
+    /// A filter holding the parsed rule tree, or a `Configuration(ParseError)` naming the
+    /// offending token
+    pub fn from_bpf(expr: &str) -> CaptureResult<PacketFilter> {
+        let rule = bpf_expression::parse(expr)?;
+        Ok(PacketFilter {
+            compiled_expression: Some(bpf_expression::render(&rule)),
+            active: RulesetHandle::new(vec![rule.clone()]),
+            rules: vec![rule],
+            is_optimized: false,
+        })
+    }
+
+    /// Replaces the live rule set without stopping capture
+    ///
+    /// Matchers that already loaded the previous generation finish with it; every load after
+    /// this returns the new rules. Invalid rules are rejected before anything is published.
+    ///
+    /// # Arguments
+    /// * `new_rules` - Rules to make live
+    ///
+    /// # Returns
+    /// An error if a rule is invalid, in which case the previous rules stay live
+    pub fn swap_ruleset(&mut self, new_rules: Vec<FilterRule>) -> CaptureResult<()> {
+        new_rules.iter().try_for_each(check_rule)?;
+        self.publish(Some(new_rules), None);
+        Ok(())
+    }
+
+    /// Publishes rules and a compiled ruleset as the next generation, keeping whichever is None
+    ///
+    /// Every activation goes through here, so the filter's own rules and the generation
+    /// matchers load always change together.
+    fn publish(&mut self, rules: Option<Vec<FilterRule>>, ruleset: Option<Arc<CompiledRuleset>>) {
+        if let Some(rules) = &rules {
+            self.rules = rules.clone();
+            self.compiled_expression = Some(self.to_bpf());
+            self.is_optimized = false;
+        }
+        self.active.publish(rules, ruleset);
+    }
+
+    /// Gets the generation of the live rule set, incremented by every successful swap
+    pub fn active_generation(&self) -> u64 {
+        self.active.load().generation
+    }
+
+    /// Gets a handle matchers use to load the live rule set
+    pub fn ruleset_handle(&self) -> RulesetHandle {
+        self.active.clone()
+    }
+
+    /// Compiles a control-plane ruleset and makes it the one `evaluate` uses
+    ///
+    /// The compiled ruleset is published as a new generation, as `swap_ruleset` publishes
+    /// rules, so matchers holding a `RulesetHandle` switch to it atomically.
+    ///
+    /// # Arguments
+    /// * `ruleset` - Ruleset to activate
+    ///
+    /// # Returns
+    /// An error if the dry run finds problems that prevent activation
+    pub fn activate_ruleset(&mut self, ruleset: &FilterRuleset) -> CaptureResult<()> {
+        let report = ruleset::dry_run(ruleset, &OffloadCapabilities::none()).map_err(Box::new)?;
+        if let Some(error) = report.errors.first() {
+            return Err(CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ValidationFailed),
+                &format!(
+                    "Ruleset {} cannot be activated: {}",
+                    ruleset.id, error.message
+                ),
+            ));
+        }
+        self.publish(None, Some(Arc::new(CompiledRuleset::compile(ruleset))));
+        Ok(())
+    }
+
+    /// Finds the action for a packet under the active ruleset, counting per-rule matches
+    ///
+    /// Matchers on other threads do the same through `ruleset_handle`.
+    ///
+    /// # Arguments
+    /// * `packet` - Packet fields
+    /// * `at` - Capture time of the packet, recorded as the matching rule's last match
+    ///
+    /// # Returns
+    /// The action of the first matching rule, the ruleset's default action, or None if no
+    /// ruleset is active
+    pub fn evaluate(&self, packet: &PacketFields, at: SystemTime) -> Option<FilterAction> {
+        self.active.evaluate(packet, at)
+    }
+
+    /// Finds the action for a packet under the active ruleset, deciding flow sampling
+    ///
+    /// # Arguments
+    /// * `packet` - Packet fields
+    /// * `at` - Capture time of the packet
+    ///
+    /// # Returns
+    /// The action to apply, as `RulesetHandle::evaluate_flow` decides it
+    pub fn evaluate_flow(&self, packet: &PacketFields, at: SystemTime) -> Option<FilterAction> {
+        self.active.evaluate_flow(packet, at)
+    }
+
+    /// Gets how often each rule of the active ruleset was evaluated and matched
+    ///
+    /// # Returns
+    /// Counts keyed by rule id, including rules that never fired; empty without a ruleset
+    pub fn rule_stats(&self) -> HashMap<RuleId, RuleMatchStats> {
+        self.active
+            .load()
+            .ruleset
+            .as_ref()
+            .map(|ruleset| ruleset.rule_stats())
+            .unwrap_or_default()
+    }
+
+    /// Zeroes the per-rule counts of the active ruleset
+    pub fn reset_stats(&self) {
+        if let Some(ruleset) = &self.active.load().ruleset {
+            ruleset.reset_stats();
+        }
+    }
+
+    /// Renders the filter's rules as a tcpdump-style expression, for confirming what was parsed
+    ///
+    /// # Returns
+    /// The rules joined with `and`, or an empty string for a filter without rules
+    pub fn to_bpf(&self) -> String {
+        let rule = self
+            .rules
+            .iter()
+            .cloned()
+            .reduce(|combined, rule| FilterRule::And(Box::new(combined), Box::new(rule)));
+        rule.map(|rule| bpf_expression::render(&rule))
+            .unwrap_or_default()
+    }
+
+    /// Gets the filter's rules
+    pub fn rules(&self) -> &[FilterRule] {
+        &self.rules
+    }
+
+    /// Dry-runs a ruleset before activation
+    ///
+    /// Compiles the ruleset, lints it, and checks which rules fit the hardware offload, without
+    /// touching the running ruleset. The control plane calls this before pushing a ruleset live
+    /// and only activates it if the report is valid.
+    ///
+    /// # Arguments
+    /// * `ruleset` - Ruleset to check
+    /// * `hardware` - Offload capabilities of the capture hardware
+    ///
+    /// # Returns
+    /// The dry-run report, or an error if the ruleset cannot be checked
+    pub fn validate_and_compile(
+        ruleset: &FilterRuleset,
+        hardware: &OffloadCapabilities,
+    ) -> Result<RulesetReport, CaptureError> {
+        ruleset::dry_run(ruleset, hardware)
+    }
+}
+
+/// Rejects rules whose bounds can never describe a packet
+fn check_rule(rule: &FilterRule) -> CaptureResult<()> {
+    let invalid = |message: &str| {
+        Err(CaptureError::new(
+            CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+            message,
+        ))
+    };
+    match rule {
+        FilterRule::Net {
+            addr, prefix_len, ..
+        } => {
+            let max = if addr.is_ipv4() { 32 } else { 128 };
+            if *prefix_len > max {
+                return invalid(&format!("Prefix length {} exceeds {}", prefix_len, max));
+            }
+            Ok(())
+        }
+        FilterRule::PortRange { start, end, .. } if start > end => {
+            invalid(&format!("Port range {}-{} is inverted", start, end))
+        }
+        FilterRule::And(left, right) | FilterRule::Or(left, right) => {
+            check_rule(left)?;
+            check_rule(right)
+        }
+        FilterRule::Not(inner) => check_rule(inner),
+        _ => Ok(()),
+    }
 }
 
 impl FilterRule {
@@ -78,6 +428,14 @@ impl FilterRule {
     pub fn validate(&self) -> Result<(), CaptureError> {
         unimplemented!()
     }
+
+    /// Dry-runs a ruleset before activation, as `PacketFilter::validate_and_compile` does
+    pub fn validate_and_compile(
+        ruleset: &FilterRuleset,
+        hardware: &OffloadCapabilities,
+    ) -> Result<RulesetReport, CaptureError> {
+        PacketFilter::validate_and_compile(ruleset, hardware)
+    }
 }
 
 #[derive(Default)]
@@ -126,3 +484,319 @@ impl FilterBuilder {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::{CaptureErrorKind, ConfigErrorKind};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_from_bpf_round_trips() {
+        let filter = PacketFilter::from_bpf("tcp port 443 and not host 10.0.0.1").unwrap();
+        assert_eq!(filter.rules().len(), 1);
+        assert_eq!(filter.to_bpf(), "tcp and port 443 and not host 10.0.0.1");
+        assert_eq!(
+            PacketFilter::from_bpf(&filter.to_bpf()).unwrap().rules(),
+            filter.rules()
+        );
+    }
+
+    #[test]
+    fn test_from_bpf_rejects_invalid_expression() {
+        let err = PacketFilter::from_bpf("tcp port 443 andd udp").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::ParseError)
+        ));
+        assert!(err.to_string().contains("'andd'"));
+    }
+
+    #[test]
+    fn test_swap_ruleset_publishes_new_generation() {
+        let mut filter = PacketFilter::from_bpf("port 53").unwrap();
+        let handle = filter.ruleset_handle();
+        let before = handle.load();
+        assert_eq!(filter.active_generation(), 0);
+
+        filter.swap_ruleset(vec![FilterRule::Port(443)]).unwrap();
+        assert_eq!(filter.active_generation(), 1);
+        assert_eq!(handle.load().rules(), &[FilterRule::Port(443)]);
+        assert_eq!(filter.to_bpf(), "port 443");
+        // A generation loaded before the swap is unaffected
+        assert_eq!(before.generation(), 0);
+        assert_eq!(before.rules().len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_swap_keeps_previous_rules() {
+        let mut filter = PacketFilter::from_bpf("port 53").unwrap();
+        let inverted = FilterRule::Not(Box::new(FilterRule::PortRange {
+            direction: FilterDirection::Any,
+            start: 100,
+            end: 10,
+        }));
+        let err = filter.swap_ruleset(vec![inverted]).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue)
+        ));
+        assert_eq!(filter.active_generation(), 0);
+        assert_eq!(filter.to_bpf(), "port 53");
+    }
+
+    #[test]
+    fn test_concurrent_matchers_during_swaps() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        // Generation n holds n + 1 rules all matching port n, so a torn read would show up as
+        // a mismatched length or port
+        let rules = |generation: u16| vec![FilterRule::Port(generation); generation as usize + 1];
+        let mut filter = PacketFilter::from_bpf("port 0").unwrap();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let matchers: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = filter.ruleset_handle();
+                let running = Arc::clone(&running);
+                thread::spawn(move || {
+                    let mut last = 0;
+                    let mut loads = 0u64;
+                    while running.load(Ordering::Relaxed) || loads == 0 {
+                        let active = handle.load();
+                        assert!(active.generation() >= last);
+                        last = active.generation();
+                        assert_eq!(active.rules().len() as u64, last + 1);
+                        // Generation 0 holds the parsed `port 0`
+                        assert!(
+                            last == 0
+                                || active
+                                    .rules()
+                                    .iter()
+                                    .all(|rule| *rule == FilterRule::Port(last as u16))
+                        );
+                        loads += 1;
+                    }
+                    last
+                })
+            })
+            .collect();
+
+        for generation in 1..=200u16 {
+            filter.swap_ruleset(rules(generation)).unwrap();
+            assert_eq!(filter.active_generation(), generation as u64);
+        }
+        running.store(false, Ordering::Relaxed);
+        for matcher in matchers {
+            assert!(matcher.join().unwrap() <= 200);
+        }
+        assert_eq!(filter.ruleset_handle().load().generation(), 200);
+    }
+
+    #[test]
+    fn test_concurrent_evaluation_during_ruleset_swaps() {
+        use crate::capture_engine::control::traits::{FilterCondition, FilterRule as Rule};
+        use std::net::Ipv4Addr;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        // Odd generations drop port 22, even ones accept it
+        let ruleset = |generation: u64| FilterRuleset {
+            id: format!("v{}", generation),
+            rules: vec![Rule {
+                id: "ssh".to_string(),
+                priority: 1,
+                conditions: vec![FilterCondition::DestPort(22)],
+                action: if generation % 2 == 1 {
+                    FilterAction::Drop
+                } else {
+                    FilterAction::Accept
+                },
+            }],
+            default_action: FilterAction::Accept,
+        };
+        let packet = PacketFields {
+            src_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            dst_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            src_port: 50000,
+            dst_port: 22,
+            protocol: 6,
+        };
+        let mut filter = PacketFilter::from_bpf("ip").unwrap();
+        filter.activate_ruleset(&ruleset(1)).unwrap();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let evaluators: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = filter.ruleset_handle();
+                let running = Arc::clone(&running);
+                thread::spawn(move || {
+                    let mut evaluations = 0u64;
+                    while running.load(Ordering::Relaxed) || evaluations == 0 {
+                        assert!(handle.evaluate(&packet, UNIX_EPOCH).is_some());
+                        let active = handle.load();
+                        let action = active.ruleset().unwrap().evaluate(&packet, UNIX_EPOCH);
+                        assert_eq!(
+                            matches!(action, FilterAction::Drop),
+                            active.generation() % 2 == 1
+                        );
+                        evaluations += 1;
+                    }
+                    evaluations
+                })
+            })
+            .collect();
+
+        for generation in 2..=200 {
+            filter.activate_ruleset(&ruleset(generation)).unwrap();
+            assert_eq!(filter.active_generation(), generation);
+        }
+        running.store(false, Ordering::Relaxed);
+        let evaluations: u64 = evaluators.into_iter().map(|t| t.join().unwrap()).sum();
+        assert!(evaluations >= 4);
+        assert!(matches!(
+            filter.evaluate(&packet, UNIX_EPOCH),
+            Some(FilterAction::Accept)
+        ));
+    }
+
+    #[test]
+    fn test_rule_swaps_and_ruleset_activations_share_generations() {
+        let mut filter = PacketFilter::from_bpf("ip").unwrap();
+        filter
+            .activate_ruleset(&FilterRuleset {
+                id: "v1".to_string(),
+                rules: Vec::new(),
+                default_action: FilterAction::Drop,
+            })
+            .unwrap();
+        filter.swap_ruleset(vec![FilterRule::Port(53)]).unwrap();
+
+        let active = filter.ruleset_handle().load();
+        assert_eq!(active.generation(), 2);
+        assert_eq!(active.rules(), filter.rules());
+        assert_eq!(filter.to_bpf(), "port 53");
+        assert!(active.ruleset().is_some());
+    }
+
+    #[test]
+    fn test_rule_stats_include_dead_rules() {
+        use crate::capture_engine::control::traits::{FilterCondition, FilterRule as Rule};
+        use std::net::Ipv4Addr;
+
+        let mut filter = PacketFilter::from_bpf("ip").unwrap();
+        assert!(filter.rule_stats().is_empty());
+        assert!(filter
+            .evaluate(
+                &PacketFields {
+                    src_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    dst_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    src_port: 0,
+                    dst_port: 0,
+                    protocol: 1,
+                },
+                UNIX_EPOCH,
+            )
+            .is_none());
+
+        let rule = |id: &str, port: u16| Rule {
+            id: id.to_string(),
+            priority: 1,
+            conditions: vec![FilterCondition::DestPort(port)],
+            action: FilterAction::Drop,
+        };
+        filter
+            .activate_ruleset(&FilterRuleset {
+                id: "v1".to_string(),
+                rules: vec![rule("telnet", 23), rule("ssh", 22)],
+                default_action: FilterAction::Accept,
+            })
+            .unwrap();
+
+        let packet = PacketFields {
+            src_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            dst_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            src_port: 50000,
+            dst_port: 22,
+            protocol: 6,
+        };
+        let captured = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert!(matches!(
+            filter.evaluate(&packet, captured),
+            Some(FilterAction::Drop)
+        ));
+
+        let stats = filter.rule_stats();
+        assert_eq!(stats["ssh"].matched, 1);
+        assert_eq!(stats["ssh"].last_match, Some(captured));
+        assert_eq!(stats["telnet"].evaluated, 1);
+        assert_eq!(stats["telnet"].matched, 0);
+
+        filter.reset_stats();
+        assert_eq!(filter.rule_stats()["ssh"], RuleMatchStats::default());
+    }
+
+    #[test]
+    fn test_invalid_ruleset_not_activated() {
+        let mut filter = PacketFilter::from_bpf("ip").unwrap();
+        let err = filter
+            .activate_ruleset(&FilterRuleset {
+                id: "v1".to_string(),
+                rules: Vec::new(),
+                default_action: FilterAction::Sample { rate: 2.0 },
+            })
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::ValidationFailed)
+        ));
+        assert!(filter.rule_stats().is_empty());
+    }
+
+    #[test]
+    fn test_flow_sampling_keeps_whole_flows() {
+        use std::net::Ipv4Addr;
+
+        let mut filter = PacketFilter::from_bpf("ip").unwrap();
+        let ruleset = |rate| FilterRuleset {
+            id: "sampled".to_string(),
+            rules: vec![],
+            default_action: FilterAction::SampleFlow { rate },
+        };
+        assert!(filter.activate_ruleset(&ruleset(0.0)).is_err());
+        filter.activate_ruleset(&ruleset(0.5)).unwrap();
+
+        let mut kept = 0;
+        for port in 1000..1200u16 {
+            let forward = PacketFields {
+                src_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                dst_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+                src_port: port,
+                dst_port: 443,
+                protocol: 6,
+            };
+            let reverse = PacketFields {
+                src_ip: forward.dst_ip,
+                dst_ip: forward.src_ip,
+                src_port: forward.dst_port,
+                dst_port: forward.src_port,
+                protocol: 6,
+            };
+            let decision = filter.evaluate_flow(&forward, UNIX_EPOCH).unwrap();
+            assert!(matches!(
+                decision,
+                FilterAction::Accept | FilterAction::Drop
+            ));
+            assert_eq!(
+                matches!(
+                    filter.evaluate_flow(&reverse, UNIX_EPOCH),
+                    Some(FilterAction::Accept)
+                ),
+                matches!(decision, FilterAction::Accept)
+            );
+            kept += matches!(decision, FilterAction::Accept) as usize;
+        }
+        assert!((60..=140).contains(&kept));
+    }
+}
@@ -0,0 +1,331 @@
+// capture-engine/src/capture/packet_layer.rs
+use std::sync::Arc;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::traits::Packet;
+
+/// Decision a layer makes about a packet
+///
+/// # Variants
+/// * `Continue` - Pass the packet to the next layer
+/// * `Drop` - Stop processing and discard the packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerAction {
+    Continue,
+    Drop { reason: String },
+}
+
+/// Custom processing step inserted between ingestion and output
+///
+/// A layer may inspect the packet, modify its metadata, or drop it. Layers run synchronously on
+/// the packet path, so expensive work belongs in a later stage.
+pub trait PacketLayer: Send + Sync {
+    /// Gets the layer name, used to attribute drops
+    fn name(&self) -> &str;
+
+    /// Processes a packet
+    ///
+    /// # Arguments
+    /// * `packet` - Packet to process, modifiable in place
+    ///
+    /// # Returns
+    /// Whether to continue or drop the packet
+    fn process(&self, packet: &mut Packet<'_>) -> Result<LayerAction, CaptureError>;
+}
+
+/// Result of running a packet through a layer stack
+///
+/// # Variants
+/// * `Forwarded` - Every layer passed the packet on
+/// * `Dropped` - A layer dropped the packet; later layers did not run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerOutcome {
+    Forwarded,
+    Dropped { layer: String, reason: String },
+}
+
+/// Ordered composition of packet layers
+///
+/// Layers run in registration order, the first registered closest to ingestion. The order is
+/// fixed once packets start flowing, so every packet sees the same sequence of layers.
+///
+/// # Fields
+/// * `layers` - Registered layers in application order
+#[derive(Clone, Default)]
+pub struct LayerStack {
+    layers: Vec<Arc<dyn PacketLayer>>,
+}
+
+impl std::fmt::Debug for LayerStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LayerStack")
+            .field("layers", &self.names())
+            .finish()
+    }
+}
+
+impl LayerStack {
+    /// Creates an empty layer stack
+    ///
+    /// # Returns
+    /// A new LayerStack instance that forwards every packet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a layer, returning the stack for chaining
+    ///
+    /// # Arguments
+    /// * `layer` - Layer to run after those already registered
+    ///
+    /// # Returns
+    /// The updated LayerStack, or an error if a layer with the same name is registered
+    pub fn with_layer(mut self, layer: Arc<dyn PacketLayer>) -> Result<Self, CaptureError> {
+        self.push(layer)?;
+        Ok(self)
+    }
+
+    /// Appends a layer
+    ///
+    /// # Arguments
+    /// * `layer` - Layer to run after those already registered
+    ///
+    /// # Returns
+    /// An error if a layer with the same name is registered, since drops are attributed by name
+    pub fn push(&mut self, layer: Arc<dyn PacketLayer>) -> Result<(), CaptureError> {
+        if self.layers.iter().any(|l| l.name() == layer.name()) {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ValidationFailed),
+                &format!("Packet layer {} is already registered", layer.name()),
+            ));
+        }
+        self.layers.push(layer);
+        Ok(())
+    }
+
+    /// Gets the layer names in application order
+    pub fn names(&self) -> Vec<&str> {
+        self.layers.iter().map(|l| l.name()).collect()
+    }
+
+    /// Gets the number of registered layers
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Checks whether no layers are registered
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Runs a packet through the layers in order
+    ///
+    /// # Arguments
+    /// * `packet` - Packet to process
+    ///
+    /// # Returns
+    /// Whether the packet was forwarded or which layer dropped it
+    pub fn apply(&self, packet: &mut Packet<'_>) -> Result<LayerOutcome, CaptureError> {
+        for layer in &self.layers {
+            if let LayerAction::Drop { reason } = layer.process(packet)? {
+                return Ok(LayerOutcome::Dropped {
+                    layer: layer.name().to_string(),
+                    reason,
+                });
+            }
+        }
+        Ok(LayerOutcome::Forwarded)
+    }
+
+    /// Runs a batch through the layers, keeping forwarded packets in their original order
+    ///
+    /// # Arguments
+    /// * `packets` - Packets to process
+    ///
+    /// # Returns
+    /// The forwarded packets and, for each dropped packet, the layer and reason
+    pub fn apply_batch<'a>(
+        &self,
+        packets: Vec<Packet<'a>>,
+    ) -> Result<(Vec<Packet<'a>>, Vec<LayerOutcome>), CaptureError> {
+        let mut forwarded = Vec::with_capacity(packets.len());
+        let mut dropped = Vec::new();
+        for mut packet in packets {
+            match self.apply(&mut packet)? {
+                LayerOutcome::Forwarded => forwarded.push(packet),
+                outcome => dropped.push(outcome),
+            }
+        }
+        Ok((forwarded, dropped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{BufferId, PacketMetadata};
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+
+    /// Tags every packet and records the order layers ran in
+    struct TagLayer {
+        trace: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl PacketLayer for TagLayer {
+        fn name(&self) -> &str {
+            "tag"
+        }
+
+        fn process(&self, packet: &mut Packet<'_>) -> Result<LayerAction, CaptureError> {
+            self.trace.lock().push(format!("tag:{}", packet.timestamp));
+            packet
+                .metadata
+                .additional_info
+                .insert("tenant".to_string(), "blue".to_string());
+            Ok(LayerAction::Continue)
+        }
+    }
+
+    /// Drops oversized packets, but only ones the tag layer has already seen
+    struct SizeLimitLayer {
+        max_len: usize,
+        trace: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl PacketLayer for SizeLimitLayer {
+        fn name(&self) -> &str {
+            "size_limit"
+        }
+
+        fn process(&self, packet: &mut Packet<'_>) -> Result<LayerAction, CaptureError> {
+            self.trace.lock().push(format!("size:{}", packet.timestamp));
+            assert_eq!(
+                packet
+                    .metadata
+                    .additional_info
+                    .get("tenant")
+                    .map(String::as_str),
+                Some("blue"),
+                "tag layer must run first"
+            );
+            if packet.data.len() > self.max_len {
+                return Ok(LayerAction::Drop {
+                    reason: format!("{} bytes exceeds {}", packet.data.len(), self.max_len),
+                });
+            }
+            Ok(LayerAction::Continue)
+        }
+    }
+
+    /// Records packets that reach it; never runs for dropped packets
+    struct SinkLayer {
+        trace: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl PacketLayer for SinkLayer {
+        fn name(&self) -> &str {
+            "sink"
+        }
+
+        fn process(&self, packet: &mut Packet<'_>) -> Result<LayerAction, CaptureError> {
+            self.trace.lock().push(format!("sink:{}", packet.timestamp));
+            Ok(LayerAction::Continue)
+        }
+    }
+
+    fn packet(data: &[u8], timestamp: u64) -> Packet<'_> {
+        Packet {
+            timestamp,
+            data,
+            length: data.len(),
+            metadata: PacketMetadata {
+                compact_data: 0,
+                additional_info: HashMap::new(),
+            },
+            buffer_id: BufferId::new(timestamp),
+        }
+    }
+
+    fn stack(trace: &Arc<Mutex<Vec<String>>>) -> LayerStack {
+        LayerStack::new()
+            .with_layer(Arc::new(TagLayer {
+                trace: trace.clone(),
+            }))
+            .unwrap()
+            .with_layer(Arc::new(SizeLimitLayer {
+                max_len: 100,
+                trace: trace.clone(),
+            }))
+            .unwrap()
+            .with_layer(Arc::new(SinkLayer {
+                trace: trace.clone(),
+            }))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_layers_apply_in_registration_order() {
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let stack = stack(&trace);
+        assert_eq!(stack.names(), vec!["tag", "size_limit", "sink"]);
+
+        let data = [0u8; 64];
+        let mut small = packet(&data, 1);
+        assert_eq!(stack.apply(&mut small).unwrap(), LayerOutcome::Forwarded);
+        assert_eq!(
+            small.metadata.additional_info.get("tenant"),
+            Some(&"blue".to_string())
+        );
+        assert_eq!(*trace.lock(), vec!["tag:1", "size:1", "sink:1"]);
+    }
+
+    #[test]
+    fn test_drop_short_circuits_later_layers() {
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let stack = stack(&trace);
+        let small = [0u8; 64];
+        let large = [0u8; 1500];
+
+        let (forwarded, dropped) = stack
+            .apply_batch(vec![
+                packet(&small, 1),
+                packet(&large, 2),
+                packet(&small, 3),
+            ])
+            .unwrap();
+
+        let timestamps: Vec<u64> = forwarded.iter().map(|p| p.timestamp).collect();
+        assert_eq!(timestamps, vec![1, 3]);
+        assert_eq!(
+            dropped,
+            vec![LayerOutcome::Dropped {
+                layer: "size_limit".to_string(),
+                reason: "1500 bytes exceeds 100".to_string(),
+            }]
+        );
+        assert_eq!(
+            *trace.lock(),
+            vec!["tag:1", "size:1", "sink:1", "tag:2", "size:2", "tag:3", "size:3", "sink:3"]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_layer_names_are_rejected() {
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let result = stack(&trace).with_layer(Arc::new(SinkLayer { trace }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_empty_stack_forwards() {
+        let data = [0u8; 8];
+        let mut p = packet(&data, 1);
+        assert_eq!(
+            LayerStack::new().apply(&mut p).unwrap(),
+            LayerOutcome::Forwarded
+        );
+    }
+}
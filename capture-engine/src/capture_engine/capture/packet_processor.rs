@@ -2,12 +2,22 @@
 #![allow(unused)]
 #![allow(unused_variables)]
 // capture-engine/src/capture/capture_config.rs
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::SystemTime;
 
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
 use crate::capture_engine::capture::buffer_manager::Buffer;
-use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ResourceErrorKind,
+};
+use crate::capture_engine::capture::capture_statistics::{FlowStatsTable, FlowTuple};
 use crate::capture_engine::capture::packet_filter::PacketFilter;
+use crate::capture_engine::filter::ruleset::PacketFields;
+use crate::capture_engine::protocol::checksum;
+use crate::capture_engine::protocol::flow::{IPPROTO_TCP, IPPROTO_UDP};
 
 pub struct PacketMetadata {
     timestamp: SystemTime,
@@ -60,17 +70,45 @@ pub struct TransportHeader {
     protocol: Protocol,
 }
 
+/// Default smallest batch that `process_batch` spreads across the worker pool
+pub const DEFAULT_MIN_BATCH_FOR_PARALLEL: usize = 1024;
+
 #[derive(Debug)]
 pub struct PacketProcessor {
     filter: Option<PacketFilter>,
     truncate_length: Option<usize>,
     decode_protocols: bool,
     store_raw: bool,
+    flow_stats: Option<Arc<FlowStatsTable>>,
+    stats: ProcessorStats,
+    workers: Option<Arc<ThreadPool>>,
+    min_batch_for_parallel: usize,
+}
+
+/// Counters kept by a packet processor
+#[derive(Debug, Default)]
+pub struct ProcessorStats {
+    /// Packets whose IPv4, TCP or UDP checksum did not verify
+    pub checksum_errors: AtomicU64,
 }
 
 impl PacketProcessor {
     pub fn new() -> Self {
-        unimplemented!()
+        Self {
+            filter: None,
+            truncate_length: None,
+            decode_protocols: false,
+            store_raw: false,
+            flow_stats: None,
+            stats: ProcessorStats::default(),
+            workers: None,
+            min_batch_for_parallel: DEFAULT_MIN_BATCH_FOR_PARALLEL,
+        }
+    }
+
+    /// Counters for packets handled by this processor
+    pub fn stats(&self) -> &ProcessorStats {
+        &self.stats
     }
 
     pub fn process_packet(&self, buffer: Arc<Buffer>) -> Result<ProcessedPacket, CaptureError> {
@@ -96,11 +134,169 @@ impl PacketProcessor {
     pub fn enable_raw_storage(&mut self, enable: bool) {
         unimplemented!()
     }
+
+    /// Sets how many worker threads `process_batch` shards large batches across
+    ///
+    /// # Arguments
+    /// * `workers` - Worker thread count; 0 or 1 processes every batch on the calling thread
+    ///
+    /// # Returns
+    /// An error if the worker pool could not be started
+    pub fn set_parallelism(&mut self, workers: usize) -> Result<(), CaptureError> {
+        if workers <= 1 {
+            self.workers = None;
+            return Ok(());
+        }
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(workers)
+            .thread_name(|index| format!("packet-processor-{}", index))
+            .build()
+            .map_err(|e| {
+                CaptureError::new(
+                    CaptureErrorKind::Resource(ResourceErrorKind::AllocationFailed),
+                    "Failed to start packet processing workers",
+                )
+                .with_source(e)
+            })?;
+        self.workers = Some(Arc::new(pool));
+        Ok(())
+    }
+
+    /// Worker threads used for large batches, or 1 when batches are processed serially
+    pub fn parallelism(&self) -> usize {
+        self.workers
+            .as_ref()
+            .map_or(1, |pool| pool.current_num_threads())
+    }
+
+    /// Sets the smallest batch that is sharded across workers
+    ///
+    /// Smaller batches are processed on the calling thread, where the cost of handing work to
+    /// the pool would outweigh the gain.
+    pub fn set_min_batch_for_parallel(&mut self, min_batch: usize) {
+        self.min_batch_for_parallel = min_batch;
+    }
+
+    /// Applies `process` to every packet in a batch
+    ///
+    /// Batches of at least `min_batch_for_parallel` packets are split into one contiguous shard
+    /// per worker when parallelism is enabled. Results are returned in input order, and on
+    /// failure the error of the first failing packet is returned, as in the serial path. Shards
+    /// stop at their own first failure, but other shards may already have processed packets
+    /// after the failing one.
+    ///
+    /// # Arguments
+    /// * `packets` - Packets to process
+    /// * `process` - Per-packet processing, such as decoding or checksum verification
+    ///
+    /// # Returns
+    /// One result per packet, or the first error
+    pub fn process_batch<P, R, F>(&self, packets: &[P], process: F) -> Result<Vec<R>, CaptureError>
+    where
+        P: Sync,
+        R: Send,
+        F: Fn(&P) -> Result<R, CaptureError> + Sync,
+    {
+        let pool = match &self.workers {
+            Some(pool) if packets.len() >= self.min_batch_for_parallel.max(2) => pool,
+            _ => return packets.iter().map(&process).collect(),
+        };
+
+        let shard_len = packets.len().div_ceil(pool.current_num_threads());
+        let shards: Vec<Result<Vec<R>, CaptureError>> = pool.install(|| {
+            packets
+                .par_chunks(shard_len)
+                .map(|shard| shard.iter().map(&process).collect())
+                .collect()
+        });
+
+        let mut results = Vec::with_capacity(packets.len());
+        for shard in shards {
+            results.extend(shard?);
+        }
+        Ok(results)
+    }
+
+    /// Sets the flow table that processed packets are recorded against
+    pub fn set_flow_statistics(&mut self, flow_stats: Arc<FlowStatsTable>) {
+        self.flow_stats = Some(flow_stats);
+    }
+
+    /// Records an IP packet against its flow, if flow statistics are enabled
+    ///
+    /// # Arguments
+    /// * `packet` - Packet starting at the IP header
+    /// * `timestamp` - Packet timestamp
+    ///
+    /// # Returns
+    /// Whether the packet was recorded
+    pub fn record_flow(&self, packet: &[u8], timestamp: SystemTime) -> bool {
+        let Some(flow_stats) = &self.flow_stats else {
+            return false;
+        };
+        let Some(fields) = PacketFields::from_ip_packet(packet) else {
+            return false;
+        };
+        let tuple = FlowTuple {
+            src_ip: fields.src_ip,
+            dst_ip: fields.dst_ip,
+            src_port: fields.src_port,
+            dst_port: fields.dst_port,
+            protocol: fields.protocol,
+        };
+        flow_stats.record_packet(tuple, packet.len(), timestamp);
+        true
+    }
+
+    /// Verifies the IPv4 header checksum of a packet
+    ///
+    /// # Arguments
+    /// * `packet` - Packet starting at the IP header
+    ///
+    /// # Returns
+    /// Whether the checksum is correct, or None if the packet has no complete IPv4 header
+    pub fn verify_ipv4_checksum(&self, packet: &[u8]) -> Option<bool> {
+        self.count_checksum(checksum::verify_ipv4_header(packet))
+    }
+
+    /// Verifies the TCP checksum of an IPv4 or IPv6 packet, including its pseudo-header
+    ///
+    /// # Arguments
+    /// * `packet` - Packet starting at the IP header
+    ///
+    /// # Returns
+    /// Whether the checksum is correct, or None if the packet is not a complete, unfragmented
+    /// TCP segment
+    pub fn verify_tcp_checksum(&self, packet: &[u8]) -> Option<bool> {
+        self.count_checksum(checksum::verify_transport(packet, IPPROTO_TCP))
+    }
+
+    /// Verifies the UDP checksum of an IPv4 or IPv6 packet, including its pseudo-header
+    ///
+    /// A zero checksum is accepted over IPv4, where it means none was computed, and rejected
+    /// over IPv6.
+    ///
+    /// # Arguments
+    /// * `packet` - Packet starting at the IP header
+    ///
+    /// # Returns
+    /// Whether the checksum is correct, or None if the packet is not a complete, unfragmented
+    /// UDP datagram
+    pub fn verify_udp_checksum(&self, packet: &[u8]) -> Option<bool> {
+        self.count_checksum(checksum::verify_transport(packet, IPPROTO_UDP))
+    }
+
+    fn count_checksum(&self, verified: Option<bool>) -> Option<bool> {
+        if verified == Some(false) {
+            self.stats.checksum_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        verified
+    }
 }
 
 impl Default for PacketProcessor {
     fn default() -> Self {
-        unimplemented!()
+        Self::new()
     }
 }
 
@@ -173,3 +369,100 @@ pub trait PacketHandler {
 pub trait ProtocolDecoder {
     fn decode(&self, data: &[u8]) -> Result<Protocol, CaptureError>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::NetworkErrorKind;
+
+    #[test]
+    fn test_checksum_errors_are_counted() {
+        let processor = PacketProcessor::new();
+        // 192.168.0.1:12345 -> 192.168.0.199:53, payload "hello"
+        let mut packet = vec![
+            0x45, 0x00, 0x00, 0x21, 0x1c, 0x46, 0x40, 0x00, 0x40, 0x11, 0x9c, 0x6d, 0xc0, 0xa8,
+            0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7, 0x30, 0x39, 0x00, 0x35, 0x00, 0x0d, 0x09, 0x7b,
+            b'h', b'e', b'l', b'l', b'o',
+        ];
+        assert_eq!(processor.verify_ipv4_checksum(&packet), Some(true));
+        assert_eq!(processor.verify_udp_checksum(&packet), Some(true));
+        assert_eq!(processor.verify_tcp_checksum(&packet), None);
+        assert_eq!(processor.stats().checksum_errors.load(Ordering::Relaxed), 0);
+
+        packet[32] = b'O';
+        assert_eq!(processor.verify_udp_checksum(&packet), Some(false));
+        packet[8] = 0x3f;
+        assert_eq!(processor.verify_ipv4_checksum(&packet), Some(false));
+        assert_eq!(processor.stats().checksum_errors.load(Ordering::Relaxed), 2);
+    }
+
+    fn parallel_processor(workers: usize, min_batch: usize) -> PacketProcessor {
+        let mut processor = PacketProcessor::new();
+        processor.set_parallelism(workers).unwrap();
+        processor.set_min_batch_for_parallel(min_batch);
+        processor
+    }
+
+    fn reject_multiples_of(divisor: u32) -> impl Fn(&u32) -> Result<u32, CaptureError> + Sync {
+        move |packet: &u32| {
+            if packet.is_multiple_of(divisor) {
+                Err(*CaptureError::new(
+                    CaptureErrorKind::Network(NetworkErrorKind::CaptureFailure),
+                    &format!("bad packet {}", packet),
+                ))
+            } else {
+                Ok(packet * 2)
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_batch_matches_serial() {
+        let packets: Vec<u32> = (1..=10_000).collect();
+        let serial = PacketProcessor::new()
+            .process_batch(&packets, |packet| Ok(packet * 2))
+            .unwrap();
+        let parallel = parallel_processor(4, 100)
+            .process_batch(&packets, |packet| Ok(packet * 2))
+            .unwrap();
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel.len(), 10_000);
+    }
+
+    #[test]
+    fn test_parallel_batch_reports_first_failure_like_serial() {
+        // Failures at 2500, 5000 and 7500 land in different shards
+        let packets: Vec<u32> = (1..=10_000).collect();
+        let serial = PacketProcessor::new()
+            .process_batch(&packets, reject_multiples_of(2500))
+            .unwrap_err();
+        for _ in 0..10 {
+            let parallel = parallel_processor(8, 100)
+                .process_batch(&packets, reject_multiples_of(2500))
+                .unwrap_err();
+            assert_eq!(parallel.message(), serial.message());
+            assert!(matches!(
+                parallel.kind(),
+                CaptureErrorKind::Network(NetworkErrorKind::CaptureFailure)
+            ));
+        }
+        assert_eq!(serial.message(), "bad packet 2500");
+    }
+
+    #[test]
+    fn test_small_batches_stay_on_calling_thread() {
+        let processor = parallel_processor(4, 64);
+        assert_eq!(processor.parallelism(), 4);
+        let caller = std::thread::current().id();
+
+        let threads = processor
+            .process_batch(&[0u8; 63], |_| Ok(std::thread::current().id()))
+            .unwrap();
+        assert!(threads.iter().all(|thread| *thread == caller));
+
+        let threads = processor
+            .process_batch(&[0u8; 64], |_| Ok(std::thread::current().id()))
+            .unwrap();
+        assert!(threads.iter().all(|thread| *thread != caller));
+    }
+}
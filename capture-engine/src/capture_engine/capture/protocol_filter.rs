@@ -2,9 +2,11 @@
 #![allow(unused)]
 #![allow(unused_variables)]
 // capture-engine/src/capture/protocol_filter.rs
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::protocol::flow::{IPPROTO_SCTP, IPPROTO_TCP, IPPROTO_UDP};
+use crate::capture_engine::protocol::headers::{split_headers, HeaderLayer, ETHERTYPE_IPV4};
 
 /// Main protocol filter enum representing different network layers
 #[derive(Debug, Clone)]
@@ -110,7 +112,7 @@ pub enum ApplicationProtocol {
 }
 
 /// TCP flags for detailed TCP filtering
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TcpFlags {
     pub syn: bool,
     pub ack: bool,
@@ -154,6 +156,135 @@ pub enum TlsVersion {
     TLS1_3,
 }
 
+/// A header field read from a frame that may have been cut short by snaplen
+///
+/// # Variants
+/// * `Present` - The field was read from the frame
+/// * `Truncated` - The frame ended before the field could be read
+/// * `Absent` - The packet does not carry the field, e.g. ports on an ARP frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderField<T> {
+    Present(T),
+    Truncated,
+    #[default]
+    Absent,
+}
+
+impl<T> HeaderField<T> {
+    /// Returns the field value if it was read
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            HeaderField::Present(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Whether the frame ended before the field
+    pub fn is_truncated(&self) -> bool {
+        matches!(self, HeaderField::Truncated)
+    }
+
+    fn map<U>(self, f: impl FnOnce(T) -> U) -> HeaderField<U> {
+        match self {
+            HeaderField::Present(value) => HeaderField::Present(f(value)),
+            HeaderField::Truncated => HeaderField::Truncated,
+            HeaderField::Absent => HeaderField::Absent,
+        }
+    }
+}
+
+/// Header fields the protocol filter matches on, as read from a single frame
+///
+/// # Fields
+/// * `dst_mac`, `src_mac`, `ethertype` - Ethernet header; `ethertype` is the innermost one
+/// * `vlan_id` - Outermost VLAN tag, if any
+/// * `src_ip`, `dst_ip`, `ip_protocol` - Outer IPv4 or IPv6 header; for IPv6, `ip_protocol` is
+///   the next header after any extension headers
+/// * `src_port`, `dst_port` - TCP, UDP or SCTP ports; absent on non-first fragments
+/// * `tcp_flags` - TCP flags
+#[derive(Debug, Clone, Default)]
+pub struct HeaderFields {
+    pub dst_mac: HeaderField<[u8; 6]>,
+    pub src_mac: HeaderField<[u8; 6]>,
+    pub ethertype: HeaderField<u16>,
+    pub vlan_id: HeaderField<u16>,
+    pub src_ip: HeaderField<IpAddr>,
+    pub dst_ip: HeaderField<IpAddr>,
+    pub ip_protocol: HeaderField<u8>,
+    pub src_port: HeaderField<u16>,
+    pub dst_port: HeaderField<u16>,
+    pub tcp_flags: HeaderField<TcpFlags>,
+}
+
+impl HeaderFields {
+    /// Names of the fields the frame ended before
+    pub fn truncated_fields(&self) -> Vec<&'static str> {
+        [
+            ("dst_mac", self.dst_mac.is_truncated()),
+            ("src_mac", self.src_mac.is_truncated()),
+            ("ethertype", self.ethertype.is_truncated()),
+            ("vlan_id", self.vlan_id.is_truncated()),
+            ("src_ip", self.src_ip.is_truncated()),
+            ("dst_ip", self.dst_ip.is_truncated()),
+            ("ip_protocol", self.ip_protocol.is_truncated()),
+            ("src_port", self.src_port.is_truncated()),
+            ("dst_port", self.dst_port.is_truncated()),
+            ("tcp_flags", self.tcp_flags.is_truncated()),
+        ]
+        .into_iter()
+        .filter_map(|(name, truncated)| truncated.then_some(name))
+        .collect()
+    }
+
+    /// Whether any field could not be read because the frame was truncated
+    pub fn is_truncated(&self) -> bool {
+        !self.truncated_fields().is_empty()
+    }
+
+    /// Marks every network and transport field as truncated
+    fn truncate_from_l3(&mut self) {
+        self.src_ip = HeaderField::Truncated;
+        self.dst_ip = HeaderField::Truncated;
+        self.ip_protocol = HeaderField::Truncated;
+        self.truncate_from_l4();
+    }
+
+    /// Marks every transport field as truncated
+    fn truncate_from_l4(&mut self) {
+        self.src_port = HeaderField::Truncated;
+        self.dst_port = HeaderField::Truncated;
+        self.tcp_flags = HeaderField::Truncated;
+    }
+}
+
+/// Reads `N` bytes at `offset`, or marks the field truncated if the frame ends first
+fn read<const N: usize, T>(
+    frame: &[u8],
+    offset: usize,
+    f: impl FnOnce([u8; N]) -> T,
+) -> HeaderField<T> {
+    frame
+        .get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map_or(HeaderField::Truncated, |bytes| {
+            HeaderField::Present(f(bytes))
+        })
+}
+
+impl TcpFlags {
+    /// Decodes the flags byte of a TCP header
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            fin: bits & 0x01 != 0,
+            syn: bits & 0x02 != 0,
+            rst: bits & 0x04 != 0,
+            psh: bits & 0x08 != 0,
+            ack: bits & 0x10 != 0,
+            urg: bits & 0x20 != 0,
+        }
+    }
+}
+
 impl Default for ProtocolFilter {
     fn default() -> Self {
         unimplemented!()
@@ -176,6 +307,105 @@ impl ProtocolFilter {
         unimplemented!()
     }
 
+    /// Reads the header fields the filter matches on from an Ethernet frame
+    ///
+    /// Frames cut short by snaplen are not an error: fields past the end of the captured data
+    /// are marked `Truncated`, and so is every field of the layers behind them.
+    ///
+    /// # Arguments
+    /// * `frame` - Captured bytes, starting at the Ethernet header
+    ///
+    /// # Returns
+    /// The fields found in the frame
+    pub fn extract_fields(frame: &[u8]) -> HeaderFields {
+        let split = split_headers(frame);
+        let mut fields = HeaderFields {
+            dst_mac: read(frame, 0, |b: [u8; 6]| b),
+            src_mac: read(frame, 6, |b: [u8; 6]| b),
+            ..Default::default()
+        };
+        // Headers found by the walk, then the one the frame ended inside
+        let mut layers = split
+            .layers
+            .iter()
+            .copied()
+            .zip(split.offsets.iter().copied())
+            .chain(split.cut)
+            .skip(1)
+            .peekable();
+
+        let mut ethertype_at = 12;
+        while let Some(&(HeaderLayer::Vlan, offset)) = layers.peek() {
+            if fields.vlan_id == HeaderField::Absent {
+                fields.vlan_id = read(frame, offset, u16::from_be_bytes).map(|tci| tci & 0x0fff);
+            }
+            ethertype_at = offset + 2;
+            layers.next();
+        }
+        fields.ethertype = read(frame, ethertype_at, u16::from_be_bytes);
+        if fields.ethertype.is_truncated() {
+            fields.truncate_from_l3();
+            return fields;
+        }
+
+        let protocol_at = match layers.next() {
+            Some((HeaderLayer::Ipv4, offset)) => {
+                fields.src_ip = read(frame, offset + 12, |b: [u8; 4]| IpAddr::from(b));
+                fields.dst_ip = read(frame, offset + 16, |b: [u8; 4]| IpAddr::from(b));
+                let fragment_offset = read(frame, offset + 6, |b: [u8; 2]| {
+                    u16::from_be_bytes(b) & 0x1fff
+                });
+                if matches!(fragment_offset, HeaderField::Present(n) if n != 0) {
+                    // Only the first fragment carries the transport header
+                    fields.ip_protocol = read(frame, offset + 9, |b: [u8; 1]| b[0]);
+                    return fields;
+                }
+                offset + 9
+            }
+            Some((HeaderLayer::Ipv6, offset)) => {
+                fields.src_ip = read(frame, offset + 8, |b: [u8; 16]| IpAddr::from(b));
+                fields.dst_ip = read(frame, offset + 24, |b: [u8; 16]| IpAddr::from(b));
+                // Each extension header starts with the next header field
+                let mut protocol_at = offset + 6;
+                while let (Some(&(HeaderLayer::Ipv6, extension)), Some(0 | 43 | 44 | 51 | 60)) =
+                    (layers.peek(), frame.get(protocol_at))
+                {
+                    protocol_at = extension;
+                    layers.next();
+                }
+                protocol_at
+            }
+            // Not IP, or a malformed IP header
+            _ => return fields,
+        };
+        fields.ip_protocol = read(frame, protocol_at, |b: [u8; 1]| b[0]);
+
+        let transport = match layers.next() {
+            Some((HeaderLayer::Tcp | HeaderLayer::Udp | HeaderLayer::Sctp, offset)) => offset,
+            Some(_) => return fields,
+            // The frame ended inside the IP headers, so the transport header is cut off too
+            None if split.cut.is_some() => frame.len(),
+            None => return fields,
+        };
+        match fields.ip_protocol {
+            HeaderField::Present(IPPROTO_TCP | IPPROTO_UDP | IPPROTO_SCTP) => {
+                fields.src_port = read(frame, transport, u16::from_be_bytes);
+                fields.dst_port = read(frame, transport + 2, u16::from_be_bytes);
+            }
+            HeaderField::Present(_) | HeaderField::Absent => return fields,
+            HeaderField::Truncated => {
+                fields.truncate_from_l4();
+                return fields;
+            }
+        }
+        if fields.ip_protocol == HeaderField::Present(IPPROTO_TCP) {
+            fields.tcp_flags = read(frame, transport + 13, |b: [u8; 1]| {
+                TcpFlags::from_bits(b[0])
+            });
+        }
+        fields
+    }
+
     /// Optimizes the filter for better performance
     pub fn optimize(&mut self) -> Result<(), CaptureError> {
         unimplemented!()
@@ -233,6 +463,7 @@ impl ProtocolFilterBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::capture_engine::protocol::headers::{ETHERTYPE_IPV6, ETHERTYPE_VLAN};
 
     #[test]
     fn test_protocol_filter_builder() {
@@ -248,4 +479,155 @@ mod tests {
     fn test_protocol_filter_optimization() {
         // Add tests
     }
+
+    /// Ethernet + IPv4 + TCP SYN from 10.0.0.1:40000 to 10.0.0.2:443, 54 bytes
+    fn tcp_frame() -> Vec<u8> {
+        let mut frame = vec![0u8; 54];
+        frame[0..6].copy_from_slice(&[0x02, 0, 0, 0, 0, 0x02]);
+        frame[6..12].copy_from_slice(&[0x02, 0, 0, 0, 0, 0x01]);
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        frame[14] = 0x45;
+        frame[23] = IPPROTO_TCP;
+        frame[26..30].copy_from_slice(&[10, 0, 0, 1]);
+        frame[30..34].copy_from_slice(&[10, 0, 0, 2]);
+        frame[34..36].copy_from_slice(&40000u16.to_be_bytes());
+        frame[36..38].copy_from_slice(&443u16.to_be_bytes());
+        frame[46] = 0x50;
+        frame[47] = 0x02;
+        frame
+    }
+
+    #[test]
+    fn test_extract_fields_full_frame() {
+        let fields = ProtocolFilter::extract_fields(&tcp_frame());
+        assert!(!fields.is_truncated());
+        assert_eq!(fields.ethertype, HeaderField::Present(ETHERTYPE_IPV4));
+        assert_eq!(fields.vlan_id, HeaderField::Absent);
+        assert_eq!(
+            fields.dst_ip,
+            HeaderField::Present(IpAddr::from([10, 0, 0, 2]))
+        );
+        assert_eq!(fields.src_port, HeaderField::Present(40000));
+        assert_eq!(fields.dst_port, HeaderField::Present(443));
+        assert_eq!(
+            fields.tcp_flags,
+            HeaderField::Present(TcpFlags::from_bits(0x02))
+        );
+        assert!(fields.tcp_flags.value().unwrap().syn);
+    }
+
+    #[test]
+    fn test_snaplen_shorter_than_ethernet_header() {
+        let frame = tcp_frame();
+        let fields = ProtocolFilter::extract_fields(&frame[..10]);
+        assert_eq!(
+            fields.dst_mac,
+            HeaderField::Present([0x02, 0, 0, 0, 0, 0x02])
+        );
+        assert_eq!(
+            fields.truncated_fields(),
+            [
+                "src_mac",
+                "ethertype",
+                "src_ip",
+                "dst_ip",
+                "ip_protocol",
+                "src_port",
+                "dst_port",
+                "tcp_flags"
+            ]
+        );
+        assert!(ProtocolFilter::extract_fields(&[]).dst_mac.is_truncated());
+    }
+
+    #[test]
+    fn test_snaplen_at_transport_boundary() {
+        let frame = tcp_frame();
+
+        // Exactly the Ethernet and IPv4 headers: the TCP header starts at the cut
+        let fields = ProtocolFilter::extract_fields(&frame[..34]);
+        assert_eq!(
+            fields.src_ip,
+            HeaderField::Present(IpAddr::from([10, 0, 0, 1]))
+        );
+        assert_eq!(fields.ip_protocol, HeaderField::Present(IPPROTO_TCP));
+        assert_eq!(
+            fields.truncated_fields(),
+            ["src_port", "dst_port", "tcp_flags"]
+        );
+
+        // Ports fit but the flags byte does not
+        let fields = ProtocolFilter::extract_fields(&frame[..38]);
+        assert_eq!(fields.dst_port, HeaderField::Present(443));
+        assert_eq!(fields.truncated_fields(), ["tcp_flags"]);
+    }
+
+    #[test]
+    fn test_truncated_vlan_tag() {
+        let frame = tcp_frame();
+        let mut tagged = frame[..12].to_vec();
+        tagged.extend_from_slice(&ETHERTYPE_VLAN.to_be_bytes());
+        tagged.extend_from_slice(&0x2064u16.to_be_bytes());
+        tagged.extend_from_slice(&frame[12..]);
+
+        let fields = ProtocolFilter::extract_fields(&tagged);
+        assert_eq!(fields.vlan_id, HeaderField::Present(0x064));
+        assert_eq!(fields.dst_port, HeaderField::Present(443));
+
+        let fields = ProtocolFilter::extract_fields(&tagged[..15]);
+        assert!(fields.vlan_id.is_truncated());
+        assert!(fields.ethertype.is_truncated());
+    }
+
+    #[test]
+    fn test_short_ipv4_header_length_rejected() {
+        let mut frame = tcp_frame();
+        frame[14] = 0x44;
+        let fields = ProtocolFilter::extract_fields(&frame);
+        assert_eq!(fields.ethertype, HeaderField::Present(ETHERTYPE_IPV4));
+        assert_eq!(fields.src_ip, HeaderField::Absent);
+        assert_eq!(fields.src_port, HeaderField::Absent);
+        assert!(!fields.is_truncated());
+    }
+
+    #[test]
+    fn test_ipv6_extension_headers_followed_to_ports() {
+        let mut frame = tcp_frame()[..14].to_vec();
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV6.to_be_bytes());
+        let mut ipv6 = [0u8; 40];
+        ipv6[0] = 0x60;
+        ipv6[6] = 0; // hop-by-hop options
+        ipv6[8..24].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        frame.extend_from_slice(&ipv6);
+        frame.extend_from_slice(&[IPPROTO_UDP, 0, 0, 0, 0, 0, 0, 0]);
+        frame.extend_from_slice(&5353u16.to_be_bytes());
+        frame.extend_from_slice(&53u16.to_be_bytes());
+        frame.extend_from_slice(&[0u8; 4]);
+
+        let fields = ProtocolFilter::extract_fields(&frame);
+        assert_eq!(fields.ip_protocol, HeaderField::Present(IPPROTO_UDP));
+        assert_eq!(fields.src_port, HeaderField::Present(5353));
+        assert_eq!(fields.dst_port, HeaderField::Present(53));
+        assert_eq!(fields.tcp_flags, HeaderField::Absent);
+        assert!(matches!(fields.src_ip, HeaderField::Present(IpAddr::V6(_))));
+
+        // Cut inside the extension header: the transport fields are truncated
+        let fields = ProtocolFilter::extract_fields(&frame[..60]);
+        assert_eq!(fields.truncated_fields(), ["src_port", "dst_port"]);
+    }
+
+    #[test]
+    fn test_non_ip_and_fragments_have_no_ports() {
+        let mut arp = tcp_frame();
+        arp[12..14].copy_from_slice(&0x0806u16.to_be_bytes());
+        let fields = ProtocolFilter::extract_fields(&arp[..20]);
+        assert!(!fields.is_truncated());
+        assert_eq!(fields.src_ip, HeaderField::Absent);
+
+        let mut fragment = tcp_frame();
+        fragment[20..22].copy_from_slice(&0x00b9u16.to_be_bytes());
+        let fields = ProtocolFilter::extract_fields(&fragment[..34]);
+        assert!(!fields.is_truncated());
+        assert_eq!(fields.src_port, HeaderField::Absent);
+    }
 }
@@ -0,0 +1,393 @@
+// capture-engine/src/capture/recent_errors.rs
+/// Bounded in-memory ring of recent errors for diagnostics.
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, ErrorContext, ErrorSeverity,
+};
+
+/// Default number of errors kept by the ring
+pub const DEFAULT_RECENT_ERRORS_CAPACITY: usize = 256;
+
+/// A recorded error
+///
+/// # Fields
+/// * `sequence` - Position of the error in the order errors were recorded
+/// * `code` - Catalog code of the error kind, e.g. `CAP-RES-0002`
+/// * `message` - Description of the error
+/// * `severity` - Severity level of the error
+/// * `timestamp` - Time the error occurred
+/// * `context` - Full context the error was raised with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorRecord {
+    pub sequence: u64,
+    pub code: String,
+    pub message: String,
+    pub severity: ErrorSeverity,
+    pub timestamp: SystemTime,
+    pub context: ErrorContext,
+}
+
+/// Filter applied to recent errors
+///
+/// Unset criteria match every error.
+///
+/// # Fields
+/// * `severities` - Severities to include, empty for all
+/// * `code` - Exact error code, or a category such as `CAP-RES`
+/// * `component` - Component the error occurred in
+/// * `since` - Earliest error time, inclusive
+/// * `until` - Latest error time, inclusive
+/// * `limit` - Maximum number of errors returned
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorQuery {
+    pub severities: Vec<ErrorSeverity>,
+    pub code: Option<String>,
+    pub component: Option<String>,
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+    pub limit: Option<usize>,
+}
+
+impl ErrorQuery {
+    /// Creates a query matching every error
+    ///
+    /// # Returns
+    /// A new ErrorQuery instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Includes errors of a severity
+    ///
+    /// # Arguments
+    /// * `severity` - Severity to include
+    pub fn severity(mut self, severity: ErrorSeverity) -> Self {
+        self.severities.push(severity);
+        self
+    }
+
+    /// Restricts the query to an error code or category
+    ///
+    /// # Arguments
+    /// * `code` - Exact code, or category prefix without the trailing dash
+    pub fn code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    /// Restricts the query to a component
+    ///
+    /// # Arguments
+    /// * `component` - Component name
+    pub fn component(mut self, component: &str) -> Self {
+        self.component = Some(component.to_string());
+        self
+    }
+
+    /// Restricts the query to a time range
+    ///
+    /// # Arguments
+    /// * `since` - Earliest error time, inclusive
+    /// * `until` - Latest error time, inclusive
+    pub fn between(mut self, since: SystemTime, until: SystemTime) -> Self {
+        self.since = Some(since);
+        self.until = Some(until);
+        self
+    }
+
+    /// Limits the number of errors returned
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of errors
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Checks whether a recorded error matches the query
+    ///
+    /// # Arguments
+    /// * `record` - Recorded error
+    ///
+    /// # Returns
+    /// True if the error meets every set criterion
+    pub fn matches(&self, record: &ErrorRecord) -> bool {
+        if !self.severities.is_empty() && !self.severities.contains(&record.severity) {
+            return false;
+        }
+        if let Some(code) = &self.code {
+            let in_category = record
+                .code
+                .strip_prefix(code.as_str())
+                .is_some_and(|rest| rest.starts_with('-'));
+            if record.code != *code && !in_category {
+                return false;
+            }
+        }
+        if let Some(component) = &self.component {
+            if record.context.component() != Some(component.as_str()) {
+                return false;
+            }
+        }
+        if self.since.is_some_and(|since| record.timestamp < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| record.timestamp > until) {
+            return false;
+        }
+        true
+    }
+}
+
+/// Thread-safe ring of the most recent errors
+///
+/// Producers claim a slot with an atomic counter and never wait: if a reader is copying the
+/// claimed slot at that moment, the error is counted as skipped instead of blocking the
+/// producer. Once the ring is full each new error replaces the oldest one.
+///
+/// # Fields
+/// * `slots` - Ring storage, indexed by sequence modulo capacity
+/// * `next` - Sequence assigned to the next error
+/// * `skipped` - Errors not stored because their slot was busy
+#[derive(Debug)]
+pub struct RecentErrors {
+    slots: Box<[Mutex<Option<ErrorRecord>>]>,
+    next: AtomicU64,
+    skipped: AtomicU64,
+}
+
+impl RecentErrors {
+    /// Creates a ring holding up to `capacity` errors
+    ///
+    /// # Arguments
+    /// * `capacity` - Number of errors kept
+    ///
+    /// # Returns
+    /// A new RecentErrors ring, or an error if the capacity is zero
+    pub fn new(capacity: usize) -> Result<Self, CaptureError> {
+        if capacity == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Recent error ring capacity must be greater than zero",
+            ));
+        }
+        Ok(Self::with_capacity(capacity))
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity).map(|_| Mutex::new(None)).collect(),
+            next: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+        }
+    }
+
+    /// Gets the number of errors the ring can hold
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Gets the total number of errors recorded, including evicted ones
+    pub fn total_recorded(&self) -> u64 {
+        self.next.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of errors not stored because their slot was busy
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Records an error
+    ///
+    /// # Arguments
+    /// * `error` - Error to record
+    pub fn record(&self, error: &CaptureError) {
+        let sequence = self.next.fetch_add(1, Ordering::Relaxed);
+        let slot = &self.slots[(sequence % self.slots.len() as u64) as usize];
+        let Some(mut entry) = slot.try_lock() else {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        // A producer that stalled after claiming its sequence must not overwrite a newer error
+        if entry
+            .as_ref()
+            .is_some_and(|existing| existing.sequence > sequence)
+        {
+            return;
+        }
+        *entry = Some(ErrorRecord {
+            sequence,
+            code: error.code().to_string(),
+            message: error.message().to_string(),
+            severity: error.severity(),
+            timestamp: error.timestamp(),
+            context: error.context().clone(),
+        });
+    }
+
+    /// Queries the recorded errors
+    ///
+    /// # Arguments
+    /// * `filter` - Criteria errors must match
+    ///
+    /// # Returns
+    /// Matching errors, newest first
+    pub fn recent_errors(&self, filter: &ErrorQuery) -> Vec<ErrorRecord> {
+        let mut records: Vec<ErrorRecord> = self
+            .slots
+            .iter()
+            .filter_map(|slot| slot.lock().clone())
+            .filter(|record| filter.matches(record))
+            .collect();
+        records.sort_by_key(|record| Reverse(record.sequence));
+        if let Some(limit) = filter.limit {
+            records.truncate(limit);
+        }
+        records
+    }
+}
+
+impl Default for RecentErrors {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_RECENT_ERRORS_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::{
+        ErrorBuilder, NetworkErrorKind, ResourceErrorKind,
+    };
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn error(kind: CaptureErrorKind, severity: ErrorSeverity, component: &str) -> CaptureError {
+        ErrorBuilder::new()
+            .kind(kind)
+            .message("failure")
+            .severity(severity)
+            .component(component)
+            .build()
+            .unwrap()
+    }
+
+    fn quota(severity: ErrorSeverity) -> CaptureError {
+        error(
+            CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded),
+            severity,
+            "buffer_manager",
+        )
+    }
+
+    fn timeout() -> CaptureError {
+        error(
+            CaptureErrorKind::Network(NetworkErrorKind::Timeout),
+            ErrorSeverity::Warning,
+            "interface",
+        )
+    }
+
+    #[test]
+    fn test_recorded_errors_are_retrievable_newest_first() {
+        let ring = RecentErrors::new(8).unwrap();
+        ring.record(&quota(ErrorSeverity::Error));
+        ring.record(&timeout());
+
+        let records = ring.recent_errors(&ErrorQuery::new());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].code, "CAP-NET-0004");
+        assert_eq!(records[1].code, "CAP-RES-0002");
+        assert_eq!(records[1].context.component(), Some("buffer_manager"));
+        assert_eq!(records[1].message, "failure");
+    }
+
+    #[test]
+    fn test_filter_by_severity_code_and_component() {
+        let ring = RecentErrors::new(8).unwrap();
+        ring.record(&quota(ErrorSeverity::Critical));
+        ring.record(&quota(ErrorSeverity::Error));
+        ring.record(&timeout());
+
+        let critical = ring.recent_errors(&ErrorQuery::new().severity(ErrorSeverity::Critical));
+        assert_eq!(critical.len(), 1);
+        assert_eq!(critical[0].severity, ErrorSeverity::Critical);
+
+        let quota_errors = ring.recent_errors(&ErrorQuery::new().code("CAP-RES-0002"));
+        assert_eq!(quota_errors.len(), 2);
+        assert_eq!(
+            ring.recent_errors(&ErrorQuery::new().code("CAP-RES")).len(),
+            2
+        );
+        assert!(ring
+            .recent_errors(&ErrorQuery::new().code("CAP-R"))
+            .is_empty());
+
+        let network = ring.recent_errors(&ErrorQuery::new().component("interface"));
+        assert_eq!(network.len(), 1);
+        assert_eq!(network[0].code, "CAP-NET-0004");
+
+        let limited = ring.recent_errors(&ErrorQuery::new().limit(1));
+        assert_eq!(limited[0].code, "CAP-NET-0004");
+    }
+
+    #[test]
+    fn test_filter_by_time_range() {
+        let ring = RecentErrors::new(8).unwrap();
+        let err = quota(ErrorSeverity::Error);
+        ring.record(&err);
+        let at = err.timestamp();
+
+        let window = ErrorQuery::new().between(at, at + Duration::from_secs(1));
+        assert_eq!(ring.recent_errors(&window).len(), 1);
+        let later =
+            ErrorQuery::new().between(at + Duration::from_secs(1), at + Duration::from_secs(2));
+        assert!(ring.recent_errors(&later).is_empty());
+    }
+
+    #[test]
+    fn test_ring_evicts_oldest() {
+        let ring = RecentErrors::new(3).unwrap();
+        for _ in 0..5 {
+            ring.record(&quota(ErrorSeverity::Error));
+        }
+
+        let sequences: Vec<u64> = ring
+            .recent_errors(&ErrorQuery::new())
+            .iter()
+            .map(|record| record.sequence)
+            .collect();
+        assert_eq!(sequences, vec![4, 3, 2]);
+        assert_eq!(ring.total_recorded(), 5);
+        assert!(RecentErrors::new(0).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_producers() {
+        let ring = Arc::new(RecentErrors::new(64).unwrap());
+        let producers: Vec<_> = (0..4)
+            .map(|_| {
+                let ring = Arc::clone(&ring);
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        ring.record(&timeout());
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(ring.total_recorded(), 400);
+        let records = ring.recent_errors(&ErrorQuery::new());
+        assert!(!records.is_empty() && records.len() <= 64);
+        assert!(records.windows(2).all(|w| w[0].sequence > w[1].sequence));
+    }
+}
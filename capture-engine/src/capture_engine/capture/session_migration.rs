@@ -0,0 +1,387 @@
+// capture-engine/src/capture/session_migration.rs
+/// Moves a running capture session from one interface to another without stopping it.
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, RuntimeErrorKind,
+};
+use crate::capture_engine::capture::capture_session::{SessionAction, SessionState};
+use crate::capture_engine::interface::batch::CaptureBatchResult;
+use crate::capture_engine::interface::source::{PacketSource, SourceRunner};
+
+/// Default limit on batches drained from the old interface during a migration
+const DEFAULT_MAX_DRAIN_BATCHES: usize = 1024;
+
+/// Migration settings
+///
+/// # Fields
+/// * `max_drain_batches` - Batches drained from the old interface before it is detached; bounds
+///   the drain when the old interface keeps receiving traffic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationConfig {
+    pub max_drain_batches: usize,
+}
+
+impl Default for MigrationConfig {
+    fn default() -> Self {
+        Self {
+            max_drain_batches: DEFAULT_MAX_DRAIN_BATCHES,
+        }
+    }
+}
+
+/// Outcome of a completed migration
+///
+/// # Fields
+/// * `from` - Interface the session was capturing on
+/// * `to` - Interface the session now captures on
+/// * `drained_packets` - Buffered packets from the old interface delivered to the session
+/// * `drain_complete` - Whether the old interface's buffer was emptied before detaching
+/// * `drain_error` - Error that cut the drain short, if any
+/// * `detach_error` - Error raised while closing the old interface, if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from: String,
+    pub to: String,
+    pub drained_packets: u64,
+    pub drain_complete: bool,
+    pub drain_error: Option<String>,
+    pub detach_error: Option<String>,
+}
+
+/// A running session bound to the interface it captures from
+///
+/// Flow state lives in the pipeline behind the batch handler, which is the same for both
+/// interfaces, so a migration does not reset it.
+///
+/// # Fields
+/// * `session_id` - Session identifier
+/// * `state` - Current session state
+/// * `runner` - Runner for the interface the session captures on
+/// * `config` - Migration settings
+pub struct ActiveSession {
+    session_id: String,
+    state: SessionState,
+    runner: SourceRunner,
+    config: MigrationConfig,
+}
+
+impl ActiveSession {
+    /// Creates a session capturing from a runner
+    ///
+    /// # Arguments
+    /// * `session_id` - Session identifier
+    /// * `runner` - Runner for the session's interface
+    /// * `config` - Migration settings
+    ///
+    /// # Returns
+    /// A new ActiveSession in the `Created` state, or an error if the settings are invalid
+    pub fn new(
+        session_id: &str,
+        runner: SourceRunner,
+        config: MigrationConfig,
+    ) -> Result<Self, CaptureError> {
+        if config.max_drain_batches == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Migration drain limit must be greater than zero",
+            ));
+        }
+        Ok(Self {
+            session_id: session_id.to_string(),
+            state: SessionState::Created,
+            runner,
+            config,
+        })
+    }
+
+    /// Gets the session identifier
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Gets the current session state
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    /// Gets the name of the interface the session captures on
+    pub fn interface(&self) -> &str {
+        self.runner.source_name()
+    }
+
+    /// Opens the interface and starts capturing
+    pub fn start(&mut self) -> Result<(), CaptureError> {
+        if self.state != SessionState::Created {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                "Only a newly created session can be started",
+            ));
+        }
+        self.runner.open()?;
+        self.state = SessionState::Running;
+        Ok(())
+    }
+
+    /// Polls one batch from the session's interface
+    ///
+    /// # Arguments
+    /// * `handler` - Pipeline receiving the batch
+    ///
+    /// # Returns
+    /// The number of packets delivered
+    pub fn run_batch<F>(&mut self, handler: F) -> Result<usize, CaptureError>
+    where
+        F: FnMut(&CaptureBatchResult<'_>) -> Result<(), CaptureError>,
+    {
+        self.require_running()?;
+        self.runner.run_batch(handler)
+    }
+
+    /// Checks whether a session action can be applied
+    ///
+    /// Only `MigrateToInterface` is supported; it is executed by `migrate`, which also needs the
+    /// new interface's source.
+    ///
+    /// # Arguments
+    /// * `action` - Action to apply
+    pub fn check_action(&self, action: &SessionAction) -> Result<(), CaptureError> {
+        match action {
+            SessionAction::MigrateToInterface(target) if target == self.interface() => {
+                Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    "Session already captures on the target interface",
+                ))
+            }
+            SessionAction::MigrateToInterface(_) => self.require_running(),
+            _ => Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                "Action is not supported by an active session",
+            )),
+        }
+    }
+
+    /// Moves the session to a new interface while it keeps capturing
+    ///
+    /// The new interface is attached and opened first, so it starts buffering traffic. The old
+    /// interface's buffered packets are then drained into the handler, and only afterwards is
+    /// the new interface polled, so packets from the old interface precede those from the new
+    /// one. The session stays `Running` throughout. If the new interface cannot be opened the
+    /// session remains on the old one; once it is open, drain or detach failures on the old
+    /// interface are reported but the session still moves to the new one.
+    ///
+    /// # Arguments
+    /// * `source` - Source for the new interface
+    /// * `handler` - Pipeline receiving drained batches
+    ///
+    /// # Returns
+    /// A report of the migration, or an error if the session did not move
+    pub fn migrate<F>(
+        &mut self,
+        source: Box<dyn PacketSource>,
+        mut handler: F,
+    ) -> Result<MigrationReport, CaptureError>
+    where
+        F: FnMut(&CaptureBatchResult<'_>) -> Result<(), CaptureError>,
+    {
+        self.check_action(&SessionAction::MigrateToInterface(
+            source.name().to_string(),
+        ))?;
+
+        let mut next = SourceRunner::new(source, self.runner.batch_size())?;
+        next.set_snaplen(self.runner.snaplen())?;
+        next.open()?;
+
+        let mut report = MigrationReport {
+            from: self.interface().to_string(),
+            to: next.source_name().to_string(),
+            drained_packets: 0,
+            drain_complete: false,
+            drain_error: None,
+            detach_error: None,
+        };
+
+        for _ in 0..self.config.max_drain_batches {
+            match self.runner.run_batch(&mut handler) {
+                Ok(0) => {
+                    report.drain_complete = true;
+                    break;
+                }
+                Ok(delivered) => report.drained_packets += delivered as u64,
+                Err(error) => {
+                    report.drain_error = Some(error.to_string());
+                    break;
+                }
+            }
+        }
+
+        let mut previous = std::mem::replace(&mut self.runner, next);
+        if let Err(error) = previous.close() {
+            report.detach_error = Some(error.to_string());
+        }
+        Ok(report)
+    }
+
+    fn require_running(&self) -> Result<(), CaptureError> {
+        if self.state != SessionState::Running {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                "Session is not capturing",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::interface::injection::{InjectionHandle, InjectionSource};
+    use crate::capture_engine::interface::source::PacketSourceKind;
+    use crate::traits::Packet;
+    use std::collections::HashMap;
+
+    // Frames carry a flow id in byte 0 and a per-flow sequence number in byte 1
+    fn frame(flow: u8, seq: u8) -> Vec<u8> {
+        vec![flow, seq, 0, 0]
+    }
+
+    fn injection(name: &str) -> (Box<dyn PacketSource>, InjectionHandle) {
+        let source = InjectionSource::new(name);
+        let handle = source.handle();
+        (Box::new(source), handle)
+    }
+
+    fn session(name: &str) -> (ActiveSession, InjectionHandle) {
+        let (source, handle) = injection(name);
+        let runner = SourceRunner::new(source, 4).unwrap();
+        let mut session = ActiveSession::new("s-1", runner, MigrationConfig::default()).unwrap();
+        session.start().unwrap();
+        (session, handle)
+    }
+
+    fn record(
+        flows: &mut HashMap<u8, Vec<u8>>,
+    ) -> impl FnMut(&CaptureBatchResult<'_>) -> Result<(), CaptureError> + '_ {
+        |batch| {
+            for packet in &batch.packets {
+                flows
+                    .entry(packet.data[0])
+                    .or_default()
+                    .push(packet.data[1]);
+            }
+            Ok(())
+        }
+    }
+
+    /// Source whose open or poll fails, standing in for a broken interface
+    struct FailingSource {
+        fail_open: bool,
+    }
+
+    impl PacketSource for FailingSource {
+        fn name(&self) -> &str {
+            "eni-broken"
+        }
+
+        fn kind(&self) -> PacketSourceKind {
+            PacketSourceKind::Custom("failing".to_string())
+        }
+
+        fn open(&mut self) -> Result<(), CaptureError> {
+            if self.fail_open {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                    "attach failed",
+                ));
+            }
+            Ok(())
+        }
+
+        fn poll_batch(&mut self, _max: usize) -> Result<Vec<Packet<'_>>, CaptureError> {
+            Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                "interface detached",
+            ))
+        }
+
+        fn close(&mut self) -> Result<(), CaptureError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_migration_drains_old_interface_and_keeps_flow_order() {
+        let (mut session, old) = session("eni-old");
+        let mut flows = HashMap::new();
+
+        for seq in 0..3 {
+            old.inject(frame(1, seq), seq as u64);
+        }
+        session.run_batch(record(&mut flows)).unwrap();
+
+        // Traffic arrives on both interfaces while the migration is in progress
+        for seq in 3..10 {
+            old.inject(frame(1, seq), seq as u64);
+        }
+        old.inject(frame(2, 0), 20);
+        let (new_source, new) = injection("eni-new");
+        new.inject(frame(1, 10), 30);
+        new.inject(frame(2, 1), 31);
+
+        let report = session.migrate(new_source, record(&mut flows)).unwrap();
+        assert_eq!(session.state(), &SessionState::Running);
+        assert_eq!(session.interface(), "eni-new");
+        assert_eq!(report.from, "eni-old");
+        assert_eq!(report.drained_packets, 8);
+        assert!(report.drain_complete);
+        assert_eq!(old.pending(), 0);
+
+        session.run_batch(record(&mut flows)).unwrap();
+        assert_eq!(flows[&1], (0..=10).collect::<Vec<u8>>());
+        assert_eq!(flows[&2], vec![0, 1]);
+    }
+
+    #[test]
+    fn test_failed_attach_leaves_session_on_old_interface() {
+        let (mut session, old) = session("eni-old");
+        old.inject(frame(1, 0), 0);
+
+        let result = session.migrate(
+            Box::new(FailingSource { fail_open: true }),
+            |_: &CaptureBatchResult<'_>| Ok(()),
+        );
+        assert!(result.is_err());
+        assert_eq!(session.state(), &SessionState::Running);
+        assert_eq!(session.interface(), "eni-old");
+        assert_eq!(session.run_batch(|_| Ok(())).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_failed_drain_still_moves_to_new_interface() {
+        let runner = SourceRunner::new(Box::new(FailingSource { fail_open: false }), 4).unwrap();
+        let mut session = ActiveSession::new("s-2", runner, MigrationConfig::default()).unwrap();
+        session.start().unwrap();
+
+        let (new_source, new) = injection("eni-new");
+        new.inject(frame(1, 0), 0);
+        let report = session.migrate(new_source, |_| Ok(())).unwrap();
+
+        assert!(!report.drain_complete);
+        assert!(report.drain_error.is_some());
+        assert_eq!(session.interface(), "eni-new");
+        assert_eq!(session.state(), &SessionState::Running);
+        assert_eq!(session.run_batch(|_| Ok(())).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_migration_requires_running_session() {
+        let (source, _) = injection("eni-old");
+        let runner = SourceRunner::new(source, 4).unwrap();
+        let mut session = ActiveSession::new("s-3", runner, MigrationConfig::default()).unwrap();
+        let (new_source, _) = injection("eni-new");
+        assert!(session.migrate(new_source, |_| Ok(())).is_err());
+
+        session.start().unwrap();
+        let (same, _) = injection("eni-old");
+        assert!(session.migrate(same, |_| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,593 @@
+// capture-engine/src/capture/session_report.rs
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, ResourceErrorKind, SystemErrorKind,
+};
+use crate::capture_engine::capture::capture_session::{SessionState, SessionStats};
+use crate::capture_engine::capture::recent_errors::{ErrorQuery, ErrorRecord, RecentErrors};
+use crate::capture_engine::filter::adaptive_sampling::{AdaptiveSampleRate, SampleRateSummary};
+use crate::capture_engine::filter::stats::FilterStats;
+use crate::capture_engine::interface::batch::CaptureBatchResult;
+use crate::capture_engine::interface::topology::InterfaceTopology;
+
+/// Default number of top talkers included in a report
+const DEFAULT_TOP_TALKERS: usize = 10;
+/// Default number of recent errors included in a report
+const DEFAULT_REPORT_ERRORS: usize = 20;
+
+/// Where and how session reports are produced
+///
+/// # Fields
+/// * `output_dir` - Directory the JSON report is written to, None to only emit it as an event
+/// * `top_talkers` - Number of top talkers included in the report
+/// * `recent_errors` - Number of recent errors included in the report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionReportConfig {
+    pub output_dir: Option<PathBuf>,
+    pub top_talkers: usize,
+    pub recent_errors: usize,
+}
+
+impl Default for SessionReportConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: None,
+            top_talkers: DEFAULT_TOP_TALKERS,
+            recent_errors: DEFAULT_REPORT_ERRORS,
+        }
+    }
+}
+
+/// How the session ended
+///
+/// # Variants
+/// * `Completed` - The session stopped normally
+/// * `Failed` - The session stopped because of an error; report counts may be partial
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SessionOutcome {
+    Completed,
+    Failed { error: String },
+}
+
+/// Packet drops broken down by reason
+///
+/// # Fields
+/// * `resource` - Packets lost because the engine could not keep up
+/// * `kernel` - Packets dropped by the kernel or NIC before reaching the engine
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DropCounts {
+    pub resource: u64,
+    pub kernel: u64,
+}
+
+/// Packet counts for the session
+///
+/// # Fields
+/// * `total` - Packets evaluated by the filter
+/// * `captured` - Packets captured to output
+/// * `filtered` - Packets dropped deliberately by filter policy
+/// * `truncated` - Captured packets shorter than their original frame
+/// * `dropped` - Packets lost, by reason
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PacketCounts {
+    pub total: u64,
+    pub captured: u64,
+    pub filtered: u64,
+    pub truncated: u64,
+    pub dropped: DropCounts,
+}
+
+/// Traffic attributed to a single source address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TalkerStats {
+    pub address: IpAddr,
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+/// Bytes written to a single output destination
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestinationBytes {
+    pub destination_id: String,
+    pub bytes_written: u64,
+}
+
+/// Summary artifact produced when a capture session stops
+///
+/// # Fields
+/// * `session_id` - Session the report describes
+/// * `outcome` - Whether the session completed or failed
+/// * `started_at` - Session start in milliseconds since the Unix epoch, if it started
+/// * `stopped_at` - Session stop in milliseconds since the Unix epoch
+/// * `duration_ms` - Session duration in milliseconds, if it started
+/// * `packets` - Packet counts
+/// * `top_talkers` - Source addresses with the most bytes, largest first
+/// * `destinations` - Bytes written per output destination
+/// * `filter_ruleset_id` - Id of the filter ruleset in effect when the session stopped
+/// * `recent_errors` - Most recent errors at the time the session stopped, newest first
+/// * `sampling_seed` - Seed of the session's sampling decisions, for replay
+/// * `interface_topology` - NUMA node and IRQ affinity of each capture interface
+/// * `sample_rate` - Adaptive sampling rate at stop and its range, if adaptive sampling ran
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionReport {
+    pub session_id: String,
+    pub outcome: SessionOutcome,
+    pub started_at: Option<u64>,
+    pub stopped_at: u64,
+    pub duration_ms: Option<u64>,
+    pub packets: PacketCounts,
+    pub top_talkers: Vec<TalkerStats>,
+    pub destinations: Vec<DestinationBytes>,
+    pub filter_ruleset_id: Option<String>,
+    pub recent_errors: Vec<ErrorRecord>,
+    pub sampling_seed: Option<u64>,
+    pub interface_topology: Vec<InterfaceTopology>,
+    pub sample_rate: Option<SampleRateSummary>,
+}
+
+impl SessionReport {
+    /// Serializes the report to JSON
+    ///
+    /// # Returns
+    /// The JSON representation of the report
+    pub fn to_json(&self) -> Result<String, CaptureError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+                "Failed to serialize session report",
+            )
+            .with_source(e)
+        })
+    }
+
+    /// Writes the report as `session-<id>-report.json` into a directory
+    ///
+    /// # Arguments
+    /// * `dir` - Directory to write the report into
+    ///
+    /// # Returns
+    /// The path of the written report
+    pub fn write_to_dir(&self, dir: &Path) -> Result<PathBuf, CaptureError> {
+        let path = dir.join(format!("session-{}-report.json", self.session_id));
+        fs::write(&path, self.to_json()?).map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::System(SystemErrorKind::IoError),
+                &format!("Failed to write session report {}", path.display()),
+            )
+            .with_source(e)
+        })?;
+        Ok(path)
+    }
+}
+
+/// Collects per-session data that is not tracked by the engine statistics
+///
+/// # Fields
+/// * `session_id` - Session being collected
+/// * `talkers` - Packets and bytes per source address
+/// * `destinations` - Bytes written per output destination
+/// * `kernel_drops` - Kernel drops reported by capture batches
+/// * `truncated` - Truncated packets reported by capture batches
+/// * `filter_ruleset_id` - Id of the filter ruleset currently in effect
+/// * `errors` - Recent error ring sampled into the report
+/// * `sampling_seed` - Seed of the session's sampling decisions
+/// * `interface_topology` - Topology of the session's capture interfaces
+/// * `adaptive_rate` - Adaptive sampling rate sampled into the report
+#[derive(Debug, Default)]
+pub struct SessionReportCollector {
+    session_id: String,
+    talkers: HashMap<IpAddr, (u64, u64)>,
+    destinations: HashMap<String, u64>,
+    kernel_drops: u64,
+    truncated: u64,
+    filter_ruleset_id: Option<String>,
+    errors: Option<Arc<RecentErrors>>,
+    sampling_seed: Option<u64>,
+    interface_topology: Vec<InterfaceTopology>,
+    adaptive_rate: Option<Arc<AdaptiveSampleRate>>,
+}
+
+impl SessionReportCollector {
+    /// Creates a collector for a session
+    ///
+    /// # Arguments
+    /// * `session_id` - Session being collected
+    ///
+    /// # Returns
+    /// A new SessionReportCollector instance
+    pub fn new(session_id: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Records a captured packet against its source address
+    ///
+    /// # Arguments
+    /// * `source` - Source address of the packet
+    /// * `bytes` - Packet length in bytes
+    pub fn record_packet(&mut self, source: IpAddr, bytes: u64) {
+        let entry = self.talkers.entry(source).or_default();
+        entry.0 += 1;
+        entry.1 += bytes;
+    }
+
+    /// Records the drop and truncation counts of a capture batch
+    ///
+    /// # Arguments
+    /// * `batch` - Result of a capture poll
+    pub fn record_batch(&mut self, batch: &CaptureBatchResult) {
+        self.kernel_drops += batch.kernel_drops;
+        self.truncated += batch.truncated;
+    }
+
+    /// Records bytes written to an output destination
+    ///
+    /// # Arguments
+    /// * `destination_id` - Destination the bytes were written to
+    /// * `bytes` - Number of bytes written
+    pub fn record_destination_bytes(&mut self, destination_id: &str, bytes: u64) {
+        *self
+            .destinations
+            .entry(destination_id.to_string())
+            .or_default() += bytes;
+    }
+
+    /// Records the filter ruleset that is now in effect
+    ///
+    /// # Arguments
+    /// * `ruleset_id` - Id of the active ruleset
+    pub fn set_filter_ruleset(&mut self, ruleset_id: &str) {
+        self.filter_ruleset_id = Some(ruleset_id.to_string());
+    }
+
+    /// Records the seed the session's sampler was created from
+    ///
+    /// # Arguments
+    /// * `seed` - Sampler seed, as returned by `PacketSampler::seed`
+    pub fn set_sampling_seed(&mut self, seed: u64) {
+        self.sampling_seed = Some(seed);
+    }
+
+    /// Records the topology of the session's capture interfaces
+    ///
+    /// # Arguments
+    /// * `topology` - Topology per interface, as discovered by `TopologyProbe`
+    pub fn set_interface_topology(&mut self, topology: Vec<InterfaceTopology>) {
+        self.interface_topology = topology;
+    }
+
+    /// Includes the effective rate of adaptive sampling in the report
+    ///
+    /// # Arguments
+    /// * `rate` - Adaptive rate the session samples with
+    pub fn attach_adaptive_rate(&mut self, rate: Arc<AdaptiveSampleRate>) {
+        self.adaptive_rate = Some(rate);
+    }
+
+    /// Includes errors from a recent error ring in the report
+    ///
+    /// # Arguments
+    /// * `errors` - Ring the engine records errors into
+    pub fn attach_error_ring(&mut self, errors: Arc<RecentErrors>) {
+        self.errors = Some(errors);
+    }
+
+    /// Produces the report for a session that has stopped
+    ///
+    /// Reports are produced for both normal and error stops; on error the counts reflect
+    /// whatever was collected before the failure.
+    ///
+    /// # Arguments
+    /// * `final_state` - State the session ended in, either `Stopped` or `Error`
+    /// * `stats` - Session statistics
+    /// * `filter_stats` - Filter statistics for the session
+    /// * `config` - Report configuration
+    /// * `stopped_at` - Time the session stopped
+    ///
+    /// # Returns
+    /// The session report, or an error if the session has not stopped
+    pub fn finish(
+        &self,
+        final_state: &SessionState,
+        stats: &SessionStats,
+        filter_stats: &FilterStats,
+        config: &SessionReportConfig,
+        stopped_at: SystemTime,
+    ) -> Result<SessionReport, CaptureError> {
+        let outcome = match final_state {
+            SessionState::Stopped => SessionOutcome::Completed,
+            SessionState::Error(error) => SessionOutcome::Failed {
+                error: error.clone(),
+            },
+            _ => {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                    "Session report requires a stopped session",
+                ))
+            }
+        };
+
+        let mut top_talkers: Vec<TalkerStats> = self
+            .talkers
+            .iter()
+            .map(|(address, (packets, bytes))| TalkerStats {
+                address: *address,
+                packets: *packets,
+                bytes: *bytes,
+            })
+            .collect();
+        top_talkers.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.address.cmp(&b.address)));
+        top_talkers.truncate(config.top_talkers);
+
+        let mut destinations: Vec<DestinationBytes> = self
+            .destinations
+            .iter()
+            .map(|(destination_id, bytes)| DestinationBytes {
+                destination_id: destination_id.clone(),
+                bytes_written: *bytes,
+            })
+            .collect();
+        destinations.sort_by(|a, b| a.destination_id.cmp(&b.destination_id));
+
+        let started_at = stats.start_time;
+        let duration_ms = started_at
+            .and_then(|start| stopped_at.duration_since(start).ok())
+            .map(|d| d.as_millis() as u64);
+
+        Ok(SessionReport {
+            session_id: self.session_id.clone(),
+            outcome,
+            started_at: started_at.map(epoch_millis),
+            stopped_at: epoch_millis(stopped_at),
+            duration_ms,
+            packets: PacketCounts {
+                total: filter_stats.evaluated(),
+                captured: stats.packets_captured,
+                filtered: filter_stats.policy_drops(),
+                truncated: self.truncated,
+                dropped: DropCounts {
+                    resource: filter_stats.loss_drops(),
+                    kernel: self.kernel_drops,
+                },
+            },
+            top_talkers,
+            destinations,
+            filter_ruleset_id: self.filter_ruleset_id.clone(),
+            recent_errors: self
+                .errors
+                .as_ref()
+                .map(|errors| errors.recent_errors(&ErrorQuery::new().limit(config.recent_errors)))
+                .unwrap_or_default(),
+            sampling_seed: self.sampling_seed,
+            interface_topology: self.interface_topology.clone(),
+            sample_rate: self.adaptive_rate.as_ref().map(|rate| rate.summary()),
+        })
+    }
+}
+
+fn epoch_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::control::traits::FilterAction;
+    use crate::capture_engine::filter::adaptive_sampling::{
+        AdaptiveSamplingConfig, CapacityFeedback,
+    };
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    fn addr(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))
+    }
+
+    // Simulates a session: 3 talkers, some policy drops, one resource drop, two destinations
+    fn run_session(
+        collector: &mut SessionReportCollector,
+        stats: &mut SessionStats,
+        filter_stats: &FilterStats,
+    ) {
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        stats.start_time = Some(start);
+        collector.set_filter_ruleset("ruleset-v3");
+
+        let traffic = [(1, 1500), (1, 1500), (2, 200), (3, 9000), (2, 200), (1, 64)];
+        for (host, bytes) in traffic {
+            filter_stats.record_action(&FilterAction::Accept);
+            collector.record_packet(addr(host), bytes);
+            stats.packets_captured += 1;
+            stats.bytes_captured += bytes;
+            collector.record_destination_bytes("s3-archive", bytes);
+        }
+        collector.record_destination_bytes("local", 4096);
+
+        for _ in 0..4 {
+            filter_stats.record_action(&FilterAction::Drop);
+        }
+        filter_stats.record_action(&FilterAction::Accept);
+        filter_stats.record_loss_drop();
+
+        collector.record_batch(&CaptureBatchResult {
+            packets: vec![],
+            kernel_drops: 3,
+            truncated: 2,
+            rate_limited: false,
+        });
+    }
+
+    #[test]
+    fn test_stopped_session_report_matches_statistics() {
+        let mut collector = SessionReportCollector::new("session-1");
+        let mut stats = SessionStats::default();
+        let filter_stats = FilterStats::default();
+        run_session(&mut collector, &mut stats, &filter_stats);
+
+        let stopped_at = UNIX_EPOCH + Duration::from_secs(1_060);
+        let report = collector
+            .finish(
+                &SessionState::Stopped,
+                &stats,
+                &filter_stats,
+                &SessionReportConfig::default(),
+                stopped_at,
+            )
+            .unwrap();
+
+        assert_eq!(report.outcome, SessionOutcome::Completed);
+        assert_eq!(report.duration_ms, Some(60_000));
+        assert_eq!(report.packets.total, filter_stats.evaluated());
+        assert_eq!(report.packets.captured, stats.packets_captured);
+        assert_eq!(report.packets.filtered, filter_stats.policy_drops());
+        assert_eq!(report.packets.dropped.resource, filter_stats.loss_drops());
+        assert_eq!(report.packets.dropped.kernel, 3);
+        assert_eq!(report.packets.truncated, 2);
+        assert_eq!(report.filter_ruleset_id.as_deref(), Some("ruleset-v3"));
+
+        let talker_bytes: u64 = report.top_talkers.iter().map(|t| t.bytes).sum();
+        assert_eq!(talker_bytes, stats.bytes_captured);
+        assert_eq!(report.top_talkers[0].address, addr(3));
+        assert_eq!(report.top_talkers[1].address, addr(1));
+        assert_eq!(report.top_talkers[1].packets, 3);
+
+        assert_eq!(
+            report.destinations,
+            vec![
+                DestinationBytes {
+                    destination_id: "local".to_string(),
+                    bytes_written: 4096,
+                },
+                DestinationBytes {
+                    destination_id: "s3-archive".to_string(),
+                    bytes_written: stats.bytes_captured,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_stop_produces_partial_report() {
+        let mut collector = SessionReportCollector::new("session-2");
+        let filter_stats = FilterStats::default();
+        filter_stats.record_action(&FilterAction::Accept);
+        collector.record_packet(addr(9), 100);
+
+        // The session failed before it recorded a start time
+        let report = collector
+            .finish(
+                &SessionState::Error("interface went down".to_string()),
+                &SessionStats::default(),
+                &filter_stats,
+                &SessionReportConfig::default(),
+                SystemTime::now(),
+            )
+            .unwrap();
+
+        assert!(matches!(
+            &report.outcome,
+            SessionOutcome::Failed { error } if error == "interface went down"
+        ));
+        assert_eq!(report.duration_ms, None);
+        assert_eq!(report.packets.total, 1);
+        assert_eq!(report.top_talkers.len(), 1);
+    }
+
+    #[test]
+    fn test_report_requires_stopped_session() {
+        let collector = SessionReportCollector::new("session-3");
+        let result = collector.finish(
+            &SessionState::Running,
+            &SessionStats::default(),
+            &FilterStats::default(),
+            &SessionReportConfig::default(),
+            SystemTime::now(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_top_talkers_limit() {
+        let mut collector = SessionReportCollector::new("session-4");
+        for host in 1..=20 {
+            collector.record_packet(addr(host), host as u64);
+        }
+        let config = SessionReportConfig {
+            top_talkers: 5,
+            ..Default::default()
+        };
+
+        let report = collector
+            .finish(
+                &SessionState::Stopped,
+                &SessionStats::default(),
+                &FilterStats::default(),
+                &config,
+                SystemTime::now(),
+            )
+            .unwrap();
+
+        let hosts: Vec<_> = report.top_talkers.iter().map(|t| t.address).collect();
+        assert_eq!(
+            hosts,
+            vec![addr(20), addr(19), addr(18), addr(17), addr(16)]
+        );
+    }
+
+    #[test]
+    fn test_report_written_as_json() {
+        let mut collector = SessionReportCollector::new("session-5");
+        collector.record_destination_bytes("local", 10);
+        let errors = Arc::new(RecentErrors::new(4).unwrap());
+        errors.record(&CaptureError::new(
+            CaptureErrorKind::System(SystemErrorKind::IoError),
+            "disk full",
+        ));
+        collector.attach_error_ring(errors);
+        collector.set_sampling_seed(42);
+        collector.set_interface_topology(vec![InterfaceTopology {
+            interface: "veth0".to_string(),
+            numa_node: None,
+            irqs: vec![],
+        }]);
+        let rate = Arc::new(AdaptiveSampleRate::new(AdaptiveSamplingConfig::default()).unwrap());
+        rate.apply(CapacityFeedback::Congested);
+        collector.attach_adaptive_rate(rate);
+        let report = collector
+            .finish(
+                &SessionState::Stopped,
+                &SessionStats::default(),
+                &FilterStats::default(),
+                &SessionReportConfig::default(),
+                SystemTime::now(),
+            )
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("sparktrap-report-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = report.write_to_dir(&dir).unwrap();
+        assert!(path.ends_with("session-session-5-report.json"));
+        assert_eq!(report.recent_errors.len(), 1);
+        assert_eq!(report.recent_errors[0].code, "CAP-SYS-0003");
+        assert_eq!(report.sampling_seed, Some(42));
+        assert_eq!(report.interface_topology[0].numa_node, None);
+        assert_eq!(report.sample_rate.as_ref().unwrap().effective_rate, 0.5);
+
+        let restored: SessionReport =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(restored, report);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,341 @@
+// capture-engine/src/capture/shutdown_drain.rs
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::capture_engine::capture::buffer_manager::BufferPoolAccounting;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, RuntimeErrorKind,
+};
+use crate::capture_engine::output::traits::{FlushControl, FlushMode};
+use crate::traits::{Error, StartStop};
+
+/// Default interval between checks for outstanding buffers
+pub const DEFAULT_DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Drains in-flight packets before the capture engine tears down
+///
+/// Draining stops every ingest source so no new packets enter, flushes processing stages so
+/// their packets reach the outputs, flushes the outputs, and then waits for every buffer to be
+/// released back to the pool. A source that fails to stop or a stage that fails to flush does
+/// not stop the rest of the drain: each failure is recorded in `failures` and the drain carries
+/// on, so buffered packets still reach the outputs. The whole drain is bounded by a single
+/// timeout.
+///
+/// # Fields
+/// * `ingest` - Sources stopped first
+/// * `pipeline` - Processing stages flushed once ingest has stopped
+/// * `outputs` - Output destinations flushed last
+/// * `pool` - Pool accounting watched for outstanding buffers
+/// * `poll_interval` - Interval between checks for outstanding buffers
+/// * `failures` - Stop and flush failures of the last drain
+pub struct ShutdownDrain {
+    ingest: Vec<Box<dyn StartStop>>,
+    pipeline: Vec<Box<dyn FlushControl>>,
+    outputs: Vec<Box<dyn FlushControl>>,
+    pool: Arc<BufferPoolAccounting>,
+    poll_interval: Duration,
+    failures: Vec<CaptureError>,
+}
+
+impl ShutdownDrain {
+    /// Creates a drain watching the given buffer pool
+    ///
+    /// # Arguments
+    /// * `pool` - Accounting of the pool whose buffers must all be released
+    ///
+    /// # Returns
+    /// A drain with no registered stages
+    pub fn new(pool: Arc<BufferPoolAccounting>) -> Self {
+        Self {
+            ingest: Vec::new(),
+            pipeline: Vec::new(),
+            outputs: Vec::new(),
+            pool,
+            poll_interval: DEFAULT_DRAIN_POLL_INTERVAL,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Registers an ingest source, stopped at the start of the drain
+    pub fn add_ingest(&mut self, source: Box<dyn StartStop>) {
+        self.ingest.push(source);
+    }
+
+    /// Registers a processing stage, flushed after ingest stops
+    pub fn add_pipeline_stage(&mut self, stage: Box<dyn FlushControl>) {
+        self.pipeline.push(stage);
+    }
+
+    /// Registers an output destination, flushed after the processing stages
+    pub fn add_output(&mut self, output: Box<dyn FlushControl>) {
+        self.outputs.push(output);
+    }
+
+    /// Sets the interval between checks for outstanding buffers
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+    }
+
+    /// Returns the stop and flush failures recorded by the last drain
+    pub fn failures(&self) -> &[CaptureError] {
+        &self.failures
+    }
+
+    /// Stops ingest, flushes the pipeline and outputs, and waits for buffers to be released
+    ///
+    /// # Arguments
+    /// * `timeout` - Bound on the whole drain
+    ///
+    /// # Returns
+    /// An error if the timeout elapsed, or else one combining every stop and flush failure; a
+    /// timeout is a `Runtime(Timeout)` error naming the buffers still outstanding
+    pub async fn drain(&mut self, timeout: Duration) -> Result<(), CaptureError> {
+        let deadline = Instant::now() + timeout;
+        self.failures.clear();
+
+        for source in &mut self.ingest {
+            let stopped = within(deadline, &self.pool, timeout, source.stop()).await?;
+            if let Err(e) = stopped {
+                self.failures.push(stage_error("Failed to stop ingest", e));
+            }
+        }
+
+        for stage in self.pipeline.iter_mut().chain(self.outputs.iter_mut()) {
+            let flushed = within(
+                deadline,
+                &self.pool,
+                timeout,
+                stage.flush_with_mode(FlushMode::Normal),
+            )
+            .await?;
+            if let Err(e) = flushed {
+                self.failures
+                    .push(stage_error("Failed to flush during drain", e));
+            }
+        }
+
+        let pool = Arc::clone(&self.pool);
+        let poll_interval = self.poll_interval;
+        within(deadline, &self.pool, timeout, async move {
+            while pool.snapshot().in_use > 0 {
+                tokio::time::sleep(poll_interval).await;
+            }
+        })
+        .await?;
+
+        match self.failures.as_slice() {
+            [] => Ok(()),
+            failures => Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                &format!(
+                    "Shutdown drain completed with {} failures: {}",
+                    failures.len(),
+                    failures
+                        .iter()
+                        .map(CaptureError::message)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                ),
+            )),
+        }
+    }
+}
+
+/// Runs a drain step, failing with the outstanding buffer count if the deadline passes
+async fn within<T>(
+    deadline: Instant,
+    pool: &BufferPoolAccounting,
+    timeout: Duration,
+    step: impl Future<Output = T>,
+) -> Result<T, CaptureError> {
+    tokio::time::timeout_at(deadline, step).await.map_err(|_| {
+        *CaptureError::new(
+            CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout),
+            &format!(
+                "Shutdown drain timed out after {:?} with {} buffers outstanding",
+                timeout,
+                pool.snapshot().in_use
+            ),
+        )
+    })
+}
+
+fn stage_error(context: &str, error: Error) -> CaptureError {
+    *CaptureError::new(
+        CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+        &format!("{}: {}", context, error),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::buffer_manager::BufferMemoryType;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    struct Ingest(Log, bool);
+
+    #[async_trait]
+    impl StartStop for Ingest {
+        async fn start(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn stop(&mut self) -> Result<(), Error> {
+            self.0.lock().push("stop ingest".to_string());
+            if self.1 {
+                return Err(Error::Runtime("socket busy".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    struct Stage {
+        name: &'static str,
+        log: Log,
+        delay: Duration,
+        fails: bool,
+    }
+
+    #[async_trait]
+    impl FlushControl for Stage {
+        async fn flush_with_mode(&mut self, mode: FlushMode) -> Result<(), Error> {
+            tokio::time::sleep(self.delay).await;
+            self.log
+                .lock()
+                .push(format!("flush {} {:?}", self.name, mode));
+            if self.fails {
+                return Err(Error::Runtime("disk full".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    fn stage(name: &'static str, log: &Log) -> Box<Stage> {
+        Box::new(Stage {
+            name,
+            log: Arc::clone(log),
+            delay: Duration::ZERO,
+            fails: false,
+        })
+    }
+
+    fn pool_with_in_use(in_use: usize) -> Arc<BufferPoolAccounting> {
+        let pool = Arc::new(BufferPoolAccounting::default());
+        pool.add_buffers(BufferMemoryType::Heap, 8);
+        for _ in 0..in_use {
+            pool.acquire(BufferMemoryType::Heap).unwrap();
+        }
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_drain_runs_in_order_and_waits_for_buffers() {
+        let log: Log = Arc::default();
+        let pool = pool_with_in_use(2);
+        let mut drain = ShutdownDrain::new(Arc::clone(&pool));
+        drain.set_poll_interval(Duration::from_millis(1));
+        // Registered out of order to show the drain orders the phases
+        drain.add_output(stage("output", &log));
+        drain.add_pipeline_stage(stage("pipeline", &log));
+        drain.add_ingest(Box::new(Ingest(Arc::clone(&log), false)));
+
+        let releaser = {
+            let pool = Arc::clone(&pool);
+            tokio::spawn(async move {
+                for _ in 0..2 {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    pool.release(BufferMemoryType::Heap).unwrap();
+                }
+            })
+        };
+        drain.drain(Duration::from_secs(5)).await.unwrap();
+        releaser.await.unwrap();
+
+        assert_eq!(pool.snapshot().in_use, 0);
+        assert_eq!(
+            *log.lock(),
+            vec![
+                "stop ingest",
+                "flush pipeline Normal",
+                "flush output Normal"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeout_reports_outstanding_buffers() {
+        let pool = pool_with_in_use(3);
+        let mut drain = ShutdownDrain::new(pool);
+        drain.set_poll_interval(Duration::from_millis(1));
+
+        let err = drain.drain(Duration::from_millis(30)).await.unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout)
+        ));
+        assert!(err.message().contains("3 buffers outstanding"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_slow_flush_counts_against_timeout() {
+        let log: Log = Arc::default();
+        let mut drain = ShutdownDrain::new(pool_with_in_use(0));
+        drain.add_output(Box::new(Stage {
+            name: "slow",
+            log: Arc::clone(&log),
+            delay: Duration::from_secs(60),
+            fails: false,
+        }));
+
+        let err = drain.drain(Duration::from_millis(30)).await.unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout)
+        ));
+        assert!(log.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_flush_still_flushes_later_stages() {
+        let log: Log = Arc::default();
+        let mut drain = ShutdownDrain::new(pool_with_in_use(0));
+        drain.add_pipeline_stage(Box::new(Stage {
+            name: "pipeline",
+            log: Arc::clone(&log),
+            delay: Duration::ZERO,
+            fails: true,
+        }));
+        drain.add_output(stage("output", &log));
+
+        let err = drain.drain(Duration::from_secs(5)).await.unwrap_err();
+        assert!(err.message().contains("disk full"), "{}", err);
+        assert_eq!(log.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_ingest_stop_still_flushes_outputs() {
+        let log: Log = Arc::default();
+        let mut drain = ShutdownDrain::new(pool_with_in_use(0));
+        drain.add_ingest(Box::new(Ingest(Arc::clone(&log), true)));
+        drain.add_output(Box::new(Stage {
+            name: "output",
+            log: Arc::clone(&log),
+            delay: Duration::ZERO,
+            fails: true,
+        }));
+
+        let err = drain.drain(Duration::from_secs(5)).await.unwrap_err();
+        assert_eq!(*log.lock(), vec!["stop ingest", "flush output Normal"]);
+        assert!(err.message().contains("2 failures"), "{}", err);
+        assert!(err.message().contains("socket busy"), "{}", err);
+        assert!(err.message().contains("disk full"), "{}", err);
+        assert_eq!(drain.failures().len(), 2);
+    }
+}
@@ -0,0 +1,339 @@
+// capture-engine/src/capture/stage_control.rs
+use parking_lot::RwLock;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ResourceErrorKind,
+};
+use crate::capture_engine::capture::traits::PipelineStage;
+
+/// Tracks which pipeline stages are paused
+///
+/// Pausing a stage only affects that stage; upstream stages keep running and hand their work to
+/// the paused stage's backlog until it fills up.
+///
+/// # Fields
+/// * `paused` - Set of currently paused stages
+#[derive(Debug, Default)]
+pub struct StageControl {
+    paused: RwLock<HashSet<PipelineStage>>,
+}
+
+impl StageControl {
+    /// Pauses a stage
+    ///
+    /// # Arguments
+    /// * `stage` - Stage to pause
+    ///
+    /// # Returns
+    /// True if the stage was running
+    pub fn pause(&self, stage: PipelineStage) -> bool {
+        self.paused.write().insert(stage)
+    }
+
+    /// Resumes a stage
+    ///
+    /// # Arguments
+    /// * `stage` - Stage to resume
+    ///
+    /// # Returns
+    /// True if the stage was paused
+    pub fn resume(&self, stage: &PipelineStage) -> bool {
+        self.paused.write().remove(stage)
+    }
+
+    /// Checks whether a stage is paused
+    ///
+    /// # Arguments
+    /// * `stage` - Stage to check
+    ///
+    /// # Returns
+    /// True if the stage is paused
+    pub fn is_paused(&self, stage: &PipelineStage) -> bool {
+        self.paused.read().contains(stage)
+    }
+}
+
+/// What happened to an item submitted to a stage
+///
+/// # Variants
+/// * `Processed` - The stage handled the item immediately
+/// * `Buffered` - The stage is paused and the item was queued in its backlog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageSubmit {
+    Processed,
+    Buffered,
+}
+
+/// Input side of a pipeline stage that can be paused independently
+///
+/// While the stage is paused, submitted items are held in a bounded backlog. Once the backlog
+/// is full, submissions fail with a quota error so the upstream stage can apply backpressure.
+/// Resuming the stage drains the backlog in submission order before new items are processed.
+///
+/// # Type Parameters
+/// * `T` - Item handled by the stage
+///
+/// # Fields
+/// * `stage` - Stage this input belongs to
+/// * `control` - Shared pause state
+/// * `backlog` - Items accumulated while paused
+/// * `capacity` - Maximum backlog size
+#[derive(Debug)]
+pub struct PausableStage<T> {
+    stage: PipelineStage,
+    control: Arc<StageControl>,
+    backlog: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> PausableStage<T> {
+    /// Creates a stage input with a bounded backlog
+    ///
+    /// # Arguments
+    /// * `stage` - Stage this input belongs to
+    /// * `control` - Shared pause state
+    /// * `capacity` - Maximum number of items held while paused
+    ///
+    /// # Returns
+    /// A new PausableStage instance
+    pub fn new(stage: PipelineStage, control: Arc<StageControl>, capacity: usize) -> Self {
+        Self {
+            stage,
+            control,
+            backlog: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Submits an item to the stage
+    ///
+    /// The backlog is drained first so items keep their submission order. If the stage is
+    /// paused during the drain, or the drain fails, the item is queued behind the remaining
+    /// backlog rather than processed out of order or dropped.
+    ///
+    /// # Arguments
+    /// * `item` - Item to handle
+    /// * `handler` - Stage logic applied to each item
+    ///
+    /// # Returns
+    /// Whether the item was processed or buffered, a quota error if the backlog is full, or the
+    /// drain error after the item was queued
+    pub fn submit<F>(&mut self, item: T, mut handler: F) -> Result<StageSubmit, CaptureError>
+    where
+        F: FnMut(T) -> Result<(), CaptureError>,
+    {
+        // Preserve ordering: anything accumulated while paused goes first
+        let drained = self.drain(&mut handler);
+        if drained.is_ok() && self.backlog.is_empty() && !self.control.is_paused(&self.stage) {
+            handler(item)?;
+            return Ok(StageSubmit::Processed);
+        }
+
+        if self.backlog.len() >= self.capacity {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded),
+                &format!("{:?} stage is paused and its backlog is full", self.stage),
+            ));
+        }
+        self.backlog.push_back(item);
+        drained?;
+        Ok(StageSubmit::Buffered)
+    }
+
+    /// Processes the accumulated backlog if the stage is running
+    ///
+    /// On error, the failed item is dropped and the remaining backlog is kept.
+    ///
+    /// # Arguments
+    /// * `handler` - Stage logic applied to each item
+    ///
+    /// # Returns
+    /// The number of items processed
+    pub fn drain<F>(&mut self, mut handler: F) -> Result<usize, CaptureError>
+    where
+        F: FnMut(T) -> Result<(), CaptureError>,
+    {
+        let mut processed = 0;
+        while !self.control.is_paused(&self.stage) {
+            let Some(item) = self.backlog.pop_front() else {
+                break;
+            };
+            handler(item)?;
+            processed += 1;
+        }
+        Ok(processed)
+    }
+
+    /// Returns the number of items waiting in the backlog
+    pub fn backlog_len(&self) -> usize {
+        self.backlog.len()
+    }
+
+    /// Checks whether the backlog is full, i.e. upstream should apply backpressure
+    pub fn is_saturated(&self) -> bool {
+        self.backlog.len() >= self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pipeline {
+        control: Arc<StageControl>,
+        filtering: PausableStage<u32>,
+        output: PausableStage<u32>,
+        written: Vec<u32>,
+    }
+
+    impl Pipeline {
+        fn new(output_capacity: usize) -> Self {
+            let control = Arc::new(StageControl::default());
+            Self {
+                filtering: PausableStage::new(PipelineStage::Filtering, control.clone(), 16),
+                output: PausableStage::new(PipelineStage::Output, control.clone(), output_capacity),
+                control,
+                written: Vec::new(),
+            }
+        }
+
+        // Ingests a packet: filtering passes it straight to the output stage
+        fn ingest(&mut self, packet: u32) -> Result<StageSubmit, CaptureError> {
+            let output = &mut self.output;
+            let written = &mut self.written;
+            let mut outcome = StageSubmit::Processed;
+            self.filtering.submit(packet, |packet| {
+                outcome = output.submit(packet, |packet| {
+                    written.push(packet);
+                    Ok(())
+                })?;
+                Ok(())
+            })?;
+            Ok(outcome)
+        }
+
+        fn resume_output(&mut self) -> usize {
+            self.control.resume(&PipelineStage::Output);
+            let written = &mut self.written;
+            self.output
+                .drain(|packet| {
+                    written.push(packet);
+                    Ok(())
+                })
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_paused_output_buffers_and_resume_drains() {
+        let mut pipeline = Pipeline::new(32);
+        pipeline.control.pause(PipelineStage::Output);
+
+        for packet in 0..20 {
+            assert_eq!(pipeline.ingest(packet).unwrap(), StageSubmit::Buffered);
+        }
+        assert!(pipeline.written.is_empty());
+        assert_eq!(pipeline.output.backlog_len(), 20);
+        assert!(!pipeline.control.is_paused(&PipelineStage::Filtering));
+
+        assert_eq!(pipeline.resume_output(), 20);
+        assert_eq!(pipeline.written, (0..20).collect::<Vec<_>>());
+        assert_eq!(pipeline.output.backlog_len(), 0);
+    }
+
+    #[test]
+    fn test_pause_outlasting_capacity_backpressures() {
+        let mut pipeline = Pipeline::new(4);
+        pipeline.control.pause(PipelineStage::Output);
+
+        for packet in 0..4 {
+            pipeline.ingest(packet).unwrap();
+        }
+        assert!(pipeline.output.is_saturated());
+
+        let err = pipeline.ingest(4).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded)
+        ));
+
+        pipeline.resume_output();
+        assert_eq!(pipeline.written, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_backlog_written_before_new_packets() {
+        let mut pipeline = Pipeline::new(8);
+        pipeline.control.pause(PipelineStage::Output);
+        pipeline.ingest(1).unwrap();
+        pipeline.ingest(2).unwrap();
+
+        // Resume without an explicit drain; the next packet flushes the backlog first
+        pipeline.control.resume(&PipelineStage::Output);
+        assert_eq!(pipeline.ingest(3).unwrap(), StageSubmit::Processed);
+        assert_eq!(pipeline.written, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_item_queued_when_drain_fails_or_pauses() {
+        let control = Arc::new(StageControl::default());
+        let mut stage = PausableStage::new(PipelineStage::Output, control.clone(), 8);
+        control.pause(PipelineStage::Output);
+        for item in [1, 2, 3] {
+            stage.submit(item, |_| Ok(())).unwrap();
+        }
+        control.resume(&PipelineStage::Output);
+
+        // The first backlog item fails: the new item waits behind the rest
+        let mut written = Vec::new();
+        let err = stage
+            .submit(4, |item| {
+                if item == 1 {
+                    return Err(*CaptureError::new(
+                        CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable),
+                        "write failed",
+                    ));
+                }
+                written.push(item);
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable)
+        ));
+        assert!(written.is_empty());
+        assert_eq!(stage.backlog_len(), 3);
+
+        // The stage is paused by the handler mid-drain: the new item is buffered, not processed
+        let result = stage.submit(5, |item| {
+            written.push(item);
+            control.pause(PipelineStage::Output);
+            Ok(())
+        });
+        assert_eq!(result.unwrap(), StageSubmit::Buffered);
+        assert_eq!(written, vec![2]);
+        assert_eq!(stage.backlog_len(), 3);
+
+        control.resume(&PipelineStage::Output);
+        stage
+            .drain(|item| {
+                written.push(item);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(written, vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_pause_and_resume_report_transitions() {
+        let control = StageControl::default();
+        assert!(control.pause(PipelineStage::Output));
+        assert!(!control.pause(PipelineStage::Output));
+        assert!(control.is_paused(&PipelineStage::Output));
+        assert!(control.resume(&PipelineStage::Output));
+        assert!(!control.resume(&PipelineStage::Output));
+    }
+}
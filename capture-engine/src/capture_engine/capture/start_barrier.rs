@@ -0,0 +1,161 @@
+// capture-engine/src/capture/start_barrier.rs
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, RuntimeErrorKind,
+};
+use crate::capture_engine::capture::clock::Clock;
+
+/// Holds capture ingestion until a scheduled wall-clock instant
+///
+/// Nodes participating in a distributed capture arm a barrier for the same start time, so their
+/// captured timelines begin together. A node armed after the start time has already passed
+/// reports a missed barrier instead of starting unsynchronized.
+///
+/// # Fields
+/// * `scheduled_start` - Instant at which ingestion may begin
+/// * `clock` - Clock used to observe the current time
+#[derive(Debug, Clone)]
+pub struct StartBarrier {
+    scheduled_start: SystemTime,
+    clock: Arc<dyn Clock>,
+}
+
+impl StartBarrier {
+    /// Arms a barrier for the scheduled start time
+    ///
+    /// # Arguments
+    /// * `scheduled_start` - Instant at which ingestion may begin
+    /// * `clock` - Clock used to observe the current time
+    ///
+    /// # Returns
+    /// The armed barrier, or a timeout error if the scheduled start has already passed
+    pub fn arm(scheduled_start: SystemTime, clock: Arc<dyn Clock>) -> Result<Self, CaptureError> {
+        let now = clock.now();
+        if let Ok(late_by) = now.duration_since(scheduled_start) {
+            if !late_by.is_zero() {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout),
+                    &format!("Missed scheduled capture start by {:?}", late_by),
+                ));
+            }
+        }
+
+        Ok(Self {
+            scheduled_start,
+            clock,
+        })
+    }
+
+    /// Gets the scheduled start time
+    ///
+    /// # Returns
+    /// The instant at which ingestion may begin
+    pub fn scheduled_start(&self) -> SystemTime {
+        self.scheduled_start
+    }
+
+    /// Gets the time remaining until the barrier opens
+    ///
+    /// # Returns
+    /// The remaining duration, or zero if the barrier is already open
+    pub fn remaining(&self) -> Duration {
+        self.scheduled_start
+            .duration_since(self.clock.now())
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// Checks whether ingestion may begin
+    ///
+    /// # Returns
+    /// True once the clock has reached the scheduled start
+    pub fn is_open(&self) -> bool {
+        self.clock.now() >= self.scheduled_start
+    }
+
+    /// Waits until the scheduled start
+    ///
+    /// # Returns
+    /// The clock time at which the barrier opened
+    pub async fn wait(&self) -> SystemTime {
+        loop {
+            let now = self.clock.now();
+            if now >= self.scheduled_start {
+                return now;
+            }
+            self.clock.sleep_until(self.scheduled_start).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::clock::ManualClock;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn epoch_plus(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[tokio::test]
+    async fn test_capture_begins_at_scheduled_time() {
+        let clock = Arc::new(ManualClock::new(epoch_plus(1_000)));
+        let scheduled = epoch_plus(1_010);
+        let barrier = StartBarrier::arm(scheduled, clock.clone()).unwrap();
+        assert_eq!(barrier.remaining(), Duration::from_secs(10));
+
+        let started = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let started = started.clone();
+            tokio::spawn(async move {
+                let opened_at = barrier.wait().await;
+                started.store(true, Ordering::SeqCst);
+                opened_at
+            })
+        };
+
+        clock.advance(Duration::from_millis(9_999));
+        tokio::task::yield_now().await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!started.load(Ordering::SeqCst));
+
+        clock.advance(Duration::from_millis(1));
+        let opened_at = handle.await.unwrap();
+        assert!(started.load(Ordering::SeqCst));
+        assert_eq!(opened_at, scheduled);
+    }
+
+    #[tokio::test]
+    async fn test_barrier_armed_at_scheduled_instant_opens_immediately() {
+        let clock = Arc::new(ManualClock::new(epoch_plus(500)));
+        let barrier = StartBarrier::arm(epoch_plus(500), clock).unwrap();
+        assert!(barrier.is_open());
+        assert_eq!(barrier.wait().await, epoch_plus(500));
+    }
+
+    #[test]
+    fn test_late_arming_reports_missed_barrier() {
+        let clock = Arc::new(ManualClock::new(epoch_plus(1_000)));
+        clock.advance(Duration::from_millis(250));
+
+        let err = StartBarrier::arm(epoch_plus(1_000), clock).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::Timeout)
+        ));
+        assert!(err.to_string().contains("Missed scheduled capture start"));
+    }
+
+    #[test]
+    fn test_remaining_reaches_zero() {
+        let clock = Arc::new(ManualClock::new(epoch_plus(0)));
+        let barrier = StartBarrier::arm(epoch_plus(5), clock.clone()).unwrap();
+        assert!(!barrier.is_open());
+
+        clock.set(epoch_plus(7));
+        assert!(barrier.is_open());
+        assert_eq!(barrier.remaining(), Duration::ZERO);
+    }
+}
@@ -1,18 +1,67 @@
 // capture-engine/src/capture/capture_error.rs
 /// A state machine for managing the state of the capture engine.
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{Debug, Write};
 use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::SystemTime;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
 
 use crate::capture_engine::capture::capture_error::{
-    CaptureError, CaptureErrorKind, ConfigErrorKind, ResourceErrorKind,
+    CaptureError, CaptureErrorKind, ConfigErrorKind, ResourceErrorKind, RuntimeErrorKind,
 };
+use crate::capture_engine::capture::state_validator::InvariantChecker;
+
+/// Cause of a state transition
+///
+/// Typed alongside the free-text reason so transitions can be grouped and queried by cause and
+/// reported to the control plane without parsing strings.
+///
+/// # Variants
+/// * `OperatorCommand` - Requested by an operator through the control plane
+/// * `Pressure` - Triggered by memory, CPU, network or storage pressure
+/// * `Recovery` - Part of recovering from a failure
+/// * `CloudLifecycle` - Driven by the cloud provider, such as instance stop or spot reclaim
+/// * `Error` - Forced by an error
+/// * `Scheduled` - Started by a schedule or timer
+/// * `Quota` - Ended by a session's packet or byte budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionReason {
+    OperatorCommand,
+    Pressure,
+    Recovery,
+    CloudLifecycle,
+    Error,
+    Scheduled,
+    Quota,
+}
+
+impl TransitionReason {
+    /// Returns the stable name used in metrics and control plane reports
+    ///
+    /// # Returns
+    /// The snake_case name of the reason
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransitionReason::OperatorCommand => "operator_command",
+            TransitionReason::Pressure => "pressure",
+            TransitionReason::Recovery => "recovery",
+            TransitionReason::CloudLifecycle => "cloud_lifecycle",
+            TransitionReason::Error => "error",
+            TransitionReason::Scheduled => "scheduled",
+            TransitionReason::Quota => "quota",
+        }
+    }
+}
 
 /// Represents a generic state transition event
 ///
 /// The transition event captures the source and target states, the timestamp of the transition,
-/// and an optional reason for the transition.
+/// an optional typed cause, and an optional free-text reason for the transition.
 ///
 /// # Type Parameters
 /// * `S` - The type of the state
@@ -22,12 +71,14 @@ use crate::capture_engine::capture::capture_error::{
 /// * `to` - The target state
 /// * `timestamp` - The timestamp of the transition
 /// * `reason` - An optional reason for the transition
+/// * `category` - An optional typed cause of the transition
 #[derive(Debug, Clone)]
 pub struct StateTransition<S> {
     from: S,
     to: S,
     timestamp: SystemTime,
     reason: Option<String>,
+    category: Option<TransitionReason>,
 }
 
 impl<S> StateTransition<S>
@@ -48,9 +99,22 @@ where
             to,
             timestamp: SystemTime::now(),
             reason,
+            category: None,
         }
     }
 
+    /// Sets the typed cause of the transition
+    ///
+    /// # Arguments
+    /// * `category` - The cause of the transition
+    ///
+    /// # Returns
+    /// The transition with its cause set
+    pub fn with_category(mut self, category: TransitionReason) -> Self {
+        self.category = Some(category);
+        self
+    }
+
     /// Get the source state
     ///
     /// # Returns
@@ -82,6 +146,80 @@ where
     pub fn reason(&self) -> Option<&String> {
         self.reason.as_ref()
     }
+
+    /// Get the typed cause of the transition if any
+    ///
+    /// # Returns
+    /// The cause of the transition
+    pub fn category(&self) -> Option<TransitionReason> {
+        self.category
+    }
+}
+
+/// Result of a transition request
+///
+/// # Variants
+/// * `Applied` - The transition was performed and recorded in history
+/// * `Suppressed` - The transition repeated the previous one within the debounce window and was
+///   dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionOutcome {
+    Applied,
+    Suppressed,
+}
+
+/// What happens to history when a transition is applied and history is full
+///
+/// # Variants
+/// * `DropOldest` - Evict the oldest transition to make room
+/// * `DropNewest` - Apply the transition without recording it
+/// * `Reject` - Refuse the transition with an error
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoryOverflowPolicy {
+    #[default]
+    DropOldest,
+    DropNewest,
+    Reject,
+}
+
+/// Predicate deciding at runtime whether an allowed transition may proceed
+///
+/// Called with the source and target states. `Ok(false)` rejects the transition; an error
+/// rejects it and is returned to the caller.
+pub type TransitionGuard<S> = Arc<dyn Fn(&S, &S) -> Result<bool, CaptureError> + Send + Sync>;
+
+/// Guards keyed by the transition they protect
+struct TransitionGuards<S>(HashMap<(S, S), TransitionGuard<S>>);
+
+impl<S> std::fmt::Debug for TransitionGuards<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TransitionGuards({})", self.0.len())
+    }
+}
+
+/// Callback invoked with each applied transition
+pub type TransitionObserver<S> = Arc<dyn Fn(&StateTransition<S>) + Send + Sync>;
+
+/// Callback invoked with the current state and the attempted target of a rejected transition
+pub type FailedTransitionObserver<S> = Arc<dyn Fn(&S, &S) + Send + Sync>;
+
+/// Registered transition callbacks
+struct TransitionObservers<S> {
+    applied: Vec<TransitionObserver<S>>,
+    failed: Vec<FailedTransitionObserver<S>>,
+    spill: Option<TransitionObserver<S>>,
+}
+
+impl<S> std::fmt::Debug for TransitionObservers<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TransitionObservers(applied: {}, failed: {}, spill: {})",
+            self.applied.len(),
+            self.failed.len(),
+            self.spill.is_some()
+        )
+    }
 }
 
 /// Core state machine implementation
@@ -94,19 +232,37 @@ where
 /// # Fields
 /// * `current_state` - The current state of the state machine
 /// * `allowed_transitions` - A map of allowed transitions between states
+/// * `wildcard_targets` - States reachable from every other state
 /// * `history` - A queue of state transitions
 /// * `max_history` - The maximum number of transitions to keep in history
+/// * `history_overflow` - What happens when a transition arrives with history full
+/// * `last_unrecorded` - The most recent transition, if `DropNewest` kept it out of history
 /// * `metrics` - Metrics for state machine transitions
+/// * `debounce_window` - Window within which a repeat of the previous transition is suppressed
+/// * `guards` - Runtime predicates on guarded transitions
+/// * `observers` - Callbacks notified of applied, rejected and spilled transitions
+/// * `invariants` - Predicates every entered state must satisfy
+/// * `entered_at` - When the current state was entered
+/// * `timed_transitions` - Target state and timeout for states that expire
 #[derive(Debug)]
 pub struct StateMachine<S>
 where
     S: Clone + Eq + Hash,
 {
     current_state: S,
+    entered_at: SystemTime,
+    timed_transitions: HashMap<S, (S, Duration)>,
     allowed_transitions: HashMap<S, Vec<S>>,
+    wildcard_targets: HashSet<S>,
+    guards: TransitionGuards<S>,
+    observers: TransitionObservers<S>,
+    invariants: Option<InvariantChecker<S>>,
     history: VecDeque<StateTransition<S>>,
     max_history: usize,
+    history_overflow: HistoryOverflowPolicy,
+    last_unrecorded: Option<StateTransition<S>>,
     metrics: StateMetrics,
+    debounce_window: Option<Duration>,
 }
 
 impl<S> StateMachine<S>
@@ -131,17 +287,46 @@ where
 
         Ok(StateMachine {
             current_state: initial_state,
+            entered_at: SystemTime::now(),
+            timed_transitions: HashMap::new(),
             allowed_transitions: HashMap::new(),
+            wildcard_targets: HashSet::new(),
+            guards: TransitionGuards(HashMap::new()),
+            observers: TransitionObservers {
+                applied: Vec::new(),
+                failed: Vec::new(),
+                spill: None,
+            },
+            invariants: None,
             history: VecDeque::with_capacity(max_history),
             max_history,
-            metrics: StateMetrics {
-                transitions_count: AtomicU64::new(0),
-                failed_transitions: AtomicU64::new(0),
-                average_transition_time: AtomicU64::new(0),
-            },
+            history_overflow: HistoryOverflowPolicy::default(),
+            last_unrecorded: None,
+            metrics: StateMetrics::new(),
+            debounce_window: None,
         })
     }
 
+    /// Enables or disables debouncing of repeated transitions
+    ///
+    /// When enabled, a transition whose source and target equal those of the immediately
+    /// preceding transition, and which arrives within `window` of it, is suppressed rather than
+    /// recorded. Distinct transitions are never suppressed.
+    ///
+    /// # Arguments
+    /// * `window` - Debounce window, or None to disable debouncing
+    pub fn set_debounce_window(&mut self, window: Option<Duration>) {
+        self.debounce_window = window;
+    }
+
+    /// Returns the debounce window
+    ///
+    /// # Returns
+    /// The debounce window, or None if debouncing is disabled
+    pub fn debounce_window(&self) -> Option<Duration> {
+        self.debounce_window
+    }
+
     /// Adds allowed transition between states
     ///
     /// # Arguments
@@ -154,6 +339,166 @@ where
         self.allowed_transitions.entry(from).or_default().push(to);
     }
 
+    /// Allows a transition to `to` from every other state
+    ///
+    /// The wildcard also covers states that only appear in edges added later. It is consulted
+    /// after explicit edges, so a guard on an explicit edge into `to` still applies. Staying in
+    /// `to` needs an explicit self-transition.
+    ///
+    /// # Arguments
+    /// * `to` - The target state
+    pub fn add_transition_from_any(&mut self, to: S) {
+        self.wildcard_targets.insert(to);
+    }
+
+    /// Sets what happens when a transition is applied while history is full
+    ///
+    /// # Arguments
+    /// * `policy` - The overflow policy
+    pub fn set_history_overflow_policy(&mut self, policy: HistoryOverflowPolicy) {
+        self.history_overflow = policy;
+    }
+
+    /// Returns the history overflow policy
+    ///
+    /// # Returns
+    /// The overflow policy
+    pub fn history_overflow_policy(&self) -> HistoryOverflowPolicy {
+        self.history_overflow
+    }
+
+    /// Sets a callback receiving each transition dropped from history on overflow
+    ///
+    /// Called synchronously with the evicted transition under `DropOldest`, or the unrecorded
+    /// one under `DropNewest`, so it can be persisted before it is lost. Panics are caught as
+    /// for `on_transition`. Transitions removed by `clear_history` or `rollback` are not
+    /// spilled.
+    ///
+    /// # Arguments
+    /// * `spill` - The callback, or None to drop transitions silently
+    pub fn set_history_spill(&mut self, spill: Option<TransitionObserver<S>>) {
+        self.observers.spill = spill;
+    }
+
+    /// Registers a callback invoked after every applied transition
+    ///
+    /// Callbacks run synchronously on the transitioning thread, in registration order, after
+    /// the new state and history entry are in place and before the transition call returns.
+    /// Suppressed transitions are not reported. A callback that panics is caught so the
+    /// remaining callbacks still run and the state machine stays usable.
+    ///
+    /// # Arguments
+    /// * `callback` - Called with the applied transition
+    pub fn on_transition(&mut self, callback: TransitionObserver<S>) {
+        self.observers.applied.push(callback);
+    }
+
+    /// Registers a callback invoked when a transition is rejected
+    ///
+    /// Called synchronously, in registration order, when a transition is not allowed or is
+    /// rejected by its guard, before the error is returned. Panics are caught as for
+    /// `on_transition`.
+    ///
+    /// # Arguments
+    /// * `callback` - Called with the current state and the attempted target
+    pub fn on_failed_transition(&mut self, callback: FailedTransitionObserver<S>) {
+        self.observers.failed.push(callback);
+    }
+
+    /// Sets the invariants checked after every transition
+    ///
+    /// A transition whose target state breaks an invariant is rolled back: the state, history
+    /// and transition count are left as they were, it counts as a failed transition, and an
+    /// error naming the first broken invariant is returned. Rollbacks are not checked.
+    ///
+    /// # Arguments
+    /// * `checker` - The invariants, or None to stop checking
+    pub fn set_invariant_checker(&mut self, checker: Option<InvariantChecker<S>>) {
+        self.invariants = checker;
+    }
+
+    /// Adds an allowed transition that proceeds only when `guard` holds
+    ///
+    /// The guard is evaluated on every attempt after the transition is found to be allowed.
+    /// Adding a guard to a transition that already has one replaces it.
+    ///
+    /// # Arguments
+    /// * `from` - The source state
+    /// * `to` - The target state
+    /// * `guard` - Predicate called with the source and target states
+    pub fn add_guarded_transition(&mut self, from: S, to: S, guard: TransitionGuard<S>) {
+        let allowed = self.allowed_transitions.entry(from.clone()).or_default();
+        if !allowed.contains(&to) {
+            allowed.push(to.clone());
+        }
+        self.guards.0.insert((from, to), guard);
+    }
+
+    /// Adds an allowed transition taken automatically once `from` has been held too long
+    ///
+    /// A state has at most one timed transition; adding another replaces it.
+    ///
+    /// # Arguments
+    /// * `from` - The state that expires
+    /// * `to` - The state entered on expiry
+    /// * `timeout` - How long `from` may be held before `tick` leaves it
+    pub fn add_timed_transition(&mut self, from: S, to: S, timeout: Duration) {
+        let allowed = self.allowed_transitions.entry(from.clone()).or_default();
+        if !allowed.contains(&to) {
+            allowed.push(to.clone());
+        }
+        self.timed_transitions.insert(from, (to, timeout));
+    }
+
+    /// Returns when the current state was entered
+    ///
+    /// # Returns
+    /// The time of the last applied transition or rollback, or of construction
+    pub fn entered_at(&self) -> SystemTime {
+        self.entered_at
+    }
+
+    /// Takes the timed transition out of the current state if it has expired
+    ///
+    /// The transition is recorded with the `Scheduled` reason and passes through guards and
+    /// debouncing like any other.
+    ///
+    /// # Arguments
+    /// * `now` - The current time
+    ///
+    /// # Returns
+    /// The transition taken, or None if no timed transition applies, its timeout has not
+    /// elapsed, or it was rejected or suppressed
+    pub fn tick(&mut self, now: SystemTime) -> Option<StateTransition<S>> {
+        let (to, timeout) = self.timed_transitions.get(&self.current_state)?.clone();
+        let held = now.duration_since(self.entered_at).ok()?;
+        if held <= timeout {
+            return None;
+        }
+
+        let from = self.current_state.clone();
+        let reason = format!("State held for {:?}, timeout {:?}", held, timeout);
+        let outcome = self
+            .record_transition(
+                to.clone(),
+                Some(TransitionReason::Scheduled),
+                Some(reason.clone()),
+                now,
+            )
+            .ok()?;
+        match outcome {
+            // Built here since the transition may not have been recorded in history
+            TransitionOutcome::Applied => Some(StateTransition {
+                from,
+                to,
+                timestamp: now,
+                reason: Some(reason),
+                category: Some(TransitionReason::Scheduled),
+            }),
+            TransitionOutcome::Suppressed => None,
+        }
+    }
+
     /// Checks if transition to target state is allowed
     ///
     /// # Arguments
@@ -164,7 +509,8 @@ where
     pub fn can_transition_to(&self, target: &S) -> bool {
         self.allowed_transitions
             .get(&self.current_state)
-            .map_or(false, |allowed| allowed.contains(target))
+            .is_some_and(|allowed| allowed.contains(target))
+            || (self.wildcard_targets.contains(target) && *target != self.current_state)
     }
 
     /// Attempts to transition to new state
@@ -180,34 +526,197 @@ where
         new_state: S,
         reason: Option<String>,
     ) -> Result<(), CaptureError> {
+        self.apply_transition(new_state, reason).map(|_| ())
+    }
+
+    /// Attempts to transition to new state, reporting whether it was debounced
+    ///
+    /// # Arguments
+    /// * `new_state` - The target state
+    /// * `reason` - An optional reason for the transition
+    ///
+    /// # Returns
+    /// Whether the transition was applied or suppressed, or an error if it is not allowed
+    pub fn apply_transition(
+        &mut self,
+        new_state: S,
+        reason: Option<String>,
+    ) -> Result<TransitionOutcome, CaptureError> {
+        self.record_transition(new_state, None, reason, SystemTime::now())
+    }
+
+    /// Attempts to transition to new state with a typed cause
+    ///
+    /// # Arguments
+    /// * `new_state` - The target state
+    /// * `category` - The cause of the transition
+    /// * `detail` - Optional human-readable detail
+    ///
+    /// # Returns
+    /// Whether the transition was applied or suppressed, or an error if it is not allowed
+    pub fn apply_categorized_transition(
+        &mut self,
+        new_state: S,
+        category: TransitionReason,
+        detail: Option<String>,
+    ) -> Result<TransitionOutcome, CaptureError> {
+        self.record_transition(new_state, Some(category), detail, SystemTime::now())
+    }
+
+    /// Validates, debounces and records a transition
+    ///
+    /// The time taken from validation to commit, excluding observers, is fed to the metrics.
+    fn record_transition(
+        &mut self,
+        new_state: S,
+        category: Option<TransitionReason>,
+        reason: Option<String>,
+        now: SystemTime,
+    ) -> Result<TransitionOutcome, CaptureError> {
+        let started = Instant::now();
         if !self.can_transition_to(&new_state) {
             self.metrics
                 .failed_transitions
                 .fetch_add(1, Ordering::Relaxed);
+            self.notify_failed(&new_state);
             return Err(*CaptureError::new(
                 CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
                 "Invalid state transition",
             ));
         }
+        if let Err(e) = self.check_guard(&new_state) {
+            self.notify_failed(&new_state);
+            return Err(e);
+        }
+
+        if self.is_debounced(&new_state, now) {
+            self.metrics.record_suppressed_transition();
+            return Ok(TransitionOutcome::Suppressed);
+        }
+
+        // Invariants depend only on the entered state, so checking before committing leaves
+        // nothing to undo on a violation
+        if let Err(e) = self.check_invariants(&new_state) {
+            self.metrics.record_failed_transition();
+            self.notify_failed(&new_state);
+            return Err(e);
+        }
+
+        let history_full = self.history.len() >= self.max_history;
+        if history_full && self.history_overflow == HistoryOverflowPolicy::Reject {
+            self.metrics.record_failed_transition();
+            self.notify_failed(&new_state);
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded),
+                "State history is full",
+            ));
+        }
 
         let transition = StateTransition {
             from: self.current_state.clone(),
             to: new_state.clone(),
-            timestamp: SystemTime::now(),
+            timestamp: now,
             reason,
+            category,
         };
 
         // Update history
-        if self.history.len() >= self.max_history {
-            self.history.pop_front();
-        }
-        self.history.push_back(transition);
+        self.last_unrecorded = match (history_full, self.history_overflow) {
+            (true, HistoryOverflowPolicy::DropNewest) => {
+                self.spill(&transition);
+                Some(transition)
+            }
+            (true, _) => {
+                if let Some(evicted) = self.history.pop_front() {
+                    self.spill(&evicted);
+                }
+                self.history.push_back(transition);
+                None
+            }
+            (false, _) => {
+                self.history.push_back(transition);
+                None
+            }
+        };
 
         self.current_state = new_state;
-        self.metrics
-            .transitions_count
-            .fetch_add(1, Ordering::Relaxed);
-        Ok(())
+        self.entered_at = now;
+        let elapsed = u64::try_from(started.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        self.metrics.record_transition(elapsed);
+        if let Some(transition) = self.last_unrecorded.as_ref().or(self.history.back()) {
+            for observer in &self.observers.applied {
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| observer(transition)));
+            }
+        }
+        Ok(TransitionOutcome::Applied)
+    }
+
+    /// Checks the state a transition would enter against the invariants, if any are set
+    fn check_invariants(&self, new_state: &S) -> Result<(), CaptureError> {
+        let Some(broken) = self
+            .invariants
+            .as_ref()
+            .and_then(|checker| checker.first_violation(new_state))
+        else {
+            return Ok(());
+        };
+        Err(*CaptureError::new(
+            CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+            &format!("State invariant violated: {}", broken),
+        ))
+    }
+
+    /// Hands a transition dropped from history to the spill callback, if any
+    fn spill(&self, transition: &StateTransition<S>) {
+        if let Some(spill) = &self.observers.spill {
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| spill(transition)));
+        }
+    }
+
+    /// Reports a rejected transition to the failed transition callbacks
+    fn notify_failed(&self, attempted: &S) {
+        for observer in &self.observers.failed {
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                observer(&self.current_state, attempted)
+            }));
+        }
+    }
+
+    /// Evaluates the guard on the transition to `new_state`, if it has one
+    ///
+    /// A rejection or guard error counts as a failed transition.
+    fn check_guard(&self, new_state: &S) -> Result<(), CaptureError> {
+        let key = (self.current_state.clone(), new_state.clone());
+        let Some(guard) = self.guards.0.get(&key) else {
+            return Ok(());
+        };
+        match guard(&self.current_state, new_state) {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                self.metrics.record_failed_transition();
+                Err(*CaptureError::new(
+                    CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                    "State transition rejected by guard",
+                ))
+            }
+            Err(e) => {
+                self.metrics.record_failed_transition();
+                Err(e)
+            }
+        }
+    }
+
+    /// Checks whether a transition repeats the previous one within the debounce window
+    fn is_debounced(&self, new_state: &S, now: SystemTime) -> bool {
+        let (Some(window), Some(last)) = (self.debounce_window, self.history.back()) else {
+            return false;
+        };
+
+        last.from == self.current_state
+            && last.to == *new_state
+            && now
+                .duration_since(last.timestamp)
+                .map_or(true, |elapsed| elapsed <= window)
     }
 
     /// Returns current state
@@ -226,15 +735,238 @@ where
         &self.history
     }
 
+    /// Returns the transitions in history with the given cause
+    ///
+    /// # Arguments
+    /// * `category` - The cause to filter by
+    ///
+    /// # Returns
+    /// An iterator over matching transitions, oldest first
+    pub fn history_by_reason(
+        &self,
+        category: TransitionReason,
+    ) -> impl Iterator<Item = &StateTransition<S>> {
+        self.history
+            .iter()
+            .filter(move |transition| transition.category == Some(category))
+    }
+
+    /// Returns the transitions in history into a state
+    ///
+    /// # Arguments
+    /// * `state` - The target state to filter by
+    ///
+    /// # Returns
+    /// The matching transitions, oldest first
+    pub fn transitions_to(&self, state: &S) -> Vec<&StateTransition<S>> {
+        self.history.iter().filter(|t| t.to == *state).collect()
+    }
+
+    /// Returns the transitions in history out of a state
+    ///
+    /// # Arguments
+    /// * `state` - The source state to filter by
+    ///
+    /// # Returns
+    /// The matching transitions, oldest first
+    pub fn transitions_from(&self, state: &S) -> Vec<&StateTransition<S>> {
+        self.history.iter().filter(|t| t.from == *state).collect()
+    }
+
+    /// Returns the transitions in history at or after a time
+    ///
+    /// # Arguments
+    /// * `since` - The earliest timestamp to include
+    ///
+    /// # Returns
+    /// The matching transitions, oldest first
+    pub fn transitions_since(&self, since: SystemTime) -> Vec<&StateTransition<S>> {
+        self.history
+            .iter()
+            .filter(|t| t.timestamp >= since)
+            .collect()
+    }
+
+    /// Returns how long the machine has spent in a state
+    ///
+    /// # Arguments
+    /// * `state` - The state to measure
+    ///
+    /// # Returns
+    /// The total dwell time; see `time_in_state_at`
+    pub fn time_in_state(&self, state: &S) -> Duration {
+        self.time_in_state_at(state, SystemTime::now())
+    }
+
+    /// Returns how long the machine has spent in a state as of `now`
+    ///
+    /// Sums the intervals between consecutive transitions in history that entered `state`, plus
+    /// the time since `entered_at` if `state` is current. Time before the oldest retained
+    /// transition is not known and not counted.
+    ///
+    /// # Arguments
+    /// * `state` - The state to measure
+    /// * `now` - The current time
+    ///
+    /// # Returns
+    /// The total dwell time
+    pub fn time_in_state_at(&self, state: &S, now: SystemTime) -> Duration {
+        let mut total = self
+            .history
+            .iter()
+            .zip(self.history.iter().skip(1))
+            .filter(|(entered, _)| entered.to == *state)
+            .map(|(entered, left)| {
+                left.timestamp
+                    .duration_since(entered.timestamp)
+                    .unwrap_or_default()
+            })
+            .sum();
+        if self.current_state == *state {
+            total += now.duration_since(self.entered_at).unwrap_or_default();
+        }
+        total
+    }
+
+    /// Counts the transitions in history by cause
+    ///
+    /// # Returns
+    /// The number of transitions for each cause; uncategorized transitions are not counted
+    pub fn reason_counts(&self) -> HashMap<TransitionReason, usize> {
+        let mut counts = HashMap::new();
+        for category in self.history.iter().filter_map(|t| t.category) {
+            *counts.entry(category).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the most recent transition in history, which `rollback` would undo
+    ///
+    /// # Returns
+    /// The last transition in history, if any. `rollback` refuses to undo it if a later
+    /// transition was applied without being recorded
+    pub fn last_transition(&self) -> Option<&StateTransition<S>> {
+        self.history.back()
+    }
+
+    /// Undoes the most recent transition
+    ///
+    /// The transition is removed from history and the machine returns to its source state.
+    /// The reverse transition does not need to be allowed, and guards are not consulted.
+    ///
+    /// # Returns
+    /// An error if there is no transition in history to undo, or if the most recent
+    /// transition was not recorded because of the `DropNewest` overflow policy
+    pub fn rollback(&mut self) -> Result<(), CaptureError> {
+        if self.last_unrecorded.is_some() {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                "Most recent transition is not in history",
+            ));
+        }
+        let Some(last) = self.history.pop_back() else {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                "No transition to roll back",
+            ));
+        };
+        self.current_state = last.from;
+        self.entered_at = SystemTime::now();
+        self.metrics.record_rollback();
+        Ok(())
+    }
+
     /// Clears transition history
     ///
     /// # Returns
     /// A reference to the state machine
     pub fn clear_history(&mut self) {
         self.history.clear();
+        self.last_unrecorded = None;
+    }
+
+    /// Returns transition metrics
+    ///
+    /// # Returns
+    /// A reference to the state machine metrics
+    pub fn metrics(&self) -> &StateMetrics {
+        &self.metrics
     }
 }
 
+impl<S> StateMachine<S>
+where
+    S: Clone + Eq + Hash + Debug,
+{
+    /// Renders the allowed transitions as a Graphviz DOT graph
+    ///
+    /// States are labelled with their `Debug` output and the current state is filled. Wildcard
+    /// transitions are drawn dashed from a `*` node. Nodes and edges are sorted by label so the
+    /// output is stable across runs.
+    ///
+    /// # Returns
+    /// The graph in DOT syntax
+    pub fn to_dot(&self) -> String {
+        let label = |state: &S| {
+            format!("{:?}", state)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        };
+
+        let mut nodes = BTreeSet::new();
+        let mut edges = BTreeSet::new();
+        nodes.insert(label(&self.current_state));
+        for (from, targets) in &self.allowed_transitions {
+            let from = label(from);
+            nodes.insert(from.clone());
+            for to in targets {
+                let to = label(to);
+                nodes.insert(to.clone());
+                edges.insert((from.clone(), to));
+            }
+        }
+        let wildcards: BTreeSet<String> = self.wildcard_targets.iter().map(label).collect();
+        nodes.extend(wildcards.iter().cloned());
+
+        let current = label(&self.current_state);
+        let mut dot = String::from("digraph StateMachine {\n");
+        for node in &nodes {
+            if *node == current {
+                let _ = writeln!(dot, "    \"{}\" [style=filled, fillcolor=lightblue];", node);
+            } else {
+                let _ = writeln!(dot, "    \"{}\";", node);
+            }
+        }
+        for (from, to) in &edges {
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\";", from, to);
+        }
+        for to in &wildcards {
+            let _ = writeln!(dot, "    \"*\" -> \"{}\" [style=dashed];", to);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Point-in-time copy of `StateMetrics`
+///
+/// # Fields
+/// * `transitions_count` - The number of transitions
+/// * `failed_transitions` - The number of failed transitions
+/// * `average_transition_time` - The average transition time in nanoseconds
+/// * `ema_transition_time` - The recency-weighted transition time, if enabled
+/// * `suppressed_transitions` - The number of transitions suppressed by debouncing
+/// * `rollbacks` - The number of transitions undone by rollback
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateMetricsSnapshot {
+    pub transitions_count: u64,
+    pub failed_transitions: u64,
+    pub average_transition_time: u64,
+    pub ema_transition_time: Option<u64>,
+    pub suppressed_transitions: u64,
+    pub rollbacks: u64,
+}
+
 /// Metrics for state machine transitions
 ///
 /// The state metrics capture information about the number of transitions, failed transitions,
@@ -244,11 +976,17 @@ where
 /// * `transitions_count` - The total number of transitions
 /// * `failed_transitions` - The total number of failed transitions
 /// * `average_transition_time` - The average transition time in nanoseconds
+/// * `suppressed_transitions` - The total number of transitions suppressed by debouncing
+/// * `rollbacks` - The total number of transitions undone by rollback
+/// * `ema_transition_time` - Recency-weighted transition time, if enabled
 #[derive(Debug, Default)]
 pub struct StateMetrics {
     transitions_count: AtomicU64,
     failed_transitions: AtomicU64,
     average_transition_time: AtomicU64,
+    suppressed_transitions: AtomicU64,
+    rollbacks: AtomicU64,
+    ema_transition_time: Option<ExponentialMovingAverage>,
 }
 
 impl StateMetrics {
@@ -261,9 +999,26 @@ impl StateMetrics {
             transitions_count: AtomicU64::new(0),
             failed_transitions: AtomicU64::new(0),
             average_transition_time: AtomicU64::new(0),
+            suppressed_transitions: AtomicU64::new(0),
+            rollbacks: AtomicU64::new(0),
+            ema_transition_time: None,
         }
     }
 
+    /// Creates a StateMetrics instance that also tracks an exponential moving average
+    ///
+    /// # Arguments
+    /// * `alpha` - Weight of each new sample, in (0, 1]
+    ///
+    /// # Returns
+    /// A new StateMetrics instance, or a configuration error if `alpha` is out of range
+    pub fn with_ema(alpha: f64) -> Result<Self, CaptureError> {
+        Ok(Self {
+            ema_transition_time: Some(ExponentialMovingAverage::new(alpha)?),
+            ..Self::new()
+        })
+    }
+
     /// Records a successful transition
     ///
     /// # Arguments
@@ -288,6 +1043,9 @@ impl StateMetrics {
 
         self.average_transition_time
             .store(new_avg, Ordering::Relaxed);
+        if let Some(ema) = &self.ema_transition_time {
+            ema.record(duration_ns);
+        }
     }
 
     /// Records a failed transition
@@ -298,6 +1056,32 @@ impl StateMetrics {
         self.failed_transitions.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Records a transition suppressed by debouncing
+    pub fn record_suppressed_transition(&self) {
+        self.suppressed_transitions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a transition undone by rollback
+    pub fn record_rollback(&self) {
+        self.rollbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the total number of transitions undone by rollback
+    ///
+    /// # Returns
+    /// The total number of rollbacks
+    pub fn rollbacks(&self) -> u64 {
+        self.rollbacks.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total number of transitions suppressed by debouncing
+    ///
+    /// # Returns
+    /// The total number of suppressed transitions
+    pub fn suppressed_transitions(&self) -> u64 {
+        self.suppressed_transitions.load(Ordering::Relaxed)
+    }
+
     /// Returns the total number of transitions
     ///
     /// # Returns
@@ -321,6 +1105,126 @@ impl StateMetrics {
     pub fn average_transition_time(&self) -> u64 {
         self.average_transition_time.load(Ordering::Relaxed)
     }
+
+    /// Returns the exponential moving average of transition times
+    ///
+    /// # Returns
+    /// The recency-weighted transition time in nanoseconds, or None if the average was not
+    /// enabled at construction
+    pub fn ema_transition_time(&self) -> Option<u64> {
+        self.ema_transition_time
+            .as_ref()
+            .map(ExponentialMovingAverage::value)
+    }
+
+    /// Copies the metrics and zeroes them, so each call covers only the interval since the last
+    ///
+    /// Each counter is read and zeroed in one atomic swap, so a transition recorded
+    /// concurrently is counted in exactly one snapshot. The snapshot is not atomic across
+    /// counters: the averages of an interval may include a transition counted in the previous
+    /// one.
+    ///
+    /// # Returns
+    /// The metrics recorded since the previous reset
+    pub fn snapshot_and_reset(&self) -> StateMetricsSnapshot {
+        StateMetricsSnapshot {
+            transitions_count: self.transitions_count.swap(0, Ordering::Relaxed),
+            failed_transitions: self.failed_transitions.swap(0, Ordering::Relaxed),
+            average_transition_time: self.average_transition_time.swap(0, Ordering::Relaxed),
+            ema_transition_time: self
+                .ema_transition_time
+                .as_ref()
+                .map(ExponentialMovingAverage::take),
+            suppressed_transitions: self.suppressed_transitions.swap(0, Ordering::Relaxed),
+            rollbacks: self.rollbacks.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Exponential moving average of durations
+///
+/// Each sample moves the average `alpha` of the way towards it, so older samples decay
+/// geometrically instead of being weighted equally forever. The first sample seeds the average.
+///
+/// # Fields
+/// * `alpha` - Weight of each new sample, in (0, 1]
+/// * `bits` - Current average as `f64` bits; NaN until the first sample
+#[derive(Debug)]
+pub struct ExponentialMovingAverage {
+    alpha: f64,
+    bits: AtomicU64,
+}
+
+impl ExponentialMovingAverage {
+    /// Creates an empty average
+    ///
+    /// # Arguments
+    /// * `alpha` - Weight of each new sample, in (0, 1]
+    ///
+    /// # Returns
+    /// A new average, or a configuration error if `alpha` is out of range
+    pub fn new(alpha: f64) -> Result<Self, CaptureError> {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "EMA alpha must be greater than 0 and at most 1",
+            ));
+        }
+        Ok(Self {
+            alpha,
+            bits: AtomicU64::new(f64::NAN.to_bits()),
+        })
+    }
+
+    /// Returns the weight of each new sample
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Adds a sample
+    ///
+    /// # Arguments
+    /// * `sample` - Duration in nanoseconds
+    pub fn record(&self, sample: u64) {
+        let sample = sample as f64;
+        // Never fails: the closure always returns Some
+        let _ = self
+            .bits
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                let current = f64::from_bits(bits);
+                let next = if current.is_nan() {
+                    sample
+                } else {
+                    current + self.alpha * (sample - current)
+                };
+                Some(next.to_bits())
+            });
+    }
+
+    /// Returns the current average
+    ///
+    /// # Returns
+    /// The average in nanoseconds, rounded, or 0 before the first sample
+    pub fn value(&self) -> u64 {
+        Self::rounded(self.bits.load(Ordering::Relaxed))
+    }
+
+    /// Returns the current average and empties it, so the next sample seeds a new one
+    ///
+    /// # Returns
+    /// The average in nanoseconds, rounded, or 0 if there were no samples
+    pub fn take(&self) -> u64 {
+        Self::rounded(self.bits.swap(f64::NAN.to_bits(), Ordering::Relaxed))
+    }
+
+    fn rounded(bits: u64) -> u64 {
+        let value = f64::from_bits(bits);
+        if value.is_nan() {
+            0
+        } else {
+            value.round() as u64
+        }
+    }
 }
 
 /// Builder pattern for state machine configuration
@@ -333,14 +1237,26 @@ impl StateMetrics {
 /// # Fields
 /// * `initial_state` - The initial state of the state machine
 /// * `transitions` - A list of allowed transitions between states
+/// * `wildcard_targets` - States reachable from every other state
 /// * `max_history` - The maximum number of transitions to keep in history
+/// * `history_overflow` - What happens when a transition arrives with history full
+/// * `history_spill` - Callback receiving transitions dropped from history
+/// * `debounce_window` - Optional window for suppressing repeated transitions
+/// * `timed_transitions` - Transitions taken when their source state expires
+/// * `ema_alpha` - Weight of each sample in the transition time moving average, if enabled
 pub struct StateMachineBuilder<S>
 where
     S: Clone + Eq + Hash,
 {
     initial_state: Option<S>,
     transitions: Vec<(S, S)>,
+    wildcard_targets: Vec<S>,
     max_history: usize,
+    history_overflow: HistoryOverflowPolicy,
+    history_spill: Option<TransitionObserver<S>>,
+    debounce_window: Option<Duration>,
+    timed_transitions: Vec<(S, S, Duration)>,
+    ema_alpha: Option<f64>,
 }
 
 impl<S> StateMachineBuilder<S>
@@ -355,7 +1271,13 @@ where
         StateMachineBuilder {
             initial_state: None,
             transitions: Vec::new(),
+            wildcard_targets: Vec::new(),
             max_history: 100,
+            history_overflow: HistoryOverflowPolicy::default(),
+            history_spill: None,
+            debounce_window: None,
+            timed_transitions: Vec::new(),
+            ema_alpha: None,
         }
     }
 
@@ -383,6 +1305,30 @@ where
         self
     }
 
+    /// Sets what happens when a transition is applied while history is full
+    ///
+    /// # Arguments
+    /// * `policy` - The overflow policy; `DropOldest` by default
+    ///
+    /// # Returns
+    /// A reference to the state machine builder
+    pub fn history_overflow_policy(mut self, policy: HistoryOverflowPolicy) -> Self {
+        self.history_overflow = policy;
+        self
+    }
+
+    /// Sets a callback receiving each transition dropped from history on overflow
+    ///
+    /// # Arguments
+    /// * `spill` - Called with the dropped transition before it is lost
+    ///
+    /// # Returns
+    /// A reference to the state machine builder
+    pub fn history_spill(mut self, spill: TransitionObserver<S>) -> Self {
+        self.history_spill = Some(spill);
+        self
+    }
+
     /// Adds a valid state transition
     ///
     /// # Arguments
@@ -396,6 +1342,56 @@ where
         self
     }
 
+    /// Makes a state reachable from every other state, such as an error state
+    ///
+    /// # Arguments
+    /// * `to` - The target state
+    ///
+    /// # Returns
+    /// A reference to the state machine builder
+    pub fn add_transition_from_any(mut self, to: S) -> Self {
+        self.wildcard_targets.push(to);
+        self
+    }
+
+    /// Adds a transition taken by `tick` once `from` has been held longer than `timeout`
+    ///
+    /// # Arguments
+    /// * `from` - The state that expires
+    /// * `to` - The state entered on expiry
+    /// * `timeout` - How long `from` may be held
+    ///
+    /// # Returns
+    /// A reference to the state machine builder
+    pub fn add_timed_transition(mut self, from: S, to: S, timeout: Duration) -> Self {
+        self.timed_transitions.push((from, to, timeout));
+        self
+    }
+
+    /// Enables debouncing of repeated transitions
+    ///
+    /// # Arguments
+    /// * `window` - Window within which a repeat of the previous transition is suppressed
+    ///
+    /// # Returns
+    /// A reference to the state machine builder
+    pub fn debounce_window(mut self, window: Duration) -> Self {
+        self.debounce_window = Some(window);
+        self
+    }
+
+    /// Tracks an exponential moving average of transition times alongside the cumulative one
+    ///
+    /// # Arguments
+    /// * `alpha` - Weight of each new sample, in (0, 1]
+    ///
+    /// # Returns
+    /// A reference to the state machine builder
+    pub fn ema_alpha(mut self, alpha: f64) -> Self {
+        self.ema_alpha = Some(alpha);
+        self
+    }
+
     /// Builds and validates the StateMachine configuration
     ///
     /// # Returns
@@ -427,11 +1423,23 @@ where
         }
 
         let mut machine = StateMachine::new(self.initial_state.unwrap(), self.max_history)?;
+        machine.set_debounce_window(self.debounce_window);
+        machine.set_history_overflow_policy(self.history_overflow);
+        machine.set_history_spill(self.history_spill);
+        if let Some(alpha) = self.ema_alpha {
+            machine.metrics = StateMetrics::with_ema(alpha)?;
+        }
 
         // Register all transitions
         for (from, to) in self.transitions {
             machine.add_transition(from, to);
         }
+        for to in self.wildcard_targets {
+            machine.add_transition_from_any(to);
+        }
+        for (from, to, timeout) in self.timed_transitions {
+            machine.add_timed_transition(from, to, timeout);
+        }
 
         Ok(machine)
     }
@@ -529,6 +1537,102 @@ mod tests {
         assert_eq!(sm.history().len(), 2);
     }
 
+    /// Machine with history of 2 that has filled it with Initial -> Processing -> Complete
+    fn full_history(
+        policy: HistoryOverflowPolicy,
+    ) -> (
+        StateMachine<TestState>,
+        Arc<std::sync::Mutex<Vec<TestState>>>,
+    ) {
+        let spilled = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let spilled_clone = Arc::clone(&spilled);
+        let mut sm = StateMachineBuilder::new()
+            .initial_state(TestState::Initial)
+            .max_history(2)
+            .history_overflow_policy(policy)
+            .history_spill(Arc::new(move |t: &StateTransition<TestState>| {
+                spilled_clone.lock().unwrap().push(*t.to());
+            }))
+            .add_transition(TestState::Initial, TestState::Processing)
+            .add_transition(TestState::Processing, TestState::Complete)
+            .add_transition(TestState::Complete, TestState::End)
+            .build()
+            .unwrap();
+        sm.transition_to(TestState::Processing, None).unwrap();
+        sm.transition_to(TestState::Complete, None).unwrap();
+        assert_eq!(sm.history().len(), 2);
+        assert!(spilled.lock().unwrap().is_empty());
+        (sm, spilled)
+    }
+
+    fn history_targets(sm: &StateMachine<TestState>) -> Vec<TestState> {
+        sm.history().iter().map(|t| *t.to()).collect()
+    }
+
+    #[test]
+    fn test_overflow_drop_oldest_spills_evicted() {
+        let (mut sm, spilled) = full_history(HistoryOverflowPolicy::DropOldest);
+        sm.transition_to(TestState::End, None).unwrap();
+        assert_eq!(sm.current_state(), &TestState::End);
+        assert_eq!(history_targets(&sm), [TestState::Complete, TestState::End]);
+        assert_eq!(*spilled.lock().unwrap(), [TestState::Processing]);
+    }
+
+    #[test]
+    fn test_overflow_drop_newest_keeps_history() {
+        let (mut sm, spilled) = full_history(HistoryOverflowPolicy::DropNewest);
+        sm.transition_to(TestState::End, None).unwrap();
+        assert_eq!(sm.current_state(), &TestState::End);
+        assert_eq!(
+            history_targets(&sm),
+            [TestState::Processing, TestState::Complete]
+        );
+        assert_eq!(*spilled.lock().unwrap(), [TestState::End]);
+        // The last recorded transition no longer leads to the current state
+        assert!(sm.rollback().is_err());
+        assert_eq!(sm.current_state(), &TestState::End);
+    }
+
+    #[test]
+    fn test_drop_newest_cycle_is_not_rolled_back() {
+        let (mut sm, spilled) = full_history(HistoryOverflowPolicy::DropNewest);
+        sm.add_transition(TestState::Complete, TestState::Processing);
+
+        // Both legs of the cycle are dropped, so the last recorded transition leads to the
+        // current state again without being the one that entered it
+        sm.transition_to(TestState::Processing, None).unwrap();
+        sm.transition_to(TestState::Complete, None).unwrap();
+        assert_eq!(
+            *spilled.lock().unwrap(),
+            [TestState::Processing, TestState::Complete]
+        );
+        assert_eq!(sm.last_transition().unwrap().to(), sm.current_state());
+
+        let err = sm.rollback().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::StateError)
+        ));
+        assert_eq!(sm.current_state(), &TestState::Complete);
+        assert_eq!(sm.history().len(), 2);
+    }
+
+    #[test]
+    fn test_overflow_reject_refuses_transition() {
+        let (mut sm, spilled) = full_history(HistoryOverflowPolicy::Reject);
+        let err = sm.transition_to(TestState::End, None).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded)
+        ));
+        assert_eq!(sm.current_state(), &TestState::Complete);
+        assert_eq!(sm.metrics().failed_transitions(), 1);
+        assert!(spilled.lock().unwrap().is_empty());
+
+        sm.clear_history();
+        sm.transition_to(TestState::End, None).unwrap();
+    }
+
     #[test]
     fn test_transition_with_no_reason() {
         let mut sm = setup();
@@ -693,7 +1797,7 @@ mod tests {
         sm.add_transition(TestState::Processing, TestState::Complete);
 
         let should_succeed = sm.can_transition_to(&TestState::Complete);
-        assert_eq!(should_succeed, false); // Can't skip Processing
+        assert!(!should_succeed); // Can't skip Processing
 
         assert!(sm.can_transition_to(&TestState::Processing));
         sm.transition_to(TestState::Processing, None).unwrap();
@@ -871,8 +1975,9 @@ mod tests {
             .unwrap();
 
         // Should have some reasonable default for max_history
-        assert!(sm.history().len() == 0);
-        sm.transition_to(TestState::Processing, None).err().unwrap(); // Should fail as no transitions defined
+        assert!(sm.history().is_empty());
+        // Should fail as no transitions defined
+        sm.transition_to(TestState::Processing, None).err().unwrap();
     }
 
     #[test]
@@ -900,6 +2005,57 @@ mod tests {
         assert_eq!(metrics.average_transition_time(), 150); // (100 + 200) / 2
     }
 
+    #[test]
+    fn test_applied_transitions_feed_transition_time() {
+        let mut sm: StateMachine<TestState> = StateMachineBuilder::new()
+            .initial_state(TestState::Initial)
+            .add_transition(TestState::Processing, TestState::Complete)
+            .ema_alpha(0.5)
+            .build()
+            .unwrap();
+        // The guard makes the transition take measurably long
+        sm.add_guarded_transition(
+            TestState::Initial,
+            TestState::Processing,
+            Arc::new(|_, _| {
+                std::thread::sleep(Duration::from_millis(2));
+                Ok(true)
+            }),
+        );
+        assert_eq!(sm.metrics().ema_transition_time(), Some(0));
+
+        sm.transition_to(TestState::Processing, None).unwrap();
+        let ema = sm.metrics().ema_transition_time().unwrap();
+        assert!(ema >= 2_000_000, "{}", ema);
+        assert_eq!(sm.metrics().average_transition_time(), ema);
+
+        sm.transition_to(TestState::Complete, None).unwrap();
+        assert!(sm.metrics().ema_transition_time().unwrap() < ema);
+        assert_eq!(sm.metrics().transitions_count(), 2);
+    }
+
+    #[test]
+    fn test_transition_ema_weights_recent_samples() {
+        let sm: StateMachine<TestState> = StateMachineBuilder::new()
+            .initial_state(TestState::Initial)
+            .ema_alpha(0.5)
+            .build()
+            .unwrap();
+        let metrics = sm.metrics();
+        metrics.record_transition(100);
+        metrics.record_transition(300);
+        metrics.record_transition(300);
+        assert_eq!(metrics.ema_transition_time(), Some(250));
+        assert_eq!(metrics.average_transition_time(), 233);
+        assert_eq!(StateMetrics::new().ema_transition_time(), None);
+
+        let invalid = StateMachineBuilder::new()
+            .initial_state(TestState::Initial)
+            .ema_alpha(2.0)
+            .build();
+        assert!(invalid.is_err());
+    }
+
     #[test]
     fn test_record_failed_transitions() {
         let metrics = StateMetrics::new();
@@ -929,6 +2085,57 @@ mod tests {
         assert_eq!(metrics_arc.average_transition_time(), 100);
     }
 
+    #[test]
+    fn test_snapshot_and_reset_under_concurrent_recording() {
+        use std::sync::atomic::AtomicBool;
+
+        let metrics = Arc::new(StateMetrics::new());
+        let recording = Arc::new(AtomicBool::new(true));
+        let (threads, samples) = (4, 20_000u64);
+
+        let recorders: Vec<_> = (0..threads)
+            .map(|_| {
+                let metrics = Arc::clone(&metrics);
+                thread::spawn(move || {
+                    for _ in 0..samples {
+                        metrics.record_transition(100);
+                        metrics.record_failed_transition();
+                        metrics.record_suppressed_transition();
+                    }
+                })
+            })
+            .collect();
+        let resetter = {
+            let (metrics, recording) = (Arc::clone(&metrics), Arc::clone(&recording));
+            thread::spawn(move || {
+                let mut snapshots = Vec::new();
+                while recording.load(Ordering::Relaxed) {
+                    snapshots.push(metrics.snapshot_and_reset());
+                    thread::yield_now();
+                }
+                snapshots
+            })
+        };
+        for recorder in recorders {
+            recorder.join().unwrap();
+        }
+        recording.store(false, Ordering::Relaxed);
+        let mut snapshots = resetter.join().unwrap();
+        snapshots.push(metrics.snapshot_and_reset());
+
+        let total = threads * samples;
+        let sum = |f: fn(&StateMetricsSnapshot) -> u64| snapshots.iter().map(f).sum::<u64>();
+        assert_eq!(sum(|s| s.transitions_count), total);
+        assert_eq!(sum(|s| s.failed_transitions), total);
+        assert_eq!(sum(|s| s.suppressed_transitions), total);
+        // Averages may straddle intervals but never exceed the recorded duration
+        assert!(snapshots.iter().all(|s| s.average_transition_time <= 100));
+        assert_eq!(
+            metrics.snapshot_and_reset(),
+            StateMetricsSnapshot::default()
+        );
+    }
+
     #[test]
     fn test_concurrent_failed_transitions() {
         let metrics = StateMetrics::new();
@@ -982,6 +2189,419 @@ mod tests {
         assert!(diff < Duration::from_secs(1));
     }
 
+    #[test]
+    fn test_transitions_carry_typed_reason() {
+        let mut sm = setup();
+        sm.apply_categorized_transition(
+            TestState::Processing,
+            TransitionReason::OperatorCommand,
+            Some("start requested by admin".to_string()),
+        )
+        .unwrap();
+        sm.apply_categorized_transition(TestState::Error, TransitionReason::Pressure, None)
+            .unwrap();
+
+        let last = sm.history().back().unwrap();
+        assert_eq!(last.category(), Some(TransitionReason::Pressure));
+        assert!(last.reason().is_none());
+        let first = sm.history().front().unwrap();
+        assert_eq!(first.category(), Some(TransitionReason::OperatorCommand));
+        assert_eq!(
+            first.reason().map(String::as_str),
+            Some("start requested by admin")
+        );
+
+        let untyped = StateTransition::new(TestState::Initial, TestState::Processing, None);
+        assert!(untyped.category().is_none());
+        let typed = untyped.with_category(TransitionReason::Scheduled);
+        assert_eq!(typed.category(), Some(TransitionReason::Scheduled));
+        assert_eq!(
+            serde_json::to_string(&TransitionReason::CloudLifecycle).unwrap(),
+            "\"cloud_lifecycle\""
+        );
+    }
+
+    #[test]
+    fn test_history_filtered_by_reason() {
+        let mut sm = StateMachine::new(TestState::Initial, 10).unwrap();
+        sm.add_transition(TestState::Initial, TestState::Processing);
+        sm.add_transition(TestState::Processing, TestState::Error);
+        sm.add_transition(TestState::Error, TestState::Initial);
+        for _ in 0..2 {
+            sm.apply_categorized_transition(
+                TestState::Processing,
+                TransitionReason::Scheduled,
+                None,
+            )
+            .unwrap();
+            sm.apply_categorized_transition(TestState::Error, TransitionReason::Error, None)
+                .unwrap();
+            sm.apply_categorized_transition(TestState::Initial, TransitionReason::Recovery, None)
+                .unwrap();
+        }
+        sm.transition_to(TestState::Processing, Some("untyped".to_string()))
+            .unwrap();
+
+        let recoveries: Vec<_> = sm.history_by_reason(TransitionReason::Recovery).collect();
+        assert_eq!(recoveries.len(), 2);
+        assert!(recoveries.iter().all(|t| *t.to() == TestState::Initial));
+        assert_eq!(sm.history_by_reason(TransitionReason::Pressure).count(), 0);
+
+        let counts = sm.reason_counts();
+        assert_eq!(counts[&TransitionReason::Scheduled], 2);
+        assert_eq!(counts[&TransitionReason::Error], 2);
+        assert_eq!(counts.values().sum::<usize>(), 6);
+    }
+
+    #[test]
+    fn test_to_dot_is_sorted_and_highlights_current_state() {
+        let mut sm = setup();
+        sm.add_transition(TestState::Error, TestState::Error);
+        sm.add_transition(TestState::Error, TestState::Initial);
+        sm.transition_to(TestState::Processing, None).unwrap();
+
+        let expected = "digraph StateMachine {
+    \"Complete\";
+    \"Error\";
+    \"Initial\";
+    \"Processing\" [style=filled, fillcolor=lightblue];
+    \"Error\" -> \"Error\";
+    \"Error\" -> \"Initial\";
+    \"Initial\" -> \"Processing\";
+    \"Processing\" -> \"Complete\";
+    \"Processing\" -> \"Error\";
+}
+";
+        assert_eq!(sm.to_dot(), expected);
+        assert_eq!(sm.to_dot(), expected);
+    }
+
+    #[test]
+    fn test_wildcard_transition_reaches_unconnected_state() {
+        let mut sm = StateMachineBuilder::new()
+            .initial_state(TestState::Initial)
+            .add_transition(TestState::Initial, TestState::Processing)
+            .add_transition_from_any(TestState::Error)
+            .build()
+            .unwrap();
+        // Reviewing only appears in an edge added after the wildcard
+        sm.add_transition(TestState::Error, TestState::Reviewing);
+
+        assert!(sm.can_transition_to(&TestState::Error));
+        assert!(!sm.can_transition_to(&TestState::Complete));
+        sm.transition_to(TestState::Processing, None).unwrap();
+        sm.transition_to(TestState::Error, None).unwrap();
+        assert!(!sm.can_transition_to(&TestState::Error));
+        sm.transition_to(TestState::Reviewing, None).unwrap();
+        sm.transition_to(TestState::Error, Some("failed".to_string()))
+            .unwrap();
+
+        let last = sm.last_transition().unwrap();
+        assert_eq!(last.from(), &TestState::Reviewing);
+        assert_eq!(last.to(), &TestState::Error);
+        assert_eq!(sm.metrics().transitions_count(), 4);
+        assert!(sm.to_dot().contains("\"*\" -> \"Error\" [style=dashed];"));
+    }
+
+    #[test]
+    fn test_explicit_guard_applies_over_wildcard() {
+        let mut sm = StateMachine::new(TestState::Initial, 5).unwrap();
+        sm.add_transition_from_any(TestState::Error);
+        sm.add_guarded_transition(
+            TestState::Initial,
+            TestState::Error,
+            Arc::new(|_, _| Ok(false)),
+        );
+        assert!(sm.transition_to(TestState::Error, None).is_err());
+        assert_eq!(sm.current_state(), &TestState::Initial);
+    }
+
+    #[test]
+    fn test_history_queries_and_dwell_time() {
+        let mut sm = setup();
+        sm.add_transition(TestState::Error, TestState::Processing);
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        for (secs, to) in [
+            (0, TestState::Processing),
+            (10, TestState::Error),
+            (15, TestState::Processing),
+            (45, TestState::Complete),
+        ] {
+            sm.record_transition(to, None, None, at(secs)).unwrap();
+        }
+
+        let into_processing = sm.transitions_to(&TestState::Processing);
+        assert_eq!(into_processing.len(), 2);
+        assert_eq!(into_processing[1].from(), &TestState::Error);
+        assert_eq!(sm.transitions_from(&TestState::Processing).len(), 2);
+        assert!(sm.transitions_from(&TestState::Complete).is_empty());
+        let recent = sm.transitions_since(at(15));
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].timestamp(), at(15));
+
+        // Processing for 0..10 and 15..45, Error for 10..15
+        assert_eq!(
+            sm.time_in_state_at(&TestState::Processing, at(100)),
+            Duration::from_secs(40)
+        );
+        assert_eq!(
+            sm.time_in_state_at(&TestState::Error, at(100)),
+            Duration::from_secs(5)
+        );
+        // Complete is current, entered at 45
+        assert_eq!(
+            sm.time_in_state_at(&TestState::Complete, at(100)),
+            Duration::from_secs(55)
+        );
+        assert_eq!(
+            sm.time_in_state_at(&TestState::Initial, at(100)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_observers_called_in_order() {
+        let mut sm = setup();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        for id in 0..2 {
+            let seen = Arc::clone(&seen);
+            sm.on_transition(Arc::new(move |t: &StateTransition<TestState>| {
+                seen.lock().unwrap().push((id, *t.from(), *t.to()));
+            }));
+        }
+        let failed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let failed_clone = Arc::clone(&failed);
+        sm.on_failed_transition(Arc::new(move |from: &TestState, to: &TestState| {
+            failed_clone.lock().unwrap().push((*from, *to));
+        }));
+
+        sm.transition_to(TestState::Processing, None).unwrap();
+        assert!(sm.transition_to(TestState::Initial, None).is_err());
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                (0, TestState::Initial, TestState::Processing),
+                (1, TestState::Initial, TestState::Processing)
+            ]
+        );
+        assert_eq!(
+            *failed.lock().unwrap(),
+            [(TestState::Processing, TestState::Initial)]
+        );
+    }
+
+    #[test]
+    fn test_panicking_observer_is_isolated() {
+        let mut sm = setup();
+        let calls = Arc::new(AtomicU64::new(0));
+        sm.on_transition(Arc::new(|_: &StateTransition<TestState>| {
+            panic!("observer failure")
+        }));
+        let calls_clone = Arc::clone(&calls);
+        sm.on_transition(Arc::new(move |_: &StateTransition<TestState>| {
+            calls_clone.fetch_add(1, Ordering::Relaxed);
+        }));
+        sm.on_failed_transition(Arc::new(|_: &TestState, _: &TestState| {
+            panic!("failed observer failure")
+        }));
+
+        sm.transition_to(TestState::Processing, None).unwrap();
+        assert!(sm.transition_to(TestState::Initial, None).is_err());
+        sm.transition_to(TestState::Complete, None).unwrap();
+
+        assert_eq!(sm.current_state(), &TestState::Complete);
+        assert_eq!(sm.history().len(), 2);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_invariant_rejects_allowed_transition() {
+        let mut sm = setup();
+        sm.set_invariant_checker(Some(
+            InvariantChecker::new()
+                .invariant("not_initial", |s: &TestState| *s != TestState::Initial)
+                .invariant("no_error", |s: &TestState| *s != TestState::Error),
+        ));
+        let failed = Arc::new(AtomicU64::new(0));
+        let failed_clone = Arc::clone(&failed);
+        sm.on_failed_transition(Arc::new(move |_: &TestState, _: &TestState| {
+            failed_clone.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        sm.transition_to(TestState::Processing, None).unwrap();
+        let entered = sm.entered_at();
+        assert!(sm.can_transition_to(&TestState::Error));
+        let err = sm.transition_to(TestState::Error, None).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::InvalidState)
+        ));
+        assert!(err.message().contains("no_error"), "{}", err.message());
+
+        assert_eq!(sm.current_state(), &TestState::Processing);
+        assert_eq!(sm.entered_at(), entered);
+        assert_eq!(sm.history().len(), 1);
+        assert_eq!(sm.metrics().transitions_count(), 1);
+        assert_eq!(sm.metrics().failed_transitions(), 1);
+        assert_eq!(failed.load(Ordering::Relaxed), 1);
+
+        sm.set_invariant_checker(None);
+        sm.transition_to(TestState::Error, None).unwrap();
+    }
+
+    #[test]
+    fn test_to_dot_escapes_labels() {
+        let mut sm = StateMachine::new("say \"hi\"".to_string(), 1).unwrap();
+        sm.add_transition("say \"hi\"".to_string(), "done".to_string());
+        let dot = sm.to_dot();
+        assert!(
+            dot.contains(r#""\"say \\\"hi\\\"\"" -> "\"done\"";"#),
+            "{}",
+            dot
+        );
+    }
+
+    #[test]
+    fn test_guarded_transition_checks_predicate() {
+        let drained = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let mut sm = setup();
+        let flag = drained.clone();
+        sm.add_guarded_transition(
+            TestState::Processing,
+            TestState::Complete,
+            Arc::new(move |from, to| {
+                assert_eq!((*from, *to), (TestState::Processing, TestState::Complete));
+                Ok(flag.load(Ordering::SeqCst))
+            }),
+        );
+        sm.transition_to(TestState::Processing, None).unwrap();
+
+        let err = sm.transition_to(TestState::Complete, None).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::InvalidState)
+        ));
+        assert_eq!(*sm.current_state(), TestState::Processing);
+        assert_eq!(sm.metrics().failed_transitions(), 1);
+
+        drained.store(true, Ordering::SeqCst);
+        sm.transition_to(TestState::Complete, None).unwrap();
+        assert_eq!(*sm.current_state(), TestState::Complete);
+        assert_eq!(sm.history().len(), 2);
+    }
+
+    #[test]
+    fn test_guard_error_propagates() {
+        let mut sm = setup();
+        sm.add_guarded_transition(
+            TestState::Initial,
+            TestState::Processing,
+            Arc::new(|_, _| {
+                Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    "probe failed",
+                ))
+            }),
+        );
+
+        let err = sm.transition_to(TestState::Processing, None).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue)
+        ));
+        assert_eq!(*sm.current_state(), TestState::Initial);
+        assert_eq!(sm.metrics().failed_transitions(), 1);
+
+        // Guarding an already allowed transition does not duplicate it
+        assert_eq!(sm.allowed_transitions[&TestState::Initial].len(), 1);
+    }
+
+    #[test]
+    fn test_rollback_restores_previous_state() {
+        let mut sm = setup();
+        sm.transition_to(TestState::Processing, None).unwrap();
+        sm.transition_to(TestState::Complete, Some("done".to_string()))
+            .unwrap();
+
+        let last = sm.last_transition().unwrap();
+        assert_eq!(
+            (*last.from(), *last.to()),
+            (TestState::Processing, TestState::Complete)
+        );
+
+        // Complete -> Processing is not an allowed transition, but rollback does not need it
+        sm.rollback().unwrap();
+        assert_eq!(*sm.current_state(), TestState::Processing);
+        assert_eq!(sm.history().len(), 1);
+        sm.rollback().unwrap();
+        assert_eq!(*sm.current_state(), TestState::Initial);
+        assert_eq!(sm.metrics().rollbacks(), 2);
+        assert!(sm.last_transition().is_none());
+
+        let err = sm.rollback().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed)
+        ));
+        assert_eq!(sm.metrics().rollbacks(), 2);
+    }
+
+    #[test]
+    fn test_timed_transition_fires_after_timeout() {
+        let mut sm = StateMachineBuilder::new()
+            .initial_state(TestState::Initial)
+            .add_transition(TestState::Initial, TestState::Processing)
+            .add_timed_transition(
+                TestState::Processing,
+                TestState::Error,
+                Duration::from_secs(30),
+            )
+            .build()
+            .unwrap();
+
+        // No timed transition out of the initial state
+        let far_future = sm.entered_at() + Duration::from_secs(3_600);
+        assert!(sm.tick(far_future).is_none());
+
+        sm.transition_to(TestState::Processing, None).unwrap();
+        let entered = sm.entered_at();
+        assert!(sm.tick(entered + Duration::from_secs(30)).is_none());
+        assert_eq!(*sm.current_state(), TestState::Processing);
+
+        let expired = entered + Duration::from_secs(31);
+        let transition = sm.tick(expired).unwrap();
+        assert_eq!(
+            (*transition.from(), *transition.to()),
+            (TestState::Processing, TestState::Error)
+        );
+        assert_eq!(transition.timestamp(), expired);
+        assert_eq!(transition.category(), Some(TransitionReason::Scheduled));
+        assert_eq!(*sm.current_state(), TestState::Error);
+        assert_eq!(sm.entered_at(), expired);
+        assert!(sm.tick(expired + Duration::from_secs(3_600)).is_none());
+    }
+
+    #[test]
+    fn test_timed_transition_respects_guard() {
+        let mut sm = setup();
+        sm.add_timed_transition(
+            TestState::Initial,
+            TestState::Processing,
+            Duration::from_millis(10),
+        );
+        sm.add_guarded_transition(
+            TestState::Initial,
+            TestState::Processing,
+            Arc::new(|_, _| Ok(false)),
+        );
+
+        assert!(sm.tick(sm.entered_at() + Duration::from_secs(1)).is_none());
+        assert_eq!(*sm.current_state(), TestState::Initial);
+        assert_eq!(sm.metrics().failed_transitions(), 1);
+    }
+
     #[test]
     fn test_state_transition_no_reason() {
         let transition = StateTransition::new(TestState::Start, TestState::End, None);
@@ -1009,6 +2629,99 @@ mod tests {
         }
 
         // Average should not overflow
-        assert!(metrics.average_transition_time() <= u64::MAX);
+        assert_eq!(metrics.average_transition_time(), u64::MAX / 2);
+    }
+
+    fn debounced_machine(window: Duration) -> StateMachine<TestState> {
+        StateMachineBuilder::new()
+            .initial_state(TestState::Processing)
+            .add_transition(TestState::Processing, TestState::Processing)
+            .add_transition(TestState::Processing, TestState::Pending)
+            .add_transition(TestState::Pending, TestState::Processing)
+            .debounce_window(window)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_debounce_collapses_rapid_identical_transitions() {
+        let mut sm = debounced_machine(Duration::from_secs(60));
+
+        for _ in 0..5 {
+            sm.transition_to(TestState::Processing, None).unwrap();
+        }
+
+        assert_eq!(sm.history().len(), 1);
+        assert_eq!(sm.metrics().transitions_count(), 1);
+        assert_eq!(sm.metrics().suppressed_transitions(), 4);
+    }
+
+    #[test]
+    fn test_debounce_preserves_distinct_transition_in_between() {
+        let mut sm = debounced_machine(Duration::from_secs(60));
+
+        assert_eq!(
+            sm.apply_transition(TestState::Processing, None).unwrap(),
+            TransitionOutcome::Applied
+        );
+        assert_eq!(
+            sm.apply_transition(TestState::Pending, None).unwrap(),
+            TransitionOutcome::Applied
+        );
+        assert_eq!(
+            sm.apply_transition(TestState::Processing, None).unwrap(),
+            TransitionOutcome::Applied
+        );
+        assert_eq!(
+            sm.apply_transition(TestState::Processing, None).unwrap(),
+            TransitionOutcome::Applied
+        );
+        assert_eq!(
+            sm.apply_transition(TestState::Processing, None).unwrap(),
+            TransitionOutcome::Suppressed
+        );
+
+        let path: Vec<_> = sm.history().iter().map(|t| *t.to()).collect();
+        assert_eq!(
+            path,
+            vec![
+                TestState::Processing,
+                TestState::Pending,
+                TestState::Processing,
+                TestState::Processing
+            ]
+        );
+        assert_eq!(sm.metrics().suppressed_transitions(), 1);
+    }
+
+    #[test]
+    fn test_debounce_window_expiry_records_repeat() {
+        let mut sm = debounced_machine(Duration::from_millis(10));
+
+        sm.transition_to(TestState::Processing, None).unwrap();
+        thread::sleep(Duration::from_millis(30));
+        sm.transition_to(TestState::Processing, None).unwrap();
+
+        assert_eq!(sm.history().len(), 2);
+        assert_eq!(sm.metrics().suppressed_transitions(), 0);
+    }
+
+    #[test]
+    fn test_debounce_disabled_by_default() {
+        let mut sm = setup();
+        sm.add_transition(TestState::Initial, TestState::Initial);
+        assert!(sm.debounce_window().is_none());
+
+        sm.transition_to(TestState::Initial, None).unwrap();
+        sm.transition_to(TestState::Initial, None).unwrap();
+        assert_eq!(sm.history().len(), 2);
+    }
+
+    #[test]
+    fn test_debounce_does_not_bypass_transition_rules() {
+        let mut sm = debounced_machine(Duration::from_secs(60));
+        assert!(sm.transition_to(TestState::Complete, None).is_err());
+        assert_eq!(sm.metrics().failed_transitions(), 1);
+        assert_eq!(sm.metrics().suppressed_transitions(), 0);
     }
 }
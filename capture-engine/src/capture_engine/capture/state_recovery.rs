@@ -3,22 +3,239 @@
 #![allow(unused_variables)]
 // capture-engine/src/capture/state_recovery.rs
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
-use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, CaptureResult, ConfigErrorKind, RuntimeErrorKind,
+};
 use crate::capture_engine::capture::state_sync::StateSync;
 
 /// Represents a point-in-time snapshot of system state
-#[derive(Clone, Serialize, Deserialize)]
+///
+/// A delta snapshot holds only the states that changed since its base, plus the ids of
+/// states that were removed. It can only be applied to the snapshot it was taken against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateSnapshot<S: Clone> {
     snapshot_id: String,
     timestamp: SystemTime,
     states: HashMap<String, S>,
     metadata: HashMap<String, String>,
     version: String,
+    #[serde(default)]
+    delta: Option<DeltaBase>,
+}
+
+/// Base a delta snapshot was taken against
+///
+/// # Fields
+/// * `snapshot_id` - Id of the base snapshot
+/// * `content_hash` - Hash of the base snapshot's states
+/// * `removed` - States present in the base but absent from the delta's snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaBase {
+    pub snapshot_id: String,
+    pub content_hash: String,
+    pub removed: Vec<String>,
+}
+
+impl<S: Clone + Serialize + Eq> StateSnapshot<S> {
+    /// Creates a full snapshot with a new id, taken now
+    pub fn new(states: HashMap<String, S>, metadata: HashMap<String, String>) -> Self {
+        Self {
+            snapshot_id: uuid::Uuid::new_v4().to_string(),
+            timestamp: SystemTime::now(),
+            states,
+            metadata,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            delta: None,
+        }
+    }
+
+    /// Gets the snapshot id
+    pub fn snapshot_id(&self) -> &str {
+        &self.snapshot_id
+    }
+
+    /// Gets the time the snapshot was taken
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Gets the states; for a delta, only those that changed since the base
+    pub fn states(&self) -> &HashMap<String, S> {
+        &self.states
+    }
+
+    /// Gets the base of a delta snapshot, None for a full snapshot
+    pub fn delta_base(&self) -> Option<&DeltaBase> {
+        self.delta.as_ref()
+    }
+
+    /// Hashes the states, independently of id, time and map order
+    ///
+    /// # Returns
+    /// A hex SHA-256 digest, or an error if a state cannot be serialized
+    pub fn content_hash(&self) -> CaptureResult<String> {
+        let ordered: BTreeMap<_, _> = self.states.iter().collect();
+        let bytes = serde_json::to_vec(&ordered).map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                "Failed to serialize snapshot states",
+            )
+            .with_source(e)
+        })?;
+        Ok(Sha256::digest(&bytes)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+
+    /// Records this snapshot as the differences from a base
+    ///
+    /// # Arguments
+    /// * `base` - Full snapshot, or a snapshot rebuilt from a chain, to diff against
+    ///
+    /// # Returns
+    /// A delta with this snapshot's id, time and metadata
+    pub fn delta_from(&self, base: &StateSnapshot<S>) -> CaptureResult<StateSnapshot<S>> {
+        if base.delta.is_some() {
+            return Err(state_error(&format!(
+                "Snapshot {} is a delta and must be rebuilt before it can be a base",
+                base.snapshot_id
+            )));
+        }
+        let states = self
+            .states
+            .iter()
+            .filter(|(id, state)| base.states.get(*id) != Some(*state))
+            .map(|(id, state)| (id.clone(), state.clone()))
+            .collect();
+        let mut removed: Vec<String> = base
+            .states
+            .keys()
+            .filter(|id| !self.states.contains_key(*id))
+            .cloned()
+            .collect();
+        removed.sort();
+
+        Ok(StateSnapshot {
+            snapshot_id: self.snapshot_id.clone(),
+            timestamp: self.timestamp,
+            states,
+            metadata: self.metadata.clone(),
+            version: self.version.clone(),
+            delta: Some(DeltaBase {
+                snapshot_id: base.snapshot_id.clone(),
+                content_hash: base.content_hash()?,
+                removed,
+            }),
+        })
+    }
+
+    /// Applies a delta taken against this snapshot
+    ///
+    /// # Arguments
+    /// * `delta` - Delta whose base is this snapshot
+    ///
+    /// # Returns
+    /// The full snapshot the delta was taken from, or `Runtime(StateError)` if the delta was
+    /// taken against a different snapshot or this snapshot's states no longer match its hash
+    pub fn apply_delta(&self, delta: &StateSnapshot<S>) -> CaptureResult<StateSnapshot<S>> {
+        let Some(base) = &delta.delta else {
+            return Err(state_error(&format!(
+                "Snapshot {} is not a delta",
+                delta.snapshot_id
+            )));
+        };
+        if base.snapshot_id != self.snapshot_id {
+            return Err(state_error(&format!(
+                "Delta {} applies to snapshot {}, not {}",
+                delta.snapshot_id, base.snapshot_id, self.snapshot_id
+            )));
+        }
+        if base.content_hash != self.content_hash()? {
+            return Err(state_error(&format!(
+                "Snapshot {} does not match the state delta {} was taken against",
+                self.snapshot_id, delta.snapshot_id
+            )));
+        }
+
+        let mut states = self.states.clone();
+        for id in &base.removed {
+            states.remove(id);
+        }
+        states.extend(
+            delta
+                .states
+                .iter()
+                .map(|(id, state)| (id.clone(), state.clone())),
+        );
+        Ok(StateSnapshot {
+            snapshot_id: delta.snapshot_id.clone(),
+            timestamp: delta.timestamp,
+            states,
+            metadata: delta.metadata.clone(),
+            version: delta.version.clone(),
+            delta: None,
+        })
+    }
+}
+
+fn state_error(message: &str) -> Box<CaptureError> {
+    CaptureError::new(
+        CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+        message,
+    )
+}
+
+/// Schema version written with every persisted snapshot
+pub const SNAPSHOT_SCHEMA_VERSION: u16 = 1;
+
+/// Leading bytes of a snapshot persisted in a binary format
+const SNAPSHOT_MAGIC: &[u8; 4] = b"SNAP";
+
+/// Encoding used to persist snapshots
+///
+/// Binary formats are prefixed with a magic and the schema version; JSON carries the version as
+/// a `schema_version` field so it stays self-describing.
+///
+/// # Variants
+/// * `Bincode` - Compact binary, the production default
+/// * `Json` - Human-readable, for debugging
+/// * `MessagePack` - Compact binary readable by other languages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotFormat {
+    #[default]
+    Bincode,
+    Json,
+    MessagePack,
+}
+
+#[derive(Serialize)]
+struct VersionedSnapshot<'a, S: Clone> {
+    schema_version: u16,
+    snapshot: &'a StateSnapshot<S>,
+}
+
+fn parse_error(message: &str) -> Box<CaptureError> {
+    CaptureError::new(
+        CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+        message,
+    )
+}
+
+fn check_schema_version(version: u64) -> CaptureResult<()> {
+    if version != u64::from(SNAPSHOT_SCHEMA_VERSION) {
+        return Err(parse_error(&format!(
+            "Snapshot schema version {} is not supported (expected {})",
+            version, SNAPSHOT_SCHEMA_VERSION
+        )));
+    }
+    Ok(())
 }
 
 /// Represents a recovery point that can be used to restore state
@@ -49,7 +266,9 @@ pub struct StateRecoveryConfig {
 }
 
 /// Manages state recovery operations
-pub struct StateRecoveryManager<S: Clone + Serialize + for<'de> Deserialize<'de> + Eq + Hash> {
+pub struct StateRecoveryManager<
+    S: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync + Eq + Hash + 'static,
+> {
     config: StateRecoveryConfig,
     snapshots: VecDeque<StateSnapshot<S>>,
     recovery_points: Vec<RecoveryPoint>,
@@ -91,23 +310,50 @@ impl<S: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync + Eq + Hash
         snapshot_storage: Box<dyn SnapshotStorage<S>>,
         validator: Box<dyn StateValidator<S>>,
     ) -> Self {
-        unimplemented!()
+        Self {
+            config,
+            snapshots: VecDeque::new(),
+            recovery_points: Vec::new(),
+            state_sync,
+            snapshot_storage,
+            validator,
+        }
     }
 
     /// Creates a new snapshot of current state
+    ///
+    /// The snapshot is persisted to the snapshot storage and held in memory, where at most
+    /// `max_snapshots` are kept; the oldest is released first.
     pub async fn create_snapshot(
         &mut self,
         metadata: HashMap<String, String>,
     ) -> Result<StateSnapshot<S>, CaptureError> {
-        unimplemented!()
+        let snapshot = self.generate_snapshot(metadata)?;
+        self.snapshot_storage.store_snapshot(&snapshot).await?;
+        self.snapshots.push_back(snapshot.clone());
+        while self.snapshots.len() > self.config.max_snapshots.max(1) {
+            self.snapshots.pop_front();
+        }
+        Ok(snapshot)
     }
 
     /// Creates a recovery point
+    ///
+    /// Takes a snapshot of current state and records it with its validation hash.
     pub async fn create_recovery_point(
         &mut self,
         metadata: HashMap<String, String>,
     ) -> Result<RecoveryPoint, CaptureError> {
-        unimplemented!()
+        let snapshot = self.create_snapshot(metadata.clone()).await?;
+        let point = RecoveryPoint {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: snapshot.timestamp,
+            snapshot_id: snapshot.snapshot_id.clone(),
+            validation_hash: self.validator.generate_validation_hash(&snapshot),
+            metadata,
+        };
+        self.recovery_points.push(point.clone());
+        Ok(point)
     }
 
     /// Restores state from a recovery point
@@ -124,17 +370,173 @@ impl<S: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync + Eq + Hash
     }
 
     /// Generates a new snapshot from current state
+    ///
+    /// The snapshot holds the synchronized state keyed by engine id.
     fn generate_snapshot(
         &self,
         metadata: HashMap<String, String>,
     ) -> Result<StateSnapshot<S>, CaptureError> {
-        unimplemented!()
+        let state = self.state_sync.current_state()?;
+        let states = HashMap::from([(self.state_sync.engine_id().to_string(), state)]);
+        Ok(StateSnapshot::new(states, metadata))
     }
 
     /// Cleans up old snapshots and recovery points
     pub async fn cleanup_old_snapshots(&mut self) -> Result<(), CaptureError> {
         unimplemented!()
     }
+
+    /// Creates a snapshot holding only the state that changed since a recovery point
+    ///
+    /// # Arguments
+    /// * `base` - Recovery point whose snapshot the delta is taken against
+    ///
+    /// # Returns
+    /// A delta snapshot of the current state, or an error if the base snapshot is not held
+    pub fn create_delta_snapshot(&self, base: &RecoveryPoint) -> CaptureResult<StateSnapshot<S>> {
+        let base = self.rebuild_snapshot(&base.snapshot_id)?;
+        let current = self.generate_snapshot(HashMap::new()).map_err(Box::new)?;
+        current.delta_from(&base)
+    }
+
+    /// Rebuilds a snapshot by replaying deltas over their base
+    ///
+    /// # Arguments
+    /// * `base` - Full snapshot the first delta was taken against
+    /// * `deltas` - Deltas in the order they were taken, each against the one before
+    ///
+    /// # Returns
+    /// The snapshot of the last delta, or `Runtime(StateError)` if a delta is out of order or
+    /// taken against a different base
+    pub fn restore_from_chain(
+        base: &StateSnapshot<S>,
+        deltas: &[StateSnapshot<S>],
+    ) -> CaptureResult<StateSnapshot<S>> {
+        if base.delta.is_some() {
+            return Err(state_error(&format!(
+                "Chain base {} must be a full snapshot",
+                base.snapshot_id
+            )));
+        }
+        deltas
+            .iter()
+            .try_fold(base.clone(), |snapshot, delta| snapshot.apply_delta(delta))
+    }
+
+    /// Encodes a snapshot for persistence
+    ///
+    /// # Arguments
+    /// * `snapshot` - Snapshot to encode
+    /// * `format` - Encoding to use
+    ///
+    /// # Returns
+    /// The encoded snapshot, tagged with the current schema version
+    pub fn save_snapshot(
+        snapshot: &StateSnapshot<S>,
+        format: SnapshotFormat,
+    ) -> CaptureResult<Vec<u8>> {
+        let encode_error = |e: &dyn std::fmt::Display| {
+            CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                &format!("Failed to encode snapshot {}: {}", snapshot.snapshot_id, e),
+            )
+        };
+        let payload = match format {
+            SnapshotFormat::Json => {
+                return serde_json::to_vec_pretty(&VersionedSnapshot {
+                    schema_version: SNAPSHOT_SCHEMA_VERSION,
+                    snapshot,
+                })
+                .map_err(|e| encode_error(&e));
+            }
+            SnapshotFormat::Bincode => {
+                bincode::serialize(snapshot).map_err(|e| encode_error(&e))?
+            }
+            SnapshotFormat::MessagePack => {
+                rmp_serde::to_vec_named(snapshot).map_err(|e| encode_error(&e))?
+            }
+        };
+
+        let mut bytes = Vec::with_capacity(SNAPSHOT_MAGIC.len() + 2 + payload.len());
+        bytes.extend_from_slice(SNAPSHOT_MAGIC);
+        bytes.extend_from_slice(&SNAPSHOT_SCHEMA_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Decodes a persisted snapshot
+    ///
+    /// # Arguments
+    /// * `bytes` - Snapshot as written by `save_snapshot`
+    /// * `format` - Encoding the snapshot was written in
+    ///
+    /// # Returns
+    /// The snapshot, or `Configuration(ParseError)` if it is malformed or was written with a
+    /// different schema version
+    pub fn load_snapshot(bytes: &[u8], format: SnapshotFormat) -> CaptureResult<StateSnapshot<S>> {
+        let decode_error = |e: &dyn std::fmt::Display| {
+            parse_error(&format!("Failed to decode {:?} snapshot: {}", format, e))
+        };
+        if format == SnapshotFormat::Json {
+            let mut value: serde_json::Value =
+                serde_json::from_slice(bytes).map_err(|e| decode_error(&e))?;
+            let version = value
+                .get("schema_version")
+                .and_then(serde_json::Value::as_u64)
+                .ok_or_else(|| parse_error("Snapshot has no schema_version"))?;
+            check_schema_version(version)?;
+            return serde_json::from_value(value["snapshot"].take()).map_err(|e| decode_error(&e));
+        }
+
+        let header_len = SNAPSHOT_MAGIC.len() + 2;
+        if bytes.len() < header_len || &bytes[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC {
+            return Err(parse_error("Snapshot has no binary header"));
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        check_schema_version(u64::from(version))?;
+        let payload = &bytes[header_len..];
+        match format {
+            SnapshotFormat::Bincode => bincode::deserialize(payload).map_err(|e| decode_error(&e)),
+            SnapshotFormat::MessagePack => {
+                rmp_serde::from_slice(payload).map_err(|e| decode_error(&e))
+            }
+            SnapshotFormat::Json => unreachable!("JSON snapshots are decoded above"),
+        }
+    }
+
+    /// Rebuilds a held snapshot, following delta bases back to a full snapshot
+    ///
+    /// # Arguments
+    /// * `snapshot_id` - Id of a snapshot held by the manager
+    ///
+    /// # Returns
+    /// The full snapshot, or `Runtime(EntityNotFound)` if it or a base it needs is not held
+    pub fn rebuild_snapshot(&self, snapshot_id: &str) -> CaptureResult<StateSnapshot<S>> {
+        let mut chain = Vec::new();
+        let mut next = snapshot_id.to_string();
+        let base = loop {
+            let snapshot = self
+                .snapshots
+                .iter()
+                .find(|snapshot| snapshot.snapshot_id == next)
+                .ok_or_else(|| {
+                    CaptureError::new(
+                        CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound),
+                        &format!("Snapshot {} is not held", next),
+                    )
+                })?;
+            match &snapshot.delta {
+                Some(base) if chain.len() < self.snapshots.len() => {
+                    next = base.snapshot_id.clone();
+                    chain.push(snapshot.clone());
+                }
+                Some(_) => return Err(state_error("Snapshot deltas form a cycle")),
+                None => break snapshot,
+            }
+        };
+        chain.reverse();
+        Self::restore_from_chain(base, &chain)
+    }
 }
 
 /// Default file-based snapshot storage implementation
@@ -163,3 +565,289 @@ impl<S: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static> S
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::state_machine::StateMachine;
+    use crate::capture_engine::capture::state_sync::{
+        StateChangeEvent, StateReporter, StateSyncBuilder, StateSyncConfig,
+    };
+    use std::future::Future;
+    use std::pin::Pin;
+
+    type Manager = StateRecoveryManager<String>;
+
+    fn snapshot(states: &[(&str, &str)]) -> StateSnapshot<String> {
+        StateSnapshot::new(
+            states
+                .iter()
+                .map(|(id, state)| (id.to_string(), state.to_string()))
+                .collect(),
+            HashMap::new(),
+        )
+    }
+
+    fn assert_state_error(err: &CaptureError) {
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::StateError)
+        ));
+    }
+
+    #[test]
+    fn test_delta_holds_only_changes() {
+        let base = snapshot(&[("eth0", "capturing"), ("eth1", "idle"), ("buf", "ok")]);
+        let current = snapshot(&[("eth0", "capturing"), ("eth1", "paused"), ("eth2", "idle")]);
+        let delta = current.delta_from(&base).unwrap();
+
+        assert_eq!(delta.states().len(), 2);
+        assert_eq!(delta.states()["eth1"], "paused");
+        assert_eq!(delta.states()["eth2"], "idle");
+        let delta_base = delta.delta_base().unwrap();
+        assert_eq!(delta_base.snapshot_id, base.snapshot_id());
+        assert_eq!(delta_base.removed, vec!["buf"]);
+
+        let rebuilt = base.apply_delta(&delta).unwrap();
+        assert_eq!(rebuilt.snapshot_id(), current.snapshot_id());
+        assert_eq!(rebuilt.states(), current.states());
+        assert!(rebuilt.delta_base().is_none());
+    }
+
+    #[test]
+    fn test_chain_replays_in_order() {
+        let base = snapshot(&[("eth0", "idle")]);
+        let second = snapshot(&[("eth0", "capturing")]);
+        let third = snapshot(&[("eth0", "capturing"), ("eth1", "capturing")]);
+        let deltas = vec![
+            second.delta_from(&base).unwrap(),
+            third.delta_from(&second).unwrap(),
+        ];
+
+        let restored = Manager::restore_from_chain(&base, &deltas).unwrap();
+        assert_eq!(restored.snapshot_id(), third.snapshot_id());
+        assert_eq!(restored.states(), third.states());
+
+        let reversed: Vec<_> = deltas.iter().rev().cloned().collect();
+        assert_state_error(&Manager::restore_from_chain(&base, &reversed).unwrap_err());
+    }
+
+    #[test]
+    fn test_delta_rejected_on_wrong_base() {
+        let base = snapshot(&[("eth0", "idle")]);
+        let delta = snapshot(&[("eth0", "capturing")])
+            .delta_from(&base)
+            .unwrap();
+
+        let other = snapshot(&[("eth0", "idle")]);
+        assert_state_error(&other.apply_delta(&delta).unwrap_err());
+
+        // Same id but different content, e.g. a corrupted base
+        let mut tampered = base.clone();
+        tampered
+            .states
+            .insert("eth0".to_string(), "error".to_string());
+        assert_state_error(&tampered.apply_delta(&delta).unwrap_err());
+
+        // A delta cannot serve as a chain base
+        assert_state_error(&Manager::restore_from_chain(&delta, &[]).unwrap_err());
+        assert_state_error(&base.apply_delta(&base).unwrap_err());
+    }
+
+    fn assert_parse_error(err: &CaptureError) {
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::ParseError)
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_in_each_format() {
+        let base = snapshot(&[("eth0", "idle"), ("eth1", "capturing")]);
+        let delta = snapshot(&[("eth0", "capturing")])
+            .delta_from(&base)
+            .unwrap();
+        for format in [
+            SnapshotFormat::Bincode,
+            SnapshotFormat::Json,
+            SnapshotFormat::MessagePack,
+        ] {
+            for original in [&base, &delta] {
+                let bytes = Manager::save_snapshot(original, format).unwrap();
+                let loaded = Manager::load_snapshot(&bytes, format).unwrap();
+                assert_eq!(loaded.snapshot_id(), original.snapshot_id(), "{:?}", format);
+                assert_eq!(loaded.timestamp(), original.timestamp());
+                assert_eq!(loaded.states(), original.states());
+                assert_eq!(loaded.delta_base(), original.delta_base());
+            }
+        }
+        assert_eq!(SnapshotFormat::default(), SnapshotFormat::Bincode);
+
+        let json = Manager::save_snapshot(&base, SnapshotFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["schema_version"], SNAPSHOT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_older_schema_version_rejected() {
+        let base = snapshot(&[("eth0", "idle")]);
+
+        for format in [SnapshotFormat::Bincode, SnapshotFormat::MessagePack] {
+            let mut bytes = Manager::save_snapshot(&base, format).unwrap();
+            bytes[4..6].copy_from_slice(&0u16.to_le_bytes());
+            assert_parse_error(&Manager::load_snapshot(&bytes, format).unwrap_err());
+        }
+
+        let json = Manager::save_snapshot(&base, SnapshotFormat::Json).unwrap();
+        let mut value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        value["schema_version"] = 0.into();
+        let older = serde_json::to_vec(&value).unwrap();
+        assert_parse_error(&Manager::load_snapshot(&older, SnapshotFormat::Json).unwrap_err());
+
+        // Unversioned JSON predates the schema field
+        let unversioned = serde_json::to_vec(&value["snapshot"]).unwrap();
+        assert_parse_error(
+            &Manager::load_snapshot(&unversioned, SnapshotFormat::Json).unwrap_err(),
+        );
+        assert_parse_error(&Manager::load_snapshot(b"SN", SnapshotFormat::Bincode).unwrap_err());
+    }
+
+    #[test]
+    fn test_delta_round_trips_through_serde() {
+        let base = snapshot(&[("eth0", "idle"), ("eth1", "idle")]);
+        let delta = snapshot(&[("eth0", "capturing")])
+            .delta_from(&base)
+            .unwrap();
+        let json = serde_json::to_string(&delta).unwrap();
+        let decoded: StateSnapshot<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.delta_base(), delta.delta_base());
+        assert_eq!(base.apply_delta(&decoded).unwrap().states().len(), 1);
+    }
+
+    struct NullReporter;
+
+    impl StateReporter<String> for NullReporter {
+        fn report_state<'a>(
+            &'a self,
+            _event: &'a StateChangeEvent<String>,
+        ) -> Pin<Box<dyn Future<Output = Result<(), CaptureError>> + Send + 'a>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        stored: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl SnapshotStorage<String> for MemoryStorage {
+        async fn store_snapshot(
+            &self,
+            snapshot: &StateSnapshot<String>,
+        ) -> Result<(), CaptureError> {
+            self.stored
+                .lock()
+                .unwrap()
+                .push(snapshot.snapshot_id().to_string());
+            Ok(())
+        }
+
+        async fn load_snapshot(
+            &self,
+            snapshot_id: &str,
+        ) -> Result<StateSnapshot<String>, CaptureError> {
+            Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound),
+                snapshot_id,
+            ))
+        }
+
+        async fn list_snapshots(&self) -> Result<Vec<String>, CaptureError> {
+            Ok(self.stored.lock().unwrap().clone())
+        }
+
+        async fn delete_snapshot(&self, _snapshot_id: &str) -> Result<(), CaptureError> {
+            Ok(())
+        }
+    }
+
+    struct HashValidator;
+
+    impl StateValidator<String> for HashValidator {
+        fn validate_snapshot(
+            &self,
+            _snapshot: &StateSnapshot<String>,
+        ) -> Result<bool, CaptureError> {
+            Ok(true)
+        }
+
+        fn validate_recovery_point(&self, _point: &RecoveryPoint) -> Result<bool, CaptureError> {
+            Ok(true)
+        }
+
+        fn generate_validation_hash(&self, snapshot: &StateSnapshot<String>) -> String {
+            snapshot.content_hash().unwrap()
+        }
+    }
+
+    fn manager() -> Manager {
+        let mut machine = StateMachine::new("idle".to_string(), 16).unwrap();
+        machine.add_transition("idle".to_string(), "capturing".to_string());
+        let sync = StateSyncBuilder::new()
+            .with_engine_id("engine-1".to_string())
+            .with_state_machine(machine)
+            .with_reporter(Box::new(NullReporter))
+            .with_config(StateSyncConfig::default())
+            .build()
+            .unwrap();
+        let config = StateRecoveryConfig {
+            snapshot_interval: Duration::from_secs(60),
+            max_snapshots: 4,
+            snapshot_storage_path: String::new(),
+            validation_enabled: true,
+            compression_enabled: false,
+            retention_period: Duration::from_secs(3600),
+        };
+        Manager::new(
+            config,
+            Arc::new(sync),
+            Box::new(MemoryStorage::default()),
+            Box::new(HashValidator),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_delta_from_recovery_point_restores_current_state() {
+        let mut manager = manager();
+        let point = manager.create_recovery_point(HashMap::new()).await.unwrap();
+        let base = manager.rebuild_snapshot(&point.snapshot_id).unwrap();
+        assert_eq!(point.validation_hash, base.content_hash().unwrap());
+        assert_eq!(base.states()["engine-1"], "idle");
+
+        manager
+            .state_sync
+            .update_state("capturing".to_string(), HashMap::new())
+            .await
+            .unwrap();
+        let delta = manager.create_delta_snapshot(&point).unwrap();
+        assert_eq!(delta.delta_base().unwrap().snapshot_id, point.snapshot_id);
+        assert_eq!(delta.states().len(), 1);
+
+        let restored = base.apply_delta(&delta).unwrap();
+        assert_eq!(restored.states()["engine-1"], "capturing");
+        assert!(restored.delta_base().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delta_needs_held_base() {
+        let mut manager = manager();
+        let point = manager.create_recovery_point(HashMap::new()).await.unwrap();
+        manager.snapshots.clear();
+        let err = manager.create_delta_snapshot(&point).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound)
+        ));
+    }
+}
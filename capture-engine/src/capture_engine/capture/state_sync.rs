@@ -1,17 +1,21 @@
 // capture-engine/src/capture/state_sync.rs
 /// Synchronizes the state of the capture engine with the control plane.
 use crate::capture_engine::capture::capture_error::{
-    CaptureError, CaptureErrorKind, ConfigErrorKind, RuntimeErrorKind,
+    CaptureError, CaptureErrorKind, ConfigErrorKind, ResourceErrorKind, RuntimeErrorKind,
 };
-use crate::capture_engine::capture::state_machine::{StateMachine, StateTransition};
-use std::collections::HashMap;
+use crate::capture_engine::capture::state_machine::{
+    ExponentialMovingAverage, StateMachine, StateTransition, TransitionOutcome, TransitionReason,
+};
+use std::collections::{HashMap, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
-    Arc, RwLock,
+    Arc, Mutex, RwLock, Weak,
 };
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
 
 /// Represents a state change event
 ///
@@ -138,13 +142,24 @@ impl<S: Clone> StateChangeEvent<S> {
 /// * `Immediate` - Synchronize state changes immediately with all nodes
 /// * `Eventual` - Synchronize state changes with a specified delay
 /// * `OnDemand` - Synchronize state changes only on specific triggers
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SyncStrategy {
     Immediate,
     Eventual { delay_ms: u64 },
     OnDemand,
 }
 
+/// What to do with a failed event when the dead-letter queue is full
+///
+/// # Variants
+/// * `DropOldest` - Evict the oldest dead letter to make room, keeping the latest state
+/// * `Reject` - Discard the new event, keeping the earliest failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadLetterPolicy {
+    DropOldest,
+    Reject,
+}
+
 /// Configuration for state synchronization
 ///
 /// This struct is used to configure state synchronization behavior
@@ -153,11 +168,27 @@ pub enum SyncStrategy {
 /// * `report_interval` - Interval for reporting state changes
 /// * `retry_attempts` - Number of retry attempts for failed sync operations
 /// * `retry_delay` - Delay between retry attempts
+/// * `strategy` - When state changes are reported
+/// * `max_batch_size` - Pending events that force an early flush under `Eventual`
+/// * `dead_letter_capacity` - Failed events kept for later resending; 0 keeps none
+/// * `dead_letter_policy` - What to do with a failed event when the dead-letter queue is full
+/// * `circuit_failure_threshold` - Consecutive failed reports that open the circuit
+/// * `circuit_cooldown` - How long an open circuit fails fast before allowing trial reports
+/// * `circuit_half_open_probes` - Trial reports that must succeed before the circuit closes
+/// * `ema_alpha` - Weight of each sample in the sync time moving average, if enabled
 #[derive(Debug, Clone)]
 pub struct StateSyncConfig {
     report_interval: Duration,
     retry_attempts: u32,
     retry_delay: Duration,
+    strategy: SyncStrategy,
+    max_batch_size: usize,
+    dead_letter_capacity: usize,
+    dead_letter_policy: DeadLetterPolicy,
+    circuit_failure_threshold: u32,
+    circuit_cooldown: Duration,
+    circuit_half_open_probes: u32,
+    ema_alpha: Option<f64>,
 }
 
 impl Default for StateSyncConfig {
@@ -170,6 +201,14 @@ impl Default for StateSyncConfig {
             report_interval: Duration::from_secs(1),
             retry_attempts: 3,
             retry_delay: Duration::from_secs(1),
+            strategy: SyncStrategy::Immediate,
+            max_batch_size: 64,
+            dead_letter_capacity: 1024,
+            dead_letter_policy: DeadLetterPolicy::DropOldest,
+            circuit_failure_threshold: 5,
+            circuit_cooldown: Duration::from_secs(30),
+            circuit_half_open_probes: 1,
+            ema_alpha: None,
         }
     }
 }
@@ -210,6 +249,166 @@ impl StateSyncConfig {
         self
     }
 
+    /// Sets when state changes are reported
+    ///
+    /// # Arguments
+    /// * `strategy` - Synchronization strategy
+    ///
+    /// # Returns
+    /// A new StateSyncConfig instance with the specified strategy
+    pub fn with_strategy(mut self, strategy: SyncStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets the number of pending events that forces an early flush under `Eventual`
+    ///
+    /// # Arguments
+    /// * `size` - Maximum pending events
+    ///
+    /// # Returns
+    /// A new StateSyncConfig instance with the specified batch size
+    pub fn with_max_batch_size(mut self, size: usize) -> Self {
+        self.max_batch_size = size;
+        self
+    }
+
+    /// Sets the number of permanently failed events kept for later resending
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum dead letters; 0 discards failed events
+    ///
+    /// # Returns
+    /// A new StateSyncConfig instance with the specified capacity
+    pub fn with_dead_letter_capacity(mut self, capacity: usize) -> Self {
+        self.dead_letter_capacity = capacity;
+        self
+    }
+
+    /// Sets what happens to a failed event when the dead-letter queue is full
+    ///
+    /// # Arguments
+    /// * `policy` - Dead-letter overflow policy
+    ///
+    /// # Returns
+    /// A new StateSyncConfig instance with the specified policy
+    pub fn with_dead_letter_policy(mut self, policy: DeadLetterPolicy) -> Self {
+        self.dead_letter_policy = policy;
+        self
+    }
+
+    /// Sets the number of consecutive failed reports that opens the circuit
+    ///
+    /// # Arguments
+    /// * `threshold` - Consecutive failures before reports fail fast
+    ///
+    /// # Returns
+    /// A new StateSyncConfig instance with the specified threshold
+    pub fn with_circuit_failure_threshold(mut self, threshold: u32) -> Self {
+        self.circuit_failure_threshold = threshold;
+        self
+    }
+
+    /// Sets how long an open circuit fails fast before allowing trial reports
+    ///
+    /// # Arguments
+    /// * `cooldown` - Time the circuit stays open
+    ///
+    /// # Returns
+    /// A new StateSyncConfig instance with the specified cooldown
+    pub fn with_circuit_cooldown(mut self, cooldown: Duration) -> Self {
+        self.circuit_cooldown = cooldown;
+        self
+    }
+
+    /// Sets the number of trial reports that must succeed before the circuit closes
+    ///
+    /// # Arguments
+    /// * `probes` - Trial reports allowed while half-open
+    ///
+    /// # Returns
+    /// A new StateSyncConfig instance with the specified probe count
+    pub fn with_circuit_half_open_probes(mut self, probes: u32) -> Self {
+        self.circuit_half_open_probes = probes;
+        self
+    }
+
+    /// Tracks an exponential moving average of sync times alongside the cumulative one
+    ///
+    /// # Arguments
+    /// * `alpha` - Weight of each new sample, in (0, 1]
+    ///
+    /// # Returns
+    /// A new StateSyncConfig instance with the moving average enabled
+    pub fn with_ema_alpha(mut self, alpha: f64) -> Self {
+        self.ema_alpha = Some(alpha);
+        self
+    }
+
+    /// Returns the number of consecutive failed reports that opens the circuit
+    ///
+    /// # Returns
+    /// The failure threshold
+    pub fn circuit_failure_threshold(&self) -> u32 {
+        self.circuit_failure_threshold
+    }
+
+    /// Returns how long an open circuit fails fast
+    ///
+    /// # Returns
+    /// The cooldown duration
+    pub fn circuit_cooldown(&self) -> Duration {
+        self.circuit_cooldown
+    }
+
+    /// Returns the number of trial reports that must succeed before the circuit closes
+    ///
+    /// # Returns
+    /// The half-open probe count
+    pub fn circuit_half_open_probes(&self) -> u32 {
+        self.circuit_half_open_probes
+    }
+
+    /// Returns the weight of each sample in the sync time moving average
+    ///
+    /// # Returns
+    /// The EMA alpha, or None if only the cumulative average is tracked
+    pub fn ema_alpha(&self) -> Option<f64> {
+        self.ema_alpha
+    }
+
+    /// Returns the dead-letter queue capacity
+    ///
+    /// # Returns
+    /// The maximum number of dead letters
+    pub fn dead_letter_capacity(&self) -> usize {
+        self.dead_letter_capacity
+    }
+
+    /// Returns the dead-letter overflow policy
+    ///
+    /// # Returns
+    /// The dead-letter policy
+    pub fn dead_letter_policy(&self) -> DeadLetterPolicy {
+        self.dead_letter_policy
+    }
+
+    /// Returns the synchronization strategy
+    ///
+    /// # Returns
+    /// The synchronization strategy
+    pub fn strategy(&self) -> SyncStrategy {
+        self.strategy
+    }
+
+    /// Returns the number of pending events that forces an early flush
+    ///
+    /// # Returns
+    /// The maximum batch size
+    pub fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
+
     /// Returns the report interval for state synchronization
     ///
     /// # Returns
@@ -251,10 +450,336 @@ impl StateSyncConfig {
                 "retry_delay must be greater than 0",
             ));
         }
+        if self.max_batch_size == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "max_batch_size must be greater than 0",
+            ));
+        }
+        if self.circuit_failure_threshold == 0 || self.circuit_half_open_probes == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "circuit_failure_threshold and circuit_half_open_probes must be greater than 0",
+            ));
+        }
         Ok(())
     }
 }
 
+/// State of the circuit breaker around the state reporter
+///
+/// # Variants
+/// * `Closed` - Reports go through; consecutive failures are counted
+/// * `Open` - Reports fail fast until the cooldown has passed
+/// * `HalfOpen` - A limited number of trial reports decide whether to close or reopen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probes_in_flight: u32,
+    probe_successes: u32,
+    half_open_round: u64,
+}
+
+/// Permission to attempt one report, released when dropped
+///
+/// A report cancelled while in flight drops its admission without settling it, which frees
+/// its half-open probe slot so the circuit is not left waiting for a result that never comes.
+///
+/// # Fields
+/// * `breaker` - Breaker that admitted the report
+/// * `probe` - Half-open round the report probes, or None outside half-open
+/// * `settled` - Whether the outcome has been recorded
+struct Admission<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: Option<u64>,
+    settled: bool,
+}
+
+impl Admission<'_> {
+    /// Records that the control plane answered
+    fn succeed(mut self) {
+        self.settled = true;
+        self.breaker.record_success(self.probe);
+    }
+
+    /// Records a transient failure
+    fn fail(mut self, now: Instant) {
+        self.settled = true;
+        self.breaker.record_failure(now);
+    }
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        if let (false, Some(round)) = (self.settled, self.probe) {
+            self.breaker.release_probe(round);
+        }
+    }
+}
+
+/// Circuit breaker that stops reports from hammering an unreachable control plane
+///
+/// Only transient failures count: a non-retryable error means the control plane answered,
+/// so it is treated like a success.
+///
+/// # Fields
+/// * `failure_threshold` - Consecutive failures that open the circuit
+/// * `cooldown` - Time the circuit stays open
+/// * `half_open_probes` - Trial reports that must succeed to close the circuit
+/// * `state` - Current state and counters
+#[derive(Debug)]
+struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    half_open_probes: u32,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(config: &StateSyncConfig) -> Self {
+        Self {
+            failure_threshold: config.circuit_failure_threshold(),
+            cooldown: config.circuit_cooldown(),
+            half_open_probes: config.circuit_half_open_probes(),
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probes_in_flight: 0,
+                probe_successes: 0,
+                half_open_round: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Admits a report if one may be attempted now, moving an open circuit to half-open after
+    /// the cooldown
+    fn admit(&self, now: Instant) -> Option<Admission<'_>> {
+        let mut breaker = self.lock();
+        if breaker.state == CircuitState::Open {
+            let cooled = breaker
+                .opened_at
+                .is_none_or(|opened| now.duration_since(opened) >= self.cooldown);
+            if !cooled {
+                return None;
+            }
+            breaker.state = CircuitState::HalfOpen;
+            breaker.probes_in_flight = 0;
+            breaker.probe_successes = 0;
+            breaker.half_open_round += 1;
+        }
+        let mut probe = None;
+        if breaker.state == CircuitState::HalfOpen {
+            if breaker.probes_in_flight >= self.half_open_probes {
+                return None;
+            }
+            breaker.probes_in_flight += 1;
+            probe = Some(breaker.half_open_round);
+        }
+        Some(Admission {
+            breaker: self,
+            probe,
+            settled: false,
+        })
+    }
+
+    /// Frees the slot of a probe that ended without a result
+    fn release_probe(&self, round: u64) {
+        let mut breaker = self.lock();
+        if breaker.state == CircuitState::HalfOpen && breaker.half_open_round == round {
+            breaker.probes_in_flight = breaker.probes_in_flight.saturating_sub(1);
+        }
+    }
+
+    fn record_success(&self, probe: Option<u64>) {
+        let mut breaker = self.lock();
+        match breaker.state {
+            CircuitState::Closed => breaker.consecutive_failures = 0,
+            // Only probes of the current round count towards closing the circuit
+            CircuitState::HalfOpen if probe == Some(breaker.half_open_round) => {
+                breaker.probes_in_flight = breaker.probes_in_flight.saturating_sub(1);
+                breaker.probe_successes += 1;
+                if breaker.probe_successes >= self.half_open_probes {
+                    breaker.state = CircuitState::Closed;
+                    breaker.consecutive_failures = 0;
+                    breaker.opened_at = None;
+                }
+            }
+            // A report admitted before the circuit opened or in an earlier round
+            CircuitState::HalfOpen | CircuitState::Open => {}
+        }
+    }
+
+    fn record_failure(&self, now: Instant) {
+        let mut breaker = self.lock();
+        let open = match breaker.state {
+            CircuitState::Closed => {
+                breaker.consecutive_failures += 1;
+                breaker.consecutive_failures >= self.failure_threshold
+            }
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if open {
+            breaker.state = CircuitState::Open;
+            breaker.opened_at = Some(now);
+        }
+    }
+}
+
+/// Number of histogram buckets: one for zero and one per power of two up to 2^64
+pub const LATENCY_BUCKETS: usize = 65;
+
+/// Lock-free histogram of latencies with power-of-two buckets
+///
+/// Bucket 0 counts zero values and bucket `i` counts values in `[2^(i-1), 2^i)`, so any `u64`
+/// is recorded with at most a factor-of-two error and recording is a single atomic increment.
+///
+/// # Fields
+/// * `buckets` - Count of values in each bucket
+/// * `max` - Largest value recorded, used to tighten the top percentile bound
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS],
+    max: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            max: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Records one value
+    ///
+    /// # Arguments
+    /// * `value` - Latency to record
+    pub fn record(&self, value: u64) {
+        let bucket = (u64::BITS - value.leading_zeros()) as usize;
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+    }
+
+    /// Returns the largest value bucket `index` can hold
+    ///
+    /// # Arguments
+    /// * `index` - Bucket index, below `LATENCY_BUCKETS`
+    ///
+    /// # Returns
+    /// The inclusive upper bound of the bucket
+    pub fn bucket_upper_bound(index: usize) -> u64 {
+        match index {
+            0 => 0,
+            64.. => u64::MAX,
+            i => (1u64 << i) - 1,
+        }
+    }
+
+    /// Returns the count of each bucket
+    ///
+    /// # Returns
+    /// Non-cumulative `(upper_bound, count)` pairs in ascending order, ready to export as a
+    /// Prometheus histogram after summing
+    pub fn bucket_counts(&self) -> Vec<(u64, u64)> {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (Self::bucket_upper_bound(i), count.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Returns the count of each bucket and zeroes it
+    ///
+    /// # Returns
+    /// Non-cumulative `(upper_bound, count)` pairs in ascending order; every recorded value
+    /// lands in exactly one call's counts
+    pub fn take_bucket_counts(&self) -> Vec<(u64, u64)> {
+        self.max.store(0, Ordering::Relaxed);
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                (
+                    Self::bucket_upper_bound(i),
+                    count.swap(0, Ordering::Relaxed),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns an upper bound on the given quantile
+    ///
+    /// # Arguments
+    /// * `quantile` - Quantile between 0.0 and 1.0
+    ///
+    /// # Returns
+    /// The upper bound of the bucket holding the quantile, capped at the largest value
+    /// recorded, or 0 if nothing was recorded
+    pub fn quantile(&self, quantile: f64) -> u64 {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+
+        let rank = ((quantile.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Self::bucket_upper_bound(i).min(self.max.load(Ordering::Relaxed));
+            }
+        }
+        self.max.load(Ordering::Relaxed)
+    }
+}
+
+/// Point-in-time copy of `SyncMetrics`
+///
+/// # Fields
+/// * `sync_attempts` - Number of attempted sync operations
+/// * `failed_syncs` - Number of failed sync operations
+/// * `average_sync_time` - Average time for successful sync operations
+/// * `ema_sync_time` - Recency-weighted sync time, if enabled
+/// * `latency_buckets` - Non-cumulative `(upper_bound_ns, count)` pairs of successful sync times
+/// * `dead_lettered` - Failed events added to the dead-letter queue
+/// * `dead_letters_dropped` - Failed events lost because the dead-letter queue was full
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncMetricsSnapshot {
+    pub sync_attempts: u64,
+    pub failed_syncs: u64,
+    pub average_sync_time: u64,
+    pub ema_sync_time: Option<u64>,
+    pub latency_buckets: Vec<(u64, u64)>,
+    pub dead_lettered: u64,
+    pub dead_letters_dropped: u64,
+}
+
 /// Metrics for sync operations
 ///
 /// This struct is used to track synchronization metrics for state changes
@@ -263,11 +788,19 @@ impl StateSyncConfig {
 /// * `sync_attempts` - Number of attempted sync operations
 /// * `failed_syncs` - Number of failed sync operations
 /// * `average_sync_time` - Average time for successful sync operations
+/// * `latency` - Distribution of successful sync times
+/// * `dead_lettered` - Failed events added to the dead-letter queue
+/// * `dead_letters_dropped` - Failed events lost because the dead-letter queue was full
+/// * `ema_sync_time` - Recency-weighted time for successful sync operations, if enabled
 #[derive(Debug, Default)]
 pub struct SyncMetrics {
     sync_attempts: AtomicU64,
     failed_syncs: AtomicU64,
     average_sync_time: AtomicU64,
+    latency: LatencyHistogram,
+    dead_lettered: AtomicU64,
+    dead_letters_dropped: AtomicU64,
+    ema_sync_time: Option<ExponentialMovingAverage>,
 }
 
 impl SyncMetrics {
@@ -279,15 +812,30 @@ impl SyncMetrics {
         Self::default()
     }
 
+    /// Creates a metrics instance that also tracks an exponential moving average
+    ///
+    /// # Arguments
+    /// * `alpha` - Weight of each new sample, in (0, 1]
+    ///
+    /// # Returns
+    /// A new SyncMetrics instance, or a configuration error if `alpha` is out of range
+    pub fn with_ema(alpha: f64) -> Result<Self, CaptureError> {
+        Ok(Self {
+            ema_sync_time: Some(ExponentialMovingAverage::new(alpha)?),
+            ..Self::default()
+        })
+    }
+
     /// Records a successful sync attempt
     ///
     /// # Arguments
-    /// * `duration_ms` - Duration of the sync operation in milliseconds
+    /// * `duration_ns` - Duration of the sync operation in nanoseconds
     ///
     /// # Notes
     /// Careful with this one, need to check for overflow
-    pub fn record_sync_attempt(&self, duration_ms: u64) {
+    pub fn record_sync_attempt(&self, duration_ns: u64) {
         self.sync_attempts.fetch_add(1, Ordering::Relaxed);
+        self.latency.record(duration_ns);
 
         // Calculate running average to prevent overflow
         let current_avg = self.average_sync_time.load(Ordering::Relaxed);
@@ -295,19 +843,22 @@ impl SyncMetrics {
 
         // Use checked arithmetic operations
         let new_avg = if attempts == 1 {
-            duration_ms
+            duration_ns
         } else {
             // Formula: new_avg = ((old_avg * (n-1)) + new_value) / n
             // Rewritten to minimize overflow risk:
             // new_avg = old_avg + (new_value - old_avg) / n
             current_avg.saturating_add(
-                (duration_ms.saturating_sub(current_avg))
+                (duration_ns.saturating_sub(current_avg))
                     .checked_div(attempts)
                     .unwrap_or(0),
             )
         };
 
         self.average_sync_time.store(new_avg, Ordering::Relaxed);
+        if let Some(ema) = &self.ema_sync_time {
+            ema.record(duration_ns);
+        }
     }
 
     /// Records a failed sync attempt
@@ -317,6 +868,32 @@ impl SyncMetrics {
         self.failed_syncs.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Records a failed event added to the dead-letter queue
+    pub fn record_dead_letter(&self) {
+        self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a failed event lost because the dead-letter queue was full
+    pub fn record_dead_letter_dropped(&self) {
+        self.dead_letters_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of events added to the dead-letter queue
+    ///
+    /// # Returns
+    /// The number of dead-lettered events
+    pub fn dead_lettered(&self) -> u64 {
+        self.dead_lettered.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of failed events lost to a full dead-letter queue
+    ///
+    /// # Returns
+    /// The number of evicted or rejected dead letters
+    pub fn dead_letters_dropped(&self) -> u64 {
+        self.dead_letters_dropped.load(Ordering::Relaxed)
+    }
+
     /// Returns the number of sync attempts
     ///
     /// # Returns
@@ -340,6 +917,250 @@ impl SyncMetrics {
     pub fn average_sync_time(&self) -> u64 {
         self.average_sync_time.load(Ordering::Relaxed)
     }
+
+    /// Returns the exponential moving average of successful sync times
+    ///
+    /// # Returns
+    /// The recency-weighted sync time in nanoseconds, or None if the average was not enabled
+    /// at construction
+    pub fn ema_sync_time(&self) -> Option<u64> {
+        self.ema_sync_time
+            .as_ref()
+            .map(ExponentialMovingAverage::value)
+    }
+
+    /// Returns the median sync time
+    ///
+    /// # Returns
+    /// An upper bound on the median successful sync time in nanoseconds
+    pub fn p50(&self) -> u64 {
+        self.latency.quantile(0.50)
+    }
+
+    /// Returns the 95th percentile sync time
+    ///
+    /// # Returns
+    /// An upper bound on the 95th percentile successful sync time in nanoseconds
+    pub fn p95(&self) -> u64 {
+        self.latency.quantile(0.95)
+    }
+
+    /// Returns the 99th percentile sync time
+    ///
+    /// # Returns
+    /// An upper bound on the 99th percentile successful sync time in nanoseconds
+    pub fn p99(&self) -> u64 {
+        self.latency.quantile(0.99)
+    }
+
+    /// Returns the sync time histogram
+    ///
+    /// # Returns
+    /// Non-cumulative `(upper_bound_ns, count)` pairs in ascending order
+    pub fn latency_buckets(&self) -> Vec<(u64, u64)> {
+        self.latency.bucket_counts()
+    }
+
+    /// Copies the metrics and zeroes them, so each call covers only the interval since the last
+    ///
+    /// Each counter and histogram bucket is read and zeroed in one atomic swap, so a sample
+    /// recorded concurrently is counted in exactly one snapshot. The snapshot is not atomic
+    /// across counters: a concurrent `record_sync_attempt` may land its count in one interval
+    /// and its histogram bucket in the next, and the averages of an interval may include a
+    /// sample whose count fell in the previous one.
+    ///
+    /// # Returns
+    /// The metrics recorded since the previous reset
+    pub fn snapshot_and_reset(&self) -> SyncMetricsSnapshot {
+        SyncMetricsSnapshot {
+            sync_attempts: self.sync_attempts.swap(0, Ordering::Relaxed),
+            failed_syncs: self.failed_syncs.swap(0, Ordering::Relaxed),
+            average_sync_time: self.average_sync_time.swap(0, Ordering::Relaxed),
+            ema_sync_time: self
+                .ema_sync_time
+                .as_ref()
+                .map(ExponentialMovingAverage::take),
+            latency_buckets: self.latency.take_bucket_counts(),
+            dead_lettered: self.dead_lettered.swap(0, Ordering::Relaxed),
+            dead_letters_dropped: self.dead_letters_dropped.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+/// How the local state relates to the state the control plane reports
+///
+/// # Variants
+/// * `InSync` - Both report the same state
+/// * `LocalAhead` - The control plane holds a state the local machine has since left
+/// * `RemoteAhead` - The control plane holds a state the local machine can move to
+/// * `Conflict` - Neither explains the other; an operator has to decide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Divergence {
+    InSync,
+    LocalAhead,
+    RemoteAhead,
+    Conflict,
+}
+
+/// Result of comparing the local state with the control plane's
+///
+/// # Type Parameters
+/// * `S` - Type of the state machine state
+///
+/// # Fields
+/// * `divergence` - How the two states relate
+/// * `local_state` - The local current state
+/// * `remote_state` - The state reported by the control plane
+/// * `reconciliation` - Transition that brings the lagging side up to date, if any
+/// * `unreported` - Local transitions made since the local machine was in the remote state
+#[derive(Debug, Clone)]
+pub struct DivergenceReport<S: Clone> {
+    divergence: Divergence,
+    local_state: S,
+    remote_state: S,
+    reconciliation: Option<StateTransition<S>>,
+    unreported: Vec<StateTransition<S>>,
+}
+
+impl<S: Clone> DivergenceReport<S> {
+    /// Returns how the local and remote states relate
+    ///
+    /// # Returns
+    /// The divergence classification
+    pub fn divergence(&self) -> Divergence {
+        self.divergence
+    }
+
+    /// Returns the local current state
+    ///
+    /// # Returns
+    /// A reference to the local state
+    pub fn local_state(&self) -> &S {
+        &self.local_state
+    }
+
+    /// Returns the state reported by the control plane
+    ///
+    /// # Returns
+    /// A reference to the remote state
+    pub fn remote_state(&self) -> &S {
+        &self.remote_state
+    }
+
+    /// Returns the suggested reconciliation
+    ///
+    /// For `LocalAhead` this runs from the remote state to the local one and is for the
+    /// control plane to apply; for `RemoteAhead` it runs from the local state to the remote one
+    /// and is for the local machine to apply.
+    ///
+    /// # Returns
+    /// The suggested transition, or None when in sync or in conflict
+    pub fn reconciliation(&self) -> Option<&StateTransition<S>> {
+        self.reconciliation.as_ref()
+    }
+
+    /// Returns the local transitions the control plane has not seen
+    ///
+    /// # Returns
+    /// The transitions after the local machine last left the remote state, oldest first
+    pub fn unreported(&self) -> &[StateTransition<S>] {
+        &self.unreported
+    }
+
+    /// Returns whether an operator has to resolve the divergence
+    ///
+    /// # Returns
+    /// True for a conflict
+    pub fn requires_manual_intervention(&self) -> bool {
+        self.divergence == Divergence::Conflict
+    }
+}
+
+/// Compares the local state machine with the state the control plane reports
+///
+/// Classification is deterministic and checked in this order:
+/// 1. `InSync` if the states are equal
+/// 2. `LocalAhead` if the remote state was entered, or left first, in the retained local
+///    history, i.e. the local machine passed through it
+/// 3. `RemoteAhead` if the local machine allows a transition to the remote state
+/// 4. `Conflict` otherwise
+///
+/// History evidence takes precedence over reachability, so a state that is both in history
+/// and reachable is treated as stale remote state rather than a new target.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsistencyChecker;
+
+impl ConsistencyChecker {
+    /// Creates a new consistency checker
+    ///
+    /// # Returns
+    /// A new ConsistencyChecker instance
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Compares the local state machine with a remote state
+    ///
+    /// # Arguments
+    /// * `local` - The local state machine
+    /// * `remote` - The state reported by the control plane
+    ///
+    /// # Returns
+    /// The divergence report
+    pub fn check<S>(&self, local: &StateMachine<S>, remote: &S) -> DivergenceReport<S>
+    where
+        S: Clone + Eq + std::hash::Hash,
+    {
+        let current = local.current_state();
+        let report = |divergence, reconciliation, unreported| DivergenceReport {
+            divergence,
+            local_state: current.clone(),
+            remote_state: remote.clone(),
+            reconciliation,
+            unreported,
+        };
+
+        if current == remote {
+            return report(Divergence::InSync, None, Vec::new());
+        }
+
+        let history = local.history();
+        let left_remote_at = history
+            .iter()
+            .rposition(|transition| transition.to() == remote)
+            .map(|index| index + 1)
+            .or_else(|| {
+                history
+                    .front()
+                    .filter(|first| first.from() == remote)
+                    .map(|_| 0)
+            });
+        if let Some(start) = left_remote_at {
+            let unreported: Vec<_> = history.iter().skip(start).cloned().collect();
+            let replay = StateTransition::new(
+                remote.clone(),
+                current.clone(),
+                Some(format!(
+                    "Control plane behind by {} transitions",
+                    unreported.len()
+                )),
+            )
+            .with_category(TransitionReason::Recovery);
+            return report(Divergence::LocalAhead, Some(replay), unreported);
+        }
+
+        if local.can_transition_to(remote) {
+            let catch_up = StateTransition::new(
+                current.clone(),
+                remote.clone(),
+                Some("Catch up with control plane".to_string()),
+            )
+            .with_category(TransitionReason::Recovery);
+            return report(Divergence::RemoteAhead, Some(catch_up), Vec::new());
+        }
+
+        report(Divergence::Conflict, None, Vec::new())
+    }
 }
 
 /// State synchronization engine
@@ -352,15 +1173,212 @@ impl SyncMetrics {
 /// # Fields
 /// * `engine_id` - Unique identifier for the capture engine instance
 /// * `state_machine` - Local state machine for tracking state changes
-/// * `control_plane_reporter` - Reporter for state change events
-/// * `metrics` - Metrics for sync operations
+/// * `queue` - Reporter, metrics and events held back by the sync strategy
 /// * `config` - Configuration for state synchronization
-pub struct StateSync<S: Clone + Eq + std::hash::Hash> {
+/// * `flush_signal` - Wakes the background flush task when an event is deferred
+/// * `flush_stop` - Tells the background flush task to exit
+/// * `flush_task` - Background task flushing `Eventual` batches after their delay
+/// * `consistency_checker` - Compares local and remote state before updates are pushed
+/// * `remote_state` - Last state known to be held by the control plane
+pub struct StateSync<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> {
     engine_id: String,
     state_machine: Arc<RwLock<StateMachine<S>>>,
-    control_plane_reporter: Box<dyn StateReporter<S>>,
-    metrics: SyncMetrics,
+    queue: Arc<EventQueue<S>>,
     config: StateSyncConfig,
+    consistency_checker: Option<ConsistencyChecker>,
+    remote_state: Mutex<Option<S>>,
+    flush_signal: Arc<Notify>,
+    flush_stop: Arc<Notify>,
+    flush_task: Option<JoinHandle<()>>,
+}
+
+/// Reports state change events, holding back those the sync strategy defers
+///
+/// # Fields
+/// * `reporter` - Reporter for state change events
+/// * `metrics` - Metrics for sync operations
+/// * `retry_attempts` - Attempts per event before it is counted as failed
+/// * `retry_delay` - Delay between attempts
+/// * `pending` - Events not yet reported, oldest first
+/// * `flush_lock` - Serializes flushes so events are reported in order
+/// * `dead_letters` - Events that failed permanently, oldest first
+/// * `dead_letter_capacity` - Maximum dead letters
+/// * `dead_letter_policy` - What to do with a failed event when `dead_letters` is full
+/// * `breaker` - Circuit breaker failing reports fast while the control plane is down
+struct EventQueue<S: Clone> {
+    reporter: Box<dyn StateReporter<S>>,
+    metrics: SyncMetrics,
+    retry_attempts: u32,
+    retry_delay: Duration,
+    pending: Mutex<VecDeque<StateChangeEvent<S>>>,
+    flush_lock: tokio::sync::Mutex<()>,
+    dead_letters: Mutex<VecDeque<StateChangeEvent<S>>>,
+    dead_letter_capacity: usize,
+    dead_letter_policy: DeadLetterPolicy,
+    breaker: CircuitBreaker,
+}
+
+impl<S: Clone + Send + Sync + 'static> EventQueue<S> {
+    /// Holds an event back and returns the number now pending
+    fn push(&self, event: StateChangeEvent<S>) -> usize {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.push_back(event);
+        pending.len()
+    }
+
+    fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Keeps a permanently failed event for later resending
+    fn dead_letter(&self, event: &StateChangeEvent<S>) {
+        let mut dead_letters = self.dead_letters.lock().unwrap_or_else(|e| e.into_inner());
+        if dead_letters.len() >= self.dead_letter_capacity {
+            self.metrics.record_dead_letter_dropped();
+            if self.dead_letter_policy == DeadLetterPolicy::Reject || dead_letters.is_empty() {
+                return;
+            }
+            dead_letters.pop_front();
+        }
+        dead_letters.push_back(event.clone());
+        self.metrics.record_dead_letter();
+    }
+
+    fn drain_dead_letters(&self) -> Vec<StateChangeEvent<S>> {
+        self.dead_letters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain(..)
+            .collect()
+    }
+
+    fn dead_letter_count(&self) -> usize {
+        self.dead_letters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    /// Reports every pending event in order
+    ///
+    /// Events that still fail after all retries are counted as failed syncs and dead-lettered;
+    /// the last such error is returned once the rest of the batch has been reported. Each event
+    /// stays queued until its report completes, so a cancelled flush loses nothing and the next
+    /// flush resends the event it was cancelled on.
+    async fn flush(&self) -> Result<(), CaptureError> {
+        let _guard = self.flush_lock.lock().await;
+        let mut result = Ok(());
+        for _ in 0..self.pending_count() {
+            let Some(event) = self.front() else {
+                break;
+            };
+            let outcome = self.report(&event).await;
+            self.pending
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .pop_front();
+            if let Err(e) = outcome {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    fn front(&self) -> Option<StateChangeEvent<S>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .front()
+            .cloned()
+    }
+
+    /// Reports one event, retrying as configured, and dead-letters it if it still fails
+    async fn report(&self, event: &StateChangeEvent<S>) -> Result<(), CaptureError> {
+        let result = self.report_with_retry(event).await;
+        if result.is_err() {
+            self.dead_letter(event);
+        }
+        result
+    }
+
+    async fn report_with_retry(&self, event: &StateChangeEvent<S>) -> Result<(), CaptureError> {
+        let start = SystemTime::now();
+        let mut attempts = 0;
+        let mut last_error = None;
+
+        while attempts < self.retry_attempts {
+            let Some(admission) = self.breaker.admit(Instant::now()) else {
+                self.metrics.record_failed_sync();
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable),
+                    "Control plane circuit is open; state report failed fast",
+                ));
+            };
+            match self.reporter.report_state(event).await {
+                Ok(_) => {
+                    admission.succeed();
+                    // Record successful sync
+                    if let Ok(duration) = start.elapsed() {
+                        self.metrics.record_sync_attempt(duration.as_nanos() as u64);
+                    }
+                    return Ok(());
+                }
+                Err(e) if !e.is_retryable() => {
+                    admission.succeed();
+                    self.metrics.record_failed_sync();
+                    return Err(e);
+                }
+                Err(e) => {
+                    admission.fail(Instant::now());
+                    attempts += 1;
+                    last_error = Some(e);
+                    if attempts < self.retry_attempts {
+                        tokio::time::sleep(self.retry_delay).await;
+                    }
+                }
+            }
+        }
+
+        // Record failed sync
+        self.metrics.record_failed_sync();
+
+        // Return last error if all retries failed
+        Err(last_error.unwrap_or_else(|| {
+            *CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                "Failed to report state change after all retries",
+            )
+        }))
+    }
+}
+
+/// Time `StateSync::shutdown` waits for the flush task to finish a flush in progress
+pub const FLUSH_TASK_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Flushes deferred events `delay` after the first of each batch is queued
+///
+/// Returns once `stop` is notified, finishing a flush already in progress first.
+async fn run_flush_task<S: Clone + Send + Sync + 'static>(
+    queue: Weak<EventQueue<S>>,
+    signal: Arc<Notify>,
+    stop: Arc<Notify>,
+    delay: Duration,
+) {
+    loop {
+        tokio::select! {
+            _ = signal.notified() => {}
+            _ = stop.notified() => return,
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = stop.notified() => return,
+        }
+        let Some(queue) = queue.upgrade() else {
+            return;
+        };
+        // Failures are recorded in the sync metrics
+        let _ = queue.flush().await;
+    }
 }
 
 /// Trait for reporting state changes
@@ -394,18 +1412,28 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSync<S> {
 
     /// Updates the state machine with a new state
     ///
+    /// With a consistency checker and a known remote state, the update is refused while the
+    /// local and remote states conflict.
+    ///
     /// # Arguments
     /// * `new_state` - New state to transition to
     /// * `metadata` - Additional metadata for the state change event
     ///
     /// # Returns
-    /// An error if the state change could not be reported
+    /// An error if the states conflict or the state change could not be reported
     pub async fn update_state(
         &self,
         new_state: S,
         metadata: HashMap<String, String>,
     ) -> Result<(), CaptureError> {
-        let start = SystemTime::now();
+        if let Some(report) = self.check_known_remote()? {
+            if report.requires_manual_intervention() {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                    "Local and control plane state conflict; manual intervention required",
+                ));
+            }
+        }
 
         // Get current state and create transition
         let current_state = self
@@ -423,7 +1451,8 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSync<S> {
         let transition = StateTransition::new(current_state, new_state.clone(), None);
 
         // Update local state machine
-        self.state_machine
+        let outcome = self
+            .state_machine
             .write()
             .map_err(|_| {
                 CaptureError::new(
@@ -431,43 +1460,159 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSync<S> {
                     "Failed to acquire state machine write lock",
                 )
             })?
-            .transition_to(new_state, Some("State update".to_string()))?;
+            .apply_transition(new_state, Some("State update".to_string()))?;
 
-        let event = StateChangeEvent::new(self.engine_id.clone(), transition, metadata);
+        // Debounced repeats are not reported to the control plane
+        if outcome == TransitionOutcome::Suppressed {
+            return Ok(());
+        }
 
-        // Attempt to report state change
-        let mut attempts = 0;
-        let mut last_error = None;
+        let event = StateChangeEvent::new(self.engine_id.clone(), transition, metadata);
 
-        while attempts < self.config.retry_attempts() {
-            match self.control_plane_reporter.report_state(&event).await {
-                Ok(_) => {
-                    // Record successful sync
-                    if let Ok(duration) = start.elapsed() {
-                        self.metrics.record_sync_attempt(duration.as_nanos() as u64);
+        match self.config.strategy() {
+            SyncStrategy::Immediate => {
+                self.queue.report(&event).await?;
+                if let Ok(mut remote) = self.remote_state.lock() {
+                    if remote.is_some() {
+                        *remote = Some(event.transition().to().clone());
                     }
-                    return Ok(());
                 }
-                Err(e) => {
-                    attempts += 1;
-                    last_error = Some(e);
-                    if attempts < self.config.retry_attempts() {
-                        tokio::time::sleep(self.config.retry_delay()).await;
-                    }
+                Ok(())
+            }
+            SyncStrategy::Eventual { .. } => {
+                if self.queue.push(event) >= self.config.max_batch_size() {
+                    self.queue.flush().await
+                } else {
+                    self.flush_signal.notify_one();
+                    Ok(())
                 }
             }
+            SyncStrategy::OnDemand => {
+                self.queue.push(event);
+                Ok(())
+            }
         }
+    }
 
-        // Record failed sync
-        self.metrics.record_failed_sync();
+    /// Compares the local state with a state reported by the control plane
+    ///
+    /// The remote state is remembered and checked again before each update. Uses the
+    /// configured consistency checker, or a default one.
+    ///
+    /// # Arguments
+    /// * `remote` - The state the control plane holds for this engine
+    ///
+    /// # Returns
+    /// The divergence report
+    pub fn check_consistency(&self, remote: S) -> Result<DivergenceReport<S>, CaptureError> {
+        let report = self.with_machine(|machine| {
+            self.consistency_checker
+                .unwrap_or_default()
+                .check(machine, &remote)
+        })?;
+        if let Ok(mut known) = self.remote_state.lock() {
+            *known = Some(remote);
+        }
+        Ok(report)
+    }
+
+    /// Runs the configured consistency checker against the last known remote state
+    fn check_known_remote(&self) -> Result<Option<DivergenceReport<S>>, CaptureError> {
+        let Some(checker) = self.consistency_checker else {
+            return Ok(None);
+        };
+        let Some(remote) = self
+            .remote_state
+            .lock()
+            .ok()
+            .and_then(|known| known.clone())
+        else {
+            return Ok(None);
+        };
+        self.with_machine(|machine| Some(checker.check(machine, &remote)))
+    }
+
+    /// Runs `f` with the state machine read-locked
+    fn with_machine<T>(&self, f: impl FnOnce(&StateMachine<S>) -> T) -> Result<T, CaptureError> {
+        self.state_machine
+            .read()
+            .map_err(|_| {
+                *CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
+                    "Failed to acquire state machine read lock",
+                )
+            })
+            .map(|machine| f(&machine))
+    }
+
+    /// Reports every event held back by the sync strategy
+    ///
+    /// # Returns
+    /// The last error if any event could not be reported after all retries; the other events
+    /// are still reported
+    pub async fn flush_pending(&self) -> Result<(), CaptureError> {
+        self.queue.flush().await
+    }
+
+    /// Returns the number of events waiting to be reported
+    ///
+    /// # Returns
+    /// The number of pending events
+    pub fn pending_count(&self) -> usize {
+        self.queue.pending_count()
+    }
+
+    /// Takes the events that could not be reported after all retries
+    ///
+    /// A reconciliation pass can resend them once the control plane is reachable again.
+    ///
+    /// # Returns
+    /// The dead-lettered events, oldest first
+    pub fn drain_dead_letters(&self) -> Vec<StateChangeEvent<S>> {
+        self.queue.drain_dead_letters()
+    }
+
+    /// Returns the state of the circuit breaker around the reporter
+    ///
+    /// An open circuit stays `Open` here until the first report after its cooldown.
+    ///
+    /// # Returns
+    /// The circuit state
+    pub fn circuit_state(&self) -> CircuitState {
+        self.queue.breaker.state()
+    }
+
+    /// Returns the number of events in the dead-letter queue
+    ///
+    /// # Returns
+    /// The number of dead letters
+    pub fn dead_letter_count(&self) -> usize {
+        self.queue.dead_letter_count()
+    }
+
+    /// Stops the background flush task and reports pending events
+    ///
+    /// A flush the task has already started is allowed `FLUSH_TASK_STOP_TIMEOUT` to finish
+    /// before the task is aborted; events it had not reported stay queued for the final flush.
+    ///
+    /// # Returns
+    /// The result of the final flush
+    pub async fn shutdown(mut self) -> Result<(), CaptureError> {
+        if let Some(mut task) = self.flush_task.take() {
+            self.flush_stop.notify_one();
+            if tokio::time::timeout(FLUSH_TASK_STOP_TIMEOUT, &mut task)
+                .await
+                .is_err()
+            {
+                task.abort();
+            }
+        }
+        self.queue.flush().await
+    }
 
-        // Return last error if all retries failed
-        Err(last_error.unwrap_or_else(|| {
-            *CaptureError::new(
-                CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed),
-                "Failed to report state change after all retries",
-            )
-        }))
+    /// Returns the id of the capture engine instance whose state is synchronized
+    pub fn engine_id(&self) -> &str {
+        &self.engine_id
     }
 
     /// Returns the state synchronization configuration
@@ -475,7 +1620,7 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSync<S> {
     /// # Returns
     /// A reference to the state synchronization configuration
     pub fn metrics(&self) -> &SyncMetrics {
-        &self.metrics
+        &self.queue.metrics
     }
 
     /// Returns the state synchronization configuration
@@ -495,6 +1640,38 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSync<S> {
     }
 }
 
+impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> Drop for StateSync<S> {
+    /// Stops the flush task and hands pending events to a final flush so the last
+    /// transitions are not lost
+    ///
+    /// Inside a runtime the final flush is spawned; outside one it runs to completion on a
+    /// temporary runtime before the drop returns.
+    fn drop(&mut self) {
+        if let Some(task) = self.flush_task.take() {
+            task.abort();
+        }
+        if self.queue.pending_count() == 0 {
+            return;
+        }
+        let queue = self.queue.clone();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    let _ = queue.flush().await;
+                });
+            }
+            Err(_) => {
+                if let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    let _ = runtime.block_on(queue.flush());
+                }
+            }
+        }
+    }
+}
+
 /// Builder for StateSync
 ///
 /// This struct is used to build a new StateSync instance
@@ -507,11 +1684,13 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSync<S> {
 /// * `state_machine` - Local state machine for tracking state changes
 /// * `control_plane_reporter` - Reporter for state change events
 /// * `config` - Configuration for state synchronization
+/// * `consistency_checker` - Optional check of local against remote state before updates
 pub struct StateSyncBuilder<S: Clone + Eq + std::hash::Hash> {
     engine_id: Option<String>,
     state_machine: Option<StateMachine<S>>,
     control_plane_reporter: Option<Box<dyn StateReporter<S>>>,
     config: Option<StateSyncConfig>,
+    consistency_checker: Option<ConsistencyChecker>,
 }
 
 impl<S: Clone + Eq + std::hash::Hash> Clone for StateSyncBuilder<S>
@@ -528,6 +1707,7 @@ where
             state_machine: self.state_machine.clone(),
             control_plane_reporter: None, // Can't clone the reporter
             config: self.config.clone(),
+            consistency_checker: self.consistency_checker,
         }
     }
 }
@@ -543,6 +1723,7 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSyncBuilder<S
             state_machine: None,
             control_plane_reporter: None,
             config: None,
+            consistency_checker: None,
         }
     }
 
@@ -594,6 +1775,18 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSyncBuilder<S
         self
     }
 
+    /// Checks local against remote state before each update once a remote state is known
+    ///
+    /// # Arguments
+    /// * `checker` - Consistency checker to run before pushing updates
+    ///
+    /// # Returns
+    /// The updated StateSyncBuilder instance
+    pub fn with_consistency_checker(mut self, checker: ConsistencyChecker) -> Self {
+        self.consistency_checker = Some(checker);
+        self
+    }
+
     /// Builds a new StateSync instance
     ///
     /// # Returns
@@ -627,12 +1820,47 @@ impl<S: Clone + Eq + std::hash::Hash + Send + Sync + 'static> StateSyncBuilder<S
             )
         })?;
 
+        let metrics = match config.ema_alpha() {
+            Some(alpha) => SyncMetrics::with_ema(alpha)?,
+            None => SyncMetrics::new(),
+        };
+        let queue = Arc::new(EventQueue {
+            reporter: control_plane_reporter,
+            metrics,
+            retry_attempts: config.retry_attempts(),
+            retry_delay: config.retry_delay(),
+            pending: Mutex::new(VecDeque::new()),
+            flush_lock: tokio::sync::Mutex::new(()),
+            dead_letters: Mutex::new(VecDeque::new()),
+            dead_letter_capacity: config.dead_letter_capacity(),
+            dead_letter_policy: config.dead_letter_policy(),
+            breaker: CircuitBreaker::new(&config),
+        });
+        let flush_signal = Arc::new(Notify::new());
+        let flush_stop = Arc::new(Notify::new());
+        // Without a runtime, Eventual batches flush only at max_batch_size or on request
+        let flush_task = match (config.strategy(), tokio::runtime::Handle::try_current()) {
+            (SyncStrategy::Eventual { delay_ms }, Ok(handle)) => {
+                Some(handle.spawn(run_flush_task(
+                    Arc::downgrade(&queue),
+                    flush_signal.clone(),
+                    flush_stop.clone(),
+                    Duration::from_millis(delay_ms),
+                )))
+            }
+            _ => None,
+        };
+
         Ok(StateSync {
             engine_id,
             state_machine: Arc::new(RwLock::new(state_machine)),
-            control_plane_reporter,
-            metrics: SyncMetrics::new(),
+            queue,
             config,
+            consistency_checker: self.consistency_checker,
+            remote_state: Mutex::new(None),
+            flush_signal,
+            flush_stop,
+            flush_task,
         })
     }
 }
@@ -843,6 +2071,7 @@ mod state_config_tests {
             report_interval: Duration::from_secs(5),
             retry_attempts: 5,
             retry_delay: Duration::from_millis(500),
+            ..Default::default()
         };
 
         assert_eq!(config.report_interval, Duration::from_secs(5));
@@ -856,6 +2085,7 @@ mod state_config_tests {
             report_interval: Duration::from_secs(0),
             retry_attempts: 0,
             retry_delay: Duration::from_secs(0),
+            ..Default::default()
         };
 
         assert_eq!(config.report_interval, Duration::from_secs(0));
@@ -869,6 +2099,7 @@ mod state_config_tests {
             report_interval: Duration::from_secs(u64::MAX),
             retry_attempts: u32::MAX,
             retry_delay: Duration::from_secs(u64::MAX),
+            ..Default::default()
         };
 
         assert_eq!(config.report_interval, Duration::from_secs(u64::MAX));
@@ -882,6 +2113,7 @@ mod state_config_tests {
             report_interval: Duration::from_secs(2),
             retry_attempts: 4,
             retry_delay: Duration::from_millis(750),
+            ..Default::default()
         };
 
         let cloned = original.clone();
@@ -908,6 +2140,65 @@ mod sync_metrics_tests {
     use super::*;
     use std::sync::Arc;
 
+    #[test]
+    fn test_percentiles_expose_tail_latency() {
+        let metrics = SyncMetrics::new();
+        assert_eq!((metrics.p50(), metrics.p99()), (0, 0));
+
+        for _ in 0..98 {
+            metrics.record_sync_attempt(1_000);
+        }
+        metrics.record_sync_attempt(5_000_000);
+        metrics.record_sync_attempt(5_000_000);
+
+        // 1_000 falls in the [512, 1023] bucket and is reported by its upper bound
+        assert_eq!(metrics.p50(), 1_023);
+        assert_eq!(metrics.p95(), 1_023);
+        // The tail bucket is capped at the largest value seen
+        assert_eq!(metrics.p99(), 5_000_000);
+        assert!(metrics.average_sync_time() < metrics.p99());
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        let histogram = LatencyHistogram::default();
+        for value in [0, 1, 2, 3, 4, u64::MAX] {
+            histogram.record(value);
+        }
+        let buckets = histogram.bucket_counts();
+        assert_eq!(buckets.len(), LATENCY_BUCKETS);
+        assert_eq!(buckets[0], (0, 1));
+        assert_eq!(buckets[1], (1, 1));
+        assert_eq!(buckets[2], (3, 2));
+        assert_eq!(buckets[3], (7, 1));
+        assert_eq!(buckets[64], (u64::MAX, 1));
+        assert_eq!(histogram.quantile(1.0), u64::MAX);
+        assert_eq!(histogram.quantile(0.0), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_histogram_updates() {
+        let metrics = Arc::new(SyncMetrics::new());
+        let handles: Vec<_> = (0..8u64)
+            .map(|i| {
+                let metrics = Arc::clone(&metrics);
+                tokio::spawn(async move {
+                    for _ in 0..1_000 {
+                        metrics.record_sync_attempt(100 * (i + 1));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let recorded: u64 = metrics.latency_buckets().iter().map(|(_, n)| n).sum();
+        assert_eq!(recorded, metrics.sync_attempts());
+        assert_eq!(recorded, 8_000);
+        assert!(metrics.p50() <= metrics.p99());
+    }
+
     #[test]
     fn test_new_metrics() {
         let metrics = SyncMetrics::new();
@@ -1039,7 +2330,7 @@ mod sync_metrics_tests {
         }
 
         // Average should not overflow
-        assert!(metrics.average_sync_time() <= u64::MAX);
+        assert_eq!(metrics.average_sync_time(), u64::MAX / 2);
     }
 
     #[test]
@@ -1051,7 +2342,7 @@ mod sync_metrics_tests {
             metrics.record_sync_attempt(1);
         }
 
-        assert!(metrics.sync_attempts() <= u64::MAX);
+        assert_eq!(metrics.sync_attempts(), 100);
     }
 
     #[test]
@@ -1063,7 +2354,7 @@ mod sync_metrics_tests {
             metrics.record_failed_sync();
         }
 
-        assert!(metrics.failed_syncs() <= u64::MAX);
+        assert_eq!(metrics.failed_syncs(), 100);
     }
 
     #[tokio::test]
@@ -1110,6 +2401,104 @@ mod sync_metrics_tests {
         assert_eq!(metrics.average_sync_time(), 0);
     }
 
+    #[test]
+    fn test_ema_converges_after_step_change() {
+        let metrics = SyncMetrics::with_ema(0.2).unwrap();
+        for _ in 0..100 {
+            metrics.record_sync_attempt(1_000);
+        }
+        assert_eq!(metrics.ema_sync_time(), Some(1_000));
+
+        // After a step to 10µs the EMA closes (1 - alpha)^n of the gap while the cumulative
+        // average stays diluted by the old samples
+        let mut previous = 1_000;
+        for n in 1..=40 {
+            metrics.record_sync_attempt(10_000);
+            let ema = metrics.ema_sync_time().unwrap();
+            let expected = 10_000.0 - 9_000.0 * 0.8f64.powi(n);
+            assert!((ema as f64 - expected).abs() <= 1.0, "step {}: {}", n, ema);
+            assert!(ema >= previous);
+            previous = ema;
+        }
+        assert!(previous >= 9_998);
+        assert!(metrics.average_sync_time() < 4_000);
+    }
+
+    #[test]
+    fn test_snapshot_and_reset_under_concurrent_recording() {
+        use std::sync::atomic::AtomicBool;
+
+        let metrics = Arc::new(SyncMetrics::with_ema(0.5).unwrap());
+        let recording = Arc::new(AtomicBool::new(true));
+        let (threads, samples) = (4, 20_000u64);
+
+        let recorders: Vec<_> = (0..threads)
+            .map(|_| {
+                let metrics = Arc::clone(&metrics);
+                std::thread::spawn(move || {
+                    for i in 0..samples {
+                        metrics.record_sync_attempt(100 + i % 900);
+                        metrics.record_failed_sync();
+                        metrics.record_dead_letter();
+                    }
+                })
+            })
+            .collect();
+        let resetter = {
+            let (metrics, recording) = (Arc::clone(&metrics), Arc::clone(&recording));
+            std::thread::spawn(move || {
+                let mut snapshots = Vec::new();
+                while recording.load(Ordering::Relaxed) {
+                    snapshots.push(metrics.snapshot_and_reset());
+                    std::thread::yield_now();
+                }
+                snapshots
+            })
+        };
+        for recorder in recorders {
+            recorder.join().unwrap();
+        }
+        recording.store(false, Ordering::Relaxed);
+        let mut snapshots = resetter.join().unwrap();
+        snapshots.push(metrics.snapshot_and_reset());
+
+        // Every sample lands in exactly one interval
+        let total = threads * samples;
+        let sum = |f: fn(&SyncMetricsSnapshot) -> u64| snapshots.iter().map(f).sum::<u64>();
+        assert_eq!(sum(|s| s.sync_attempts), total);
+        assert_eq!(sum(|s| s.failed_syncs), total);
+        assert_eq!(sum(|s| s.dead_lettered), total);
+        let bucketed = sum(|s| s.latency_buckets.iter().map(|(_, count)| count).sum());
+        assert_eq!(bucketed, total);
+
+        // Averages may straddle intervals but stay within the recorded range
+        for snapshot in snapshots.iter().filter(|s| s.sync_attempts > 0) {
+            assert!(snapshot.average_sync_time <= 999);
+            assert!(snapshot.ema_sync_time.unwrap() <= 999);
+        }
+
+        let empty = metrics.snapshot_and_reset();
+        assert_eq!((empty.sync_attempts, empty.ema_sync_time), (0, Some(0)));
+        assert!(empty.latency_buckets.iter().all(|(_, count)| *count == 0));
+        metrics.record_sync_attempt(50);
+        let snapshot = metrics.snapshot_and_reset();
+        assert_eq!(
+            (snapshot.average_sync_time, snapshot.ema_sync_time),
+            (50, Some(50))
+        );
+    }
+
+    #[test]
+    fn test_ema_disabled_and_invalid_alpha() {
+        let metrics = SyncMetrics::new();
+        metrics.record_sync_attempt(100);
+        assert_eq!(metrics.ema_sync_time(), None);
+        assert_eq!(SyncMetrics::with_ema(1.0).unwrap().ema_sync_time(), Some(0));
+        for alpha in [0.0, -0.5, 1.5, f64::NAN] {
+            assert!(SyncMetrics::with_ema(alpha).is_err());
+        }
+    }
+
     #[test]
     fn test_metrics_independence() {
         let metrics1 = SyncMetrics::new();
@@ -1225,7 +2614,7 @@ mod state_sync_tests {
         metrics.record_sync_attempt(u64::MAX);
         assert_eq!(metrics.average_sync_time(), u64::MAX);
 
-        assert!(metrics.failed_syncs() <= u64::MAX);
+        assert_eq!(metrics.failed_syncs(), 0);
     }
 
     async fn sync_with_retry(
@@ -1249,6 +2638,7 @@ mod state_sync_tests {
             retry_attempts: 3,
             retry_delay: Duration::from_millis(100),
             report_interval: Duration::from_secs(1),
+            ..Default::default()
         };
 
         // Test with failing sync that should retry
@@ -1289,6 +2679,7 @@ mod state_sync_tests {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::capture_engine::capture::capture_error::NetworkErrorKind;
     use mockall::mock;
     use mockall::predicate::*;
     use std::future::Future;
@@ -1341,6 +2732,7 @@ mod tests {
                 report_interval: Duration::from_secs(1),
                 retry_attempts: 3,
                 retry_delay: Duration::from_secs(1),
+                ..Default::default()
             };
 
             let mock_reporter = MockStateReporter::new();
@@ -1475,4 +2867,606 @@ mod tests {
         assert_eq!(state_sync.engine_id, "test-engine");
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_debounced_transitions_are_not_reported() -> Result<(), CaptureError> {
+        let mut ctx = TestContext::new();
+        ctx.state_machine
+            .add_transition(TestState::Final, TestState::Final);
+        ctx.state_machine
+            .set_debounce_window(Some(Duration::from_secs(60)));
+
+        ctx.mock_reporter
+            .expect_report_state()
+            .times(2)
+            .returning(|_event| Box::pin(async { Ok(()) }));
+
+        let state_sync = StateSyncBuilder::<TestState>::new()
+            .with_engine_id("test-engine".to_string())
+            .with_state_machine(ctx.state_machine)
+            .with_reporter(Box::new(ctx.mock_reporter))
+            .with_config(ctx.config)
+            .build()?;
+
+        state_sync
+            .update_state(TestState::Final, HashMap::new())
+            .await?;
+        state_sync
+            .update_state(TestState::Final, HashMap::new())
+            .await?;
+        state_sync
+            .update_state(TestState::Final, HashMap::new())
+            .await?;
+
+        let state_machine = state_sync.state_machine.read().unwrap();
+        assert_eq!(state_machine.metrics().suppressed_transitions(), 1);
+        assert_eq!(state_machine.metrics().transitions_count(), 2);
+        Ok(())
+    }
+
+    /// Reporter recording the target state of every event it receives, after `delay`
+    #[derive(Clone, Default)]
+    struct RecordingReporter {
+        reported: Arc<Mutex<Vec<TestState>>>,
+        delay: Duration,
+    }
+
+    impl RecordingReporter {
+        fn reported(&self) -> Vec<TestState> {
+            self.reported.lock().unwrap().clone()
+        }
+    }
+
+    impl StateReporter<TestState> for RecordingReporter {
+        fn report_state<'a>(
+            &'a self,
+            event: &'a StateChangeEvent<TestState>,
+        ) -> Pin<Box<dyn Future<Output = Result<(), CaptureError>> + Send + 'a>> {
+            Box::pin(async move {
+                if !self.delay.is_zero() {
+                    tokio::time::sleep(self.delay).await;
+                }
+                self.reported
+                    .lock()
+                    .unwrap()
+                    .push(event.transition().to().clone());
+                Ok(())
+            })
+        }
+    }
+
+    fn batching_sync(
+        strategy: SyncStrategy,
+        max_batch_size: usize,
+    ) -> (StateSync<TestState>, RecordingReporter) {
+        slow_batching_sync(strategy, max_batch_size, Duration::ZERO)
+    }
+
+    fn slow_batching_sync(
+        strategy: SyncStrategy,
+        max_batch_size: usize,
+        delay: Duration,
+    ) -> (StateSync<TestState>, RecordingReporter) {
+        let mut ctx = TestContext::new();
+        ctx.state_machine
+            .add_transition(TestState::Final, TestState::Initial);
+        let reporter = RecordingReporter {
+            delay,
+            ..Default::default()
+        };
+        let sync = StateSyncBuilder::<TestState>::new()
+            .with_engine_id("test-engine".to_string())
+            .with_state_machine(ctx.state_machine)
+            .with_reporter(Box::new(reporter.clone()))
+            .with_config(
+                ctx.config
+                    .with_strategy(strategy)
+                    .with_max_batch_size(max_batch_size),
+            )
+            .build()
+            .unwrap();
+        (sync, reporter)
+    }
+
+    async fn toggle(sync: &StateSync<TestState>, times: usize) {
+        for i in 0..times {
+            let state = if i % 2 == 0 {
+                TestState::Final
+            } else {
+                TestState::Initial
+            };
+            sync.update_state(state, HashMap::new()).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_eventual_flushes_after_delay() {
+        let (sync, reporter) = batching_sync(SyncStrategy::Eventual { delay_ms: 50 }, 64);
+        toggle(&sync, 3).await;
+        assert_eq!(sync.pending_count(), 3);
+        assert!(reporter.reported().is_empty());
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(sync.pending_count(), 0);
+        assert_eq!(
+            reporter.reported(),
+            vec![TestState::Final, TestState::Initial, TestState::Final]
+        );
+        assert_eq!(sync.metrics().sync_attempts(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_eventual_flushes_at_max_batch_size() {
+        let (sync, reporter) = batching_sync(SyncStrategy::Eventual { delay_ms: 60_000 }, 2);
+        toggle(&sync, 3).await;
+        assert_eq!(reporter.reported().len(), 2);
+        assert_eq!(sync.pending_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_on_demand_waits_for_explicit_flush() {
+        let (sync, reporter) = batching_sync(SyncStrategy::OnDemand, 2);
+        toggle(&sync, 4).await;
+        assert_eq!(sync.pending_count(), 4);
+        assert!(reporter.reported().is_empty());
+
+        sync.flush_pending().await.unwrap();
+        assert_eq!(sync.pending_count(), 0);
+        assert_eq!(reporter.reported().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_immediate_reports_without_queueing() {
+        let (sync, reporter) = batching_sync(SyncStrategy::Immediate, 64);
+        toggle(&sync, 2).await;
+        assert_eq!(sync.pending_count(), 0);
+        assert_eq!(reporter.reported().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pending_events_flushed_on_shutdown_and_drop() {
+        let (sync, reporter) = batching_sync(SyncStrategy::OnDemand, 64);
+        toggle(&sync, 1).await;
+        sync.shutdown().await.unwrap();
+        assert_eq!(reporter.reported(), vec![TestState::Final]);
+
+        let (sync, reporter) = batching_sync(SyncStrategy::Eventual { delay_ms: 60_000 }, 64);
+        toggle(&sync, 2).await;
+        drop(sync);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(reporter.reported().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_during_background_flush_delivers_queued_events() {
+        let (sync, reporter) = slow_batching_sync(
+            SyncStrategy::Eventual { delay_ms: 10 },
+            64,
+            Duration::from_millis(30),
+        );
+        toggle(&sync, 3).await;
+
+        // The flush task is now reporting the first event
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        assert!(reporter.reported().is_empty());
+        sync.shutdown().await.unwrap();
+        assert_eq!(
+            reporter.reported(),
+            vec![TestState::Final, TestState::Initial, TestState::Final]
+        );
+    }
+
+    #[test]
+    fn test_drop_without_runtime_delivers_queued_events() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (sync, reporter) =
+            runtime.block_on(async { batching_sync(SyncStrategy::OnDemand, 64) });
+        runtime.block_on(toggle(&sync, 2));
+        drop(runtime);
+
+        drop(sync);
+        assert_eq!(
+            reporter.reported(),
+            vec![TestState::Final, TestState::Initial]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fatal_errors_are_not_retried() {
+        let mut ctx = TestContext::new();
+        ctx.mock_reporter
+            .expect_report_state()
+            .times(1)
+            .returning(|_event| {
+                Box::pin(async {
+                    Err(*CaptureError::new(
+                        CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                        "bad endpoint",
+                    ))
+                })
+            });
+
+        let state_sync = StateSyncBuilder::<TestState>::new()
+            .with_engine_id("test-engine".to_string())
+            .with_state_machine(ctx.state_machine)
+            .with_reporter(Box::new(ctx.mock_reporter))
+            .with_config(ctx.config)
+            .build()
+            .unwrap();
+
+        let err = state_sync
+            .update_state(TestState::Final, HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(!err.is_retryable());
+        assert_eq!(state_sync.metrics().failed_syncs(), 1);
+    }
+
+    /// Reporter that fails with a transient error while `failing` is set
+    #[derive(Clone, Default)]
+    struct FlakyReporter {
+        failing: Arc<std::sync::atomic::AtomicBool>,
+        calls: Arc<AtomicU64>,
+        inner: RecordingReporter,
+    }
+
+    impl StateReporter<TestState> for FlakyReporter {
+        fn report_state<'a>(
+            &'a self,
+            event: &'a StateChangeEvent<TestState>,
+        ) -> Pin<Box<dyn Future<Output = Result<(), CaptureError>> + Send + 'a>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failing.load(Ordering::SeqCst) {
+                return Box::pin(async {
+                    Err(*CaptureError::new(
+                        CaptureErrorKind::Network(NetworkErrorKind::Timeout),
+                        "control plane unreachable",
+                    ))
+                });
+            }
+            self.inner.report_state(event)
+        }
+    }
+
+    fn dead_letter_sync(policy: DeadLetterPolicy) -> (StateSync<TestState>, FlakyReporter) {
+        let mut ctx = TestContext::new();
+        ctx.state_machine
+            .add_transition(TestState::Final, TestState::Initial);
+        let reporter = FlakyReporter::default();
+        reporter.failing.store(true, Ordering::SeqCst);
+        let sync = StateSyncBuilder::<TestState>::new()
+            .with_engine_id("test-engine".to_string())
+            .with_state_machine(ctx.state_machine)
+            .with_reporter(Box::new(reporter.clone()))
+            .with_config(
+                ctx.config
+                    .with_retry_attempts(2)
+                    .with_retry_delay(Duration::from_millis(1))
+                    .with_strategy(SyncStrategy::OnDemand)
+                    .with_dead_letter_capacity(2)
+                    .with_dead_letter_policy(policy),
+            )
+            .build()
+            .unwrap();
+        (sync, reporter)
+    }
+
+    fn targets(events: &[StateChangeEvent<TestState>]) -> Vec<TestState> {
+        events
+            .iter()
+            .map(|event| event.transition().to().clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_failed_events_are_dead_lettered() {
+        let (sync, reporter) = dead_letter_sync(DeadLetterPolicy::DropOldest);
+        toggle(&sync, 1).await;
+        assert!(sync.flush_pending().await.is_err());
+        assert_eq!(sync.dead_letter_count(), 1);
+        assert_eq!(sync.metrics().dead_lettered(), 1);
+
+        // Reconciliation resends once the control plane is back
+        reporter.failing.store(false, Ordering::SeqCst);
+        let dead_letters = sync.drain_dead_letters();
+        assert_eq!(sync.dead_letter_count(), 0);
+        for event in &dead_letters {
+            reporter.report_state(event).await.unwrap();
+        }
+        assert_eq!(reporter.inner.reported(), vec![TestState::Final]);
+    }
+
+    #[tokio::test]
+    async fn test_full_dead_letter_queue_drops_oldest() {
+        let (sync, _reporter) = dead_letter_sync(DeadLetterPolicy::DropOldest);
+        toggle(&sync, 3).await;
+        assert!(sync.flush_pending().await.is_err());
+
+        assert_eq!(
+            targets(&sync.drain_dead_letters()),
+            vec![TestState::Initial, TestState::Final]
+        );
+        assert_eq!(sync.metrics().dead_lettered(), 3);
+        assert_eq!(sync.metrics().dead_letters_dropped(), 1);
+        assert_eq!(sync.metrics().failed_syncs(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_full_dead_letter_queue_rejects_new() {
+        let (sync, _reporter) = dead_letter_sync(DeadLetterPolicy::Reject);
+        toggle(&sync, 3).await;
+        assert!(sync.flush_pending().await.is_err());
+
+        assert_eq!(
+            targets(&sync.drain_dead_letters()),
+            vec![TestState::Final, TestState::Initial]
+        );
+        assert_eq!(sync.metrics().dead_lettered(), 2);
+        assert_eq!(sync.metrics().dead_letters_dropped(), 1);
+    }
+
+    fn breaker_sync(threshold: u32, probes: u32) -> (StateSync<TestState>, FlakyReporter) {
+        let mut ctx = TestContext::new();
+        ctx.state_machine
+            .add_transition(TestState::Final, TestState::Initial);
+        let reporter = FlakyReporter::default();
+        let sync = StateSyncBuilder::<TestState>::new()
+            .with_engine_id("test-engine".to_string())
+            .with_state_machine(ctx.state_machine)
+            .with_reporter(Box::new(reporter.clone()))
+            .with_config(
+                ctx.config
+                    .with_retry_attempts(1)
+                    .with_circuit_failure_threshold(threshold)
+                    .with_circuit_cooldown(Duration::from_millis(50))
+                    .with_circuit_half_open_probes(probes),
+            )
+            .build()
+            .unwrap();
+        (sync, reporter)
+    }
+
+    /// Toggles the state once, returning the report result
+    async fn report_once(sync: &StateSync<TestState>) -> Result<(), CaptureError> {
+        let state = match sync.current_state().unwrap() {
+            TestState::Initial => TestState::Final,
+            TestState::Final => TestState::Initial,
+        };
+        sync.update_state(state, HashMap::new()).await
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_fails_fast() {
+        let (sync, reporter) = breaker_sync(3, 1);
+        reporter.failing.store(true, Ordering::SeqCst);
+        for _ in 0..3 {
+            assert!(report_once(&sync).await.is_err());
+        }
+        assert_eq!(sync.circuit_state(), CircuitState::Open);
+
+        let err = report_once(&sync).await.unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable)
+        ));
+        // The open circuit never reached the reporter
+        assert_eq!(reporter.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(sync.dead_letter_count(), 4);
+
+        // After the cooldown a successful trial report closes the circuit
+        reporter.failing.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        report_once(&sync).await.unwrap();
+        assert_eq!(sync.circuit_state(), CircuitState::Closed);
+        assert_eq!(reporter.calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_reopens_circuit() {
+        let (sync, reporter) = breaker_sync(1, 2);
+        reporter.failing.store(true, Ordering::SeqCst);
+        assert!(report_once(&sync).await.is_err());
+        assert_eq!(sync.circuit_state(), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(report_once(&sync).await.is_err());
+        assert_eq!(sync.circuit_state(), CircuitState::Open);
+        assert!(report_once(&sync).await.is_err());
+        assert_eq!(reporter.calls.load(Ordering::SeqCst), 2);
+
+        // Both probes must succeed before the circuit closes
+        reporter.failing.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        report_once(&sync).await.unwrap();
+        assert_eq!(sync.circuit_state(), CircuitState::HalfOpen);
+        report_once(&sync).await.unwrap();
+        assert_eq!(sync.circuit_state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_success_resets_consecutive_failures() {
+        let (sync, reporter) = breaker_sync(3, 1);
+        for failing in [true, true, false, true, true] {
+            reporter.failing.store(failing, Ordering::SeqCst);
+            let _ = report_once(&sync).await;
+        }
+        assert_eq!(sync.circuit_state(), CircuitState::Closed);
+        assert_eq!(reporter.calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_cancelled_probe_frees_its_slot() {
+        let breaker = CircuitBreaker::new(
+            &StateSyncConfig::default()
+                .with_circuit_failure_threshold(1)
+                .with_circuit_cooldown(Duration::ZERO)
+                .with_circuit_half_open_probes(1),
+        );
+        breaker.admit(Instant::now()).unwrap().fail(Instant::now());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // A probe dropped without a result, as when its report future is cancelled
+        let probe = breaker.admit(Instant::now()).unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.admit(Instant::now()).is_none());
+        drop(probe);
+
+        breaker.admit(Instant::now()).unwrap().succeed();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_stale_probe_does_not_close_next_round() {
+        let breaker = CircuitBreaker::new(
+            &StateSyncConfig::default()
+                .with_circuit_failure_threshold(1)
+                .with_circuit_cooldown(Duration::ZERO)
+                .with_circuit_half_open_probes(2),
+        );
+        breaker.admit(Instant::now()).unwrap().fail(Instant::now());
+        let stale = breaker.admit(Instant::now()).unwrap();
+        breaker.admit(Instant::now()).unwrap().fail(Instant::now());
+
+        // The next round starts with both slots free and ignores the earlier probe
+        let first = breaker.admit(Instant::now()).unwrap();
+        stale.succeed();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        let second = breaker.admit(Instant::now()).unwrap();
+        first.succeed();
+        second.succeed();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_circuit_config_validation() {
+        let config = StateSyncConfig::default().with_retry_delay(Duration::from_millis(1));
+        assert!(config.validate().is_ok());
+        assert!(config
+            .clone()
+            .with_circuit_failure_threshold(0)
+            .validate()
+            .is_err());
+        assert!(config.with_circuit_half_open_probes(0).validate().is_err());
+    }
+
+    /// Machine over Idle -> Capturing -> Draining -> Stopped, plus Draining -> Capturing
+    fn lifecycle() -> StateMachine<&'static str> {
+        let mut machine = StateMachine::new("idle", 16).unwrap();
+        machine.add_transition("idle", "capturing");
+        machine.add_transition("capturing", "draining");
+        machine.add_transition("draining", "capturing");
+        machine.add_transition("draining", "stopped");
+        machine
+    }
+
+    #[test]
+    fn test_consistency_in_sync() {
+        let machine = lifecycle();
+        let report = ConsistencyChecker::new().check(&machine, &"idle");
+        assert_eq!(report.divergence(), Divergence::InSync);
+        assert!(report.reconciliation().is_none());
+        assert!(!report.requires_manual_intervention());
+    }
+
+    #[test]
+    fn test_consistency_local_ahead() {
+        let mut machine = lifecycle();
+        for state in ["capturing", "draining", "capturing", "draining"] {
+            machine.transition_to(state, None).unwrap();
+        }
+        // Capturing was entered twice; only transitions after the last visit are unreported
+        let report = ConsistencyChecker::new().check(&machine, &"capturing");
+        assert_eq!(report.divergence(), Divergence::LocalAhead);
+        assert_eq!(report.unreported().len(), 1);
+        let replay = report.reconciliation().unwrap();
+        assert_eq!((*replay.from(), *replay.to()), ("capturing", "draining"));
+
+        // Reachable from draining, but history shows it is stale
+        assert_eq!(
+            ConsistencyChecker::new()
+                .check(&machine, &"idle")
+                .unreported()
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_consistency_remote_ahead() {
+        let mut machine = lifecycle();
+        machine.transition_to("capturing", None).unwrap();
+        machine.transition_to("draining", None).unwrap();
+        let report = ConsistencyChecker::new().check(&machine, &"stopped");
+        assert_eq!(report.divergence(), Divergence::RemoteAhead);
+        assert!(report.unreported().is_empty());
+        let catch_up = report.reconciliation().unwrap();
+        assert_eq!((*catch_up.from(), *catch_up.to()), ("draining", "stopped"));
+        assert_eq!(catch_up.category(), Some(TransitionReason::Recovery));
+    }
+
+    #[test]
+    fn test_consistency_conflict() {
+        let mut machine = lifecycle();
+        machine.transition_to("capturing", None).unwrap();
+        let report = ConsistencyChecker::new().check(&machine, &"stopped");
+        assert_eq!(report.divergence(), Divergence::Conflict);
+        assert!(report.reconciliation().is_none());
+        assert!(report.requires_manual_intervention());
+
+        // Without history a stale state cannot be told apart from a conflicting one
+        machine.clear_history();
+        assert_eq!(
+            ConsistencyChecker::new()
+                .check(&machine, &"idle")
+                .divergence(),
+            Divergence::Conflict
+        );
+    }
+
+    struct NullReporter;
+
+    impl StateReporter<&'static str> for NullReporter {
+        fn report_state<'a>(
+            &'a self,
+            _event: &'a StateChangeEvent<&'static str>,
+        ) -> Pin<Box<dyn Future<Output = Result<(), CaptureError>> + Send + 'a>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conflict_blocks_update() {
+        let sync = StateSyncBuilder::new()
+            .with_engine_id("test-engine".to_string())
+            .with_state_machine(lifecycle())
+            .with_reporter(Box::new(NullReporter))
+            .with_config(StateSyncConfig::default().with_strategy(SyncStrategy::Immediate))
+            .with_consistency_checker(ConsistencyChecker::new())
+            .build()
+            .unwrap();
+
+        sync.update_state("capturing", HashMap::new())
+            .await
+            .unwrap();
+        let report = sync.check_consistency("capturing").unwrap();
+        assert_eq!(report.divergence(), Divergence::InSync);
+        // The pushed update becomes the known remote state
+        sync.update_state("draining", HashMap::new()).await.unwrap();
+
+        assert!(sync.check_consistency("stopped").is_ok());
+        sync.update_state("capturing", HashMap::new())
+            .await
+            .unwrap();
+        let report = sync.check_consistency("stopped").unwrap();
+        assert!(report.requires_manual_intervention());
+        let err = sync
+            .update_state("draining", HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::StateError)
+        ));
+        assert_eq!(sync.current_state().unwrap(), "capturing");
+    }
 }
@@ -3,12 +3,18 @@
 #![allow(unused_variables)]
 // capture-engine/src/capture/state_validator.rs
 use async_trait::async_trait;
-use std::collections::HashMap;
+use parking_lot::Mutex;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::fmt;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
 
 pub type ValidatorFn<S> = dyn Fn(&S, &S) -> Result<bool, CaptureError> + Send + Sync;
 
@@ -19,18 +25,74 @@ pub struct ValidationRule<S> {
     pub severity: ValidationSeverity,
     pub validator: Arc<ValidatorFn<S>>,
     pub metadata: HashMap<String, String>,
+    /// Names of rules that must run before this one
+    pub dependencies: Vec<String>,
+    /// Order among rules whose dependencies are satisfied; lower values run first
+    pub priority: u32,
 }
 
+/// Fluent builder for validation rules
+///
+/// Name and severity are required; the validator closure is wrapped for the rule, so custom
+/// checks are plain closures over the current and proposed states.
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// use capture_engine::capture_engine::capture::state_validator::{
+///     StateValidator, ValidationRuleBuilder, ValidationSeverity, ValidatorConfig,
+/// };
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// enum EngineState {
+///     Ready,
+///     Capturing,
+/// }
+///
+/// let buffered = Arc::new(AtomicUsize::new(3));
+/// let buffers = Arc::clone(&buffered);
+///
+/// let mut validator = StateValidator::new(ValidatorConfig::default());
+/// validator
+///     .register_rule(
+///         ValidationRuleBuilder::new()
+///             .name("buffers_drained")
+///             .description("Capture buffers must be empty before returning to Ready")
+///             .severity(ValidationSeverity::Critical)
+///             .priority(10)
+///             .validator(move |current: &EngineState, proposed: &EngineState| {
+///                 let stopping =
+///                     *current == EngineState::Capturing && *proposed == EngineState::Ready;
+///                 Ok(!stopping || buffers.load(Ordering::Acquire) == 0)
+///             }),
+///     )
+///     .unwrap();
+///
+/// let runtime = tokio::runtime::Runtime::new().unwrap();
+/// let results = runtime
+///     .block_on(validator.validate_transition(&EngineState::Capturing, &EngineState::Ready))
+///     .unwrap();
+/// assert!(!results[0].passed());
+///
+/// buffered.store(0, Ordering::Release);
+/// let results = runtime
+///     .block_on(validator.validate_transition(&EngineState::Capturing, &EngineState::Ready))
+///     .unwrap();
+/// assert!(results[0].passed());
+/// ```
 pub struct ValidationRuleBuilder<S> {
     name: Option<String>,
     description: Option<String>,
     severity: Option<ValidationSeverity>,
     validator: Option<Box<ValidatorFn<S>>>,
     metadata: HashMap<String, String>,
+    dependencies: Vec<String>,
+    priority: u32,
 }
 
 /// Severity levels for validation rules
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ValidationSeverity {
     Critical, // Must pass or state transition fails
     Warning,  // Generates warning but allows transition
@@ -48,6 +110,87 @@ pub struct ValidationResult {
     metadata: HashMap<String, String>,
 }
 
+/// How far validation runs once a rule fails
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ValidationMode {
+    /// Stop at the first failed `Critical` check
+    FailFast,
+    /// Run every rule and validator
+    #[default]
+    CollectAll,
+}
+
+/// Outcome of validating a transition, with failures grouped by severity
+///
+/// Only `Critical` failures block the transition; `Warning` and `Info` failures are kept as
+/// annotations.
+#[derive(Debug, Clone)]
+pub struct ValidationReport {
+    mode: ValidationMode,
+    results: Vec<ValidationResult>,
+    failures: HashMap<ValidationSeverity, Vec<ValidationResult>>,
+    halted: bool,
+}
+
+impl ValidationReport {
+    fn new(mode: ValidationMode) -> Self {
+        Self {
+            mode,
+            results: Vec::new(),
+            failures: HashMap::new(),
+            halted: false,
+        }
+    }
+
+    /// Records a check, returning whether evaluation should stop
+    fn record(&mut self, result: ValidationResult) -> bool {
+        let halt = self.mode == ValidationMode::FailFast
+            && !result.passed
+            && result.severity == ValidationSeverity::Critical;
+        if !result.passed {
+            self.failures
+                .entry(result.severity.clone())
+                .or_default()
+                .push(result.clone());
+        }
+        self.results.push(result);
+        self.halted = halt;
+        halt
+    }
+
+    /// Mode the report was produced in
+    pub fn mode(&self) -> ValidationMode {
+        self.mode
+    }
+
+    /// Every check that ran, in execution order
+    pub fn results(&self) -> &[ValidationResult] {
+        &self.results
+    }
+
+    /// Failed checks of the given severity, in execution order
+    pub fn failures(&self, severity: &ValidationSeverity) -> &[ValidationResult] {
+        self.failures
+            .get(severity)
+            .map_or(&[], |failures| failures.as_slice())
+    }
+
+    /// Failed checks grouped by severity
+    pub fn failures_by_severity(&self) -> &HashMap<ValidationSeverity, Vec<ValidationResult>> {
+        &self.failures
+    }
+
+    /// Whether evaluation stopped early at a critical failure
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Whether the transition may proceed, i.e. no critical check failed
+    pub fn allows_transition(&self) -> bool {
+        self.failures(&ValidationSeverity::Critical).is_empty()
+    }
+}
+
 /// Configuration for state validation
 #[derive(Clone)]
 pub struct ValidatorConfig {
@@ -62,8 +205,24 @@ pub struct ValidatorConfig {
 pub struct StateValidator<S> {
     config: ValidatorConfig,
     rules: HashMap<String, ValidationRule<S>>,
+    execution_order: Vec<String>,
     validation_history: Vec<ValidationResult>,
     custom_validators: Vec<Box<dyn CustomValidator<S>>>,
+    cache: Mutex<Option<ValidationCache<S>>>,
+    cache_hits: AtomicU64,
+    cache_generation: AtomicU64,
+}
+
+/// Memoized validation reports keyed on the transition pair and mode
+struct ValidationCache<S> {
+    ttl: Duration,
+    max_entries: usize,
+    entries: HashMap<(S, S, ValidationMode), CachedReport>,
+}
+
+struct CachedReport {
+    report: ValidationReport,
+    stored_at: Instant,
 }
 
 /// Trait for implementing custom validators
@@ -74,41 +233,196 @@ pub trait CustomValidator<S>: Send + Sync {
     fn get_severity(&self) -> ValidationSeverity;
 }
 
-/// Trait for state invariant checking
-pub trait InvariantChecker<S> {
-    fn check_invariants(&self, state: &S) -> Result<Vec<ValidationResult>, CaptureError>;
-    fn get_invariant_rules(&self) -> Vec<ValidationRule<S>>;
+pub type InvariantFn<S> = dyn Fn(&S) -> bool + Send + Sync;
+
+/// Named predicates that must hold for every state a machine enters
+///
+/// Unlike rules, which judge a transition, invariants judge only the state reached, so they
+/// apply whichever edge led there.
+pub struct InvariantChecker<S> {
+    invariants: Vec<(String, Arc<InvariantFn<S>>)>,
 }
 
 impl Default for ValidatorConfig {
     fn default() -> Self {
-        unimplemented!()
+        Self {
+            enabled: true,
+            fail_fast: false,
+            validation_timeout: Duration::from_secs(5),
+            max_retries: 0,
+            retry_delay: Duration::from_millis(100),
+        }
+    }
+}
+
+impl ValidationResult {
+    /// Name of the rule or validator that produced the result
+    pub fn rule_name(&self) -> &str {
+        &self.rule_name
+    }
+
+    /// Whether the check passed
+    pub fn passed(&self) -> bool {
+        self.passed
+    }
+
+    /// Severity of the rule
+    pub fn severity(&self) -> &ValidationSeverity {
+        &self.severity
+    }
+
+    /// Failure detail, if any
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
     }
 }
 
 impl<S: Clone + Send + Sync + 'static> StateValidator<S> {
     /// Creates a new StateValidator with the given configuration
     pub fn new(config: ValidatorConfig) -> Self {
-        unimplemented!()
+        Self {
+            config,
+            rules: HashMap::new(),
+            execution_order: Vec::new(),
+            validation_history: Vec::new(),
+            custom_validators: Vec::new(),
+            cache: Mutex::new(None),
+            cache_hits: AtomicU64::new(0),
+            cache_generation: AtomicU64::new(0),
+        }
     }
 
-    /// Adds a new validation rule
-    pub fn add_rule(&mut self, rule: ValidationRule<S>) {
-        unimplemented!()
+    /// Memoizes reports from `validate_cached` for up to `ttl`, holding at most `max_entries`
+    ///
+    /// Replaces any existing cache, dropping its entries.
+    pub fn enable_cache(&mut self, ttl: Duration, max_entries: usize) {
+        self.invalidate_cache();
+        *self.cache.get_mut() = Some(ValidationCache {
+            ttl,
+            max_entries: max_entries.max(1),
+            entries: HashMap::new(),
+        });
+    }
+
+    /// Turns the cache off and drops its entries
+    pub fn disable_cache(&mut self) {
+        self.invalidate_cache();
+        *self.cache.get_mut() = None;
+    }
+
+    /// Drops every cached report
+    ///
+    /// Rule changes made through this validator invalidate the cache themselves; call this when
+    /// the outcome of existing rules may have changed, e.g. because they read external state.
+    /// Evaluations already in flight when the cache is invalidated do not store their reports.
+    pub fn invalidate_cache(&self) {
+        self.cache_generation.fetch_add(1, Ordering::AcqRel);
+        if let Some(cache) = self.cache.lock().as_mut() {
+            cache.entries.clear();
+        }
+    }
+
+    /// Number of validations answered from the cache
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    /// Adds a new validation rule, replacing any rule with the same name
+    ///
+    /// Dependencies may name rules that are registered later; they are ordered once present.
+    /// A rule whose dependencies would form a cycle is rejected with a
+    /// `Configuration(ValidationFailed)` error naming the cycle, and the validator is left
+    /// unchanged.
+    pub fn add_rule(&mut self, rule: ValidationRule<S>) -> Result<(), CaptureError> {
+        let name = rule.name.clone();
+        let previous = self.rules.insert(name.clone(), rule);
+        match self.order_rules() {
+            Ok(order) => {
+                self.execution_order = order;
+                self.invalidate_cache();
+                Ok(())
+            }
+            Err(cycle) => {
+                match previous {
+                    Some(previous) => self.rules.insert(name, previous),
+                    None => self.rules.remove(&name),
+                };
+                Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::ValidationFailed),
+                    &format!("Validation rule dependency cycle: {}", cycle.join(" -> ")),
+                ))
+            }
+        }
+    }
+
+    /// Names of the registered rules in the order they run
+    pub fn execution_order(&self) -> &[String] {
+        &self.execution_order
+    }
+
+    /// Builds a rule and adds it as `add_rule` does
+    pub fn register_rule(&mut self, builder: ValidationRuleBuilder<S>) -> Result<(), CaptureError> {
+        self.add_rule(builder.build()?)
     }
 
     /// Adds a custom validator
     pub fn add_custom_validator(&mut self, validator: Box<dyn CustomValidator<S>>) {
-        unimplemented!()
+        self.custom_validators.push(validator);
+        self.invalidate_cache();
     }
 
-    /// Validates a state transition
+    /// Validates a state transition and records the results in the history
+    ///
+    /// Runs every check unless the configuration enables fail-fast.
     pub async fn validate_transition(
         &mut self,
         current_state: &S,
         proposed_state: &S,
     ) -> Result<Vec<ValidationResult>, CaptureError> {
-        unimplemented!()
+        let mode = if self.config.fail_fast {
+            ValidationMode::FailFast
+        } else {
+            ValidationMode::CollectAll
+        };
+        let report = self
+            .validate_with(current_state, proposed_state, mode)
+            .await?;
+        self.validation_history
+            .extend(report.results().iter().cloned());
+        Ok(report.results)
+    }
+
+    /// Validates a state transition in the given mode
+    ///
+    /// Rules run after their dependencies, then custom validators run in registration order.
+    /// In `FailFast` mode evaluation halts at the first failed `Critical` check.
+    pub async fn validate_with(
+        &self,
+        current_state: &S,
+        proposed_state: &S,
+        mode: ValidationMode,
+    ) -> Result<ValidationReport, CaptureError> {
+        let mut report = ValidationReport::new(mode);
+        if !self.config.enabled {
+            return Ok(report);
+        }
+
+        for name in &self.execution_order {
+            let rule = &self.rules[name];
+            let result = self
+                .execute_rule(rule, current_state, proposed_state)
+                .await?;
+            if report.record(result) {
+                return Ok(report);
+            }
+        }
+        for validator in &self.custom_validators {
+            let result = validator.validate(current_state, proposed_state).await?;
+            if report.record(result) {
+                break;
+            }
+        }
+        Ok(report)
     }
 
     /// Executes a single validation rule
@@ -118,17 +432,198 @@ impl<S: Clone + Send + Sync + 'static> StateValidator<S> {
         current_state: &S,
         proposed_state: &S,
     ) -> Result<ValidationResult, CaptureError> {
-        unimplemented!()
+        let passed = (rule.validator)(current_state, proposed_state)?;
+        Ok(ValidationResult {
+            rule_name: rule.name.clone(),
+            passed,
+            severity: rule.severity.clone(),
+            message: (!passed).then(|| rule.description.clone()),
+            timestamp: SystemTime::now(),
+            metadata: rule.metadata.clone(),
+        })
+    }
+
+    /// Orders rules so each runs after its registered dependencies
+    ///
+    /// Among rules that are ready to run, lower priority values go first, then names. On a
+    /// cycle, returns the rule names around it with the first name repeated at the end.
+    fn order_rules(&self) -> Result<Vec<String>, Vec<String>> {
+        let mut pending: HashMap<&str, usize> = HashMap::new();
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (name, rule) in &self.rules {
+            let registered = self.registered_dependencies(rule);
+            pending.insert(name, registered.len());
+            for dependency in registered {
+                dependents.entry(dependency).or_default().push(name);
+            }
+        }
+
+        let mut ready: BinaryHeap<Reverse<(u32, &str)>> = pending
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(name, _)| Reverse((self.rules[*name].priority, *name)))
+            .collect();
+        let mut order = Vec::with_capacity(self.rules.len());
+        while let Some(Reverse((_, name))) = ready.pop() {
+            order.push(name.to_string());
+            for dependent in dependents.get(name).into_iter().flatten() {
+                let count = pending.get_mut(dependent).expect("dependent is registered");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(Reverse((self.rules[*dependent].priority, dependent)));
+                }
+            }
+        }
+
+        if order.len() == self.rules.len() {
+            Ok(order)
+        } else {
+            Err(self.find_cycle(&pending))
+        }
+    }
+
+    /// Dependencies of `rule` that are registered, without duplicates
+    fn registered_dependencies<'a>(&'a self, rule: &'a ValidationRule<S>) -> Vec<&'a str> {
+        let mut registered: Vec<&str> = Vec::new();
+        for dependency in &rule.dependencies {
+            if self.rules.contains_key(dependency) && !registered.contains(&dependency.as_str()) {
+                registered.push(dependency);
+            }
+        }
+        registered
+    }
+
+    /// Follows unresolved dependencies from an unordered rule until one repeats
+    fn find_cycle(&self, pending: &HashMap<&str, usize>) -> Vec<String> {
+        let mut start = pending
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(name, _)| *name)
+            .min()
+            .expect("an unordered rule exists when ordering fails");
+        let mut path: Vec<&str> = Vec::new();
+        loop {
+            if let Some(position) = path.iter().position(|name| *name == start) {
+                let mut cycle: Vec<String> = path[position..]
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect();
+                cycle.push(start.to_string());
+                return cycle;
+            }
+            path.push(start);
+            // Every unordered rule waits on at least one other unordered rule
+            start = self
+                .registered_dependencies(&self.rules[start])
+                .into_iter()
+                .filter(|dependency| pending[dependency] > 0)
+                .min()
+                .expect("an unordered rule has an unordered dependency");
+        }
     }
 
     /// Gets validation history
     pub fn get_validation_history(&self) -> &[ValidationResult] {
-        unimplemented!()
+        &self.validation_history
     }
 
     /// Clears validation history
     pub fn clear_history(&mut self) {
-        unimplemented!()
+        self.validation_history.clear();
+    }
+}
+
+impl<S: Clone + Hash + Eq + Send + Sync + 'static> StateValidator<S> {
+    /// Validates a state transition, reusing a cached report for the same pair and mode
+    ///
+    /// Without an enabled cache this is `validate_with`. Reports are cached whatever their
+    /// failures, including warnings, and expire after the cache TTL.
+    pub async fn validate_cached(
+        &self,
+        current_state: &S,
+        proposed_state: &S,
+        mode: ValidationMode,
+    ) -> Result<ValidationReport, CaptureError> {
+        self.validate_cached_at(current_state, proposed_state, mode, Instant::now())
+            .await
+    }
+
+    async fn validate_cached_at(
+        &self,
+        current_state: &S,
+        proposed_state: &S,
+        mode: ValidationMode,
+        now: Instant,
+    ) -> Result<ValidationReport, CaptureError> {
+        let key = (current_state.clone(), proposed_state.clone(), mode);
+        let cached = self
+            .cache
+            .lock()
+            .as_mut()
+            .map(|cache| cache.lookup(&key, now));
+        match cached {
+            None => {
+                return self
+                    .validate_with(current_state, proposed_state, mode)
+                    .await
+            }
+            Some(Some(report)) => {
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(report);
+            }
+            Some(None) => {}
+        }
+
+        let generation = self.cache_generation.load(Ordering::Acquire);
+        let report = self
+            .validate_with(current_state, proposed_state, mode)
+            .await?;
+
+        let mut cache = self.cache.lock();
+        if let Some(cache) = cache.as_mut() {
+            if self.cache_generation.load(Ordering::Acquire) == generation {
+                cache.insert(key, report.clone(), now);
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl<S: Clone + Hash + Eq> ValidationCache<S> {
+    /// Returns the report stored for `key` if it has not expired, dropping it if it has
+    fn lookup(&mut self, key: &(S, S, ValidationMode), now: Instant) -> Option<ValidationReport> {
+        let entry = self.entries.get(key)?;
+        if now.saturating_duration_since(entry.stored_at) < self.ttl {
+            return Some(entry.report.clone());
+        }
+        self.entries.remove(key);
+        None
+    }
+
+    /// Stores a report, making room by dropping expired and then oldest entries
+    fn insert(&mut self, key: (S, S, ValidationMode), report: ValidationReport, now: Instant) {
+        if self.entries.len() >= self.max_entries {
+            let ttl = self.ttl;
+            self.entries
+                .retain(|_, entry| now.saturating_duration_since(entry.stored_at) < ttl);
+        }
+        if self.entries.len() >= self.max_entries {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            key,
+            CachedReport {
+                report,
+                stored_at: now,
+            },
+        );
     }
 }
 
@@ -142,39 +637,497 @@ impl<S> fmt::Debug for ValidationRule<S> {
 
 impl<S> Default for ValidationRuleBuilder<S> {
     fn default() -> Self {
-        unimplemented!()
+        Self {
+            name: None,
+            description: None,
+            severity: None,
+            validator: None,
+            metadata: HashMap::new(),
+            dependencies: Vec::new(),
+            priority: 0,
+        }
     }
 }
 
 impl<S> ValidationRuleBuilder<S> {
     pub fn new() -> Self {
-        unimplemented!()
+        Self::default()
     }
 
+    /// Sets the rule name, which identifies it in results and dependencies; required
     pub fn name(mut self, name: &str) -> Self {
-        unimplemented!()
+        self.name = Some(name.to_string());
+        self
     }
 
+    /// Sets the message reported when the rule fails
     pub fn description(mut self, desc: &str) -> Self {
-        unimplemented!()
+        self.description = Some(desc.to_string());
+        self
     }
 
+    /// Sets how a failure of the rule is treated; required
     pub fn severity(mut self, severity: ValidationSeverity) -> Self {
-        unimplemented!()
+        self.severity = Some(severity);
+        self
     }
 
+    /// Sets the check, called with the current and proposed states; required
+    ///
+    /// `Ok(false)` fails the rule; an error aborts validation.
     pub fn validator<F>(mut self, validator: F) -> Self
     where
         F: Fn(&S, &S) -> Result<bool, CaptureError> + Send + Sync + 'static,
     {
-        unimplemented!()
+        self.validator = Some(Box::new(validator));
+        self
     }
 
+    /// Attaches metadata copied into each result of the rule
     pub fn metadata(mut self, key: &str, value: &str) -> Self {
-        unimplemented!()
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
     }
 
+    /// Requires the named rule to run before this one
+    pub fn depends_on(mut self, rule_name: &str) -> Self {
+        self.dependencies.push(rule_name.to_string());
+        self
+    }
+
+    /// Sets the order among rules whose dependencies are satisfied; lower values run first
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Builds the rule
+    ///
+    /// A missing or blank name, or a missing severity or validator, is a
+    /// `Configuration(MissingRequired)` error.
     pub fn build(self) -> Result<ValidationRule<S>, CaptureError> {
-        unimplemented!()
+        let missing = |field: &str| {
+            *CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::MissingRequired),
+                &format!("Validation rule {} is required", field),
+            )
+        };
+        let name = self
+            .name
+            .filter(|name| !name.trim().is_empty())
+            .ok_or_else(|| missing("name"))?;
+        let severity = self.severity.ok_or_else(|| missing("severity"))?;
+        let validator = self.validator.ok_or_else(|| missing("validator"))?;
+        Ok(ValidationRule {
+            description: self.description.unwrap_or_default(),
+            name,
+            severity,
+            validator: Arc::from(validator),
+            metadata: self.metadata,
+            dependencies: self.dependencies,
+            priority: self.priority,
+        })
+    }
+}
+
+impl<S> InvariantChecker<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an invariant; invariants are checked in the order added
+    pub fn invariant<F>(mut self, name: &str, predicate: F) -> Self
+    where
+        F: Fn(&S) -> bool + Send + Sync + 'static,
+    {
+        self.invariants
+            .push((name.to_string(), Arc::new(predicate)));
+        self
+    }
+
+    /// Number of invariants
+    pub fn len(&self) -> usize {
+        self.invariants.len()
+    }
+
+    /// Whether there are no invariants
+    pub fn is_empty(&self) -> bool {
+        self.invariants.is_empty()
+    }
+
+    /// Name of the first invariant `state` breaks, if any
+    pub fn first_violation(&self, state: &S) -> Option<&str> {
+        self.invariants
+            .iter()
+            .find(|(_, predicate)| !predicate(state))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Checks every invariant against `state`, as critical results in the order added
+    pub fn check_invariants(&self, state: &S) -> Vec<ValidationResult> {
+        self.invariants
+            .iter()
+            .map(|(name, predicate)| {
+                let passed = predicate(state);
+                ValidationResult {
+                    rule_name: name.clone(),
+                    passed,
+                    severity: ValidationSeverity::Critical,
+                    message: (!passed).then(|| format!("Invariant {} violated", name)),
+                    timestamp: SystemTime::now(),
+                    metadata: HashMap::new(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl<S> Default for InvariantChecker<S> {
+    fn default() -> Self {
+        Self {
+            invariants: Vec::new(),
+        }
+    }
+}
+
+impl<S> Clone for InvariantChecker<S> {
+    fn clone(&self) -> Self {
+        Self {
+            invariants: self.invariants.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for InvariantChecker<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.invariants.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, priority: u32, dependencies: &[&str]) -> ValidationRule<u32> {
+        dependencies
+            .iter()
+            .fold(
+                ValidationRuleBuilder::new()
+                    .name(name)
+                    .severity(ValidationSeverity::Critical)
+                    .priority(priority)
+                    .validator(|_: &u32, proposed: &u32| Ok(*proposed > 0)),
+                |builder, dependency| builder.depends_on(dependency),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn position(validator: &StateValidator<u32>, name: &str) -> usize {
+        validator
+            .execution_order()
+            .iter()
+            .position(|rule| rule == name)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_diamond_dependencies_run_in_order() {
+        let mut validator = StateValidator::new(ValidatorConfig::default());
+        // Registered before its dependencies to show ordering is not registration order
+        validator
+            .add_rule(rule("commit", 0, &["left", "right"]))
+            .unwrap();
+        validator.add_rule(rule("left", 5, &["base"])).unwrap();
+        validator.add_rule(rule("right", 1, &["base"])).unwrap();
+        validator.add_rule(rule("base", 9, &[])).unwrap();
+
+        assert_eq!(
+            validator.execution_order(),
+            &["base", "right", "left", "commit"]
+        );
+        let results = validator.validate_transition(&0, &1).await.unwrap();
+        let ran: Vec<&str> = results.iter().map(|r| r.rule_name()).collect();
+        assert_eq!(ran, vec!["base", "right", "left", "commit"]);
+        assert!(results.iter().all(|r| r.passed()));
+        assert_eq!(validator.get_validation_history().len(), 4);
+    }
+
+    #[test]
+    fn test_builder_requires_name_and_severity() {
+        let complete = || {
+            ValidationRuleBuilder::new()
+                .name("positive")
+                .severity(ValidationSeverity::Warning)
+                .validator(|_: &u32, proposed: &u32| Ok(*proposed > 0))
+        };
+        let rule = complete().build().unwrap();
+        assert_eq!(rule.severity, ValidationSeverity::Warning);
+        assert!((rule.validator)(&0, &1).unwrap());
+
+        let missing = |builder: ValidationRuleBuilder<u32>| {
+            matches!(
+                builder.build().unwrap_err().kind(),
+                CaptureErrorKind::Configuration(ConfigErrorKind::MissingRequired)
+            )
+        };
+        assert!(missing(complete().name("  ")));
+        assert!(missing(
+            ValidationRuleBuilder::new()
+                .name("no_severity")
+                .validator(|_: &u32, _: &u32| Ok(true))
+        ));
+        assert!(missing(
+            ValidationRuleBuilder::new()
+                .name("no_validator")
+                .severity(ValidationSeverity::Info)
+        ));
+
+        let mut validator = StateValidator::new(ValidatorConfig::default());
+        validator.register_rule(complete()).unwrap();
+        assert!(validator.register_rule(complete().name("")).is_err());
+        assert_eq!(validator.execution_order(), ["positive"]);
+    }
+
+    #[test]
+    fn test_independent_rules_ordered_by_priority() {
+        let mut validator = StateValidator::new(ValidatorConfig::default());
+        validator.add_rule(rule("late", 10, &[])).unwrap();
+        validator.add_rule(rule("early", 1, &[])).unwrap();
+        validator
+            .add_rule(rule("after_late", 0, &["late"]))
+            .unwrap();
+
+        assert!(position(&validator, "early") < position(&validator, "late"));
+        assert!(position(&validator, "late") < position(&validator, "after_late"));
+    }
+
+    #[test]
+    fn test_cycle_is_rejected_and_named() {
+        let mut validator = StateValidator::new(ValidatorConfig::default());
+        validator.add_rule(rule("a", 0, &["c"])).unwrap();
+        validator.add_rule(rule("b", 0, &["a"])).unwrap();
+
+        let err = validator.add_rule(rule("c", 0, &["b"])).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::ValidationFailed)
+        ));
+        let message = err.to_string();
+        assert!(message.contains("a -> c -> b -> a"), "{}", message);
+
+        // The rejected rule is not registered
+        assert_eq!(validator.execution_order(), &["a", "b"]);
+    }
+
+    #[test]
+    fn test_self_dependency_is_a_cycle() {
+        let mut validator = StateValidator::new(ValidatorConfig::default());
+        assert!(validator.add_rule(rule("loop", 0, &["loop"])).is_err());
+        assert!(validator.execution_order().is_empty());
+    }
+
+    fn check(
+        name: &str,
+        priority: u32,
+        severity: ValidationSeverity,
+        passes: bool,
+    ) -> ValidationRule<u32> {
+        ValidationRuleBuilder::new()
+            .name(name)
+            .priority(priority)
+            .severity(severity)
+            .validator(move |_: &u32, _: &u32| Ok(passes))
+            .build()
+            .unwrap()
+    }
+
+    fn mixed_validator() -> StateValidator<u32> {
+        let mut validator = StateValidator::new(ValidatorConfig::default());
+        validator
+            .add_rule(check("warn", 0, ValidationSeverity::Warning, false))
+            .unwrap();
+        validator
+            .add_rule(check("critical", 1, ValidationSeverity::Critical, false))
+            .unwrap();
+        validator
+            .add_rule(check("info", 2, ValidationSeverity::Info, false))
+            .unwrap();
+        validator
+            .add_rule(check("ok", 3, ValidationSeverity::Critical, true))
+            .unwrap();
+        validator
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_halts_at_first_critical_failure() {
+        let validator = mixed_validator();
+        let report = validator
+            .validate_with(&0, &1, ValidationMode::FailFast)
+            .await
+            .unwrap();
+
+        let ran: Vec<&str> = report.results().iter().map(|r| r.rule_name()).collect();
+        assert_eq!(ran, vec!["warn", "critical"]);
+        assert!(report.halted());
+        assert!(!report.allows_transition());
+        assert_eq!(report.failures(&ValidationSeverity::Warning).len(), 1);
+        assert!(report.failures(&ValidationSeverity::Info).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_all_groups_failures_by_severity() {
+        let validator = mixed_validator();
+        let report = validator
+            .validate_with(&0, &1, ValidationMode::default())
+            .await
+            .unwrap();
+
+        assert_eq!(report.results().len(), 4);
+        assert!(!report.halted());
+        for severity in [
+            ValidationSeverity::Critical,
+            ValidationSeverity::Warning,
+            ValidationSeverity::Info,
+        ] {
+            assert_eq!(report.failures(&severity).len(), 1);
+        }
+        assert_eq!(report.failures_by_severity().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_warning_failures_do_not_block_transition() {
+        let mut validator = StateValidator::new(ValidatorConfig::default());
+        validator
+            .add_rule(check("warn", 0, ValidationSeverity::Warning, false))
+            .unwrap();
+        validator
+            .add_rule(check("ok", 1, ValidationSeverity::Critical, true))
+            .unwrap();
+
+        let report = validator
+            .validate_with(&0, &1, ValidationMode::FailFast)
+            .await
+            .unwrap();
+        assert!(report.allows_transition());
+        assert_eq!(report.results().len(), 2);
+        assert_eq!(
+            report.failures(&ValidationSeverity::Warning)[0].rule_name(),
+            "warn"
+        );
+    }
+
+    fn counting_validator(calls: &Arc<AtomicU64>) -> StateValidator<u32> {
+        let calls = Arc::clone(calls);
+        let mut validator = StateValidator::new(ValidatorConfig::default());
+        validator
+            .add_rule(
+                ValidationRuleBuilder::new()
+                    .name("counted")
+                    .severity(ValidationSeverity::Warning)
+                    .validator(move |_: &u32, _: &u32| {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        Ok(false)
+                    })
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        validator
+    }
+
+    #[tokio::test]
+    async fn test_cache_reuses_reports_until_ttl_expires() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let mut validator = counting_validator(&calls);
+        validator.enable_cache(Duration::from_secs(5), 16);
+        let start = Instant::now();
+        let mode = ValidationMode::CollectAll;
+
+        let first = validator
+            .validate_cached_at(&0, &1, mode, start)
+            .await
+            .unwrap();
+        let second = validator
+            .validate_cached_at(&0, &1, mode, start + Duration::from_secs(4))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(validator.cache_hits(), 1);
+        // Warning failures are cached like any other report
+        assert_eq!(second.failures(&ValidationSeverity::Warning).len(), 1);
+        assert_eq!(first.results().len(), second.results().len());
+
+        // A different pair or mode is a separate entry
+        validator
+            .validate_cached_at(&1, &0, mode, start)
+            .await
+            .unwrap();
+        validator
+            .validate_cached_at(&0, &1, ValidationMode::FailFast, start)
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        validator
+            .validate_cached_at(&0, &1, mode, start + Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(validator.cache_hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_evicts_oldest_entry_when_full() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let mut validator = counting_validator(&calls);
+        validator.enable_cache(Duration::from_secs(60), 2);
+        let start = Instant::now();
+        let mode = ValidationMode::CollectAll;
+
+        for (offset, to) in [1, 2, 3].into_iter().enumerate() {
+            let now = start + Duration::from_secs(offset as u64);
+            validator
+                .validate_cached_at(&0, &to, mode, now)
+                .await
+                .unwrap();
+        }
+        let later = start + Duration::from_secs(10);
+        validator
+            .validate_cached_at(&0, &3, mode, later)
+            .await
+            .unwrap();
+        assert_eq!(validator.cache_hits(), 1);
+        validator
+            .validate_cached_at(&0, &1, mode, later)
+            .await
+            .unwrap();
+        assert_eq!(validator.cache_hits(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rule_changes_invalidate_cache() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let mut validator = counting_validator(&calls);
+        validator.enable_cache(Duration::from_secs(60), 16);
+        let mode = ValidationMode::CollectAll;
+
+        let report = validator.validate_cached(&0, &1, mode).await.unwrap();
+        assert!(report.allows_transition());
+
+        validator
+            .add_rule(check("blocker", 0, ValidationSeverity::Critical, false))
+            .unwrap();
+        let report = validator.validate_cached(&0, &1, mode).await.unwrap();
+        assert!(!report.allows_transition());
+        assert_eq!(report.results().len(), 2);
+
+        validator.invalidate_cache();
+        validator.validate_cached(&0, &1, mode).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(validator.cache_hits(), 0);
     }
 }
@@ -1,4 +1,5 @@
 // capture/trait.rs
+use crate::capture_engine::capture::packet_layer::PacketLayer;
 use crate::traits::{
     BackpressureControl, Cleanup, Error, HealthCheck, Lifecycle, Packet, PauseResume,
     PressureAction, PressureAware, PressureStatus, RateLimiter, ResourceManager, StartStop,
@@ -8,6 +9,7 @@ use crate::traits::{
 /// Although scaling is handled elsewhere, `CaptureManager` still needs to handle local pressure
 /// and adapt by throttling or applying backpressure.
 use async_trait::async_trait;
+use std::sync::Arc;
 
 /// Trait for managing the capture process.
 #[async_trait]
@@ -31,6 +33,12 @@ pub trait CaptureManager:
         stage: PipelineStage,
         action: PressureAction,
     ) -> Result<(), Error>;
+    /// Pauses a single stage while upstream stages keep running into its backlog.
+    async fn pause_stage(&mut self, stage: PipelineStage) -> Result<(), Error>;
+    /// Resumes a paused stage and drains its backlog.
+    async fn resume_stage(&mut self, stage: PipelineStage) -> Result<(), Error>;
+    /// Appends a packet layer run between ingestion and output, after those already registered.
+    fn register_layer(&mut self, layer: Arc<dyn PacketLayer>) -> Result<(), Error>;
 }
 
 // A fixed structure for pipeline stages:
@@ -10,7 +10,9 @@ use std::time::{Duration, SystemTime};
 
 use super::capture_config::InterfaceConfiguration;
 use crate::capture_engine::capture::buffer_manager::{BufferMemory, BufferMemoryType};
-use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, RuntimeErrorKind,
+};
 use crate::capture_engine::capture::capture_session::SessionAction;
 use crate::capture_engine::capture::state_machine::{StateMachine, StateTransition};
 use crate::capture_engine::capture::state_recovery::RecoveryPoint;
@@ -256,6 +258,183 @@ pub struct TransactionContext {
     pub metadata: HashMap<String, String>,
     pub config: TransactionConfig,
     pub resources: Vec<TransactionResource>,
+    pub savepoints: Vec<Savepoint>,
+    pub rollback_only: bool,
+}
+
+/// Named position in a transaction's operation log
+///
+/// # Fields
+/// * `name` - Name given when the savepoint was set
+/// * `operation_count` - Operations recorded before the savepoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Savepoint {
+    pub name: String,
+    pub operation_count: usize,
+}
+
+impl TransactionContext {
+    /// Records an operation performed by the transaction
+    ///
+    /// # Arguments
+    /// * `operation` - The operation to record
+    pub fn record_operation(&mut self, operation: TransactionOperation) {
+        self.operations.push(operation);
+    }
+
+    /// Sets a savepoint after the operations recorded so far
+    ///
+    /// Savepoints nest; reusing a name shadows the earlier savepoint until it is released.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the savepoint
+    pub fn savepoint(&mut self, name: &str) {
+        self.savepoints.push(Savepoint {
+            name: name.to_string(),
+            operation_count: self.operations.len(),
+        });
+    }
+
+    /// Undoes the operations recorded after a savepoint
+    ///
+    /// The savepoint itself remains set, so it can be rolled back to again; savepoints set after
+    /// it are discarded.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the savepoint
+    ///
+    /// # Returns
+    /// The undone operations, most recent first, or `Runtime(EntityNotFound)` if no savepoint
+    /// has that name
+    pub fn rollback_to(&mut self, name: &str) -> Result<Vec<TransactionOperation>, CaptureError> {
+        let index = self.find_savepoint(name)?;
+        self.savepoints.truncate(index + 1);
+        let mut undone = self
+            .operations
+            .split_off(self.savepoints[index].operation_count);
+        undone.reverse();
+        Ok(undone)
+    }
+
+    /// Releases a savepoint, keeping the operations recorded after it
+    ///
+    /// Savepoints set after it are released too.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the savepoint
+    ///
+    /// # Returns
+    /// `Runtime(EntityNotFound)` if no savepoint has that name
+    pub fn release_savepoint(&mut self, name: &str) -> Result<(), CaptureError> {
+        let index = self.find_savepoint(name)?;
+        self.savepoints.truncate(index);
+        Ok(())
+    }
+
+    /// Gets how long the transaction may stay open, from its config
+    pub fn timeout(&self) -> Duration {
+        self.config.timeout
+    }
+
+    /// Gets how long the transaction has been open
+    ///
+    /// # Arguments
+    /// * `now` - Current time; a time before the start counts as zero
+    pub fn elapsed(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.start_time).unwrap_or_default()
+    }
+
+    /// Checks whether the transaction has been open longer than its timeout
+    ///
+    /// # Arguments
+    /// * `now` - Current time
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.elapsed(now) > self.timeout()
+    }
+
+    /// Times out an expired transaction
+    ///
+    /// An expired transaction that has not finished moves to `TimedOut`, unless its state machine
+    /// forbids it, and is marked rollback-only so it can no longer commit. This never blocks, so
+    /// the orchestrator can poll it.
+    ///
+    /// # Arguments
+    /// * `now` - Current time
+    ///
+    /// # Returns
+    /// True if the transaction timed out on this call
+    pub fn check_timeout(&mut self, now: SystemTime) -> bool {
+        let finished = matches!(
+            self.state.current_state(),
+            TransactionState::Committed
+                | TransactionState::RolledBack
+                | TransactionState::Failed
+                | TransactionState::TimedOut
+        );
+        if finished || !self.is_expired(now) {
+            return false;
+        }
+
+        self.rollback_only = true;
+        if self.state.can_transition_to(&TransactionState::TimedOut) {
+            let reason = format!(
+                "Open for {:?}, timeout {:?}",
+                self.elapsed(now),
+                self.timeout()
+            );
+            // A guard rejection is counted in the state machine metrics; the transaction is
+            // rollback-only either way
+            let _ = self
+                .state
+                .transition_to(TransactionState::TimedOut, Some(reason));
+        }
+        true
+    }
+
+    /// Commits the transaction, discarding all savepoints
+    ///
+    /// # Returns
+    /// `Runtime(StateError)` if the transaction is rollback-only, or an error if the state
+    /// machine does not allow the transition to `Committed`
+    pub fn commit(&mut self) -> Result<(), CaptureError> {
+        if self.rollback_only {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                &format!("Transaction {} is marked for rollback", self.id),
+            ));
+        }
+        self.state
+            .transition_to(TransactionState::Committed, None)?;
+        self.savepoints.clear();
+        Ok(())
+    }
+
+    /// Rolls back the whole transaction, discarding all savepoints
+    ///
+    /// # Returns
+    /// The undone operations, most recent first, or an error if the state machine does not
+    /// allow the transition to `RolledBack`
+    pub fn rollback(&mut self) -> Result<Vec<TransactionOperation>, CaptureError> {
+        self.state
+            .transition_to(TransactionState::RolledBack, None)?;
+        self.savepoints.clear();
+        let mut undone = std::mem::take(&mut self.operations);
+        undone.reverse();
+        Ok(undone)
+    }
+
+    /// Finds the most recent savepoint with a name
+    fn find_savepoint(&self, name: &str) -> Result<usize, CaptureError> {
+        self.savepoints
+            .iter()
+            .rposition(|savepoint| savepoint.name == name)
+            .ok_or_else(|| {
+                *CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound),
+                    &format!("Savepoint '{}' not found in transaction {}", name, self.id),
+                )
+            })
+    }
 }
 
 #[async_trait::async_trait]
@@ -339,3 +518,160 @@ impl TransactionCoordinator for DefaultTransactionCoordinator {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> TransactionContext {
+        let mut state = StateMachine::new(TransactionState::Initial, 10).unwrap();
+        state.add_transition(TransactionState::Initial, TransactionState::Committed);
+        state.add_transition(TransactionState::Initial, TransactionState::RolledBack);
+        state.add_transition(TransactionState::Initial, TransactionState::TimedOut);
+        state.add_transition(TransactionState::TimedOut, TransactionState::RolledBack);
+        TransactionContext {
+            id: "tx-1".to_string(),
+            parent_id: None,
+            start_time: SystemTime::now(),
+            state,
+            operations: Vec::new(),
+            metadata: HashMap::new(),
+            config: TransactionConfigBuilder::new().build().unwrap(),
+            resources: Vec::new(),
+            savepoints: Vec::new(),
+            rollback_only: false,
+        }
+    }
+
+    fn sync(entity_id: &str) -> TransactionOperation {
+        TransactionOperation::StateSync {
+            entity_id: entity_id.to_string(),
+            new_state: "active".to_string(),
+        }
+    }
+
+    fn entity_ids(operations: &[TransactionOperation]) -> Vec<&str> {
+        operations
+            .iter()
+            .map(|operation| match operation {
+                TransactionOperation::StateSync { entity_id, .. } => entity_id.as_str(),
+                other => panic!("unexpected operation {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rollback_to_undoes_only_later_operations() {
+        let mut tx = context();
+        tx.record_operation(sync("a"));
+        tx.savepoint("outer");
+        tx.record_operation(sync("b"));
+        tx.savepoint("inner");
+        tx.record_operation(sync("c"));
+        tx.record_operation(sync("d"));
+
+        let undone = tx.rollback_to("inner").unwrap();
+        assert_eq!(entity_ids(&undone), vec!["d", "c"]);
+        assert_eq!(entity_ids(&tx.operations), vec!["a", "b"]);
+
+        let undone = tx.rollback_to("outer").unwrap();
+        assert_eq!(entity_ids(&undone), vec!["b"]);
+        assert_eq!(entity_ids(&tx.operations), vec!["a"]);
+        // Savepoints after the target are gone, the target remains
+        assert!(tx.rollback_to("inner").is_err());
+        assert!(tx.rollback_to("outer").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_savepoint_is_not_found() {
+        let mut tx = context();
+        tx.savepoint("sp");
+        tx.release_savepoint("sp").unwrap();
+        for err in [
+            tx.rollback_to("sp").unwrap_err(),
+            tx.release_savepoint("missing").unwrap_err(),
+        ] {
+            assert!(matches!(
+                err.kind(),
+                CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_release_keeps_operations() {
+        let mut tx = context();
+        tx.savepoint("outer");
+        tx.record_operation(sync("a"));
+        tx.savepoint("inner");
+        tx.record_operation(sync("b"));
+
+        tx.release_savepoint("outer").unwrap();
+        assert!(tx.savepoints.is_empty());
+        assert_eq!(entity_ids(&tx.operations), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_full_commit_and_rollback_discard_savepoints() {
+        let mut tx = context();
+        tx.savepoint("sp");
+        tx.record_operation(sync("a"));
+        tx.commit().unwrap();
+        assert_eq!(tx.state.current_state(), &TransactionState::Committed);
+        assert!(tx.savepoints.is_empty());
+        assert_eq!(tx.operations.len(), 1);
+
+        let mut tx = context();
+        tx.record_operation(sync("a"));
+        tx.savepoint("sp");
+        tx.record_operation(sync("b"));
+        let undone = tx.rollback().unwrap();
+        assert_eq!(entity_ids(&undone), vec!["b", "a"]);
+        assert_eq!(tx.state.current_state(), &TransactionState::RolledBack);
+        assert!(tx.savepoints.is_empty());
+        assert!(tx.rollback_to("sp").is_err());
+    }
+
+    #[test]
+    fn test_expired_transaction_times_out_once() {
+        let mut tx = context();
+        let start = tx.start_time;
+        let timeout = tx.timeout();
+
+        assert_eq!(tx.elapsed(start - Duration::from_secs(1)), Duration::ZERO);
+        assert!(!tx.is_expired(start + timeout));
+        assert!(!tx.check_timeout(start + timeout));
+        assert_eq!(tx.state.current_state(), &TransactionState::Initial);
+
+        let late = start + timeout + Duration::from_millis(1);
+        assert!(tx.is_expired(late));
+        assert!(tx.check_timeout(late));
+        assert_eq!(tx.state.current_state(), &TransactionState::TimedOut);
+        assert!(tx.rollback_only);
+        assert!(!tx.check_timeout(late + timeout));
+    }
+
+    #[test]
+    fn test_timed_out_transaction_must_roll_back() {
+        let mut tx = context();
+        tx.record_operation(sync("a"));
+        assert!(tx.check_timeout(tx.start_time + tx.timeout() * 2));
+
+        let err = tx.commit().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Runtime(RuntimeErrorKind::StateError)
+        ));
+        assert_eq!(entity_ids(&tx.rollback().unwrap()), vec!["a"]);
+        assert_eq!(tx.state.current_state(), &TransactionState::RolledBack);
+    }
+
+    #[test]
+    fn test_finished_transaction_does_not_time_out() {
+        let mut tx = context();
+        tx.commit().unwrap();
+        assert!(!tx.check_timeout(tx.start_time + tx.timeout() * 2));
+        assert_eq!(tx.state.current_state(), &TransactionState::Committed);
+        assert!(!tx.rollback_only);
+    }
+}
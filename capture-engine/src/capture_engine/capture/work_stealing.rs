@@ -0,0 +1,578 @@
+// capture-engine/src/capture/work_stealing.rs
+use parking_lot::Mutex;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, ResourceErrorKind,
+};
+use crate::capture_engine::telemetry::traits::{
+    MetricType, MetricUnit, MetricValue, TelemetryData,
+};
+
+/// Telemetry name for steal operations performed by a worker
+pub const STEALS_METRIC: &str = "capture.workers.steals";
+/// Telemetry name for flows moved to a worker by stealing
+pub const STOLEN_FLOWS_METRIC: &str = "capture.workers.stolen_flows";
+/// Telemetry name for queued items moved to a worker by stealing
+pub const STOLEN_ITEMS_METRIC: &str = "capture.workers.stolen_items";
+
+/// Work-stealing settings
+///
+/// # Fields
+/// * `enabled` - Whether idle workers may steal at all
+/// * `min_victim_backlog` - Queued items a worker must have before others steal from it
+/// * `max_flows_per_steal` - Upper bound on flows moved by a single steal
+#[derive(Debug, Clone)]
+pub struct WorkStealingConfig {
+    pub enabled: bool,
+    pub min_victim_backlog: usize,
+    pub max_flows_per_steal: usize,
+}
+
+impl Default for WorkStealingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_victim_backlog: 64,
+            max_flows_per_steal: 4,
+        }
+    }
+}
+
+/// Pending work for a single flow handed to a worker
+///
+/// The worker must call `WorkStealingScheduler::complete` once it has processed the items so
+/// that later work for the flow can be scheduled or stolen.
+///
+/// # Fields
+/// * `key` - Flow the items belong to
+/// * `items` - Items in arrival order
+/// * `stolen` - Whether the flow was taken from another worker's queue
+#[derive(Debug)]
+pub struct FlowBatch<K, T> {
+    pub key: K,
+    pub items: Vec<T>,
+    pub stolen: bool,
+}
+
+/// Steal counters for one worker
+#[derive(Debug, Default)]
+pub struct StealStats {
+    steals: AtomicU64,
+    stolen_flows: AtomicU64,
+    stolen_items: AtomicU64,
+}
+
+impl StealStats {
+    fn record(&self, flows: u64, items: u64) {
+        self.steals.fetch_add(1, Ordering::Relaxed);
+        self.stolen_flows.fetch_add(flows, Ordering::Relaxed);
+        self.stolen_items.fetch_add(items, Ordering::Relaxed);
+    }
+
+    /// Returns the number of steal operations performed
+    pub fn steals(&self) -> u64 {
+        self.steals.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of flows taken from other workers
+    pub fn stolen_flows(&self) -> u64 {
+        self.stolen_flows.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of queued items taken from other workers
+    pub fn stolen_items(&self) -> u64 {
+        self.stolen_items.load(Ordering::Relaxed)
+    }
+}
+
+/// Queue state of a single worker
+struct WorkerQueue<K, T> {
+    /// Flows with pending items, in the order they became ready
+    order: VecDeque<K>,
+    pending: HashMap<K, VecDeque<T>>,
+    /// Flows this worker is currently processing
+    active: HashSet<K>,
+    len: usize,
+}
+
+impl<K: Hash + Eq + Clone, T> WorkerQueue<K, T> {
+    fn new() -> Self {
+        Self {
+            order: VecDeque::new(),
+            pending: HashMap::new(),
+            active: HashSet::new(),
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, key: K, item: T) {
+        let queue = self.pending.entry(key.clone()).or_default();
+        if queue.is_empty() && !self.active.contains(&key) {
+            self.order.push_back(key);
+        }
+        queue.push_back(item);
+        self.len += 1;
+    }
+
+    fn push_flow(&mut self, key: K, items: VecDeque<T>) {
+        self.len += items.len();
+        self.order.push_back(key.clone());
+        self.pending.insert(key, items);
+    }
+
+    fn take_flow(&mut self, key: &K) -> VecDeque<T> {
+        let items = self.pending.remove(key).unwrap_or_default();
+        self.len -= items.len();
+        items
+    }
+}
+
+/// Distributes per-flow work across workers with optional bounded work stealing
+///
+/// Every flow has a home worker chosen by hashing its key. A worker takes all queued items of
+/// one flow at a time, and no other worker can receive that flow's items until it calls
+/// `complete`, so items of a flow are always processed in arrival order by one worker at a
+/// time. When stealing is enabled, an idle worker moves whole queued flows off the busiest
+/// worker; in-flight and pinned flows are never stolen. Flows that need reassembly should be
+/// pinned so they stay with the worker holding their state.
+///
+/// # Fields
+/// * `queues` - Per-worker queues
+/// * `routes` - Current owner of flows that have queued or in-flight work
+/// * `pinned` - Flows excluded from stealing
+/// * `capacity` - Maximum queued items per worker
+/// * `config` - Work-stealing settings
+/// * `stats` - Per-worker steal counters
+pub struct WorkStealingScheduler<K, T> {
+    queues: Vec<Mutex<WorkerQueue<K, T>>>,
+    routes: Mutex<HashMap<K, usize>>,
+    pinned: Mutex<HashSet<K>>,
+    capacity: usize,
+    config: WorkStealingConfig,
+    stats: Vec<StealStats>,
+}
+
+impl<K: Hash + Eq + Clone, T> WorkStealingScheduler<K, T> {
+    /// Creates a scheduler
+    ///
+    /// # Arguments
+    /// * `workers` - Number of workers
+    /// * `capacity` - Maximum queued items per worker
+    /// * `config` - Work-stealing settings
+    ///
+    /// # Returns
+    /// A new scheduler, or an error if `workers` or `capacity` is zero
+    pub fn new(
+        workers: usize,
+        capacity: usize,
+        config: WorkStealingConfig,
+    ) -> Result<Self, CaptureError> {
+        if workers == 0 || capacity == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Worker count and queue capacity must be greater than zero",
+            ));
+        }
+        Ok(Self {
+            queues: (0..workers)
+                .map(|_| Mutex::new(WorkerQueue::new()))
+                .collect(),
+            routes: Mutex::new(HashMap::new()),
+            pinned: Mutex::new(HashSet::new()),
+            capacity,
+            config,
+            stats: (0..workers).map(|_| StealStats::default()).collect(),
+        })
+    }
+
+    /// Returns the number of workers
+    pub fn workers(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Returns the worker a flow is sharded to when no other worker owns it
+    ///
+    /// # Arguments
+    /// * `key` - Flow key
+    pub fn home_worker(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.queues.len() as u64) as usize
+    }
+
+    /// Excludes a flow from stealing
+    ///
+    /// # Arguments
+    /// * `key` - Flow key
+    pub fn pin_flow(&self, key: K) {
+        self.pinned.lock().insert(key);
+    }
+
+    /// Allows a pinned flow to be stolen again
+    ///
+    /// # Arguments
+    /// * `key` - Flow key
+    pub fn unpin_flow(&self, key: &K) {
+        self.pinned.lock().remove(key);
+    }
+
+    /// Queues an item for its flow's current owner
+    ///
+    /// # Arguments
+    /// * `key` - Flow the item belongs to
+    /// * `item` - Work item
+    ///
+    /// # Returns
+    /// The worker the item was queued on, or a quota error if that worker's queue is full
+    pub fn submit(&self, key: K, item: T) -> Result<usize, CaptureError> {
+        let mut routes = self.routes.lock();
+        let owner = routes
+            .get(&key)
+            .copied()
+            .unwrap_or_else(|| self.home_worker(&key));
+        let mut queue = self.queues[owner].lock();
+        if queue.len >= self.capacity {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded),
+                &format!("Worker {} queue is full", owner),
+            ));
+        }
+        queue.push(key.clone(), item);
+        routes.insert(key, owner);
+        Ok(owner)
+    }
+
+    /// Takes the next flow's pending work for a worker, stealing if it has none of its own
+    ///
+    /// # Arguments
+    /// * `worker` - Worker index
+    ///
+    /// # Returns
+    /// The next batch, or None if there is nothing to do
+    pub fn next(&self, worker: usize) -> Option<FlowBatch<K, T>> {
+        if let Some(batch) = self.take_own(worker, false) {
+            return Some(batch);
+        }
+        if self.config.enabled && self.steal(worker) {
+            return self.take_own(worker, true);
+        }
+        None
+    }
+
+    /// Marks a flow batch as processed
+    ///
+    /// # Arguments
+    /// * `worker` - Worker that processed the batch
+    /// * `key` - Flow the batch belonged to
+    pub fn complete(&self, worker: usize, key: &K) {
+        let mut routes = self.routes.lock();
+        let mut queue = self.queues[worker].lock();
+        queue.active.remove(key);
+        let has_pending = queue
+            .pending
+            .get(key)
+            .is_some_and(|items| !items.is_empty());
+        if has_pending {
+            // Items that arrived while the flow was in flight become runnable now
+            queue.order.push_back(key.clone());
+        } else {
+            queue.pending.remove(key);
+            if routes.get(key) == Some(&worker) {
+                routes.remove(key);
+            }
+        }
+    }
+
+    /// Returns the number of items queued on a worker
+    ///
+    /// # Arguments
+    /// * `worker` - Worker index
+    pub fn queued(&self, worker: usize) -> usize {
+        self.queues[worker].lock().len
+    }
+
+    /// Returns a worker's steal counters
+    ///
+    /// # Arguments
+    /// * `worker` - Worker index
+    pub fn steal_stats(&self, worker: usize) -> &StealStats {
+        &self.stats[worker]
+    }
+
+    /// Returns steal counters as telemetry
+    ///
+    /// # Arguments
+    /// * `timestamp` - Timestamp to stamp the data points with
+    ///
+    /// # Returns
+    /// Three counters per worker, tagged with a `worker` attribute
+    pub fn telemetry(&self, timestamp: u64) -> Vec<TelemetryData> {
+        let counter = |name: &str, description: &str, worker: usize, value: u64| TelemetryData {
+            timestamp,
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type: MetricType::Counter,
+            value: MetricValue::Integer(value as i64),
+            attributes: HashMap::from([("worker".to_string(), worker.to_string())]),
+            resource: None,
+        };
+
+        self.stats
+            .iter()
+            .enumerate()
+            .flat_map(|(worker, stats)| {
+                [
+                    counter(
+                        STEALS_METRIC,
+                        "Steal operations performed",
+                        worker,
+                        stats.steals(),
+                    ),
+                    counter(
+                        STOLEN_FLOWS_METRIC,
+                        "Flows taken from other workers",
+                        worker,
+                        stats.stolen_flows(),
+                    ),
+                    counter(
+                        STOLEN_ITEMS_METRIC,
+                        "Queued items taken from other workers",
+                        worker,
+                        stats.stolen_items(),
+                    ),
+                ]
+            })
+            .collect()
+    }
+
+    fn take_own(&self, worker: usize, stolen: bool) -> Option<FlowBatch<K, T>> {
+        let mut queue = self.queues[worker].lock();
+        while let Some(key) = queue.order.pop_front() {
+            let items = queue.take_flow(&key);
+            if items.is_empty() {
+                continue;
+            }
+            queue.active.insert(key.clone());
+            return Some(FlowBatch {
+                key,
+                items: items.into(),
+                stolen,
+            });
+        }
+        None
+    }
+
+    /// Moves whole queued flows from the busiest worker to `thief`
+    fn steal(&self, thief: usize) -> bool {
+        let mut routes = self.routes.lock();
+        let victim = (0..self.queues.len())
+            .filter(|&w| w != thief)
+            .map(|w| (w, self.queues[w].lock().len))
+            .filter(|&(_, len)| len >= self.config.min_victim_backlog.max(1))
+            .max_by_key(|&(_, len)| len)
+            .map(|(w, _)| w);
+        let Some(victim) = victim else {
+            return false;
+        };
+
+        // Lock in index order so concurrent steals between the same pair cannot deadlock
+        let (mut victim_queue, mut thief_queue) = if victim < thief {
+            let v = self.queues[victim].lock();
+            let t = self.queues[thief].lock();
+            (v, t)
+        } else {
+            let t = self.queues[thief].lock();
+            let v = self.queues[victim].lock();
+            (v, t)
+        };
+
+        let pinned = self.pinned.lock();
+        // Leave the victim the flows it would run next; take from the back of its queue
+        let candidates: Vec<K> = victim_queue
+            .order
+            .iter()
+            .rev()
+            .filter(|key| !victim_queue.active.contains(key) && !pinned.contains(key))
+            .take(self.config.max_flows_per_steal)
+            .cloned()
+            .collect();
+        drop(pinned);
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let mut items = 0;
+        for key in &candidates {
+            let flow = victim_queue.take_flow(key);
+            items += flow.len();
+            thief_queue.push_flow(key.clone(), flow);
+            routes.insert(key.clone(), thief);
+        }
+        victim_queue.order.retain(|key| !candidates.contains(key));
+        self.stats[thief].record(candidates.len() as u64, items as u64);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn stealing() -> WorkStealingConfig {
+        WorkStealingConfig {
+            enabled: true,
+            min_victim_backlog: 2,
+            max_flows_per_steal: 2,
+        }
+    }
+
+    /// Flow keys that all hash to `worker`
+    fn flows_on(
+        scheduler: &WorkStealingScheduler<u32, u32>,
+        worker: usize,
+        count: usize,
+    ) -> Vec<u32> {
+        (0..)
+            .filter(|k| scheduler.home_worker(k) == worker)
+            .take(count)
+            .collect()
+    }
+
+    #[test]
+    fn test_idle_worker_steals_whole_queued_flows() {
+        let scheduler = WorkStealingScheduler::new(2, 1024, stealing()).unwrap();
+        let flows = flows_on(&scheduler, 0, 4);
+        for seq in 0..3 {
+            for &flow in &flows {
+                assert_eq!(scheduler.submit(flow, seq).unwrap(), 0);
+            }
+        }
+
+        let stolen = scheduler.next(1).unwrap();
+        assert!(stolen.stolen);
+        assert_eq!(stolen.items, vec![0, 1, 2]);
+        assert_eq!(scheduler.steal_stats(1).steals(), 1);
+        assert_eq!(scheduler.steal_stats(1).stolen_flows(), 2);
+        assert_eq!(scheduler.steal_stats(1).stolen_items(), 6);
+        assert_eq!(scheduler.queued(0), 6);
+
+        // New work for a stolen flow follows it to the thief
+        assert_eq!(scheduler.submit(stolen.key, 3).unwrap(), 1);
+        scheduler.complete(1, &stolen.key);
+        let follow_up = scheduler.next(1).unwrap();
+        assert!(!follow_up.stolen);
+        assert_ne!(follow_up.key, stolen.key);
+    }
+
+    #[test]
+    fn test_in_flight_and_pinned_flows_are_not_stolen() {
+        let scheduler = WorkStealingScheduler::new(2, 1024, stealing()).unwrap();
+        let flows = flows_on(&scheduler, 0, 2);
+        scheduler.pin_flow(flows[1]);
+        for seq in 0..3 {
+            scheduler.submit(flows[0], seq).unwrap();
+            scheduler.submit(flows[1], seq).unwrap();
+        }
+
+        let running = scheduler.next(0).unwrap();
+        assert_eq!(running.key, flows[0]);
+        scheduler.submit(flows[0], 3).unwrap();
+
+        // flows[0] is in flight on worker 0 and flows[1] is pinned
+        assert!(scheduler.next(1).is_none());
+        assert_eq!(scheduler.steal_stats(1).steals(), 0);
+
+        scheduler.complete(0, &flows[0]);
+        assert_eq!(scheduler.next(1).unwrap().items, vec![3]);
+    }
+
+    #[test]
+    fn test_stealing_disabled_by_default() {
+        let scheduler: WorkStealingScheduler<u32, u32> =
+            WorkStealingScheduler::new(2, 1024, WorkStealingConfig::default()).unwrap();
+        for &flow in &flows_on(&scheduler, 0, 4) {
+            for seq in 0..100 {
+                scheduler.submit(flow, seq).unwrap();
+            }
+        }
+        assert!(scheduler.next(1).is_none());
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let scheduler = WorkStealingScheduler::new(1, 2, stealing()).unwrap();
+        scheduler.submit(1u32, 0u32).unwrap();
+        scheduler.submit(1, 1).unwrap();
+        let err = scheduler.submit(2, 0).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_skewed_load_is_balanced_and_per_flow_order_holds() {
+        const WORKERS: usize = 4;
+        const FLOWS: usize = 24;
+        const PER_FLOW: u32 = 40;
+
+        let scheduler = Arc::new(WorkStealingScheduler::new(WORKERS, 4096, stealing()).unwrap());
+        // Every flow hashes to worker 0, leaving the others idle without stealing
+        let flows = flows_on(&scheduler, 0, FLOWS);
+        for seq in 0..PER_FLOW {
+            for &flow in &flows {
+                scheduler.submit(flow, seq).unwrap();
+            }
+        }
+
+        let processed = Arc::new(Mutex::new(HashMap::<u32, Vec<u32>>::new()));
+        let handles: Vec<_> = (0..WORKERS)
+            .map(|worker| {
+                let scheduler = scheduler.clone();
+                let processed = processed.clone();
+                std::thread::spawn(move || {
+                    let mut idle_polls = 0;
+                    while idle_polls < 50 {
+                        let Some(batch) = scheduler.next(worker) else {
+                            idle_polls += 1;
+                            std::thread::sleep(Duration::from_millis(1));
+                            continue;
+                        };
+                        idle_polls = 0;
+                        for item in &batch.items {
+                            processed.lock().entry(batch.key).or_default().push(*item);
+                            std::thread::sleep(Duration::from_micros(50));
+                        }
+                        scheduler.complete(worker, &batch.key);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let processed = processed.lock();
+        assert_eq!(processed.len(), FLOWS);
+        for items in processed.values() {
+            assert_eq!(*items, (0..PER_FLOW).collect::<Vec<_>>());
+        }
+        let thieves = (1..WORKERS)
+            .filter(|&w| scheduler.steal_stats(w).steals() > 0)
+            .count();
+        assert!(thieves > 0, "idle workers should have stolen work");
+
+        let telemetry = scheduler.telemetry(0);
+        assert_eq!(telemetry.len(), WORKERS * 3);
+        assert!(telemetry.iter().any(
+            |t| t.name == STEALS_METRIC && matches!(t.value, MetricValue::Integer(v) if v > 0)
+        ));
+    }
+}
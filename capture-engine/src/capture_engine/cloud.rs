@@ -1 +1,9 @@
+pub mod dedup;
+pub mod imds;
+pub mod mirror_validation;
+pub mod spot;
 pub mod traits;
+
+pub use imds::ImdsClient;
+pub use mirror_validation::validate_mirror_config;
+pub use spot::{PreemptionHandler, SpotMonitor};
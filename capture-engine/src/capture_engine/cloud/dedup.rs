@@ -0,0 +1,344 @@
+// cloud/dedup.rs
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::cloud::traits::{FilterAction, FilterRule, MirrorSessionConfig};
+use crate::capture_engine::protocol::flow::FlowKey;
+
+impl FilterRule {
+    /// Whether the rule matches a flow in either direction; a rule with an invalid protocol
+    /// matches nothing.
+    pub fn matches(&self, key: &FlowKey) -> bool {
+        let protocol_matches = match protocol_number(&self.protocol) {
+            Ok(Some(protocol)) => protocol == key.protocol,
+            Ok(None) => true,
+            Err(_) => false,
+        };
+        protocol_matches
+            && (self.matches_direction(key.addr_a, key.port_a, key.addr_b, key.port_b)
+                || self.matches_direction(key.addr_b, key.port_b, key.addr_a, key.port_a))
+    }
+
+    fn matches_direction(&self, src: IpAddr, src_port: u16, dst: IpAddr, dst_port: u16) -> bool {
+        self.source_ip.is_none_or(|ip| ip == src)
+            && self.dest_ip.is_none_or(|ip| ip == dst)
+            && self.source_port.is_none_or(|port| port == src_port)
+            && self.dest_port.is_none_or(|port| port == dst_port)
+    }
+}
+
+/// Parses a rule protocol; None means any protocol.
+///
+/// Only "", "all", "any" and "-1" are wildcards. Other values must be a protocol name or an IP
+/// protocol number from 0 to 255.
+pub(super) fn protocol_number(protocol: &str) -> Result<Option<u8>, CaptureError> {
+    match protocol.to_ascii_lowercase().as_str() {
+        "tcp" => Ok(Some(6)),
+        "udp" => Ok(Some(17)),
+        "icmp" => Ok(Some(1)),
+        "" | "all" | "any" | "-1" => Ok(None),
+        other => other.parse().map(Some).map_err(|_| {
+            *CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                &format!("Unknown mirror filter protocol {:?}", protocol),
+            )
+        }),
+    }
+}
+
+impl MirrorSessionConfig {
+    /// Whether the session mirrors a flow seen on the `source` ENI.
+    ///
+    /// A session only covers flows of its own source. Rules are evaluated in order and the first
+    /// match decides; a session without rules mirrors everything from its source.
+    pub fn covers(&self, source: &str, key: &FlowKey) -> bool {
+        if self.source != source {
+            return false;
+        }
+        if self.filter_rules.is_empty() {
+            return true;
+        }
+        self.filter_rules
+            .iter()
+            .find(|rule| rule.matches(key))
+            .is_some_and(|rule| !matches!(rule.action, FilterAction::Drop))
+    }
+}
+
+/// Which overlapping sessions cover a flow and which one captures it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowAssignment {
+    /// Session that captures the flow, if any session covers it.
+    pub owner: Option<String>,
+    /// Every session covering the flow, in configuration order.
+    pub covering: Vec<String>,
+}
+
+/// Per-session dedup counters.
+#[derive(Debug, Default)]
+pub struct SessionDedupStats {
+    owned: AtomicU64,
+    deferred: AtomicU64,
+}
+
+impl SessionDedupStats {
+    /// Flows this session captures.
+    pub fn owned(&self) -> u64 {
+        self.owned.load(Ordering::Relaxed)
+    }
+
+    /// Flows this session covers but leaves to another session.
+    pub fn deferred(&self) -> u64 {
+        self.deferred.load(Ordering::Relaxed)
+    }
+
+    /// Flows this session would have captured without dedup.
+    pub fn covered(&self) -> u64 {
+        self.owned() + self.deferred()
+    }
+}
+
+/// Assigns each flow to a single owning session among overlapping mirror sessions.
+///
+/// Sessions overlap when they mirror the same source ENI; a flow is assigned among the sessions
+/// of the source it was seen on, so sessions of other sources never take it over. Ownership
+/// uses rendezvous hashing of the session id and flow key with a fixed hash, so every node
+/// picks the same owner without coordination, and adding or removing a session only moves the
+/// flows that session owns or would own.
+#[derive(Debug)]
+pub struct FlowDeduplicator {
+    sessions: Vec<MirrorSessionConfig>,
+    stats: HashMap<String, SessionDedupStats>,
+}
+
+impl FlowDeduplicator {
+    /// Creates a deduplicator over the given sessions.
+    pub fn new(sessions: Vec<MirrorSessionConfig>) -> Self {
+        let stats = sessions
+            .iter()
+            .map(|s| (s.session_id.clone(), SessionDedupStats::default()))
+            .collect();
+        Self { sessions, stats }
+    }
+
+    /// Computes the covering sessions and owner of a flow seen on `source` without recording it.
+    pub fn assignment(&self, source: &str, key: &FlowKey) -> FlowAssignment {
+        let covering: Vec<&MirrorSessionConfig> = self
+            .sessions
+            .iter()
+            .filter(|s| s.covers(source, key))
+            .collect();
+        let owner = covering
+            .iter()
+            .max_by_key(|s| (rendezvous_weight(&s.session_id, key), &s.session_id))
+            .map(|s| s.session_id.clone());
+        FlowAssignment {
+            owner,
+            covering: covering.iter().map(|s| s.session_id.clone()).collect(),
+        }
+    }
+
+    /// Assigns a flow newly seen on `source` and updates the per-session counters.
+    pub fn record_flow(&self, source: &str, key: &FlowKey) -> FlowAssignment {
+        let assignment = self.assignment(source, key);
+        for session in &assignment.covering {
+            if let Some(stats) = self.stats.get(session) {
+                let counter = if assignment.owner.as_ref() == Some(session) {
+                    &stats.owned
+                } else {
+                    &stats.deferred
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        assignment
+    }
+
+    /// Whether `session_id` should capture the flow seen on `source`.
+    pub fn should_capture(&self, session_id: &str, source: &str, key: &FlowKey) -> bool {
+        self.assignment(source, key).owner.as_deref() == Some(session_id)
+    }
+
+    /// Counters for a session.
+    pub fn stats(&self, session_id: &str) -> Option<&SessionDedupStats> {
+        self.stats.get(session_id)
+    }
+}
+
+/// Rendezvous weight of a session for a flow, using FNV-1a so all nodes agree.
+fn rendezvous_weight(session_id: &str, key: &FlowKey) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    };
+    write(session_id.as_bytes());
+    write(&[0xff]);
+    for addr in [key.addr_a, key.addr_b] {
+        match addr {
+            IpAddr::V4(v4) => write(&v4.octets()),
+            IpAddr::V6(v6) => write(&v6.octets()),
+        }
+    }
+    write(&key.port_a.to_be_bytes());
+    write(&key.port_b.to_be_bytes());
+    write(&[key.protocol]);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::protocol::flow::{IPPROTO_TCP, IPPROTO_UDP};
+    use std::net::Ipv4Addr;
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))
+    }
+
+    const ENI: &str = "eni-0a1b";
+
+    fn session(id: &str, rules: Vec<FilterRule>) -> MirrorSessionConfig {
+        session_on(ENI, id, rules)
+    }
+
+    fn session_on(source: &str, id: &str, rules: Vec<FilterRule>) -> MirrorSessionConfig {
+        MirrorSessionConfig {
+            session_id: id.to_string(),
+            source: source.to_string(),
+            target: "nlb-capture".to_string(),
+            filter_rules: rules,
+            vni: None,
+            truncate_length: None,
+        }
+    }
+
+    fn rule(protocol: &str, dest_port: Option<u16>, action: FilterAction) -> FilterRule {
+        FilterRule {
+            protocol: protocol.to_string(),
+            source_ip: None,
+            dest_ip: None,
+            source_port: None,
+            dest_port,
+            action,
+        }
+    }
+
+    fn flows() -> Vec<FlowKey> {
+        (0..200u16)
+            .map(|i| FlowKey::new(ip((i % 50) as u8), 40_000 + i, ip(200), 443, IPPROTO_TCP))
+            .collect()
+    }
+
+    #[test]
+    fn test_overlapping_sessions_capture_each_flow_once() {
+        // "web" mirrors TCP 443 only; "all" mirrors everything; they overlap on 443
+        let dedup = FlowDeduplicator::new(vec![
+            session("web", vec![rule("tcp", Some(443), FilterAction::Accept)]),
+            session("all", vec![]),
+        ]);
+
+        for key in flows() {
+            let assignment = dedup.record_flow(ENI, &key);
+            assert_eq!(assignment.covering, vec!["web", "all"]);
+            let capturing: Vec<_> = ["web", "all"]
+                .into_iter()
+                .filter(|s| dedup.should_capture(s, ENI, &key))
+                .collect();
+            assert_eq!(capturing.len(), 1);
+            assert_eq!(Some(capturing[0].to_string()), assignment.owner);
+        }
+
+        let web = dedup.stats("web").unwrap();
+        let all = dedup.stats("all").unwrap();
+        assert_eq!(web.owned() + all.owned(), 200);
+        assert_eq!(web.covered(), 200);
+        assert_eq!(all.covered(), 200);
+        // Both sessions own a share of the overlap
+        assert!(web.owned() > 0 && all.owned() > 0);
+    }
+
+    #[test]
+    fn test_ownership_is_stable() {
+        let sessions = vec![session("a", vec![]), session("b", vec![])];
+        let first = FlowDeduplicator::new(sessions.clone());
+        let reordered = FlowDeduplicator::new(sessions.into_iter().rev().collect());
+        for key in flows() {
+            let owner = first.assignment(ENI, &key).owner;
+            assert_eq!(owner, first.assignment(ENI, &key).owner);
+            assert_eq!(owner, reordered.assignment(ENI, &key).owner);
+            // Either direction of the flow has the same owner
+            let reverse = FlowKey::new(key.addr_b, key.port_b, key.addr_a, key.port_a, 6);
+            assert_eq!(owner, first.assignment(ENI, &reverse).owner);
+        }
+    }
+
+    #[test]
+    fn test_adding_a_session_only_moves_flows_to_it() {
+        let before = FlowDeduplicator::new(vec![session("a", vec![]), session("b", vec![])]);
+        let after = FlowDeduplicator::new(vec![
+            session("a", vec![]),
+            session("b", vec![]),
+            session("c", vec![]),
+        ]);
+        for key in flows() {
+            let old = before.assignment(ENI, &key).owner.unwrap();
+            let new = after.assignment(ENI, &key).owner.unwrap();
+            assert!(new == old || new == "c");
+        }
+    }
+
+    #[test]
+    fn test_sessions_of_other_sources_do_not_take_flows() {
+        let dedup = FlowDeduplicator::new(vec![
+            session_on("eni-a", "a", vec![]),
+            session_on("eni-b", "b", vec![]),
+            session_on("eni-b", "b2", vec![]),
+        ]);
+        for key in flows() {
+            // Only the source's own session covers flows seen on eni-a, so it owns them all
+            let assignment = dedup.record_flow("eni-a", &key);
+            assert_eq!(assignment.covering, vec!["a"]);
+            assert_eq!(assignment.owner.as_deref(), Some("a"));
+            assert!(dedup.should_capture("a", "eni-a", &key));
+            assert!(!dedup.should_capture("b", "eni-a", &key));
+
+            let assignment = dedup.record_flow("eni-b", &key);
+            assert_eq!(assignment.covering, vec!["b", "b2"]);
+            assert_ne!(assignment.owner.as_deref(), Some("a"));
+        }
+        assert_eq!(dedup.stats("a").unwrap().owned(), 200);
+        assert_eq!(dedup.stats("a").unwrap().deferred(), 0);
+        let b = dedup.stats("b").unwrap();
+        let b2 = dedup.stats("b2").unwrap();
+        assert_eq!(b.owned() + b2.owned(), 200);
+        assert_eq!(dedup.assignment("eni-c", &flows()[0]).owner, None);
+    }
+
+    #[test]
+    fn test_uncovered_and_dropped_flows_have_no_owner() {
+        let dedup = FlowDeduplicator::new(vec![session(
+            "web",
+            vec![
+                rule("tcp", Some(22), FilterAction::Drop),
+                rule("tcp", None, FilterAction::Accept),
+            ],
+        )]);
+        let ssh = FlowKey::new(ip(1), 50_000, ip(2), 22, IPPROTO_TCP);
+        let dns = FlowKey::new(ip(1), 50_000, ip(2), 53, IPPROTO_UDP);
+        let https = FlowKey::new(ip(1), 50_000, ip(2), 443, IPPROTO_TCP);
+
+        assert_eq!(dedup.assignment(ENI, &ssh).owner, None);
+        assert_eq!(dedup.assignment(ENI, &dns).owner, None);
+        assert_eq!(dedup.assignment(ENI, &https).owner.as_deref(), Some("web"));
+    }
+}
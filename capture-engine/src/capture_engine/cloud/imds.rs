@@ -0,0 +1,354 @@
+// cloud/imds.rs
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, CaptureResult, CloudErrorKind,
+};
+use crate::capture_engine::cloud::traits::InstanceMetadata;
+
+/// Address of the EC2 instance metadata service.
+pub const DEFAULT_IMDS_ENDPOINT: &str = "169.254.169.254:80";
+/// Per-request timeout; short so non-EC2 hosts fail fast instead of hanging.
+pub const DEFAULT_IMDS_TIMEOUT: Duration = Duration::from_secs(1);
+/// Lifetime requested for IMDSv2 session tokens.
+pub const DEFAULT_IMDS_TOKEN_TTL: Duration = Duration::from_secs(21_600);
+
+const TOKEN_PATH: &str = "/latest/api/token";
+const TOKEN_TTL_HEADER: &str = "X-aws-ec2-metadata-token-ttl-seconds";
+const TOKEN_HEADER: &str = "X-aws-ec2-metadata-token";
+
+/// Client for the EC2 instance metadata service using IMDSv2 session tokens.
+///
+/// Every fetch first obtains a token with `PUT /latest/api/token` and sends it with each
+/// metadata read. There is no IMDSv1 fallback: if no token can be obtained the fetch fails
+/// with `Cloud(MetadataError)`.
+#[derive(Debug, Clone)]
+pub struct ImdsClient {
+    endpoint: String,
+    timeout: Duration,
+    token_ttl: Duration,
+}
+
+impl Default for ImdsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImdsClient {
+    /// Creates a client for the default metadata endpoint.
+    pub fn new() -> Self {
+        Self {
+            endpoint: DEFAULT_IMDS_ENDPOINT.to_string(),
+            timeout: DEFAULT_IMDS_TIMEOUT,
+            token_ttl: DEFAULT_IMDS_TOKEN_TTL,
+        }
+    }
+
+    /// Sets the `host:port` of the metadata service, e.g. a mock server in tests.
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Sets the timeout applied to each metadata request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the lifetime requested for session tokens.
+    pub fn with_token_ttl(mut self, token_ttl: Duration) -> Self {
+        self.token_ttl = token_ttl;
+        self
+    }
+
+    /// Metadata service endpoint.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Per-request timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Fetches instance identity, placement and VPC from the metadata service.
+    pub async fn fetch_instance_metadata(&self) -> CaptureResult<InstanceMetadata> {
+        let token = self.fetch_token().await?;
+        let mac = self.get(&token, "/latest/meta-data/mac").await?;
+        Ok(InstanceMetadata {
+            instance_id: self.get(&token, "/latest/meta-data/instance-id").await?,
+            instance_type: self.get(&token, "/latest/meta-data/instance-type").await?,
+            availability_zone: self
+                .get(&token, "/latest/meta-data/placement/availability-zone")
+                .await?,
+            region: self
+                .get(&token, "/latest/meta-data/placement/region")
+                .await?,
+            vpc_id: self
+                .get(
+                    &token,
+                    &format!("/latest/meta-data/network/interfaces/macs/{mac}/vpc-id"),
+                )
+                .await?,
+            tags: HashMap::new(),
+        })
+    }
+
+    /// Reads a metadata path that may be absent.
+    ///
+    /// Returns None when the service answers 404, as it does for notices that have not been
+    /// issued.
+    pub async fn fetch_optional(&self, path: &str) -> CaptureResult<Option<String>> {
+        let token = self.fetch_token().await?;
+        let (status, body) = self.request("GET", path, &[(TOKEN_HEADER, &token)]).await?;
+        match status {
+            200 => Ok(Some(body.trim().to_string())),
+            404 => Ok(None),
+            _ => Err(metadata_error(&format!(
+                "metadata request for {path} returned status {status}"
+            ))),
+        }
+    }
+
+    /// Obtains an IMDSv2 session token.
+    async fn fetch_token(&self) -> CaptureResult<String> {
+        let ttl = self.token_ttl.as_secs().max(1).to_string();
+        let (status, body) = self
+            .request("PUT", TOKEN_PATH, &[(TOKEN_TTL_HEADER, &ttl)])
+            .await?;
+        if status != 200 || body.is_empty() {
+            return Err(metadata_error(&format!(
+                "IMDSv2 token request returned status {status}; IMDSv1 fallback is disabled"
+            )));
+        }
+        Ok(body)
+    }
+
+    /// Reads one metadata value with the session token.
+    async fn get(&self, token: &str, path: &str) -> CaptureResult<String> {
+        let (status, body) = self.request("GET", path, &[(TOKEN_HEADER, token)]).await?;
+        if status != 200 {
+            return Err(metadata_error(&format!(
+                "metadata request for {path} returned status {status}"
+            )));
+        }
+        Ok(body.trim().to_string())
+    }
+
+    /// Sends one HTTP/1.1 request and returns the status code and body.
+    async fn request(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+    ) -> CaptureResult<(u16, String)> {
+        let exchange = async {
+            let mut stream = TcpStream::connect(&self.endpoint).await?;
+            let mut request = format!(
+                "{method} {path} HTTP/1.1\r\nHost: {}\r\n\
+                 Connection: close\r\nContent-Length: 0\r\n",
+                self.endpoint
+            );
+            for (name, value) in headers {
+                request.push_str(&format!("{name}: {value}\r\n"));
+            }
+            request.push_str("\r\n");
+            stream.write_all(request.as_bytes()).await?;
+
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await?;
+            Ok::<_, std::io::Error>(response)
+        };
+
+        let response = match tokio::time::timeout(self.timeout, exchange).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => {
+                return Err(Box::new(
+                    CaptureError::new(
+                        CaptureErrorKind::Cloud(CloudErrorKind::MetadataError),
+                        &format!("metadata service unreachable at {}", self.endpoint),
+                    )
+                    .with_source(e),
+                ))
+            }
+            Err(_) => {
+                return Err(metadata_error(&format!(
+                    "metadata service at {} did not respond within {:?}",
+                    self.endpoint, self.timeout
+                )))
+            }
+        };
+        parse_response(&response)
+    }
+}
+
+/// Parses a `Connection: close` HTTP/1.1 response.
+fn parse_response(response: &[u8]) -> CaptureResult<(u16, String)> {
+    let text = String::from_utf8_lossy(response);
+    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+        return Err(metadata_error("malformed metadata response"));
+    };
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| metadata_error("malformed metadata response status line"))?;
+    let length = head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            value.trim().parse::<usize>().ok()
+        } else {
+            None
+        }
+    });
+    let body = match length {
+        Some(length) => body.get(..length).unwrap_or(body),
+        None => body,
+    };
+    Ok((status, body.to_string()))
+}
+
+fn metadata_error(message: &str) -> Box<CaptureError> {
+    CaptureError::new(
+        CaptureErrorKind::Cloud(CloudErrorKind::MetadataError),
+        message,
+    )
+}
+
+/// Minimal HTTP server standing in for the metadata service in tests.
+#[cfg(test)]
+pub(crate) mod mock_server {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answers each request with `respond(request)` and returns the listening `host:port`.
+    pub(crate) async fn serve<F>(respond: F) -> String
+    where
+        F: Fn(&str) -> (u16, String) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let (status, body) = respond(&String::from_utf8_lossy(&request));
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        addr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    const TOKEN: &str = "test-token";
+
+    /// Serves IMDSv2 responses; `issue_token` controls whether token requests succeed.
+    async fn mock_imds(issue_token: bool) -> String {
+        mock_server::serve(move |request| {
+            let (status, body) = respond(request, issue_token);
+            (status, body.to_string())
+        })
+        .await
+    }
+
+    fn respond(request: &str, issue_token: bool) -> (u16, &'static str) {
+        let path = request.split_whitespace().nth(1).unwrap_or_default();
+        if request.starts_with("PUT ") {
+            let has_ttl = request.contains(&format!("{TOKEN_TTL_HEADER}: 21600"));
+            return match (path, issue_token && has_ttl) {
+                (TOKEN_PATH, true) => (200, TOKEN),
+                _ => (403, ""),
+            };
+        }
+        if !request.contains(&format!("{TOKEN_HEADER}: {TOKEN}")) {
+            return (401, "");
+        }
+        match path {
+            "/latest/meta-data/mac" => (200, "0a:1b:2c:3d:4e:5f"),
+            "/latest/meta-data/instance-id" => (200, "i-0123456789abcdef0"),
+            "/latest/meta-data/instance-type" => (200, "c6in.large"),
+            "/latest/meta-data/placement/availability-zone" => (200, "us-east-1a"),
+            "/latest/meta-data/placement/region" => (200, "us-east-1"),
+            "/latest/meta-data/network/interfaces/macs/0a:1b:2c:3d:4e:5f/vpc-id" => {
+                (200, "vpc-0abc")
+            }
+            _ => (404, ""),
+        }
+    }
+
+    fn is_metadata_error(error: &CaptureError) -> bool {
+        matches!(
+            error.kind(),
+            CaptureErrorKind::Cloud(CloudErrorKind::MetadataError)
+        )
+    }
+
+    #[tokio::test]
+    async fn test_fetch_uses_token_flow() {
+        let client = ImdsClient::new().with_endpoint(mock_imds(true).await);
+
+        let metadata = client.fetch_instance_metadata().await.unwrap();
+        assert_eq!(metadata.instance_id, "i-0123456789abcdef0");
+        assert_eq!(metadata.instance_type, "c6in.large");
+        assert_eq!(metadata.availability_zone, "us-east-1a");
+        assert_eq!(metadata.region, "us-east-1");
+        assert_eq!(metadata.vpc_id, "vpc-0abc");
+    }
+
+    #[tokio::test]
+    async fn test_missing_token_is_metadata_error() {
+        let client = ImdsClient::new().with_endpoint(mock_imds(false).await);
+
+        let error = client.fetch_instance_metadata().await.unwrap_err();
+        assert!(is_metadata_error(&error));
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_endpoint_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let client = ImdsClient::new()
+            .with_endpoint(addr)
+            .with_timeout(Duration::from_millis(50));
+
+        let started = std::time::Instant::now();
+        let error = client.fetch_instance_metadata().await.unwrap_err();
+        assert!(is_metadata_error(&error));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_parse_response_honours_content_length() {
+        let (status, body) =
+            parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nabcdef").unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(body, "abc");
+        assert!(parse_response(b"garbage").is_err());
+    }
+}
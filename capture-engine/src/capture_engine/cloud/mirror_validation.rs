@@ -0,0 +1,303 @@
+// cloud/mirror_validation.rs
+//! Checks mirror session configs against what the instance can receive before they are pushed.
+use std::collections::HashSet;
+
+use crate::capture_engine::cloud::dedup::protocol_number;
+use crate::capture_engine::cloud::traits::{
+    InstanceLimits, MirrorSessionConfig, NetworkCapabilities,
+};
+use crate::traits::{ValidationError, ValidationResult, ValidationWarning};
+
+/// Truncate lengths below this may cut off the inner IPv6 and TCP headers with options.
+pub const MIN_RECOMMENDED_TRUNCATE_LENGTH: u32 = 128;
+
+/// Validates a mirror session config against the instance's capabilities and limits.
+///
+/// `active_sessions` are the sessions the instance already receives; a session with the same id
+/// as `config` is replaced rather than counted twice. Each violated constraint yields its own
+/// error, and a truncate length short enough to lose headers yields a warning.
+pub fn validate_mirror_config(
+    config: &MirrorSessionConfig,
+    active_sessions: &[MirrorSessionConfig],
+    capabilities: &NetworkCapabilities,
+    limits: &InstanceLimits,
+) -> ValidationResult {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (field, value) in [
+        ("session_id", &config.session_id),
+        ("source", &config.source),
+        ("target", &config.target),
+    ] {
+        if value.trim().is_empty() {
+            errors.push(ValidationError::MissingField {
+                field: field.to_string(),
+            });
+        }
+    }
+
+    let sessions: HashSet<&str> = active_sessions
+        .iter()
+        .map(|session| session.session_id.as_str())
+        .chain([config.session_id.as_str()])
+        .collect();
+    if sessions.len() > limits.max_mirror_sessions as usize {
+        errors.push(ValidationError::ConstraintViolation {
+            field: "session_id".to_string(),
+            constraint: format!(
+                "instance supports at most {} mirror sessions, {} requested",
+                limits.max_mirror_sessions,
+                sessions.len()
+            ),
+        });
+    }
+
+    for (index, rule) in config.filter_rules.iter().enumerate() {
+        let reason = match protocol_number(&rule.protocol) {
+            Err(e) => Some(e.to_string()),
+            Ok(Some(number)) if !protocol_supported(number, &capabilities.supported_protocols) => {
+                Some(format!(
+                    "protocol {:?} is not supported; supported: {}",
+                    rule.protocol,
+                    capabilities.supported_protocols.join(", ")
+                ))
+            }
+            Ok(_) => None,
+        };
+        if let Some(reason) = reason {
+            errors.push(ValidationError::InvalidValue {
+                field: format!("filter_rules[{}].protocol", index),
+                reason,
+            });
+        }
+    }
+
+    if let Some(vni) = config.vni {
+        if !capabilities.vni_range.contains(&vni) {
+            errors.push(ValidationError::ConstraintViolation {
+                field: "vni".to_string(),
+                constraint: format!(
+                    "VNI {} is outside {}..={}",
+                    vni,
+                    capabilities.vni_range.start(),
+                    capabilities.vni_range.end()
+                ),
+            });
+        }
+    }
+
+    if let Some(length) = config.truncate_length {
+        let range = &capabilities.truncate_length_range;
+        if !range.contains(&length) {
+            errors.push(ValidationError::ConstraintViolation {
+                field: "truncate_length".to_string(),
+                constraint: format!(
+                    "truncate length {} is outside {}..={}",
+                    length,
+                    range.start(),
+                    range.end()
+                ),
+            });
+        } else if length < MIN_RECOMMENDED_TRUNCATE_LENGTH {
+            warnings.push(ValidationWarning::PerformanceImpact {
+                field: "truncate_length".to_string(),
+                impact: format!(
+                    "{} bytes may cut off transport headers, leaving flows unclassified",
+                    length
+                ),
+            });
+        }
+    }
+
+    ValidationResult {
+        is_valid: errors.is_empty(),
+        errors,
+        warnings,
+    }
+}
+
+/// Whether a rule protocol number is one of the supported protocols.
+fn protocol_supported(number: u8, supported: &[String]) -> bool {
+    supported
+        .iter()
+        .any(|candidate| matches!(protocol_number(candidate), Ok(Some(n)) if n == number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::{CaptureErrorKind, ConfigErrorKind};
+    use crate::capture_engine::cloud::traits::{FilterAction, FilterRule};
+
+    fn capabilities() -> NetworkCapabilities {
+        NetworkCapabilities {
+            max_bandwidth_mbps: 10_000,
+            supported_protocols: vec!["tcp".to_string(), "udp".to_string()],
+            vni_range: 1..=16_777_215,
+            truncate_length_range: 1..=8947,
+        }
+    }
+
+    fn limits(max_mirror_sessions: u32) -> InstanceLimits {
+        InstanceLimits {
+            max_memory_mb: 16_384,
+            max_storage_gb: 100,
+            max_cpu_cores: 4,
+            max_mirror_sessions,
+        }
+    }
+
+    fn session(id: &str) -> MirrorSessionConfig {
+        MirrorSessionConfig {
+            session_id: id.to_string(),
+            source: format!("eni-{}", id),
+            target: "nlb-capture".to_string(),
+            filter_rules: Vec::new(),
+            vni: Some(100),
+            truncate_length: None,
+        }
+    }
+
+    fn rule(protocol: &str) -> FilterRule {
+        FilterRule {
+            protocol: protocol.to_string(),
+            source_ip: None,
+            dest_ip: None,
+            source_port: None,
+            dest_port: None,
+            action: FilterAction::Mirror,
+        }
+    }
+
+    fn validate(config: &MirrorSessionConfig) -> ValidationResult {
+        validate_mirror_config(config, &[], &capabilities(), &limits(3))
+    }
+
+    #[test]
+    fn test_valid_config_passes() {
+        let mut config = session("a");
+        config.filter_rules = vec![rule("tcp"), rule("17"), rule("all")];
+        config.truncate_length = Some(256);
+        let result = validate(&config);
+        assert!(result.is_valid);
+        assert!(result.errors.is_empty());
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_too_many_sessions() {
+        let active = [session("a"), session("b"), session("c")];
+        let result = validate_mirror_config(&session("d"), &active, &capabilities(), &limits(3));
+        assert!(!result.is_valid);
+        assert!(matches!(
+            &result.errors[..],
+            [ValidationError::ConstraintViolation { field, .. }] if field == "session_id"
+        ));
+
+        // Replacing an active session does not add one
+        let result = validate_mirror_config(&session("c"), &active, &capabilities(), &limits(3));
+        assert!(result.is_valid);
+    }
+
+    #[test]
+    fn test_unsupported_protocol_per_rule() {
+        let mut config = session("a");
+        config.filter_rules = vec![rule("TCP"), rule("icmp"), rule("udp"), rule("47")];
+        let result = validate(&config);
+        let fields: Vec<&str> = result
+            .errors
+            .iter()
+            .filter_map(|error| match error {
+                ValidationError::InvalidValue { field, .. } => Some(field.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            ["filter_rules[1].protocol", "filter_rules[3].protocol"]
+        );
+    }
+
+    #[test]
+    fn test_bogus_protocol_rejected() {
+        let mut config = session("a");
+        config.filter_rules = vec![rule("any"), rule("tcpp"), rule("256"), rule("-1")];
+        let result = validate(&config);
+        let fields: Vec<&str> = result
+            .errors
+            .iter()
+            .filter_map(|error| match error {
+                ValidationError::InvalidValue { field, .. } => Some(field.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            ["filter_rules[1].protocol", "filter_rules[2].protocol"]
+        );
+
+        let err = protocol_number("tcpp").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue)
+        ));
+    }
+
+    #[test]
+    fn test_vni_out_of_range() {
+        let mut config = session("a");
+        config.vni = Some(0);
+        assert!(matches!(
+            &validate(&config).errors[..],
+            [ValidationError::ConstraintViolation { field, .. }] if field == "vni"
+        ));
+        config.vni = Some(1 << 24);
+        assert!(!validate(&config).is_valid);
+        config.vni = None;
+        assert!(validate(&config).is_valid);
+    }
+
+    #[test]
+    fn test_truncate_length_bounds() {
+        let mut config = session("a");
+        for length in [0, 8948] {
+            config.truncate_length = Some(length);
+            let result = validate(&config);
+            assert!(matches!(
+                &result.errors[..],
+                [ValidationError::ConstraintViolation { field, .. }] if field == "truncate_length"
+            ));
+            assert!(result.warnings.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_small_truncate_length_warns() {
+        let mut config = session("a");
+        config.truncate_length = Some(64);
+        let result = validate(&config);
+        assert!(result.is_valid);
+        assert!(matches!(
+            &result.warnings[..],
+            [ValidationWarning::PerformanceImpact { field, .. }] if field == "truncate_length"
+        ));
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let mut config = session("a");
+        config.source = String::new();
+        config.target = " ".to_string();
+        let result = validate(&config);
+        let missing: Vec<&str> = result
+            .errors
+            .iter()
+            .filter_map(|error| match error {
+                ValidationError::MissingField { field } => Some(field.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(missing, ["source", "target"]);
+    }
+}
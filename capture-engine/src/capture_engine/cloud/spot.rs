@@ -0,0 +1,413 @@
+// cloud/spot.rs
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, CaptureResult, CloudErrorKind,
+};
+use crate::capture_engine::cloud::imds::ImdsClient;
+use crate::capture_engine::cloud::traits::{CloudEvent, CloudLifecycleEvent};
+use crate::capture_engine::output::traits::{FlushControl, FlushMode};
+use crate::capture_engine::state::traits::{CaptureState, StateManager, StateTransition};
+use crate::traits::{Error, EventHandler};
+
+/// Metadata path that carries a spot interruption notice once one is issued.
+pub const INSTANCE_ACTION_PATH: &str = "/latest/meta-data/spot/instance-action";
+/// Default interval between instance-action polls.
+pub const DEFAULT_SPOT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Default time kept back from the emergency flush for shutting down after it.
+pub const DEFAULT_SHUTDOWN_MARGIN: Duration = Duration::from_secs(10);
+
+/// Body of a spot instance-action notice.
+///
+/// The action (terminate, stop or hibernate) is not needed: each ends capture on the instance.
+#[derive(Debug, Deserialize)]
+struct InstanceAction {
+    time: String,
+}
+
+/// Polls the instance-action metadata endpoint for a spot interruption notice.
+///
+/// When a notice appears, `run` emits `CloudLifecycleEvent::InstancePreempt` with the time left
+/// before the instance is reclaimed and stops polling.
+#[derive(Debug, Clone)]
+pub struct SpotMonitor {
+    client: ImdsClient,
+    poll_interval: Duration,
+}
+
+impl SpotMonitor {
+    /// Creates a monitor polling through `client` every `poll_interval`.
+    pub fn new(client: ImdsClient, poll_interval: Duration) -> Self {
+        Self {
+            client,
+            poll_interval,
+        }
+    }
+
+    /// Interval between polls.
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Checks once for an interruption notice.
+    ///
+    /// Returns the time remaining before the instance action at `now`, or None if no notice
+    /// has been issued. A notice already past its action time has zero time remaining.
+    pub async fn poll_once(&self, now: SystemTime) -> CaptureResult<Option<Duration>> {
+        let Some(body) = self.client.fetch_optional(INSTANCE_ACTION_PATH).await? else {
+            return Ok(None);
+        };
+        let action: InstanceAction = serde_json::from_str(&body).map_err(|e| {
+            Box::new(
+                CaptureError::new(
+                    CaptureErrorKind::Cloud(CloudErrorKind::MetadataError),
+                    "malformed spot instance-action notice",
+                )
+                .with_source(e),
+            )
+        })?;
+        let action_time = parse_timestamp(&action.time).ok_or_else(|| {
+            CaptureError::new(
+                CaptureErrorKind::Cloud(CloudErrorKind::MetadataError),
+                &format!("invalid instance-action time {:?}", action.time),
+            )
+        })?;
+        Ok(Some(
+            action_time.duration_since(now).unwrap_or(Duration::ZERO),
+        ))
+    }
+
+    /// Polls until a notice is seen and sends it as an `InstancePreempt` event.
+    ///
+    /// Failed polls are retried at the next interval, since a metadata hiccup must not end
+    /// monitoring. Returns once the notice is sent or the receiver is gone. The future holds no
+    /// state between polls, so dropping it to cancel monitoring is safe at any await point.
+    pub async fn run(&self, events: mpsc::Sender<CloudEvent>) {
+        loop {
+            if let Ok(Some(remaining)) = self.poll_once(SystemTime::now()).await {
+                let event = CloudEvent::Lifecycle(CloudLifecycleEvent::InstancePreempt(remaining));
+                let _ = events.send(event).await;
+                return;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.poll_interval) => {}
+                _ = events.closed() => return,
+            }
+        }
+    }
+}
+
+/// Parses an ISO 8601 UTC timestamp such as `2024-09-18T08:22:00Z`.
+fn parse_timestamp(value: &str) -> Option<SystemTime> {
+    let value = value.strip_suffix('Z')?;
+    let (date, time) = value.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+
+    // Days since the epoch from a proleptic Gregorian civil date.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = u64::try_from(era * 146_097 + day_of_era - 719_468).ok()?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86_400 + hour * 3_600 + minute * 60 + second))
+}
+
+/// Reacts to spot interruption by shutting capture down and flushing output.
+///
+/// On `InstancePreempt`, a `Capturing -> ShuttingDown` transition is requested first, so no new
+/// packets are taken in while output drains. Buffered output is then flushed with
+/// `FlushMode::Emergency`, bounded by the notice's remaining time less the shutdown margin.
+/// With no more than the margin left, the flush is skipped. The flush runs even if the
+/// transition request fails; the transition error is reported first.
+pub struct PreemptionHandler {
+    output: Box<dyn FlushControl>,
+    state: Box<dyn StateManager>,
+    shutdown_margin: Duration,
+}
+
+impl PreemptionHandler {
+    /// Creates a handler acting on the given output path and state manager.
+    pub fn new(output: Box<dyn FlushControl>, state: Box<dyn StateManager>) -> Self {
+        Self {
+            output,
+            state,
+            shutdown_margin: DEFAULT_SHUTDOWN_MARGIN,
+        }
+    }
+
+    /// Sets the time kept back from the flush for shutting down after it.
+    pub fn with_shutdown_margin(mut self, margin: Duration) -> Self {
+        self.shutdown_margin = margin;
+        self
+    }
+
+    async fn preempt(&mut self, remaining: Duration) -> Result<(), Error> {
+        let transitioned = self
+            .state
+            .request_state_transition(StateTransition {
+                from_state: CaptureState::Capturing,
+                to_state: CaptureState::ShuttingDown,
+                reason: format!("spot interruption in {}s", remaining.as_secs()),
+            })
+            .await;
+
+        let deadline = remaining.saturating_sub(self.shutdown_margin);
+        let flushed = if deadline.is_zero() {
+            Err(Error::Timeout(format!(
+                "skipped emergency flush: {remaining:?} left is within the {:?} shutdown margin",
+                self.shutdown_margin
+            )))
+        } else {
+            match tokio::time::timeout(deadline, self.output.flush_with_mode(FlushMode::Emergency))
+                .await
+            {
+                Ok(result) => result,
+                Err(_) => Err(Error::Timeout(format!(
+                    "emergency flush did not finish within {deadline:?}"
+                ))),
+            }
+        };
+
+        transitioned?;
+        flushed
+    }
+}
+
+#[async_trait]
+impl EventHandler<CloudEvent> for PreemptionHandler {
+    async fn handle_event(&mut self, event: CloudEvent) -> Result<(), Error> {
+        match event {
+            CloudEvent::Lifecycle(CloudLifecycleEvent::InstancePreempt(remaining)) => {
+                self.preempt(remaining).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::cloud::imds::mock_server;
+    use crate::capture_engine::state::traits::{StateEvent, SystemState};
+    use crate::traits::{HealthCheck, HealthStatus, Lifecycle};
+    use parking_lot::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const NOTICE: &str = r#"{"action":"terminate","time":"2024-09-18T08:22:00Z"}"#;
+
+    fn notice_time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_726_647_720)
+    }
+
+    /// Serves a notice after `quiet_polls` instance-action requests answered 404.
+    async fn mock_imds(quiet_polls: usize, polls: Arc<AtomicUsize>) -> String {
+        mock_server::serve(move |request| {
+            if request.starts_with("PUT ") {
+                return (200, "token".to_string());
+            }
+            if polls.fetch_add(1, Ordering::SeqCst) < quiet_polls {
+                (404, String::new())
+            } else {
+                (200, NOTICE.to_string())
+            }
+        })
+        .await
+    }
+
+    fn monitor(endpoint: String) -> SpotMonitor {
+        SpotMonitor::new(
+            ImdsClient::new().with_endpoint(endpoint),
+            Duration::from_millis(10),
+        )
+    }
+
+    #[derive(Default)]
+    struct Calls {
+        flushes: Vec<FlushMode>,
+        transitions: Vec<(CaptureState, CaptureState)>,
+        order: Vec<&'static str>,
+    }
+
+    struct MockOutput(Arc<Mutex<Calls>>);
+
+    #[async_trait]
+    impl FlushControl for MockOutput {
+        async fn flush_with_mode(&mut self, mode: FlushMode) -> Result<(), Error> {
+            let mut calls = self.0.lock();
+            calls.flushes.push(mode);
+            calls.order.push("flush");
+            Ok(())
+        }
+    }
+
+    struct MockState(Arc<Mutex<Calls>>);
+
+    #[async_trait]
+    impl Lifecycle for MockState {
+        async fn initialize(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn shutdown(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl EventHandler<StateEvent> for MockState {
+        async fn handle_event(&mut self, _event: StateEvent) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl HealthCheck for MockState {
+        fn health_check(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    #[async_trait]
+    impl StateManager for MockState {
+        fn system_state(&self) -> SystemState {
+            unimplemented!()
+        }
+
+        async fn persist_state(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn request_state_transition(
+            &mut self,
+            transition: StateTransition,
+        ) -> Result<(), Error> {
+            let mut calls = self.0.lock();
+            calls
+                .transitions
+                .push((transition.from_state, transition.to_state));
+            calls.order.push("transition");
+            Ok(())
+        }
+
+        async fn handle_pressure_change(
+            &mut self,
+            _pressure_state: crate::capture_engine::state::traits::PressureState,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("2024-09-18T08:22:00Z"), Some(notice_time()));
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(UNIX_EPOCH));
+        assert_eq!(parse_timestamp("2024-09-18 08:22:00"), None);
+    }
+
+    #[tokio::test]
+    async fn test_poll_once_reports_remaining_time() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let monitor = monitor(mock_imds(1, polls).await);
+        let now = notice_time() - Duration::from_secs(120);
+
+        assert_eq!(monitor.poll_once(now).await.unwrap(), None);
+        assert_eq!(
+            monitor.poll_once(now).await.unwrap(),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_emits_preempt_and_stops_polling() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let monitor = monitor(mock_imds(2, polls.clone()).await);
+        let (tx, mut rx) = mpsc::channel(4);
+
+        tokio::time::timeout(Duration::from_secs(5), monitor.run(tx))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            rx.recv().await,
+            Some(CloudEvent::Lifecycle(CloudLifecycleEvent::InstancePreempt(
+                _
+            )))
+        ));
+        assert!(rx.recv().await.is_none());
+        assert_eq!(polls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_when_receiver_dropped() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let monitor = monitor(mock_imds(usize::MAX, polls).await);
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+
+        tokio::time::timeout(Duration::from_secs(5), monitor.run(tx))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_preempt_flushes_and_requests_shutdown() {
+        let calls = Arc::new(Mutex::new(Calls::default()));
+        let mut handler = PreemptionHandler::new(
+            Box::new(MockOutput(calls.clone())),
+            Box::new(MockState(calls.clone())),
+        );
+
+        handler
+            .handle_event(CloudEvent::Lifecycle(CloudLifecycleEvent::InstancePreempt(
+                Duration::from_secs(120),
+            )))
+            .await
+            .unwrap();
+
+        let calls = calls.lock();
+        assert_eq!(calls.flushes, vec![FlushMode::Emergency]);
+        assert_eq!(
+            calls.transitions,
+            vec![(CaptureState::Capturing, CaptureState::ShuttingDown)]
+        );
+        assert_eq!(calls.order, vec!["transition", "flush"]);
+    }
+
+    #[tokio::test]
+    async fn test_notice_within_margin_skips_flush() {
+        let calls = Arc::new(Mutex::new(Calls::default()));
+        let mut handler = PreemptionHandler::new(
+            Box::new(MockOutput(calls.clone())),
+            Box::new(MockState(calls.clone())),
+        )
+        .with_shutdown_margin(Duration::from_secs(10));
+
+        let result = handler
+            .handle_event(CloudEvent::Lifecycle(CloudLifecycleEvent::InstancePreempt(
+                Duration::from_secs(4),
+            )))
+            .await;
+
+        assert!(matches!(result, Err(Error::Timeout(_))));
+        let calls = calls.lock();
+        assert!(calls.flushes.is_empty());
+        assert_eq!(
+            calls.transitions,
+            vec![(CaptureState::Capturing, CaptureState::ShuttingDown)]
+        );
+    }
+}
@@ -4,6 +4,7 @@ use crate::traits::{Error, EventHandler, HealthCheck, Lifecycle, ValidationResul
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::net::IpAddr;
+use std::ops::RangeInclusive;
 use std::time::Duration;
 
 /// Events specific to cloud management.
@@ -64,6 +65,8 @@ pub struct InstanceMetadata {
     pub instance_id: String,
     pub instance_type: String,
     pub availability_zone: String,
+    pub region: String,
+    pub vpc_id: String,
     pub tags: HashMap<String, String>,
 }
 
@@ -74,6 +77,10 @@ pub struct MirrorSessionConfig {
     pub source: String,
     pub target: String,
     pub filter_rules: Vec<FilterRule>,
+    /// VXLAN network identifier of mirrored traffic; None lets the cloud assign one.
+    pub vni: Option<u32>,
+    /// Bytes of each packet to mirror; None mirrors whole packets.
+    pub truncate_length: Option<u32>,
 }
 
 /// Update to a network interface.
@@ -95,6 +102,8 @@ pub struct InstanceLimits {
     pub max_memory_mb: u64,
     pub max_storage_gb: u64,
     pub max_cpu_cores: u32,
+    /// Mirror sessions the instance can receive at once.
+    pub max_mirror_sessions: u32,
 }
 
 /// Capabilities of the network.
@@ -102,6 +111,10 @@ pub struct InstanceLimits {
 pub struct NetworkCapabilities {
     pub max_bandwidth_mbps: u32,
     pub supported_protocols: Vec<String>,
+    /// VXLAN network identifiers a mirror session may use.
+    pub vni_range: RangeInclusive<u32>,
+    /// Truncate lengths a mirror session may request.
+    pub truncate_length_range: RangeInclusive<u32>,
 }
 
 /// Filter rule for mirror sessions.
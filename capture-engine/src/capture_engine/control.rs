@@ -1 +1,6 @@
+pub mod audit;
+#[cfg(feature = "grpc")]
+pub mod grpc_reporter;
+#[cfg(feature = "http_server")]
+pub mod http_server;
 pub mod traits;
@@ -0,0 +1,465 @@
+// control/audit.rs
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use crate::capture_engine::capture::clock::Clock;
+use crate::capture_engine::control::traits::ControlCommand;
+use crate::capture_engine::security::traits::{
+    Action, AuthRequest, AuthzDecision, SecurityManager,
+};
+use crate::traits::Error;
+
+/// Resource name used when authorizing control commands
+pub const CONTROL_RESOURCE: &str = "capture-engine/control";
+
+/// Outcome of a control-plane command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CommandOutcome {
+    Applied,
+    Rejected { reason: String },
+}
+
+/// A single control-plane command recorded in the audit trail
+///
+/// # Fields
+/// * `timestamp` - Milliseconds since the Unix epoch when the command was received
+/// * `identity` - Identity that issued the command
+/// * `authenticated` - Whether the identity was successfully authenticated
+/// * `command` - Command name
+/// * `outcome` - Whether the command was applied or rejected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub identity: String,
+    pub authenticated: bool,
+    pub command: String,
+    pub outcome: CommandOutcome,
+}
+
+impl AuditEntry {
+    /// Checks whether the command was rejected
+    pub fn is_rejected(&self) -> bool {
+        matches!(self.outcome, CommandOutcome::Rejected { .. })
+    }
+}
+
+/// Filter for querying the audit trail; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub identity: Option<String>,
+    pub command: Option<String>,
+    pub rejected_only: bool,
+    pub since: Option<u64>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.identity
+            .as_ref()
+            .is_none_or(|id| *id == entry.identity)
+            && self.command.as_ref().is_none_or(|c| *c == entry.command)
+            && (!self.rejected_only || entry.is_rejected())
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+    }
+}
+
+/// Bounded log of control-plane commands, dropping the oldest entries when full
+#[derive(Debug)]
+pub struct CommandAuditLog {
+    entries: Mutex<VecDeque<AuditEntry>>,
+    capacity: usize,
+}
+
+impl CommandAuditLog {
+    /// Creates an audit log holding at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Appends an entry, evicting the oldest when the log is full
+    pub fn record(&self, entry: AuditEntry) {
+        let mut entries = self.entries.lock();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Returns all retained entries, oldest first
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().iter().cloned().collect()
+    }
+
+    /// Returns the retained entries matching the query, oldest first
+    pub fn query(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        self.entries
+            .lock()
+            .iter()
+            .filter(|entry| query.matches(entry))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the number of retained entries
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    /// Checks whether the log is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().is_empty()
+    }
+}
+
+impl ControlCommand {
+    /// Stable name of the command, used for authorization and auditing
+    pub fn name(&self) -> &'static str {
+        match self {
+            ControlCommand::StartCapture => "start_capture",
+            ControlCommand::StopCapture => "stop_capture",
+            ControlCommand::UpdateFilters(_) => "update_filters",
+            ControlCommand::Pause => "pause",
+            ControlCommand::Resume => "resume",
+        }
+    }
+}
+
+/// Authenticates, authorizes and applies control commands, auditing every attempt
+pub struct AuditedCommandHandler {
+    security: Arc<dyn SecurityManager>,
+    audit_log: Arc<CommandAuditLog>,
+    clock: Arc<dyn Clock>,
+}
+
+impl AuditedCommandHandler {
+    /// Creates a handler that records into the given audit log
+    pub fn new(
+        security: Arc<dyn SecurityManager>,
+        audit_log: Arc<CommandAuditLog>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            security,
+            audit_log,
+            clock,
+        }
+    }
+
+    /// Gets the audit log this handler records into
+    pub fn audit_log(&self) -> &Arc<CommandAuditLog> {
+        &self.audit_log
+    }
+
+    /// Handles a command, applying it only if the requester is authenticated and authorized
+    ///
+    /// The command is recorded in the audit trail whether it is applied or rejected.
+    pub async fn handle<F>(
+        &self,
+        request: AuthRequest,
+        command: ControlCommand,
+        apply: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(ControlCommand) -> Result<(), Error>,
+    {
+        let timestamp = self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let identity = request.identity.id.clone();
+        let name = command.name();
+
+        let token = match self.security.authenticate(request).await {
+            Ok(token) => token,
+            Err(e) => {
+                self.record(timestamp, identity, false, name, Err(e.to_string()));
+                return Err(e);
+            }
+        };
+
+        let action = Action {
+            resource: CONTROL_RESOURCE.to_string(),
+            operation: name.to_string(),
+            context: HashMap::new(),
+        };
+        let result = match self.security.authorize(&token, &action).await {
+            Ok(AuthzDecision::Allow) => apply(command),
+            Ok(AuthzDecision::Deny { reason }) => Err(Error::Authorization(reason)),
+            Err(e) => Err(e),
+        };
+
+        let outcome = result.as_ref().map(|_| ()).map_err(|e| e.to_string());
+        self.record(timestamp, identity, true, name, outcome);
+        result
+    }
+
+    fn record(
+        &self,
+        timestamp: u64,
+        identity: String,
+        authenticated: bool,
+        command: &str,
+        outcome: Result<(), String>,
+    ) {
+        self.audit_log.record(AuditEntry {
+            timestamp,
+            identity,
+            authenticated,
+            command: command.to_string(),
+            outcome: match outcome {
+                Ok(()) => CommandOutcome::Applied,
+                Err(reason) => CommandOutcome::Rejected { reason },
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::clock::ManualClock;
+    use crate::capture_engine::output::manifest::OutputManifest;
+    use crate::capture_engine::security::traits::{
+        AuthContext, AuthToken, Credentials, Identity, SecurityAlert, SecurityEvent, SecurityPolicy,
+    };
+    use crate::traits::{EventHandler, HealthCheck, HealthStatus, Lifecycle};
+    use async_trait::async_trait;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+
+    // Authenticates "operator" and "viewer"; only "operator" may issue commands
+    struct TestSecurity;
+
+    #[async_trait]
+    impl Lifecycle for TestSecurity {
+        async fn initialize(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn shutdown(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl EventHandler<SecurityEvent> for TestSecurity {
+        async fn handle_event(&mut self, _event: SecurityEvent) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl HealthCheck for TestSecurity {
+        fn health_check(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    #[async_trait]
+    impl SecurityManager for TestSecurity {
+        async fn authenticate(&self, request: AuthRequest) -> Result<AuthToken, Error> {
+            match request.identity.id.as_str() {
+                "operator" | "viewer" => Ok(AuthToken {
+                    token: request.identity.id.clone(),
+                    expires_at: u64::MAX,
+                    scopes: vec![],
+                    issued_at: 0,
+                    issuer: "test".to_string(),
+                }),
+                _ => Err(Error::Authentication("unknown identity".to_string())),
+            }
+        }
+
+        async fn authorize(
+            &self,
+            token: &AuthToken,
+            _action: &Action,
+        ) -> Result<AuthzDecision, Error> {
+            if token.token == "operator" {
+                Ok(AuthzDecision::Allow)
+            } else {
+                Ok(AuthzDecision::Deny {
+                    reason: "read-only identity".to_string(),
+                })
+            }
+        }
+
+        async fn validate_identity(&self, _identity: &Identity) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn continuous_verification(&self) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn apply_policy(&mut self, _policy: SecurityPolicy) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn handle_security_alert(&mut self, _alert: SecurityAlert) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn rotate_keys(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn request(id: &str) -> AuthRequest {
+        AuthRequest {
+            identity: Identity {
+                id: id.to_string(),
+                attributes: HashMap::new(),
+            },
+            credentials: Credentials::Token("secret".to_string()),
+            context: AuthContext {
+                source_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                user_agent: None,
+                device_info: None,
+            },
+        }
+    }
+
+    fn handler(capacity: usize) -> (AuditedCommandHandler, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_000)));
+        let handler = AuditedCommandHandler::new(
+            Arc::new(TestSecurity),
+            Arc::new(CommandAuditLog::new(capacity)),
+            clock.clone(),
+        );
+        (handler, clock)
+    }
+
+    #[tokio::test]
+    async fn test_applied_command_is_audited() {
+        let (handler, _) = handler(10);
+        let mut applied = false;
+        handler
+            .handle(request("operator"), ControlCommand::StartCapture, |_| {
+                applied = true;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert!(applied);
+        let entries = handler.audit_log().entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].identity, "operator");
+        assert!(entries[0].authenticated);
+        assert_eq!(entries[0].command, "start_capture");
+        assert_eq!(entries[0].outcome, CommandOutcome::Applied);
+        assert_eq!(entries[0].timestamp, 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_command_is_rejected_and_audited() {
+        let (handler, _) = handler(10);
+        let result = handler
+            .handle(request("viewer"), ControlCommand::StopCapture, |_| {
+                panic!("unauthorized command must not be applied")
+            })
+            .await;
+
+        assert!(matches!(result, Err(Error::Authorization(_))));
+        let entry = &handler.audit_log().entries()[0];
+        assert_eq!(entry.identity, "viewer");
+        assert!(entry.authenticated);
+        assert!(matches!(
+            &entry.outcome,
+            CommandOutcome::Rejected { reason } if reason.contains("read-only")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_command_is_audited() {
+        let (handler, _) = handler(10);
+        let result = handler
+            .handle(request("intruder"), ControlCommand::Pause, |_| Ok(()))
+            .await;
+
+        assert!(matches!(result, Err(Error::Authentication(_))));
+        let entry = &handler.audit_log().entries()[0];
+        assert_eq!(entry.identity, "intruder");
+        assert!(!entry.authenticated);
+        assert!(entry.is_rejected());
+    }
+
+    #[tokio::test]
+    async fn test_failed_apply_is_recorded_as_rejected() {
+        let (handler, _) = handler(10);
+        let result = handler
+            .handle(request("operator"), ControlCommand::Resume, |_| {
+                Err(Error::Runtime("not paused".to_string()))
+            })
+            .await;
+
+        assert!(result.is_err());
+        let entry = &handler.audit_log().entries()[0];
+        assert!(matches!(
+            &entry.outcome,
+            CommandOutcome::Rejected { reason } if reason.contains("not paused")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_is_bounded_and_queryable() {
+        let (handler, clock) = handler(3);
+        for id in ["operator", "viewer", "operator", "viewer"] {
+            clock.advance(Duration::from_secs(1));
+            let _ = handler
+                .handle(request(id), ControlCommand::StartCapture, |_| Ok(()))
+                .await;
+        }
+
+        let log = handler.audit_log();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.entries()[0].timestamp, 1_002_000);
+
+        let rejected = log.query(&AuditQuery {
+            rejected_only: true,
+            ..Default::default()
+        });
+        assert_eq!(rejected.len(), 2);
+        assert!(rejected.iter().all(|e| e.identity == "viewer"));
+
+        let operator_since = log.query(&AuditQuery {
+            identity: Some("operator".to_string()),
+            since: Some(1_003_000),
+            ..Default::default()
+        });
+        assert_eq!(operator_since.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_audit_trail_written_to_manifest() {
+        let (handler, _) = handler(10);
+        let _ = handler
+            .handle(request("viewer"), ControlCommand::StopCapture, |_| Ok(()))
+            .await;
+
+        let manifest =
+            OutputManifest::new("archive").with_command_audit(handler.audit_log().entries());
+        let restored = OutputManifest::from_json(&manifest.to_json().unwrap()).unwrap();
+        assert_eq!(restored.command_audit, handler.audit_log().entries());
+    }
+
+    #[test]
+    fn test_audit_entry_serializes_outcome() {
+        let entry = AuditEntry {
+            timestamp: 1,
+            identity: "operator".to_string(),
+            authenticated: true,
+            command: "pause".to_string(),
+            outcome: CommandOutcome::Rejected {
+                reason: "denied".to_string(),
+            },
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains("\"status\":\"rejected\""));
+        assert_eq!(serde_json::from_str::<AuditEntry>(&json).unwrap(), entry);
+    }
+}
@@ -0,0 +1,533 @@
+// control/grpc_reporter.rs
+//! `StateReporter` that sends state changes to the control plane over gRPC.
+//!
+//! Each `StateChangeEvent` becomes a `StateChangeMessage` sent with the unary
+//! `sparktrap.control.v1.StateReporting/ReportState` call. States are sent by their `Debug`
+//! name, which for the engine's state enums is the variant name.
+//!
+//! The channel is opened on first use. When connecting fails, further attempts are held off with
+//! exponential backoff; reports made in the meantime fail at once with a retryable
+//! `Network(Timeout)` error, so the `StateSync` retry loop paces reconnection rather than
+//! blocking on it. A report that fails because the transport broke drops the channel, and the
+//! next report reconnects.
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use tonic::client::Grpc;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic::{Code, Request, Status};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, NetworkErrorKind, RuntimeErrorKind,
+    SecurityErrorKind,
+};
+use crate::capture_engine::capture::state_sync::{StateChangeEvent, StateReporter};
+
+/// gRPC service that receives state reports.
+pub const STATE_REPORTING_SERVICE: &str = "sparktrap.control.v1.StateReporting";
+/// Path of the unary report call.
+pub const REPORT_STATE_PATH: &str = "/sparktrap.control.v1.StateReporting/ReportState";
+
+/// Wire form of a state change event.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StateChangeMessage {
+    #[prost(string, tag = "1")]
+    pub entity_id: String,
+    #[prost(string, tag = "2")]
+    pub from_state: String,
+    #[prost(string, tag = "3")]
+    pub to_state: String,
+    /// Event time as nanoseconds since the Unix epoch.
+    #[prost(uint64, tag = "4")]
+    pub timestamp_unix_nanos: u64,
+    #[prost(string, optional, tag = "5")]
+    pub reason: Option<String>,
+    /// Stable name of the transition's `TransitionReason`.
+    #[prost(string, optional, tag = "6")]
+    pub category: Option<String>,
+    #[prost(map = "string, string", tag = "7")]
+    pub metadata: HashMap<String, String>,
+}
+
+/// Empty acknowledgement returned by the control plane.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ReportAck {}
+
+impl StateChangeMessage {
+    /// Converts an event to its wire form.
+    pub fn from_event<S: Clone + Debug>(event: &StateChangeEvent<S>) -> Self {
+        let transition = event.transition();
+        Self {
+            entity_id: event.entity_id().clone(),
+            from_state: format!("{:?}", transition.from()),
+            to_state: format!("{:?}", transition.to()),
+            timestamp_unix_nanos: event
+                .timestamp()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or(0),
+            reason: transition.reason().cloned(),
+            category: transition
+                .category()
+                .map(|category| category.as_str().to_string()),
+            metadata: event.metadata().clone(),
+        }
+    }
+}
+
+/// TLS settings for the control plane channel, as PEM.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    /// CA bundle to verify the server with; the system roots are used when None.
+    pub ca_certificate: Option<Vec<u8>>,
+    /// Client certificate and key for mutual TLS.
+    pub client_certificate: Option<Vec<u8>>,
+    pub client_key: Option<Vec<u8>>,
+    /// Name to verify the server certificate against, when it differs from the endpoint host.
+    pub domain_name: Option<String>,
+}
+
+impl TlsConfig {
+    pub(crate) fn to_client_config(&self) -> Result<ClientTlsConfig, CaptureError> {
+        let mut config = ClientTlsConfig::new();
+        config = match &self.ca_certificate {
+            Some(pem) => config.ca_certificate(Certificate::from_pem(pem)),
+            None => config.with_native_roots(),
+        };
+        match (&self.client_certificate, &self.client_key) {
+            (Some(cert), Some(key)) => config = config.identity(Identity::from_pem(cert, key)),
+            (None, None) => {}
+            _ => {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::MissingRequired),
+                    "Client certificate and key must be set together",
+                ))
+            }
+        }
+        if let Some(domain_name) = &self.domain_name {
+            config = config.domain_name(domain_name.clone());
+        }
+        Ok(config)
+    }
+}
+
+/// gRPC reporter settings.
+#[derive(Debug, Clone)]
+pub struct GrpcReporterConfig {
+    /// Control plane URL, e.g. `https://control.example:8443`.
+    pub endpoint: String,
+    /// TLS settings; plaintext when None.
+    pub tls: Option<TlsConfig>,
+    /// Time allowed to open the channel.
+    pub connect_timeout: Duration,
+    /// Time allowed for each report.
+    pub request_timeout: Duration,
+    /// Hold-off after the first failed connection attempt.
+    pub initial_backoff: Duration,
+    /// Longest hold-off between connection attempts.
+    pub max_backoff: Duration,
+}
+
+impl GrpcReporterConfig {
+    /// Settings for an endpoint with default timeouts and backoff.
+    pub fn new(endpoint: impl Into<String>, tls: Option<TlsConfig>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            tls,
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Channel state shared by concurrent reports.
+struct Connection {
+    channel: Option<Channel>,
+    backoff: Duration,
+    retry_at: Option<Instant>,
+}
+
+/// Reports state changes to the control plane over gRPC.
+pub struct GrpcStateReporter {
+    endpoint: Endpoint,
+    config: GrpcReporterConfig,
+    connection: Mutex<Connection>,
+}
+
+impl GrpcStateReporter {
+    /// Creates a reporter; the channel is opened on the first report.
+    ///
+    /// Fails with a configuration error if the endpoint URL or TLS settings are invalid.
+    pub fn new(config: GrpcReporterConfig) -> Result<Self, CaptureError> {
+        let mut endpoint = Endpoint::from_shared(config.endpoint.clone())
+            .map_err(|e| {
+                CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    &format!("Invalid control plane endpoint '{}'", config.endpoint),
+                )
+                .with_source(e)
+            })?
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout);
+        if let Some(tls) = &config.tls {
+            endpoint = endpoint.tls_config(tls.to_client_config()?).map_err(|e| {
+                CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    "Invalid control plane TLS configuration",
+                )
+                .with_source(e)
+            })?;
+        }
+        Ok(Self {
+            endpoint,
+            connection: Mutex::new(Connection {
+                channel: None,
+                backoff: config.initial_backoff,
+                retry_at: None,
+            }),
+            config,
+        })
+    }
+
+    /// Whether a channel is currently open.
+    pub fn is_connected(&self) -> bool {
+        self.connection.lock().channel.is_some()
+    }
+
+    /// Sends one report.
+    pub async fn report(&self, message: StateChangeMessage) -> Result<(), CaptureError> {
+        let mut client = Grpc::new(self.channel().await?);
+        client.ready().await.map_err(|e| {
+            self.disconnect();
+            network_timeout(&format!(
+                "Control plane channel to {} is not ready",
+                self.config.endpoint
+            ))
+            .with_source(e)
+        })?;
+        let result = client
+            .unary::<_, ReportAck, _>(
+                Request::new(message),
+                PathAndQuery::from_static(REPORT_STATE_PATH),
+                ProstCodec::default(),
+            )
+            .await;
+        result.map(|_| ()).map_err(|status| {
+            if is_transport_failure(status.code()) {
+                self.disconnect();
+            }
+            status_error(status)
+        })
+    }
+
+    /// Returns the open channel, connecting unless backing off.
+    async fn channel(&self) -> Result<Channel, CaptureError> {
+        {
+            let connection = self.connection.lock();
+            if let Some(channel) = &connection.channel {
+                return Ok(channel.clone());
+            }
+            let now = Instant::now();
+            if let Some(retry_at) = connection.retry_at.filter(|retry_at| now < *retry_at) {
+                return Err(*network_timeout(&format!(
+                    "Reconnecting to {} in {:?}",
+                    self.config.endpoint,
+                    retry_at - now
+                )));
+            }
+        }
+
+        let connected = self.endpoint.connect().await;
+        let mut connection = self.connection.lock();
+        match connected {
+            Ok(channel) => {
+                connection.channel = Some(channel.clone());
+                connection.backoff = self.config.initial_backoff;
+                connection.retry_at = None;
+                Ok(channel)
+            }
+            Err(e) => {
+                connection.retry_at = Some(Instant::now() + connection.backoff);
+                connection.backoff = (connection.backoff * 2).min(self.config.max_backoff);
+                Err(network_timeout(&format!(
+                    "Failed to connect to control plane at {}",
+                    self.config.endpoint
+                ))
+                .with_source(e))
+            }
+        }
+    }
+
+    /// Drops a broken channel so the next report reconnects.
+    fn disconnect(&self) {
+        self.connection.lock().channel = None;
+    }
+}
+
+impl<S: Clone + Debug + Send + Sync> StateReporter<S> for GrpcStateReporter {
+    fn report_state<'a>(
+        &'a self,
+        event: &'a StateChangeEvent<S>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), CaptureError>> + Send + 'a>> {
+        Box::pin(self.report(StateChangeMessage::from_event(event)))
+    }
+}
+
+fn network_timeout(message: &str) -> Box<CaptureError> {
+    CaptureError::new(
+        CaptureErrorKind::Network(NetworkErrorKind::Timeout),
+        message,
+    )
+}
+
+/// Statuses that mean the channel or server is unusable rather than the report rejected.
+fn is_transport_failure(code: Code) -> bool {
+    matches!(
+        code,
+        Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled | Code::Unknown
+    )
+}
+
+/// Maps a call status to an error the `StateSync` retry loop can classify.
+fn status_error(status: Status) -> CaptureError {
+    let kind = match status.code() {
+        code if is_transport_failure(code) => CaptureErrorKind::Network(NetworkErrorKind::Timeout),
+        Code::ResourceExhausted | Code::Aborted => {
+            CaptureErrorKind::Network(NetworkErrorKind::Timeout)
+        }
+        Code::Unauthenticated => {
+            CaptureErrorKind::Security(SecurityErrorKind::AuthenticationFailed)
+        }
+        Code::PermissionDenied => CaptureErrorKind::Security(SecurityErrorKind::AccessDenied),
+        Code::Unimplemented => CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+        Code::Internal | Code::DataLoss => {
+            CaptureErrorKind::Runtime(RuntimeErrorKind::OperationFailed)
+        }
+        _ => CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+    };
+    let message = format!(
+        "Control plane rejected state report ({:?}): {}",
+        status.code(),
+        status.message()
+    );
+    CaptureError::new(kind, &message).with_source(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::state_machine::{StateTransition, TransitionReason};
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use tonic::codegen::{http, Body, BoxFuture, Context, Poll, Service, StdError};
+    use tonic::server::{Grpc as ServerGrpc, NamedService, UnaryService};
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum LinkState {
+        Down,
+        Up,
+    }
+
+    /// In-process control plane that records reports; entity ids ending in a status name are
+    /// answered with that status.
+    #[derive(Clone, Default)]
+    struct MockControlPlane {
+        received: Arc<Mutex<Vec<StateChangeMessage>>>,
+    }
+
+    impl NamedService for MockControlPlane {
+        const NAME: &'static str = STATE_REPORTING_SERVICE;
+    }
+
+    struct ReportStateSvc(MockControlPlane);
+
+    impl UnaryService<StateChangeMessage> for ReportStateSvc {
+        type Response = ReportAck;
+        type Future = BoxFuture<tonic::Response<ReportAck>, Status>;
+
+        fn call(&mut self, request: tonic::Request<StateChangeMessage>) -> Self::Future {
+            let received = self.0.received.clone();
+            Box::pin(async move {
+                let message = request.into_inner();
+                if message.entity_id.ends_with("denied") {
+                    return Err(Status::permission_denied("not allowed"));
+                }
+                if message.entity_id.ends_with("unavailable") {
+                    return Err(Status::unavailable("draining"));
+                }
+                received.lock().push(message);
+                Ok(tonic::Response::new(ReportAck {}))
+            })
+        }
+    }
+
+    impl<B> Service<http::Request<B>> for MockControlPlane
+    where
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<B>) -> Self::Future {
+            if request.uri().path() != REPORT_STATE_PATH {
+                return Box::pin(async { Ok(Status::unimplemented("unknown method").into_http()) });
+            }
+            let svc = ReportStateSvc(self.clone());
+            Box::pin(async move {
+                let mut grpc = ServerGrpc::new(ProstCodec::default());
+                Ok(grpc.unary(svc, request).await)
+            })
+        }
+    }
+
+    async fn serve(listener: TcpListener) -> (MockControlPlane, oneshot::Sender<()>) {
+        let mock = MockControlPlane::default();
+        let (stop, stopped) = oneshot::channel();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        let router = Server::builder().add_service(mock.clone());
+        tokio::spawn(async move {
+            let _ = router
+                .serve_with_incoming_shutdown(incoming, async {
+                    let _ = stopped.await;
+                })
+                .await;
+        });
+        (mock, stop)
+    }
+
+    fn reporter(addr: std::net::SocketAddr) -> GrpcStateReporter {
+        let mut config = GrpcReporterConfig::new(format!("http://{}", addr), None);
+        config.connect_timeout = Duration::from_secs(1);
+        config.request_timeout = Duration::from_secs(2);
+        config.initial_backoff = Duration::from_millis(200);
+        GrpcStateReporter::new(config).unwrap()
+    }
+
+    fn event(entity_id: &str) -> StateChangeEvent<LinkState> {
+        let transition = StateTransition::new(
+            LinkState::Down,
+            LinkState::Up,
+            Some("carrier detected".to_string()),
+        )
+        .with_category(TransitionReason::Recovery);
+        let metadata = HashMap::from([("interface".to_string(), "eth0".to_string())]);
+        StateChangeEvent::new(entity_id.to_string(), transition, metadata)
+    }
+
+    #[tokio::test]
+    async fn test_reports_event_to_control_plane() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (mock, _stop) = serve(listener).await;
+        let reporter = reporter(addr);
+
+        let event = event("engine-1");
+        reporter.report_state(&event).await.unwrap();
+        assert!(reporter.is_connected());
+
+        let received = mock.received.lock().clone();
+        assert_eq!(received.len(), 1);
+        let message = &received[0];
+        assert_eq!(message.entity_id, "engine-1");
+        assert_eq!(message.from_state, "Down");
+        assert_eq!(message.to_state, "Up");
+        assert_eq!(message.reason.as_deref(), Some("carrier detected"));
+        assert_eq!(message.category.as_deref(), Some("recovery"));
+        assert_eq!(message.metadata["interface"], "eth0");
+        assert!(message.timestamp_unix_nanos > 0);
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_with_backoff() {
+        // Reserve an address with nothing listening on it
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let reporter = reporter(addr);
+        let event = event("engine-1");
+
+        let err = reporter.report_state(&event).await.unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Network(NetworkErrorKind::Timeout)
+        ));
+        assert!(err.is_retryable());
+
+        // Held off without another connection attempt
+        let err = reporter.report_state(&event).await.unwrap_err();
+        assert!(err.to_string().contains("Reconnecting"), "{}", err);
+
+        let (mock, _stop) = serve(TcpListener::bind(addr).await.unwrap()).await;
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        reporter.report_state(&event).await.unwrap();
+        assert_eq!(mock.received.lock().len(), 1);
+        assert_eq!(
+            reporter.connection.lock().backoff,
+            Duration::from_millis(200)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_mapped_to_error_kind() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (_mock, _stop) = serve(listener).await;
+        let reporter = reporter(addr);
+
+        let err = reporter
+            .report_state(&event("engine-denied"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Security(SecurityErrorKind::AccessDenied)
+        ));
+        assert!(!err.is_retryable());
+        assert!(reporter.is_connected());
+
+        let err = reporter
+            .report_state(&event("engine-unavailable"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Network(NetworkErrorKind::Timeout)
+        ));
+        assert!(!reporter.is_connected());
+    }
+
+    #[test]
+    fn test_invalid_configuration_rejected() {
+        assert!(GrpcStateReporter::new(GrpcReporterConfig::new("not a url", None)).is_err());
+
+        let tls = TlsConfig {
+            client_certificate: Some(b"cert".to_vec()),
+            ..Default::default()
+        };
+        let err = GrpcStateReporter::new(GrpcReporterConfig::new("https://localhost", Some(tls)))
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::MissingRequired)
+        ));
+    }
+}
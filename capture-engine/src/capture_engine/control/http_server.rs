@@ -0,0 +1,574 @@
+// control/http_server.rs
+//! Embedded HTTP server for metrics, probes and diagnostics.
+//!
+//! The server runs on its own thread with a single-threaded Tokio runtime, so request handling
+//! never competes with the capture runtime. Endpoints:
+//!
+//! - `GET /metrics` - telemetry in the Prometheus text format
+//! - `GET /healthz` - liveness probe, 503 when unhealthy
+//! - `GET /readyz` - readiness probe, 503 when not ready
+//! - `GET /diagnostics` - diagnostics report as JSON
+//!
+//! Requests are unauthenticated unless a `SecurityManager` is configured, in which case each
+//! request must carry an `Authorization: Bearer <token>` header that the manager authenticates
+//! and authorizes for the endpoint.
+use std::net::{SocketAddr, TcpListener as StdTcpListener};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+use crate::capture_engine::security::traits::{
+    Action, AuthContext, AuthRequest, AuthzDecision, Credentials, Identity, SecurityManager,
+};
+use crate::capture_engine::telemetry::prometheus;
+use crate::capture_engine::telemetry::traits::TelemetryData;
+use crate::traits::{Error, HealthStatus};
+
+/// Resource name used when authorizing diagnostics requests
+pub const DIAGNOSTICS_RESOURCE: &str = "capture-engine/diagnostics";
+
+/// Identity presented to the security manager for bearer-token requests
+const BEARER_IDENTITY: &str = "http-bearer";
+/// Largest request head accepted
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+/// Time allowed for a client to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Source of the data served by the diagnostics server.
+pub trait DiagnosticsProvider: Send + Sync {
+    /// Current telemetry, exported on `/metrics`.
+    fn metrics(&self) -> Vec<TelemetryData>;
+
+    /// Liveness, exported on `/healthz`.
+    fn liveness(&self) -> HealthStatus;
+
+    /// Readiness, exported on `/readyz`.
+    fn readiness(&self) -> HealthStatus;
+
+    /// Diagnostics report, exported on `/diagnostics`.
+    fn diagnostics(&self) -> serde_json::Value;
+}
+
+/// Diagnostics server settings.
+#[derive(Clone)]
+pub struct HttpServerConfig {
+    /// Address to listen on; port 0 picks a free port.
+    pub bind: SocketAddr,
+    /// Security manager for bearer-token auth, None to serve without auth.
+    pub security: Option<Arc<dyn SecurityManager>>,
+}
+
+impl HttpServerConfig {
+    /// Creates a config serving without auth on `bind`.
+    pub fn new(bind: SocketAddr) -> Self {
+        Self {
+            bind,
+            security: None,
+        }
+    }
+
+    /// Requires bearer tokens accepted by `security` on every request.
+    pub fn with_bearer_auth(mut self, security: Arc<dyn SecurityManager>) -> Self {
+        self.security = Some(security);
+        self
+    }
+}
+
+/// A running diagnostics server; stopped on `shutdown` or drop.
+pub struct DiagnosticsServer {
+    local_addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DiagnosticsServer {
+    /// Binds the configured address and starts serving on a dedicated thread.
+    pub fn start(
+        config: HttpServerConfig,
+        provider: Arc<dyn DiagnosticsProvider>,
+    ) -> Result<Self, Error> {
+        let listener = StdTcpListener::bind(config.bind).map_err(Error::IO)?;
+        listener.set_nonblocking(true).map_err(Error::IO)?;
+        let local_addr = listener.local_addr().map_err(Error::IO)?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(Error::IO)?;
+        let (shutdown, stop) = oneshot::channel();
+        let security = config.security;
+        let thread = std::thread::Builder::new()
+            .name("diagnostics-http".to_string())
+            .spawn(move || runtime.block_on(serve(listener, provider, security, stop)))
+            .map_err(Error::IO)?;
+
+        Ok(Self {
+            local_addr,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    /// Address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stops the server and waits for its thread to exit.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for DiagnosticsServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A response ready to be written.
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn new(status: u16, content_type: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::new(
+            status,
+            "application/json",
+            json!({ "error": message }).to_string(),
+        )
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len()
+        );
+        if self.status == 401 {
+            head.push_str("WWW-Authenticate: Bearer\r\n");
+        }
+        head.push_str("\r\n");
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(self.body.as_bytes());
+        bytes
+    }
+}
+
+/// The parts of a request the server uses.
+struct Request {
+    method: String,
+    path: String,
+    bearer: Option<String>,
+    user_agent: Option<String>,
+}
+
+async fn serve(
+    listener: StdTcpListener,
+    provider: Arc<dyn DiagnosticsProvider>,
+    security: Option<Arc<dyn SecurityManager>>,
+    mut stop: oneshot::Receiver<()>,
+) {
+    let Ok(listener) = TcpListener::from_std(listener) else {
+        return;
+    };
+    loop {
+        tokio::select! {
+            _ = &mut stop => break,
+            accepted = listener.accept() => {
+                let Ok((stream, peer)) = accepted else {
+                    continue;
+                };
+                let provider = Arc::clone(&provider);
+                let security = security.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, peer, provider, security).await;
+                });
+            }
+        }
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    provider: Arc<dyn DiagnosticsProvider>,
+    security: Option<Arc<dyn SecurityManager>>,
+) -> std::io::Result<()> {
+    let response = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(Some(request))) => respond(request, peer, provider.as_ref(), security).await,
+        Ok(Ok(None)) => Response::error(400, "malformed request"),
+        Ok(Err(e)) => return Err(e),
+        Err(_) => Response::error(408, "request timed out"),
+    };
+    stream.write_all(&response.to_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<Request>> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_REQUEST_HEAD {
+            return Ok(None);
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+    Ok(parse_request(&String::from_utf8_lossy(&buf)))
+}
+
+fn parse_request(head: &str) -> Option<Request> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let mut bearer = None;
+    let mut user_agent = None;
+    for line in lines.take_while(|line| !line.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("authorization") {
+            bearer = value
+                .strip_prefix("Bearer ")
+                .map(|token| token.trim().to_string());
+        } else if name.eq_ignore_ascii_case("user-agent") {
+            user_agent = Some(value.to_string());
+        }
+    }
+
+    Some(Request {
+        method,
+        path,
+        bearer,
+        user_agent,
+    })
+}
+
+async fn respond(
+    request: Request,
+    peer: SocketAddr,
+    provider: &dyn DiagnosticsProvider,
+    security: Option<Arc<dyn SecurityManager>>,
+) -> Response {
+    let endpoint = match request.path.as_str() {
+        "/metrics" => "metrics",
+        "/healthz" => "healthz",
+        "/readyz" => "readyz",
+        "/diagnostics" => "diagnostics",
+        _ => return Response::error(404, "not found"),
+    };
+    if request.method != "GET" {
+        return Response::error(405, "method not allowed");
+    }
+    if let Some(security) = security {
+        if let Err(response) = authorize(&request, peer, endpoint, security.as_ref()).await {
+            return response;
+        }
+    }
+
+    match endpoint {
+        "metrics" => Response::new(
+            200,
+            "text/plain; version=0.0.4",
+            prometheus::render(&provider.metrics()),
+        ),
+        "healthz" => probe(provider.liveness()),
+        "readyz" => probe(provider.readiness()),
+        _ => match serde_json::to_string_pretty(&provider.diagnostics()) {
+            Ok(body) => Response::new(200, "application/json", body),
+            Err(_) => Response::error(500, "failed to serialize diagnostics"),
+        },
+    }
+}
+
+async fn authorize(
+    request: &Request,
+    peer: SocketAddr,
+    endpoint: &str,
+    security: &dyn SecurityManager,
+) -> Result<(), Response> {
+    let Some(bearer) = &request.bearer else {
+        return Err(Response::error(401, "bearer token required"));
+    };
+    let auth = AuthRequest {
+        identity: Identity {
+            id: BEARER_IDENTITY.to_string(),
+            attributes: Default::default(),
+        },
+        credentials: Credentials::Token(bearer.clone()),
+        context: AuthContext {
+            source_ip: peer.ip(),
+            user_agent: request.user_agent.clone(),
+            device_info: None,
+        },
+    };
+    let token = security
+        .authenticate(auth)
+        .await
+        .map_err(|_| Response::error(401, "invalid bearer token"))?;
+
+    let action = Action {
+        resource: DIAGNOSTICS_RESOURCE.to_string(),
+        operation: endpoint.to_string(),
+        context: Default::default(),
+    };
+    match security.authorize(&token, &action).await {
+        Ok(AuthzDecision::Allow) => Ok(()),
+        Ok(AuthzDecision::Deny { reason }) => Err(Response::error(403, &reason)),
+        Err(_) => Err(Response::error(403, "authorization failed")),
+    }
+}
+
+fn probe(status: HealthStatus) -> Response {
+    let (code, body) = match status {
+        HealthStatus::Healthy => (200, json!({ "status": "healthy" })),
+        HealthStatus::Degraded(reason) => (200, json!({ "status": "degraded", "reason": reason })),
+        HealthStatus::Unhealthy(reason) => {
+            (503, json!({ "status": "unhealthy", "reason": reason }))
+        }
+    };
+    Response::new(code, "application/json", body.to_string())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::security::traits::{
+        AuthToken, SecurityAlert, SecurityEvent, SecurityPolicy,
+    };
+    use crate::capture_engine::telemetry::traits::{MetricType, MetricUnit, MetricValue};
+    use crate::traits::{EventHandler, HealthCheck, Lifecycle};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::{Ipv4Addr, TcpStream as StdTcpStream};
+
+    struct TestProvider {
+        ready: bool,
+    }
+
+    impl DiagnosticsProvider for TestProvider {
+        fn metrics(&self) -> Vec<TelemetryData> {
+            vec![TelemetryData {
+                timestamp: 0,
+                name: "capture.packets".to_string(),
+                description: Some("Packets captured".to_string()),
+                unit: Some(MetricUnit::Count),
+                metric_type: MetricType::Counter,
+                value: MetricValue::Integer(42),
+                attributes: HashMap::new(),
+                resource: None,
+            }]
+        }
+
+        fn liveness(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+
+        fn readiness(&self) -> HealthStatus {
+            if self.ready {
+                HealthStatus::Healthy
+            } else {
+                HealthStatus::Unhealthy("interfaces not attached".to_string())
+            }
+        }
+
+        fn diagnostics(&self) -> serde_json::Value {
+            json!({ "session_id": "s-1", "recent_errors": [] })
+        }
+    }
+
+    // Accepts tokens "operator" and "viewer"; only "operator" may read diagnostics
+    struct TestSecurity;
+
+    #[async_trait]
+    impl Lifecycle for TestSecurity {
+        async fn initialize(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn shutdown(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl EventHandler<SecurityEvent> for TestSecurity {
+        async fn handle_event(&mut self, _event: SecurityEvent) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl HealthCheck for TestSecurity {
+        fn health_check(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    #[async_trait]
+    impl SecurityManager for TestSecurity {
+        async fn authenticate(&self, request: AuthRequest) -> Result<AuthToken, Error> {
+            match request.credentials {
+                Credentials::Token(token) if token == "operator" || token == "viewer" => {
+                    Ok(AuthToken {
+                        token,
+                        expires_at: u64::MAX,
+                        scopes: vec![],
+                        issued_at: 0,
+                        issuer: "test".to_string(),
+                    })
+                }
+                _ => Err(Error::Authentication("unknown token".to_string())),
+            }
+        }
+
+        async fn authorize(
+            &self,
+            token: &AuthToken,
+            action: &Action,
+        ) -> Result<AuthzDecision, Error> {
+            if token.token == "operator" || action.operation != "diagnostics" {
+                Ok(AuthzDecision::Allow)
+            } else {
+                Ok(AuthzDecision::Deny {
+                    reason: "diagnostics require operator".to_string(),
+                })
+            }
+        }
+
+        async fn validate_identity(&self, _identity: &Identity) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn continuous_verification(&self) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn apply_policy(&mut self, _policy: SecurityPolicy) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn handle_security_alert(&mut self, _alert: SecurityAlert) -> Result<(), Error> {
+            Ok(())
+        }
+        async fn rotate_keys(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn start(ready: bool, security: Option<Arc<dyn SecurityManager>>) -> DiagnosticsServer {
+        let mut config = HttpServerConfig::new(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+        if let Some(security) = security {
+            config = config.with_bearer_auth(security);
+        }
+        DiagnosticsServer::start(config, Arc::new(TestProvider { ready })).unwrap()
+    }
+
+    fn get(server: &DiagnosticsServer, path: &str, token: Option<&str>) -> (u16, String) {
+        let mut stream = StdTcpStream::connect(server.local_addr()).unwrap();
+        let auth = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+            path, auth
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn test_endpoints_without_auth() {
+        let server = start(true, None);
+
+        let (status, body) = get(&server, "/metrics", None);
+        assert_eq!(status, 200);
+        assert!(body.contains("# TYPE capture_packets counter\ncapture_packets 42\n"));
+
+        let (status, body) = get(&server, "/healthz", None);
+        assert_eq!(status, 200);
+        let health: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(health["status"], "healthy");
+
+        assert_eq!(get(&server, "/readyz", None).0, 200);
+
+        let (status, body) = get(&server, "/diagnostics", None);
+        assert_eq!(status, 200);
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["session_id"], "s-1");
+
+        assert_eq!(get(&server, "/unknown", None).0, 404);
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_unready_node_fails_readiness() {
+        let server = start(false, None);
+        let (status, body) = get(&server, "/readyz", None);
+        assert_eq!(status, 503);
+        assert!(body.contains("interfaces not attached"));
+        assert_eq!(get(&server, "/healthz", None).0, 200);
+    }
+
+    #[test]
+    fn test_bearer_auth() {
+        let server = start(true, Some(Arc::new(TestSecurity)));
+
+        assert_eq!(get(&server, "/metrics", None).0, 401);
+        assert_eq!(get(&server, "/metrics", Some("forged")).0, 401);
+        assert_eq!(get(&server, "/metrics", Some("viewer")).0, 200);
+        assert_eq!(get(&server, "/diagnostics", Some("viewer")).0, 403);
+        assert_eq!(get(&server, "/diagnostics", Some("operator")).0, 200);
+    }
+}
@@ -3,7 +3,10 @@ use crate::traits::{Error, EventHandler, HealthCheck, Lifecycle};
 /// `ControlManager` handles control events like configuration updates or start/stop commands.
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::Arc;
+
+use crate::capture_engine::control::audit::CommandAuditLog;
 
 /// Events specific to control management.
 #[derive(Debug)]
@@ -32,6 +35,9 @@ pub trait ControlManager:
 
     /// Applies a configuration update.
     async fn apply_configuration(&mut self, config: Configuration) -> Result<(), Error>;
+
+    /// Audit trail of received control commands.
+    fn audit_trail(&self) -> Arc<CommandAuditLog>;
 }
 
 /// Represents the configuration data.
@@ -64,6 +70,66 @@ pub enum FilterCondition {
     SourcePort(u16),
     DestPort(u16),
     Protocol(u8),
+    /// IPv6 source and destination prefixes; an absent side matches any address.
+    /// Never matches IPv4 packets.
+    Ipv6Address {
+        src: Option<Ipv6Prefix>,
+        dst: Option<Ipv6Prefix>,
+    },
+}
+
+/// IPv6 network prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ipv6Prefix {
+    addr: Ipv6Addr,
+    prefix_len: u8,
+}
+
+impl Ipv6Prefix {
+    /// Prefix of `prefix_len` bits; host bits of `addr` are cleared. None if `prefix_len`
+    /// exceeds 128.
+    pub fn new(addr: Ipv6Addr, prefix_len: u8) -> Option<Self> {
+        if prefix_len > 128 {
+            return None;
+        }
+        Some(Self {
+            addr: Ipv6Addr::from(u128::from(addr) & Self::mask(prefix_len)),
+            prefix_len,
+        })
+    }
+
+    /// Prefix matching exactly one address.
+    pub fn host(addr: Ipv6Addr) -> Self {
+        Self {
+            addr,
+            prefix_len: 128,
+        }
+    }
+
+    pub fn addr(&self) -> Ipv6Addr {
+        self.addr
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Whether `addr` lies within the prefix.
+    pub fn contains(&self, addr: &Ipv6Addr) -> bool {
+        u128::from(*addr) & Self::mask(self.prefix_len) == u128::from(self.addr)
+    }
+
+    fn mask(prefix_len: u8) -> u128 {
+        u128::MAX
+            .checked_shl(128 - u32::from(prefix_len))
+            .unwrap_or(0)
+    }
+}
+
+impl std::fmt::Display for Ipv6Prefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
 }
 
 /// Actions for filter rules.
@@ -72,4 +138,12 @@ pub enum FilterAction {
     Accept,
     Drop,
     Mirror,
+    /// Keep a `rate` fraction of matching packets, drop the rest.
+    Sample {
+        rate: f64,
+    },
+    /// Keep a `rate` fraction of matching flows, keeping or dropping every packet of a flow.
+    SampleFlow {
+        rate: f64,
+    },
 }
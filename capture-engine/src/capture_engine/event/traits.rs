@@ -1,5 +1,6 @@
 // event/traits.rs
 use crate::capture_engine::buffer::traits::BufferEvent;
+use crate::capture_engine::capture::session_report::SessionReport;
 use crate::traits::{Error, PressureStatus};
 /// `EventSystem` defines a generic system for event publication and subscription.
 ///
@@ -11,6 +12,7 @@ use tokio::sync::mpsc;
 pub enum SystemEvent {
     BufferEvent(BufferEvent),
     CaptureEvent,
+    SessionReport(Box<SessionReport>),
     CloudEvent,
     ControlEvent,
     FilterEvent,
@@ -1 +1,10 @@
+pub mod adaptive_sampling;
+pub mod bpf_expression;
+pub mod dedup;
+pub mod head_capture;
+pub mod payload_limit;
+pub mod ruleset;
+pub mod sampling;
+pub mod stats;
 pub mod traits;
+pub mod xdp;
@@ -0,0 +1,316 @@
+// filter/adaptive_sampling.rs
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::control::traits::FilterAction;
+use crate::capture_engine::filter::sampling::PacketSampler;
+use crate::traits::PressureLevel;
+
+/// Bounds and step sizes of an adaptive sampling rate
+///
+/// # Fields
+/// * `min_rate` - Lowest rate the controller may set, so some traffic is always captured
+/// * `max_rate` - Highest rate the controller may set
+/// * `initial_rate` - Rate before any feedback arrives
+/// * `target_utilization` - Fraction of reported downstream capacity to aim for, leaving headroom
+/// * `decrease_factor` - Multiplier applied on congestion
+/// * `increase_step` - Amount added on relief
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveSamplingConfig {
+    pub min_rate: f64,
+    pub max_rate: f64,
+    pub initial_rate: f64,
+    pub target_utilization: f64,
+    pub decrease_factor: f64,
+    pub increase_step: f64,
+}
+
+impl Default for AdaptiveSamplingConfig {
+    fn default() -> Self {
+        Self {
+            min_rate: 0.01,
+            max_rate: 1.0,
+            initial_rate: 1.0,
+            target_utilization: 0.8,
+            decrease_factor: 0.5,
+            increase_step: 0.05,
+        }
+    }
+}
+
+impl AdaptiveSamplingConfig {
+    /// Validates the configuration
+    ///
+    /// # Returns
+    /// An error if a rate is outside 0.0..=1.0, the bounds are inverted, or a step would not
+    /// move the rate
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        let unit = |value: f64| (0.0..=1.0).contains(&value);
+        let valid = unit(self.min_rate)
+            && unit(self.max_rate)
+            && self.min_rate > 0.0
+            && self.min_rate <= self.max_rate
+            && (self.min_rate..=self.max_rate).contains(&self.initial_rate)
+            && self.target_utilization > 0.0
+            && self.target_utilization <= 1.0
+            && self.decrease_factor > 0.0
+            && self.decrease_factor < 1.0
+            && self.increase_step > 0.0;
+        if !valid {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Adaptive sampling needs 0 < min_rate <= initial_rate <= max_rate <= 1, \
+                 0 < target_utilization <= 1, 0 < decrease_factor < 1 and a positive increase_step",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Feedback from downstream about how much traffic it can take
+///
+/// # Variants
+/// * `Capacity` - The collector can accept `capacity_pps` packets per second while
+///   `offered_pps` packets per second reach sampling
+/// * `Congested` - Output is applying backpressure
+/// * `Relieved` - Output has headroom again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapacityFeedback {
+    Capacity { capacity_pps: f64, offered_pps: f64 },
+    Congested,
+    Relieved,
+}
+
+impl CapacityFeedback {
+    /// Maps an output pressure level to feedback
+    ///
+    /// # Arguments
+    /// * `level` - Pressure reported by the output path
+    ///
+    /// # Returns
+    /// `Relieved` for normal pressure, `Congested` otherwise
+    pub fn from_pressure(level: &PressureLevel) -> Self {
+        match level {
+            PressureLevel::Normal => CapacityFeedback::Relieved,
+            _ => CapacityFeedback::Congested,
+        }
+    }
+}
+
+/// Summary of an adaptive rate for the session report
+///
+/// # Fields
+/// * `effective_rate` - Rate in effect when the summary was taken
+/// * `lowest_rate` - Lowest rate set during the session
+/// * `highest_rate` - Highest rate set during the session
+/// * `adjustments` - Feedback signals that changed the rate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleRateSummary {
+    pub effective_rate: f64,
+    pub lowest_rate: f64,
+    pub highest_rate: f64,
+    pub adjustments: u64,
+}
+
+/// Sampling rate adjusted by downstream capacity feedback
+///
+/// Explicit capacity reports set the rate that keeps downstream at its target utilization.
+/// Backpressure without a capacity figure halves the rate (by default) and relief raises it in
+/// small steps, so the rate backs off quickly under congestion and recovers fidelity gradually.
+/// The rate always stays within the configured bounds.
+///
+/// # Fields
+/// * `config` - Bounds and step sizes
+/// * `state` - Current rate and its history
+#[derive(Debug)]
+pub struct AdaptiveSampleRate {
+    config: AdaptiveSamplingConfig,
+    state: Mutex<SampleRateSummary>,
+}
+
+impl AdaptiveSampleRate {
+    /// Creates an adaptive rate
+    ///
+    /// # Arguments
+    /// * `config` - Bounds and step sizes
+    ///
+    /// # Returns
+    /// A new AdaptiveSampleRate instance, or an error if the configuration is invalid
+    pub fn new(config: AdaptiveSamplingConfig) -> Result<Self, CaptureError> {
+        config.validate()?;
+        let rate = config.initial_rate;
+        Ok(Self {
+            config,
+            state: Mutex::new(SampleRateSummary {
+                effective_rate: rate,
+                lowest_rate: rate,
+                highest_rate: rate,
+                adjustments: 0,
+            }),
+        })
+    }
+
+    /// Gets the rate currently applied to sampled traffic
+    pub fn effective_rate(&self) -> f64 {
+        self.state.lock().effective_rate
+    }
+
+    /// Gets the current rate and its history
+    pub fn summary(&self) -> SampleRateSummary {
+        self.state.lock().clone()
+    }
+
+    /// Adjusts the rate from downstream feedback
+    ///
+    /// # Arguments
+    /// * `feedback` - Capacity signal from the collector or output path
+    ///
+    /// # Returns
+    /// The new effective rate
+    pub fn apply(&self, feedback: CapacityFeedback) -> f64 {
+        let mut state = self.state.lock();
+        let current = state.effective_rate;
+        let target = match feedback {
+            CapacityFeedback::Capacity {
+                capacity_pps,
+                offered_pps,
+            } => {
+                if offered_pps <= 0.0 {
+                    self.config.max_rate
+                } else {
+                    capacity_pps.max(0.0) * self.config.target_utilization / offered_pps
+                }
+            }
+            CapacityFeedback::Congested => current * self.config.decrease_factor,
+            CapacityFeedback::Relieved => current + self.config.increase_step,
+        };
+        let rate = if target.is_nan() {
+            current
+        } else {
+            target.clamp(self.config.min_rate, self.config.max_rate)
+        };
+
+        if rate != current {
+            state.effective_rate = rate;
+            state.lowest_rate = state.lowest_rate.min(rate);
+            state.highest_rate = state.highest_rate.max(rate);
+            state.adjustments += 1;
+        }
+        rate
+    }
+
+    /// Resolves a `Sample` action at the adaptive rate
+    ///
+    /// # Arguments
+    /// * `sampler` - Seeded decision source of the session
+    /// * `action` - Action selected by the filter
+    ///
+    /// # Returns
+    /// `Accept` or `Drop` for `Sample`, whose static rate is replaced by the effective rate;
+    /// other actions are returned unchanged
+    pub fn resolve(&self, sampler: &mut PacketSampler, action: &FilterAction) -> FilterAction {
+        match action {
+            FilterAction::Sample { .. } => sampler.resolve(&FilterAction::Sample {
+                rate: self.effective_rate(),
+            }),
+            other => other.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adaptive() -> AdaptiveSampleRate {
+        AdaptiveSampleRate::new(AdaptiveSamplingConfig {
+            min_rate: 0.1,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_congestion_lowers_rate_within_bounds() {
+        let rate = adaptive();
+        assert_eq!(rate.apply(CapacityFeedback::Congested), 0.5);
+        assert_eq!(rate.apply(CapacityFeedback::Congested), 0.25);
+        for _ in 0..10 {
+            rate.apply(CapacityFeedback::Congested);
+        }
+        assert_eq!(rate.effective_rate(), 0.1);
+
+        let summary = rate.summary();
+        assert_eq!(summary.lowest_rate, 0.1);
+        assert_eq!(summary.highest_rate, 1.0);
+        // Once pinned at the floor further congestion is not an adjustment
+        assert_eq!(summary.adjustments, 4);
+    }
+
+    #[test]
+    fn test_relief_raises_rate_within_bounds() {
+        let rate = adaptive();
+        rate.apply(CapacityFeedback::from_pressure(&PressureLevel::Critical));
+        assert_eq!(rate.effective_rate(), 0.5);
+
+        rate.apply(CapacityFeedback::from_pressure(&PressureLevel::Normal));
+        assert!((rate.effective_rate() - 0.55).abs() < 1e-9);
+        for _ in 0..20 {
+            rate.apply(CapacityFeedback::Relieved);
+        }
+        assert_eq!(rate.effective_rate(), 1.0);
+    }
+
+    #[test]
+    fn test_capacity_report_targets_utilization() {
+        let rate = adaptive();
+        // 10k pps offered, collector takes 5k: aim for 80% of 5k
+        let effective = rate.apply(CapacityFeedback::Capacity {
+            capacity_pps: 5_000.0,
+            offered_pps: 10_000.0,
+        });
+        assert!((effective - 0.4).abs() < 1e-9);
+
+        // A collector that can barely keep up is still sent the minimum
+        let effective = rate.apply(CapacityFeedback::Capacity {
+            capacity_pps: 10.0,
+            offered_pps: 10_000.0,
+        });
+        assert_eq!(effective, 0.1);
+    }
+
+    #[test]
+    fn test_resolve_uses_effective_rate() {
+        let rate = adaptive();
+        for _ in 0..10 {
+            rate.apply(CapacityFeedback::Congested);
+        }
+        let mut sampler = PacketSampler::new(Some(7));
+        let kept = (0..2_000)
+            .filter(|_| {
+                matches!(
+                    rate.resolve(&mut sampler, &FilterAction::Sample { rate: 1.0 }),
+                    FilterAction::Accept
+                )
+            })
+            .count();
+        assert!((100..=300).contains(&kept), "kept {}", kept);
+        assert!(matches!(
+            rate.resolve(&mut sampler, &FilterAction::Drop),
+            FilterAction::Drop
+        ));
+    }
+
+    #[test]
+    fn test_invalid_bounds_rejected() {
+        let config = AdaptiveSamplingConfig {
+            min_rate: 0.6,
+            max_rate: 0.5,
+            ..Default::default()
+        };
+        assert!(AdaptiveSampleRate::new(config).is_err());
+    }
+}
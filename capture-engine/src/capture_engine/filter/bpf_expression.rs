@@ -0,0 +1,736 @@
+// filter/bpf_expression.rs
+use std::fmt;
+use std::net::IpAddr;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, CaptureResult, ConfigErrorKind,
+};
+use crate::capture_engine::capture::packet_filter::{FilterDirection, FilterRule};
+
+/// Ethertypes with a BPF keyword
+const ETHER_KEYWORDS: [(&str, u16); 4] = [
+    ("ip", 0x0800),
+    ("ip6", 0x86dd),
+    ("arp", 0x0806),
+    ("rarp", 0x8035),
+];
+
+/// IP protocols with a BPF keyword
+const PROTO_KEYWORDS: [(&str, u8); 6] = [
+    ("icmp", 1),
+    ("igmp", 2),
+    ("tcp", 6),
+    ("udp", 17),
+    ("icmp6", 58),
+    ("sctp", 132),
+];
+
+/// TCP flag names in bit order
+const TCP_FLAGS: [(&str, u8); 8] = [
+    ("tcp-fin", 0x01),
+    ("tcp-syn", 0x02),
+    ("tcp-rst", 0x04),
+    ("tcp-push", 0x08),
+    ("tcp-ack", 0x10),
+    ("tcp-urg", 0x20),
+    ("tcp-ece", 0x40),
+    ("tcp-cwr", 0x80),
+];
+
+/// Lexical token of a BPF expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Amp,
+    Pipe,
+    Bang,
+    Eq,
+    Ne,
+    AndAnd,
+    OrOr,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => f.write_str(word),
+            Token::LParen => f.write_str("("),
+            Token::RParen => f.write_str(")"),
+            Token::LBracket => f.write_str("["),
+            Token::RBracket => f.write_str("]"),
+            Token::Amp => f.write_str("&"),
+            Token::Pipe => f.write_str("|"),
+            Token::Bang => f.write_str("!"),
+            Token::Eq => f.write_str("="),
+            Token::Ne => f.write_str("!="),
+            Token::AndAnd => f.write_str("&&"),
+            Token::OrOr => f.write_str("||"),
+        }
+    }
+}
+
+/// Parses a tcpdump-style filter expression
+///
+/// Supports the subset operators use in practice: `ip`, `ip6`, `arp`, `rarp` and
+/// `ether proto`; `host`, `net` with a CIDR prefix, `proto` and the protocol keywords; `port`
+/// and `portrange`, optionally qualified by a protocol; `src` and `dst` qualifiers; TCP flag
+/// tests on `tcp[tcpflags]`; and `and`, `or`, `not` with their symbolic forms and parentheses.
+/// As in tcpdump, `not` binds tightest while `and` and `or` share a precedence and associate
+/// left to right. Host names are not resolved.
+///
+/// # Arguments
+/// * `expr` - Filter expression
+///
+/// # Returns
+/// The parsed rule tree, or a `Configuration(ParseError)` naming the offending token
+pub fn parse(expr: &str) -> CaptureResult<FilterRule> {
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return Err(parse_error("Empty BPF expression"));
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let rule = parser.expression()?;
+    match parser.peek() {
+        Some(token) => Err(unexpected(token)),
+        None => Ok(rule),
+    }
+}
+
+/// Renders a rule tree as a filter expression
+///
+/// Parsing the result yields the same tree for every rule `parse` produces. `Custom` rules are
+/// inserted verbatim in parentheses.
+///
+/// # Arguments
+/// * `rule` - Rule tree to render
+///
+/// # Returns
+/// The filter expression
+pub fn render(rule: &FilterRule) -> String {
+    match rule {
+        FilterRule::Protocol(name) => match PROTO_KEYWORDS.iter().find(|(kw, _)| kw == name) {
+            Some((keyword, _)) => keyword.to_string(),
+            None => format!("proto {}", name),
+        },
+        FilterRule::Port(port) => format!("port {}", port),
+        FilterRule::Host(host) => format!("host {}", host),
+        FilterRule::Custom(expr) => format!("({})", expr),
+        FilterRule::EtherType(ethertype) => {
+            match ETHER_KEYWORDS.iter().find(|(_, value)| value == ethertype) {
+                Some((keyword, _)) => keyword.to_string(),
+                None => format!("ether proto 0x{:04x}", ethertype),
+            }
+        }
+        FilterRule::Net {
+            direction,
+            addr,
+            prefix_len,
+        } => {
+            if *prefix_len == max_prefix(addr) {
+                format!("{}host {}", qualifier(direction), addr)
+            } else {
+                format!("{}net {}/{}", qualifier(direction), addr, prefix_len)
+            }
+        }
+        FilterRule::PortRange {
+            direction,
+            start,
+            end,
+        } => {
+            if start == end {
+                format!("{}port {}", qualifier(direction), start)
+            } else {
+                format!("{}portrange {}-{}", qualifier(direction), start, end)
+            }
+        }
+        FilterRule::TcpFlags { mask, equals } => match equals {
+            None => format!("tcp[tcpflags] & {} != 0", render_flags(*mask)),
+            Some(value) => format!(
+                "tcp[tcpflags] & {} = {}",
+                render_flags(*mask),
+                render_flags(*value)
+            ),
+        },
+        FilterRule::And(left, right) => render_binary("and", left, right),
+        FilterRule::Or(left, right) => render_binary("or", left, right),
+        FilterRule::Not(inner) => format!("not {}", render_operand(inner)),
+    }
+}
+
+fn render_binary(op: &str, left: &FilterRule, right: &FilterRule) -> String {
+    // Left association makes parentheses redundant only on a left operand of the same operator
+    let left = match (op, left) {
+        ("and", FilterRule::And(..)) | ("or", FilterRule::Or(..)) => render(left),
+        _ => render_operand(left),
+    };
+    format!("{} {} {}", left, op, render_operand(right))
+}
+
+fn render_operand(rule: &FilterRule) -> String {
+    match rule {
+        FilterRule::And(..) | FilterRule::Or(..) => format!("({})", render(rule)),
+        _ => render(rule),
+    }
+}
+
+fn qualifier(direction: &FilterDirection) -> &'static str {
+    match direction {
+        FilterDirection::Src => "src ",
+        FilterDirection::Dst => "dst ",
+        FilterDirection::Any => "",
+    }
+}
+
+fn render_flags(bits: u8) -> String {
+    if bits == 0 {
+        return "0".to_string();
+    }
+    let names: Vec<_> = TCP_FLAGS
+        .iter()
+        .filter(|(_, bit)| bits & bit != 0)
+        .map(|(name, _)| *name)
+        .collect();
+    match names.as_slice() {
+        [single] => single.to_string(),
+        _ => format!("({})", names.join("|")),
+    }
+}
+
+fn max_prefix(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn tokenize(expr: &str) -> CaptureResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let token = match c {
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '&' | '|' | '!' | '=' => {
+                chars.next();
+                let next = chars.peek().copied();
+                let pair = matches!(
+                    (c, next),
+                    ('&', Some('&')) | ('|', Some('|')) | ('!', Some('=')) | ('=', Some('='))
+                );
+                let token = match (c, next) {
+                    ('&', Some('&')) => Token::AndAnd,
+                    ('|', Some('|')) => Token::OrOr,
+                    ('!', Some('=')) => Token::Ne,
+                    ('=', Some('=')) => Token::Eq,
+                    ('&', _) => Token::Amp,
+                    ('|', _) => Token::Pipe,
+                    ('!', _) => Token::Bang,
+                    _ => Token::Eq,
+                };
+                if pair {
+                    chars.next();
+                }
+                tokens.push(token);
+                continue;
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek().filter(|c| is_word_char(**c)) {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word.to_ascii_lowercase()));
+                continue;
+            }
+            other => {
+                return Err(parse_error(&format!(
+                    "Unexpected character '{}' in BPF expression",
+                    other
+                )))
+            }
+        };
+        chars.next();
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '/' | '-' | '_' | '\\')
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_word(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Word(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> CaptureResult<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| parse_error("Unexpected end of BPF expression"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn next_word(&mut self) -> CaptureResult<String> {
+        match self.next()? {
+            Token::Word(word) => Ok(word),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> CaptureResult<()> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(unexpected(&token))
+        }
+    }
+
+    fn expression(&mut self) -> CaptureResult<FilterRule> {
+        let mut rule = self.unary()?;
+        loop {
+            let and = match self.peek() {
+                Some(Token::AndAnd) => true,
+                Some(Token::OrOr) => false,
+                Some(Token::Word(word)) if word == "and" => true,
+                Some(Token::Word(word)) if word == "or" => false,
+                _ => return Ok(rule),
+            };
+            self.pos += 1;
+            let right = self.unary()?;
+            rule = if and {
+                FilterRule::And(Box::new(rule), Box::new(right))
+            } else {
+                FilterRule::Or(Box::new(rule), Box::new(right))
+            };
+        }
+    }
+
+    fn unary(&mut self) -> CaptureResult<FilterRule> {
+        match self.next()? {
+            Token::Bang => Ok(FilterRule::Not(Box::new(self.unary()?))),
+            Token::Word(word) if word == "not" => Ok(FilterRule::Not(Box::new(self.unary()?))),
+            Token::LParen => {
+                let rule = self.expression()?;
+                self.expect(Token::RParen)?;
+                Ok(rule)
+            }
+            Token::Word(word) => self.primitive(word),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    fn primitive(&mut self, word: String) -> CaptureResult<FilterRule> {
+        if let Some(&(_, ethertype)) = ETHER_KEYWORDS.iter().find(|(kw, _)| *kw == word) {
+            let ether = FilterRule::EtherType(ethertype);
+            if self.peek_word() == Some("proto") && matches!(word.as_str(), "ip" | "ip6") {
+                self.pos += 1;
+                let proto = self.protocol()?;
+                return Ok(FilterRule::And(Box::new(ether), Box::new(proto)));
+            }
+            return Ok(ether);
+        }
+
+        if PROTO_KEYWORDS.iter().any(|(kw, _)| *kw == word) {
+            if word == "tcp" && self.peek() == Some(&Token::LBracket) {
+                return self.tcp_flags();
+            }
+            let proto = FilterRule::Protocol(word.clone());
+            let qualified = matches!(word.as_str(), "tcp" | "udp" | "sctp")
+                && matches!(self.peek_word(), Some("src" | "dst" | "port" | "portrange"));
+            if !qualified {
+                return Ok(proto);
+            }
+            let next = self.next_word()?;
+            let ports = self.directional(next)?;
+            if !matches!(ports, FilterRule::PortRange { .. }) {
+                return Err(parse_error(&format!(
+                    "Protocol '{}' only qualifies port primitives",
+                    word
+                )));
+            }
+            return Ok(FilterRule::And(Box::new(proto), Box::new(ports)));
+        }
+
+        match word.as_str() {
+            "ether" => {
+                let token = self.next_word()?;
+                if token != "proto" {
+                    return Err(unexpected(&Token::Word(token)));
+                }
+                let value = self.next_word()?;
+                let ethertype = match value.strip_prefix('\\') {
+                    Some(name) => ETHER_KEYWORDS
+                        .iter()
+                        .find(|(kw, _)| *kw == name)
+                        .map(|&(_, ethertype)| ethertype),
+                    None => parse_number(&value).and_then(|n| u16::try_from(n).ok()),
+                };
+                ethertype
+                    .map(FilterRule::EtherType)
+                    .ok_or_else(|| unexpected(&Token::Word(value)))
+            }
+            "proto" => self.protocol(),
+            _ => self.directional(word),
+        }
+    }
+
+    /// Parses `[src|dst] host|net|port|portrange <value>`, or `src|dst <address>`
+    fn directional(&mut self, word: String) -> CaptureResult<FilterRule> {
+        let (direction, keyword) = match word.as_str() {
+            "src" | "dst" => {
+                let direction = if word == "src" {
+                    FilterDirection::Src
+                } else {
+                    FilterDirection::Dst
+                };
+                match self.peek_word() {
+                    Some("host" | "net" | "port" | "portrange") => (direction, self.next_word()?),
+                    _ => (direction, "host".to_string()),
+                }
+            }
+            _ => (FilterDirection::Any, word),
+        };
+
+        match keyword.as_str() {
+            "host" => {
+                let value = self.next_word()?;
+                let addr: IpAddr = value.parse().map_err(|_| unexpected(&Token::Word(value)))?;
+                Ok(FilterRule::Net {
+                    direction,
+                    prefix_len: max_prefix(&addr),
+                    addr,
+                })
+            }
+            "net" => {
+                let value = self.next_word()?;
+                let (addr, prefix_len) =
+                    parse_cidr(&value).ok_or_else(|| unexpected(&Token::Word(value.clone())))?;
+                Ok(FilterRule::Net {
+                    direction,
+                    addr,
+                    prefix_len,
+                })
+            }
+            "port" => {
+                let value = self.next_word()?;
+                let port: u16 = value.parse().map_err(|_| unexpected(&Token::Word(value)))?;
+                Ok(FilterRule::PortRange {
+                    direction,
+                    start: port,
+                    end: port,
+                })
+            }
+            "portrange" => {
+                let value = self.next_word()?;
+                let range = value.split_once('-').and_then(|(start, end)| {
+                    let (start, end): (u16, u16) = (start.parse().ok()?, end.parse().ok()?);
+                    (start <= end).then_some((start, end))
+                });
+                let (start, end) = range.ok_or_else(|| unexpected(&Token::Word(value)))?;
+                Ok(FilterRule::PortRange {
+                    direction,
+                    start,
+                    end,
+                })
+            }
+            _ => Err(unexpected(&Token::Word(keyword))),
+        }
+    }
+
+    /// Parses the operand of `proto`: a protocol keyword, escaped or not, or a number
+    fn protocol(&mut self) -> CaptureResult<FilterRule> {
+        let value = self.next_word()?;
+        let name = value.strip_prefix('\\').unwrap_or(&value);
+        if PROTO_KEYWORDS.iter().any(|(kw, _)| *kw == name) {
+            return Ok(FilterRule::Protocol(name.to_string()));
+        }
+        match parse_number(name).and_then(|n| u8::try_from(n).ok()) {
+            Some(number) => Ok(FilterRule::Protocol(
+                PROTO_KEYWORDS
+                    .iter()
+                    .find(|(_, value)| *value == number)
+                    .map_or_else(|| number.to_string(), |(kw, _)| kw.to_string()),
+            )),
+            None => Err(unexpected(&Token::Word(value))),
+        }
+    }
+
+    /// Parses `tcp[tcpflags] & <flags> != 0` or `tcp[tcpflags] & <flags> = <flags>`
+    fn tcp_flags(&mut self) -> CaptureResult<FilterRule> {
+        self.expect(Token::LBracket)?;
+        let field = self.next_word()?;
+        if field != "tcpflags" && field != "13" {
+            return Err(unexpected(&Token::Word(field)));
+        }
+        self.expect(Token::RBracket)?;
+        self.expect(Token::Amp)?;
+        let mask = self.flags()?;
+        let equals = match self.next()? {
+            Token::Ne => {
+                let zero = self.next_word()?;
+                if parse_number(&zero) != Some(0) {
+                    return Err(unexpected(&Token::Word(zero)));
+                }
+                None
+            }
+            Token::Eq => Some(self.flags()?),
+            other => return Err(unexpected(&other)),
+        };
+        Ok(FilterRule::TcpFlags { mask, equals })
+    }
+
+    /// Parses a flag name or number, or a parenthesized `|` list of them
+    fn flags(&mut self) -> CaptureResult<u8> {
+        if self.peek() != Some(&Token::LParen) {
+            return self.flag();
+        }
+        self.pos += 1;
+        let mut bits = self.flag()?;
+        loop {
+            match self.next()? {
+                Token::Pipe => bits |= self.flag()?,
+                Token::RParen => return Ok(bits),
+                other => return Err(unexpected(&other)),
+            }
+        }
+    }
+
+    fn flag(&mut self) -> CaptureResult<u8> {
+        let word = self.next_word()?;
+        TCP_FLAGS
+            .iter()
+            .find(|(name, _)| *name == word)
+            .map(|&(_, bit)| bit)
+            .or_else(|| parse_number(&word).and_then(|n| u8::try_from(n).ok()))
+            .ok_or_else(|| unexpected(&Token::Word(word)))
+    }
+}
+
+fn parse_number(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parses `addr/len`, or a bare address as a host network; host bits must be clear
+fn parse_cidr(value: &str) -> Option<(IpAddr, u8)> {
+    let (addr, prefix_len) = match value.split_once('/') {
+        Some((addr, len)) => {
+            let addr: IpAddr = addr.parse().ok()?;
+            (addr, len.parse().ok()?)
+        }
+        None => {
+            let addr: IpAddr = value.parse().ok()?;
+            (addr, max_prefix(&addr))
+        }
+    };
+    if prefix_len > max_prefix(&addr) {
+        return None;
+    }
+    let host_bits_clear = match addr {
+        IpAddr::V4(v4) => u32::from(v4)
+            .checked_shl(u32::from(prefix_len))
+            .unwrap_or(0)
+            .eq(&0),
+        IpAddr::V6(v6) => u128::from(v6)
+            .checked_shl(u32::from(prefix_len))
+            .unwrap_or(0)
+            .eq(&0),
+    };
+    host_bits_clear.then_some((addr, prefix_len))
+}
+
+fn unexpected(token: &Token) -> Box<CaptureError> {
+    parse_error(&format!("Unexpected token '{}' in BPF expression", token))
+}
+
+fn parse_error(message: &str) -> Box<CaptureError> {
+    CaptureError::new(
+        CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+        message,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn host(direction: FilterDirection, last: u8) -> FilterRule {
+        FilterRule::Net {
+            direction,
+            addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)),
+            prefix_len: 32,
+        }
+    }
+
+    fn port(direction: FilterDirection, port: u16) -> FilterRule {
+        FilterRule::PortRange {
+            direction,
+            start: port,
+            end: port,
+        }
+    }
+
+    #[test]
+    fn test_parses_operator_example() {
+        let rule = parse("tcp port 443 and not host 10.0.0.1").unwrap();
+        assert_eq!(
+            rule,
+            FilterRule::And(
+                Box::new(FilterRule::And(
+                    Box::new(FilterRule::Protocol("tcp".to_string())),
+                    Box::new(port(FilterDirection::Any, 443)),
+                )),
+                Box::new(FilterRule::Not(Box::new(host(FilterDirection::Any, 1)))),
+            )
+        );
+    }
+
+    #[test]
+    fn test_and_or_share_precedence() {
+        // tcpdump reads this as (src A or dst B) and port 53, not src A or (dst B and port 53)
+        let rule = parse("src 10.0.0.1 || dst host 10.0.0.2 && port 53").unwrap();
+        assert_eq!(
+            rule,
+            FilterRule::And(
+                Box::new(FilterRule::Or(
+                    Box::new(host(FilterDirection::Src, 1)),
+                    Box::new(host(FilterDirection::Dst, 2)),
+                )),
+                Box::new(port(FilterDirection::Any, 53)),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parses_layer_primitives() {
+        assert_eq!(parse("arp").unwrap(), FilterRule::EtherType(0x0806));
+        assert_eq!(
+            parse("ether proto 0x88cc").unwrap(),
+            FilterRule::EtherType(0x88cc)
+        );
+        assert_eq!(
+            parse("ip proto 17").unwrap(),
+            FilterRule::And(
+                Box::new(FilterRule::EtherType(0x0800)),
+                Box::new(FilterRule::Protocol("udp".to_string())),
+            )
+        );
+        assert_eq!(
+            parse("dst net 192.168.0.0/16").unwrap(),
+            FilterRule::Net {
+                direction: FilterDirection::Dst,
+                addr: "192.168.0.0".parse().unwrap(),
+                prefix_len: 16,
+            }
+        );
+        assert_eq!(
+            parse("udp src portrange 1000-2000").unwrap(),
+            FilterRule::And(
+                Box::new(FilterRule::Protocol("udp".to_string())),
+                Box::new(FilterRule::PortRange {
+                    direction: FilterDirection::Src,
+                    start: 1000,
+                    end: 2000,
+                }),
+            )
+        );
+        assert_eq!(
+            parse("host fe80::1").unwrap(),
+            FilterRule::Net {
+                direction: FilterDirection::Any,
+                addr: "fe80::1".parse().unwrap(),
+                prefix_len: 128,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parses_tcp_flags() {
+        assert_eq!(
+            parse("tcp[tcpflags] & (tcp-syn|tcp-ack) == tcp-syn").unwrap(),
+            FilterRule::TcpFlags {
+                mask: 0x12,
+                equals: Some(0x02),
+            }
+        );
+        assert_eq!(
+            parse("tcp[13] & tcp-rst != 0").unwrap(),
+            FilterRule::TcpFlags {
+                mask: 0x04,
+                equals: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors_name_offending_token() {
+        for (expr, token) in [
+            ("tcp port https", "'https'"),
+            ("host 10.0.0.1 and", "end of BPF expression"),
+            ("(port 80", "end of BPF expression"),
+            ("port 80)", "')'"),
+            ("net 10.0.0.1/8", "'10.0.0.1/8'"),
+            ("portrange 20-10", "'20-10'"),
+            ("frobnicate", "'frobnicate'"),
+            ("tcp[tcpflags] & tcp-bogus != 0", "'tcp-bogus'"),
+        ] {
+            let err = parse(expr).unwrap_err();
+            assert!(
+                matches!(
+                    err.kind(),
+                    CaptureErrorKind::Configuration(ConfigErrorKind::ParseError)
+                ),
+                "{}",
+                expr
+            );
+            assert!(err.to_string().contains(token), "{}: {}", expr, err);
+        }
+        assert!(parse("   ").is_err());
+    }
+
+    #[test]
+    fn test_render_round_trips() {
+        for expr in [
+            "tcp and port 443 and not host 10.0.0.1",
+            "(src host 10.0.0.1 or dst net 10.1.0.0/16) and udp",
+            "not (arp or ip6)",
+            "tcp[tcpflags] & (tcp-syn|tcp-ack) = tcp-syn",
+            "ether proto 0x88cc or proto 47",
+            "port 53 or (tcp and dst portrange 8000-8080)",
+        ] {
+            let rule = parse(expr).unwrap();
+            assert_eq!(render(&rule), expr);
+            assert_eq!(parse(&render(&rule)).unwrap(), rule);
+        }
+    }
+}
@@ -0,0 +1,359 @@
+// filter/dedup.rs
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::capture::packet_layer::{LayerAction, PacketLayer};
+use crate::capture_engine::protocol::flow::FlowTable;
+use crate::capture_engine::protocol::headers::{
+    ETHERTYPE_IPV4, ETHERTYPE_IPV6, ETHERTYPE_QINQ, ETHERTYPE_VLAN,
+};
+use crate::capture_engine::telemetry::traits::{
+    MetricType, MetricUnit, MetricValue, TelemetryData,
+};
+use crate::traits::Packet;
+
+/// Telemetry name for duplicate packets suppressed
+pub const DUPLICATES_SUPPRESSED_METRIC: &str = "capture.dedup.packets.suppressed";
+
+/// Bytes after the IP header included in the fingerprint; covers the TCP header up to the
+/// urgent pointer, and the whole UDP or ICMP echo header
+const TRANSPORT_FINGERPRINT_LEN: usize = 20;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Duplicate suppression settings
+///
+/// # Fields
+/// * `window_ns` - Time after a packet is first seen during which copies of it are dropped
+/// * `capacity` - Fingerprints remembered at once; the oldest is forgotten when full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupConfig {
+    pub window_ns: u64,
+    pub capacity: usize,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            window_ns: 10_000_000,
+            capacity: 65_536,
+        }
+    }
+}
+
+impl DedupConfig {
+    /// Validates the settings
+    ///
+    /// # Returns
+    /// An error if the window or capacity is zero
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        if self.window_ns == 0 || self.capacity == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Dedup window and capacity must be greater than zero",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Counters for duplicate suppression
+#[derive(Debug, Default)]
+pub struct DedupStats {
+    checked: AtomicU64,
+    suppressed: AtomicU64,
+    unfingerprinted: AtomicU64,
+}
+
+impl DedupStats {
+    /// Gets the number of packets checked
+    pub fn checked(&self) -> u64 {
+        self.checked.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of duplicate packets suppressed
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of non-IP or truncated packets passed through unchecked
+    pub fn unfingerprinted(&self) -> u64 {
+        self.unfingerprinted.load(Ordering::Relaxed)
+    }
+
+    /// Exports the suppression counter as telemetry
+    ///
+    /// # Arguments
+    /// * `timestamp` - Timestamp to stamp the data point with
+    pub fn telemetry(&self, timestamp: u64) -> Vec<TelemetryData> {
+        vec![TelemetryData {
+            timestamp,
+            name: DUPLICATES_SUPPRESSED_METRIC.to_string(),
+            description: Some("Mirrored packets dropped as duplicates".to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type: MetricType::Counter,
+            value: MetricValue::Integer(self.suppressed() as i64),
+            attributes: HashMap::new(),
+            resource: None,
+        }]
+    }
+}
+
+/// Drops packets delivered more than once within a short window
+///
+/// Traffic mirroring combined with asymmetric routing can deliver the same packet from two
+/// sources. Each packet is fingerprinted by an FNV-1a hash over its IP header and the start of
+/// its transport header, which takes in the IP id and the TCP sequence number. Fields rewritten
+/// per hop (TTL, hop limit and the IPv4 header checksum) are left out so both copies match.
+///
+/// The window runs from the first sighting and is not extended by copies, so a retransmission
+/// arriving after the window is kept. Timestamps are packet timestamps in nanoseconds. Frames
+/// without an IP header, or cut short before the transport header, are never suppressed.
+///
+/// # Fields
+/// * `config` - Window and capacity
+/// * `seen` - Fingerprints within the window
+/// * `stats` - Suppression counters
+#[derive(Debug)]
+pub struct Deduplicator {
+    config: DedupConfig,
+    seen: Mutex<FlowTable<(), u64>>,
+    stats: DedupStats,
+}
+
+impl Deduplicator {
+    /// Creates a deduplicator
+    ///
+    /// # Arguments
+    /// * `config` - Window and capacity
+    ///
+    /// # Returns
+    /// A new Deduplicator, or an error if the settings are invalid
+    pub fn new(config: DedupConfig) -> Result<Self, CaptureError> {
+        config.validate()?;
+        Ok(Self {
+            seen: Mutex::new(FlowTable::new(config.capacity, config.window_ns)),
+            config,
+            stats: DedupStats::default(),
+        })
+    }
+
+    /// Gets the settings
+    pub fn config(&self) -> &DedupConfig {
+        &self.config
+    }
+
+    /// Gets the suppression counters
+    pub fn stats(&self) -> &DedupStats {
+        &self.stats
+    }
+
+    /// Checks whether a frame is a copy of one seen within the window, remembering it if not
+    ///
+    /// # Arguments
+    /// * `frame` - Captured frame, starting at the Ethernet header
+    /// * `now` - Packet timestamp in nanoseconds
+    ///
+    /// # Returns
+    /// True if the frame is a duplicate and should be dropped
+    pub fn is_duplicate(&self, frame: &[u8], now: u64) -> bool {
+        self.stats.checked.fetch_add(1, Ordering::Relaxed);
+        let Some(hash) = fingerprint(frame) else {
+            self.stats.unfingerprinted.fetch_add(1, Ordering::Relaxed);
+            return false;
+        };
+
+        let mut seen = self.seen.lock();
+        seen.evict_idle(now);
+        match seen.first_seen(&hash) {
+            Some(first) if now.saturating_sub(first) <= self.config.window_ns => {
+                self.stats.suppressed.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            _ => {
+                seen.remove(&hash);
+                seen.get_or_insert_with(hash, now, || ());
+                false
+            }
+        }
+    }
+}
+
+impl PacketLayer for Deduplicator {
+    fn name(&self) -> &str {
+        "dedup"
+    }
+
+    fn process(&self, packet: &mut Packet<'_>) -> Result<LayerAction, CaptureError> {
+        if self.is_duplicate(packet.data, packet.timestamp) {
+            return Ok(LayerAction::Drop {
+                reason: "duplicate packet".to_string(),
+            });
+        }
+        Ok(LayerAction::Continue)
+    }
+}
+
+/// Hashes the per-hop-invariant IP and transport header bytes of an Ethernet frame
+fn fingerprint(frame: &[u8]) -> Option<u64> {
+    let mut offset = 12;
+    let mut ethertype = u16::from_be_bytes(frame.get(offset..offset + 2)?.try_into().ok()?);
+    while matches!(ethertype, ETHERTYPE_VLAN | ETHERTYPE_QINQ) {
+        offset += 4;
+        ethertype = u16::from_be_bytes(frame.get(offset..offset + 2)?.try_into().ok()?);
+    }
+    let ip = frame.get(offset + 2..)?;
+
+    let (header_len, skipped): (usize, &[usize]) = match ethertype {
+        // TTL, header checksum
+        ETHERTYPE_IPV4 => (usize::from(ip.first()? & 0x0f) * 4, &[8, 10, 11]),
+        // Hop limit
+        ETHERTYPE_IPV6 => (40, &[7]),
+        _ => return None,
+    };
+    let transport_end = (header_len + TRANSPORT_FINGERPRINT_LEN).min(ip.len());
+    if header_len < 20 || transport_end <= header_len {
+        return None;
+    }
+
+    let hash = ip[..transport_end]
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !skipped.contains(i))
+        .fold(FNV_OFFSET, |hash, (_, &byte)| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        });
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{BufferId, PacketMetadata};
+
+    const MS: u64 = 1_000_000;
+
+    /// Ethernet + IPv4 + TCP from 10.0.0.1:40000 to 10.0.0.2:443
+    fn tcp_frame(ip_id: u16, seq: u32, ttl: u8) -> Vec<u8> {
+        let mut frame = vec![0u8; 64];
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        frame[14] = 0x45;
+        frame[18..20].copy_from_slice(&ip_id.to_be_bytes());
+        frame[22] = ttl;
+        frame[23] = 6;
+        frame[24..26].copy_from_slice(&u16::from(ttl).to_be_bytes());
+        frame[26..30].copy_from_slice(&[10, 0, 0, 1]);
+        frame[30..34].copy_from_slice(&[10, 0, 0, 2]);
+        frame[34..36].copy_from_slice(&40000u16.to_be_bytes());
+        frame[36..38].copy_from_slice(&443u16.to_be_bytes());
+        frame[38..42].copy_from_slice(&seq.to_be_bytes());
+        frame[46] = 0x50;
+        frame
+    }
+
+    fn dedup(window_ns: u64, capacity: usize) -> Deduplicator {
+        Deduplicator::new(DedupConfig {
+            window_ns,
+            capacity,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_duplicate_within_window_is_suppressed() {
+        let dedup = dedup(10 * MS, 16);
+        assert!(!dedup.is_duplicate(&tcp_frame(1, 1000, 64), 0));
+        // The second copy crossed one more hop
+        assert!(dedup.is_duplicate(&tcp_frame(1, 1000, 63), 2 * MS));
+        assert!(dedup.is_duplicate(&tcp_frame(1, 1000, 64), 10 * MS));
+
+        // Different IP id or sequence number is a different packet
+        assert!(!dedup.is_duplicate(&tcp_frame(2, 1000, 64), 3 * MS));
+        assert!(!dedup.is_duplicate(&tcp_frame(1, 2448, 64), 3 * MS));
+        assert_eq!(dedup.stats().suppressed(), 2);
+        assert_eq!(dedup.stats().checked(), 5);
+    }
+
+    #[test]
+    fn test_retransmission_outside_window_is_kept() {
+        let dedup = dedup(10 * MS, 16);
+        assert!(!dedup.is_duplicate(&tcp_frame(1, 1000, 64), 0));
+        assert!(dedup.is_duplicate(&tcp_frame(1, 1000, 64), 9 * MS));
+
+        // Copies do not extend the window, and the retransmission opens a new one
+        assert!(!dedup.is_duplicate(&tcp_frame(1, 1000, 64), 11 * MS));
+        assert!(dedup.is_duplicate(&tcp_frame(1, 1000, 63), 12 * MS));
+        assert_eq!(dedup.stats().suppressed(), 2);
+    }
+
+    #[test]
+    fn test_capacity_forgets_oldest() {
+        let dedup = dedup(10 * MS, 2);
+        for seq in 0..3 {
+            assert!(!dedup.is_duplicate(&tcp_frame(1, seq, 64), MS));
+        }
+        assert!(!dedup.is_duplicate(&tcp_frame(1, 0, 64), 2 * MS));
+        assert!(dedup.is_duplicate(&tcp_frame(1, 2, 64), 2 * MS));
+    }
+
+    #[test]
+    fn test_non_ip_and_truncated_frames_pass() {
+        let dedup = dedup(10 * MS, 16);
+        let mut arp = tcp_frame(1, 1000, 64);
+        arp[12..14].copy_from_slice(&0x0806u16.to_be_bytes());
+        let cut = &tcp_frame(1, 1000, 64)[..34];
+        for _ in 0..2 {
+            assert!(!dedup.is_duplicate(&arp, 0));
+            assert!(!dedup.is_duplicate(cut, 0));
+        }
+        assert_eq!(dedup.stats().unfingerprinted(), 4);
+        assert_eq!(dedup.stats().suppressed(), 0);
+    }
+
+    #[test]
+    fn test_layer_drops_duplicates() {
+        let dedup = dedup(10 * MS, 16);
+        let data = tcp_frame(7, 1, 64);
+        let mut packet = Packet {
+            timestamp: MS,
+            data: &data,
+            length: data.len(),
+            metadata: PacketMetadata {
+                compact_data: 0,
+                additional_info: HashMap::new(),
+            },
+            buffer_id: BufferId::new(1),
+        };
+        assert_eq!(dedup.process(&mut packet).unwrap(), LayerAction::Continue);
+        assert!(matches!(
+            dedup.process(&mut packet).unwrap(),
+            LayerAction::Drop { .. }
+        ));
+        assert!(matches!(
+            dedup.stats().telemetry(0)[0].value,
+            MetricValue::Integer(1)
+        ));
+    }
+
+    #[test]
+    fn test_zero_window_or_capacity_is_rejected() {
+        for config in [
+            DedupConfig {
+                window_ns: 0,
+                ..DedupConfig::default()
+            },
+            DedupConfig {
+                capacity: 0,
+                ..DedupConfig::default()
+            },
+        ] {
+            assert!(Deduplicator::new(config).is_err());
+        }
+    }
+}
@@ -0,0 +1,374 @@
+// filter/head_capture.rs
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::protocol::flow::{FlowKey, FlowTable};
+use crate::capture_engine::telemetry::traits::{
+    MetricType, MetricUnit, MetricValue, TelemetryData,
+};
+
+/// Telemetry name for packets captured as part of a flow's head
+pub const HEAD_PACKETS_METRIC: &str = "capture.flow_head.packets.head";
+/// Telemetry name for tail packets kept by sampling
+pub const TAIL_SAMPLED_METRIC: &str = "capture.flow_head.packets.tail_sampled";
+/// Telemetry name for tail packets dropped
+pub const TAIL_DROPPED_METRIC: &str = "capture.flow_head.packets.tail_dropped";
+
+/// What happens to packets after a flow's head has been captured
+///
+/// # Variants
+/// * `Drop` - Drop every tail packet
+/// * `Sample` - Keep one in every `one_in` tail packets of each flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TailPolicy {
+    Drop,
+    Sample { one_in: u32 },
+}
+
+/// Head-of-flow capture settings
+///
+/// A flow's head ends at whichever limit is reached first.
+///
+/// # Fields
+/// * `max_bytes` - Bytes of each flow to capture in full
+/// * `max_packets` - Packets of each flow to capture in full
+/// * `tail` - Handling of packets after the head
+/// * `max_flows` - Flows tracked at once
+/// * `idle_timeout_ns` - Idle time after which a flow is forgotten and its next packet starts a
+///   new head
+#[derive(Debug, Clone)]
+pub struct HeadCaptureConfig {
+    pub max_bytes: Option<u64>,
+    pub max_packets: Option<u64>,
+    pub tail: TailPolicy,
+    pub max_flows: usize,
+    pub idle_timeout_ns: u64,
+}
+
+impl HeadCaptureConfig {
+    /// Creates a config capturing the first `max_bytes` of each flow and dropping the rest
+    ///
+    /// # Arguments
+    /// * `max_bytes` - Bytes of each flow to capture
+    ///
+    /// # Returns
+    /// A new HeadCaptureConfig instance
+    pub fn first_bytes(max_bytes: u64) -> Self {
+        Self {
+            max_bytes: Some(max_bytes),
+            max_packets: None,
+            tail: TailPolicy::Drop,
+            max_flows: 65_536,
+            idle_timeout_ns: 120_000_000_000,
+        }
+    }
+
+    /// Validates the settings
+    ///
+    /// # Returns
+    /// An error if no head limit is set or the sampling rate is zero
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        if self.max_bytes.is_none() && self.max_packets.is_none() {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::MissingRequired),
+                "Head capture needs a byte or packet limit",
+            ));
+        }
+        if self.tail == (TailPolicy::Sample { one_in: 0 }) || self.max_flows == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Tail sampling rate and flow limit must be greater than zero",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Decision for a single packet
+///
+/// # Variants
+/// * `Head` - Part of the flow's head; capture in full
+/// * `TailSampled` - After the head, kept by sampling
+/// * `TailDropped` - After the head, dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadDecision {
+    Head,
+    TailSampled,
+    TailDropped,
+}
+
+impl HeadDecision {
+    /// Checks whether the packet should be captured
+    pub fn is_captured(&self) -> bool {
+        !matches!(self, HeadDecision::TailDropped)
+    }
+}
+
+/// Counters for head-of-flow capture
+#[derive(Debug, Default)]
+pub struct HeadCaptureStats {
+    head_packets: AtomicU64,
+    head_bytes: AtomicU64,
+    tail_sampled: AtomicU64,
+    tail_dropped: AtomicU64,
+}
+
+impl HeadCaptureStats {
+    /// Gets the number of packets captured as part of flow heads
+    pub fn head_packets(&self) -> u64 {
+        self.head_packets.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of bytes captured as part of flow heads
+    pub fn head_bytes(&self) -> u64 {
+        self.head_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of tail packets kept by sampling
+    pub fn tail_sampled(&self) -> u64 {
+        self.tail_sampled.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of tail packets dropped
+    pub fn tail_dropped(&self) -> u64 {
+        self.tail_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Exports the packet counters as telemetry
+    ///
+    /// # Arguments
+    /// * `timestamp` - Timestamp to stamp the data points with
+    pub fn telemetry(&self, timestamp: u64) -> Vec<TelemetryData> {
+        let counter = |name: &str, description: &str, value: u64| TelemetryData {
+            timestamp,
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type: MetricType::Counter,
+            value: MetricValue::Integer(value as i64),
+            attributes: HashMap::new(),
+            resource: None,
+        };
+
+        vec![
+            counter(
+                HEAD_PACKETS_METRIC,
+                "Packets captured within flow heads",
+                self.head_packets(),
+            ),
+            counter(
+                TAIL_SAMPLED_METRIC,
+                "Tail packets kept by sampling",
+                self.tail_sampled(),
+            ),
+            counter(
+                TAIL_DROPPED_METRIC,
+                "Tail packets dropped after the flow head",
+                self.tail_dropped(),
+            ),
+        ]
+    }
+}
+
+/// Progress of one flow through its head
+#[derive(Debug, Default)]
+struct FlowHead {
+    bytes: u64,
+    packets: u64,
+    tail_packets: u64,
+}
+
+/// Captures the first bytes or packets of each flow and drops or samples the rest
+///
+/// Packets are never cut: the packet that crosses the byte limit is still captured in full, so
+/// a flow's head covers at least its first `max_bytes` bytes, and every later packet is tail.
+///
+/// # Fields
+/// * `config` - Head capture settings
+/// * `flows` - Per-flow progress
+/// * `stats` - Head and tail counters
+#[derive(Debug)]
+pub struct HeadCapture {
+    config: HeadCaptureConfig,
+    flows: FlowTable<FlowHead>,
+    stats: HeadCaptureStats,
+}
+
+impl HeadCapture {
+    /// Creates a head capture filter
+    ///
+    /// # Arguments
+    /// * `config` - Head capture settings
+    ///
+    /// # Returns
+    /// A new HeadCapture, or an error if the settings are invalid
+    pub fn new(config: HeadCaptureConfig) -> Result<Self, CaptureError> {
+        config.validate()?;
+        Ok(Self {
+            flows: FlowTable::new(config.max_flows, config.idle_timeout_ns),
+            config,
+            stats: HeadCaptureStats::default(),
+        })
+    }
+
+    /// Gets the head and tail counters
+    pub fn stats(&self) -> &HeadCaptureStats {
+        &self.stats
+    }
+
+    /// Decides whether a packet is part of its flow's head
+    ///
+    /// # Arguments
+    /// * `key` - Flow the packet belongs to
+    /// * `len` - Packet length in bytes
+    /// * `now` - Packet timestamp in nanoseconds
+    ///
+    /// # Returns
+    /// Whether the packet is head, sampled tail, or dropped tail
+    pub fn decide(&mut self, key: FlowKey, len: usize, now: u64) -> HeadDecision {
+        let (flow, _) = self.flows.get_or_insert_with(key, now, FlowHead::default);
+
+        let in_head = self.config.max_bytes.is_none_or(|max| flow.bytes < max)
+            && self.config.max_packets.is_none_or(|max| flow.packets < max);
+        if in_head {
+            flow.bytes += len as u64;
+            flow.packets += 1;
+            self.stats.head_packets.fetch_add(1, Ordering::Relaxed);
+            self.stats
+                .head_bytes
+                .fetch_add(len as u64, Ordering::Relaxed);
+            return HeadDecision::Head;
+        }
+
+        flow.tail_packets += 1;
+        let sampled = match self.config.tail {
+            TailPolicy::Drop => false,
+            TailPolicy::Sample { one_in } => flow.tail_packets % one_in as u64 == 0,
+        };
+        if sampled {
+            self.stats.tail_sampled.fetch_add(1, Ordering::Relaxed);
+            HeadDecision::TailSampled
+        } else {
+            self.stats.tail_dropped.fetch_add(1, Ordering::Relaxed);
+            HeadDecision::TailDropped
+        }
+    }
+
+    /// Forgets flows idle past the timeout
+    ///
+    /// # Arguments
+    /// * `now` - Current capture time in nanoseconds
+    ///
+    /// # Returns
+    /// The number of flows forgotten
+    pub fn evict_idle(&mut self, now: u64) -> usize {
+        self.flows.evict_idle(now).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::protocol::flow::IPPROTO_TCP;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn key(port: u16) -> FlowKey {
+        FlowKey::new(
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            port,
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            443,
+            IPPROTO_TCP,
+        )
+    }
+
+    fn decisions(capture: &mut HeadCapture, key: FlowKey, lens: &[usize]) -> Vec<HeadDecision> {
+        lens.iter()
+            .enumerate()
+            .map(|(i, &len)| capture.decide(key, len, i as u64))
+            .collect()
+    }
+
+    #[test]
+    fn test_first_bytes_are_captured_then_tail_dropped() {
+        let mut capture = HeadCapture::new(HeadCaptureConfig::first_bytes(1_000)).unwrap();
+        let result = decisions(&mut capture, key(1), &[400, 400, 400, 400, 60]);
+
+        // The third packet crosses the limit and is still captured whole
+        assert_eq!(
+            result,
+            vec![
+                HeadDecision::Head,
+                HeadDecision::Head,
+                HeadDecision::Head,
+                HeadDecision::TailDropped,
+                HeadDecision::TailDropped,
+            ]
+        );
+        assert_eq!(capture.stats().head_packets(), 3);
+        assert_eq!(capture.stats().head_bytes(), 1_200);
+        assert_eq!(capture.stats().tail_dropped(), 2);
+
+        // Another flow gets its own head
+        assert_eq!(capture.decide(key(2), 1_500, 10), HeadDecision::Head);
+    }
+
+    #[test]
+    fn test_packet_limit_and_tail_sampling() {
+        let config = HeadCaptureConfig {
+            max_bytes: None,
+            max_packets: Some(2),
+            tail: TailPolicy::Sample { one_in: 3 },
+            ..HeadCaptureConfig::first_bytes(0)
+        };
+        let mut capture = HeadCapture::new(config).unwrap();
+        let result = decisions(&mut capture, key(1), &[60; 8]);
+
+        assert_eq!(
+            result,
+            vec![
+                HeadDecision::Head,
+                HeadDecision::Head,
+                HeadDecision::TailDropped,
+                HeadDecision::TailDropped,
+                HeadDecision::TailSampled,
+                HeadDecision::TailDropped,
+                HeadDecision::TailDropped,
+                HeadDecision::TailSampled,
+            ]
+        );
+        assert_eq!(capture.stats().tail_sampled(), 2);
+        assert_eq!(capture.stats().tail_dropped(), 4);
+        assert_eq!(capture.stats().telemetry(0).len(), 3);
+    }
+
+    #[test]
+    fn test_idle_flow_starts_a_new_head() {
+        let config = HeadCaptureConfig {
+            idle_timeout_ns: 100,
+            ..HeadCaptureConfig::first_bytes(100)
+        };
+        let mut capture = HeadCapture::new(config).unwrap();
+        assert_eq!(capture.decide(key(1), 200, 0), HeadDecision::Head);
+        assert_eq!(capture.decide(key(1), 200, 10), HeadDecision::TailDropped);
+        assert_eq!(capture.evict_idle(1_000), 1);
+        assert_eq!(capture.decide(key(1), 200, 1_000), HeadDecision::Head);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let none = HeadCaptureConfig {
+            max_bytes: None,
+            ..HeadCaptureConfig::first_bytes(1)
+        };
+        assert!(HeadCapture::new(none).is_err());
+        let zero_rate = HeadCaptureConfig {
+            tail: TailPolicy::Sample { one_in: 0 },
+            ..HeadCaptureConfig::first_bytes(1)
+        };
+        assert!(HeadCapture::new(zero_rate).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+// filter/payload_limit.rs
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::capture::packet_layer::{LayerAction, PacketLayer};
+use crate::capture_engine::protocol::headers::{split_headers, HeaderSplit};
+use crate::traits::Packet;
+
+/// Metadata key holding the number of header bytes retained
+pub const HEADER_LEN_KEY: &str = "capture.header_len";
+/// Metadata key holding the number of payload bytes retained
+pub const PAYLOAD_KEPT_KEY: &str = "capture.payload_kept";
+/// Metadata key holding the payload length before truncation
+pub const PAYLOAD_LEN_KEY: &str = "capture.payload_len";
+
+/// Where a packet was cut
+///
+/// # Fields
+/// * `header_len` - Header bytes, all of which are kept
+/// * `payload_kept` - Payload bytes kept after the headers
+/// * `payload_len` - Payload bytes in the captured frame before truncation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadCut {
+    pub header_len: usize,
+    pub payload_kept: usize,
+    pub payload_len: usize,
+}
+
+impl PayloadCut {
+    /// Gets the number of bytes kept
+    pub fn keep_len(&self) -> usize {
+        self.header_len + self.payload_kept
+    }
+}
+
+/// Truncation that keeps every header plus at most a fixed number of payload bytes
+///
+/// Unlike a byte snap length, the cut point is found by parsing the frame, so variable-length
+/// stacks (IP and TCP options, IPv6 extension headers, VLAN tags, tunnels) never lose header
+/// bytes. The split is recorded in the packet metadata under `HEADER_LEN_KEY`,
+/// `PAYLOAD_KEPT_KEY` and `PAYLOAD_LEN_KEY`.
+///
+/// # Fields
+/// * `max_payload` - Payload bytes kept after the innermost header
+/// * `truncated` - Packets that lost payload bytes
+#[derive(Debug)]
+pub struct PayloadLimit {
+    max_payload: usize,
+    truncated: AtomicU64,
+}
+
+impl PayloadLimit {
+    /// Creates a limit keeping `max_payload` payload bytes per packet
+    ///
+    /// # Arguments
+    /// * `max_payload` - Payload bytes to keep; zero keeps headers only
+    ///
+    /// # Returns
+    /// A new PayloadLimit instance
+    pub fn new(max_payload: usize) -> Self {
+        Self {
+            max_payload,
+            truncated: AtomicU64::new(0),
+        }
+    }
+
+    /// Gets the configured payload limit
+    pub fn max_payload(&self) -> usize {
+        self.max_payload
+    }
+
+    /// Gets the number of packets that lost payload bytes
+    pub fn truncated(&self) -> u64 {
+        self.truncated.load(Ordering::Relaxed)
+    }
+
+    /// Computes where a frame is cut
+    ///
+    /// # Arguments
+    /// * `frame` - Captured frame, starting at the Ethernet header
+    ///
+    /// # Returns
+    /// The header/payload split and the payload bytes kept
+    pub fn cut(&self, frame: &[u8]) -> PayloadCut {
+        let HeaderSplit { header_len, .. } = split_headers(frame);
+        let payload_len = frame.len() - header_len;
+        PayloadCut {
+            header_len,
+            payload_kept: payload_len.min(self.max_payload),
+            payload_len,
+        }
+    }
+}
+
+impl PacketLayer for PayloadLimit {
+    fn name(&self) -> &str {
+        "payload_limit"
+    }
+
+    fn process(&self, packet: &mut Packet<'_>) -> Result<LayerAction, CaptureError> {
+        let cut = self.cut(packet.data);
+        if cut.payload_kept < cut.payload_len {
+            packet.data = &packet.data[..cut.keep_len()];
+            self.truncated.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let info = &mut packet.metadata.additional_info;
+        info.insert(HEADER_LEN_KEY.to_string(), cut.header_len.to_string());
+        info.insert(PAYLOAD_KEPT_KEY.to_string(), cut.payload_kept.to_string());
+        info.insert(PAYLOAD_LEN_KEY.to_string(), cut.payload_len.to_string());
+        Ok(LayerAction::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{BufferId, PacketMetadata};
+    use std::collections::HashMap;
+
+    fn packet(data: &[u8]) -> Packet<'_> {
+        Packet {
+            timestamp: 0,
+            data,
+            length: data.len(),
+            metadata: PacketMetadata {
+                compact_data: 0,
+                additional_info: HashMap::new(),
+            },
+            buffer_id: BufferId::new(0),
+        }
+    }
+
+    fn ethernet_ipv4() -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        frame
+    }
+
+    fn ipv4(protocol: u8, options: usize) -> Vec<u8> {
+        let mut header = vec![0u8; 20 + options];
+        header[0] = 0x40 | ((20 + options) / 4) as u8;
+        header[9] = protocol;
+        header
+    }
+
+    fn udp(dst_port: u16) -> Vec<u8> {
+        let mut header = vec![0u8; 8];
+        header[2..4].copy_from_slice(&dst_port.to_be_bytes());
+        header
+    }
+
+    fn tcp(options: usize) -> Vec<u8> {
+        let mut header = vec![0u8; 20 + options];
+        header[12] = (((20 + options) / 4) as u8) << 4;
+        header
+    }
+
+    fn metadata(packet: &Packet<'_>, key: &str) -> usize {
+        packet.metadata.additional_info[key].parse().unwrap()
+    }
+
+    #[test]
+    fn test_ip_options_headers_retained() {
+        // 40 bytes of IP options and 12 of TCP options; a 64 byte snaplen would cut the TCP header
+        let mut frame = ethernet_ipv4();
+        frame.extend(ipv4(6, 40));
+        frame.extend(tcp(12));
+        let header_len = frame.len();
+        frame.extend((0..100u8).collect::<Vec<_>>());
+
+        let limit = PayloadLimit::new(16);
+        let mut packet = packet(&frame);
+        assert_eq!(limit.process(&mut packet).unwrap(), LayerAction::Continue);
+
+        assert_eq!(header_len, 14 + 60 + 32);
+        assert_eq!(packet.data.len(), header_len + 16);
+        assert_eq!(packet.data, &frame[..header_len + 16]);
+        assert_eq!(metadata(&packet, HEADER_LEN_KEY), header_len);
+        assert_eq!(metadata(&packet, PAYLOAD_KEPT_KEY), 16);
+        assert_eq!(metadata(&packet, PAYLOAD_LEN_KEY), 100);
+        assert_eq!(limit.truncated(), 1);
+    }
+
+    #[test]
+    fn test_vxlan_inner_headers_retained() {
+        let mut inner = vec![0u8; 12];
+        inner.extend_from_slice(&[0x81, 0x00, 0x00, 0x05, 0x08, 0x00]);
+        inner.extend(ipv4(17, 0));
+        inner.extend(udp(53));
+
+        let mut frame = ethernet_ipv4();
+        frame.extend(ipv4(17, 0));
+        frame.extend(udp(4789));
+        frame.extend_from_slice(&[0x08, 0, 0, 0, 0, 0, 0x2a, 0]);
+        frame.extend(&inner);
+        let header_len = frame.len();
+        frame.extend_from_slice(&[0xab; 40]);
+
+        let limit = PayloadLimit::new(8);
+        let mut packet = packet(&frame);
+        limit.process(&mut packet).unwrap();
+
+        assert_eq!(header_len, 14 + 20 + 8 + 8 + 18 + 20 + 8);
+        assert_eq!(metadata(&packet, HEADER_LEN_KEY), header_len);
+        assert_eq!(packet.data.len(), header_len + 8);
+        assert_eq!(&packet.data[header_len..], &[0xab; 8]);
+    }
+
+    #[test]
+    fn test_short_payload_untouched() {
+        let mut frame = ethernet_ipv4();
+        frame.extend(ipv4(17, 0));
+        frame.extend(udp(53));
+        frame.extend_from_slice(&[1, 2, 3]);
+
+        let limit = PayloadLimit::new(16);
+        let mut packet = packet(&frame);
+        limit.process(&mut packet).unwrap();
+
+        assert_eq!(packet.data.len(), frame.len());
+        assert_eq!(metadata(&packet, PAYLOAD_KEPT_KEY), 3);
+        assert_eq!(limit.truncated(), 0);
+    }
+}
@@ -0,0 +1,1221 @@
+// filter/ruleset.rs
+use std::collections::{HashMap, HashSet};
+use std::mem::size_of;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::SystemTime;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::control::traits::{
+    FilterAction, FilterCondition, FilterConfig, FilterRule, Ipv6Prefix,
+};
+use crate::capture_engine::filter::stats::{RuleCounters, RuleId, RuleMatchStats};
+use crate::capture_engine::protocol::flow::{IPPROTO_TCP, IPPROTO_UDP};
+
+/// Instructions of the fixed BPF prologue: ethertype load and check, accept and reject returns
+const BPF_PROLOGUE_INSNS: usize = 4;
+/// Kernel limit on classic BPF program length
+pub const BPF_MAX_INSNS: usize = 4096;
+/// Bloom filter bits allocated per indexed address
+const BLOOM_BITS_PER_ADDRESS: usize = 16;
+/// Hash functions applied per Bloom filter lookup
+const BLOOM_HASHES: u32 = 4;
+
+/// A versioned set of filter rules pushed by the control plane
+///
+/// # Fields
+/// * `id` - Ruleset identifier, reported in session reports once active
+/// * `rules` - Filter rules; lower priority values are evaluated first
+/// * `default_action` - Action for packets matching no rule
+#[derive(Debug, Clone)]
+pub struct FilterRuleset {
+    pub id: String,
+    pub rules: Vec<FilterRule>,
+    pub default_action: FilterAction,
+}
+
+impl FilterRuleset {
+    /// Creates a ruleset from a control-plane filter configuration
+    ///
+    /// # Arguments
+    /// * `id` - Ruleset identifier
+    /// * `config` - Filter configuration
+    ///
+    /// # Returns
+    /// A new FilterRuleset instance
+    pub fn from_config(id: &str, config: FilterConfig) -> Self {
+        Self {
+            id: id.to_string(),
+            rules: config.rules,
+            default_action: config.default_action,
+        }
+    }
+}
+
+/// Filtering capabilities of the capture hardware
+///
+/// # Fields
+/// * `max_rules` - Rules the NIC or kernel filter can hold
+/// * `max_conditions_per_rule` - Conditions a single offloaded rule may test
+/// * `supports_ipv6` - Whether IPv6 address matches can be offloaded
+/// * `max_bpf_instructions` - Length limit of the kernel BPF program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffloadCapabilities {
+    pub max_rules: usize,
+    pub max_conditions_per_rule: usize,
+    pub supports_ipv6: bool,
+    pub max_bpf_instructions: usize,
+}
+
+impl OffloadCapabilities {
+    /// Capabilities of a host without filter offload; everything runs in software
+    pub fn none() -> Self {
+        Self {
+            max_rules: 0,
+            max_conditions_per_rule: 0,
+            supports_ipv6: false,
+            max_bpf_instructions: 0,
+        }
+    }
+}
+
+impl Default for OffloadCapabilities {
+    fn default() -> Self {
+        Self {
+            max_rules: 1024,
+            max_conditions_per_rule: 4,
+            supports_ipv6: false,
+            max_bpf_instructions: BPF_MAX_INSNS,
+        }
+    }
+}
+
+/// A problem found while validating a ruleset
+///
+/// # Fields
+/// * `rule_id` - Rule the finding applies to, None for the ruleset as a whole
+/// * `message` - Description of the problem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RulesetFinding {
+    pub rule_id: Option<String>,
+    pub message: String,
+}
+
+/// Result of a ruleset dry run
+///
+/// # Fields
+/// * `ruleset_id` - Ruleset that was validated
+/// * `rule_count` - Number of rules in the ruleset
+/// * `errors` - Problems that prevent activation
+/// * `warnings` - Problems that do not prevent activation
+/// * `offloaded_rules` - Ids of rules that fit hardware offload
+/// * `offload_coverage` - Fraction of rules offloaded, 0.0 to 1.0
+/// * `bpf_instructions` - Estimated length of the BPF program for the offloaded rules
+/// * `estimated_memory_bytes` - Memory the compiled ruleset is estimated to use
+#[derive(Debug, Clone, PartialEq)]
+pub struct RulesetReport {
+    pub ruleset_id: String,
+    pub rule_count: usize,
+    pub errors: Vec<RulesetFinding>,
+    pub warnings: Vec<RulesetFinding>,
+    pub offloaded_rules: Vec<String>,
+    pub offload_coverage: f64,
+    pub bpf_instructions: usize,
+    pub estimated_memory_bytes: usize,
+}
+
+impl RulesetReport {
+    /// Checks whether the ruleset can be activated
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Packet fields a ruleset is evaluated against
+///
+/// # Fields
+/// * `src_ip` - Source address
+/// * `dst_ip` - Destination address
+/// * `src_port` - Source port, 0 for protocols without ports
+/// * `dst_port` - Destination port, 0 for protocols without ports
+/// * `protocol` - Transport protocol number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketFields {
+    pub src_ip: IpAddr,
+    pub dst_ip: IpAddr,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub protocol: u8,
+}
+
+impl PacketFields {
+    /// Extracts the fields of an IPv4 or IPv6 packet
+    ///
+    /// IPv6 extension headers (hop-by-hop, routing, destination options, fragment and
+    /// authentication) are followed to the real transport protocol. Non-first fragments and
+    /// packets truncated before the transport header report their protocol with ports of 0.
+    ///
+    /// # Arguments
+    /// * `packet` - Packet starting at the IP header
+    ///
+    /// # Returns
+    /// The packet fields, or None if the IP header is truncated or the version is unknown
+    pub fn from_ip_packet(packet: &[u8]) -> Option<Self> {
+        let (src_ip, dst_ip, protocol, transport) = match packet.first()? >> 4 {
+            4 => {
+                let ihl = (packet[0] & 0x0f) as usize * 4;
+                let header = packet.get(..20)?;
+                let src = Ipv4Addr::new(header[12], header[13], header[14], header[15]);
+                let dst = Ipv4Addr::new(header[16], header[17], header[18], header[19]);
+                let fragment_offset = u16::from_be_bytes([header[6], header[7]]) & 0x1fff;
+                let transport = packet.get(ihl.max(20)..).filter(|_| fragment_offset == 0);
+                (IpAddr::V4(src), IpAddr::V4(dst), header[9], transport)
+            }
+            6 => {
+                let header = packet.get(..40)?;
+                let src: [u8; 16] = header[8..24].try_into().ok()?;
+                let dst: [u8; 16] = header[24..40].try_into().ok()?;
+                let (protocol, transport) = ipv6_transport(packet, header[6]);
+                (
+                    IpAddr::V6(Ipv6Addr::from(src)),
+                    IpAddr::V6(Ipv6Addr::from(dst)),
+                    protocol,
+                    transport,
+                )
+            }
+            _ => return None,
+        };
+
+        let ports = transport
+            .filter(|_| matches!(protocol, IPPROTO_TCP | IPPROTO_UDP))
+            .and_then(|transport| transport.get(..4))
+            .map(|ports| {
+                (
+                    u16::from_be_bytes([ports[0], ports[1]]),
+                    u16::from_be_bytes([ports[2], ports[3]]),
+                )
+            });
+        let (src_port, dst_port) = ports.unwrap_or((0, 0));
+        Some(Self {
+            src_ip,
+            dst_ip,
+            src_port,
+            dst_port,
+            protocol,
+        })
+    }
+}
+
+/// Walks the IPv6 extension header chain
+///
+/// Returns the transport protocol and its header, which is None for non-first fragments and
+/// chains truncated before the transport header.
+fn ipv6_transport(packet: &[u8], first: u8) -> (u8, Option<&[u8]>) {
+    let mut next = first;
+    let mut offset = 40;
+    let mut first_fragment = true;
+    loop {
+        let len = match next {
+            // Hop-by-hop, routing and destination options
+            0 | 43 | 60 => packet.get(offset + 1).map(|len| (*len as usize + 1) * 8),
+            44 => {
+                let offset_field = packet
+                    .get(offset + 2..offset + 4)
+                    .map(|field| u16::from_be_bytes([field[0], field[1]]) >> 3);
+                first_fragment &= offset_field == Some(0);
+                offset_field.map(|_| 8)
+            }
+            // Authentication header
+            51 => packet.get(offset + 1).map(|len| (*len as usize + 2) * 4),
+            protocol => {
+                let transport = packet.get(offset..).filter(|_| first_fragment);
+                return (protocol, transport);
+            }
+        };
+        match (len, packet.get(offset)) {
+            (Some(len), Some(&header_next)) => {
+                next = header_next;
+                offset += len;
+            }
+            // The chain is cut off before the transport protocol is known
+            _ => return (next, None),
+        }
+    }
+}
+
+/// Bloom filter over the addresses referenced by address conditions
+#[derive(Debug, Clone)]
+struct AddressBloom {
+    bits: Vec<u64>,
+}
+
+impl AddressBloom {
+    fn new(addresses: &HashSet<IpAddr>) -> Self {
+        let words = (addresses.len() * BLOOM_BITS_PER_ADDRESS)
+            .div_ceil(64)
+            .max(1);
+        let mut bloom = Self {
+            bits: vec![0; words],
+        };
+        for addr in addresses {
+            for bit in bloom.positions(addr) {
+                bloom.bits[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        bloom
+    }
+
+    fn may_contain(&self, addr: &IpAddr) -> bool {
+        self.positions(addr)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn positions(&self, addr: &IpAddr) -> impl Iterator<Item = usize> {
+        let octets = match addr {
+            IpAddr::V4(v4) => v4.to_ipv6_mapped().octets(),
+            IpAddr::V6(v6) => v6.octets(),
+        };
+        // Double hashing from two FNV-1a variants
+        let h1 = fnv1a(&octets, 0xcbf2_9ce4_8422_2325);
+        let h2 = fnv1a(&octets, 0x8422_2325_cbf2_9ce4) | 1;
+        let size = self.bits.len() * 64;
+        (0..BLOOM_HASHES as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % size as u64) as usize)
+    }
+}
+
+fn fnv1a(bytes: &[u8], seed: u64) -> u64 {
+    bytes.iter().fold(seed, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// A ruleset compiled for evaluation
+///
+/// Rules are kept in priority order. Rules testing addresses are indexed by address so a packet
+/// only considers the rules that name one of its addresses, and a Bloom filter over those
+/// addresses skips the index entirely for the common case of unlisted hosts.
+///
+/// # Fields
+/// * `rules` - Rules in evaluation order
+/// * `address_rules` - Positions in `rules` of the rules testing each address
+/// * `generic_rules` - Positions in `rules` of the rules without address conditions
+/// * `bloom` - Bloom filter over indexed addresses
+/// * `default_action` - Action for packets matching no rule
+/// * `counters` - Match counters of each rule, parallel to `rules`
+#[derive(Debug, Clone)]
+pub struct CompiledRuleset {
+    rules: Vec<FilterRule>,
+    address_rules: HashMap<IpAddr, Vec<usize>>,
+    generic_rules: Vec<usize>,
+    bloom: AddressBloom,
+    default_action: FilterAction,
+    counters: Vec<RuleCounters>,
+}
+
+impl CompiledRuleset {
+    /// Compiles a ruleset
+    ///
+    /// # Arguments
+    /// * `ruleset` - Ruleset to compile
+    ///
+    /// # Returns
+    /// The compiled ruleset
+    pub fn compile(ruleset: &FilterRuleset) -> Self {
+        let mut rules = ruleset.rules.clone();
+        rules.sort_by_key(|rule| rule.priority);
+
+        let mut address_rules: HashMap<IpAddr, Vec<usize>> = HashMap::new();
+        let mut generic_rules = Vec::new();
+        for (index, rule) in rules.iter().enumerate() {
+            let addresses: Vec<IpAddr> = rule
+                .conditions
+                .iter()
+                .filter_map(|condition| match condition {
+                    FilterCondition::SourceIp(addr) | FilterCondition::DestIp(addr) => Some(*addr),
+                    _ => None,
+                })
+                .collect();
+            if addresses.is_empty() {
+                generic_rules.push(index);
+            }
+            for addr in addresses {
+                let entry = address_rules.entry(addr).or_default();
+                if entry.last() != Some(&index) {
+                    entry.push(index);
+                }
+            }
+        }
+        let bloom = AddressBloom::new(&address_rules.keys().copied().collect());
+
+        Self {
+            counters: (0..rules.len()).map(|_| RuleCounters::default()).collect(),
+            rules,
+            address_rules,
+            generic_rules,
+            bloom,
+            default_action: ruleset.default_action.clone(),
+        }
+    }
+
+    /// Finds the action for a packet
+    ///
+    /// # Arguments
+    /// * `packet` - Packet fields
+    /// * `at` - Capture time of the packet, recorded as the matching rule's last match; a
+    ///   batch may share one clock reading
+    ///
+    /// # Returns
+    /// The action of the first matching rule, or the default action
+    pub fn evaluate(&self, packet: &PacketFields, at: SystemTime) -> &FilterAction {
+        let indexed = |addr: &IpAddr| {
+            if !self.bloom.may_contain(addr) {
+                return &[][..];
+            }
+            self.address_rules.get(addr).map_or(&[][..], Vec::as_slice)
+        };
+        let mut candidates = Candidates {
+            lists: [
+                &self.generic_rules,
+                indexed(&packet.src_ip),
+                indexed(&packet.dst_ip),
+            ],
+        };
+
+        candidates
+            .find(|&index| {
+                self.counters[index].record_evaluated();
+                rule_matches(&self.rules[index], packet)
+            })
+            .map(|index| {
+                self.counters[index].record_match(at);
+                &self.rules[index].action
+            })
+            .unwrap_or(&self.default_action)
+    }
+
+    /// Gets the match counts of every rule
+    ///
+    /// Rules that were never evaluated are included with zero counts, so dead rules stand out.
+    /// Counts of rules sharing an id are combined.
+    ///
+    /// # Returns
+    /// Match counts keyed by rule id
+    pub fn rule_stats(&self) -> HashMap<RuleId, RuleMatchStats> {
+        let mut stats: HashMap<RuleId, RuleMatchStats> = HashMap::new();
+        for (rule, counters) in self.rules.iter().zip(&self.counters) {
+            let counts = counters.snapshot();
+            let entry = stats.entry(rule.id.clone()).or_default();
+            entry.evaluated += counts.evaluated;
+            entry.matched += counts.matched;
+            entry.last_match = entry.last_match.max(counts.last_match);
+        }
+        stats
+    }
+
+    /// Zeroes the match counts of every rule
+    pub fn reset_stats(&self) {
+        self.counters.iter().for_each(RuleCounters::reset);
+    }
+
+    /// Finds the action for a raw IP packet
+    ///
+    /// # Arguments
+    /// * `packet` - Packet starting at the IP header
+    /// * `at` - Capture time of the packet
+    ///
+    /// # Returns
+    /// The action of the first matching rule, or the default action if none matches or the
+    /// packet cannot be parsed
+    pub fn evaluate_packet(&self, packet: &[u8], at: SystemTime) -> &FilterAction {
+        match PacketFields::from_ip_packet(packet) {
+            Some(fields) => self.evaluate(&fields, at),
+            None => &self.default_action,
+        }
+    }
+
+    /// Estimates the memory used by the compiled ruleset in bytes
+    pub fn estimated_memory_bytes(&self) -> usize {
+        let rules: usize = self
+            .rules
+            .iter()
+            .map(|rule| {
+                size_of::<FilterRule>()
+                    + rule.id.len()
+                    + rule.conditions.len() * size_of::<FilterCondition>()
+            })
+            .sum();
+        let index: usize = self
+            .address_rules
+            .values()
+            .map(|indexed| {
+                size_of::<IpAddr>() + size_of::<Vec<usize>>() + indexed.len() * size_of::<usize>()
+            })
+            .sum();
+        rules + index + self.generic_rules.len() * size_of::<usize>() + self.bloom.bits.len() * 8
+    }
+}
+
+/// Rule positions drawn from ascending lists, merged in ascending order without duplicates
+///
+/// # Fields
+/// * `lists` - Positions not yet yielded from each list
+struct Candidates<'a> {
+    lists: [&'a [usize]; 3],
+}
+
+impl Iterator for Candidates<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let next = *self.lists.iter().filter_map(|list| list.first()).min()?;
+        for list in &mut self.lists {
+            if list.first() == Some(&next) {
+                *list = &list[1..];
+            }
+        }
+        Some(next)
+    }
+}
+
+fn rule_matches(rule: &FilterRule, packet: &PacketFields) -> bool {
+    rule.conditions.iter().all(|condition| match condition {
+        FilterCondition::SourceIp(addr) => packet.src_ip == *addr,
+        FilterCondition::DestIp(addr) => packet.dst_ip == *addr,
+        FilterCondition::SourcePort(port) => packet.src_port == *port,
+        FilterCondition::DestPort(port) => packet.dst_port == *port,
+        FilterCondition::Protocol(protocol) => packet.protocol == *protocol,
+        FilterCondition::Ipv6Address { src, dst } => match (packet.src_ip, packet.dst_ip) {
+            (IpAddr::V6(src_ip), IpAddr::V6(dst_ip)) => {
+                src.is_none_or(|prefix| prefix.contains(&src_ip))
+                    && dst.is_none_or(|prefix| prefix.contains(&dst_ip))
+            }
+            _ => false,
+        },
+    })
+}
+
+/// Validates and compiles a ruleset without activating it
+///
+/// # Arguments
+/// * `ruleset` - Ruleset to check
+/// * `hardware` - Offload capabilities of the capture hardware
+///
+/// # Returns
+/// The dry-run report, or an error if the ruleset has no id
+pub fn dry_run(
+    ruleset: &FilterRuleset,
+    hardware: &OffloadCapabilities,
+) -> Result<RulesetReport, CaptureError> {
+    if ruleset.id.trim().is_empty() {
+        return Err(*CaptureError::new(
+            CaptureErrorKind::Configuration(ConfigErrorKind::MissingRequired),
+            "Filter ruleset requires an id",
+        ));
+    }
+
+    let compiled = CompiledRuleset::compile(ruleset);
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    lint(
+        &compiled.rules,
+        &ruleset.default_action,
+        &mut errors,
+        &mut warnings,
+    );
+
+    let mut offloaded_rules = Vec::new();
+    let mut bpf_instructions = BPF_PROLOGUE_INSNS;
+    for rule in &compiled.rules {
+        match offload_blocker(rule, hardware) {
+            Some(reason) => warnings.push(finding(
+                Some(&rule.id),
+                &format!("Rule runs in software: {}", reason),
+            )),
+            None if offloaded_rules.len() >= hardware.max_rules => warnings.push(finding(
+                Some(&rule.id),
+                &format!(
+                    "Rule runs in software: hardware holds {} rules",
+                    hardware.max_rules
+                ),
+            )),
+            None => {
+                let insns = bpf_rule_instructions(rule);
+                if bpf_instructions + insns > hardware.max_bpf_instructions {
+                    warnings.push(finding(
+                        Some(&rule.id),
+                        "Rule runs in software: BPF program length limit reached",
+                    ));
+                } else {
+                    bpf_instructions += insns;
+                    offloaded_rules.push(rule.id.clone());
+                }
+            }
+        }
+    }
+    if offloaded_rules.is_empty() {
+        bpf_instructions = 0;
+    }
+
+    let offload_coverage = if compiled.rules.is_empty() {
+        1.0
+    } else {
+        offloaded_rules.len() as f64 / compiled.rules.len() as f64
+    };
+
+    Ok(RulesetReport {
+        ruleset_id: ruleset.id.clone(),
+        rule_count: compiled.rules.len(),
+        errors,
+        warnings,
+        offloaded_rules,
+        offload_coverage,
+        bpf_instructions,
+        estimated_memory_bytes: compiled.estimated_memory_bytes(),
+    })
+}
+
+fn lint(
+    rules: &[FilterRule],
+    default_action: &FilterAction,
+    errors: &mut Vec<RulesetFinding>,
+    warnings: &mut Vec<RulesetFinding>,
+) {
+    let mut ids = HashSet::new();
+    for (index, rule) in rules.iter().enumerate() {
+        if rule.id.trim().is_empty() {
+            errors.push(finding(None, "Rule without an id"));
+        } else if !ids.insert(rule.id.as_str()) {
+            errors.push(finding(Some(&rule.id), "Duplicate rule id"));
+        }
+        if let FilterAction::Sample { rate } | FilterAction::SampleFlow { rate } = rule.action {
+            if !(rate > 0.0 && rate <= 1.0) {
+                errors.push(finding(
+                    Some(&rule.id),
+                    "Sample rate must be greater than 0 and at most 1",
+                ));
+            }
+        }
+        if let Some(reason) = contradiction(rule) {
+            errors.push(finding(
+                Some(&rule.id),
+                &format!("Rule can never match: {}", reason),
+            ));
+            continue;
+        }
+        if let Some(shadowing) = rules[..index]
+            .iter()
+            .find(|earlier| contradiction(earlier).is_none() && covers(earlier, rule))
+        {
+            warnings.push(finding(
+                Some(&rule.id),
+                &format!("Rule is unreachable, shadowed by {}", shadowing.id),
+            ));
+        }
+    }
+    if let FilterAction::Sample { rate } | FilterAction::SampleFlow { rate } = default_action {
+        if !(*rate > 0.0 && *rate <= 1.0) {
+            errors.push(finding(
+                None,
+                "Default sample rate must be greater than 0 and at most 1",
+            ));
+        }
+    }
+}
+
+/// Describes why a rule's conditions can never all hold, if they cannot
+fn contradiction(rule: &FilterRule) -> Option<String> {
+    let mut protocol = None;
+    let mut fields: HashMap<&'static str, String> = HashMap::new();
+    let mut has_port = false;
+    let mut ipv6_prefixes = Vec::new();
+    for condition in &rule.conditions {
+        let (field, value) = match condition {
+            FilterCondition::SourceIp(addr) => ("source address", addr.to_string()),
+            FilterCondition::DestIp(addr) => ("destination address", addr.to_string()),
+            FilterCondition::SourcePort(port) => {
+                has_port = true;
+                ("source port", port.to_string())
+            }
+            FilterCondition::DestPort(port) => {
+                has_port = true;
+                ("destination port", port.to_string())
+            }
+            FilterCondition::Protocol(proto) => {
+                protocol = Some(*proto);
+                ("protocol", proto.to_string())
+            }
+            FilterCondition::Ipv6Address { src, dst } => {
+                ipv6_prefixes.push((*src, *dst));
+                continue;
+            }
+        };
+        if let Some(existing) = fields.insert(field, value.clone()) {
+            if existing != value {
+                return Some(format!("{} is both {} and {}", field, existing, value));
+            }
+        }
+    }
+    for (src, dst) in ipv6_prefixes {
+        for (field, prefix) in [("source address", src), ("destination address", dst)] {
+            let (Some(prefix), Some(addr)) = (prefix, fields.get(field)) else {
+                continue;
+            };
+            let inside = match addr.parse() {
+                Ok(IpAddr::V6(v6)) => prefix.contains(&v6),
+                _ => false,
+            };
+            if !inside {
+                return Some(format!("{} {} is outside {}", field, addr, prefix));
+            }
+        }
+    }
+    match protocol {
+        Some(proto) if has_port && proto != IPPROTO_TCP && proto != IPPROTO_UDP => {
+            Some(format!("protocol {} has no ports", proto))
+        }
+        _ => None,
+    }
+}
+
+/// Checks whether every packet matching `later` also matches `earlier`
+fn covers(earlier: &FilterRule, later: &FilterRule) -> bool {
+    earlier.conditions.iter().all(|condition| {
+        later
+            .conditions
+            .iter()
+            .any(|other| same_condition(condition, other))
+    })
+}
+
+fn same_condition(a: &FilterCondition, b: &FilterCondition) -> bool {
+    match (a, b) {
+        (FilterCondition::SourceIp(x), FilterCondition::SourceIp(y))
+        | (FilterCondition::DestIp(x), FilterCondition::DestIp(y)) => x == y,
+        (FilterCondition::SourcePort(x), FilterCondition::SourcePort(y))
+        | (FilterCondition::DestPort(x), FilterCondition::DestPort(y)) => x == y,
+        (FilterCondition::Protocol(x), FilterCondition::Protocol(y)) => x == y,
+        (
+            FilterCondition::Ipv6Address { src: xs, dst: xd },
+            FilterCondition::Ipv6Address { src: ys, dst: yd },
+        ) => xs == ys && xd == yd,
+        _ => false,
+    }
+}
+
+/// Describes why a rule cannot be offloaded, if it cannot
+pub(crate) fn offload_blocker(rule: &FilterRule, hardware: &OffloadCapabilities) -> Option<String> {
+    if hardware.max_rules == 0 {
+        return Some("hardware offload unavailable".to_string());
+    }
+    match rule.action {
+        FilterAction::Accept | FilterAction::Drop => {}
+        FilterAction::Mirror => return Some("mirror actions are not offloadable".to_string()),
+        FilterAction::Sample { .. } | FilterAction::SampleFlow { .. } => {
+            return Some("sample actions are not offloadable".to_string())
+        }
+    }
+    if rule.conditions.len() > hardware.max_conditions_per_rule {
+        return Some(format!(
+            "{} conditions exceed the hardware limit of {}",
+            rule.conditions.len(),
+            hardware.max_conditions_per_rule
+        ));
+    }
+    let ipv6 = rule.conditions.iter().any(|condition| {
+        matches!(
+            condition,
+            FilterCondition::SourceIp(IpAddr::V6(_))
+                | FilterCondition::DestIp(IpAddr::V6(_))
+                | FilterCondition::Ipv6Address { .. }
+        )
+    });
+    if ipv6 && !hardware.supports_ipv6 {
+        return Some("IPv6 address matches are not offloadable".to_string());
+    }
+    None
+}
+
+/// Instructions needed to test a rule's conditions and return its verdict
+fn bpf_rule_instructions(rule: &FilterRule) -> usize {
+    let tests: usize = rule
+        .conditions
+        .iter()
+        .map(|condition| match condition {
+            // Load and compare
+            FilterCondition::Protocol(_) => 2,
+            // Load the IP header length, then load and compare the port
+            FilterCondition::SourcePort(_) | FilterCondition::DestPort(_) => 3,
+            FilterCondition::SourceIp(IpAddr::V4(_)) | FilterCondition::DestIp(IpAddr::V4(_)) => 2,
+            // Four 32-bit words
+            FilterCondition::SourceIp(IpAddr::V6(_)) | FilterCondition::DestIp(IpAddr::V6(_)) => 8,
+            FilterCondition::Ipv6Address { src, dst } => [src, dst]
+                .into_iter()
+                .flatten()
+                .map(prefix_instructions)
+                .sum(),
+        })
+        .sum();
+    tests + 1
+}
+
+/// Load and compare for each 32-bit word the prefix covers, plus a mask for a partial word
+fn prefix_instructions(prefix: &Ipv6Prefix) -> usize {
+    let len = prefix.prefix_len() as usize;
+    2 * len.div_ceil(32) + usize::from(!len.is_multiple_of(32))
+}
+
+fn finding(rule_id: Option<&str>, message: &str) -> RulesetFinding {
+    RulesetFinding {
+        rule_id: rule_id.map(str::to_string),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::packet_filter::PacketFilter;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn rule(
+        id: &str,
+        priority: u32,
+        conditions: Vec<FilterCondition>,
+        action: FilterAction,
+    ) -> FilterRule {
+        FilterRule {
+            id: id.to_string(),
+            priority,
+            conditions,
+            action,
+        }
+    }
+
+    fn ruleset(rules: Vec<FilterRule>) -> FilterRuleset {
+        FilterRuleset {
+            id: "ruleset-v4".to_string(),
+            rules,
+            default_action: FilterAction::Accept,
+        }
+    }
+
+    fn host(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))
+    }
+
+    fn clean() -> FilterRuleset {
+        ruleset(vec![
+            rule(
+                "drop-scanner",
+                1,
+                vec![FilterCondition::SourceIp(host(66))],
+                FilterAction::Drop,
+            ),
+            rule(
+                "keep-dns",
+                2,
+                vec![
+                    FilterCondition::Protocol(IPPROTO_UDP),
+                    FilterCondition::DestPort(53),
+                ],
+                FilterAction::Accept,
+            ),
+            rule(
+                "drop-ssh",
+                3,
+                vec![
+                    FilterCondition::Protocol(IPPROTO_TCP),
+                    FilterCondition::DestPort(22),
+                ],
+                FilterAction::Drop,
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_clean_ruleset_report() {
+        let report =
+            PacketFilter::validate_and_compile(&clean(), &OffloadCapabilities::default()).unwrap();
+
+        assert!(report.is_valid());
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.rule_count, 3);
+        assert_eq!(report.offload_coverage, 1.0);
+        assert_eq!(
+            report.offloaded_rules,
+            vec!["drop-scanner", "keep-dns", "drop-ssh"]
+        );
+        // Prologue, then 3 + 6 + 6 instructions for the rules
+        assert_eq!(report.bpf_instructions, BPF_PROLOGUE_INSNS + 3 + 6 + 6);
+        assert!(report.estimated_memory_bytes > 0);
+    }
+
+    #[test]
+    fn test_lint_findings() {
+        let report = PacketFilter::validate_and_compile(
+            &ruleset(vec![
+                rule(
+                    "all-tcp",
+                    1,
+                    vec![FilterCondition::Protocol(IPPROTO_TCP)],
+                    FilterAction::Accept,
+                ),
+                rule(
+                    "tcp-http",
+                    2,
+                    vec![
+                        FilterCondition::Protocol(IPPROTO_TCP),
+                        FilterCondition::DestPort(80),
+                    ],
+                    FilterAction::Drop,
+                ),
+                rule(
+                    "icmp-port",
+                    3,
+                    vec![FilterCondition::Protocol(1), FilterCondition::DestPort(7)],
+                    FilterAction::Drop,
+                ),
+                rule(
+                    "two-protocols",
+                    4,
+                    vec![
+                        FilterCondition::Protocol(IPPROTO_TCP),
+                        FilterCondition::Protocol(IPPROTO_UDP),
+                    ],
+                    FilterAction::Drop,
+                ),
+                rule("all-tcp", 5, vec![], FilterAction::Sample { rate: 1.5 }),
+            ]),
+            &OffloadCapabilities::default(),
+        )
+        .unwrap();
+
+        assert!(!report.is_valid());
+        let error_rules: Vec<_> = report
+            .errors
+            .iter()
+            .map(|e| e.rule_id.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            error_rules,
+            vec!["icmp-port", "two-protocols", "all-tcp", "all-tcp"]
+        );
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.rule_id.as_deref() == Some("tcp-http")
+                && w.message.contains("shadowed by all-tcp")));
+    }
+
+    #[test]
+    fn test_offload_findings() {
+        let mut set = clean();
+        set.rules.push(rule(
+            "mirror-web",
+            4,
+            vec![FilterCondition::DestPort(443)],
+            FilterAction::Mirror,
+        ));
+        set.rules.push(rule(
+            "v6-host",
+            5,
+            vec![FilterCondition::DestIp(IpAddr::V6(Ipv6Addr::LOCALHOST))],
+            FilterAction::Drop,
+        ));
+        let hardware = OffloadCapabilities {
+            max_rules: 2,
+            ..OffloadCapabilities::default()
+        };
+
+        let report = PacketFilter::validate_and_compile(&set, &hardware).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.offloaded_rules, vec!["drop-scanner", "keep-dns"]);
+        assert_eq!(report.offload_coverage, 0.4);
+        let software: Vec<_> = report
+            .warnings
+            .iter()
+            .map(|w| w.rule_id.as_deref().unwrap())
+            .collect();
+        assert_eq!(software, vec!["drop-ssh", "mirror-web", "v6-host"]);
+
+        let none = PacketFilter::validate_and_compile(&set, &OffloadCapabilities::none()).unwrap();
+        assert_eq!(none.offload_coverage, 0.0);
+        assert_eq!(none.bpf_instructions, 0);
+    }
+
+    #[test]
+    fn test_compiled_evaluation() {
+        let compiled = CompiledRuleset::compile(&clean());
+        let packet = |src: IpAddr, protocol: u8, dst_port: u16| PacketFields {
+            src_ip: src,
+            dst_ip: host(1),
+            src_port: 40000,
+            dst_port,
+            protocol,
+        };
+
+        assert!(matches!(
+            compiled.evaluate(&packet(host(66), IPPROTO_UDP, 53), UNIX_EPOCH),
+            FilterAction::Drop
+        ));
+        assert!(matches!(
+            compiled.evaluate(&packet(host(5), IPPROTO_UDP, 53), UNIX_EPOCH),
+            FilterAction::Accept
+        ));
+        assert!(matches!(
+            compiled.evaluate(&packet(host(5), IPPROTO_TCP, 22), UNIX_EPOCH),
+            FilterAction::Drop
+        ));
+        assert!(matches!(
+            compiled.evaluate(&packet(host(5), IPPROTO_TCP, 8080), UNIX_EPOCH),
+            FilterAction::Accept
+        ));
+    }
+
+    #[test]
+    fn test_candidates_merge_in_rule_order() {
+        let merged: Vec<usize> = Candidates {
+            lists: [&[1, 4, 9], &[0, 4, 7], &[0, 4, 7]],
+        }
+        .collect();
+        assert_eq!(merged, vec![0, 1, 4, 7, 9]);
+        assert_eq!(Candidates { lists: [&[]; 3] }.next(), None);
+    }
+
+    #[test]
+    fn test_ruleset_requires_id() {
+        let mut set = clean();
+        set.id = String::new();
+        assert!(PacketFilter::validate_and_compile(&set, &OffloadCapabilities::default()).is_err());
+    }
+
+    /// IPv6 packet from 2001:db8::1 to 2001:db8:1::2 with `extensions` (next header, bytes)
+    /// chained in front of a TCP header from port 40000 to 443
+    fn ipv6_packet(extensions: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut packet = vec![0u8; 40];
+        packet[0] = 0x60;
+        packet[8..24].copy_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        packet[24..40].copy_from_slice(&"2001:db8:1::2".parse::<Ipv6Addr>().unwrap().octets());
+        let mut next_field = 6;
+        for (kind, bytes) in extensions {
+            packet[next_field] = *kind;
+            next_field = packet.len();
+            packet.extend_from_slice(bytes);
+        }
+        packet[next_field] = IPPROTO_TCP;
+        let mut tcp = vec![0u8; 20];
+        tcp[..2].copy_from_slice(&40000u16.to_be_bytes());
+        tcp[2..4].copy_from_slice(&443u16.to_be_bytes());
+        packet.extend_from_slice(&tcp);
+        packet
+    }
+
+    fn prefix(addr: &str, len: u8) -> Ipv6Prefix {
+        Ipv6Prefix::new(addr.parse().unwrap(), len).unwrap()
+    }
+
+    fn ipv6_ruleset() -> CompiledRuleset {
+        CompiledRuleset::compile(&ruleset(vec![
+            rule(
+                "drop-v6-https",
+                1,
+                vec![
+                    FilterCondition::Ipv6Address {
+                        src: Some(prefix("2001:db8::", 64)),
+                        dst: None,
+                    },
+                    FilterCondition::Protocol(IPPROTO_TCP),
+                    FilterCondition::DestPort(443),
+                ],
+                FilterAction::Drop,
+            ),
+            rule(
+                "mirror-v6-tcp",
+                2,
+                vec![
+                    FilterCondition::Ipv6Address {
+                        src: None,
+                        dst: Some(prefix("2001:db8:1::", 48)),
+                    },
+                    FilterCondition::Protocol(IPPROTO_TCP),
+                ],
+                FilterAction::Mirror,
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_ipv6_prefix() {
+        let net = prefix("2001:db8::ffff", 64);
+        assert_eq!(net.to_string(), "2001:db8::/64");
+        assert!(net.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(!net.contains(&"2001:db8:0:1::1".parse().unwrap()));
+        assert!(prefix("::", 0).contains(&"ff02::1".parse().unwrap()));
+        assert!(Ipv6Prefix::new(Ipv6Addr::LOCALHOST, 129).is_none());
+    }
+
+    #[test]
+    fn test_ipv6_extension_headers_reach_transport() {
+        let compiled = ipv6_ruleset();
+        let hop_by_hop = (0, vec![0u8; 8]);
+        let dest_options = (60, {
+            let mut options = vec![0u8; 16];
+            options[1] = 1;
+            options
+        });
+        let packet = ipv6_packet(&[hop_by_hop, dest_options]);
+
+        let fields = PacketFields::from_ip_packet(&packet).unwrap();
+        assert_eq!(fields.protocol, IPPROTO_TCP);
+        assert_eq!((fields.src_port, fields.dst_port), (40000, 443));
+        assert!(matches!(
+            compiled.evaluate_packet(&packet, UNIX_EPOCH),
+            FilterAction::Drop
+        ));
+    }
+
+    #[test]
+    fn test_ipv6_fragments() {
+        let compiled = ipv6_ruleset();
+        let first = ipv6_packet(&[(44, vec![0u8; 8])]);
+        let fields = PacketFields::from_ip_packet(&first).unwrap();
+        assert_eq!((fields.protocol, fields.dst_port), (IPPROTO_TCP, 443));
+
+        // Later fragments carry no ports; only the protocol-level rule can match
+        let mut later = vec![0u8; 8];
+        later[2..4].copy_from_slice(&(185u16 << 3).to_be_bytes());
+        let later = ipv6_packet(&[(44, later)]);
+        let fields = PacketFields::from_ip_packet(&later).unwrap();
+        assert_eq!((fields.protocol, fields.dst_port), (IPPROTO_TCP, 0));
+        assert!(matches!(
+            compiled.evaluate_packet(&later, UNIX_EPOCH),
+            FilterAction::Mirror
+        ));
+    }
+
+    #[test]
+    fn test_truncated_ipv6_packets_do_not_panic() {
+        let compiled = ipv6_ruleset();
+        let packet = ipv6_packet(&[(0, vec![0u8; 8])]);
+        // Ports need the first 4 bytes of the TCP header at offset 48
+        for len in 0..52 {
+            let truncated = &packet[..len];
+            let action = compiled.evaluate_packet(truncated, UNIX_EPOCH);
+            match PacketFields::from_ip_packet(truncated) {
+                None => assert!(len < 40 && matches!(action, FilterAction::Accept)),
+                Some(fields) => assert_eq!((fields.src_port, fields.dst_port), (0, 0)),
+            }
+        }
+        // Truncated inside the TCP header: the protocol is known but the ports are not
+        let fields = PacketFields::from_ip_packet(&packet[..50]).unwrap();
+        assert_eq!(fields.protocol, IPPROTO_TCP);
+    }
+
+    #[test]
+    fn test_ipv6_conditions_never_match_ipv4() {
+        let compiled = ipv6_ruleset();
+        let packet = PacketFields {
+            src_ip: host(1),
+            dst_ip: host(2),
+            src_port: 40000,
+            dst_port: 443,
+            protocol: IPPROTO_TCP,
+        };
+        assert!(matches!(
+            compiled.evaluate(&packet, UNIX_EPOCH),
+            FilterAction::Accept
+        ));
+    }
+
+    #[test]
+    fn test_ipv6_prefix_lint_and_offload() {
+        let set = ruleset(vec![
+            rule(
+                "outside",
+                1,
+                vec![
+                    FilterCondition::SourceIp(IpAddr::V6("2001:db8:2::1".parse().unwrap())),
+                    FilterCondition::Ipv6Address {
+                        src: Some(prefix("2001:db8::", 64)),
+                        dst: None,
+                    },
+                ],
+                FilterAction::Drop,
+            ),
+            rule(
+                "v6-net",
+                2,
+                vec![FilterCondition::Ipv6Address {
+                    src: Some(prefix("2001:db8::", 48)),
+                    dst: None,
+                }],
+                FilterAction::Drop,
+            ),
+        ]);
+        let report =
+            PacketFilter::validate_and_compile(&set, &OffloadCapabilities::default()).unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].message.contains("outside 2001:db8::/64"));
+        assert!(report.warnings.iter().any(|w| w
+            .message
+            .contains("IPv6 address matches are not offloadable")));
+
+        let hardware = OffloadCapabilities {
+            supports_ipv6: true,
+            ..Default::default()
+        };
+        let report = PacketFilter::validate_and_compile(&set, &hardware).unwrap();
+        // A /64 is two whole words; a /48 spans two words and needs a mask for the partial one
+        assert_eq!(
+            report.bpf_instructions,
+            BPF_PROLOGUE_INSNS + (8 + 4 + 1) + (5 + 1)
+        );
+    }
+
+    #[test]
+    fn test_rule_stats_track_evaluations_and_matches() {
+        let compiled = CompiledRuleset::compile(&clean());
+        let dns = PacketFields {
+            src_ip: host(1),
+            dst_ip: host(2),
+            src_port: 40000,
+            dst_port: 53,
+            protocol: IPPROTO_UDP,
+        };
+        let captured = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for _ in 0..3 {
+            compiled.evaluate(&dns, captured);
+        }
+
+        let stats = compiled.rule_stats();
+        assert_eq!(stats.len(), 3);
+        let keep_dns = stats["keep-dns"];
+        assert_eq!((keep_dns.evaluated, keep_dns.matched), (3, 3));
+        assert_eq!(keep_dns.last_match, Some(captured));
+        // The scanner rule is indexed by address and never considered for other hosts
+        assert_eq!(stats["drop-scanner"], RuleMatchStats::default());
+        assert_eq!(stats["drop-ssh"], RuleMatchStats::default());
+
+        compiled.evaluate(
+            &PacketFields {
+                protocol: IPPROTO_TCP,
+                dst_port: 80,
+                ..dns
+            },
+            captured,
+        );
+        let stats = compiled.rule_stats();
+        assert_eq!(stats["drop-ssh"].evaluated, 1);
+        assert_eq!(stats["drop-ssh"].matched, 0);
+        assert!(stats["drop-ssh"].last_match.is_none());
+
+        compiled.reset_stats();
+        assert!(compiled
+            .rule_stats()
+            .values()
+            .all(|counts| *counts == RuleMatchStats::default()));
+    }
+}
@@ -0,0 +1,386 @@
+// filter/sampling.rs
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::capture_engine::control::traits::FilterAction;
+use crate::capture_engine::filter::ruleset::PacketFields;
+use crate::capture_engine::protocol::flow::FlowKey;
+
+/// SipHash key for flow sampling decisions
+///
+/// Fixed so every capture node, and every release, keeps the same flows at a given rate.
+const FLOW_SAMPLING_KEY: (u64, u64) = (0x7370_6172_6b74_7261, 0x702d_666c_6f77_7321);
+
+/// Seeded random source for sampling and jitter decisions
+///
+/// Decisions come from a ChaCha8 stream whose output is fixed for a given seed across platforms
+/// and releases, so replaying the same packets with the recorded seed reproduces every
+/// decision. Without a configured seed one is drawn from OS entropy and can be read back
+/// with `seed` for the session report.
+///
+/// # Fields
+/// * `seed` - Seed the stream was created from
+/// * `rng` - Decision stream
+#[derive(Debug, Clone)]
+pub struct PacketSampler {
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl PacketSampler {
+    /// Creates a sampler
+    ///
+    /// # Arguments
+    /// * `seed` - Seed to use, None to draw one from OS entropy
+    ///
+    /// # Returns
+    /// A new PacketSampler instance
+    pub fn new(seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(|| rand::rngs::OsRng.next_u64());
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Gets the seed the sampler was created from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Decides whether to keep a packet sampled at `rate`
+    ///
+    /// # Arguments
+    /// * `rate` - Fraction of packets to keep, clamped to 0.0..=1.0
+    ///
+    /// # Returns
+    /// True if the packet is kept
+    pub fn sample(&mut self, rate: f64) -> bool {
+        if rate.is_nan() || rate <= 0.0 {
+            return false;
+        }
+        if rate >= 1.0 {
+            return true;
+        }
+        self.rng.gen::<f64>() < rate
+    }
+
+    /// Resolves a `Sample` action to `Accept` or `Drop`
+    ///
+    /// # Arguments
+    /// * `action` - Action selected by the filter
+    ///
+    /// # Returns
+    /// The action to apply; actions other than `Sample`, including `SampleFlow`, which needs
+    /// the packet, are returned unchanged
+    pub fn resolve(&mut self, action: &FilterAction) -> FilterAction {
+        match action {
+            FilterAction::Sample { rate } if self.sample(*rate) => FilterAction::Accept,
+            FilterAction::Sample { .. } => FilterAction::Drop,
+            other => other.clone(),
+        }
+    }
+
+    /// Resolves a `Sample` or `SampleFlow` action for a packet to `Accept` or `Drop`
+    ///
+    /// # Arguments
+    /// * `action` - Action selected by the filter
+    /// * `packet` - Fields of the packet the action was selected for
+    ///
+    /// # Returns
+    /// The action to apply; other actions are returned unchanged
+    pub fn resolve_packet(&mut self, action: &FilterAction, packet: &PacketFields) -> FilterAction {
+        match action {
+            FilterAction::SampleFlow { .. } => resolve_flow(action, packet),
+            other => self.resolve(other),
+        }
+    }
+
+    /// Draws a jitter delay
+    ///
+    /// # Arguments
+    /// * `max` - Upper bound of the delay
+    ///
+    /// # Returns
+    /// A delay between zero and `max`, inclusive
+    pub fn jitter(&mut self, max: Duration) -> Duration {
+        let nanos = max.as_nanos().min(u64::MAX as u128) as u64;
+        Duration::from_nanos(self.rng.gen_range(0..=nanos))
+    }
+}
+
+/// Decides whether a flow is kept when sampling flows at `rate`
+///
+/// The decision is a SipHash-2-4, under a fixed key, of the flow's normalized 5-tuple, so
+/// every packet of a flow gets the same decision and reversing source and destination yields
+/// the same decision for both directions of a bidirectional flow. Decisions do not depend on
+/// the sampler seed and are the same on every node.
+///
+/// # Arguments
+/// * `packet` - Fields of a packet of the flow
+/// * `rate` - Fraction of flows to keep, clamped to 0.0..=1.0
+///
+/// # Returns
+/// True if the flow is kept
+pub fn sample_flow(packet: &PacketFields, rate: f64) -> bool {
+    if rate.is_nan() || rate <= 0.0 {
+        return false;
+    }
+    if rate >= 1.0 {
+        return true;
+    }
+    let key = FlowKey::new(
+        packet.src_ip,
+        packet.src_port,
+        packet.dst_ip,
+        packet.dst_port,
+        packet.protocol,
+    );
+    let mut tuple = Vec::with_capacity(38);
+    for (addr, port) in [(key.addr_a, key.port_a), (key.addr_b, key.port_b)] {
+        match addr {
+            IpAddr::V4(addr) => {
+                tuple.push(4);
+                tuple.extend_from_slice(&addr.to_ipv6_mapped().octets());
+            }
+            IpAddr::V6(addr) => {
+                tuple.push(6);
+                tuple.extend_from_slice(&addr.octets());
+            }
+        }
+        tuple.extend_from_slice(&port.to_be_bytes());
+    }
+    tuple.push(key.protocol);
+    // The top 53 bits, as a uniform fraction in 0.0..1.0
+    let position = (siphash24(FLOW_SAMPLING_KEY, &tuple) >> 11) as f64 / (1u64 << 53) as f64;
+    position < rate
+}
+
+/// Resolves a `SampleFlow` action to `Accept` or `Drop`
+///
+/// # Arguments
+/// * `action` - Action selected by the filter
+/// * `packet` - Fields of the packet the action was selected for
+///
+/// # Returns
+/// The action to apply; actions other than `SampleFlow` are returned unchanged
+pub fn resolve_flow(action: &FilterAction, packet: &PacketFields) -> FilterAction {
+    match action {
+        FilterAction::SampleFlow { rate } if sample_flow(packet, *rate) => FilterAction::Accept,
+        FilterAction::SampleFlow { .. } => FilterAction::Drop,
+        other => other.clone(),
+    }
+}
+
+/// SipHash-2-4 of `data` under `key`
+fn siphash24(key: (u64, u64), data: &[u8]) -> u64 {
+    let mut v = [
+        key.0 ^ 0x736f_6d65_7073_6575,
+        key.1 ^ 0x646f_7261_6e64_6f6d,
+        key.0 ^ 0x6c79_6765_6e65_7261,
+        key.1 ^ 0x7465_6462_7974_6573,
+    ];
+    let compress = |v: &mut [u64; 4], word: u64| {
+        v[3] ^= word;
+        sip_round(v);
+        sip_round(v);
+        v[0] ^= word;
+    };
+    let mut words = data.chunks_exact(8);
+    for word in &mut words {
+        compress(&mut v, u64::from_le_bytes(word.try_into().unwrap()));
+    }
+    // The last word holds the remaining bytes and the low byte of the length
+    let last = words
+        .remainder()
+        .iter()
+        .enumerate()
+        .fold((data.len() as u64) << 56, |last, (i, byte)| {
+            last | (u64::from(*byte) << (8 * i))
+        });
+    compress(&mut v, last);
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::filter::stats::FilterStats;
+    use crate::capture_engine::interface::injection::InjectionSource;
+    use crate::capture_engine::interface::source::SourceRunner;
+
+    // Replays the same injected packets through a sampler and collects the decisions
+    fn replay(seed: Option<u64>) -> (u64, Vec<bool>, FilterStats) {
+        let source = InjectionSource::new("replay");
+        let handle = source.handle();
+        for i in 0..500u32 {
+            handle.inject(i.to_be_bytes().to_vec(), i as u64);
+        }
+        handle.finish();
+
+        let mut sampler = PacketSampler::new(seed);
+        let stats = FilterStats::default();
+        let mut decisions = Vec::new();
+        let mut runner = SourceRunner::new(Box::new(source), 64).unwrap();
+        runner.open().unwrap();
+        runner
+            .run_to_end(|batch| {
+                for _ in &batch.packets {
+                    let action = sampler.resolve(&FilterAction::Sample { rate: 0.25 });
+                    decisions.push(matches!(action, FilterAction::Accept));
+                    stats.record_action(&action);
+                }
+                Ok(())
+            })
+            .unwrap();
+        (sampler.seed(), decisions, stats)
+    }
+
+    #[test]
+    fn test_same_seed_reproduces_decisions() {
+        let (seed, first, first_stats) = replay(Some(42));
+        let (_, second, second_stats) = replay(Some(42));
+
+        assert_eq!(seed, 42);
+        assert_eq!(first.len(), 500);
+        assert_eq!(first, second);
+        assert_eq!(first_stats.accepted(), second_stats.accepted());
+        assert_eq!(first_stats.policy_drops(), second_stats.policy_drops());
+
+        // Roughly a quarter of the packets are kept
+        let kept = first.iter().filter(|kept| **kept).count();
+        assert!((75..=175).contains(&kept));
+    }
+
+    #[test]
+    fn test_different_seeds_diverge() {
+        let (_, first, _) = replay(Some(1));
+        let (_, second, _) = replay(Some(2));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_entropy_seed_can_be_replayed() {
+        let (seed, first, _) = replay(None);
+        let (_, second, _) = replay(Some(seed));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_rate_bounds_and_jitter() {
+        let mut sampler = PacketSampler::new(Some(7));
+        assert!(!sampler.sample(0.0));
+        assert!(sampler.sample(1.0));
+        assert!(matches!(
+            sampler.resolve(&FilterAction::Mirror),
+            FilterAction::Mirror
+        ));
+
+        let max = Duration::from_millis(5);
+        let delays: Vec<_> = (0..10).map(|_| sampler.jitter(max)).collect();
+        assert!(delays.iter().all(|delay| *delay <= max));
+        let mut replayed = PacketSampler::new(Some(7));
+        replayed.sample(0.0);
+        replayed.sample(1.0);
+        let replayed: Vec<_> = (0..10).map(|_| replayed.jitter(max)).collect();
+        assert_eq!(delays, replayed);
+    }
+
+    #[test]
+    fn test_siphash_reference_vectors() {
+        // From the SipHash paper: key 00..0f, messages 00..(n-1)
+        let key = (0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+        let message: Vec<u8> = (0..15).collect();
+        assert_eq!(siphash24(key, &[]), 0x726f_db47_dd0e_0e31);
+        assert_eq!(siphash24(key, &message), 0xa129_ca61_49be_45e5);
+    }
+
+    fn synthetic_flow(i: u32) -> PacketFields {
+        let [a, b, c, d] = i.to_be_bytes();
+        PacketFields {
+            src_ip: IpAddr::from([10, b, c, d]),
+            dst_ip: IpAddr::from([192, 168, a, 1]),
+            src_port: 1024 + (i % 50_000) as u16,
+            dst_port: 443,
+            protocol: 6,
+        }
+    }
+
+    fn reversed(packet: &PacketFields) -> PacketFields {
+        PacketFields {
+            src_ip: packet.dst_ip,
+            dst_ip: packet.src_ip,
+            src_port: packet.dst_port,
+            dst_port: packet.src_port,
+            protocol: packet.protocol,
+        }
+    }
+
+    #[test]
+    fn test_flow_sampling_rate_within_tolerance() {
+        const FLOWS: u32 = 100_000;
+        for rate in [0.01, 0.1, 0.25, 0.5, 0.9] {
+            let kept = (0..FLOWS)
+                .filter(|i| sample_flow(&synthetic_flow(*i), rate))
+                .count();
+            let realized = kept as f64 / FLOWS as f64;
+            // More than five standard deviations of a binomial proportion
+            let tolerance = 5.0 * (rate * (1.0 - rate) / FLOWS as f64).sqrt();
+            assert!(
+                (realized - rate).abs() < tolerance,
+                "rate {} realized {}",
+                rate,
+                realized
+            );
+        }
+    }
+
+    #[test]
+    fn test_flow_sampling_is_consistent_in_both_directions() {
+        let mut sampler = PacketSampler::new(Some(3));
+        let action = FilterAction::SampleFlow { rate: 0.5 };
+        let mut kept = 0;
+        for i in 0..1000 {
+            let packet = synthetic_flow(i);
+            let decision = sample_flow(&packet, 0.5);
+            assert_eq!(sample_flow(&reversed(&packet), 0.5), decision);
+            for _ in 0..3 {
+                let resolved = sampler.resolve_packet(&action, &packet);
+                assert_eq!(matches!(resolved, FilterAction::Accept), decision);
+            }
+            kept += decision as usize;
+        }
+        assert!((400..=600).contains(&kept));
+
+        // A kept flow stays kept as the rate rises
+        let packet = synthetic_flow(7);
+        let rates = [0.05, 0.2, 0.4, 0.6, 0.8, 0.95];
+        let first_kept = rates.iter().position(|rate| sample_flow(&packet, *rate));
+        if let Some(first) = first_kept {
+            assert!(rates[first..]
+                .iter()
+                .all(|rate| sample_flow(&packet, *rate)));
+        }
+        assert!(!sample_flow(&packet, 0.0));
+        assert!(sample_flow(&packet, 1.0));
+    }
+}
@@ -0,0 +1,221 @@
+// filter/stats.rs
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::capture_engine::capture::capture_statistics::DropMetrics;
+use crate::capture_engine::control::traits::FilterAction;
+
+/// Counters describing what the packet filter did with the traffic it evaluated
+///
+/// # Fields
+/// * `evaluated` - Packets the filter evaluated
+/// * `accepted` - Packets matched by an `Accept` action, or a sampling action not yet resolved
+/// * `mirrored` - Packets matched by a `Mirror` action
+/// * `drops` - Policy drops from `Drop` actions and loss drops of packets that passed the filter
+#[derive(Debug, Default)]
+pub struct FilterStats {
+    evaluated: AtomicU64,
+    accepted: AtomicU64,
+    mirrored: AtomicU64,
+    drops: DropMetrics,
+}
+
+impl FilterStats {
+    /// Records the action the filter took for a packet
+    ///
+    /// # Arguments
+    /// * `action` - Action selected by the filter
+    pub fn record_action(&self, action: &FilterAction) {
+        self.evaluated.fetch_add(1, Ordering::Relaxed);
+        match action {
+            FilterAction::Accept
+            | FilterAction::Sample { .. }
+            | FilterAction::SampleFlow { .. } => {
+                self.accepted.fetch_add(1, Ordering::Relaxed);
+            }
+            FilterAction::Mirror => {
+                self.mirrored.fetch_add(1, Ordering::Relaxed);
+            }
+            FilterAction::Drop => self.drops.record_policy_drop(),
+        }
+    }
+
+    /// Records a packet the filter kept but which was lost to resource pressure
+    pub fn record_loss_drop(&self) {
+        self.drops.record_loss_drop();
+    }
+
+    /// Gets the number of packets evaluated
+    pub fn evaluated(&self) -> u64 {
+        self.evaluated.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of packets accepted
+    pub fn accepted(&self) -> u64 {
+        self.accepted.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of packets mirrored
+    pub fn mirrored(&self) -> u64 {
+        self.mirrored.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of packets dropped by a `Drop` action
+    pub fn policy_drops(&self) -> u64 {
+        self.drops.policy_drops()
+    }
+
+    /// Gets the number of packets lost after passing the filter
+    pub fn loss_drops(&self) -> u64 {
+        self.drops.loss_drops()
+    }
+
+    /// Gets the drop counters, e.g. for telemetry export
+    pub fn drops(&self) -> &DropMetrics {
+        &self.drops
+    }
+}
+
+/// Identifier of a filter rule, as assigned by the control plane
+pub type RuleId = String;
+
+/// How often a single rule was evaluated and matched
+///
+/// # Fields
+/// * `evaluated` - Packets the rule was tested against
+/// * `matched` - Packets the rule matched and decided the action for
+/// * `last_match` - Time of the most recent match, None if the rule never matched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleMatchStats {
+    pub evaluated: u64,
+    pub matched: u64,
+    pub last_match: Option<SystemTime>,
+}
+
+/// Lock-free match counters of a single rule
+///
+/// Updates are relaxed atomic adds, so counting adds no contention to the hot path. The last
+/// match time is stored as nanoseconds since the Unix epoch, with 0 meaning never.
+///
+/// # Fields
+/// * `evaluated` - Packets the rule was tested against
+/// * `matched` - Packets the rule matched
+/// * `last_match_nanos` - Time of the most recent match
+#[derive(Debug, Default)]
+pub struct RuleCounters {
+    evaluated: AtomicU64,
+    matched: AtomicU64,
+    last_match_nanos: AtomicU64,
+}
+
+impl RuleCounters {
+    /// Records that the rule was tested against a packet
+    pub fn record_evaluated(&self) {
+        self.evaluated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the rule matched a packet
+    ///
+    /// # Arguments
+    /// * `at` - Time of the match
+    pub fn record_match(&self, at: SystemTime) {
+        self.matched.fetch_add(1, Ordering::Relaxed);
+        let nanos = at.duration_since(UNIX_EPOCH).map_or(1, |since| {
+            since.as_nanos().clamp(1, u64::MAX as u128) as u64
+        });
+        self.last_match_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    /// Gets the current counts
+    pub fn snapshot(&self) -> RuleMatchStats {
+        let nanos = self.last_match_nanos.load(Ordering::Relaxed);
+        RuleMatchStats {
+            evaluated: self.evaluated.load(Ordering::Relaxed),
+            matched: self.matched.load(Ordering::Relaxed),
+            last_match: (nanos != 0).then(|| UNIX_EPOCH + Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// Zeroes the counts
+    pub fn reset(&self) {
+        self.evaluated.store(0, Ordering::Relaxed);
+        self.matched.store(0, Ordering::Relaxed);
+        self.last_match_nanos.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Clone for RuleCounters {
+    fn clone(&self) -> Self {
+        Self {
+            evaluated: AtomicU64::new(self.evaluated.load(Ordering::Relaxed)),
+            matched: AtomicU64::new(self.matched.load(Ordering::Relaxed)),
+            last_match_nanos: AtomicU64::new(self.last_match_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::buffer_manager::{BufferMemoryType, BufferPoolAccounting};
+
+    // Filters a packet, then tries to place kept packets in the buffer pool
+    fn ingest(stats: &FilterStats, pool: &BufferPoolAccounting, action: FilterAction) {
+        stats.record_action(&action);
+        if matches!(action, FilterAction::Drop) {
+            return;
+        }
+        if pool.acquire(BufferMemoryType::Heap).is_err() {
+            stats.record_loss_drop();
+        }
+    }
+
+    #[test]
+    fn test_drop_rule_counts_as_policy_drop() {
+        let stats = FilterStats::default();
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 10);
+
+        for _ in 0..5 {
+            ingest(&stats, &pool, FilterAction::Drop);
+        }
+
+        assert_eq!(stats.evaluated(), 5);
+        assert_eq!(stats.policy_drops(), 5);
+        assert_eq!(stats.loss_drops(), 0);
+    }
+
+    #[test]
+    fn test_buffer_exhaustion_counts_as_loss_drop() {
+        let stats = FilterStats::default();
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 2);
+
+        for _ in 0..5 {
+            ingest(&stats, &pool, FilterAction::Accept);
+        }
+
+        assert_eq!(stats.accepted(), 5);
+        assert_eq!(stats.loss_drops(), 3);
+        assert_eq!(stats.policy_drops(), 0);
+    }
+
+    #[test]
+    fn test_drop_causes_never_cross_contaminate() {
+        let stats = FilterStats::default();
+        let pool = BufferPoolAccounting::default();
+        pool.add_buffers(BufferMemoryType::Heap, 1);
+
+        ingest(&stats, &pool, FilterAction::Mirror);
+        ingest(&stats, &pool, FilterAction::Drop);
+        ingest(&stats, &pool, FilterAction::Accept);
+        ingest(&stats, &pool, FilterAction::Drop);
+        ingest(&stats, &pool, FilterAction::Mirror);
+
+        assert_eq!(stats.evaluated(), 5);
+        assert_eq!(stats.policy_drops(), 2);
+        assert_eq!(stats.loss_drops(), 2);
+        assert_eq!(stats.mirrored(), 2);
+        assert_eq!(stats.accepted(), 1);
+    }
+}
@@ -0,0 +1,1537 @@
+// filter/xdp.rs
+//! XDP offload of filter rules, deciding Accept and Drop in the kernel before userspace
+//!
+//! The rules are compiled into an eBPF program attached to the capture interface. Offloading
+//! keeps first-match semantics by only taking the longest prefix of offloadable rules in
+//! priority order; the first rule that cannot be offloaded and every rule after it stay in
+//! software. Frames the program does not decide are passed up unchanged, so the software
+//! ruleset, which still holds every rule, handles them as before.
+//!
+//! The program only parses untagged IPv4. Anything else is always passed to software.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::capture_engine::control::traits::{FilterAction, FilterCondition};
+use crate::capture_engine::filter::ruleset::{offload_blocker, FilterRuleset, OffloadCapabilities};
+use crate::capture_engine::filter::stats::{RuleId, RuleMatchStats};
+use crate::capture_engine::protocol::flow::{IPPROTO_TCP, IPPROTO_UDP};
+
+/// Rules a single XDP program holds
+pub const XDP_MAX_RULES: usize = 256;
+/// Conditions a single offloaded rule may test
+const XDP_MAX_CONDITIONS: usize = 8;
+/// Verifier limit on instructions of a privileged program
+pub const XDP_MAX_INSNS: usize = 1_000_000;
+
+/// XDP verdict that drops the frame in the driver
+pub const XDP_DROP: u32 = 1;
+/// XDP verdict that hands the frame to the network stack and the capture socket
+pub const XDP_PASS: u32 = 2;
+
+const ETH_HLEN: i16 = 14;
+const ETH_P_IP: u16 = 0x0800;
+const IPV4_MIN_HLEN: i16 = 20;
+/// Fragment offset bits of the IPv4 flags and fragment offset field
+const IPV4_FRAGMENT_OFFSET: u16 = 0x1fff;
+
+/// Stack slots holding the parsed packet fields, as offsets from the frame pointer
+const SLOT_PROTOCOL: i16 = -8;
+const SLOT_SRC_IP: i16 = -16;
+const SLOT_DST_IP: i16 = -24;
+const SLOT_SRC_PORT: i16 = -32;
+const SLOT_DST_PORT: i16 = -40;
+/// Stack slot of the counter map key
+const SLOT_KEY: i16 = -48;
+
+/// `bpf_map_lookup_elem` helper id
+const BPF_FUNC_MAP_LOOKUP_ELEM: i32 = 1;
+/// Source register marker of a 64-bit immediate load holding a map fd
+const BPF_PSEUDO_MAP_FD: u8 = 1;
+
+// Instruction classes
+const BPF_LD: u8 = 0x00;
+const BPF_LDX: u8 = 0x01;
+const BPF_ST: u8 = 0x02;
+const BPF_STX: u8 = 0x03;
+const BPF_JMP: u8 = 0x05;
+const BPF_JMP32: u8 = 0x06;
+const BPF_ALU64: u8 = 0x07;
+
+// Access sizes
+const BPF_W: u8 = 0x00;
+const BPF_H: u8 = 0x08;
+const BPF_B: u8 = 0x10;
+const BPF_DW: u8 = 0x18;
+
+// Addressing modes
+const BPF_IMM: u8 = 0x00;
+const BPF_MEM: u8 = 0x60;
+const BPF_ATOMIC: u8 = 0xc0;
+
+// Operand sources
+const BPF_K: u8 = 0x00;
+const BPF_X: u8 = 0x08;
+
+// ALU operations
+const BPF_ADD: u8 = 0x00;
+const BPF_AND: u8 = 0x50;
+const BPF_LSH: u8 = 0x60;
+const BPF_RSH: u8 = 0x70;
+const BPF_MOV: u8 = 0xb0;
+
+// Jump operations
+const BPF_JEQ: u8 = 0x10;
+const BPF_JGT: u8 = 0x20;
+const BPF_JGE: u8 = 0x30;
+const BPF_JSET: u8 = 0x40;
+const BPF_JNE: u8 = 0x50;
+const BPF_CALL: u8 = 0x80;
+const BPF_EXIT: u8 = 0x90;
+
+const R0: u8 = 0;
+const R1: u8 = 1;
+const R2: u8 = 2;
+const R3: u8 = 3;
+const R4: u8 = 4;
+const R5: u8 = 5;
+const R6: u8 = 6;
+const R7: u8 = 7;
+const R8: u8 = 8;
+const R10: u8 = 10;
+
+/// How much of a ruleset runs in the kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OffloadStatus {
+    /// Every rule is evaluated in software
+    NotOffloaded,
+    /// Every rule is evaluated by the XDP program
+    Full,
+    /// Only a prefix of the rules is offloaded
+    ///
+    /// # Fields
+    /// * `software_rules` - Rules left to software, in evaluation order
+    Partial { software_rules: Vec<RuleId> },
+}
+
+/// Matches counted on each side of the offload
+///
+/// Offloaded Accept matches reach userspace and are counted again by the software ruleset.
+///
+/// # Fields
+/// * `offloaded` - Frames each offloaded rule matched in the kernel
+/// * `software` - Frames each rule matched in software
+/// * `passed_to_software` - IPv4 frames no offloaded rule matched
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffloadMatchCounts {
+    pub offloaded: HashMap<RuleId, u64>,
+    pub software: HashMap<RuleId, u64>,
+    pub passed_to_software: u64,
+}
+
+/// Filtering capabilities of the XDP program
+pub fn xdp_capabilities() -> OffloadCapabilities {
+    OffloadCapabilities {
+        max_rules: XDP_MAX_RULES,
+        max_conditions_per_rule: XDP_MAX_CONDITIONS,
+        supports_ipv6: false,
+        max_bpf_instructions: XDP_MAX_INSNS,
+    }
+}
+
+/// A rule compiled for the XDP program
+///
+/// # Fields
+/// * `id` - Rule id
+/// * `operands` - Stack slot and expected value of each condition
+/// * `verdict` - XDP verdict on a match
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct XdpRule {
+    id: RuleId,
+    operands: Vec<(i16, i32)>,
+    verdict: u32,
+}
+
+/// The split of a ruleset between the XDP program and software
+///
+/// # Fields
+/// * `rules` - Offloaded rules in evaluation order; rule `i` counts into map slot `i`
+/// * `software_rules` - Rules left to software, in evaluation order
+/// * `default_verdict` - Verdict for IPv4 frames no rule matched; only Drop when every rule
+///   is offloaded and the default action is Drop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XdpPlan {
+    rules: Vec<XdpRule>,
+    software_rules: Vec<RuleId>,
+    default_verdict: u32,
+}
+
+impl XdpPlan {
+    /// Splits a ruleset between the XDP program and software
+    ///
+    /// # Arguments
+    /// * `ruleset` - Ruleset to offload
+    ///
+    /// # Returns
+    /// The offload plan
+    pub fn new(ruleset: &FilterRuleset) -> Self {
+        let mut ordered = ruleset.rules.clone();
+        ordered.sort_by_key(|rule| rule.priority);
+
+        let capabilities = xdp_capabilities();
+        let mut rules = Vec::new();
+        let mut software_rules = Vec::new();
+        for rule in &ordered {
+            let offloadable = software_rules.is_empty()
+                && rules.len() < capabilities.max_rules
+                && offload_blocker(rule, &capabilities).is_none();
+            let operands: Option<Vec<(i16, i32)>> = if offloadable {
+                rule.conditions.iter().map(condition_operand).collect()
+            } else {
+                None
+            };
+            match operands {
+                Some(operands) => rules.push(XdpRule {
+                    id: rule.id.clone(),
+                    operands,
+                    verdict: match rule.action {
+                        FilterAction::Drop => XDP_DROP,
+                        _ => XDP_PASS,
+                    },
+                }),
+                None => software_rules.push(rule.id.clone()),
+            }
+        }
+
+        let default_verdict = match ruleset.default_action {
+            FilterAction::Drop if software_rules.is_empty() => XDP_DROP,
+            _ => XDP_PASS,
+        };
+        Self {
+            rules,
+            software_rules,
+            default_verdict,
+        }
+    }
+
+    /// Gets how much of the ruleset runs in the kernel
+    pub fn status(&self) -> OffloadStatus {
+        if self.rules.is_empty() && self.default_verdict == XDP_PASS {
+            OffloadStatus::NotOffloaded
+        } else if self.software_rules.is_empty() {
+            OffloadStatus::Full
+        } else {
+            OffloadStatus::Partial {
+                software_rules: self.software_rules.clone(),
+            }
+        }
+    }
+
+    /// Gets the ids of the offloaded rules in evaluation order
+    pub fn offloaded_rules(&self) -> Vec<&str> {
+        self.rules.iter().map(|rule| rule.id.as_str()).collect()
+    }
+
+    /// Gets the ids of the rules left to software in evaluation order
+    pub fn software_rules(&self) -> &[RuleId] {
+        &self.software_rules
+    }
+
+    /// Gets the counter map entries the program needs: one per rule plus the fall-through
+    pub fn counter_slots(&self) -> u32 {
+        self.rules.len() as u32 + 1
+    }
+
+    /// Splits raw counter map values into per-rule and software counts
+    ///
+    /// # Arguments
+    /// * `counters` - Counter map values, indexed by slot
+    /// * `software` - Match counts of the software ruleset
+    ///
+    /// # Returns
+    /// The combined match counts
+    pub fn match_counts(
+        &self,
+        counters: &[u64],
+        software: &HashMap<RuleId, RuleMatchStats>,
+    ) -> OffloadMatchCounts {
+        OffloadMatchCounts {
+            offloaded: self
+                .rules
+                .iter()
+                .enumerate()
+                .map(|(slot, rule)| (rule.id.clone(), counters.get(slot).copied().unwrap_or(0)))
+                .collect(),
+            software: software
+                .iter()
+                .map(|(id, stats)| (id.clone(), stats.matched))
+                .collect(),
+            passed_to_software: counters.get(self.rules.len()).copied().unwrap_or(0),
+        }
+    }
+
+    /// Compiles the plan into an XDP program
+    ///
+    /// Each decision increments the counter of the deciding rule, or the fall-through counter,
+    /// in an array map of `counter_slots` u64 values.
+    ///
+    /// # Arguments
+    /// * `counters_fd` - File descriptor of the counter map
+    ///
+    /// # Returns
+    /// The program instructions
+    pub fn compile(&self, counters_fd: i32) -> Vec<BpfInsn> {
+        let mut asm = Assembler::default();
+        let pass = asm.label();
+        let ports = asm.label();
+        let rules = asm.label();
+
+        // r2 = data, r3 = data_end; anything shorter than an IPv4 header goes to software
+        asm.emit(BpfInsn::mov64_reg(R6, R1));
+        asm.emit(BpfInsn::ldx(BPF_W, R2, R6, 0));
+        asm.emit(BpfInsn::ldx(BPF_W, R3, R6, 4));
+        asm.emit(BpfInsn::mov64_reg(R4, R2));
+        asm.emit(BpfInsn::alu64_imm(
+            BPF_ADD,
+            R4,
+            (ETH_HLEN + IPV4_MIN_HLEN) as i32,
+        ));
+        asm.jump(BpfInsn::jmp_reg(BPF_JGT, R4, R3), pass);
+        asm.emit(BpfInsn::ldx(BPF_H, R4, R2, 12));
+        asm.jump(BpfInsn::jmp32_imm(BPF_JNE, R4, network_u16(ETH_P_IP)), pass);
+
+        // r4 = header length, clamped to the minimum like the software parser
+        asm.emit(BpfInsn::ldx(BPF_B, R4, R2, ETH_HLEN));
+        asm.emit(BpfInsn::mov64_reg(R5, R4));
+        asm.emit(BpfInsn::alu64_imm(BPF_RSH, R5, 4));
+        asm.jump(BpfInsn::jmp_imm(BPF_JNE, R5, 4), pass);
+        asm.emit(BpfInsn::alu64_imm(BPF_AND, R4, 0x0f));
+        asm.emit(BpfInsn::alu64_imm(BPF_LSH, R4, 2));
+        asm.emit(BpfInsn::jmp_imm(BPF_JGE, R4, IPV4_MIN_HLEN as i32).with_off(1));
+        asm.emit(BpfInsn::mov64_imm(R4, IPV4_MIN_HLEN as i32));
+
+        // Fields are kept in network byte order, so rule values are converted instead
+        asm.emit(BpfInsn::ldx(BPF_B, R7, R2, ETH_HLEN + 9));
+        asm.emit(BpfInsn::stx(BPF_DW, R10, R7, SLOT_PROTOCOL));
+        asm.emit(BpfInsn::ldx(BPF_W, R5, R2, ETH_HLEN + 12));
+        asm.emit(BpfInsn::stx(BPF_DW, R10, R5, SLOT_SRC_IP));
+        asm.emit(BpfInsn::ldx(BPF_W, R5, R2, ETH_HLEN + 16));
+        asm.emit(BpfInsn::stx(BPF_DW, R10, R5, SLOT_DST_IP));
+        asm.emit(BpfInsn::mov64_imm(R5, 0));
+        asm.emit(BpfInsn::stx(BPF_DW, R10, R5, SLOT_SRC_PORT));
+        asm.emit(BpfInsn::stx(BPF_DW, R10, R5, SLOT_DST_PORT));
+
+        // Ports are only read from first fragments of TCP and UDP with both ports present
+        asm.jump(BpfInsn::jmp_imm(BPF_JEQ, R7, IPPROTO_TCP as i32), ports);
+        asm.jump(BpfInsn::jmp_imm(BPF_JNE, R7, IPPROTO_UDP as i32), rules);
+        asm.bind(ports);
+        asm.emit(BpfInsn::ldx(BPF_H, R5, R2, ETH_HLEN + 6));
+        asm.jump(
+            BpfInsn::jmp32_imm(BPF_JSET, R5, network_u16(IPV4_FRAGMENT_OFFSET)),
+            rules,
+        );
+        asm.emit(BpfInsn::mov64_reg(R5, R2));
+        asm.emit(BpfInsn::alu64_reg(BPF_ADD, R5, R4));
+        asm.emit(BpfInsn::alu64_imm(BPF_ADD, R5, ETH_HLEN as i32));
+        asm.emit(BpfInsn::mov64_reg(R8, R5));
+        asm.emit(BpfInsn::alu64_imm(BPF_ADD, R8, 4));
+        asm.jump(BpfInsn::jmp_reg(BPF_JGT, R8, R3), rules);
+        asm.emit(BpfInsn::ldx(BPF_H, R1, R5, 0));
+        asm.emit(BpfInsn::stx(BPF_DW, R10, R1, SLOT_SRC_PORT));
+        asm.emit(BpfInsn::ldx(BPF_H, R1, R5, 2));
+        asm.emit(BpfInsn::stx(BPF_DW, R10, R1, SLOT_DST_PORT));
+
+        asm.bind(rules);
+        for (slot, rule) in self.rules.iter().enumerate() {
+            let next = asm.label();
+            for &(field, value) in &rule.operands {
+                asm.emit(BpfInsn::ldx(BPF_DW, R1, R10, field));
+                asm.jump(BpfInsn::jmp32_imm(BPF_JNE, R1, value), next);
+            }
+            count_and_return(&mut asm, counters_fd, slot as i32, rule.verdict);
+            asm.bind(next);
+        }
+        count_and_return(
+            &mut asm,
+            counters_fd,
+            self.rules.len() as i32,
+            self.default_verdict,
+        );
+
+        asm.bind(pass);
+        asm.emit(BpfInsn::mov64_imm(R0, XDP_PASS as i32));
+        asm.emit(BpfInsn::exit());
+        asm.finish()
+    }
+}
+
+/// Emits the tail of a decision: bump the slot's counter, then return the verdict
+fn count_and_return(asm: &mut Assembler, counters_fd: i32, slot: i32, verdict: u32) {
+    let [load_fd, load_fd_high] = BpfInsn::ld_map_fd(R1, counters_fd);
+    asm.emit(load_fd);
+    asm.emit(load_fd_high);
+    asm.emit(BpfInsn::mov64_reg(R2, R10));
+    asm.emit(BpfInsn::alu64_imm(BPF_ADD, R2, SLOT_KEY as i32));
+    asm.emit(BpfInsn::st_imm(BPF_W, R10, SLOT_KEY, slot));
+    asm.emit(BpfInsn::call(BPF_FUNC_MAP_LOOKUP_ELEM));
+    asm.emit(BpfInsn::jmp_imm(BPF_JEQ, R0, 0).with_off(2));
+    asm.emit(BpfInsn::mov64_imm(R1, 1));
+    asm.emit(BpfInsn::atomic_add(BPF_DW, R0, R1, 0));
+    asm.emit(BpfInsn::mov64_imm(R0, verdict as i32));
+    asm.emit(BpfInsn::exit());
+}
+
+/// Gets the stack slot and network-order value a condition compares, or None if the program
+/// cannot test it
+fn condition_operand(condition: &FilterCondition) -> Option<(i16, i32)> {
+    match condition {
+        FilterCondition::SourceIp(IpAddr::V4(addr)) => {
+            Some((SLOT_SRC_IP, u32::from_ne_bytes(addr.octets()) as i32))
+        }
+        FilterCondition::DestIp(IpAddr::V4(addr)) => {
+            Some((SLOT_DST_IP, u32::from_ne_bytes(addr.octets()) as i32))
+        }
+        FilterCondition::SourcePort(port) => Some((SLOT_SRC_PORT, network_u16(*port))),
+        FilterCondition::DestPort(port) => Some((SLOT_DST_PORT, network_u16(*port))),
+        FilterCondition::Protocol(protocol) => Some((SLOT_PROTOCOL, *protocol as i32)),
+        FilterCondition::SourceIp(IpAddr::V6(_))
+        | FilterCondition::DestIp(IpAddr::V6(_))
+        | FilterCondition::Ipv6Address { .. } => None,
+    }
+}
+
+/// Gets the value a native-endian load of a big-endian u16 field yields
+fn network_u16(value: u16) -> i32 {
+    u16::from_ne_bytes(value.to_be_bytes()) as i32
+}
+
+/// One eBPF instruction, laid out like the kernel's `struct bpf_insn`
+///
+/// # Fields
+/// * `code` - Opcode
+/// * `regs` - Destination and source registers, packed into nibbles
+/// * `off` - Signed offset of memory accesses and jumps
+/// * `imm` - Signed immediate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct BpfInsn {
+    pub code: u8,
+    pub regs: u8,
+    pub off: i16,
+    pub imm: i32,
+}
+
+impl BpfInsn {
+    fn new(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> Self {
+        // The register bitfields follow the byte order of the target
+        #[cfg(target_endian = "little")]
+        let regs = (src << 4) | (dst & 0x0f);
+        #[cfg(target_endian = "big")]
+        let regs = (dst << 4) | (src & 0x0f);
+        Self {
+            code,
+            regs,
+            off,
+            imm,
+        }
+    }
+
+    /// Gets the destination register
+    pub fn dst(&self) -> u8 {
+        #[cfg(target_endian = "little")]
+        return self.regs & 0x0f;
+        #[cfg(target_endian = "big")]
+        return self.regs >> 4;
+    }
+
+    /// Gets the source register
+    pub fn src(&self) -> u8 {
+        #[cfg(target_endian = "little")]
+        return self.regs >> 4;
+        #[cfg(target_endian = "big")]
+        return self.regs & 0x0f;
+    }
+
+    fn with_off(mut self, off: i16) -> Self {
+        self.off = off;
+        self
+    }
+
+    fn mov64_reg(dst: u8, src: u8) -> Self {
+        Self::new(BPF_ALU64 | BPF_MOV | BPF_X, dst, src, 0, 0)
+    }
+
+    fn mov64_imm(dst: u8, imm: i32) -> Self {
+        Self::new(BPF_ALU64 | BPF_MOV | BPF_K, dst, 0, 0, imm)
+    }
+
+    fn alu64_imm(op: u8, dst: u8, imm: i32) -> Self {
+        Self::new(BPF_ALU64 | op | BPF_K, dst, 0, 0, imm)
+    }
+
+    fn alu64_reg(op: u8, dst: u8, src: u8) -> Self {
+        Self::new(BPF_ALU64 | op | BPF_X, dst, src, 0, 0)
+    }
+
+    fn ldx(size: u8, dst: u8, src: u8, off: i16) -> Self {
+        Self::new(BPF_LDX | size | BPF_MEM, dst, src, off, 0)
+    }
+
+    fn stx(size: u8, dst: u8, src: u8, off: i16) -> Self {
+        Self::new(BPF_STX | size | BPF_MEM, dst, src, off, 0)
+    }
+
+    fn st_imm(size: u8, dst: u8, off: i16, imm: i32) -> Self {
+        Self::new(BPF_ST | size | BPF_MEM, dst, 0, off, imm)
+    }
+
+    fn atomic_add(size: u8, dst: u8, src: u8, off: i16) -> Self {
+        Self::new(BPF_STX | size | BPF_ATOMIC, dst, src, off, BPF_ADD as i32)
+    }
+
+    fn ld_map_fd(dst: u8, fd: i32) -> [Self; 2] {
+        [
+            Self::new(BPF_LD | BPF_DW | BPF_IMM, dst, BPF_PSEUDO_MAP_FD, 0, fd),
+            Self::new(0, 0, 0, 0, 0),
+        ]
+    }
+
+    fn jmp_imm(op: u8, dst: u8, imm: i32) -> Self {
+        Self::new(BPF_JMP | op | BPF_K, dst, 0, 0, imm)
+    }
+
+    fn jmp_reg(op: u8, dst: u8, src: u8) -> Self {
+        Self::new(BPF_JMP | op | BPF_X, dst, src, 0, 0)
+    }
+
+    fn jmp32_imm(op: u8, dst: u8, imm: i32) -> Self {
+        Self::new(BPF_JMP32 | op | BPF_K, dst, 0, 0, imm)
+    }
+
+    fn call(helper: i32) -> Self {
+        Self::new(BPF_JMP | BPF_CALL, 0, 0, 0, helper)
+    }
+
+    fn exit() -> Self {
+        Self::new(BPF_JMP | BPF_EXIT, 0, 0, 0, 0)
+    }
+}
+
+/// Instruction buffer with forward jump labels
+///
+/// # Fields
+/// * `insns` - Emitted instructions
+/// * `labels` - Bound position of each label
+/// * `fixups` - Jumps waiting for their label, as instruction index and label
+#[derive(Debug, Default)]
+struct Assembler {
+    insns: Vec<BpfInsn>,
+    labels: Vec<Option<usize>>,
+    fixups: Vec<(usize, usize)>,
+}
+
+impl Assembler {
+    fn label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn bind(&mut self, label: usize) {
+        self.labels[label] = Some(self.insns.len());
+    }
+
+    fn emit(&mut self, insn: BpfInsn) {
+        self.insns.push(insn);
+    }
+
+    fn jump(&mut self, insn: BpfInsn, label: usize) {
+        self.fixups.push((self.insns.len(), label));
+        self.insns.push(insn);
+    }
+
+    fn finish(mut self) -> Vec<BpfInsn> {
+        for (at, label) in self.fixups {
+            let target = self.labels[label].unwrap_or(self.insns.len());
+            self.insns[at].off = (target as isize - at as isize - 1) as i16;
+        }
+        self.insns
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "xdp"))]
+mod loader {
+    use std::collections::HashMap;
+    use std::ffi::CString;
+    use std::io;
+    use std::mem::size_of;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    use super::{BpfInsn, OffloadMatchCounts, OffloadStatus, XdpPlan};
+    use crate::capture_engine::capture::capture_error::{
+        CaptureError, CaptureErrorKind, ConfigErrorKind, NetworkErrorKind, SystemErrorKind,
+    };
+    use crate::capture_engine::filter::ruleset::FilterRuleset;
+    use crate::capture_engine::filter::stats::{RuleId, RuleMatchStats};
+
+    const BPF_MAP_CREATE: libc::c_int = 0;
+    const BPF_MAP_LOOKUP_ELEM: libc::c_int = 1;
+    const BPF_PROG_LOAD: libc::c_int = 5;
+    const BPF_LINK_CREATE: libc::c_int = 28;
+    const BPF_LINK_DETACH: libc::c_int = 34;
+
+    const BPF_MAP_TYPE_ARRAY: u32 = 2;
+    const BPF_PROG_TYPE_XDP: u32 = 6;
+    const BPF_XDP: u32 = 37;
+
+    const XDP_FLAGS_SKB_MODE: u32 = 1 << 1;
+    const XDP_FLAGS_DRV_MODE: u32 = 1 << 2;
+
+    const PROGRAM_NAME: &[u8] = b"sparktrap_xdp";
+    const LICENSE: &[u8] = b"Dual MIT/GPL\0";
+    /// Size of the verifier log fetched when a load fails
+    const VERIFIER_LOG_SIZE: usize = 64 * 1024;
+
+    /// Where the XDP program runs.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum XdpMode {
+        /// Let the kernel pick the driver hook when available.
+        #[default]
+        Auto,
+        /// In the NIC driver; fails if the driver lacks XDP support.
+        Native,
+        /// On the generic skb path; works everywhere but after skb allocation.
+        Generic,
+    }
+
+    impl XdpMode {
+        fn flags(self) -> u32 {
+            match self {
+                XdpMode::Auto => 0,
+                XdpMode::Native => XDP_FLAGS_DRV_MODE,
+                XdpMode::Generic => XDP_FLAGS_SKB_MODE,
+            }
+        }
+    }
+
+    #[repr(C)]
+    struct MapCreateAttr {
+        map_type: u32,
+        key_size: u32,
+        value_size: u32,
+        max_entries: u32,
+        map_flags: u32,
+    }
+
+    #[repr(C)]
+    struct ProgLoadAttr {
+        prog_type: u32,
+        insn_cnt: u32,
+        insns: u64,
+        license: u64,
+        log_level: u32,
+        log_size: u32,
+        log_buf: u64,
+        kern_version: u32,
+        prog_flags: u32,
+        prog_name: [u8; 16],
+        prog_ifindex: u32,
+        expected_attach_type: u32,
+    }
+
+    #[repr(C)]
+    struct LinkCreateAttr {
+        prog_fd: u32,
+        target_ifindex: u32,
+        attach_type: u32,
+        flags: u32,
+    }
+
+    #[repr(C)]
+    struct LinkDetachAttr {
+        link_fd: u32,
+    }
+
+    #[repr(C)]
+    struct MapElemAttr {
+        map_fd: u32,
+        pad: u32,
+        key: u64,
+        value: u64,
+        flags: u64,
+    }
+
+    /// Issues a bpf(2) command.
+    pub(super) fn bpf<T>(cmd: libc::c_int, attr: &mut T) -> io::Result<libc::c_long> {
+        // SAFETY: `attr` is a live bpf_attr variant and the size passed is its size.
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_bpf,
+                cmd,
+                attr as *mut T,
+                size_of::<T>() as libc::c_uint,
+            )
+        };
+        if rc < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(rc)
+        }
+    }
+
+    /// Issues a bpf(2) command that returns a new file descriptor.
+    fn bpf_fd<T>(cmd: libc::c_int, attr: &mut T) -> io::Result<OwnedFd> {
+        let fd = bpf(cmd, attr)?;
+        // SAFETY: the command returned a new descriptor owned by nothing else.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+    }
+
+    /// A counter map and the program compiled against it, loaded but not attached.
+    pub(super) struct LoadedProgram {
+        pub(super) counters: OwnedFd,
+        pub(super) program: OwnedFd,
+        pub(super) slots: u32,
+    }
+
+    impl LoadedProgram {
+        /// Creates the counter map and loads the plan's program.
+        pub(super) fn load(plan: &XdpPlan) -> io::Result<Self> {
+            let slots = plan.counter_slots();
+            let mut map = MapCreateAttr {
+                map_type: BPF_MAP_TYPE_ARRAY,
+                key_size: size_of::<u32>() as u32,
+                value_size: size_of::<u64>() as u32,
+                max_entries: slots,
+                map_flags: 0,
+            };
+            let counters = bpf_fd(BPF_MAP_CREATE, &mut map)?;
+            let insns = plan.compile(counters.as_raw_fd());
+
+            let program = match Self::load_insns(&insns, None) {
+                Ok(program) => program,
+                // Load again with the verifier log so the error says why
+                Err(error) if error.raw_os_error() != Some(libc::EPERM) => {
+                    let mut log = vec![0u8; VERIFIER_LOG_SIZE];
+                    return Err(match Self::load_insns(&insns, Some(&mut log)) {
+                        Ok(_) => error,
+                        Err(error) => {
+                            let end = log.iter().position(|&b| b == 0).unwrap_or(log.len());
+                            let log = String::from_utf8_lossy(&log[..end]);
+                            let tail: Vec<&str> = log.lines().rev().take(4).collect();
+                            io::Error::new(
+                                error.kind(),
+                                format!(
+                                    "{}: {}",
+                                    error,
+                                    tail.into_iter().rev().collect::<Vec<_>>().join(" | ")
+                                ),
+                            )
+                        }
+                    });
+                }
+                Err(error) => return Err(error),
+            };
+            Ok(Self {
+                counters,
+                program,
+                slots,
+            })
+        }
+
+        fn load_insns(insns: &[BpfInsn], log: Option<&mut Vec<u8>>) -> io::Result<OwnedFd> {
+            let mut prog_name = [0u8; 16];
+            prog_name[..PROGRAM_NAME.len()].copy_from_slice(PROGRAM_NAME);
+            let (log_level, log_size, log_buf) = match log {
+                Some(log) => (1, log.len() as u32, log.as_mut_ptr() as u64),
+                None => (0, 0, 0),
+            };
+            let mut attr = ProgLoadAttr {
+                prog_type: BPF_PROG_TYPE_XDP,
+                insn_cnt: insns.len() as u32,
+                insns: insns.as_ptr() as u64,
+                license: LICENSE.as_ptr() as u64,
+                log_level,
+                log_size,
+                log_buf,
+                kern_version: 0,
+                prog_flags: 0,
+                prog_name,
+                prog_ifindex: 0,
+                expected_attach_type: BPF_XDP,
+            };
+            bpf_fd(BPF_PROG_LOAD, &mut attr)
+        }
+
+        /// Reads every counter map slot.
+        pub(super) fn counters(&self) -> io::Result<Vec<u64>> {
+            (0..self.slots)
+                .map(|slot| {
+                    let key = slot;
+                    let mut value = 0u64;
+                    let mut attr = MapElemAttr {
+                        map_fd: self.counters.as_raw_fd() as u32,
+                        pad: 0,
+                        key: &key as *const u32 as u64,
+                        value: &mut value as *mut u64 as u64,
+                        flags: 0,
+                    };
+                    bpf(BPF_MAP_LOOKUP_ELEM, &mut attr)?;
+                    Ok(value)
+                })
+                .collect()
+        }
+    }
+
+    fn offload_error(message: &str, source: io::Error) -> CaptureError {
+        CaptureError::new(
+            CaptureErrorKind::System(SystemErrorKind::InitializationFailed),
+            message,
+        )
+        .with_source(source)
+    }
+
+    /// Filter rules offloaded to an XDP program on an interface.
+    ///
+    /// The program is attached through a bpf link, which the kernel detaches as soon as the
+    /// link's last descriptor closes. Dropping the filter, or the process exiting for any
+    /// reason, therefore never leaves the program on the NIC.
+    pub struct XdpFilter {
+        interface: String,
+        plan: XdpPlan,
+        program: LoadedProgram,
+        link: Option<OwnedFd>,
+    }
+
+    impl std::fmt::Debug for XdpFilter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("XdpFilter")
+                .field("interface", &self.interface)
+                .field("status", &self.plan.status())
+                .field("attached", &self.link.is_some())
+                .finish()
+        }
+    }
+
+    impl XdpFilter {
+        /// Offloads what it can of a ruleset to an interface.
+        ///
+        /// Returns `Ok(None)` when no rule can be offloaded; the ruleset then runs entirely in
+        /// software and nothing is attached.
+        pub fn attach(
+            interface: &str,
+            ruleset: &FilterRuleset,
+            mode: XdpMode,
+        ) -> Result<Option<Self>, CaptureError> {
+            let plan = XdpPlan::new(ruleset);
+            if plan.status() == OffloadStatus::NotOffloaded {
+                return Ok(None);
+            }
+
+            let name = CString::new(interface).map_err(|_| {
+                CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    "Interface name contains a NUL byte",
+                )
+            })?;
+            // SAFETY: `name` is a valid NUL-terminated string.
+            let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+            if ifindex == 0 {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Network(NetworkErrorKind::InterfaceNotFound),
+                    &format!("Interface {} not found", interface),
+                ));
+            }
+
+            let program = LoadedProgram::load(&plan)
+                .map_err(|e| offload_error("Failed to load the XDP filter program", e))?;
+            let mut attr = LinkCreateAttr {
+                prog_fd: program.program.as_raw_fd() as u32,
+                target_ifindex: ifindex,
+                attach_type: BPF_XDP,
+                flags: mode.flags(),
+            };
+            let link = bpf_fd(BPF_LINK_CREATE, &mut attr).map_err(|e| {
+                offload_error(
+                    &format!("Failed to attach the XDP filter to {}", interface),
+                    e,
+                )
+            })?;
+            Ok(Some(Self {
+                interface: interface.to_string(),
+                plan,
+                program,
+                link: Some(link),
+            }))
+        }
+
+        /// Gets the interface the program is attached to.
+        pub fn interface(&self) -> &str {
+            &self.interface
+        }
+
+        /// Gets how much of the ruleset runs in the kernel.
+        pub fn status(&self) -> OffloadStatus {
+            self.plan.status()
+        }
+
+        /// Gets the offload plan.
+        pub fn plan(&self) -> &XdpPlan {
+            &self.plan
+        }
+
+        /// Combines the kernel counters with the software ruleset's match counts.
+        pub fn match_counts(
+            &self,
+            software: &HashMap<RuleId, RuleMatchStats>,
+        ) -> Result<OffloadMatchCounts, CaptureError> {
+            let counters = self
+                .program
+                .counters()
+                .map_err(|e| offload_error("Failed to read the XDP filter counters", e))?;
+            Ok(self.plan.match_counts(&counters, software))
+        }
+
+        /// Detaches the program from the interface.
+        ///
+        /// Forces the detach even if the link descriptor was duplicated elsewhere.
+        pub fn detach(mut self) -> Result<(), CaptureError> {
+            self.detach_link()
+        }
+
+        fn detach_link(&mut self) -> Result<(), CaptureError> {
+            let Some(link) = self.link.take() else {
+                return Ok(());
+            };
+            let mut attr = LinkDetachAttr {
+                link_fd: link.as_raw_fd() as u32,
+            };
+            bpf(BPF_LINK_DETACH, &mut attr).map(drop).map_err(|e| {
+                offload_error(
+                    &format!("Failed to detach the XDP filter from {}", self.interface),
+                    e,
+                )
+            })
+        }
+    }
+
+    impl Drop for XdpFilter {
+        fn drop(&mut self) {
+            // Closing the link detaches too; the explicit detach also covers duplicated fds
+            let _ = self.detach_link();
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "xdp"))]
+pub use loader::{XdpFilter, XdpMode};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::control::traits::FilterRule;
+    use crate::capture_engine::filter::ruleset::CompiledRuleset;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    const PACKET: u64 = 0x1000_0000;
+    const CONTEXT: u64 = 0x2000_0000;
+    const STACK: u64 = 0x3000_0000;
+    const STACK_SIZE: u64 = 512;
+    const MAP_VALUES: u64 = 0x4000_0000;
+    const COUNTERS_FD: i32 = 7;
+
+    /// Interpreter for the instructions the compiler emits, with bounds-checked memory.
+    struct Vm<'a> {
+        frame: &'a [u8],
+        stack: [u8; STACK_SIZE as usize],
+        counters: Vec<u64>,
+    }
+
+    impl<'a> Vm<'a> {
+        fn run(insns: &[BpfInsn], frame: &'a [u8], slots: u32) -> (u32, Vec<u64>) {
+            let mut vm = Vm {
+                frame,
+                stack: [0; STACK_SIZE as usize],
+                counters: vec![0; slots as usize],
+            };
+            let mut regs = [0u64; 11];
+            regs[1] = CONTEXT;
+            regs[10] = STACK + STACK_SIZE;
+            let mut pc = 0usize;
+            loop {
+                let insn = insns[pc];
+                let (dst, src) = (insn.dst() as usize, insn.src() as usize);
+                let imm = insn.imm as i64 as u64;
+                let target = (pc as isize + 1 + insn.off as isize) as usize;
+                pc += 1;
+                let size = |code: u8| match code & 0x18 {
+                    BPF_W => 4,
+                    BPF_H => 2,
+                    BPF_B => 1,
+                    _ => 8,
+                };
+                match insn.code {
+                    0xbf => regs[dst] = regs[src],
+                    0xb7 => regs[dst] = imm,
+                    0x07 => regs[dst] = regs[dst].wrapping_add(imm),
+                    0x0f => regs[dst] = regs[dst].wrapping_add(regs[src]),
+                    0x57 => regs[dst] &= imm,
+                    0x67 => regs[dst] <<= imm,
+                    0x77 => regs[dst] >>= imm,
+                    0x61 | 0x69 | 0x71 | 0x79 => {
+                        let addr = regs[src].wrapping_add(insn.off as i64 as u64);
+                        regs[dst] = vm.load(addr, size(insn.code));
+                    }
+                    0x7b => {
+                        let addr = regs[dst].wrapping_add(insn.off as i64 as u64);
+                        vm.store(addr, 8, regs[src]);
+                    }
+                    0x62 => {
+                        let addr = regs[dst].wrapping_add(insn.off as i64 as u64);
+                        vm.store(addr, 4, imm);
+                    }
+                    0xdb => {
+                        let addr = regs[dst].wrapping_add(insn.off as i64 as u64);
+                        let slot = ((addr - MAP_VALUES) / 8) as usize;
+                        vm.counters[slot] += regs[src];
+                    }
+                    0x18 => {
+                        assert_eq!(insn.src(), BPF_PSEUDO_MAP_FD);
+                        assert_eq!(insn.imm, COUNTERS_FD);
+                        regs[dst] = insn.imm as u64;
+                        pc += 1;
+                    }
+                    0x85 => {
+                        assert_eq!(insn.imm, BPF_FUNC_MAP_LOOKUP_ELEM);
+                        let key = vm.load(regs[2], 4);
+                        regs[0] = if key < slots as u64 {
+                            MAP_VALUES + key * 8
+                        } else {
+                            0
+                        };
+                    }
+                    0x95 => return (regs[0] as u32, vm.counters),
+                    code => {
+                        let taken = match code & 0x07 {
+                            BPF_JMP => {
+                                let operand = if code & BPF_X != 0 { regs[src] } else { imm };
+                                jump_taken(code & 0xf0, regs[dst], operand)
+                            }
+                            BPF_JMP32 => {
+                                let operand = insn.imm as u32 as u64;
+                                jump_taken(code & 0xf0, regs[dst] & 0xffff_ffff, operand)
+                            }
+                            _ => panic!("unexpected opcode {:#x}", code),
+                        };
+                        if taken {
+                            pc = target;
+                        }
+                    }
+                }
+            }
+        }
+
+        fn load(&self, addr: u64, size: usize) -> u64 {
+            let mut bytes = [0u8; 8];
+            if (CONTEXT..CONTEXT + 8).contains(&addr) {
+                return match addr - CONTEXT {
+                    0 => PACKET,
+                    4 => PACKET + self.frame.len() as u64,
+                    _ => panic!("unexpected context read"),
+                };
+            } else if (PACKET..CONTEXT).contains(&addr) {
+                let start = (addr - PACKET) as usize;
+                let field = self
+                    .frame
+                    .get(start..start + size)
+                    .expect("packet read past data_end");
+                bytes[..size].copy_from_slice(field);
+            } else {
+                let start = (addr - STACK) as usize;
+                bytes[..size].copy_from_slice(&self.stack[start..start + size]);
+            }
+            u64::from_ne_bytes(bytes)
+        }
+
+        fn store(&mut self, addr: u64, size: usize, value: u64) {
+            assert!((STACK..STACK + STACK_SIZE).contains(&addr));
+            let start = (addr - STACK) as usize;
+            self.stack[start..start + size].copy_from_slice(&value.to_ne_bytes()[..size]);
+        }
+    }
+
+    fn jump_taken(op: u8, lhs: u64, rhs: u64) -> bool {
+        match op {
+            BPF_JEQ => lhs == rhs,
+            BPF_JNE => lhs != rhs,
+            BPF_JGT => lhs > rhs,
+            BPF_JGE => lhs >= rhs,
+            BPF_JSET => lhs & rhs != 0,
+            0x00 => true,
+            _ => panic!("unexpected jump {:#x}", op),
+        }
+    }
+
+    fn rule(
+        id: &str,
+        priority: u32,
+        conditions: Vec<FilterCondition>,
+        action: FilterAction,
+    ) -> FilterRule {
+        FilterRule {
+            id: id.to_string(),
+            priority,
+            conditions,
+            action,
+        }
+    }
+
+    fn ruleset(rules: Vec<FilterRule>, default_action: FilterAction) -> FilterRuleset {
+        FilterRuleset {
+            id: "xdp".to_string(),
+            rules,
+            default_action,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn frame(
+        ethertype: u16,
+        version_ihl: u8,
+        fragment: u16,
+        protocol: u8,
+        src: [u8; 4],
+        dst: [u8; 4],
+        ports: (u16, u16),
+        transport_len: usize,
+    ) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        let ihl = ((version_ihl & 0x0f) as usize * 4).max(20);
+        let mut ip = vec![0u8; ihl];
+        ip[0] = version_ihl;
+        ip[6..8].copy_from_slice(&fragment.to_be_bytes());
+        ip[9] = protocol;
+        ip[12..16].copy_from_slice(&src);
+        ip[16..20].copy_from_slice(&dst);
+        frame.extend_from_slice(&ip);
+        let mut transport = ports.0.to_be_bytes().to_vec();
+        transport.extend_from_slice(&ports.1.to_be_bytes());
+        transport.resize(transport_len, 0xaa);
+        frame.extend_from_slice(&transport);
+        frame
+    }
+
+    fn udp(dst: [u8; 4], dst_port: u16) -> Vec<u8> {
+        frame(0x0800, 0x45, 0, 17, [10, 0, 0, 1], dst, (4000, dst_port), 8)
+    }
+
+    #[test]
+    fn test_plan_offloads_prefix_until_first_software_rule() {
+        let plan = XdpPlan::new(&ruleset(
+            vec![
+                rule(
+                    "late",
+                    30,
+                    vec![FilterCondition::DestPort(80)],
+                    FilterAction::Drop,
+                ),
+                rule(
+                    "mirror",
+                    20,
+                    vec![FilterCondition::Protocol(6)],
+                    FilterAction::Mirror,
+                ),
+                rule(
+                    "first",
+                    10,
+                    vec![FilterCondition::DestPort(53)],
+                    FilterAction::Accept,
+                ),
+            ],
+            FilterAction::Drop,
+        ));
+
+        assert_eq!(plan.offloaded_rules(), vec!["first"]);
+        assert_eq!(
+            plan.status(),
+            OffloadStatus::Partial {
+                software_rules: vec!["mirror".to_string(), "late".to_string()],
+            }
+        );
+        // The default cannot be decided in the kernel while rules remain in software
+        assert_eq!(plan.default_verdict, XDP_PASS);
+    }
+
+    #[test]
+    fn test_plan_leaves_ipv6_rules_to_software() {
+        let v6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let plan = XdpPlan::new(&ruleset(
+            vec![rule(
+                "v6",
+                1,
+                vec![FilterCondition::SourceIp(v6)],
+                FilterAction::Drop,
+            )],
+            FilterAction::Accept,
+        ));
+        assert_eq!(plan.status(), OffloadStatus::NotOffloaded);
+        assert_eq!(plan.software_rules(), ["v6".to_string()]);
+    }
+
+    #[test]
+    fn test_plan_full_offload_decides_default() {
+        let plan = XdpPlan::new(&ruleset(
+            vec![rule(
+                "dns",
+                1,
+                vec![FilterCondition::DestPort(53)],
+                FilterAction::Accept,
+            )],
+            FilterAction::Drop,
+        ));
+        assert_eq!(plan.status(), OffloadStatus::Full);
+        assert_eq!(plan.default_verdict, XDP_DROP);
+
+        let insns = plan.compile(COUNTERS_FD);
+        let (verdict, counters) = Vm::run(&insns, &udp([10, 0, 0, 2], 53), plan.counter_slots());
+        assert_eq!((verdict, counters), (XDP_PASS, vec![1, 0]));
+        let (verdict, counters) = Vm::run(&insns, &udp([10, 0, 0, 2], 80), plan.counter_slots());
+        assert_eq!((verdict, counters), (XDP_DROP, vec![0, 1]));
+    }
+
+    #[test]
+    fn test_plan_caps_offloaded_rules() {
+        let rules = (0..XDP_MAX_RULES as u32 + 2)
+            .map(|i| {
+                rule(
+                    &format!("r{}", i),
+                    i,
+                    vec![FilterCondition::DestPort(i as u16)],
+                    FilterAction::Drop,
+                )
+            })
+            .collect();
+        let plan = XdpPlan::new(&ruleset(rules, FilterAction::Accept));
+        assert_eq!(plan.offloaded_rules().len(), XDP_MAX_RULES);
+        assert_eq!(plan.software_rules().len(), 2);
+    }
+
+    #[test]
+    fn test_empty_ruleset_is_not_offloaded() {
+        let plan = XdpPlan::new(&ruleset(Vec::new(), FilterAction::Accept));
+        assert_eq!(plan.status(), OffloadStatus::NotOffloaded);
+
+        let plan = XdpPlan::new(&ruleset(Vec::new(), FilterAction::Drop));
+        assert_eq!(plan.status(), OffloadStatus::Full);
+    }
+
+    #[test]
+    fn test_match_counts_split_kernel_and_software() {
+        let plan = XdpPlan::new(&ruleset(
+            vec![
+                rule(
+                    "dns",
+                    1,
+                    vec![FilterCondition::DestPort(53)],
+                    FilterAction::Drop,
+                ),
+                rule(
+                    "web",
+                    2,
+                    vec![FilterCondition::DestPort(80)],
+                    FilterAction::Mirror,
+                ),
+            ],
+            FilterAction::Accept,
+        ));
+        let software = HashMap::from([(
+            "web".to_string(),
+            RuleMatchStats {
+                evaluated: 10,
+                matched: 4,
+                last_match: None,
+            },
+        )]);
+
+        let counts = plan.match_counts(&[7, 10], &software);
+        assert_eq!(counts.offloaded, HashMap::from([("dns".to_string(), 7)]));
+        assert_eq!(counts.software, HashMap::from([("web".to_string(), 4)]));
+        assert_eq!(counts.passed_to_software, 10);
+    }
+
+    #[test]
+    fn test_non_ipv4_frames_pass_uncounted() {
+        let plan = XdpPlan::new(&ruleset(
+            vec![rule("all", 1, Vec::new(), FilterAction::Drop)],
+            FilterAction::Drop,
+        ));
+        let insns = plan.compile(COUNTERS_FD);
+
+        let arp = frame(0x0806, 0x45, 0, 0, [0; 4], [0; 4], (0, 0), 8);
+        let ipv6_version = frame(0x0800, 0x65, 0, 17, [0; 4], [0; 4], (0, 0), 8);
+        let short = udp([10, 0, 0, 2], 53)[..30].to_vec();
+        for frame in [arp, ipv6_version, short] {
+            assert_eq!(Vm::run(&insns, &frame, 2), (XDP_PASS, vec![0, 0]));
+        }
+    }
+
+    #[test]
+    fn test_fragments_and_truncated_headers_have_no_ports() {
+        let plan = XdpPlan::new(&ruleset(
+            vec![rule(
+                "zero",
+                1,
+                vec![FilterCondition::DestPort(0)],
+                FilterAction::Drop,
+            )],
+            FilterAction::Accept,
+        ));
+        let insns = plan.compile(COUNTERS_FD);
+
+        let fragment = frame(0x0800, 0x45, 0x0010, 17, [1; 4], [2; 4], (1, 2), 8);
+        let truncated = frame(0x0800, 0x45, 0, 6, [1; 4], [2; 4], (1, 2), 3);
+        let options_past_end = frame(0x0800, 0x4f, 0, 6, [1; 4], [2; 4], (1, 2), 0);
+        let icmp = frame(0x0800, 0x45, 0, 1, [1; 4], [2; 4], (1, 2), 8);
+        for frame in [fragment, truncated, options_past_end, icmp] {
+            assert_eq!(Vm::run(&insns, &frame, 2).0, XDP_DROP);
+        }
+        let first = frame(0x0800, 0x45, 0x2000, 17, [1; 4], [2; 4], (1, 2), 8);
+        assert_eq!(Vm::run(&insns, &first, 2).0, XDP_PASS);
+    }
+
+    #[test]
+    fn test_program_decides_like_software_ruleset() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x5844_5021);
+        let addresses = [[10, 0, 0, 1], [10, 0, 0, 2], [192, 168, 1, 9]];
+        let ports = [0u16, 53, 80, 443];
+        let protocols = [1u8, 6, 17];
+
+        for _ in 0..200 {
+            let rules: Vec<FilterRule> = (0..rng.gen_range(1..6))
+                .map(|i| {
+                    let mut conditions = Vec::new();
+                    if rng.gen_bool(0.4) {
+                        let addr = addresses[rng.gen_range(0..addresses.len())];
+                        conditions.push(FilterCondition::SourceIp(IpAddr::V4(addr.into())));
+                    }
+                    if rng.gen_bool(0.4) {
+                        let addr = addresses[rng.gen_range(0..addresses.len())];
+                        conditions.push(FilterCondition::DestIp(IpAddr::V4(addr.into())));
+                    }
+                    if rng.gen_bool(0.4) {
+                        conditions.push(FilterCondition::SourcePort(ports[rng.gen_range(0..4)]));
+                    }
+                    if rng.gen_bool(0.4) {
+                        conditions.push(FilterCondition::DestPort(ports[rng.gen_range(0..4)]));
+                    }
+                    if rng.gen_bool(0.4) {
+                        conditions.push(FilterCondition::Protocol(protocols[rng.gen_range(0..3)]));
+                    }
+                    let action = match rng.gen_range(0..5) {
+                        0 => FilterAction::Mirror,
+                        1 | 2 => FilterAction::Accept,
+                        _ => FilterAction::Drop,
+                    };
+                    rule(&format!("r{}", i), rng.gen_range(0..10), conditions, action)
+                })
+                .collect();
+            let default_action = if rng.gen_bool(0.5) {
+                FilterAction::Drop
+            } else {
+                FilterAction::Accept
+            };
+            let ruleset = ruleset(rules, default_action);
+            let plan = XdpPlan::new(&ruleset);
+            let insns = plan.compile(COUNTERS_FD);
+            let software = CompiledRuleset::compile(&ruleset);
+
+            for _ in 0..50 {
+                let fragment = if rng.gen_bool(0.1) { 0x0008 } else { 0x4000 };
+                let frame = frame(
+                    0x0800,
+                    0x40 | rng.gen_range(4..8),
+                    fragment,
+                    protocols[rng.gen_range(0..3)],
+                    addresses[rng.gen_range(0..addresses.len())],
+                    addresses[rng.gen_range(0..addresses.len())],
+                    (ports[rng.gen_range(0..4)], ports[rng.gen_range(0..4)]),
+                    rng.gen_range(0..12),
+                );
+
+                software.reset_stats();
+                let action = software
+                    .evaluate_packet(&frame[ETH_HLEN as usize..], std::time::UNIX_EPOCH)
+                    .clone();
+                let software_rule = software
+                    .rule_stats()
+                    .into_iter()
+                    .find(|(_, stats)| stats.matched > 0)
+                    .map(|(id, _)| id);
+
+                let (verdict, counters) = Vm::run(&insns, &frame, plan.counter_slots());
+                let hit = counters.iter().position(|&count| count > 0);
+                assert_eq!(counters.iter().sum::<u64>(), 1);
+                let offloaded_rule =
+                    hit.and_then(|slot| plan.offloaded_rules().get(slot).map(|id| id.to_string()));
+                match &software_rule {
+                    Some(id) if plan.software_rules().contains(id) => {
+                        assert_eq!(offloaded_rule, None);
+                        assert_eq!(verdict, XDP_PASS);
+                    }
+                    _ => assert_eq!(offloaded_rule, software_rule),
+                }
+                if verdict == XDP_DROP {
+                    assert!(matches!(action, FilterAction::Drop));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_insn_encoding() {
+        let insn = BpfInsn::ldx(BPF_H, R4, R2, 12);
+        assert_eq!(
+            (insn.code, insn.dst(), insn.src(), insn.off),
+            (0x69, 4, 2, 12)
+        );
+        assert_eq!(std::mem::size_of::<BpfInsn>(), 8);
+
+        let [low, high] = BpfInsn::ld_map_fd(R1, 9);
+        assert_eq!((low.code, low.src(), low.imm), (0x18, BPF_PSEUDO_MAP_FD, 9));
+        assert_eq!(high, BpfInsn::new(0, 0, 0, 0, 0));
+
+        assert_eq!(network_u16(0x0800).to_ne_bytes()[..2], [0x08, 0x00]);
+        let addr = u32::from_ne_bytes(Ipv4Addr::new(10, 0, 0, 1).octets());
+        assert_eq!(addr.to_ne_bytes(), [10, 0, 0, 1]);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "xdp"))]
+    mod kernel {
+        use super::*;
+        use crate::capture_engine::filter::xdp::loader::{bpf, LoadedProgram};
+        use std::os::fd::AsRawFd;
+
+        const BPF_PROG_TEST_RUN: libc::c_int = 10;
+
+        #[repr(C)]
+        #[derive(Default)]
+        struct TestRunAttr {
+            prog_fd: u32,
+            retval: u32,
+            data_size_in: u32,
+            data_size_out: u32,
+            data_in: u64,
+            data_out: u64,
+            repeat: u32,
+            duration: u32,
+        }
+
+        /// Loads the plan, or None when this host may not load BPF programs.
+        fn load(plan: &XdpPlan) -> Option<LoadedProgram> {
+            match LoadedProgram::load(plan) {
+                Ok(program) => Some(program),
+                Err(e) if matches!(e.raw_os_error(), Some(libc::EPERM | libc::ENOSYS)) => None,
+                Err(e) => panic!("program rejected: {}", e),
+            }
+        }
+
+        fn test_run(program: &LoadedProgram, frame: &[u8]) -> u32 {
+            let mut out = vec![0u8; frame.len() + 256];
+            let mut attr = TestRunAttr {
+                prog_fd: program.program.as_raw_fd() as u32,
+                data_size_in: frame.len() as u32,
+                data_size_out: out.len() as u32,
+                data_in: frame.as_ptr() as u64,
+                data_out: out.as_mut_ptr() as u64,
+                repeat: 1,
+                ..Default::default()
+            };
+            bpf(BPF_PROG_TEST_RUN, &mut attr).expect("test run");
+            attr.retval
+        }
+
+        fn rules() -> FilterRuleset {
+            ruleset(
+                vec![
+                    rule(
+                        "block",
+                        1,
+                        vec![
+                            FilterCondition::DestIp(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 9))),
+                            FilterCondition::Protocol(17),
+                            FilterCondition::DestPort(53),
+                        ],
+                        FilterAction::Drop,
+                    ),
+                    rule(
+                        "web",
+                        2,
+                        vec![FilterCondition::DestPort(80)],
+                        FilterAction::Accept,
+                    ),
+                ],
+                FilterAction::Accept,
+            )
+        }
+
+        #[test]
+        fn test_kernel_verifier_accepts_program_and_counts() {
+            let plan = XdpPlan::new(&rules());
+            let Some(program) = load(&plan) else {
+                return;
+            };
+
+            assert_eq!(test_run(&program, &udp([10, 0, 0, 9], 53)), XDP_DROP);
+            assert_eq!(test_run(&program, &udp([10, 0, 0, 9], 80)), XDP_PASS);
+            assert_eq!(test_run(&program, &udp([10, 0, 0, 8], 53)), XDP_PASS);
+            assert_eq!(program.counters().unwrap(), vec![1, 1, 1]);
+        }
+
+        #[test]
+        fn test_kernel_verifier_accepts_full_rule_limit() {
+            let rules = (0..XDP_MAX_RULES as u32)
+                .map(|i| {
+                    let conditions = vec![
+                        FilterCondition::SourceIp(IpAddr::V4(Ipv4Addr::from(i))),
+                        FilterCondition::DestIp(IpAddr::V4(Ipv4Addr::from(i + 1))),
+                        FilterCondition::SourcePort(i as u16),
+                        FilterCondition::DestPort(i as u16),
+                        FilterCondition::Protocol(6),
+                    ];
+                    rule(&format!("r{}", i), i, conditions, FilterAction::Drop)
+                })
+                .collect();
+            let plan = XdpPlan::new(&ruleset(rules, FilterAction::Drop));
+            assert_eq!(plan.status(), OffloadStatus::Full);
+            // Panics if the verifier rejects the program
+            load(&plan);
+        }
+
+        #[test]
+        fn test_attach_and_detach_leave_no_program() {
+            if load(&XdpPlan::new(&rules())).is_none() {
+                return;
+            }
+            let attach = || XdpFilter::attach("lo", &rules(), XdpMode::Generic);
+
+            let filter = attach().unwrap().expect("rules are offloadable");
+            assert_eq!(filter.status(), OffloadStatus::Full);
+            // Only one XDP program may be attached, so a second attach proves the first is live
+            assert!(attach().is_err());
+            filter.detach().unwrap();
+
+            let filter = attach().unwrap().expect("interface is free after detach");
+            drop(filter);
+            let filter = attach().unwrap().expect("interface is free after drop");
+            let counts = filter.match_counts(&HashMap::new()).unwrap();
+            assert_eq!(counts.offloaded.len(), 2);
+        }
+
+        #[test]
+        fn test_attach_unknown_interface() {
+            let error = XdpFilter::attach("sparktrap-none0", &rules(), XdpMode::Auto).unwrap_err();
+            assert!(error.message().contains("not found"));
+        }
+    }
+}
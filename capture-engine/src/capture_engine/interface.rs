@@ -1 +1,15 @@
+#[cfg(target_os = "linux")]
+pub mod af_packet;
+#[cfg(target_os = "linux")]
+mod af_packet_socket;
+#[cfg(all(target_os = "linux", feature = "linux_afpacket"))]
+pub mod af_packet_v3;
+pub mod batch;
+pub mod dpdk;
+pub mod injection;
+pub mod pacing;
+pub mod pcap_file;
+pub mod rate_limit;
+pub mod source;
+pub mod topology;
 pub mod traits;
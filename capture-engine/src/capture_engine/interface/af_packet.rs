@@ -0,0 +1,163 @@
+// interface/af_packet.rs
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::interface::af_packet_socket::{
+    capture_error, kernel_drops, PacketSocket,
+};
+use crate::capture_engine::interface::batch::CaptureBatchResult;
+use crate::capture_engine::interface::source::{FrameArena, PacketSource, PacketSourceKind};
+use crate::traits::Packet;
+
+/// Default number of bytes captured per frame.
+pub const DEFAULT_SNAPLEN: usize = 65_535;
+
+/// Packet source reading from a Linux `AF_PACKET` raw socket bound to one interface.
+///
+/// Frames are received straight into the source's arena, so the only copy is the kernel's.
+/// Opening the socket requires `CAP_NET_RAW`.
+#[derive(Debug)]
+pub struct AfPacketSource {
+    interface: String,
+    snaplen: usize,
+    socket: Option<OwnedFd>,
+    arena: FrameArena,
+}
+
+impl AfPacketSource {
+    /// Creates a source for `interface` capturing up to `snaplen` bytes per frame.
+    pub fn new(interface: &str, snaplen: usize) -> Result<Self, CaptureError> {
+        if snaplen == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Snap length must be greater than zero",
+            ));
+        }
+        Ok(Self {
+            interface: interface.to_string(),
+            snaplen,
+            socket: None,
+            arena: FrameArena::default(),
+        })
+    }
+
+    fn fill(&mut self, max: usize) -> Result<(), CaptureError> {
+        self.arena.clear();
+        let Some(fd) = self.socket.as_ref().map(|s| s.as_raw_fd()) else {
+            return Err(capture_error(
+                "AF_PACKET source must be opened before polling",
+                None,
+            ));
+        };
+
+        while self.arena.len() < max {
+            let received = self.arena.fill_with(self.snaplen, |buf| {
+                // SAFETY: `buf` is a valid writable region of `buf.len()` bytes and `fd` is an
+                // open socket owned by this source.
+                let n = unsafe {
+                    libc::recv(
+                        fd,
+                        buf.as_mut_ptr().cast(),
+                        buf.len(),
+                        libc::MSG_TRUNC | libc::MSG_DONTWAIT,
+                    )
+                };
+                if n < 0 {
+                    let err = io::Error::last_os_error();
+                    return match err.kind() {
+                        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => Ok(None),
+                        _ => Err(err),
+                    };
+                }
+                // With MSG_TRUNC the return value is the frame's length on the wire
+                let original = n as usize;
+                Ok(Some((original.min(buf.len()), original, now_nanos())))
+            });
+            match received {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => return Err(capture_error("Failed to receive frame", Some(e))),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads and resets the kernel's drop counter for the socket.
+    fn kernel_drops(&self) -> u64 {
+        self.socket.as_ref().map_or(0, kernel_drops)
+    }
+}
+
+fn now_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+impl PacketSource for AfPacketSource {
+    fn name(&self) -> &str {
+        &self.interface
+    }
+
+    fn kind(&self) -> PacketSourceKind {
+        PacketSourceKind::AfPacket
+    }
+
+    fn open(&mut self) -> Result<(), CaptureError> {
+        let socket = PacketSocket::open(&self.interface)?.bind()?;
+        self.socket = Some(socket);
+        // Discard drops counted before the source was opened
+        self.kernel_drops();
+        Ok(())
+    }
+
+    fn poll_batch(&mut self, max: usize) -> Result<Vec<Packet<'_>>, CaptureError> {
+        self.fill(max)?;
+        Ok(self.arena.packets())
+    }
+
+    fn poll_capture_batch(&mut self, max: usize) -> Result<CaptureBatchResult<'_>, CaptureError> {
+        self.fill(max)?;
+        let drops = self.kernel_drops();
+        let mut builder = self.arena.batch();
+        builder.add_kernel_drops(drops);
+        Ok(builder.build())
+    }
+
+    fn close(&mut self) -> Result<(), CaptureError> {
+        self.socket = None;
+        self.arena.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::NetworkErrorKind;
+
+    #[test]
+    fn test_unknown_interface_is_rejected() {
+        let mut source = AfPacketSource::new("sparktrap-nope0", DEFAULT_SNAPLEN).unwrap();
+        assert!(matches!(
+            source.open().unwrap_err().kind(),
+            CaptureErrorKind::Network(NetworkErrorKind::InterfaceNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_poll_before_open_fails() {
+        let mut source = AfPacketSource::new("lo", DEFAULT_SNAPLEN).unwrap();
+        assert!(source.poll_batch(8).is_err());
+    }
+
+    #[test]
+    fn test_zero_snaplen_is_rejected() {
+        assert!(AfPacketSource::new("lo", 0).is_err());
+    }
+}
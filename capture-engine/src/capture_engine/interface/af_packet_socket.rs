@@ -0,0 +1,135 @@
+// interface/af_packet_socket.rs
+//! Socket setup shared by the `AF_PACKET` capture backends.
+use std::ffi::CString;
+use std::io;
+use std::mem::size_of;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, NetworkErrorKind,
+};
+
+/// Raw `AF_PACKET` socket receiving every protocol, created for one interface but not yet bound.
+///
+/// Options that must precede `bind`, such as a receive ring, are set on the raw descriptor.
+#[derive(Debug)]
+pub(crate) struct PacketSocket {
+    socket: OwnedFd,
+    ifindex: i32,
+}
+
+impl PacketSocket {
+    /// Resolves `interface` and creates a socket for it.
+    pub(crate) fn open(interface: &str) -> Result<Self, CaptureError> {
+        let name = CString::new(interface).map_err(|_| {
+            CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Interface name contains a NUL byte",
+            )
+        })?;
+        // SAFETY: `name` is a valid NUL-terminated string.
+        let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if ifindex == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Network(NetworkErrorKind::InterfaceNotFound),
+                &format!("Interface {} not found", interface),
+            ));
+        }
+
+        // SAFETY: plain socket(2) call; the result is checked before use.
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_PACKET,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                protocol() as libc::c_int,
+            )
+        };
+        if fd < 0 {
+            return Err(capture_error(
+                "Failed to open AF_PACKET socket",
+                Some(io::Error::last_os_error()),
+            ));
+        }
+        Ok(Self {
+            // SAFETY: `fd` was just returned by socket(2) and is owned by nothing else.
+            socket: unsafe { OwnedFd::from_raw_fd(fd) },
+            ifindex: ifindex as i32,
+        })
+    }
+
+    /// Binds the socket to its interface, after which it receives frames.
+    pub(crate) fn bind(self) -> Result<OwnedFd, CaptureError> {
+        // SAFETY: sockaddr_ll is plain old data, so all-zero is a valid value.
+        let mut addr: libc::sockaddr_ll = unsafe { std::mem::zeroed() };
+        addr.sll_family = libc::AF_PACKET as u16;
+        addr.sll_protocol = protocol();
+        addr.sll_ifindex = self.ifindex;
+        // SAFETY: `addr` is a fully initialized sockaddr_ll and the length matches its size.
+        let rc = unsafe {
+            libc::bind(
+                self.socket.as_raw_fd(),
+                (&addr as *const libc::sockaddr_ll).cast(),
+                size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if rc < 0 {
+            return Err(capture_error(
+                "Failed to bind AF_PACKET socket",
+                Some(io::Error::last_os_error()),
+            ));
+        }
+        Ok(self.socket)
+    }
+}
+
+impl AsRawFd for PacketSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+/// `ETH_P_ALL` in network byte order.
+fn protocol() -> u16 {
+    (libc::ETH_P_ALL as u16).to_be()
+}
+
+/// Reads and resets the kernel's drop counter for a socket.
+///
+/// The buffer is sized for `tpacket_stats_v3`; older ring versions fill only its leading
+/// `tpacket_stats` fields, which share the same layout.
+pub(crate) fn kernel_drops(socket: &OwnedFd) -> u64 {
+    let mut stats = libc::tpacket_stats_v3 {
+        tp_packets: 0,
+        tp_drops: 0,
+        tp_freeze_q_cnt: 0,
+    };
+    let mut len = size_of::<libc::tpacket_stats_v3>() as libc::socklen_t;
+    // SAFETY: `stats` and `len` are valid for writes and sized for PACKET_STATISTICS.
+    let rc = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_PACKET,
+            libc::PACKET_STATISTICS,
+            (&mut stats as *mut libc::tpacket_stats_v3).cast(),
+            &mut len,
+        )
+    };
+    // PACKET_STATISTICS resets on read, so each reading is already a delta
+    if rc == 0 {
+        u64::from(stats.tp_drops)
+    } else {
+        0
+    }
+}
+
+/// Builds a capture failure, keeping the OS error that caused it if there is one.
+pub(crate) fn capture_error(message: &str, source: Option<io::Error>) -> CaptureError {
+    let error = CaptureError::new(
+        CaptureErrorKind::Network(NetworkErrorKind::CaptureFailure),
+        message,
+    );
+    match source {
+        Some(e) => error.with_source(e),
+        None => *error,
+    }
+}
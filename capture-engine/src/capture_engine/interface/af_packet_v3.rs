@@ -0,0 +1,696 @@
+// interface/af_packet_v3.rs
+use std::collections::HashMap;
+use std::io;
+use std::mem::{offset_of, size_of};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, NetworkErrorKind, ResourceErrorKind,
+};
+use crate::capture_engine::interface::af_packet_socket::{
+    capture_error, kernel_drops, PacketSocket,
+};
+use crate::capture_engine::interface::batch::{CaptureBatchBuilder, CaptureBatchResult};
+use crate::capture_engine::interface::source::{PacketSource, PacketSourceKind};
+use crate::capture_engine::interface::traits::InterfaceMetrics;
+use crate::traits::{BufferId, Packet, PacketMetadata};
+
+/// Default size of one ring block.
+pub const DEFAULT_BLOCK_SIZE: usize = 1 << 22;
+/// Default number of ring blocks.
+pub const DEFAULT_BLOCK_COUNT: usize = 64;
+/// Default nominal frame size.
+pub const DEFAULT_FRAME_SIZE: usize = 2048;
+/// Default time after which the kernel retires a partly filled block.
+pub const DEFAULT_BLOCK_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// How a fanout group spreads packets across its sockets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FanoutMode {
+    /// By flow hash, keeping each flow on one socket.
+    Hash,
+    /// Round robin.
+    LoadBalance,
+    /// By the CPU that received the packet.
+    Cpu,
+    /// By the NIC receive queue, matching RSS queues to sockets.
+    QueueMapping,
+}
+
+impl FanoutMode {
+    fn as_raw(self) -> u32 {
+        match self {
+            FanoutMode::Hash => libc::PACKET_FANOUT_HASH,
+            FanoutMode::LoadBalance => libc::PACKET_FANOUT_LB,
+            FanoutMode::Cpu => libc::PACKET_FANOUT_CPU,
+            FanoutMode::QueueMapping => libc::PACKET_FANOUT_QM,
+        }
+    }
+}
+
+/// Membership of a `PACKET_FANOUT` group.
+///
+/// Sockets bound to the same interface with the same group id share its traffic, so one
+/// `AfPacketInterface` per worker scales capture across RSS queues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanoutConfig {
+    pub group_id: u16,
+    pub mode: FanoutMode,
+    /// Reassemble IP fragments before hashing so fragments follow their flow.
+    pub defrag: bool,
+}
+
+/// Layout of the `TPACKET_V3` receive ring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AfPacketConfig {
+    /// Size of one block; a multiple of the page size and of `frame_size`.
+    pub block_size: usize,
+    pub block_count: usize,
+    /// Nominal frame size; with `TPACKET_V3` frames are packed, so this only bounds snaplen.
+    pub frame_size: usize,
+    pub block_timeout: Duration,
+    pub fanout: Option<FanoutConfig>,
+}
+
+impl Default for AfPacketConfig {
+    fn default() -> Self {
+        Self {
+            block_size: DEFAULT_BLOCK_SIZE,
+            block_count: DEFAULT_BLOCK_COUNT,
+            frame_size: DEFAULT_FRAME_SIZE,
+            block_timeout: DEFAULT_BLOCK_TIMEOUT,
+            fanout: None,
+        }
+    }
+}
+
+impl AfPacketConfig {
+    /// Checks the layout against the kernel's ring requirements.
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        // SAFETY: sysconf has no preconditions.
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as usize;
+        if self.block_size == 0 || !self.block_size.is_multiple_of(page_size) {
+            return Err(invalid_config(&format!(
+                "Block size {} must be a non-zero multiple of the page size {}",
+                self.block_size, page_size
+            )));
+        }
+        if self.block_count == 0 {
+            return Err(invalid_config("Block count must be greater than zero"));
+        }
+        if self.frame_size < libc::TPACKET3_HDRLEN
+            || !self.frame_size.is_multiple_of(libc::TPACKET_ALIGNMENT)
+            || !self.block_size.is_multiple_of(self.frame_size)
+        {
+            return Err(invalid_config(&format!(
+                "Frame size {} must be at least {}, a multiple of {} and divide the block size",
+                self.frame_size,
+                libc::TPACKET3_HDRLEN,
+                libc::TPACKET_ALIGNMENT
+            )));
+        }
+        let frames = (self.block_size / self.frame_size).checked_mul(self.block_count);
+        if frames.and_then(|n| u32::try_from(n).ok()).is_none()
+            || u32::try_from(self.block_size).is_err()
+            || self.block_size.checked_mul(self.block_count).is_none()
+        {
+            return Err(invalid_config("Ring layout is too large"));
+        }
+        Ok(())
+    }
+}
+
+fn invalid_config(message: &str) -> CaptureError {
+    *CaptureError::new(
+        CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+        message,
+    )
+}
+
+/// Memory behind a block ring.
+enum Backing {
+    /// The socket's `PACKET_RX_RING` mapping, unmapped on drop.
+    Mapped { len: usize },
+    /// Plain memory laid out like a ring, for exercising the walker without a socket.
+    #[cfg(test)]
+    Heap(#[allow(dead_code)] Vec<u64>),
+}
+
+/// Location of one packet inside the ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameSpan {
+    offset: usize,
+    len: usize,
+    original_len: usize,
+    timestamp: u64,
+}
+
+/// Position inside the block currently being read.
+#[derive(Debug, Clone, Copy)]
+struct BlockCursor {
+    remaining: u32,
+    offset: usize,
+}
+
+/// Walks the blocks of a `TPACKET_V3` ring.
+///
+/// Blocks are handed back to the kernel only at the start of the next poll, so packets from
+/// the previous poll stay valid for as long as the caller's borrow of the source.
+struct BlockRing {
+    base: NonNull<u8>,
+    block_size: usize,
+    block_count: usize,
+    backing: Backing,
+    current: usize,
+    cursor: Option<BlockCursor>,
+    held: Vec<usize>,
+}
+
+// The ring memory is only reached through `&self`/`&mut self`, and the kernel synchronizes
+// with us through each block's status word.
+unsafe impl Send for BlockRing {}
+
+impl BlockRing {
+    fn mapped(fd: i32, block_size: usize, block_count: usize) -> Result<Self, CaptureError> {
+        let len = block_size * block_count;
+        // SAFETY: maps the socket's RX ring; the result is checked before use.
+        let addr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::AllocationFailed),
+                &format!("Failed to map a {} byte TPACKET_V3 ring", len),
+            )
+            .with_source(io::Error::last_os_error()));
+        }
+        Ok(Self::new(
+            NonNull::new(addr.cast()).expect("mmap returned a null mapping"),
+            block_size,
+            block_count,
+            Backing::Mapped { len },
+        ))
+    }
+
+    #[cfg(test)]
+    fn heap(mut memory: Vec<u64>, block_size: usize, block_count: usize) -> Self {
+        assert!(memory.len() * 8 >= block_size * block_count);
+        let base = NonNull::new(memory.as_mut_ptr().cast()).unwrap();
+        Self::new(base, block_size, block_count, Backing::Heap(memory))
+    }
+
+    fn new(base: NonNull<u8>, block_size: usize, block_count: usize, backing: Backing) -> Self {
+        Self {
+            base,
+            block_size,
+            block_count,
+            backing,
+            current: 0,
+            cursor: None,
+            held: Vec::new(),
+        }
+    }
+
+    fn block_start(&self, block: usize) -> *mut u8 {
+        // SAFETY: callers pass block < block_count, so the offset is inside the ring.
+        unsafe { self.base.as_ptr().add(block * self.block_size) }
+    }
+
+    fn status(&self, block: usize) -> &AtomicU32 {
+        let offset = offset_of!(libc::tpacket_block_desc, hdr)
+            + offset_of!(libc::tpacket_hdr_v1, block_status);
+        // SAFETY: the status word is a naturally aligned u32 inside the block descriptor, and
+        // the kernel only updates it atomically.
+        unsafe { &*self.block_start(block).add(offset).cast::<AtomicU32>() }
+    }
+
+    fn header(&self, block: usize) -> libc::tpacket_hdr_v1 {
+        let offset = offset_of!(libc::tpacket_block_desc, hdr);
+        // SAFETY: the descriptor header lies at the start of the block, which we own while
+        // its status is TP_STATUS_USER.
+        unsafe { ptr::read(self.block_start(block).add(offset).cast()) }
+    }
+
+    /// Returns blocks consumed by the previous poll to the kernel.
+    fn release_held(&mut self) {
+        for block in std::mem::take(&mut self.held) {
+            self.status(block)
+                .store(libc::TP_STATUS_KERNEL, Ordering::Release);
+        }
+    }
+
+    /// Collects up to `max` packets from blocks the kernel has handed over.
+    fn poll(&mut self, max: usize) -> Vec<FrameSpan> {
+        self.release_held();
+        let mut spans = Vec::new();
+        while spans.len() < max {
+            // A held block means the walk has wrapped around the whole ring in this poll
+            if self.held.contains(&self.current)
+                || self.status(self.current).load(Ordering::Acquire) & libc::TP_STATUS_USER == 0
+            {
+                break;
+            }
+            let mut cursor = match self.cursor {
+                Some(cursor) => cursor,
+                None => {
+                    let header = self.header(self.current);
+                    BlockCursor {
+                        remaining: header.num_pkts,
+                        offset: header.offset_to_first_pkt as usize,
+                    }
+                }
+            };
+            while cursor.remaining > 0 && spans.len() < max {
+                match self.read_packet(self.current, cursor.offset) {
+                    Some((span, next_offset)) => {
+                        spans.push(span);
+                        cursor.remaining -= 1;
+                        cursor.offset += next_offset;
+                    }
+                    // A malformed block is abandoned rather than read out of bounds
+                    None => cursor.remaining = 0,
+                }
+            }
+            if cursor.remaining > 0 {
+                self.cursor = Some(cursor);
+                break;
+            }
+            self.held.push(self.current);
+            self.cursor = None;
+            self.current = (self.current + 1) % self.block_count;
+        }
+        spans
+    }
+
+    /// Reads the packet header at `offset` within `block`.
+    ///
+    /// Returns the packet's span and the offset of the next packet relative to this one.
+    fn read_packet(&self, block: usize, offset: usize) -> Option<(FrameSpan, usize)> {
+        if offset.checked_add(size_of::<libc::tpacket3_hdr>())? > self.block_size {
+            return None;
+        }
+        // SAFETY: the header lies inside the block, checked above.
+        let header: libc::tpacket3_hdr =
+            unsafe { ptr::read_unaligned(self.block_start(block).add(offset).cast()) };
+        let data_start = offset + header.tp_mac as usize;
+        let len = header.tp_snaplen as usize;
+        if data_start.checked_add(len)? > self.block_size {
+            return None;
+        }
+        let span = FrameSpan {
+            offset: block * self.block_size + data_start,
+            len,
+            original_len: header.tp_len as usize,
+            timestamp: u64::from(header.tp_sec) * 1_000_000_000 + u64::from(header.tp_nsec),
+        };
+        Some((span, header.tp_next_offset as usize))
+    }
+
+    fn slice(&self, span: &FrameSpan) -> &[u8] {
+        // SAFETY: spans are bounds-checked against their block when read, and the block stays
+        // with us until the next poll, which needs `&mut self`.
+        unsafe { std::slice::from_raw_parts(self.base.as_ptr().add(span.offset), span.len) }
+    }
+}
+
+impl Drop for BlockRing {
+    fn drop(&mut self) {
+        match self.backing {
+            // SAFETY: the mapping was created in `mapped` with this length, and packets
+            // borrowing it cannot outlive the ring.
+            Backing::Mapped { len } => unsafe {
+                libc::munmap(self.base.as_ptr().cast(), len);
+            },
+            #[cfg(test)]
+            Backing::Heap(_) => {}
+        }
+    }
+}
+
+/// Packet source reading a Linux interface through a `TPACKET_V3` mmap'd receive ring.
+///
+/// Packets are handed out as slices of the ring itself, so the only copy is the kernel's.
+/// Kernel drops are read from `PACKET_STATISTICS` on every poll and reported both in the batch
+/// and in `metrics().dropped_packets`. Opening the socket requires `CAP_NET_RAW`.
+pub struct AfPacketInterface {
+    interface: String,
+    config: AfPacketConfig,
+    ring: Option<BlockRing>,
+    socket: Option<OwnedFd>,
+    metrics: InterfaceMetrics,
+    next_buffer_id: u64,
+}
+
+impl std::fmt::Debug for AfPacketInterface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AfPacketInterface")
+            .field("interface", &self.interface)
+            .field("config", &self.config)
+            .field("open", &self.socket.is_some())
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+impl AfPacketInterface {
+    /// Creates a source for `interface` with the given ring layout.
+    pub fn new(interface: &str, config: AfPacketConfig) -> Result<Self, CaptureError> {
+        config.validate()?;
+        Ok(Self {
+            interface: interface.to_string(),
+            config,
+            ring: None,
+            socket: None,
+            metrics: InterfaceMetrics::default(),
+            next_buffer_id: 0,
+        })
+    }
+
+    /// Ring layout.
+    pub fn config(&self) -> &AfPacketConfig {
+        &self.config
+    }
+
+    /// Packet, byte and drop counters since the source was created.
+    pub fn metrics(&self) -> &InterfaceMetrics {
+        &self.metrics
+    }
+
+    fn set_option<T>(
+        fd: i32,
+        name: libc::c_int,
+        value: &T,
+        what: &str,
+    ) -> Result<(), CaptureError> {
+        // SAFETY: `value` is valid for reads of size_of::<T>() bytes.
+        let rc = unsafe {
+            libc::setsockopt(
+                fd,
+                libc::SOL_PACKET,
+                name,
+                (value as *const T).cast(),
+                size_of::<T>() as libc::socklen_t,
+            )
+        };
+        if rc < 0 {
+            return Err(capture_error(what, Some(io::Error::last_os_error())));
+        }
+        Ok(())
+    }
+
+    /// Reads and resets the kernel's drop counter for the socket.
+    fn kernel_drops(&self) -> u64 {
+        self.socket.as_ref().map_or(0, kernel_drops)
+    }
+
+    fn collect(&mut self, max: usize) -> Result<CaptureBatchResult<'_>, CaptureError> {
+        let Some(ring) = self.ring.as_mut() else {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Network(NetworkErrorKind::CaptureFailure),
+                "AF_PACKET interface must be opened before polling",
+            ));
+        };
+        let spans = ring.poll(max);
+        let drops = self.kernel_drops();
+
+        self.metrics.packets_received += spans.len() as u64;
+        self.metrics.bytes_received += spans.iter().map(|s| s.original_len as u64).sum::<u64>();
+        self.metrics.dropped_packets += drops;
+        let first_id = self.next_buffer_id;
+        self.next_buffer_id += spans.len() as u64;
+
+        let ring = self.ring.as_ref().expect("ring checked above");
+        let mut builder = CaptureBatchBuilder::new();
+        for (id, span) in (first_id..).zip(&spans) {
+            let packet = Packet {
+                timestamp: span.timestamp,
+                data: ring.slice(span),
+                length: span.original_len,
+                metadata: PacketMetadata {
+                    compact_data: 0,
+                    additional_info: HashMap::new(),
+                },
+                buffer_id: BufferId::new(id),
+            };
+            builder.push(packet, span.original_len);
+        }
+        builder.add_kernel_drops(drops);
+        Ok(builder.build())
+    }
+}
+
+impl PacketSource for AfPacketInterface {
+    fn name(&self) -> &str {
+        &self.interface
+    }
+
+    fn kind(&self) -> PacketSourceKind {
+        PacketSourceKind::AfPacket
+    }
+
+    fn open(&mut self) -> Result<(), CaptureError> {
+        let socket = PacketSocket::open(&self.interface)?;
+        let fd = socket.as_raw_fd();
+
+        let version = libc::tpacket_versions::TPACKET_V3 as libc::c_int;
+        Self::set_option(
+            fd,
+            libc::PACKET_VERSION,
+            &version,
+            "Failed to select TPACKET_V3",
+        )?;
+        let config = &self.config;
+        let request = libc::tpacket_req3 {
+            tp_block_size: config.block_size as u32,
+            tp_block_nr: config.block_count as u32,
+            tp_frame_size: config.frame_size as u32,
+            tp_frame_nr: (config.block_size / config.frame_size * config.block_count) as u32,
+            tp_retire_blk_tov: config.block_timeout.as_millis().min(u32::MAX as u128) as u32,
+            tp_sizeof_priv: 0,
+            tp_feature_req_word: 0,
+        };
+        Self::set_option(
+            fd,
+            libc::PACKET_RX_RING,
+            &request,
+            "Failed to set up the TPACKET_V3 receive ring",
+        )?;
+        let ring = BlockRing::mapped(fd, config.block_size, config.block_count)?;
+
+        let socket = socket.bind()?;
+
+        // Fanout can only be joined once the socket is bound
+        if let Some(fanout) = config.fanout {
+            let mut mode = fanout.mode.as_raw();
+            if fanout.defrag {
+                mode |= libc::PACKET_FANOUT_FLAG_DEFRAG;
+            }
+            let arg = (u32::from(fanout.group_id) | (mode << 16)) as libc::c_int;
+            Self::set_option(
+                fd,
+                libc::PACKET_FANOUT,
+                &arg,
+                "Failed to join AF_PACKET fanout group",
+            )?;
+        }
+
+        self.ring = Some(ring);
+        self.socket = Some(socket);
+        // Discard drops counted before the source was opened
+        self.kernel_drops();
+        Ok(())
+    }
+
+    fn poll_batch(&mut self, max: usize) -> Result<Vec<Packet<'_>>, CaptureError> {
+        Ok(self.collect(max)?.packets)
+    }
+
+    fn poll_capture_batch(&mut self, max: usize) -> Result<CaptureBatchResult<'_>, CaptureError> {
+        self.collect(max)
+    }
+
+    fn close(&mut self) -> Result<(), CaptureError> {
+        // Unmap before closing the socket that owns the ring
+        self.ring = None;
+        self.socket = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_SIZE: usize = 4096;
+
+    /// Lays out blocks holding the given packets, marking each block as handed to user space.
+    fn ring_with(blocks: &[&[&[u8]]]) -> BlockRing {
+        let mut memory = vec![0u64; BLOCK_SIZE * blocks.len() / 8];
+        let base: *mut u8 = memory.as_mut_ptr().cast();
+        let first_pkt = 64;
+        let mac_offset = libc::TPACKET3_HDRLEN;
+        for (index, packets) in blocks.iter().enumerate() {
+            let block = unsafe { base.add(index * BLOCK_SIZE) };
+            let mut offset = first_pkt;
+            for (n, data) in packets.iter().enumerate() {
+                let next = if n + 1 == packets.len() {
+                    0
+                } else {
+                    (mac_offset + data.len()).next_multiple_of(libc::TPACKET_ALIGNMENT)
+                };
+                let header = libc::tpacket3_hdr {
+                    tp_next_offset: next as u32,
+                    tp_sec: 7,
+                    tp_nsec: n as u32,
+                    tp_snaplen: data.len() as u32,
+                    tp_len: data.len() as u32 + 100,
+                    tp_status: libc::TP_STATUS_USER,
+                    tp_mac: mac_offset as u16,
+                    tp_net: mac_offset as u16 + 14,
+                    hv1: libc::tpacket_hdr_variant1 {
+                        tp_rxhash: 0,
+                        tp_vlan_tci: 0,
+                        tp_vlan_tpid: 0,
+                        tp_padding: 0,
+                    },
+                    tp_padding: [0; 8],
+                };
+                unsafe {
+                    ptr::write_unaligned(block.add(offset).cast(), header);
+                    ptr::copy_nonoverlapping(
+                        data.as_ptr(),
+                        block.add(offset + mac_offset),
+                        data.len(),
+                    );
+                }
+                offset += next;
+            }
+            let header = libc::tpacket_hdr_v1 {
+                block_status: libc::TP_STATUS_USER,
+                num_pkts: packets.len() as u32,
+                offset_to_first_pkt: first_pkt as u32,
+                blk_len: BLOCK_SIZE as u32,
+                seq_num: index as u64,
+                ts_first_pkt: libc::tpacket_bd_ts {
+                    ts_sec: 0,
+                    ts_usec: 0,
+                },
+                ts_last_pkt: libc::tpacket_bd_ts {
+                    ts_sec: 0,
+                    ts_usec: 0,
+                },
+            };
+            unsafe {
+                ptr::write(
+                    block.add(offset_of!(libc::tpacket_block_desc, hdr)).cast(),
+                    header,
+                );
+            }
+        }
+        BlockRing::heap(memory, BLOCK_SIZE, blocks.len())
+    }
+
+    fn data<'a>(ring: &'a BlockRing, spans: &[FrameSpan]) -> Vec<&'a [u8]> {
+        spans.iter().map(|span| ring.slice(span)).collect()
+    }
+
+    #[test]
+    fn test_ring_yields_packets_in_place() {
+        let mut ring = ring_with(&[&[b"first", b"second"], &[b"third"]]);
+        let spans = ring.poll(16);
+
+        assert_eq!(
+            data(&ring, &spans),
+            vec![&b"first"[..], &b"second"[..], &b"third"[..]]
+        );
+        assert_eq!(spans[0].original_len, 105);
+        assert_eq!(spans[1].timestamp, 7_000_000_001);
+        let base = ring.base.as_ptr() as usize;
+        let start = ring.slice(&spans[2]).as_ptr() as usize;
+        assert!(start >= base + BLOCK_SIZE && start < base + 2 * BLOCK_SIZE);
+    }
+
+    #[test]
+    fn test_blocks_return_to_kernel_on_next_poll() {
+        let mut ring = ring_with(&[&[b"a", b"b"], &[b"c"]]);
+
+        assert_eq!(ring.poll(1).len(), 1);
+        assert_eq!(ring.status(0).load(Ordering::Acquire), libc::TP_STATUS_USER);
+
+        // Finishing block 0 keeps it until the following poll
+        let spans = ring.poll(1);
+        assert_eq!(data(&ring, &spans), vec![&b"b"[..]]);
+        assert_eq!(ring.status(0).load(Ordering::Acquire), libc::TP_STATUS_USER);
+
+        let spans = ring.poll(1);
+        assert_eq!(data(&ring, &spans), vec![&b"c"[..]]);
+        assert_eq!(
+            ring.status(0).load(Ordering::Acquire),
+            libc::TP_STATUS_KERNEL
+        );
+        assert_eq!(ring.status(1).load(Ordering::Acquire), libc::TP_STATUS_USER);
+
+        assert!(ring.poll(8).is_empty());
+        assert_eq!(
+            ring.status(1).load(Ordering::Acquire),
+            libc::TP_STATUS_KERNEL
+        );
+    }
+
+    #[test]
+    fn test_ring_stops_at_kernel_owned_block() {
+        let mut ring = ring_with(&[&[b"a"], &[b"b"]]);
+        ring.status(1)
+            .store(libc::TP_STATUS_KERNEL, Ordering::Release);
+
+        assert_eq!(ring.poll(8).len(), 1);
+        assert!(ring.poll(8).is_empty());
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(AfPacketConfig::default().validate().is_ok());
+        let bad_frame = AfPacketConfig {
+            frame_size: 100,
+            ..Default::default()
+        };
+        assert!(bad_frame.validate().is_err());
+        let bad_block = AfPacketConfig {
+            block_size: 4096 + 2048,
+            ..Default::default()
+        };
+        assert!(bad_block.validate().is_err());
+        let no_blocks = AfPacketConfig {
+            block_count: 0,
+            ..Default::default()
+        };
+        assert!(no_blocks.validate().is_err());
+    }
+
+    #[test]
+    fn test_unknown_interface_is_rejected() {
+        let mut source =
+            AfPacketInterface::new("sparktrap-nope0", AfPacketConfig::default()).unwrap();
+        assert!(matches!(
+            source.open().unwrap_err().kind(),
+            CaptureErrorKind::Network(NetworkErrorKind::InterfaceNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_poll_before_open_fails() {
+        let mut source = AfPacketInterface::new("lo", AfPacketConfig::default()).unwrap();
+        assert!(source.poll_capture_batch(8).is_err());
+        assert_eq!(source.metrics(), &InterfaceMetrics::default());
+    }
+}
@@ -0,0 +1,272 @@
+// interface/batch.rs
+use crate::traits::Packet;
+
+/// Result of a single capture poll.
+///
+/// Carries the captured packets together with what happened to traffic that did not make it
+/// into the batch, so callers can account for loss per poll rather than per session.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureBatchResult<'a> {
+    /// Packets captured during the poll.
+    pub packets: Vec<Packet<'a>>,
+    /// Packets the kernel or NIC dropped during the batch window.
+    pub kernel_drops: u64,
+    /// Captured packets whose data was cut short of the original frame length.
+    pub truncated: u64,
+    /// Whether the poll was cut short by the interface capture rate limit.
+    pub rate_limited: bool,
+}
+
+impl<'a> CaptureBatchResult<'a> {
+    /// Wraps packets from a backend that reports no drop or truncation information.
+    pub fn from_packets(packets: Vec<Packet<'a>>) -> Self {
+        Self {
+            packets,
+            ..Default::default()
+        }
+    }
+
+    /// Number of packets in the batch.
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    /// Whether the batch contains no packets.
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    /// Cuts every packet's data down to `snaplen` bytes, keeping its on-wire `length`.
+    ///
+    /// Packets already truncated by the backend are not counted again.
+    pub fn apply_snaplen(&mut self, snaplen: usize) {
+        for packet in &mut self.packets {
+            if packet.data.len() > snaplen {
+                if packet.data.len() >= packet.length {
+                    self.truncated += 1;
+                }
+                packet.data = &packet.data[..snaplen];
+            }
+        }
+    }
+}
+
+/// Converts a backend's cumulative drop counter into per-batch deltas.
+///
+/// Kernel statistics such as `PACKET_STATISTICS` are cumulative; a reading lower than the last
+/// one means the counter was reset, in which case the new reading is taken as the delta.
+#[derive(Debug, Clone, Default)]
+pub struct KernelDropCounter {
+    last: Option<u64>,
+}
+
+impl KernelDropCounter {
+    /// Records a cumulative reading and returns the drops since the previous reading.
+    ///
+    /// The first reading establishes the baseline and reports no drops.
+    pub fn delta(&mut self, cumulative: u64) -> u64 {
+        let delta = match self.last {
+            None => 0,
+            Some(last) if cumulative >= last => cumulative - last,
+            Some(_) => cumulative,
+        };
+        self.last = Some(cumulative);
+        delta
+    }
+}
+
+/// Accumulates packets for a capture poll into a `CaptureBatchResult`.
+#[derive(Debug, Default)]
+pub struct CaptureBatchBuilder<'a> {
+    result: CaptureBatchResult<'a>,
+}
+
+impl<'a> CaptureBatchBuilder<'a> {
+    /// Creates an empty batch builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a captured packet, counting it as truncated if less than `original_len` was captured.
+    pub fn push(&mut self, packet: Packet<'a>, original_len: usize) {
+        if packet.data.len() < original_len {
+            self.result.truncated += 1;
+        }
+        self.result.packets.push(packet);
+    }
+
+    /// Adds kernel drops observed during the batch window.
+    pub fn add_kernel_drops(&mut self, drops: u64) {
+        self.result.kernel_drops += drops;
+    }
+
+    /// Marks the batch as cut short by the capture rate limit.
+    pub fn set_rate_limited(&mut self) {
+        self.result.rate_limited = true;
+    }
+
+    /// Number of packets added so far.
+    pub fn len(&self) -> usize {
+        self.result.packets.len()
+    }
+
+    /// Whether no packets have been added.
+    pub fn is_empty(&self) -> bool {
+        self.result.packets.is_empty()
+    }
+
+    /// Finishes the batch.
+    pub fn build(self) -> CaptureBatchResult<'a> {
+        self.result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{BufferId, PacketMetadata};
+    use std::collections::HashMap;
+
+    /// A crafted frame: captured bytes and the length the frame had on the wire.
+    struct Frame {
+        data: Vec<u8>,
+        original_len: usize,
+    }
+
+    /// Mock source with a cumulative kernel drop counter and a per-poll packet budget.
+    struct MockSource {
+        frames: Vec<Frame>,
+        cumulative_drops: u64,
+        drop_counter: KernelDropCounter,
+        max_per_poll: usize,
+    }
+
+    impl MockSource {
+        fn new(frames: Vec<Frame>, max_per_poll: usize) -> Self {
+            let mut drop_counter = KernelDropCounter::default();
+            drop_counter.delta(0);
+            Self {
+                frames,
+                cumulative_drops: 0,
+                drop_counter,
+                max_per_poll,
+            }
+        }
+
+        fn poll(&mut self, start: usize) -> CaptureBatchResult<'_> {
+            let mut builder = CaptureBatchBuilder::new();
+            for (i, frame) in self.frames.iter().enumerate().skip(start) {
+                if builder.len() == self.max_per_poll {
+                    builder.set_rate_limited();
+                    break;
+                }
+                builder.push(packet(&frame.data, i as u64), frame.original_len);
+            }
+            builder.add_kernel_drops(self.drop_counter.delta(self.cumulative_drops));
+            builder.build()
+        }
+    }
+
+    fn packet(data: &[u8], id: u64) -> Packet<'_> {
+        Packet {
+            timestamp: id,
+            data,
+            length: data.len(),
+            metadata: PacketMetadata {
+                compact_data: 0,
+                additional_info: HashMap::new(),
+            },
+            buffer_id: BufferId::new(id),
+        }
+    }
+
+    fn frame(captured: usize, original_len: usize) -> Frame {
+        Frame {
+            data: vec![0u8; captured],
+            original_len,
+        }
+    }
+
+    #[test]
+    fn test_batch_reports_drops_and_truncations() {
+        let frames = vec![
+            frame(64, 64),
+            frame(128, 1500),
+            frame(96, 96),
+            frame(128, 9000),
+        ];
+        let mut source = MockSource::new(frames, 16);
+        source.cumulative_drops = 7;
+
+        let result = source.poll(0);
+        assert_eq!(result.len(), 4);
+        assert_eq!(result.truncated, 2);
+        assert_eq!(result.kernel_drops, 7);
+        assert!(!result.rate_limited);
+    }
+
+    #[test]
+    fn test_batch_reports_rate_limiting() {
+        let frames = (0..10).map(|_| frame(64, 64)).collect();
+        let mut source = MockSource::new(frames, 4);
+
+        let result = source.poll(0);
+        assert_eq!(result.len(), 4);
+        assert!(result.rate_limited);
+        assert_eq!(result.truncated, 0);
+        assert_eq!(result.kernel_drops, 0);
+    }
+
+    #[test]
+    fn test_kernel_drops_are_per_window() {
+        let frames = vec![frame(64, 64)];
+        let mut source = MockSource::new(frames, 16);
+
+        source.cumulative_drops = 5;
+        assert_eq!(source.poll(0).kernel_drops, 5);
+        source.cumulative_drops = 12;
+        assert_eq!(source.poll(0).kernel_drops, 7);
+        assert_eq!(source.poll(0).kernel_drops, 0);
+    }
+
+    #[test]
+    fn test_kernel_drop_counter_reset() {
+        let mut counter = KernelDropCounter::default();
+        assert_eq!(counter.delta(100), 0);
+        assert_eq!(counter.delta(150), 50);
+        // Counter reset by the backend, e.g. after the socket was reopened
+        assert_eq!(counter.delta(3), 3);
+    }
+
+    #[test]
+    fn test_apply_snaplen_keeps_wire_length() {
+        let full = [0u8; 100];
+        let short = [0u8; 40];
+        let cut = [0u8; 80];
+        let mut cut_packet = packet(&cut, 3);
+        cut_packet.length = 1500;
+        let mut result =
+            CaptureBatchResult::from_packets(vec![packet(&full, 1), packet(&short, 2), cut_packet]);
+        result.truncated = 1;
+
+        result.apply_snaplen(64);
+        let captured: Vec<(usize, usize)> = result
+            .packets
+            .iter()
+            .map(|packet| (packet.data.len(), packet.length))
+            .collect();
+        assert_eq!(captured, [(64, 100), (40, 40), (64, 1500)]);
+        // The backend already counted the third packet
+        assert_eq!(result.truncated, 2);
+    }
+
+    #[test]
+    fn test_from_packets_has_no_loss_information() {
+        let data = [1u8, 2, 3];
+        let result = CaptureBatchResult::from_packets(vec![packet(&data, 1)]);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result.kernel_drops, 0);
+        assert_eq!(result.truncated, 0);
+        assert!(!result.rate_limited);
+    }
+}
@@ -0,0 +1,817 @@
+// interface/dpdk.rs
+//! DPDK poll-mode ingest.
+//!
+//! A `DpdkInterface` burst-receives mbufs from the RX queues of one poll-mode port and hands
+//! packets out as slices of the mbufs themselves. The mbufs of a poll are held until the next
+//! poll or `close`, then returned to their pool, following the buffer ownership rules of
+//! `PacketSource`.
+//!
+//! The port is driven through `PollModePort`. The EAL-backed port and the process-wide EAL
+//! initialization are built with the `dpdk` feature, which links `libdpdk` found through
+//! pkg-config.
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, NetworkErrorKind, SystemErrorKind,
+};
+use crate::capture_engine::interface::batch::{
+    CaptureBatchBuilder, CaptureBatchResult, KernelDropCounter,
+};
+use crate::capture_engine::interface::source::{PacketSource, PacketSourceKind};
+use crate::capture_engine::interface::traits::{InterfaceMetrics, RxStats};
+use crate::traits::{BufferId, Packet, PacketMetadata};
+
+/// Default number of RX queues.
+pub const DEFAULT_RX_QUEUES: u16 = 1;
+/// Default descriptors per RX queue.
+pub const DEFAULT_QUEUE_SIZE: u16 = 1024;
+/// Default most mbufs taken from one queue per burst.
+pub const DEFAULT_BURST_SIZE: u16 = 32;
+/// Per-queue counters a port reports, DPDK's default `RTE_ETHDEV_QUEUE_STAT_CNTRS`.
+pub const QUEUE_STAT_COUNTERS: usize = 16;
+/// Polls between reads of the port counters while traffic is flowing.
+pub const STATS_POLL_INTERVAL: u64 = 256;
+
+/// Receive queue layout of a poll-mode port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OffloadConfig {
+    /// RX queues; with more than one, RSS spreads flows across them.
+    pub rx_queues: u16,
+    /// Descriptors per RX queue; a power of two.
+    pub queue_size: u16,
+}
+
+impl Default for OffloadConfig {
+    fn default() -> Self {
+        Self {
+            rx_queues: DEFAULT_RX_QUEUES,
+            queue_size: DEFAULT_QUEUE_SIZE,
+        }
+    }
+}
+
+impl OffloadConfig {
+    /// Checks the queue layout.
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        if self.rx_queues == 0 {
+            return Err(invalid_config("At least one RX queue is required"));
+        }
+        if !self.queue_size.is_power_of_two() {
+            return Err(invalid_config(&format!(
+                "RX queue size {} must be a power of two",
+                self.queue_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Descriptors across all RX queues.
+    pub fn descriptors(&self) -> u32 {
+        u32::from(self.rx_queues) * u32::from(self.queue_size)
+    }
+}
+
+/// Settings of a DPDK capture port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DpdkConfig {
+    pub port_id: u16,
+    pub offload: OffloadConfig,
+    /// Most mbufs taken from one queue per burst.
+    pub burst_size: u16,
+    /// Mbufs in the receive pool; must exceed the descriptors so mbufs held between polls do
+    /// not starve the queues.
+    pub mbuf_pool_size: u32,
+    /// Arguments for `rte_eal_init`, without the program name.
+    pub eal_args: Vec<String>,
+}
+
+impl DpdkConfig {
+    /// Settings for `port_id` with a pool twice the size of the RX rings.
+    pub fn new(port_id: u16, offload: OffloadConfig) -> Self {
+        Self {
+            port_id,
+            offload,
+            burst_size: DEFAULT_BURST_SIZE,
+            mbuf_pool_size: offload.descriptors().saturating_mul(2),
+            eal_args: Vec::new(),
+        }
+    }
+
+    /// Checks the queue layout, burst size and pool size.
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        self.offload.validate()?;
+        if self.burst_size == 0 {
+            return Err(invalid_config("Burst size must be greater than zero"));
+        }
+        if self.mbuf_pool_size <= self.offload.descriptors() {
+            return Err(invalid_config(&format!(
+                "Mbuf pool of {} must be larger than the {} RX descriptors",
+                self.mbuf_pool_size,
+                self.offload.descriptors()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Access to one poll-mode port.
+///
+/// `DpdkInterface` drives the port through this trait so its queue and mbuf handling does not
+/// depend on the DPDK libraries; `EalPort` implements it over the ethdev API.
+pub trait PollModePort: Send {
+    /// Handle to one received mbuf.
+    type Mbuf: Send;
+
+    /// Sets up the RX queues with a pool of `pool_size` mbufs and starts the port.
+    fn start(&mut self, offload: &OffloadConfig, pool_size: u32) -> Result<(), CaptureError>;
+
+    /// Receives up to `max` mbufs from `queue`, appending them to `out`.
+    fn rx_burst(&mut self, queue: u16, max: u16, out: &mut Vec<Self::Mbuf>);
+
+    /// Returns the bytes of the mbuf's first segment and the full packet length.
+    fn data<'a>(&'a self, mbuf: &'a Self::Mbuf) -> (&'a [u8], usize);
+
+    /// Returns the mbufs in `mbufs` to their pool, leaving it empty.
+    fn free(&mut self, mbufs: &mut Vec<Self::Mbuf>);
+
+    /// Reads the port's cumulative receive counters.
+    fn stats(&self) -> Result<RxStats, CaptureError>;
+
+    /// Stops the port; it may be started again.
+    fn stop(&mut self);
+}
+
+fn invalid_config(message: &str) -> CaptureError {
+    *CaptureError::new(
+        CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+        message,
+    )
+}
+
+fn init_error(message: &str) -> CaptureError {
+    *CaptureError::new(
+        CaptureErrorKind::System(SystemErrorKind::InitializationFailed),
+        message,
+    )
+}
+
+/// Checks `/proc/meminfo` contents for reserved hugepages, which the EAL needs unless
+/// `eal_args` contains `--no-huge`.
+pub fn check_hugepages(meminfo: &str, eal_args: &[String]) -> Result<(), CaptureError> {
+    if eal_args.iter().any(|arg| arg == "--no-huge") {
+        return Ok(());
+    }
+    let total = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("HugePages_Total:"))
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    if total == 0 {
+        return Err(init_error(
+            "DPDK needs hugepages but none are reserved; set vm.nr_hugepages or pass --no-huge",
+        ));
+    }
+    Ok(())
+}
+
+/// Packet source reading a DPDK poll-mode port.
+///
+/// Each poll visits the RX queues round robin, taking at most `burst_size` mbufs per queue per
+/// visit, so a busy queue cannot starve the others. Packets are slices of the first mbuf
+/// segment; longer chained packets are reported as truncated. NIC and pool drops are read from
+/// the port counters on idle polls and every `STATS_POLL_INTERVAL` polls.
+pub struct DpdkInterface<P: PollModePort> {
+    name: String,
+    config: DpdkConfig,
+    port: P,
+    held: Vec<P::Mbuf>,
+    next_queue: u16,
+    started: bool,
+    metrics: InterfaceMetrics,
+    rx_stats: RxStats,
+    drops: KernelDropCounter,
+    polls: u64,
+    next_buffer_id: u64,
+}
+
+impl<P: PollModePort> std::fmt::Debug for DpdkInterface<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DpdkInterface")
+            .field("name", &self.name)
+            .field("config", &self.config)
+            .field("started", &self.started)
+            .field("held", &self.held.len())
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+impl<P: PollModePort> DpdkInterface<P> {
+    /// Creates a source reading `port`.
+    pub fn with_port(config: DpdkConfig, port: P) -> Result<Self, CaptureError> {
+        config.validate()?;
+        Ok(Self {
+            name: format!("dpdk:{}", config.port_id),
+            config,
+            port,
+            held: Vec::new(),
+            next_queue: 0,
+            started: false,
+            metrics: InterfaceMetrics::default(),
+            rx_stats: RxStats::default(),
+            drops: KernelDropCounter::default(),
+            polls: 0,
+            next_buffer_id: 0,
+        })
+    }
+
+    /// Port settings.
+    pub fn config(&self) -> &DpdkConfig {
+        &self.config
+    }
+
+    /// Packet and byte counters of delivered packets, and drops reported by the port.
+    pub fn metrics(&self) -> &InterfaceMetrics {
+        &self.metrics
+    }
+
+    /// Port counters as of the last read.
+    pub fn rx_stats(&self) -> &RxStats {
+        &self.rx_stats
+    }
+
+    /// The underlying port.
+    pub fn port(&self) -> &P {
+        &self.port
+    }
+
+    /// Mbufs held for the packets of the last poll.
+    pub fn held_mbufs(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Reads the port counters, returning the drops since the previous read.
+    fn refresh_stats(&mut self) -> Result<u64, CaptureError> {
+        self.rx_stats = self.port.stats()?;
+        Ok(self.drops.delta(self.rx_stats.dropped()))
+    }
+
+    fn collect(&mut self, max: usize) -> Result<CaptureBatchResult<'_>, CaptureError> {
+        if !self.started {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Network(NetworkErrorKind::CaptureFailure),
+                "DPDK port must be opened before polling",
+            ));
+        }
+        self.port.free(&mut self.held);
+
+        let queues = self.config.offload.rx_queues;
+        let mut idle_queues = 0;
+        while self.held.len() < max && idle_queues < queues {
+            let want = (max - self.held.len()).min(usize::from(self.config.burst_size)) as u16;
+            let before = self.held.len();
+            self.port.rx_burst(self.next_queue, want, &mut self.held);
+            self.next_queue = (self.next_queue + 1) % queues;
+            if self.held.len() == before {
+                idle_queues += 1;
+            } else {
+                idle_queues = 0;
+            }
+        }
+
+        self.polls += 1;
+        let drops = if self.held.is_empty() || self.polls.is_multiple_of(STATS_POLL_INTERVAL) {
+            self.refresh_stats()?
+        } else {
+            0
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        let first_id = self.next_buffer_id;
+        self.next_buffer_id += self.held.len() as u64;
+
+        let mut builder = CaptureBatchBuilder::new();
+        for (id, mbuf) in (first_id..).zip(&self.held) {
+            let (data, original_len) = self.port.data(mbuf);
+            self.metrics.packets_received += 1;
+            self.metrics.bytes_received += original_len as u64;
+            let packet = Packet {
+                timestamp,
+                data,
+                length: original_len,
+                metadata: PacketMetadata {
+                    compact_data: 0,
+                    additional_info: HashMap::new(),
+                },
+                buffer_id: BufferId::new(id),
+            };
+            builder.push(packet, original_len);
+        }
+        self.metrics.dropped_packets += drops;
+        builder.add_kernel_drops(drops);
+        Ok(builder.build())
+    }
+}
+
+impl<P: PollModePort> PacketSource for DpdkInterface<P> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> PacketSourceKind {
+        PacketSourceKind::Dpdk
+    }
+
+    fn open(&mut self) -> Result<(), CaptureError> {
+        if self.started {
+            return Ok(());
+        }
+        self.port
+            .start(&self.config.offload, self.config.mbuf_pool_size)?;
+        self.started = true;
+        // Drops counted before the port was opened are not ours
+        self.refresh_stats()?;
+        Ok(())
+    }
+
+    fn poll_batch(&mut self, max: usize) -> Result<Vec<Packet<'_>>, CaptureError> {
+        Ok(self.collect(max)?.packets)
+    }
+
+    fn poll_capture_batch(&mut self, max: usize) -> Result<CaptureBatchResult<'_>, CaptureError> {
+        self.collect(max)
+    }
+
+    fn close(&mut self) -> Result<(), CaptureError> {
+        self.port.free(&mut self.held);
+        if self.started {
+            self.port.stop();
+            self.started = false;
+        }
+        Ok(())
+    }
+}
+
+impl<P: PollModePort> Drop for DpdkInterface<P> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[cfg(feature = "dpdk")]
+pub use eal::{init_eal, EalPort, Mbuf};
+
+#[cfg(feature = "dpdk")]
+impl DpdkInterface<EalPort> {
+    /// Initializes the EAL if needed and creates a source reading `config.port_id`.
+    pub fn new(config: DpdkConfig) -> Result<Self, CaptureError> {
+        init_eal(&config.eal_args)?;
+        let port = EalPort::new(config.port_id)?;
+        Self::with_port(config, port)
+    }
+}
+
+#[cfg(feature = "dpdk")]
+mod eal {
+    use std::ffi::{c_char, c_int, c_uint, c_void, CStr, CString};
+    use std::ptr::NonNull;
+    use std::sync::OnceLock;
+
+    use super::{
+        check_hugepages, init_error, OffloadConfig, PollModePort, RxStats, QUEUE_STAT_COUNTERS,
+    };
+    use crate::capture_engine::capture::capture_error::{
+        CaptureError, CaptureErrorKind, ConfigErrorKind, NetworkErrorKind,
+    };
+
+    /// Mirrors `struct sparktrap_rx_stats` in `dpdk_shim.c`.
+    #[repr(C)]
+    struct RawRxStats {
+        packets: u64,
+        bytes: u64,
+        missed: u64,
+        errors: u64,
+        no_mbuf: u64,
+        queue_packets: [u64; QUEUE_STAT_COUNTERS],
+    }
+
+    // Wrappers from dpdk_shim.c; mbufs are opaque `struct rte_mbuf` pointers
+    extern "C" {
+        fn sparktrap_eal_init(argc: c_int, argv: *mut *mut c_char) -> c_int;
+        fn sparktrap_errno() -> c_int;
+        fn sparktrap_strerror(err: c_int) -> *const c_char;
+        fn sparktrap_port_count() -> u16;
+        fn sparktrap_port_valid(port: u16) -> c_int;
+        fn sparktrap_port_start(
+            port: u16,
+            rx_queues: u16,
+            queue_size: u16,
+            pool_size: u32,
+        ) -> c_int;
+        fn sparktrap_port_stop(port: u16);
+        fn sparktrap_rx_burst(port: u16, queue: u16, mbufs: *mut *mut c_void, max: u16) -> u16;
+        fn sparktrap_mbuf_data(
+            mbuf: *const c_void,
+            data_len: *mut u32,
+            pkt_len: *mut u32,
+        ) -> *const u8;
+        fn sparktrap_mbuf_free_bulk(mbufs: *mut *mut c_void, count: c_uint);
+        fn sparktrap_port_stats(port: u16, stats: *mut RawRxStats) -> c_int;
+    }
+
+    fn strerror(err: c_int) -> String {
+        // SAFETY: rte_strerror returns a pointer to a NUL-terminated static or thread-local
+        // string.
+        unsafe { CStr::from_ptr(sparktrap_strerror(err.abs())) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    static EAL: OnceLock<Result<(), String>> = OnceLock::new();
+
+    /// Initializes the DPDK EAL once per process.
+    ///
+    /// The EAL cannot be initialized twice, so later calls return the outcome of the first,
+    /// whatever their arguments. Fails with `InitializationFailed` if no hugepages are
+    /// reserved, `rte_eal_init` fails, or no port is bound to a poll-mode driver.
+    pub fn init_eal(args: &[String]) -> Result<(), CaptureError> {
+        EAL.get_or_init(|| {
+            let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+            check_hugepages(&meminfo, args).map_err(|e| e.message().to_string())?;
+
+            let owned = std::iter::once("sparktrap")
+                .chain(args.iter().map(String::as_str))
+                .map(CString::new)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| "EAL argument contains a NUL byte".to_string())?;
+            // rte_eal_init may reorder argv and keep pointers into it for the process lifetime
+            let owned = Box::leak(owned.into_boxed_slice());
+            let argv: &mut [*mut c_char] = Box::leak(
+                owned
+                    .iter()
+                    .map(|arg| arg.as_ptr() as *mut c_char)
+                    .collect(),
+            );
+            // SAFETY: argv holds argc valid NUL-terminated strings that are never freed.
+            let rc = unsafe { sparktrap_eal_init(argv.len() as c_int, argv.as_mut_ptr()) };
+            if rc < 0 {
+                // SAFETY: reads rte_errno for this thread, just set by rte_eal_init.
+                let err = unsafe { sparktrap_errno() };
+                return Err(format!("rte_eal_init failed: {}", strerror(err)));
+            }
+            // SAFETY: the EAL is initialized.
+            if unsafe { sparktrap_port_count() } == 0 {
+                return Err(
+                    "DPDK initialized but no port is bound to a poll-mode driver".to_string(),
+                );
+            }
+            Ok(())
+        })
+        .clone()
+        .map_err(|message| init_error(&message))
+    }
+
+    /// Received mbuf.
+    #[repr(transparent)]
+    pub struct Mbuf(NonNull<c_void>);
+
+    // An mbuf belongs to whoever holds its handle until it is freed back to the pool.
+    unsafe impl Send for Mbuf {}
+
+    /// Poll-mode port driven through the DPDK ethdev API.
+    #[derive(Debug)]
+    pub struct EalPort {
+        port_id: u16,
+        rx_queues: u16,
+    }
+
+    impl EalPort {
+        /// Opens `port_id`; the EAL must have been initialized with `init_eal`.
+        pub fn new(port_id: u16) -> Result<Self, CaptureError> {
+            if !matches!(EAL.get(), Some(Ok(()))) {
+                return Err(init_error("The DPDK EAL has not been initialized"));
+            }
+            // SAFETY: the EAL is initialized.
+            if unsafe { sparktrap_port_valid(port_id) } == 0 {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Network(NetworkErrorKind::InterfaceNotFound),
+                    &format!("DPDK port {} not found", port_id),
+                ));
+            }
+            Ok(Self {
+                port_id,
+                rx_queues: 0,
+            })
+        }
+
+        fn driver_error(&self, what: &str, rc: c_int) -> CaptureError {
+            *CaptureError::new(
+                CaptureErrorKind::Network(NetworkErrorKind::DriverError),
+                &format!("{} on DPDK port {}: {}", what, self.port_id, strerror(rc)),
+            )
+        }
+    }
+
+    impl PollModePort for EalPort {
+        type Mbuf = Mbuf;
+
+        fn start(&mut self, offload: &OffloadConfig, pool_size: u32) -> Result<(), CaptureError> {
+            // SAFETY: plain values; the shim checks the port and reports errors as a return code.
+            let rc = unsafe {
+                sparktrap_port_start(
+                    self.port_id,
+                    offload.rx_queues,
+                    offload.queue_size,
+                    pool_size,
+                )
+            };
+            if rc == -libc::EEXIST {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    &format!(
+                        "DPDK port {} already has an mbuf pool of another size than {}; \
+                         restart the process to resize it",
+                        self.port_id, pool_size
+                    ),
+                ));
+            }
+            if rc != 0 {
+                return Err(self.driver_error("Failed to start", rc));
+            }
+            self.rx_queues = offload.rx_queues;
+            Ok(())
+        }
+
+        fn rx_burst(&mut self, queue: u16, max: u16, out: &mut Vec<Mbuf>) {
+            out.reserve(usize::from(max));
+            let len = out.len();
+            // SAFETY: the spare capacity holds `max` slots, and `Mbuf` is a transparent non-null
+            // pointer, so the first `received` slots hold valid mbufs once the call returns.
+            unsafe {
+                let slots = out.as_mut_ptr().add(len).cast::<*mut c_void>();
+                let received = sparktrap_rx_burst(self.port_id, queue, slots, max);
+                out.set_len(len + usize::from(received));
+            }
+        }
+
+        fn data<'a>(&'a self, mbuf: &'a Mbuf) -> (&'a [u8], usize) {
+            let (mut data_len, mut pkt_len) = (0u32, 0u32);
+            // SAFETY: the mbuf is held until freed, which needs `&mut` access to its owner, so
+            // its first segment outlives the returned slice.
+            unsafe {
+                let data = sparktrap_mbuf_data(mbuf.0.as_ptr(), &mut data_len, &mut pkt_len);
+                (
+                    std::slice::from_raw_parts(data, data_len as usize),
+                    pkt_len as usize,
+                )
+            }
+        }
+
+        fn free(&mut self, mbufs: &mut Vec<Mbuf>) {
+            if mbufs.is_empty() {
+                return;
+            }
+            // SAFETY: each handle is a received mbuf freed exactly once; the vector is emptied
+            // without dropping the handles.
+            unsafe {
+                sparktrap_mbuf_free_bulk(mbufs.as_mut_ptr().cast(), mbufs.len() as c_uint);
+                mbufs.set_len(0);
+            }
+        }
+
+        fn stats(&self) -> Result<RxStats, CaptureError> {
+            let mut raw = RawRxStats {
+                packets: 0,
+                bytes: 0,
+                missed: 0,
+                errors: 0,
+                no_mbuf: 0,
+                queue_packets: [0; QUEUE_STAT_COUNTERS],
+            };
+            // SAFETY: `raw` matches the shim's struct layout and is valid for writes.
+            let rc = unsafe { sparktrap_port_stats(self.port_id, &mut raw) };
+            if rc != 0 {
+                return Err(self.driver_error("Failed to read statistics", rc));
+            }
+            let queues = usize::from(self.rx_queues).min(QUEUE_STAT_COUNTERS);
+            Ok(RxStats {
+                packets: raw.packets,
+                bytes: raw.bytes,
+                missed: raw.missed,
+                errors: raw.errors,
+                no_mbuf: raw.no_mbuf,
+                queue_packets: raw.queue_packets[..queues].to_vec(),
+                rate_limited: 0,
+            })
+        }
+
+        fn stop(&mut self) {
+            // SAFETY: stopping a started port has no other preconditions.
+            unsafe { sparktrap_port_stop(self.port_id) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Port serving frames from memory; mbufs are indices into `frames`.
+    #[derive(Default)]
+    struct FakePort {
+        frames: Vec<(Vec<u8>, usize)>,
+        queues: Vec<VecDeque<usize>>,
+        bursts: Vec<(u16, u16)>,
+        outstanding: usize,
+        freed: usize,
+        stats: RxStats,
+        running: bool,
+    }
+
+    impl FakePort {
+        fn with_queues(queues: usize) -> Self {
+            Self {
+                queues: vec![VecDeque::new(); queues],
+                ..Default::default()
+            }
+        }
+
+        fn arrive(&mut self, queue: usize, data: &[u8], original_len: usize) {
+            self.frames.push((data.to_vec(), original_len));
+            self.queues[queue].push_back(self.frames.len() - 1);
+        }
+    }
+
+    impl PollModePort for FakePort {
+        type Mbuf = usize;
+
+        fn start(&mut self, offload: &OffloadConfig, _pool_size: u32) -> Result<(), CaptureError> {
+            assert_eq!(usize::from(offload.rx_queues), self.queues.len());
+            self.running = true;
+            Ok(())
+        }
+
+        fn rx_burst(&mut self, queue: u16, max: u16, out: &mut Vec<usize>) {
+            self.bursts.push((queue, max));
+            let ring = &mut self.queues[usize::from(queue)];
+            let take = ring.len().min(usize::from(max));
+            out.extend(ring.drain(..take));
+            self.outstanding += take;
+        }
+
+        fn data<'a>(&'a self, mbuf: &'a usize) -> (&'a [u8], usize) {
+            let (data, original_len) = &self.frames[*mbuf];
+            (data, *original_len)
+        }
+
+        fn free(&mut self, mbufs: &mut Vec<usize>) {
+            self.outstanding -= mbufs.len();
+            self.freed += mbufs.len();
+            mbufs.clear();
+        }
+
+        fn stats(&self) -> Result<RxStats, CaptureError> {
+            Ok(self.stats.clone())
+        }
+
+        fn stop(&mut self) {
+            self.running = false;
+        }
+    }
+
+    fn interface(port: FakePort, burst_size: u16) -> DpdkInterface<FakePort> {
+        let offload = OffloadConfig {
+            rx_queues: port.queues.len() as u16,
+            queue_size: 64,
+        };
+        let mut config = DpdkConfig::new(0, offload);
+        config.burst_size = burst_size;
+        DpdkInterface::with_port(config, port).unwrap()
+    }
+
+    #[test]
+    fn test_packets_borrow_mbufs_until_next_poll() {
+        let mut port = FakePort::with_queues(1);
+        port.arrive(0, b"first", 5);
+        port.arrive(0, b"chained", 3000);
+        let mut source = interface(port, 32);
+        source.open().unwrap();
+
+        let batch = source.poll_capture_batch(16).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.truncated, 1);
+        assert_eq!(batch.packets[0].data, b"first");
+        let first = batch.packets[0].data.as_ptr();
+        assert_eq!(first, source.port().frames[0].0.as_ptr());
+        assert_eq!(source.held_mbufs(), 2);
+        assert_eq!(source.port().outstanding, 2);
+
+        // The next poll returns the previous mbufs to the pool
+        assert!(source.poll_capture_batch(16).unwrap().is_empty());
+        assert_eq!((source.port().outstanding, source.port().freed), (0, 2));
+        assert_eq!(source.metrics().packets_received, 2);
+        assert_eq!(source.metrics().bytes_received, 3005);
+    }
+
+    #[test]
+    fn test_queues_polled_round_robin_in_bursts() {
+        let mut port = FakePort::with_queues(2);
+        for n in 0..4u8 {
+            port.arrive(0, &[n], 1);
+        }
+        port.arrive(1, b"q1", 2);
+        let mut source = interface(port, 2);
+        source.open().unwrap();
+
+        let batch = source.poll_capture_batch(3).unwrap();
+        let data: Vec<_> = batch.packets.iter().map(|p| p.data.to_vec()).collect();
+        assert_eq!(data, vec![vec![0], vec![1], b"q1".to_vec()]);
+        assert_eq!(source.port().bursts, vec![(0, 2), (1, 1)]);
+
+        // The walk resumes at the queue after the last one visited
+        let batch = source.poll_capture_batch(8).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(source.port().bursts[2], (0, 2));
+    }
+
+    #[test]
+    fn test_port_drops_reported_per_batch() {
+        let mut port = FakePort::with_queues(1);
+        port.stats.missed = 10;
+        let mut source = interface(port, 32);
+        source.open().unwrap();
+        assert_eq!(source.poll_capture_batch(8).unwrap().kernel_drops, 0);
+
+        source.port.stats = RxStats {
+            packets: 40,
+            bytes: 4000,
+            missed: 15,
+            errors: 1,
+            no_mbuf: 2,
+            queue_packets: vec![40],
+            rate_limited: 0,
+        };
+        assert_eq!(source.poll_capture_batch(8).unwrap().kernel_drops, 7);
+        assert_eq!(source.metrics().dropped_packets, 7);
+        assert_eq!(
+            InterfaceMetrics::from(source.rx_stats()),
+            InterfaceMetrics {
+                packets_received: 40,
+                bytes_received: 4000,
+                dropped_packets: 17,
+            }
+        );
+    }
+
+    #[test]
+    fn test_close_frees_mbufs_and_stops_port() {
+        let mut port = FakePort::with_queues(1);
+        port.arrive(0, b"held", 4);
+        let mut source = interface(port, 32);
+        assert!(source.poll_capture_batch(8).is_err());
+        source.open().unwrap();
+        assert_eq!(source.poll_capture_batch(8).unwrap().len(), 1);
+
+        source.close().unwrap();
+        assert_eq!(source.port().outstanding, 0);
+        assert!(!source.port().running);
+        assert_eq!(source.kind(), PacketSourceKind::Dpdk);
+        assert_eq!(source.name(), "dpdk:0");
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let offload = OffloadConfig::default();
+        assert!(DpdkConfig::new(0, offload).validate().is_ok());
+        let odd_ring = OffloadConfig {
+            rx_queues: 1,
+            queue_size: 1000,
+        };
+        assert!(DpdkConfig::new(0, odd_ring).validate().is_err());
+        let no_queues = OffloadConfig {
+            rx_queues: 0,
+            queue_size: 1024,
+        };
+        assert!(no_queues.validate().is_err());
+        let mut small_pool = DpdkConfig::new(0, offload);
+        small_pool.mbuf_pool_size = offload.descriptors();
+        assert!(small_pool.validate().is_err());
+    }
+
+    #[test]
+    fn test_hugepage_check() {
+        let reserved = "MemTotal: 1024 kB\nHugePages_Total:     512\nHugePages_Free: 512\n";
+        let none = "MemTotal: 1024 kB\nHugePages_Total:       0\n";
+        assert!(check_hugepages(reserved, &[]).is_ok());
+        let error = check_hugepages(none, &[]).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            CaptureErrorKind::System(SystemErrorKind::InitializationFailed)
+        ));
+        assert!(check_hugepages(none, &["--no-huge".to_string()]).is_ok());
+    }
+}
@@ -0,0 +1,133 @@
+/* interface/dpdk_shim.c
+ *
+ * Exported wrappers around the DPDK calls the Rust side needs. Most of the ethdev and mbuf
+ * fast path is static inline in the DPDK headers, so it cannot be linked to directly.
+ */
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <rte_eal.h>
+#include <rte_errno.h>
+#include <rte_ethdev.h>
+#include <rte_mbuf.h>
+#include <rte_mempool.h>
+
+#define SPARKTRAP_QUEUE_STAT_COUNTERS 16
+
+struct sparktrap_rx_stats {
+    uint64_t packets;
+    uint64_t bytes;
+    uint64_t missed;
+    uint64_t errors;
+    uint64_t no_mbuf;
+    uint64_t queue_packets[SPARKTRAP_QUEUE_STAT_COUNTERS];
+};
+
+int sparktrap_eal_init(int argc, char **argv) { return rte_eal_init(argc, argv); }
+
+int sparktrap_errno(void) { return rte_errno; }
+
+const char *sparktrap_strerror(int err) { return rte_strerror(err); }
+
+uint16_t sparktrap_port_count(void) { return rte_eth_dev_count_avail(); }
+
+int sparktrap_port_valid(uint16_t port) { return rte_eth_dev_is_valid_port(port); }
+
+/* Per-lcore cache for a pool of `pool_size` mbufs. DPDK rejects caches larger than
+ * RTE_MEMPOOL_CACHE_MAX_SIZE or than the pool size divided by 1.5. */
+static unsigned int sparktrap_pool_cache_size(uint32_t pool_size) {
+    uint32_t limit = (uint32_t)((uint64_t)pool_size * 2 / 3);
+    return limit < RTE_MEMPOOL_CACHE_MAX_SIZE ? limit : RTE_MEMPOOL_CACHE_MAX_SIZE;
+}
+
+/* Configures `rx_queues` RX queues of `queue_size` descriptors and starts the port. The mbuf
+ * pool is created on first use and kept for later restarts. A pool left by an earlier start
+ * with a different size is still referenced by the port's queues, so it cannot be replaced;
+ * -EEXIST is returned instead. Returns 0 or a negative errno. */
+int sparktrap_port_start(uint16_t port, uint16_t rx_queues, uint16_t queue_size,
+                         uint32_t pool_size) {
+    char name[RTE_MEMPOOL_NAMESIZE];
+    struct rte_eth_dev_info info;
+    struct rte_eth_conf conf;
+    struct rte_mempool *pool;
+    int socket = rte_eth_dev_socket_id(port);
+    int rc;
+    uint16_t queue;
+
+    snprintf(name, sizeof(name), "sparktrap_rx_%u", port);
+    pool = rte_mempool_lookup(name);
+    if (pool == NULL) {
+        pool = rte_pktmbuf_pool_create(name, pool_size, sparktrap_pool_cache_size(pool_size), 0,
+                                       RTE_MBUF_DEFAULT_BUF_SIZE, socket);
+        if (pool == NULL) {
+            return -rte_errno;
+        }
+    } else if (pool->size != pool_size) {
+        return -EEXIST;
+    }
+
+    rc = rte_eth_dev_info_get(port, &info);
+    if (rc != 0) {
+        return rc;
+    }
+    memset(&conf, 0, sizeof(conf));
+    if (rx_queues > 1) {
+        conf.rxmode.mq_mode = RTE_ETH_MQ_RX_RSS;
+        conf.rx_adv_conf.rss_conf.rss_hf =
+            (RTE_ETH_RSS_IP | RTE_ETH_RSS_TCP | RTE_ETH_RSS_UDP) & info.flow_type_rss_offloads;
+    }
+    rc = rte_eth_dev_configure(port, rx_queues, 0, &conf);
+    if (rc != 0) {
+        return rc;
+    }
+    for (queue = 0; queue < rx_queues; queue++) {
+        rc = rte_eth_rx_queue_setup(port, queue, queue_size, socket, NULL, pool);
+        if (rc != 0) {
+            return rc;
+        }
+    }
+    rc = rte_eth_promiscuous_enable(port);
+    if (rc != 0 && rc != -ENOTSUP) {
+        return rc;
+    }
+    return rte_eth_dev_start(port);
+}
+
+void sparktrap_port_stop(uint16_t port) { rte_eth_dev_stop(port); }
+
+uint16_t sparktrap_rx_burst(uint16_t port, uint16_t queue, struct rte_mbuf **mbufs,
+                            uint16_t max) {
+    return rte_eth_rx_burst(port, queue, mbufs, max);
+}
+
+const uint8_t *sparktrap_mbuf_data(const struct rte_mbuf *mbuf, uint32_t *data_len,
+                                   uint32_t *pkt_len) {
+    *data_len = mbuf->data_len;
+    *pkt_len = mbuf->pkt_len;
+    return rte_pktmbuf_mtod(mbuf, const uint8_t *);
+}
+
+void sparktrap_mbuf_free_bulk(struct rte_mbuf **mbufs, unsigned int count) {
+    rte_pktmbuf_free_bulk(mbufs, count);
+}
+
+int sparktrap_port_stats(uint16_t port, struct sparktrap_rx_stats *out) {
+    struct rte_eth_stats stats;
+    int rc = rte_eth_stats_get(port, &stats);
+    int queue;
+
+    if (rc != 0) {
+        return rc;
+    }
+    out->packets = stats.ipackets;
+    out->bytes = stats.ibytes;
+    out->missed = stats.imissed;
+    out->errors = stats.ierrors;
+    out->no_mbuf = stats.rx_nombuf;
+    for (queue = 0; queue < SPARKTRAP_QUEUE_STAT_COUNTERS; queue++) {
+        out->queue_packets[queue] =
+            queue < RTE_ETHDEV_QUEUE_STAT_CNTRS ? stats.q_ipackets[queue] : 0;
+    }
+    return 0;
+}
@@ -0,0 +1,161 @@
+// interface/pacing.rs
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::capture::clock::Clock;
+
+/// Replay speed settings for file sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayPacing {
+    /// Multiple of real time, e.g. 10.0 replays ten times faster than captured.
+    pub speed: f64,
+    /// Upper bound on the release rate in packets per second, regardless of speed.
+    pub max_packets_per_sec: Option<u64>,
+    /// How far replay may fall behind its schedule before it is rescheduled from the current
+    /// time. A pipeline that stops polling therefore resumes at the scaled pace instead of
+    /// bursting through the backlog.
+    pub max_lag: Duration,
+}
+
+impl ReplayPacing {
+    /// Replays at `speed` times real time with no rate cap.
+    pub fn speed(speed: f64) -> Self {
+        Self {
+            speed,
+            max_packets_per_sec: None,
+            max_lag: Duration::from_millis(100),
+        }
+    }
+
+    /// Validates the settings.
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        if !self.speed.is_finite() || self.speed <= 0.0 || self.max_packets_per_sec == Some(0) {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Replay speed and rate cap must be greater than zero",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Decides when each replayed packet may be released.
+///
+/// Packet `n` is due at `anchor_wall + (ts_n - anchor_ts) / speed`, and never sooner than the
+/// rate cap allows after the previous release. Release is pull-based: packets only leave when
+/// the pipeline polls, so a stalled pipeline stalls replay.
+#[derive(Debug)]
+pub struct ReplayPacer {
+    pacing: ReplayPacing,
+    clock: Arc<dyn Clock>,
+    anchor: Option<(SystemTime, u64)>,
+    last_release: Option<SystemTime>,
+    rescheduled: u64,
+}
+
+impl ReplayPacer {
+    /// Creates a pacer; the first packet is released immediately and anchors the schedule.
+    pub fn new(pacing: ReplayPacing, clock: Arc<dyn Clock>) -> Result<Self, CaptureError> {
+        pacing.validate()?;
+        Ok(Self {
+            pacing,
+            clock,
+            anchor: None,
+            last_release: None,
+            rescheduled: 0,
+        })
+    }
+
+    /// Times replay fell behind by more than `max_lag` and was rescheduled.
+    pub fn rescheduled(&self) -> u64 {
+        self.rescheduled
+    }
+
+    /// Wall-clock time a packet captured at `timestamp` nanoseconds is due.
+    pub fn due(&self, timestamp: u64) -> SystemTime {
+        let Some((wall, base)) = self.anchor else {
+            return self.clock.now();
+        };
+        let scaled = timestamp.saturating_sub(base) as f64 / self.pacing.speed;
+        let mut due = wall + Duration::from_nanos(scaled as u64);
+        if let (Some(last), Some(rate)) = (self.last_release, self.pacing.max_packets_per_sec) {
+            due = due.max(last + Duration::from_nanos(1_000_000_000 / rate));
+        }
+        due
+    }
+
+    /// Releases a packet if it is due.
+    ///
+    /// Returns the due time of a packet that must wait.
+    pub fn try_release(&mut self, timestamp: u64) -> Result<(), SystemTime> {
+        let now = self.clock.now();
+        if self.anchor.is_none() {
+            self.anchor = Some((now, timestamp));
+            self.last_release = Some(now);
+            return Ok(());
+        }
+
+        let due = self.due(timestamp);
+        if now < due {
+            return Err(due);
+        }
+        let lag = now.duration_since(due).unwrap_or(Duration::ZERO);
+        if lag > self.pacing.max_lag {
+            self.anchor = Some((now, timestamp));
+            self.last_release = Some(now);
+            self.rescheduled += 1;
+        } else {
+            // Releasing on schedule rather than at `now` keeps polling jitter from accumulating
+            self.last_release = Some(due);
+        }
+        Ok(())
+    }
+
+    /// Forgets the schedule so the next packet starts a new one.
+    pub fn reset(&mut self) {
+        self.anchor = None;
+        self.last_release = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::clock::ManualClock;
+    use std::time::UNIX_EPOCH;
+
+    const MS: u64 = 1_000_000;
+
+    fn pacer(pacing: ReplayPacing) -> (ReplayPacer, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(100)));
+        (ReplayPacer::new(pacing, clock.clone()).unwrap(), clock)
+    }
+
+    #[test]
+    fn test_rate_cap_spaces_releases() {
+        let (mut pacer, clock) = pacer(ReplayPacing {
+            max_packets_per_sec: Some(5),
+            ..ReplayPacing::speed(10.0)
+        });
+        // Captured 100ms apart; 10x would be 10ms but the cap allows one every 200ms
+        assert!(pacer.try_release(0).is_ok());
+        clock.advance(Duration::from_millis(199));
+        assert!(pacer.try_release(100 * MS).is_err());
+        clock.advance(Duration::from_millis(1));
+        assert!(pacer.try_release(100 * MS).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_pacing_rejected() {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+        assert!(ReplayPacer::new(ReplayPacing::speed(0.0), clock.clone()).is_err());
+        let capped = ReplayPacing {
+            max_packets_per_sec: Some(0),
+            ..ReplayPacing::speed(1.0)
+        };
+        assert!(ReplayPacer::new(capped, clock).is_err());
+    }
+}
@@ -0,0 +1,468 @@
+// interface/pcap_file.rs
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, RuntimeErrorKind, SystemErrorKind,
+};
+use crate::capture_engine::capture::clock::Clock;
+use crate::capture_engine::interface::batch::CaptureBatchResult;
+use crate::capture_engine::interface::pacing::{ReplayPacer, ReplayPacing};
+use crate::capture_engine::interface::source::{FrameArena, PacketSource, PacketSourceKind};
+use crate::traits::Packet;
+
+/// Largest record accepted whatever snap length the file declares; larger records mean corruption.
+const MAX_RECORD_LEN: usize = 256 * 1024;
+
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+/// Input a `PcapFileSource` reads from.
+enum PcapInput {
+    Path(PathBuf),
+    Reader(Box<dyn Read + Send>),
+}
+
+/// Byte order and timestamp resolution from the pcap global header.
+#[derive(Debug, Clone, Copy)]
+struct PcapFormat {
+    big_endian: bool,
+    nanos: bool,
+    snaplen: usize,
+    link_type: u32,
+}
+
+impl PcapFormat {
+    fn parse(header: &[u8; 24]) -> Result<Self, CaptureError> {
+        let le = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let be = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let (big_endian, nanos) = match (le, be) {
+            (MAGIC_MICROS, _) => (false, false),
+            (MAGIC_NANOS, _) => (false, true),
+            (_, MAGIC_MICROS) => (true, false),
+            (_, MAGIC_NANOS) => (true, true),
+            _ => {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+                    "Not a pcap file: unrecognized magic number",
+                ))
+            }
+        };
+        let mut format = Self {
+            big_endian,
+            nanos,
+            snaplen: 0,
+            link_type: 0,
+        };
+        format.snaplen = format.u32_at(header, 16) as usize;
+        format.link_type = format.u32_at(header, 20);
+        Ok(format)
+    }
+
+    fn u32_at(&self, bytes: &[u8], at: usize) -> u32 {
+        let word = [bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]];
+        if self.big_endian {
+            u32::from_be_bytes(word)
+        } else {
+            u32::from_le_bytes(word)
+        }
+    }
+}
+
+/// Record header read ahead of its data.
+#[derive(Debug, Clone, Copy)]
+struct RecordHeader {
+    timestamp: u64,
+    captured: usize,
+    original: usize,
+}
+
+/// Packet source replaying a classic libpcap capture file.
+pub struct PcapFileSource {
+    name: String,
+    input: Option<PcapInput>,
+    reader: Option<BufReader<Box<dyn Read + Send>>>,
+    format: Option<PcapFormat>,
+    arena: FrameArena,
+    exhausted: bool,
+    pacer: Option<ReplayPacer>,
+    /// Record held back by the pacer; its data is still unread.
+    pending: Option<RecordHeader>,
+}
+
+impl PcapFileSource {
+    /// Creates a source reading the capture file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self::with_input(path.display().to_string(), PcapInput::Path(path))
+    }
+
+    /// Creates a source reading pcap data from an arbitrary reader.
+    pub fn from_reader(name: &str, reader: Box<dyn Read + Send>) -> Self {
+        Self::with_input(name.to_string(), PcapInput::Reader(reader))
+    }
+
+    fn with_input(name: String, input: PcapInput) -> Self {
+        Self {
+            name,
+            input: Some(input),
+            reader: None,
+            format: None,
+            arena: FrameArena::default(),
+            exhausted: false,
+            pacer: None,
+            pending: None,
+        }
+    }
+
+    /// Replays records at their captured spacing scaled by `pacing.speed`.
+    ///
+    /// Without pacing records are read as fast as the pipeline polls.
+    pub fn with_pacing(
+        mut self,
+        pacing: ReplayPacing,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, CaptureError> {
+        self.pacer = Some(ReplayPacer::new(pacing, clock)?);
+        Ok(self)
+    }
+
+    /// Time the next paced record is due, if one is being held back.
+    pub fn next_release(&self) -> Option<SystemTime> {
+        let (pacer, pending) = (self.pacer.as_ref()?, self.pending?);
+        Some(pacer.due(pending.timestamp))
+    }
+
+    /// Times paced replay fell too far behind and was rescheduled from the current time.
+    pub fn rescheduled(&self) -> u64 {
+        self.pacer.as_ref().map_or(0, ReplayPacer::rescheduled)
+    }
+
+    /// Link-layer header type from the file header, available once opened.
+    pub fn link_type(&self) -> Option<u32> {
+        self.format.map(|f| f.link_type)
+    }
+
+    fn fill(&mut self, max: usize) -> Result<(), CaptureError> {
+        self.arena.clear();
+        let (Some(reader), Some(format)) = (self.reader.as_mut(), self.format) else {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                "Pcap source must be opened before polling",
+            ));
+        };
+
+        while self.arena.len() < max && !self.exhausted {
+            let record = match self.pending.take() {
+                Some(record) => record,
+                None => match read_record(reader, &format)? {
+                    Some(record) => record,
+                    None => {
+                        self.exhausted = true;
+                        break;
+                    }
+                },
+            };
+            if let Some(pacer) = self.pacer.as_mut() {
+                if pacer.try_release(record.timestamp).is_err() {
+                    self.pending = Some(record);
+                    break;
+                }
+            }
+
+            let RecordHeader {
+                timestamp,
+                captured,
+                original,
+            } = record;
+            let read = self.arena.fill_with(captured, |buf| {
+                reader
+                    .read_exact(buf)
+                    .map(|_| Some((captured, original, timestamp)))
+            });
+            match read {
+                Ok(_) => {}
+                // A capture cut off mid-record ends at the last complete record
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => self.exhausted = true,
+                Err(e) => return Err(io_error(e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads and decodes the next record header, returning None at a clean end of file.
+fn read_record(
+    reader: &mut impl Read,
+    format: &PcapFormat,
+) -> Result<Option<RecordHeader>, CaptureError> {
+    let mut header = [0u8; 16];
+    if !read_record_header(reader, &mut header)? {
+        return Ok(None);
+    }
+
+    let seconds = format.u32_at(&header, 0) as u64;
+    let fraction = format.u32_at(&header, 4) as u64;
+    let captured = format.u32_at(&header, 8) as usize;
+    let original = format.u32_at(&header, 12) as usize;
+    if captured > MAX_RECORD_LEN {
+        return Err(*CaptureError::new(
+            CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+            &format!(
+                "Pcap record length {} exceeds the {} byte limit",
+                captured, MAX_RECORD_LEN
+            ),
+        ));
+    }
+    let timestamp = seconds * 1_000_000_000
+        + if format.nanos {
+            fraction
+        } else {
+            fraction * 1_000
+        };
+    Ok(Some(RecordHeader {
+        timestamp,
+        captured,
+        original,
+    }))
+}
+
+/// Reads a record header, returning false at a clean end of file.
+fn read_record_header(reader: &mut impl Read, header: &mut [u8; 16]) -> Result<bool, CaptureError> {
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) => return Ok(false),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(io_error(e)),
+        }
+    }
+    Ok(true)
+}
+
+fn io_error(e: std::io::Error) -> CaptureError {
+    CaptureError::new(
+        CaptureErrorKind::System(SystemErrorKind::IoError),
+        "Failed to read pcap file",
+    )
+    .with_source(e)
+}
+
+impl PacketSource for PcapFileSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> PacketSourceKind {
+        PacketSourceKind::PcapFile
+    }
+
+    fn open(&mut self) -> Result<(), CaptureError> {
+        let reader: Box<dyn Read + Send> = match self.input.take() {
+            Some(PcapInput::Path(path)) => {
+                let file = File::open(&path).map_err(io_error);
+                // Keep the path so the source can be reopened
+                self.input = Some(PcapInput::Path(path));
+                Box::new(file?)
+            }
+            Some(PcapInput::Reader(reader)) => reader,
+            None => {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Runtime(RuntimeErrorKind::StateError),
+                    "Pcap reader input was already consumed",
+                ))
+            }
+        };
+
+        let mut reader = BufReader::new(reader);
+        let mut header = [0u8; 24];
+        reader.read_exact(&mut header).map_err(io_error)?;
+        self.format = Some(PcapFormat::parse(&header)?);
+        self.reader = Some(reader);
+        self.exhausted = false;
+        self.pending = None;
+        if let Some(pacer) = self.pacer.as_mut() {
+            pacer.reset();
+        }
+        Ok(())
+    }
+
+    fn poll_batch(&mut self, max: usize) -> Result<Vec<Packet<'_>>, CaptureError> {
+        self.fill(max)?;
+        Ok(self.arena.packets())
+    }
+
+    fn poll_capture_batch(&mut self, max: usize) -> Result<CaptureBatchResult<'_>, CaptureError> {
+        self.fill(max)?;
+        Ok(self.arena.batch().build())
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    fn close(&mut self) -> Result<(), CaptureError> {
+        self.reader = None;
+        self.pending = None;
+        self.arena.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::clock::ManualClock;
+    use std::io::Cursor;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// Builds a little-endian microsecond pcap with the given (captured, original) records.
+    fn pcap(records: &[(Vec<u8>, u32)], snaplen: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&MAGIC_MICROS.to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&4u16.to_le_bytes());
+        out.extend_from_slice(&0i32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&snaplen.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        for (i, (data, original)) in records.iter().enumerate() {
+            out.extend_from_slice(&(i as u32 + 1).to_le_bytes());
+            out.extend_from_slice(&250u32.to_le_bytes());
+            out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&original.to_le_bytes());
+            out.extend_from_slice(data);
+        }
+        out
+    }
+
+    /// Builds a nanosecond pcap with one 60 byte record at each millisecond offset.
+    fn pcap_at(offsets_ms: &[u64]) -> Vec<u8> {
+        let mut out = pcap(&[], 65535);
+        out[..4].copy_from_slice(&MAGIC_NANOS.to_le_bytes());
+        for offset in offsets_ms {
+            let ns = 1_000_000_000 + offset * 1_000_000;
+            out.extend_from_slice(&((ns / 1_000_000_000) as u32).to_le_bytes());
+            out.extend_from_slice(&((ns % 1_000_000_000) as u32).to_le_bytes());
+            out.extend_from_slice(&60u32.to_le_bytes());
+            out.extend_from_slice(&60u32.to_le_bytes());
+            out.extend_from_slice(&[0u8; 60]);
+        }
+        out
+    }
+
+    fn paced(offsets_ms: &[u64], speed: f64) -> (PcapFileSource, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(100)));
+        let mut source = source(pcap_at(offsets_ms))
+            .with_pacing(ReplayPacing::speed(speed), clock.clone())
+            .unwrap();
+        source.open().unwrap();
+        (source, clock)
+    }
+
+    fn source(bytes: Vec<u8>) -> PcapFileSource {
+        PcapFileSource::from_reader("test.pcap", Box::new(Cursor::new(bytes)))
+    }
+
+    #[test]
+    fn test_reads_records_with_truncation() {
+        let bytes = pcap(
+            &[(vec![1; 60], 60), (vec![2; 64], 1500), (vec![3; 40], 40)],
+            64,
+        );
+        let mut source = source(bytes);
+        source.open().unwrap();
+        assert_eq!(source.link_type(), Some(1));
+
+        let batch = source.poll_capture_batch(16).unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.truncated, 1);
+        assert_eq!(batch.packets[0].timestamp, 1_000_250_000);
+        assert_eq!(batch.packets[1].data, &[2u8; 64][..]);
+        assert!(source.is_exhausted());
+    }
+
+    #[test]
+    fn test_cut_off_file_ends_at_last_complete_record() {
+        let mut bytes = pcap(&[(vec![1; 60], 60), (vec![2; 60], 60)], 65535);
+        bytes.truncate(bytes.len() - 10);
+        let mut source = source(bytes);
+        source.open().unwrap();
+
+        assert_eq!(source.poll_batch(16).unwrap().len(), 1);
+        assert!(source.is_exhausted());
+    }
+
+    #[test]
+    fn test_rejects_non_pcap_input() {
+        let mut source = source(vec![0u8; 64]);
+        assert!(matches!(
+            source.open().unwrap_err().kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::ParseError)
+        ));
+    }
+
+    #[test]
+    fn test_huge_snaplen_does_not_lift_record_limit() {
+        let mut bytes = pcap(&[], u32::MAX);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        let mut source = source(bytes);
+        source.open().unwrap();
+        assert!(matches!(
+            source.poll_batch(1).unwrap_err().kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::ParseError)
+        ));
+    }
+
+    #[test]
+    fn test_poll_before_open_fails() {
+        let mut source = source(pcap(&[], 65535));
+        assert!(source.poll_batch(1).is_err());
+    }
+
+    #[test]
+    fn test_double_speed_halves_gaps() {
+        let (mut source, clock) = paced(&[0, 200, 600], 2.0);
+        let start = clock.now();
+
+        assert_eq!(source.poll_batch(16).unwrap().len(), 1);
+        assert_eq!(
+            source.next_release(),
+            Some(start + Duration::from_millis(100))
+        );
+        clock.advance(Duration::from_millis(99));
+        assert_eq!(source.poll_batch(16).unwrap().len(), 0);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(source.poll_batch(16).unwrap().len(), 1);
+        assert_eq!(
+            source.next_release(),
+            Some(start + Duration::from_millis(300))
+        );
+        clock.advance(Duration::from_millis(200));
+        let batch = source.poll_batch(16).unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].timestamp, 1_600_000_000);
+        assert!(source.is_exhausted());
+    }
+
+    #[test]
+    fn test_stalled_output_slows_replay() {
+        let (mut source, clock) = paced(&[0, 200, 400, 600], 2.0);
+        assert_eq!(source.poll_batch(16).unwrap().len(), 1);
+
+        // The pipeline stops polling well past the remaining schedule
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(source.poll_batch(16).unwrap().len(), 1);
+        assert_eq!(source.rescheduled(), 1);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(source.poll_batch(16).unwrap().len(), 1);
+        assert_eq!(source.rescheduled(), 1);
+    }
+}
@@ -0,0 +1,270 @@
+// interface/rate_limit.rs
+//! Packet and byte rate limiting of capture interfaces.
+use std::time::{Duration, Instant};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::interface::batch::CaptureBatchResult;
+use crate::capture_engine::interface::traits::RxStats;
+use crate::capture_engine::output::bandwidth::BandwidthLimiter;
+
+/// Bytes of a full-sized Ethernet frame, the unit of the byte bucket's burst allowance.
+pub const BURST_FRAME_BYTES: u64 = 1514;
+
+/// Capture rate of an interface.
+///
+/// `burst_size` is in packets: an idle interface may take that many packets at once before
+/// the packet rate applies, and the byte bucket holds that many full-sized frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureRate {
+    /// Packets per second, or None for no packet limit.
+    pub packets_per_second: Option<u64>,
+    /// Bytes per second, or None for no byte limit.
+    pub bytes_per_second: Option<u64>,
+    /// Packets allowed back to back above the rate.
+    pub burst_size: u64,
+}
+
+impl CaptureRate {
+    /// A packet-only rate allowing one second of packets as a burst.
+    pub fn packets(packets_per_second: u64) -> Self {
+        Self {
+            packets_per_second: Some(packets_per_second),
+            bytes_per_second: None,
+            burst_size: packets_per_second,
+        }
+    }
+
+    /// Validates the rate.
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        let message = if self.packets_per_second.is_none() && self.bytes_per_second.is_none() {
+            "Capture rate needs a packet or byte limit"
+        } else if self.packets_per_second == Some(0) || self.bytes_per_second == Some(0) {
+            "Capture rate limits must be greater than zero"
+        } else if self.burst_size == 0 {
+            "Capture rate burst size must be greater than zero"
+        } else {
+            return Ok(());
+        };
+        Err(*CaptureError::new(
+            CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+            message,
+        ))
+    }
+}
+
+/// Dual token bucket enforcing a `CaptureRate`.
+///
+/// A packet is admitted only if both the packet and the byte bucket hold enough tokens, so
+/// when both limits are set the more restrictive one decides. Packets are charged by their
+/// captured length.
+#[derive(Debug, Clone)]
+pub struct CaptureRateLimiter {
+    rate: CaptureRate,
+    packets: Option<BandwidthLimiter>,
+    bytes: Option<BandwidthLimiter>,
+}
+
+impl CaptureRateLimiter {
+    /// Creates a limiter with full buckets.
+    pub fn new(rate: CaptureRate, now: Instant) -> Result<Self, CaptureError> {
+        rate.validate()?;
+        Ok(Self {
+            rate,
+            packets: rate
+                .packets_per_second
+                .map(|pps| BandwidthLimiter::new(pps, rate.burst_size, now)),
+            bytes: rate.bytes_per_second.map(|bps| {
+                BandwidthLimiter::new(bps, rate.burst_size.saturating_mul(BURST_FRAME_BYTES), now)
+            }),
+        })
+    }
+
+    /// The enforced rate.
+    pub fn rate(&self) -> &CaptureRate {
+        &self.rate
+    }
+
+    /// Takes the tokens for a packet of `len` bytes if both buckets allow it.
+    ///
+    /// Returns how long the packet would have to wait otherwise; callers that can leave
+    /// packets in the ring defer polling for that long instead of dropping.
+    pub fn admit(&mut self, len: usize, now: Instant) -> Result<(), Duration> {
+        let len = len as u64;
+        let wait = self
+            .packets
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.wait_time(1, now))
+            .max(
+                self.bytes
+                    .as_mut()
+                    .map_or(Duration::ZERO, |bucket| bucket.wait_time(len, now)),
+            );
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        if let Some(bucket) = &mut self.packets {
+            let _ = bucket.try_acquire(1, now);
+        }
+        if let Some(bucket) = &mut self.bytes {
+            let _ = bucket.try_acquire(len, now);
+        }
+        Ok(())
+    }
+
+    /// Drops the packets of a batch that exceed the rate.
+    ///
+    /// Dropped packets are counted in `stats.rate_limited`, and the batch is marked rate
+    /// limited if any were dropped.
+    pub fn limit_batch<'a>(
+        &mut self,
+        mut batch: CaptureBatchResult<'a>,
+        now: Instant,
+        stats: &mut RxStats,
+    ) -> CaptureBatchResult<'a> {
+        let before = batch.packets.len();
+        batch
+            .packets
+            .retain(|packet| self.admit(packet.data.len(), now).is_ok());
+        let dropped = (before - batch.packets.len()) as u64;
+        if dropped > 0 {
+            stats.rate_limited += dropped;
+            batch.rate_limited = true;
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{BufferId, Packet, PacketMetadata};
+    use std::collections::HashMap;
+
+    const OFFERED_PPS: u64 = 10_000;
+    const PACKET_LEN: usize = 100;
+    const SECONDS: u64 = 10;
+
+    fn rate(pps: Option<u64>, bps: Option<u64>) -> CaptureRate {
+        CaptureRate {
+            packets_per_second: pps,
+            bytes_per_second: bps,
+            burst_size: 50,
+        }
+    }
+
+    /// Offers `OFFERED_PPS` packets of `PACKET_LEN` bytes for `SECONDS`, one batch per
+    /// millisecond, returning the packets admitted and the stats.
+    fn offer(rate: CaptureRate) -> (u64, RxStats) {
+        let data = [0u8; PACKET_LEN];
+        let start = Instant::now();
+        let mut limiter = CaptureRateLimiter::new(rate, start).unwrap();
+        let mut stats = RxStats::default();
+        let per_batch = OFFERED_PPS / 1000;
+        let mut admitted = 0;
+        for ms in 0..SECONDS * 1000 {
+            let packets = (0..per_batch)
+                .map(|id| Packet {
+                    timestamp: ms,
+                    data: &data,
+                    length: PACKET_LEN,
+                    metadata: PacketMetadata {
+                        compact_data: 0,
+                        additional_info: HashMap::new(),
+                    },
+                    buffer_id: BufferId::new(id),
+                })
+                .collect();
+            let now = start + Duration::from_millis(ms);
+            let batch =
+                limiter.limit_batch(CaptureBatchResult::from_packets(packets), now, &mut stats);
+            admitted += batch.len() as u64;
+        }
+        (admitted, stats)
+    }
+
+    fn assert_capped(admitted: u64, pps: u64, burst: u64) {
+        // Within the burst plus one batch interval of the limit
+        let limit = pps * SECONDS;
+        assert!(
+            admitted <= limit + burst,
+            "{} > {}",
+            admitted,
+            limit + burst
+        );
+        assert!(
+            admitted + pps / 1000 + 1 >= limit,
+            "{} < {}",
+            admitted,
+            limit
+        );
+    }
+
+    #[test]
+    fn test_packet_rate_caps_throughput() {
+        let (admitted, stats) = offer(rate(Some(1_000), None));
+        assert_capped(admitted, 1_000, 50);
+        assert_eq!(stats.rate_limited, OFFERED_PPS * SECONDS - admitted);
+    }
+
+    #[test]
+    fn test_byte_rate_caps_throughput() {
+        // 50 kB/s of 100-byte packets is 500 packets per second
+        let (admitted, stats) = offer(rate(None, Some(50_000)));
+        assert_capped(admitted, 500, 50 * BURST_FRAME_BYTES / PACKET_LEN as u64);
+        assert_eq!(stats.rate_limited, OFFERED_PPS * SECONDS - admitted);
+    }
+
+    #[test]
+    fn test_more_restrictive_limit_wins() {
+        let (bytes_bound, _) = offer(rate(Some(2_000), Some(50_000)));
+        assert_capped(bytes_bound, 500, 50 * BURST_FRAME_BYTES / PACKET_LEN as u64);
+
+        let (packets_bound, _) = offer(rate(Some(300), Some(500_000)));
+        assert_capped(packets_bound, 300, 50);
+    }
+
+    #[test]
+    fn test_admit_reports_wait_and_batch_flag() {
+        let now = Instant::now();
+        let mut limiter = CaptureRateLimiter::new(
+            CaptureRate {
+                packets_per_second: Some(10),
+                bytes_per_second: None,
+                burst_size: 1,
+            },
+            now,
+        )
+        .unwrap();
+        assert!(limiter.admit(64, now).is_ok());
+        assert_eq!(limiter.admit(64, now), Err(Duration::from_millis(100)));
+        assert!(limiter.admit(64, now + Duration::from_millis(100)).is_ok());
+
+        let mut stats = RxStats::default();
+        let batch = limiter.limit_batch(CaptureBatchResult::default(), now, &mut stats);
+        assert!(!batch.rate_limited);
+        assert_eq!(stats.rate_limited, 0);
+    }
+
+    #[test]
+    fn test_packet_limit_allows_one_second_burst() {
+        let rate = CaptureRate::packets(250);
+        assert_eq!(rate.packets_per_second, Some(250));
+        assert_eq!(rate.bytes_per_second, None);
+        assert_eq!(rate.burst_size, 250);
+        assert!(CaptureRate::packets(0).validate().is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_rates() {
+        assert!(rate(None, None).validate().is_err());
+        assert!(rate(Some(0), None).validate().is_err());
+        assert!(rate(None, Some(0)).validate().is_err());
+        let no_burst = CaptureRate {
+            burst_size: 0,
+            ..rate(Some(1), None)
+        };
+        assert!(CaptureRateLimiter::new(no_burst, Instant::now()).is_err());
+    }
+}
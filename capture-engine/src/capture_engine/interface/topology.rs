@@ -0,0 +1,326 @@
+// interface/topology.rs
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::capture_engine::telemetry::traits::{
+    MetricType, MetricUnit, MetricValue, TelemetryData,
+};
+
+pub const NUMA_NODE_METRIC: &str = "interface.numa_node";
+pub const IRQ_CPUS_METRIC: &str = "interface.irq.cpus";
+
+/// CPUs an interrupt is allowed to run on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IrqAffinity {
+    pub irq: u32,
+    /// Handler name from `/proc/interrupts`, usually naming the queue (e.g. `eth0-rx-0`).
+    pub name: Option<String>,
+    /// None when the affinity could not be read.
+    pub cpus: Option<Vec<u32>>,
+}
+
+/// NUMA placement and interrupt routing of a capture interface.
+///
+/// Fields that cannot be discovered (non-Linux hosts, virtual interfaces without a backing
+/// device, restricted sysfs) are reported as unknown rather than failing discovery.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceTopology {
+    pub interface: String,
+    pub numa_node: Option<u32>,
+    pub irqs: Vec<IrqAffinity>,
+}
+
+impl InterfaceTopology {
+    /// Whether any placement information was discovered.
+    pub fn is_known(&self) -> bool {
+        self.numa_node.is_some() || !self.irqs.is_empty()
+    }
+
+    /// Distinct CPUs serving this interface's interrupts, ascending.
+    pub fn irq_cpus(&self) -> Vec<u32> {
+        let mut cpus: Vec<u32> = self
+            .irqs
+            .iter()
+            .filter_map(|irq| irq.cpus.as_ref())
+            .flatten()
+            .copied()
+            .collect();
+        cpus.sort_unstable();
+        cpus.dedup();
+        cpus
+    }
+
+    /// Reports the NUMA node (-1 when unknown) and per-IRQ CPU counts as gauges.
+    pub fn telemetry(&self, timestamp: u64) -> Vec<TelemetryData> {
+        let gauge = |name: &str, description: &str, value: i64, attributes| TelemetryData {
+            timestamp,
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type: MetricType::Gauge,
+            value: MetricValue::Integer(value),
+            attributes,
+            resource: None,
+        };
+
+        let mut attributes = HashMap::from([("interface".to_string(), self.interface.clone())]);
+        if self.numa_node.is_none() {
+            attributes.insert("numa_node".to_string(), "unknown".to_string());
+        }
+        let mut metrics = vec![gauge(
+            NUMA_NODE_METRIC,
+            "NUMA node of the capture interface, -1 if unknown",
+            self.numa_node.map_or(-1, i64::from),
+            attributes,
+        )];
+
+        for irq in &self.irqs {
+            let cpus = irq.cpus.as_ref().map_or("unknown".to_string(), |cpus| {
+                cpus.iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            });
+            let attributes = HashMap::from([
+                ("interface".to_string(), self.interface.clone()),
+                ("irq".to_string(), irq.irq.to_string()),
+                ("queue".to_string(), irq.name.clone().unwrap_or_default()),
+                ("cpus".to_string(), cpus),
+            ]);
+            metrics.push(gauge(
+                IRQ_CPUS_METRIC,
+                "CPUs an interface interrupt is pinned to",
+                irq.cpus.as_ref().map_or(0, |cpus| cpus.len() as i64),
+                attributes,
+            ));
+        }
+        metrics
+    }
+}
+
+/// Reads interface topology from sysfs and procfs.
+#[derive(Debug, Clone)]
+pub struct TopologyProbe {
+    sys_root: PathBuf,
+    proc_root: PathBuf,
+}
+
+impl Default for TopologyProbe {
+    fn default() -> Self {
+        Self::with_roots("/sys", "/proc")
+    }
+}
+
+impl TopologyProbe {
+    /// Probe reading the host's `/sys` and `/proc`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Probe reading from alternate roots, e.g. a container mount or a test fixture.
+    pub fn with_roots(sys_root: impl Into<PathBuf>, proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            sys_root: sys_root.into(),
+            proc_root: proc_root.into(),
+        }
+    }
+
+    /// Discovers the topology of each interface.
+    pub fn discover_all(&self, interfaces: &[&str]) -> Vec<InterfaceTopology> {
+        let names = self.irq_names();
+        interfaces
+            .iter()
+            .map(|interface| self.discover_with_names(interface, &names))
+            .collect()
+    }
+
+    /// Discovers the topology of one interface.
+    pub fn discover(&self, interface: &str) -> InterfaceTopology {
+        self.discover_with_names(interface, &self.irq_names())
+    }
+
+    fn discover_with_names(
+        &self,
+        interface: &str,
+        names: &HashMap<u32, String>,
+    ) -> InterfaceTopology {
+        let device = self
+            .sys_root
+            .join("class/net")
+            .join(interface)
+            .join("device");
+
+        // The kernel reports -1 for devices without NUMA affinity
+        let numa_node = read_trimmed(&device.join("numa_node")).and_then(|s| s.parse().ok());
+
+        let mut irqs: Vec<u32> = fs::read_dir(device.join("msi_irqs"))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        irqs.sort_unstable();
+
+        InterfaceTopology {
+            interface: interface.to_string(),
+            numa_node,
+            irqs: irqs
+                .into_iter()
+                .map(|irq| IrqAffinity {
+                    irq,
+                    name: names.get(&irq).cloned(),
+                    cpus: read_trimmed(
+                        &self
+                            .proc_root
+                            .join("irq")
+                            .join(irq.to_string())
+                            .join("smp_affinity_list"),
+                    )
+                    .and_then(|list| parse_cpu_list(&list)),
+                })
+                .collect(),
+        }
+    }
+
+    /// Maps IRQ numbers to handler names from `/proc/interrupts`.
+    fn irq_names(&self) -> HashMap<u32, String> {
+        let Some(table) = read_trimmed(&self.proc_root.join("interrupts")) else {
+            return HashMap::new();
+        };
+        table
+            .lines()
+            .filter_map(|line| {
+                let (irq, rest) = line.trim_start().split_once(':')?;
+                let irq = irq.parse().ok()?;
+                let name = rest.split_whitespace().last()?;
+                Some((irq, name.to_string()))
+            })
+            .collect()
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|contents| contents.trim().to_string())
+}
+
+/// Parses a kernel CPU list such as `0-3,8,10-11`.
+pub(crate) fn parse_cpu_list(list: &str) -> Option<Vec<u32>> {
+    let mut cpus = Vec::new();
+    for part in list.split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end): (u32, u32) = (start.parse().ok()?, end.parse().ok()?);
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(part.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixture {
+        root: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let root =
+                std::env::temp_dir().join(format!("sparktrap-topology-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(root.join("sys/class/net")).unwrap();
+            fs::create_dir_all(root.join("proc")).unwrap();
+            Self { root }
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            let path = self.root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        fn probe(&self) -> TopologyProbe {
+            TopologyProbe::with_roots(self.root.join("sys"), self.root.join("proc"))
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn test_parses_numa_node_and_irq_affinity() {
+        let fixture = Fixture::new();
+        fixture.write("sys/class/net/eth0/device/numa_node", "1\n");
+        fixture.write("sys/class/net/eth0/device/msi_irqs/45", "msix\n");
+        fixture.write("sys/class/net/eth0/device/msi_irqs/44", "msix\n");
+        fixture.write("proc/irq/44/smp_affinity_list", "0-1\n");
+        fixture.write("proc/irq/45/smp_affinity_list", "8,10\n");
+        fixture.write(
+            "proc/interrupts",
+            "           CPU0       CPU1\n \
+             44:       1024          0  IR-PCI-MSI 524288-edge      eth0-rx-0\n \
+             45:          0       2048  IR-PCI-MSI 524289-edge      eth0-rx-1\n",
+        );
+
+        let topology = fixture.probe().discover("eth0");
+        assert_eq!(topology.numa_node, Some(1));
+        assert_eq!(
+            topology.irqs,
+            vec![
+                IrqAffinity {
+                    irq: 44,
+                    name: Some("eth0-rx-0".to_string()),
+                    cpus: Some(vec![0, 1]),
+                },
+                IrqAffinity {
+                    irq: 45,
+                    name: Some("eth0-rx-1".to_string()),
+                    cpus: Some(vec![8, 10]),
+                },
+            ]
+        );
+        assert_eq!(topology.irq_cpus(), vec![0, 1, 8, 10]);
+
+        let metrics = topology.telemetry(0);
+        assert_eq!(metrics.len(), 3);
+        assert!(matches!(metrics[0].value, MetricValue::Integer(1)));
+        assert_eq!(metrics[2].attributes["cpus"], "8,10");
+    }
+
+    #[test]
+    fn test_virtual_interface_reports_unknown() {
+        let fixture = Fixture::new();
+        fs::create_dir_all(fixture.root.join("sys/class/net/veth0")).unwrap();
+        fixture.write("sys/class/net/eth1/device/numa_node", "-1\n");
+
+        let topologies = fixture.probe().discover_all(&["veth0", "eth1"]);
+        for topology in &topologies {
+            assert_eq!(topology.numa_node, None);
+            assert!(topology.irqs.is_empty());
+            assert!(!topology.is_known());
+        }
+
+        let metrics = topologies[0].telemetry(0);
+        assert_eq!(metrics.len(), 1);
+        assert!(matches!(metrics[0].value, MetricValue::Integer(-1)));
+        assert_eq!(metrics[0].attributes["numa_node"], "unknown");
+    }
+
+    #[test]
+    fn test_cpu_list_parsing() {
+        assert_eq!(parse_cpu_list("0-2,5"), Some(vec![0, 1, 2, 5]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("a-b"), None);
+    }
+}
@@ -1,5 +1,7 @@
 // interface/traits.rs
 // `InterfaceManager` deals with network interfaces where packets are captured.
+use crate::capture_engine::capture::eni_lifecycle::EniState;
+use crate::capture_engine::interface::batch::CaptureBatchResult;
 use crate::traits::{Error, EventHandler, Lifecycle, Packet, PressureAware};
 ///
 /// This abstraction allows plugging in different backend implementations:
@@ -17,6 +19,12 @@ pub enum InterfaceEvent<'a> {
     PacketReceived(Packet<'a>),
     PacketDrop(PacketDropInfo),
     LinkStatusChange(LinkStatus),
+    /// A mirror ENI moved between lifecycle states.
+    EniStateChange {
+        eni_id: String,
+        from: EniState,
+        to: EniState,
+    },
 }
 
 /// Information about a packet drop.
@@ -42,13 +50,26 @@ pub trait InterfaceManager<'a>:
     /// Captures packets from the interface.
     async fn capture_packets(&mut self) -> Result<Vec<Packet>, Error>;
 
+    /// Captures a batch of packets along with drops, truncations and rate limiting seen during
+    /// the poll.
+    ///
+    /// Backends that can observe kernel drops or truncation should override this; the default
+    /// reports only the packets returned by `capture_packets`.
+    async fn capture_batch(&mut self) -> Result<CaptureBatchResult, Error> {
+        Ok(CaptureBatchResult::from_packets(
+            self.capture_packets().await?,
+        ))
+    }
+
     /// Configures the network interface.
     async fn configure_interface(&mut self, config: InterfaceConfig) -> Result<(), Error>;
 
     /// Retrieves the status of the interface.
     fn interface_status(&self) -> InterfaceStatus;
 
-    /// Sets the capture rate limit.
+    /// Sets the capture rate limit in packets per second, or removes it with None.
+    ///
+    /// Equivalent to a `CaptureRate::packets` limit; backends apply it to every captured batch.
     fn set_capture_rate_limit(&mut self, limit: Option<u64>) -> Result<(), Error>;
 }
 
@@ -69,3 +90,48 @@ pub struct InterfaceStatus {
     pub duplex: Option<String>,
     pub errors: Vec<String>,
 }
+
+/// Packet counters for a capture interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceMetrics {
+    pub packets_received: u64,
+    pub bytes_received: u64,
+    /// Packets the kernel dropped because the capture ring was full.
+    pub dropped_packets: u64,
+}
+
+/// Receive counters of an interface, as a port or socket reports them.
+///
+/// Poll-mode backends fill these from `rte_eth_stats`; `rate_limited` is kept in software.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RxStats {
+    pub packets: u64,
+    pub bytes: u64,
+    /// Packets the NIC dropped because no RX descriptor was free.
+    pub missed: u64,
+    /// Erroneous packets.
+    pub errors: u64,
+    /// Packets dropped because the pool had no free mbuf.
+    pub no_mbuf: u64,
+    /// Packets received on each RX queue, for at most `dpdk::QUEUE_STAT_COUNTERS` queues.
+    pub queue_packets: Vec<u64>,
+    /// Packets dropped by the capture rate limit; counted in software, never by the port.
+    pub rate_limited: u64,
+}
+
+impl RxStats {
+    /// Packets lost before reaching software.
+    pub fn dropped(&self) -> u64 {
+        self.missed.saturating_add(self.no_mbuf)
+    }
+}
+
+impl From<&RxStats> for InterfaceMetrics {
+    fn from(stats: &RxStats) -> Self {
+        Self {
+            packets_received: stats.packets,
+            bytes_received: stats.bytes,
+            dropped_packets: stats.dropped(),
+        }
+    }
+}
@@ -1 +1,11 @@
+pub mod bandwidth;
+pub mod compression;
+pub mod kafka;
+pub mod manifest;
+pub mod naming;
+pub mod pcap_writer;
+pub mod routing;
+pub mod s3;
+pub mod scheduler;
 pub mod traits;
+pub mod verification;
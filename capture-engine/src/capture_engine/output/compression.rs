@@ -0,0 +1,908 @@
+// output/compression.rs
+//! Compression settings and codecs applied to output payloads before they reach a destination.
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, SystemErrorKind,
+};
+use crate::capture_engine::output::manifest::CompressionInfo;
+use crate::capture_engine::output::traits::{
+    DestinationType, OutputData, OutputDestinationConfig, WriteMetrics,
+};
+
+/// Magic number at the start of a formatted (trained) Zstd dictionary
+const ZSTD_DICT_MAGIC: u32 = 0xEC30_A437;
+
+/// Default Zstd compression level
+const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+/// Payloads shorter than this are written uncompressed by default
+pub const DEFAULT_MIN_COMPRESS_SIZE: usize = 128;
+
+/// Compression algorithms supported by output destinations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionAlgorithm {
+    None,
+    Gzip,
+    Lz4,
+    Zstd,
+}
+
+/// Location of a Zstd dictionary referenced by a compression configuration
+///
+/// # Variants
+/// * `Path` - Dictionary is read from a file when the configuration is loaded
+/// * `Bytes` - Dictionary bytes are supplied inline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DictionarySource {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+/// Compression configuration for an output destination
+///
+/// # Fields
+/// * `algorithm` - Compression algorithm applied to payloads
+/// * `level` - Algorithm specific compression level
+/// * `dictionary` - Optional Zstd dictionary used for both compression and decompression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionConfig {
+    pub algorithm: CompressionAlgorithm,
+    pub level: i32,
+    pub dictionary: Option<DictionarySource>,
+}
+
+impl Default for CompressionConfig {
+    /// Creates a configuration that leaves payloads uncompressed
+    ///
+    /// # Returns
+    /// A new CompressionConfig instance with compression disabled
+    fn default() -> Self {
+        Self {
+            algorithm: CompressionAlgorithm::None,
+            level: DEFAULT_COMPRESSION_LEVEL,
+            dictionary: None,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Creates a Zstd configuration with the given level
+    ///
+    /// # Arguments
+    /// * `level` - Zstd compression level
+    ///
+    /// # Returns
+    /// A new CompressionConfig instance using Zstd without a dictionary
+    pub fn zstd(level: i32) -> Self {
+        Self {
+            algorithm: CompressionAlgorithm::Zstd,
+            level,
+            dictionary: None,
+        }
+    }
+
+    /// Sets the Zstd dictionary used by this configuration
+    ///
+    /// # Arguments
+    /// * `source` - Where the dictionary is loaded from
+    ///
+    /// # Returns
+    /// The updated CompressionConfig instance
+    pub fn with_dictionary(mut self, source: DictionarySource) -> Self {
+        self.dictionary = Some(source);
+        self
+    }
+
+    /// Loads the configured dictionary, if any
+    ///
+    /// # Returns
+    /// The loaded dictionary, None if no dictionary is configured, or an error if it fails to load
+    pub fn load_dictionary(&self) -> Result<Option<ZstdDictionary>, CaptureError> {
+        match &self.dictionary {
+            None => Ok(None),
+            Some(DictionarySource::Bytes(bytes)) => {
+                ZstdDictionary::from_bytes(bytes.clone()).map(Some)
+            }
+            Some(DictionarySource::Path(path)) => {
+                let bytes = fs::read(path).map_err(|e| {
+                    CaptureError::new(
+                        CaptureErrorKind::System(SystemErrorKind::IoError),
+                        &format!("Failed to read Zstd dictionary {}", path.display()),
+                    )
+                    .with_source(e)
+                })?;
+                ZstdDictionary::from_bytes(bytes).map(Some)
+            }
+        }
+    }
+
+    /// Validates the configuration settings
+    ///
+    /// A configured dictionary is only valid with Zstd and must load successfully.
+    ///
+    /// # Returns
+    /// An error if the configuration is invalid
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        if self.dictionary.is_some() && self.algorithm != CompressionAlgorithm::Zstd {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Compression dictionaries are only supported with Zstd",
+            ));
+        }
+        if self.algorithm == CompressionAlgorithm::Zstd
+            && !zstd::compression_level_range().contains(&self.level)
+        {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Zstd compression level is out of range",
+            ));
+        }
+        if self.algorithm == CompressionAlgorithm::Gzip && !(0..=9).contains(&self.level) {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Gzip compression level must be between 0 and 9",
+            ));
+        }
+        self.load_dictionary().map(|_| ())
+    }
+}
+
+/// A validated Zstd dictionary
+///
+/// Only formatted dictionaries (as produced by `zstd --train`) are accepted, since their embedded
+/// id is what the output manifest records to pair compressed objects with their dictionary.
+///
+/// # Fields
+/// * `id` - Dictionary id embedded in the dictionary header
+/// * `bytes` - Raw dictionary contents
+#[derive(Debug, Clone)]
+pub struct ZstdDictionary {
+    id: u32,
+    bytes: Arc<[u8]>,
+}
+
+impl ZstdDictionary {
+    /// Creates a dictionary from raw bytes, validating that Zstd can load it
+    ///
+    /// # Arguments
+    /// * `bytes` - Formatted Zstd dictionary contents
+    ///
+    /// # Returns
+    /// The loaded dictionary or a parse error if the bytes are not a usable dictionary
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, CaptureError> {
+        let id = Self::parse_id(&bytes)?;
+
+        // Loading into a decompression context fully parses the entropy tables
+        zstd::bulk::Decompressor::with_dictionary(&bytes).map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+                "Zstd dictionary failed to load",
+            )
+            .with_source(e)
+        })?;
+
+        Ok(Self {
+            id,
+            bytes: bytes.into(),
+        })
+    }
+
+    /// Returns the dictionary id
+    ///
+    /// # Returns
+    /// The id embedded in the dictionary header
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the raw dictionary contents
+    ///
+    /// # Returns
+    /// A reference to the dictionary bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn parse_id(bytes: &[u8]) -> Result<u32, CaptureError> {
+        let header_error = || {
+            *CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+                "Zstd dictionary is missing a valid dictionary header",
+            )
+        };
+
+        if bytes.len() < 8 {
+            return Err(header_error());
+        }
+        let magic = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let id = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if magic != ZSTD_DICT_MAGIC || id == 0 {
+            return Err(header_error());
+        }
+        Ok(id)
+    }
+}
+
+/// Zstd codec bound to a level and optional dictionary
+///
+/// # Fields
+/// * `level` - Zstd compression level
+/// * `dictionary` - Dictionary shared by compression and decompression
+/// * `compressor` - Compression context with the dictionary loaded, shared by clones
+#[derive(Clone)]
+pub struct ZstdCodec {
+    level: i32,
+    dictionary: Option<ZstdDictionary>,
+    compressor: Arc<Mutex<zstd::bulk::Compressor<'static>>>,
+}
+
+impl fmt::Debug for ZstdCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZstdCodec")
+            .field("level", &self.level)
+            .field("dictionary", &self.dictionary)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ZstdCodec {
+    /// Creates a codec from a validated compression configuration
+    ///
+    /// # Arguments
+    /// * `config` - Compression configuration using the Zstd algorithm
+    ///
+    /// # Returns
+    /// A new ZstdCodec instance or an error if the configuration is invalid
+    pub fn from_config(config: &CompressionConfig) -> Result<Self, CaptureError> {
+        if config.algorithm != CompressionAlgorithm::Zstd {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "ZstdCodec requires the Zstd compression algorithm",
+            ));
+        }
+        config.validate()?;
+
+        Self::new(config.level, config.load_dictionary()?)
+    }
+
+    /// Creates a codec able to decompress objects described by a manifest entry
+    ///
+    /// # Arguments
+    /// * `info` - Compression information recorded in the output manifest
+    /// * `dictionary` - Dictionary available to the reader, if any
+    ///
+    /// # Returns
+    /// A new ZstdCodec instance or an error if the dictionary does not match the manifest
+    pub fn for_manifest(
+        info: &CompressionInfo,
+        dictionary: Option<ZstdDictionary>,
+    ) -> Result<Self, CaptureError> {
+        if info.algorithm != CompressionAlgorithm::Zstd {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Manifest does not describe Zstd compressed output",
+            ));
+        }
+
+        let provided_id = dictionary.as_ref().map(ZstdDictionary::id);
+        if info.dictionary_id != provided_id {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ValidationFailed),
+                &format!(
+                    "Manifest requires dictionary {:?} but {:?} was provided",
+                    info.dictionary_id, provided_id
+                ),
+            ));
+        }
+
+        Self::new(info.level, dictionary)
+    }
+
+    /// Creates a codec, loading the dictionary into a compression context once
+    fn new(level: i32, dictionary: Option<ZstdDictionary>) -> Result<Self, CaptureError> {
+        let compressor = match &dictionary {
+            Some(dictionary) => {
+                zstd::bulk::Compressor::with_dictionary(level, dictionary.as_bytes())
+            }
+            None => zstd::bulk::Compressor::new(level),
+        }
+        .map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::System(SystemErrorKind::IoError),
+                "Failed to create Zstd compression context",
+            )
+            .with_source(e)
+        })?;
+
+        Ok(Self {
+            level,
+            dictionary,
+            compressor: Arc::new(Mutex::new(compressor)),
+        })
+    }
+
+    /// Returns the dictionary id used by this codec
+    ///
+    /// # Returns
+    /// The dictionary id, or None when compressing without a dictionary
+    pub fn dictionary_id(&self) -> Option<u32> {
+        self.dictionary.as_ref().map(ZstdDictionary::id)
+    }
+
+    /// Describes this codec for the output manifest
+    ///
+    /// # Returns
+    /// Compression information recording the algorithm, level and dictionary id
+    pub fn compression_info(&self) -> CompressionInfo {
+        CompressionInfo {
+            algorithm: CompressionAlgorithm::Zstd,
+            level: self.level,
+            dictionary_id: self.dictionary_id(),
+        }
+    }
+
+    /// Compresses a payload into a single Zstd frame
+    ///
+    /// Reuses one compression context, so the dictionary is not reloaded for every record.
+    ///
+    /// # Arguments
+    /// * `data` - Payload to compress
+    ///
+    /// # Returns
+    /// The compressed frame
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CaptureError> {
+        self.compressor.lock().compress(data).map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::System(SystemErrorKind::IoError),
+                "Zstd compression failed",
+            )
+            .with_source(e)
+        })
+    }
+
+    /// Creates a streaming encoder writing one Zstd frame to `writer`
+    ///
+    /// # Arguments
+    /// * `writer` - Sink receiving compressed bytes as they are produced
+    ///
+    /// # Returns
+    /// The encoder; the frame is complete once it is finished
+    pub fn stream_encoder<W: std::io::Write>(
+        &self,
+        writer: W,
+    ) -> Result<zstd::stream::write::Encoder<'static, W>, CaptureError> {
+        let encoder = match &self.dictionary {
+            Some(dictionary) => zstd::stream::write::Encoder::with_dictionary(
+                writer,
+                self.level,
+                dictionary.as_bytes(),
+            ),
+            None => zstd::stream::write::Encoder::new(writer, self.level),
+        };
+
+        encoder.map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::System(SystemErrorKind::IoError),
+                "Failed to create Zstd stream encoder",
+            )
+            .with_source(e)
+        })
+    }
+
+    /// Decompresses a single Zstd frame
+    ///
+    /// # Arguments
+    /// * `data` - Compressed frame
+    /// * `max_size` - Upper bound on the decompressed size
+    ///
+    /// # Returns
+    /// The decompressed payload
+    pub fn decompress(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CaptureError> {
+        let decompressor = match &self.dictionary {
+            Some(dictionary) => zstd::bulk::Decompressor::with_dictionary(dictionary.as_bytes()),
+            None => zstd::bulk::Decompressor::new(),
+        };
+
+        decompressor
+            .and_then(|mut decompressor| decompressor.decompress(data, max_size))
+            .map_err(|e| {
+                CaptureError::new(
+                    CaptureErrorKind::System(SystemErrorKind::IoError),
+                    "Zstd decompression failed",
+                )
+                .with_source(e)
+            })
+    }
+}
+
+/// Compression step of the output path, run on batches before the destination writer
+///
+/// Each payload is compressed on its own with the configured algorithm and level: Gzip through
+/// `flate2`, LZ4 frames through `lz4_flex` (which has no levels) and Zstd through `ZstdCodec`.
+/// Payloads shorter than `min_size`, and payloads that do not shrink, are passed on unchanged
+/// with `OutputMetadata::compression` left at `None`, so readers know which records to
+/// decompress. With `CompressionAlgorithm::None` batches pass through without being copied.
+///
+/// # Fields
+/// * `algorithm` - Algorithm applied to payloads
+/// * `level` - Algorithm specific compression level
+/// * `zstd` - Zstd codec, when the algorithm is Zstd
+/// * `min_size` - Shortest payload that is compressed
+#[derive(Debug, Clone)]
+pub struct CompressionStage {
+    algorithm: CompressionAlgorithm,
+    level: i32,
+    zstd: Option<ZstdCodec>,
+    min_size: usize,
+}
+
+impl CompressionStage {
+    /// Creates a stage from a compression configuration
+    ///
+    /// # Arguments
+    /// * `config` - Compression configuration of the destination
+    ///
+    /// # Returns
+    /// A new CompressionStage instance or an error if the configuration is invalid
+    pub fn new(config: &CompressionConfig) -> Result<Self, CaptureError> {
+        config.validate()?;
+        let zstd = match config.algorithm {
+            CompressionAlgorithm::Zstd => Some(ZstdCodec::from_config(config)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            algorithm: config.algorithm,
+            level: config.level,
+            zstd,
+            min_size: DEFAULT_MIN_COMPRESS_SIZE,
+        })
+    }
+
+    /// Creates the stage an output destination needs, if any
+    ///
+    /// Kafka and S3 compress natively with the destination's compression configuration, so
+    /// they get no stage and their records are passed on uncompressed. PCAP files hold raw
+    /// frames and cannot store compressed records.
+    ///
+    /// # Arguments
+    /// * `destination` - Output destination configuration
+    ///
+    /// # Returns
+    /// The stage, None if the destination needs no compression, or an error if the
+    /// configuration is invalid or the destination cannot hold compressed records
+    pub fn for_destination(
+        destination: &OutputDestinationConfig,
+    ) -> Result<Option<Self>, CaptureError> {
+        let algorithm = destination.compression.algorithm;
+        match destination.destination_type {
+            DestinationType::Kafka | DestinationType::S3 => Ok(None),
+            _ if algorithm == CompressionAlgorithm::None => Ok(None),
+            DestinationType::Pcap { .. } | DestinationType::PcapNg { .. } => {
+                Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    &format!(
+                        "Destination {} writes PCAP files, which cannot hold {:?} compressed \
+                         records",
+                        destination.destination_id, algorithm
+                    ),
+                ))
+            }
+            DestinationType::LocalFile | DestinationType::NetworkStream => {
+                Self::new(&destination.compression).map(Some)
+            }
+        }
+    }
+
+    /// Sets the shortest payload that is compressed
+    ///
+    /// # Arguments
+    /// * `min_size` - Payload length in bytes
+    ///
+    /// # Returns
+    /// The updated CompressionStage instance
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Returns the algorithm applied to payloads
+    pub fn algorithm(&self) -> CompressionAlgorithm {
+        self.algorithm
+    }
+
+    /// Compresses a batch, recording payload sizes before and after in the metrics
+    ///
+    /// # Arguments
+    /// * `batch` - Records to compress
+    /// * `metrics` - Metrics of the destination the batch is written to
+    ///
+    /// # Returns
+    /// The batch with compressed payloads, or an error if compression failed
+    pub fn apply(
+        &self,
+        mut batch: Vec<OutputData>,
+        metrics: &mut WriteMetrics,
+    ) -> Result<Vec<OutputData>, CaptureError> {
+        for data in &mut batch {
+            let original = data.data.len();
+            metrics.uncompressed_bytes += original as u64;
+            if self.algorithm != CompressionAlgorithm::None
+                && data.metadata.compression == CompressionAlgorithm::None
+                && original >= self.min_size
+            {
+                let compressed = self.compress(&data.data)?;
+                if compressed.len() < original {
+                    data.data = compressed.into();
+                    data.metadata.compression = self.algorithm;
+                }
+            }
+            metrics.compressed_bytes += data.data.len() as u64;
+        }
+        Ok(batch)
+    }
+
+    /// Compresses one payload
+    ///
+    /// # Arguments
+    /// * `data` - Payload to compress
+    ///
+    /// # Returns
+    /// The compressed payload
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CaptureError> {
+        match self.algorithm {
+            CompressionAlgorithm::None => Ok(data.to_vec()),
+            CompressionAlgorithm::Zstd => self.zstd_codec()?.compress(data),
+            CompressionAlgorithm::Gzip => {
+                let level = flate2::Compression::new(self.level as u32);
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+                encoder
+                    .write_all(data)
+                    .and_then(|_| encoder.finish())
+                    .map_err(|e| codec_error("Gzip compression failed", e))
+            }
+            CompressionAlgorithm::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder
+                    .write_all(data)
+                    .map_err(|e| codec_error("LZ4 compression failed", e))?;
+                encoder
+                    .finish()
+                    .map_err(|e| codec_error("LZ4 compression failed", e.into()))
+            }
+        }
+    }
+
+    /// Decompresses one payload compressed by this stage
+    ///
+    /// # Arguments
+    /// * `data` - Compressed payload
+    /// * `max_size` - Upper bound on the decompressed size
+    ///
+    /// # Returns
+    /// The decompressed payload
+    pub fn decompress(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CaptureError> {
+        let read_bounded = |mut reader: Box<dyn Read + '_>, name: &str| {
+            let mut output = Vec::new();
+            reader
+                .by_ref()
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut output)
+                .map_err(|e| codec_error(&format!("{} decompression failed", name), e))?;
+            if output.len() > max_size {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::System(SystemErrorKind::IoError),
+                    &format!("{} payload exceeds {} bytes", name, max_size),
+                ));
+            }
+            Ok(output)
+        };
+
+        match self.algorithm {
+            CompressionAlgorithm::None => Ok(data.to_vec()),
+            CompressionAlgorithm::Zstd => self.zstd_codec()?.decompress(data, max_size),
+            CompressionAlgorithm::Gzip => {
+                read_bounded(Box::new(flate2::read::GzDecoder::new(data)), "Gzip")
+            }
+            CompressionAlgorithm::Lz4 => {
+                read_bounded(Box::new(lz4_flex::frame::FrameDecoder::new(data)), "LZ4")
+            }
+        }
+    }
+
+    fn zstd_codec(&self) -> Result<&ZstdCodec, CaptureError> {
+        self.zstd.as_ref().ok_or_else(|| {
+            *CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Zstd compression stage has no codec",
+            )
+        })
+    }
+}
+
+fn codec_error(message: &str, e: std::io::Error) -> CaptureError {
+    CaptureError::new(CaptureErrorKind::System(SystemErrorKind::IoError), message).with_source(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::output::manifest::OutputManifest;
+    use crate::capture_engine::output::traits::OutputMetadata;
+    use bytes::Bytes;
+    use rand::{RngCore, SeedableRng};
+
+    // Records sharing the same header layout, differing only in a few fields
+    fn repetitive_records(count: usize) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|i| {
+                format!(
+                    "{{\"version\":1,\"source\":\"eni-0a1b2c3d4e5f\",\"vpc\":\"vpc-12345678\",\
+                     \"protocol\":\"tcp\",\"src_ip\":\"10.0.{}.{}\",\"dst_ip\":\"10.1.0.5\",\
+                     \"src_port\":{},\"dst_port\":443,\"flags\":\"ACK\",\"length\":{}}}",
+                    i % 7,
+                    i % 251,
+                    30000 + (i % 1000),
+                    64 + (i % 1400)
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    fn trained_dictionary() -> Vec<u8> {
+        zstd::dict::from_samples(&repetitive_records(2000), 4096).unwrap()
+    }
+
+    fn compressed_size(codec: &ZstdCodec, records: &[Vec<u8>]) -> usize {
+        records
+            .iter()
+            .map(|record| codec.compress(record).unwrap().len())
+            .sum()
+    }
+
+    #[test]
+    fn test_default_config_is_uncompressed() {
+        let config = CompressionConfig::default();
+        assert_eq!(config.algorithm, CompressionAlgorithm::None);
+        assert!(config.dictionary.is_none());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_dictionary_improves_ratio_on_small_records() {
+        let records = repetitive_records(500);
+        let original: usize = records.iter().map(Vec::len).sum();
+
+        let plain = ZstdCodec::from_config(&CompressionConfig::zstd(3)).unwrap();
+        let with_dict = ZstdCodec::from_config(
+            &CompressionConfig::zstd(3)
+                .with_dictionary(DictionarySource::Bytes(trained_dictionary())),
+        )
+        .unwrap();
+
+        let plain_size = compressed_size(&plain, &records);
+        let dict_size = compressed_size(&with_dict, &records);
+
+        assert!(plain_size < original);
+        // A trained dictionary should at least halve the output on these records
+        assert!(
+            dict_size * 2 < plain_size,
+            "dictionary size {} vs plain size {}",
+            dict_size,
+            plain_size
+        );
+    }
+
+    #[test]
+    fn test_dictionary_round_trip() {
+        let config = CompressionConfig::zstd(5)
+            .with_dictionary(DictionarySource::Bytes(trained_dictionary()));
+        let codec = ZstdCodec::from_config(&config).unwrap();
+
+        for record in repetitive_records(50) {
+            let compressed = codec.compress(&record).unwrap();
+            let restored = codec.decompress(&compressed, record.len()).unwrap();
+            assert_eq!(restored, record);
+        }
+    }
+
+    #[test]
+    fn test_dictionary_loaded_from_path() {
+        let dictionary = trained_dictionary();
+        let path = std::env::temp_dir().join(format!("sparktrap-dict-{}", uuid::Uuid::new_v4()));
+        fs::File::create(&path)
+            .unwrap()
+            .write_all(&dictionary)
+            .unwrap();
+
+        let config =
+            CompressionConfig::zstd(3).with_dictionary(DictionarySource::Path(path.clone()));
+        let loaded = config.load_dictionary().unwrap().unwrap();
+        let expected = ZstdDictionary::from_bytes(dictionary).unwrap();
+        assert_eq!(loaded.id(), expected.id());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_dictionary_file_fails_validation() {
+        let config = CompressionConfig::zstd(3).with_dictionary(DictionarySource::Path(
+            PathBuf::from("/nonexistent/sparktrap.dict"),
+        ));
+        let err = config.validate().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::System(SystemErrorKind::IoError)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_dictionary_bytes_fail_validation() {
+        let config = CompressionConfig::zstd(3)
+            .with_dictionary(DictionarySource::Bytes(b"not a dictionary".to_vec()));
+        let err = config.validate().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::ParseError)
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_dictionary_tables_fail_validation() {
+        let mut dictionary = trained_dictionary();
+        // Keep the header but destroy the entropy tables that follow it
+        for byte in dictionary.iter_mut().skip(8).take(64) {
+            *byte = 0xFF;
+        }
+        assert!(ZstdDictionary::from_bytes(dictionary).is_err());
+    }
+
+    #[test]
+    fn test_dictionary_requires_zstd() {
+        let config = CompressionConfig {
+            algorithm: CompressionAlgorithm::Gzip,
+            level: 6,
+            dictionary: Some(DictionarySource::Bytes(trained_dictionary())),
+        };
+        let err = config.validate().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue)
+        ));
+    }
+
+    #[test]
+    fn test_manifest_records_dictionary_id() {
+        let dictionary = trained_dictionary();
+        let codec = ZstdCodec::from_config(
+            &CompressionConfig::zstd(3)
+                .with_dictionary(DictionarySource::Bytes(dictionary.clone())),
+        )
+        .unwrap();
+
+        let manifest = OutputManifest::new("archive").with_compression(codec.compression_info());
+        let restored = OutputManifest::from_json(&manifest.to_json().unwrap()).unwrap();
+        let info = restored.compression.unwrap();
+        assert_eq!(info.dictionary_id, codec.dictionary_id());
+        assert!(info.dictionary_id.is_some());
+
+        // A reader holding the matching dictionary can decode output described by the manifest
+        let record = repetitive_records(1).remove(0);
+        let compressed = codec.compress(&record).unwrap();
+        let reader =
+            ZstdCodec::for_manifest(&info, Some(ZstdDictionary::from_bytes(dictionary).unwrap()))
+                .unwrap();
+        assert_eq!(
+            reader.decompress(&compressed, record.len()).unwrap(),
+            record
+        );
+    }
+
+    #[test]
+    fn test_manifest_rejects_mismatched_dictionary() {
+        let codec = ZstdCodec::from_config(
+            &CompressionConfig::zstd(3)
+                .with_dictionary(DictionarySource::Bytes(trained_dictionary())),
+        )
+        .unwrap();
+        let info = codec.compression_info();
+
+        assert!(ZstdCodec::for_manifest(&info, None).is_err());
+
+        let plain_info = ZstdCodec::from_config(&CompressionConfig::zstd(3))
+            .unwrap()
+            .compression_info();
+        let dictionary = ZstdDictionary::from_bytes(trained_dictionary()).unwrap();
+        assert!(ZstdCodec::for_manifest(&plain_info, Some(dictionary)).is_err());
+    }
+
+    fn output(payload: &[u8]) -> OutputData {
+        OutputData {
+            data: Bytes::copy_from_slice(payload),
+            length: None,
+            metadata: OutputMetadata {
+                timestamp: 0,
+                routing_info: None,
+                compression: CompressionAlgorithm::None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_stage_round_trips_every_algorithm() {
+        let payload = repetitive_records(50).concat();
+        for (algorithm, level) in [
+            (CompressionAlgorithm::Gzip, 6),
+            (CompressionAlgorithm::Lz4, 0),
+            (CompressionAlgorithm::Zstd, 3),
+        ] {
+            let config = CompressionConfig {
+                algorithm,
+                level,
+                dictionary: None,
+            };
+            let stage = CompressionStage::new(&config).unwrap();
+            let mut metrics = WriteMetrics::default();
+            let batch = stage.apply(vec![output(&payload)], &mut metrics).unwrap();
+
+            assert_eq!(batch[0].metadata.compression, algorithm);
+            assert!(metrics.compression_ratio() > 2.0, "{:?}", algorithm);
+            assert_eq!(metrics.uncompressed_bytes, payload.len() as u64);
+            assert_eq!(metrics.compressed_bytes, batch[0].data.len() as u64);
+            let restored = stage.decompress(&batch[0].data, payload.len()).unwrap();
+            assert_eq!(restored, payload, "{:?}", algorithm);
+            assert!(stage.decompress(&batch[0].data, payload.len() - 1).is_err());
+        }
+    }
+
+    #[test]
+    fn test_stage_skips_small_and_incompressible_payloads() {
+        let stage = CompressionStage::new(&CompressionConfig::zstd(3))
+            .unwrap()
+            .with_min_size(64);
+        let mut random = vec![0u8; 4096];
+        rand_chacha::ChaCha8Rng::seed_from_u64(1).fill_bytes(&mut random);
+        let small = output(&[0u8; 32]);
+        let mut metrics = WriteMetrics::default();
+        let batch = stage
+            .apply(vec![small.clone(), output(&random)], &mut metrics)
+            .unwrap();
+
+        assert_eq!(batch[0].metadata.compression, CompressionAlgorithm::None);
+        assert_eq!(batch[0].data, small.data);
+        assert_eq!(batch[1].metadata.compression, CompressionAlgorithm::None);
+        assert_eq!(batch[1].data.as_ref(), random.as_slice());
+        assert_eq!(metrics.compression_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_none_is_passthrough_without_copy() {
+        let stage = CompressionStage::new(&CompressionConfig::default()).unwrap();
+        let data = output(&repetitive_records(10).concat());
+        let pointer = data.data.as_ptr();
+        let mut metrics = WriteMetrics::default();
+        let batch = stage.apply(vec![data], &mut metrics).unwrap();
+
+        assert_eq!(batch[0].data.as_ptr(), pointer);
+        assert_eq!(metrics.uncompressed_bytes, metrics.compressed_bytes);
+    }
+
+    #[test]
+    fn test_gzip_level_validated() {
+        let config = CompressionConfig {
+            algorithm: CompressionAlgorithm::Gzip,
+            level: 10,
+            dictionary: None,
+        };
+        assert!(CompressionStage::new(&config).is_err());
+    }
+}
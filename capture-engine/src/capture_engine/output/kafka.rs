@@ -0,0 +1,1270 @@
+// output/kafka.rs
+//! Kafka topic output with at-least-once delivery.
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::FutureExt;
+use tokio::time::Instant;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, SystemErrorKind,
+};
+use crate::capture_engine::output::compression::{CompressionAlgorithm, CompressionConfig};
+use crate::capture_engine::output::traits::{
+    DestinationState, DestinationStatus, DestinationType, FlushControl, FlushMode, OutputData,
+    OutputDestinationConfig, OutputEvent, RetryPolicy, WriteFailure,
+};
+use crate::traits::Error;
+
+/// Default time the client may spend delivering one message before reporting it failed
+pub const DEFAULT_MESSAGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of records a writer keeps undelivered before it stops accepting new ones
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 10_000;
+
+/// Delay before resending a record the client had no room to queue
+pub const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Prefix of destination settings passed unchanged to the Kafka client
+pub const CLIENT_SETTING_PREFIX: &str = "kafka.";
+
+/// Acknowledgement a write needs before it counts as delivered
+///
+/// # Variants
+/// * `Leader` - The partition leader has written the record
+/// * `All` - Every in-sync replica has the record; also enables idempotent retries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KafkaAcks {
+    Leader,
+    #[default]
+    All,
+}
+
+/// How records are assigned to partitions
+///
+/// # Variants
+/// * `Default` - The client's partitioner chooses; records carry no key
+/// * `Fixed` - Every record goes to one partition, keeping them in order
+/// * `RoundRobin` - Records rotate across the first `partitions` partitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PartitionStrategy {
+    #[default]
+    Default,
+    Fixed(i32),
+    RoundRobin {
+        partitions: i32,
+    },
+}
+
+impl PartitionStrategy {
+    /// Parses `default`, `fixed:N` or `round_robin:N`
+    fn parse(value: &str) -> Result<Self, CaptureError> {
+        let invalid = || {
+            *CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                &format!(
+                    "Kafka partition strategy '{}' must be default, fixed:N or round_robin:N",
+                    value
+                ),
+            )
+        };
+        let (name, count) = match value.split_once(':') {
+            Some((name, count)) => (name, Some(count.parse::<i32>().map_err(|_| invalid())?)),
+            None => (value, None),
+        };
+        match (name, count) {
+            ("default", None) => Ok(PartitionStrategy::Default),
+            ("fixed", Some(partition)) if partition >= 0 => Ok(PartitionStrategy::Fixed(partition)),
+            ("round_robin", Some(partitions)) if partitions > 0 => {
+                Ok(PartitionStrategy::RoundRobin { partitions })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Assigns partitions to successive records
+#[derive(Debug, Clone)]
+pub struct PartitionSelector {
+    strategy: PartitionStrategy,
+    next: i32,
+}
+
+impl PartitionSelector {
+    /// Creates a selector starting at partition 0
+    pub fn new(strategy: PartitionStrategy) -> Self {
+        Self { strategy, next: 0 }
+    }
+
+    /// Returns the partition of the next record, or None to let the client choose
+    pub fn next_partition(&mut self) -> Option<i32> {
+        match self.strategy {
+            PartitionStrategy::Default => None,
+            PartitionStrategy::Fixed(partition) => Some(partition),
+            PartitionStrategy::RoundRobin { partitions } => {
+                let partition = self.next;
+                self.next = (self.next + 1) % partitions;
+                Some(partition)
+            }
+        }
+    }
+}
+
+/// Settings of a Kafka destination
+///
+/// Read from the destination settings `brokers` and `topic` (required), `acks` (`all` or
+/// `leader`), `partition`, `message_timeout_ms`, `max_retries`, `retry_backoff_ms` and
+/// `max_in_flight`. Settings starting with `kafka.` are passed to the client without the prefix.
+///
+/// # Fields
+/// * `brokers` - Comma-separated bootstrap brokers
+/// * `topic` - Topic records are written to
+/// * `acks` - Acknowledgement a write needs
+/// * `partition_strategy` - How records are assigned to partitions
+/// * `compression` - Compression applied by the client to record batches
+/// * `retry` - Resends of records whose delivery failed
+/// * `message_timeout` - Time the client may spend delivering one attempt
+/// * `max_in_flight` - Records kept undelivered before new records wait for earlier ones
+/// * `client_overrides` - Client settings passed through unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub struct KafkaConfig {
+    pub brokers: String,
+    pub topic: String,
+    pub acks: KafkaAcks,
+    pub partition_strategy: PartitionStrategy,
+    pub compression: CompressionConfig,
+    pub retry: RetryPolicy,
+    pub message_timeout: Duration,
+    pub max_in_flight: usize,
+    pub client_overrides: HashMap<String, String>,
+}
+
+impl KafkaConfig {
+    /// Reads the settings of a Kafka destination
+    ///
+    /// # Arguments
+    /// * `destination` - A `Kafka` destination
+    ///
+    /// # Returns
+    /// The settings, or an error if the destination is not Kafka, a required setting is
+    /// missing, a value does not parse, `max_in_flight` is 0, or the compression uses a
+    /// dictionary
+    pub fn from_destination(destination: &OutputDestinationConfig) -> Result<Self, CaptureError> {
+        if !matches!(destination.destination_type, DestinationType::Kafka) {
+            return Err(config_error(
+                ConfigErrorKind::InvalidValue,
+                "Destination is not Kafka",
+            ));
+        }
+        let settings = &destination.settings;
+        let required = |name: &str| {
+            settings
+                .get(name)
+                .filter(|value| !value.is_empty())
+                .cloned()
+                .ok_or_else(|| {
+                    config_error(
+                        ConfigErrorKind::MissingRequired,
+                        &format!("Kafka destination needs a '{}' setting", name),
+                    )
+                })
+        };
+        let number = |name: &str| -> Result<Option<u64>, CaptureError> {
+            settings
+                .get(name)
+                .map(|value| {
+                    value.parse::<u64>().map_err(|_| {
+                        config_error(
+                            ConfigErrorKind::ParseError,
+                            &format!("Kafka setting '{}' must be a whole number", name),
+                        )
+                    })
+                })
+                .transpose()
+        };
+
+        let acks = match settings.get("acks").map(String::as_str) {
+            None | Some("all") | Some("-1") => KafkaAcks::All,
+            Some("leader") | Some("1") => KafkaAcks::Leader,
+            Some(other) => {
+                return Err(config_error(
+                    ConfigErrorKind::InvalidValue,
+                    &format!(
+                        "Kafka acks '{}' must be all or leader for at-least-once delivery",
+                        other
+                    ),
+                ))
+            }
+        };
+        let partition_strategy = match settings.get("partition") {
+            Some(value) => PartitionStrategy::parse(value)?,
+            None => PartitionStrategy::Default,
+        };
+        if destination.compression.dictionary.is_some() {
+            return Err(config_error(
+                ConfigErrorKind::InvalidValue,
+                "Kafka compression does not support dictionaries",
+            ));
+        }
+
+        let mut retry = RetryPolicy::default();
+        if let Some(max_retries) = number("max_retries")? {
+            retry.max_retries = u32::try_from(max_retries).unwrap_or(u32::MAX);
+        }
+        if let Some(backoff_ms) = number("retry_backoff_ms")? {
+            retry.initial_backoff = Duration::from_millis(backoff_ms);
+        }
+        let message_timeout =
+            number("message_timeout_ms")?.map_or(DEFAULT_MESSAGE_TIMEOUT, Duration::from_millis);
+        let max_in_flight = number("max_in_flight")?.map_or(DEFAULT_MAX_IN_FLIGHT, |max| {
+            usize::try_from(max).unwrap_or(usize::MAX)
+        });
+        if max_in_flight == 0 {
+            return Err(config_error(
+                ConfigErrorKind::InvalidValue,
+                "Kafka setting 'max_in_flight' must be at least 1",
+            ));
+        }
+
+        let client_overrides = settings
+            .iter()
+            .filter_map(|(name, value)| {
+                name.strip_prefix(CLIENT_SETTING_PREFIX)
+                    .map(|name| (name.to_string(), value.clone()))
+            })
+            .collect();
+
+        Ok(Self {
+            brokers: required("brokers")?,
+            topic: required("topic")?,
+            acks,
+            partition_strategy,
+            compression: destination.compression.clone(),
+            retry,
+            message_timeout,
+            max_in_flight,
+            client_overrides,
+        })
+    }
+
+    /// Client settings for the producer, sorted by name
+    ///
+    /// Overrides are applied last, so they win over derived settings.
+    pub fn client_settings(&self) -> Vec<(String, String)> {
+        let mut settings = HashMap::from([
+            ("bootstrap.servers".to_string(), self.brokers.clone()),
+            (
+                "message.timeout.ms".to_string(),
+                self.message_timeout.as_millis().to_string(),
+            ),
+        ]);
+        let (acks, idempotent) = match self.acks {
+            KafkaAcks::All => ("all", true),
+            KafkaAcks::Leader => ("1", false),
+        };
+        settings.insert("acks".to_string(), acks.to_string());
+        settings.insert("enable.idempotence".to_string(), idempotent.to_string());
+
+        settings.insert(
+            "compression.type".to_string(),
+            codec(self.compression.algorithm).to_string(),
+        );
+        if self.compression.algorithm != CompressionAlgorithm::None {
+            settings.insert(
+                "compression.level".to_string(),
+                self.compression.level.to_string(),
+            );
+        }
+        settings.extend(self.client_overrides.clone());
+
+        let mut settings: Vec<_> = settings.into_iter().collect();
+        settings.sort();
+        settings
+    }
+}
+
+fn config_error(kind: ConfigErrorKind, message: &str) -> CaptureError {
+    *CaptureError::new(CaptureErrorKind::Configuration(kind), message)
+}
+
+/// Delivery outcomes of a destination, driving its status and write failure events
+///
+/// A delivery makes the destination `Active`. A failed delivery that will be retried makes it
+/// `Degraded`; one that has used up its retries, or losing every broker, makes it `Failed`.
+/// Every failed attempt queues a `WriteError` event.
+///
+/// # Fields
+/// * `destination_id` - Destination the outcomes belong to
+/// * `retry` - Resends allowed per record
+/// * `state` - Current connectivity
+/// * `last_error` - Most recent failure
+/// * `delivered` - Records acknowledged
+/// * `failed` - Records given up on
+/// * `events` - Events not yet taken by the caller
+#[derive(Debug)]
+pub struct DeliveryTracker {
+    destination_id: String,
+    retry: RetryPolicy,
+    state: DestinationState,
+    last_error: Option<String>,
+    delivered: u64,
+    failed: u64,
+    events: Vec<OutputEvent>,
+}
+
+impl DeliveryTracker {
+    /// Creates a tracker for an `Active` destination
+    pub fn new(destination_id: &str, retry: RetryPolicy) -> Self {
+        Self {
+            destination_id: destination_id.to_string(),
+            retry,
+            state: DestinationState::Active,
+            last_error: None,
+            delivered: 0,
+            failed: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Records an acknowledged record
+    pub fn delivered(&mut self) {
+        self.delivered += 1;
+        self.state = DestinationState::Active;
+    }
+
+    /// Records a failed delivery attempt
+    ///
+    /// # Arguments
+    /// * `error` - Why the attempt failed
+    /// * `retry` - Number of the retry that would follow, starting at 1
+    ///
+    /// # Returns
+    /// The delay before resending, or None if the record is given up on
+    pub fn failed(&mut self, error: &str, retry: u32) -> Option<Duration> {
+        let backoff = self.retry.backoff(retry);
+        let outcome = match backoff {
+            Some(_) => format!("retry {} of {}", retry, self.retry.max_retries),
+            None => {
+                self.failed += 1;
+                "giving up".to_string()
+            }
+        };
+        self.state = match backoff {
+            Some(_) => DestinationState::Degraded,
+            None => DestinationState::Failed,
+        };
+        self.fail(format!(
+            "Kafka destination {}: {} ({})",
+            self.destination_id, error, outcome
+        ));
+        backoff
+    }
+
+    /// Records that no broker can be reached
+    pub fn unreachable(&mut self, reason: &str) {
+        self.state = DestinationState::Failed;
+        self.fail(format!(
+            "Kafka destination {}: {}",
+            self.destination_id, reason
+        ));
+    }
+
+    fn fail(&mut self, error: String) {
+        self.last_error = Some(error.clone());
+        self.events
+            .push(OutputEvent::WriteError(WriteFailure { error }));
+    }
+
+    /// Gets the current status
+    pub fn status(&self) -> DestinationStatus {
+        DestinationStatus {
+            destination_id: self.destination_id.clone(),
+            status: self.state,
+            last_error: self.last_error.clone(),
+        }
+    }
+
+    /// Gets the number of records acknowledged
+    pub fn delivered_count(&self) -> u64 {
+        self.delivered
+    }
+
+    /// Gets the number of records given up on
+    pub fn failed_count(&self) -> u64 {
+        self.failed
+    }
+
+    /// Takes the events queued since the last call
+    pub fn take_events(&mut self) -> Vec<OutputEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// Header carrying the capture timestamp in nanoseconds since the Unix epoch
+pub const TIMESTAMP_HEADER: &str = "sparktrap.timestamp_ns";
+/// Header carrying the on-wire length of a truncated packet
+pub const LENGTH_HEADER: &str = "sparktrap.length";
+/// Header naming the algorithm the payload is compressed with
+pub const COMPRESSION_HEADER: &str = "sparktrap.compression";
+/// Header listing the destinations the data was routed to, comma-separated
+pub const DESTINATIONS_HEADER: &str = "sparktrap.destinations";
+
+fn codec(algorithm: CompressionAlgorithm) -> &'static str {
+    match algorithm {
+        CompressionAlgorithm::None => "none",
+        CompressionAlgorithm::Gzip => "gzip",
+        CompressionAlgorithm::Lz4 => "lz4",
+        CompressionAlgorithm::Zstd => "zstd",
+    }
+}
+
+/// Headers carrying the metadata of a record
+///
+/// The capture timestamp and payload compression are always sent; the on-wire length only
+/// when the data records it and the destinations only for routed data.
+///
+/// # Arguments
+/// * `data` - Data the record is written from
+///
+/// # Returns
+/// Header names and values, in a fixed order
+pub fn record_headers(data: &OutputData) -> Vec<(&'static str, String)> {
+    let metadata = &data.metadata;
+    let mut headers = vec![
+        (TIMESTAMP_HEADER, metadata.timestamp.to_string()),
+        (COMPRESSION_HEADER, codec(metadata.compression).to_string()),
+    ];
+    if let Some(length) = data.length {
+        headers.push((LENGTH_HEADER, length.to_string()));
+    }
+    if let Some(routing) = &metadata.routing_info {
+        headers.push((DESTINATIONS_HEADER, routing.destination_ids.join(",")));
+    }
+    headers
+}
+
+/// A record handed to a producer
+///
+/// # Fields
+/// * `topic` - Topic the record is written to
+/// * `partition` - Partition, or None to let the client choose
+/// * `timestamp` - Record timestamp in milliseconds since the Unix epoch
+/// * `headers` - Metadata headers
+/// * `payload` - Record value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KafkaRecord<'a> {
+    pub topic: &'a str,
+    pub partition: Option<i32>,
+    pub timestamp: i64,
+    pub headers: &'a [(&'static str, String)],
+    pub payload: &'a [u8],
+}
+
+/// Why a producer did not queue a record
+///
+/// # Variants
+/// * `QueueFull` - The client's queue has no room; the record can be sent again once it drains
+/// * `Failed` - The record was rejected for the given reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendError {
+    QueueFull,
+    Failed(String),
+}
+
+/// Client that queues records for delivery to the brokers
+pub trait RecordProducer: Send + Sync {
+    /// Resolves to the delivery report of one record, or why it was not delivered
+    type Delivery: Future<Output = Result<(), String>> + Send + Sync + Unpin;
+
+    /// Queues a record
+    ///
+    /// # Returns
+    /// The pending delivery report, or why the client could not queue the record
+    fn send(&self, record: KafkaRecord<'_>) -> Result<Self::Delivery, SendError>;
+}
+
+/// Where a record is in its delivery
+///
+/// # Variants
+/// * `Sent` - Queued by the client, waiting for its delivery report
+/// * `Reported` - The delivery report has arrived but is not yet accounted for
+/// * `Backoff` - The last attempt failed with `error`; the record is resent at `until`
+/// * `QueueFull` - The client had no room for the record; it is sent again at `until` without
+///   counting as a retry
+enum Stage<D> {
+    Sent(D),
+    Reported(Result<(), String>),
+    Backoff { until: Instant, error: String },
+    QueueFull { until: Instant },
+}
+
+/// A record that has not yet been delivered or given up on
+struct InFlight<D> {
+    data: OutputData,
+    partition: Option<i32>,
+    retries: u32,
+    stage: Stage<D>,
+}
+
+/// Writes output data to a Kafka topic with at-least-once delivery
+///
+/// Each `OutputData` becomes one record whose payload is the data, whose timestamp is
+/// `OutputMetadata::timestamp` in milliseconds and whose headers carry the rest of its
+/// metadata (see `record_headers`). Records are kept until the broker acknowledges them;
+/// failed deliveries are resent to the same partition following the retry policy, so a record
+/// may be written more than once but is never silently lost. Each record waits out its own
+/// backoff, so a failing record never holds up the others. Once `max_in_flight` records are
+/// undelivered, sending waits for earlier records to be delivered or given up on.
+///
+/// # Fields
+/// * `config` - Destination settings
+/// * `producer` - Client records are handed to
+/// * `partitions` - Partition assignment of new records
+/// * `tracker` - Delivery outcomes, status and events
+/// * `brokers_down` - Set by the client when every broker is unreachable
+/// * `in_flight` - Records not yet delivered or given up on
+pub struct KafkaWriter<P: RecordProducer> {
+    config: KafkaConfig,
+    producer: P,
+    partitions: PartitionSelector,
+    tracker: DeliveryTracker,
+    brokers_down: Arc<AtomicBool>,
+    in_flight: Vec<InFlight<P::Delivery>>,
+}
+
+impl<P: RecordProducer> KafkaWriter<P> {
+    /// Creates a writer over a producer
+    ///
+    /// # Arguments
+    /// * `destination_id` - Destination the writer's status and events belong to
+    /// * `config` - Destination settings
+    /// * `producer` - Client records are handed to
+    pub fn with_producer(destination_id: &str, config: KafkaConfig, producer: P) -> Self {
+        Self::from_parts(
+            destination_id,
+            config,
+            producer,
+            Arc::new(AtomicBool::new(false)),
+        )
+    }
+
+    fn from_parts(
+        destination_id: &str,
+        config: KafkaConfig,
+        producer: P,
+        brokers_down: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            partitions: PartitionSelector::new(config.partition_strategy),
+            tracker: DeliveryTracker::new(destination_id, config.retry),
+            config,
+            producer,
+            brokers_down,
+            in_flight: Vec::new(),
+        }
+    }
+
+    /// Queues records for delivery and collects any delivery reports already received
+    ///
+    /// # Arguments
+    /// * `batch` - Records to write, in order
+    ///
+    /// # Returns
+    /// An error naming the first record given up on
+    pub async fn send_batch(&mut self, batch: &[OutputData]) -> Result<(), CaptureError> {
+        let mut first_error = None;
+        for data in batch {
+            while self.in_flight.len() >= self.config.max_in_flight {
+                self.wait_for_progress().await;
+                if let Err(error) = self.settle(false, true).await {
+                    first_error.get_or_insert(error);
+                }
+            }
+            let partition = self.partitions.next_partition();
+            let stage = self.send(data, partition);
+            if let Err(error) = self.track(data.clone(), partition, 0, stage, true) {
+                first_error.get_or_insert(error);
+            }
+        }
+        let settled = self.settle(false, true).await;
+        first_error.map_or(settled, Err)
+    }
+
+    /// Gets the destination status, `Failed` while no broker can be reached
+    pub fn status(&self) -> DestinationStatus {
+        let mut status = self.tracker.status();
+        if self.brokers_down.load(Ordering::Relaxed) {
+            status.status = DestinationState::Failed;
+        }
+        status
+    }
+
+    /// Gets the number of records not yet delivered or given up on
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Takes the events queued since the last call
+    pub fn take_events(&mut self) -> Vec<OutputEvent> {
+        if self.brokers_down.swap(false, Ordering::Relaxed) {
+            self.tracker.unreachable("all brokers are down");
+        }
+        self.tracker.take_events()
+    }
+
+    /// Hands a record to the client
+    fn send(&self, data: &OutputData, partition: Option<i32>) -> Stage<P::Delivery> {
+        let headers = record_headers(data);
+        let record = KafkaRecord {
+            topic: &self.config.topic,
+            partition,
+            timestamp: (data.metadata.timestamp / 1_000_000) as i64,
+            headers: &headers,
+            payload: &data.data[..],
+        };
+        match self.producer.send(record) {
+            Ok(delivery) => Stage::Sent(delivery),
+            Err(SendError::QueueFull) => Stage::QueueFull {
+                until: Instant::now() + QUEUE_FULL_BACKOFF,
+            },
+            Err(SendError::Failed(error)) => Stage::Reported(Err(error)),
+        }
+    }
+
+    /// Accounts for a record's latest stage, keeping it in flight unless it is done
+    ///
+    /// # Arguments
+    /// * `retries` - Resends of the record so far
+    /// * `retry` - Schedule a resend if the record failed; otherwise give up on it at once
+    ///
+    /// # Returns
+    /// An error if the record was given up on
+    fn track(
+        &mut self,
+        data: OutputData,
+        partition: Option<i32>,
+        retries: u32,
+        stage: Stage<P::Delivery>,
+        retry: bool,
+    ) -> Result<(), CaptureError> {
+        let error = match stage {
+            Stage::Reported(Ok(())) => {
+                self.brokers_down.store(false, Ordering::Relaxed);
+                self.tracker.delivered();
+                return Ok(());
+            }
+            Stage::Reported(Err(error)) => error,
+            // A record waiting to be resent is given up on when resends stop
+            Stage::Backoff { error, .. } if !retry => error,
+            Stage::QueueFull { .. } if !retry => "producer queue is full".to_string(),
+            stage => {
+                self.in_flight.push(InFlight {
+                    data,
+                    partition,
+                    retries,
+                    stage,
+                });
+                return Ok(());
+            }
+        };
+        let next = if retry { retries + 1 } else { u32::MAX };
+        match self.tracker.failed(&error, next) {
+            Some(backoff) => {
+                self.in_flight.push(InFlight {
+                    data,
+                    partition,
+                    retries,
+                    stage: Stage::Backoff {
+                        until: Instant::now() + backoff,
+                        error,
+                    },
+                });
+                Ok(())
+            }
+            None => Err(delivery_error(&self.config.topic, &error)),
+        }
+    }
+
+    /// Collects delivery reports and resends records whose backoff has passed
+    ///
+    /// # Arguments
+    /// * `wait` - Wait for every record, including resends, instead of only collecting
+    ///   reports already received
+    /// * `retry` - Resend failed records; otherwise they are given up on at once
+    ///
+    /// # Returns
+    /// An error naming the first record given up on
+    async fn settle(&mut self, wait: bool, retry: bool) -> Result<(), CaptureError> {
+        let mut first_error = None;
+        loop {
+            let now = Instant::now();
+            for mut entry in std::mem::take(&mut self.in_flight) {
+                let mut retries = entry.retries;
+                entry.stage = match entry.stage {
+                    Stage::Sent(mut delivery) => match (&mut delivery).now_or_never() {
+                        Some(report) => Stage::Reported(report),
+                        None => Stage::Sent(delivery),
+                    },
+                    Stage::Backoff { until, .. } if retry && until <= now => {
+                        retries += 1;
+                        self.send(&entry.data, entry.partition)
+                    }
+                    Stage::QueueFull { until } if retry && until <= now => {
+                        self.send(&entry.data, entry.partition)
+                    }
+                    stage => stage,
+                };
+                if let Err(error) =
+                    self.track(entry.data, entry.partition, retries, entry.stage, retry)
+                {
+                    first_error.get_or_insert(error);
+                }
+            }
+            if !wait || self.in_flight.is_empty() {
+                break;
+            }
+            self.wait_for_progress().await;
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Waits until a delivery report arrives or the earliest backoff passes
+    async fn wait_for_progress(&mut self) {
+        let next_resend = self
+            .in_flight
+            .iter()
+            .filter_map(|entry| match entry.stage {
+                Stage::Backoff { until, .. } | Stage::QueueFull { until } => Some(until),
+                _ => None,
+            })
+            .min();
+        let in_flight = &mut self.in_flight;
+        let reports = futures::future::poll_fn(|cx| {
+            let mut reported = false;
+            for entry in in_flight.iter_mut() {
+                if let Stage::Sent(delivery) = &mut entry.stage {
+                    if let Poll::Ready(report) = Pin::new(delivery).poll(cx) {
+                        entry.stage = Stage::Reported(report);
+                        reported = true;
+                    }
+                }
+            }
+            if reported {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        });
+        match next_resend {
+            Some(until) => {
+                tokio::select! {
+                    _ = reports => {}
+                    _ = tokio::time::sleep_until(until) => {}
+                }
+            }
+            None => reports.await,
+        }
+    }
+}
+
+fn delivery_error(topic: &str, error: &str) -> CaptureError {
+    *CaptureError::new(
+        CaptureErrorKind::System(SystemErrorKind::IoError),
+        &format!("Kafka delivery to topic {} failed: {}", topic, error),
+    )
+}
+
+#[async_trait]
+impl<P: RecordProducer> FlushControl for KafkaWriter<P> {
+    /// `Normal` collects reports already received and resends records whose backoff has
+    /// passed, `Forced` waits until every record is acknowledged or given up on, and
+    /// `Emergency` waits without resending failures.
+    async fn flush_with_mode(&mut self, mode: FlushMode) -> Result<(), Error> {
+        let result = match mode {
+            FlushMode::Normal => self.settle(false, true).await,
+            FlushMode::Forced => self.settle(true, true).await,
+            FlushMode::Emergency => self.settle(true, false).await,
+        };
+        result.map_err(|e| Error::Runtime(e.message().to_string()))
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub use producer::{KafkaDestination, RdKafkaProducer};
+
+#[cfg(feature = "kafka")]
+mod producer {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use futures::future::Map;
+    use futures::FutureExt;
+    use rdkafka::config::ClientConfig;
+    use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+    use rdkafka::message::{Header, OwnedHeaders};
+    use rdkafka::producer::{DeliveryFuture, FutureProducer, FutureRecord};
+    use rdkafka::ClientContext;
+
+    use super::{KafkaConfig, KafkaRecord, KafkaWriter, RecordProducer, SendError};
+    use crate::capture_engine::capture::capture_error::{
+        CaptureError, CaptureErrorKind, ConfigErrorKind,
+    };
+    use crate::capture_engine::output::traits::OutputDestinationConfig;
+
+    /// Client context noticing when every broker is unreachable
+    pub struct BrokerContext {
+        brokers_down: Arc<AtomicBool>,
+    }
+
+    impl ClientContext for BrokerContext {
+        fn error(&self, error: KafkaError, _reason: &str) {
+            if error.rdkafka_error_code() == Some(RDKafkaErrorCode::AllBrokersDown) {
+                self.brokers_down.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    type DeliveryReport = <DeliveryFuture as std::future::Future>::Output;
+
+    fn delivery_result(report: DeliveryReport) -> Result<(), String> {
+        match report {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err((error, _))) => Err(error.to_string()),
+            Err(_) => Err("producer dropped the delivery report".to_string()),
+        }
+    }
+
+    /// `RecordProducer` over the rdkafka client
+    pub struct RdKafkaProducer(FutureProducer<BrokerContext>);
+
+    impl RecordProducer for RdKafkaProducer {
+        type Delivery = Map<DeliveryFuture, fn(DeliveryReport) -> Result<(), String>>;
+
+        fn send(&self, record: KafkaRecord<'_>) -> Result<Self::Delivery, SendError> {
+            let headers =
+                record
+                    .headers
+                    .iter()
+                    .fold(OwnedHeaders::new(), |headers, (key, value)| {
+                        headers.insert(Header {
+                            key,
+                            value: Some(value.as_str()),
+                        })
+                    });
+            let mut future_record = FutureRecord::<(), [u8]>::to(record.topic)
+                .payload(record.payload)
+                .timestamp(record.timestamp)
+                .headers(headers);
+            if let Some(partition) = record.partition {
+                future_record = future_record.partition(partition);
+            }
+            self.0
+                .send_result(future_record)
+                .map(|delivery| delivery.map(delivery_result as fn(_) -> _))
+                .map_err(|(error, _)| match error {
+                    KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull) => {
+                        SendError::QueueFull
+                    }
+                    error => SendError::Failed(error.to_string()),
+                })
+        }
+    }
+
+    /// Kafka destination writing through the rdkafka client
+    pub type KafkaDestination = KafkaWriter<RdKafkaProducer>;
+
+    impl KafkaWriter<RdKafkaProducer> {
+        /// Creates a producer for a Kafka destination
+        ///
+        /// # Arguments
+        /// * `destination` - A `Kafka` destination
+        ///
+        /// # Returns
+        /// The destination, or an error if its settings are invalid or the client could not
+        /// be created. Brokers are contacted in the background.
+        pub fn new(destination: &OutputDestinationConfig) -> Result<Self, CaptureError> {
+            let config = KafkaConfig::from_destination(destination)?;
+            let mut client = ClientConfig::new();
+            for (name, value) in config.client_settings() {
+                client.set(name, value);
+            }
+            let brokers_down = Arc::new(AtomicBool::new(false));
+            let context = BrokerContext {
+                brokers_down: Arc::clone(&brokers_down),
+            };
+            let producer = client.create_with_context(context).map_err(|e| {
+                CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    "Failed to create Kafka producer",
+                )
+                .with_source(e)
+            })?;
+
+            Ok(Self::from_parts(
+                &destination.destination_id,
+                config,
+                RdKafkaProducer(producer),
+                brokers_down,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::output::naming::CollisionPolicy;
+    use crate::capture_engine::output::traits::{OutputMetadata, QualityOfService, RoutingInfo};
+    use crate::capture_engine::output::verification::VerificationConfig;
+    use bytes::Bytes;
+    use futures::future::{ready, Ready};
+    use parking_lot::Mutex;
+    use std::collections::VecDeque;
+
+    /// Partition, headers and payload of a sent record
+    type Sent = (Option<i32>, Vec<(&'static str, String)>, Vec<u8>);
+
+    /// Records every send and reports the queued outcomes in order, then success
+    ///
+    /// The first `queue_full` sends are refused as if the client's queue were full.
+    #[derive(Clone, Default)]
+    struct MockProducer {
+        sent: Arc<Mutex<Vec<Sent>>>,
+        outcomes: Arc<Mutex<VecDeque<Result<(), String>>>>,
+        queue_full: Arc<Mutex<u32>>,
+    }
+
+    impl RecordProducer for MockProducer {
+        type Delivery = Ready<Result<(), String>>;
+
+        fn send(&self, record: KafkaRecord<'_>) -> Result<Self::Delivery, SendError> {
+            let mut queue_full = self.queue_full.lock();
+            if *queue_full > 0 {
+                *queue_full -= 1;
+                return Err(SendError::QueueFull);
+            }
+            self.sent.lock().push((
+                record.partition,
+                record.headers.to_vec(),
+                record.payload.to_vec(),
+            ));
+            Ok(ready(self.outcomes.lock().pop_front().unwrap_or(Ok(()))))
+        }
+    }
+
+    fn writer(backoff: Duration) -> (MockProducer, KafkaWriter<MockProducer>) {
+        let mut config = KafkaConfig::from_destination(&destination(&[
+            ("brokers", "b1:9092"),
+            ("topic", "packets"),
+            ("partition", "round_robin:2"),
+        ]))
+        .unwrap();
+        config.retry = RetryPolicy {
+            max_retries: 2,
+            initial_backoff: backoff,
+            max_backoff: backoff,
+        };
+        let producer = MockProducer::default();
+        let writer = KafkaWriter::with_producer("kafka-1", config, producer.clone());
+        (producer, writer)
+    }
+
+    fn data(fill: u8) -> OutputData {
+        OutputData {
+            data: Bytes::from(vec![fill; 4]),
+            length: Some(1500),
+            metadata: OutputMetadata {
+                timestamp: 1_700_000_000_123_456_789,
+                routing_info: Some(RoutingInfo {
+                    destination_ids: vec!["kafka-1".to_string(), "s3-1".to_string()],
+                }),
+                compression: CompressionAlgorithm::Zstd,
+            },
+        }
+    }
+
+    fn destination(settings: &[(&str, &str)]) -> OutputDestinationConfig {
+        OutputDestinationConfig {
+            destination_id: "kafka-1".to_string(),
+            destination_type: DestinationType::Kafka,
+            settings: settings
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            compression: CompressionConfig::zstd(5),
+            collision_policy: CollisionPolicy::default(),
+            verification: VerificationConfig::default(),
+            qos: QualityOfService::default(),
+        }
+    }
+
+    fn setting<'a>(settings: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        settings
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn test_config_defaults_to_acks_all_with_compression() {
+        let config = KafkaConfig::from_destination(&destination(&[
+            ("brokers", "b1:9092,b2:9092"),
+            ("topic", "packets"),
+        ]))
+        .unwrap();
+        assert_eq!(config.acks, KafkaAcks::All);
+        assert_eq!(config.partition_strategy, PartitionStrategy::Default);
+        assert_eq!(config.max_in_flight, DEFAULT_MAX_IN_FLIGHT);
+
+        let settings = config.client_settings();
+        assert_eq!(
+            setting(&settings, "bootstrap.servers"),
+            Some("b1:9092,b2:9092")
+        );
+        assert_eq!(setting(&settings, "acks"), Some("all"));
+        assert_eq!(setting(&settings, "enable.idempotence"), Some("true"));
+        assert_eq!(setting(&settings, "compression.type"), Some("zstd"));
+        assert_eq!(setting(&settings, "compression.level"), Some("5"));
+        assert_eq!(setting(&settings, "message.timeout.ms"), Some("30000"));
+    }
+
+    #[test]
+    fn test_config_reads_optional_settings_and_overrides() {
+        let config = KafkaConfig::from_destination(&destination(&[
+            ("brokers", "b1:9092"),
+            ("topic", "packets"),
+            ("acks", "leader"),
+            ("partition", "round_robin:4"),
+            ("max_retries", "7"),
+            ("retry_backoff_ms", "20"),
+            ("max_in_flight", "50"),
+            ("kafka.linger.ms", "5"),
+            ("kafka.acks", "all"),
+        ]))
+        .unwrap();
+        assert_eq!(config.acks, KafkaAcks::Leader);
+        assert_eq!(
+            config.partition_strategy,
+            PartitionStrategy::RoundRobin { partitions: 4 }
+        );
+        assert_eq!(config.retry.max_retries, 7);
+        assert_eq!(config.retry.initial_backoff, Duration::from_millis(20));
+        assert_eq!(config.max_in_flight, 50);
+
+        let settings = config.client_settings();
+        assert_eq!(setting(&settings, "linger.ms"), Some("5"));
+        // Explicit client settings win over derived ones
+        assert_eq!(setting(&settings, "acks"), Some("all"));
+        assert_eq!(setting(&settings, "enable.idempotence"), Some("false"));
+    }
+
+    #[test]
+    fn test_config_rejects_invalid_settings() {
+        for settings in [
+            vec![("topic", "packets")],
+            vec![("brokers", "b1:9092")],
+            vec![("brokers", "b1:9092"), ("topic", "t"), ("acks", "0")],
+            vec![
+                ("brokers", "b1:9092"),
+                ("topic", "t"),
+                ("partition", "fixed"),
+            ],
+            vec![
+                ("brokers", "b1:9092"),
+                ("topic", "t"),
+                ("partition", "round_robin:0"),
+            ],
+            vec![
+                ("brokers", "b1:9092"),
+                ("topic", "t"),
+                ("max_retries", "many"),
+            ],
+            vec![
+                ("brokers", "b1:9092"),
+                ("topic", "t"),
+                ("max_in_flight", "0"),
+            ],
+        ] {
+            assert!(
+                KafkaConfig::from_destination(&destination(&settings)).is_err(),
+                "{:?}",
+                settings
+            );
+        }
+
+        let mut not_kafka = destination(&[("brokers", "b1:9092"), ("topic", "t")]);
+        not_kafka.destination_type = DestinationType::S3;
+        assert!(KafkaConfig::from_destination(&not_kafka).is_err());
+    }
+
+    #[test]
+    fn test_partition_selection() {
+        let mut selector = PartitionSelector::new(PartitionStrategy::RoundRobin { partitions: 3 });
+        let partitions: Vec<_> = (0..5).map(|_| selector.next_partition()).collect();
+        assert_eq!(
+            partitions,
+            vec![Some(0), Some(1), Some(2), Some(0), Some(1)]
+        );
+
+        let mut selector = PartitionSelector::new(PartitionStrategy::Fixed(2));
+        assert_eq!(selector.next_partition(), Some(2));
+        let mut selector = PartitionSelector::new(PartitionStrategy::Default);
+        assert_eq!(selector.next_partition(), None);
+    }
+
+    #[test]
+    fn test_tracker_status_follows_delivery_outcomes() {
+        let retry = RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(15),
+        };
+        let mut tracker = DeliveryTracker::new("kafka-1", retry);
+        assert_eq!(tracker.status().status, DestinationState::Active);
+
+        assert_eq!(
+            tracker.failed("timed out", 1),
+            Some(Duration::from_millis(10))
+        );
+        assert_eq!(tracker.status().status, DestinationState::Degraded);
+        assert_eq!(
+            tracker.failed("timed out", 2),
+            Some(Duration::from_millis(15))
+        );
+        assert_eq!(tracker.failed("timed out", 3), None);
+        assert_eq!(tracker.status().status, DestinationState::Failed);
+        assert_eq!(tracker.failed_count(), 1);
+
+        let events = tracker.take_events();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(
+            &events[2],
+            OutputEvent::WriteError(failure) if failure.error.contains("giving up")
+        ));
+
+        tracker.delivered();
+        let status = tracker.status();
+        assert_eq!(status.status, DestinationState::Active);
+        assert!(status.last_error.is_some());
+        assert_eq!(tracker.delivered_count(), 1);
+
+        tracker.unreachable("all brokers are down");
+        assert_eq!(tracker.status().status, DestinationState::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_records_carry_metadata_headers() {
+        let (producer, mut writer) = writer(Duration::from_millis(10));
+        writer.send_batch(&[data(1), data(2)]).await.unwrap();
+        assert_eq!(writer.in_flight(), 0);
+
+        let sent = producer.sent.lock();
+        assert_eq!(sent[0].0, Some(0));
+        assert_eq!(sent[1].0, Some(1));
+        assert_eq!(sent[1].2, vec![2; 4]);
+        assert_eq!(
+            sent[0].1,
+            vec![
+                (TIMESTAMP_HEADER, "1700000000123456789".to_string()),
+                (COMPRESSION_HEADER, "zstd".to_string()),
+                (LENGTH_HEADER, "1500".to_string()),
+                (DESTINATIONS_HEADER, "kafka-1,s3-1".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_records_back_off_independently() {
+        let backoff = Duration::from_millis(100);
+        let (producer, mut writer) = writer(backoff);
+        producer
+            .outcomes
+            .lock()
+            .extend([Err("timed out".to_string()), Err("timed out".to_string())]);
+
+        writer.send_batch(&[data(1), data(2)]).await.unwrap();
+        assert_eq!(writer.in_flight(), 2);
+        assert_eq!(writer.status().status, DestinationState::Degraded);
+
+        // Both backoffs run at once, so the flush takes one backoff rather than two
+        let start = Instant::now();
+        writer.flush_with_mode(FlushMode::Forced).await.unwrap();
+        assert_eq!(start.elapsed(), backoff);
+
+        let sent = producer.sent.lock();
+        let partitions: Vec<_> = sent.iter().map(|(partition, _, _)| *partition).collect();
+        assert_eq!(partitions, vec![Some(0), Some(1), Some(0), Some(1)]);
+        assert_eq!(writer.in_flight(), 0);
+        assert_eq!(writer.status().status, DestinationState::Active);
+        assert_eq!(writer.take_events().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_records_given_up_after_retries() {
+        let (producer, mut writer) = writer(Duration::from_millis(1));
+        producer
+            .outcomes
+            .lock()
+            .extend((0..3).map(|_| Err("not leader".to_string())));
+
+        writer.send_batch(&[data(1)]).await.unwrap();
+        let error = writer.flush_with_mode(FlushMode::Forced).await.unwrap_err();
+        assert!(error.to_string().contains("not leader"), "{}", error);
+        assert_eq!(producer.sent.lock().len(), 3);
+        assert_eq!(writer.status().status, DestinationState::Failed);
+
+        // Emergency flushes give up on records waiting out a backoff
+        producer
+            .outcomes
+            .lock()
+            .push_back(Err("not leader".to_string()));
+        writer.send_batch(&[data(2)]).await.unwrap();
+        assert_eq!(writer.in_flight(), 1);
+        assert!(writer.flush_with_mode(FlushMode::Emergency).await.is_err());
+        assert_eq!(writer.in_flight(), 0);
+        assert_eq!(producer.sent.lock().len(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_full_queue_does_not_spend_retries() {
+        let (producer, mut writer) = writer(Duration::from_millis(10));
+        // More refusals than the two retries the policy allows
+        *producer.queue_full.lock() = 5;
+
+        writer.send_batch(&[data(1)]).await.unwrap();
+        assert_eq!(writer.in_flight(), 1);
+        writer.flush_with_mode(FlushMode::Forced).await.unwrap();
+
+        assert_eq!(producer.sent.lock().len(), 1);
+        assert_eq!(writer.in_flight(), 0);
+        assert_eq!(writer.status().status, DestinationState::Active);
+        assert!(writer.take_events().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sending_waits_while_in_flight_is_full() {
+        let (producer, mut writer) = writer(Duration::from_millis(10));
+        writer.config.max_in_flight = 1;
+        producer
+            .outcomes
+            .lock()
+            .push_back(Err("timed out".to_string()));
+
+        // The second record is only sent once the first has been resent and delivered
+        writer.send_batch(&[data(1), data(2)]).await.unwrap();
+        let payloads: Vec<_> = producer
+            .sent
+            .lock()
+            .iter()
+            .map(|(_, _, payload)| payload[0])
+            .collect();
+        assert_eq!(payloads, vec![1, 1, 2]);
+        assert_eq!(writer.in_flight(), 0);
+    }
+}
@@ -0,0 +1,123 @@
+// output/manifest.rs
+//! Manifest describing how objects written to an output destination were encoded.
+use serde::{Deserialize, Serialize};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::control::audit::AuditEntry;
+use crate::capture_engine::output::compression::CompressionAlgorithm;
+use crate::capture_engine::output::naming::ResolvedKey;
+
+/// Compression applied to objects written to a destination
+///
+/// # Fields
+/// * `algorithm` - Compression algorithm used
+/// * `level` - Compression level used
+/// * `dictionary_id` - Id of the Zstd dictionary readers need, if one was used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionInfo {
+    pub algorithm: CompressionAlgorithm,
+    pub level: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary_id: Option<u32>,
+}
+
+/// Manifest written alongside output objects
+///
+/// # Fields
+/// * `destination_id` - Destination the objects were written to
+/// * `compression` - Compression applied to the objects, None if uncompressed
+/// * `command_audit` - Control-plane commands received while the objects were written
+/// * `objects` - Keys the objects were written to, with any collision handling applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputManifest {
+    pub destination_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command_audit: Vec<AuditEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub objects: Vec<ResolvedKey>,
+}
+
+impl OutputManifest {
+    /// Creates a manifest for uncompressed output
+    ///
+    /// # Arguments
+    /// * `destination_id` - Destination the objects are written to
+    ///
+    /// # Returns
+    /// A new OutputManifest instance
+    pub fn new(destination_id: &str) -> Self {
+        Self {
+            destination_id: destination_id.to_string(),
+            compression: None,
+            command_audit: Vec::new(),
+            objects: Vec::new(),
+        }
+    }
+
+    /// Records the compression applied to the output
+    ///
+    /// # Arguments
+    /// * `info` - Compression information
+    ///
+    /// # Returns
+    /// The updated OutputManifest instance
+    pub fn with_compression(mut self, info: CompressionInfo) -> Self {
+        self.compression = Some(info);
+        self
+    }
+
+    /// Records control-plane commands alongside the output
+    ///
+    /// # Arguments
+    /// * `entries` - Audit entries to include
+    ///
+    /// # Returns
+    /// The updated OutputManifest instance
+    pub fn with_command_audit(mut self, entries: Vec<AuditEntry>) -> Self {
+        self.command_audit = entries;
+        self
+    }
+
+    /// Records the key an object was written to
+    ///
+    /// # Arguments
+    /// * `key` - Resolved key for the object
+    pub fn record_object(&mut self, key: ResolvedKey) {
+        self.objects.push(key);
+    }
+
+    /// Serializes the manifest to JSON
+    ///
+    /// # Returns
+    /// The JSON representation of the manifest
+    pub fn to_json(&self) -> Result<String, CaptureError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+                "Failed to serialize output manifest",
+            )
+            .with_source(e)
+        })
+    }
+
+    /// Parses a manifest from JSON
+    ///
+    /// # Arguments
+    /// * `json` - JSON representation of the manifest
+    ///
+    /// # Returns
+    /// The parsed manifest
+    pub fn from_json(json: &str) -> Result<Self, CaptureError> {
+        serde_json::from_str(json).map_err(|e| {
+            CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::ParseError),
+                "Failed to parse output manifest",
+            )
+            .with_source(e)
+        })
+    }
+}
@@ -0,0 +1,517 @@
+// output/naming.rs
+//! Resolution of output object keys when the target key is already taken.
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, ResourceErrorKind,
+};
+
+/// Default upper bound on sequence suffixes tried before giving up
+pub const DEFAULT_MAX_SEQUENCE: u32 = 10_000;
+
+/// What to do when an object already exists at the target key
+///
+/// # Variants
+/// * `Fail` - Refuse to write
+/// * `AppendSequence` - Write to the first free key with a `-N` suffix
+/// * `Overwrite` - Replace the existing object
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    Fail,
+    #[default]
+    AppendSequence,
+    Overwrite,
+}
+
+/// Create-if-absent claims on a destination's object namespace
+///
+/// Implemented per destination type; local files are created with `create_new`, and S3
+/// destinations write an empty object with `If-None-Match: *`. Checking and creating are one
+/// step, so concurrent writers never claim the same key.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Creates an empty object at `key` unless one already exists
+    ///
+    /// # Returns
+    /// True if the object was created, false if the key was taken
+    async fn create_if_absent(&self, key: &str) -> Result<bool, CaptureError>;
+}
+
+/// Object store backed by a local directory
+///
+/// # Fields
+/// * `root` - Directory keys are resolved against
+#[derive(Debug, Clone)]
+pub struct LocalFileStore {
+    root: PathBuf,
+}
+
+impl LocalFileStore {
+    /// Creates a store rooted at `root`
+    ///
+    /// # Arguments
+    /// * `root` - Directory keys are resolved against
+    ///
+    /// # Returns
+    /// A new LocalFileStore instance
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the filesystem path for a key
+    ///
+    /// # Arguments
+    /// * `key` - Object key
+    ///
+    /// # Returns
+    /// The path the object is stored at
+    pub fn path_for(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalFileStore {
+    async fn create_if_absent(&self, key: &str) -> Result<bool, CaptureError> {
+        let created = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(self.path_for(key))
+            .await;
+        match created {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::NotAvailable),
+                "Failed to create output file",
+            )
+            .with_source(e)),
+        }
+    }
+}
+
+/// In-memory object namespace, useful for destinations that track their own keys
+#[derive(Debug, Default)]
+pub struct InMemoryObjectStore {
+    keys: parking_lot::RwLock<HashSet<String>>,
+}
+
+impl InMemoryObjectStore {
+    /// Marks `key` as taken
+    ///
+    /// # Arguments
+    /// * `key` - Object key
+    pub fn insert(&self, key: &str) {
+        self.keys.write().insert(key.to_string());
+    }
+}
+
+#[async_trait]
+impl ObjectStore for InMemoryObjectStore {
+    async fn create_if_absent(&self, key: &str) -> Result<bool, CaptureError> {
+        Ok(self.keys.write().insert(key.to_string()))
+    }
+}
+
+/// Outcome of resolving a target key
+///
+/// # Fields
+/// * `requested` - Key the writer asked for
+/// * `key` - Key the object is written to
+/// * `collided` - Whether an object already existed at the requested key
+/// * `policy` - Policy applied
+/// * `sequence` - Sequence suffix of `key`, 0 if it has none
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedKey {
+    pub requested: String,
+    pub key: String,
+    pub collided: bool,
+    pub policy: CollisionPolicy,
+    #[serde(default)]
+    pub sequence: u32,
+}
+
+/// Applies a collision policy to target keys
+///
+/// # Fields
+/// * `policy` - Policy to apply on collision
+/// * `max_sequence` - Highest sequence suffix tried under `AppendSequence`
+#[derive(Debug, Clone)]
+pub struct KeyResolver {
+    policy: CollisionPolicy,
+    max_sequence: u32,
+}
+
+impl Default for KeyResolver {
+    fn default() -> Self {
+        Self::new(CollisionPolicy::default())
+    }
+}
+
+impl KeyResolver {
+    /// Creates a resolver for a policy
+    ///
+    /// # Arguments
+    /// * `policy` - Policy to apply on collision
+    ///
+    /// # Returns
+    /// A new KeyResolver instance
+    pub fn new(policy: CollisionPolicy) -> Self {
+        Self {
+            policy,
+            max_sequence: DEFAULT_MAX_SEQUENCE,
+        }
+    }
+
+    /// Sets the highest sequence suffix tried
+    ///
+    /// # Arguments
+    /// * `max_sequence` - Highest suffix
+    ///
+    /// # Returns
+    /// The updated KeyResolver instance
+    pub fn with_max_sequence(mut self, max_sequence: u32) -> Self {
+        self.max_sequence = max_sequence;
+        self
+    }
+
+    /// Returns the policy applied on collision
+    pub fn policy(&self) -> CollisionPolicy {
+        self.policy
+    }
+
+    /// Resolves the key to write an object to
+    ///
+    /// The resolved key is claimed in the store as part of resolving it, so no other writer
+    /// resolves the same key; under `Overwrite` the existing object is left for the writer to
+    /// replace.
+    ///
+    /// # Arguments
+    /// * `store` - Destination namespace to check
+    /// * `requested` - Key the writer asked for
+    ///
+    /// # Returns
+    /// The resolved key, or an error if the policy forbids writing
+    pub async fn resolve(
+        &self,
+        store: &dyn ObjectStore,
+        requested: &str,
+    ) -> Result<ResolvedKey, CaptureError> {
+        self.resolve_sequence(store, requested, 0).await
+    }
+
+    /// Resolves the key of one object in a series named after a base key
+    ///
+    /// Object `sequence` of the series is requested at `base` with a `-N` suffix, or at `base`
+    /// itself for 0. Under `AppendSequence` a collision moves on to the next free suffix, so
+    /// rotated objects keep counting up from existing ones. Keys are claimed as `resolve`
+    /// claims them.
+    ///
+    /// # Arguments
+    /// * `store` - Destination namespace to check
+    /// * `base` - Key the series is named after
+    /// * `sequence` - Sequence suffix requested
+    ///
+    /// # Returns
+    /// The resolved key, or an error if the policy forbids writing
+    pub async fn resolve_sequence(
+        &self,
+        store: &dyn ObjectStore,
+        base: &str,
+        sequence: u32,
+    ) -> Result<ResolvedKey, CaptureError> {
+        for (next, candidate) in self.candidates(base, sequence)? {
+            if store.create_if_absent(&candidate).await? {
+                return Ok(self.resolved(base, sequence, candidate, next));
+            }
+        }
+        self.collided(base, sequence)
+    }
+
+    /// Resolves the key of one object in a series with a blocking create-if-absent call
+    ///
+    /// Behaves as `resolve_sequence`, for writers that create objects synchronously.
+    ///
+    /// # Arguments
+    /// * `base` - Key the series is named after
+    /// * `sequence` - Sequence suffix requested
+    /// * `create_if_absent` - Creates an object at a key unless one exists, returning whether
+    ///   it was created
+    ///
+    /// # Returns
+    /// The resolved key, or an error if the policy forbids writing
+    pub fn resolve_sequence_with(
+        &self,
+        base: &str,
+        sequence: u32,
+        mut create_if_absent: impl FnMut(&str) -> Result<bool, CaptureError>,
+    ) -> Result<ResolvedKey, CaptureError> {
+        for (next, candidate) in self.candidates(base, sequence)? {
+            if create_if_absent(&candidate)? {
+                return Ok(self.resolved(base, sequence, candidate, next));
+            }
+        }
+        self.collided(base, sequence)
+    }
+
+    /// Lists the sequence numbers and keys claimed in turn for an object of a series
+    fn candidates(
+        &self,
+        base: &str,
+        sequence: u32,
+    ) -> Result<impl Iterator<Item = (u32, String)>, CaptureError> {
+        if base.is_empty() {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Output object key must not be empty",
+            ));
+        }
+        let last = match self.policy {
+            CollisionPolicy::AppendSequence => self.max_sequence.max(sequence),
+            CollisionPolicy::Fail | CollisionPolicy::Overwrite => sequence,
+        };
+        let base = base.to_string();
+        Ok((sequence..=last).map(move |next| (next, series_key(&base, next))))
+    }
+
+    fn resolved(&self, base: &str, requested: u32, key: String, sequence: u32) -> ResolvedKey {
+        ResolvedKey {
+            requested: series_key(base, requested),
+            key,
+            collided: sequence != requested,
+            policy: self.policy,
+            sequence,
+        }
+    }
+
+    /// Applies the policy once every candidate key was taken
+    fn collided(&self, base: &str, sequence: u32) -> Result<ResolvedKey, CaptureError> {
+        let requested = series_key(base, sequence);
+        match self.policy {
+            CollisionPolicy::Fail => Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::InvalidState),
+                &format!("Output object already exists at key {}", requested),
+            )),
+            CollisionPolicy::Overwrite => Ok(ResolvedKey {
+                key: requested.clone(),
+                requested,
+                collided: true,
+                policy: self.policy,
+                sequence,
+            }),
+            CollisionPolicy::AppendSequence => Err(*CaptureError::new(
+                CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded),
+                &format!("No free sequence suffix for output key {}", base),
+            )),
+        }
+    }
+}
+
+/// Gets the key of object `sequence` in a series, the base key itself for 0
+fn series_key(base: &str, sequence: u32) -> String {
+    match sequence {
+        0 => base.to_string(),
+        n => sequenced_key(base, n),
+    }
+}
+
+/// Inserts a `-N` suffix before the extension of the key's final segment
+///
+/// # Arguments
+/// * `key` - Original key
+/// * `sequence` - Suffix number
+///
+/// # Returns
+/// The suffixed key, e.g. `dir/capture-2.pcap` for `dir/capture.pcap`
+pub fn sequenced_key(key: &str, sequence: u32) -> String {
+    let name_start = key.rfind('/').map_or(0, |i| i + 1);
+    match key[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let split = name_start + dot;
+            format!("{}-{}{}", &key[..split], sequence, &key[split..])
+        }
+        _ => format!("{}-{}", key, sequence),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::output::manifest::OutputManifest;
+
+    fn store_with(keys: &[&str]) -> InMemoryObjectStore {
+        let store = InMemoryObjectStore::default();
+        for key in keys {
+            store.insert(key);
+        }
+        store
+    }
+
+    #[test]
+    fn test_default_policy_does_not_overwrite() {
+        assert_eq!(CollisionPolicy::default(), CollisionPolicy::AppendSequence);
+    }
+
+    #[test]
+    fn test_sequenced_key() {
+        assert_eq!(sequenced_key("a/capture.pcap", 2), "a/capture-2.pcap");
+        assert_eq!(sequenced_key("a.b/capture", 1), "a.b/capture-1");
+        assert_eq!(sequenced_key(".hidden", 1), ".hidden-1");
+        assert_eq!(sequenced_key("x.tar.zst", 3), "x.tar-3.zst");
+    }
+
+    #[tokio::test]
+    async fn test_no_collision_uses_requested_key() {
+        let store = store_with(&[]);
+        let resolved = KeyResolver::new(CollisionPolicy::Fail)
+            .resolve(&store, "out/a.pcap")
+            .await
+            .unwrap();
+        assert_eq!(resolved.key, "out/a.pcap");
+        assert!(!resolved.collided);
+    }
+
+    #[tokio::test]
+    async fn test_fail_policy_rejects_existing_key() {
+        let store = store_with(&["out/a.pcap"]);
+        let err = KeyResolver::new(CollisionPolicy::Fail)
+            .resolve(&store, "out/a.pcap")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::InvalidState)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_append_sequence_skips_taken_suffixes() {
+        let store = store_with(&["out/a.pcap", "out/a-1.pcap"]);
+        let resolved = KeyResolver::new(CollisionPolicy::AppendSequence)
+            .resolve(&store, "out/a.pcap")
+            .await
+            .unwrap();
+        assert_eq!(resolved.key, "out/a-2.pcap");
+        assert_eq!(resolved.requested, "out/a.pcap");
+        assert_eq!(resolved.sequence, 2);
+        assert!(resolved.collided);
+    }
+
+    #[tokio::test]
+    async fn test_sequence_continues_from_requested_suffix() {
+        let store = store_with(&["out/a-1.pcap", "out/a-2.pcap"]);
+        let resolved = KeyResolver::new(CollisionPolicy::AppendSequence)
+            .resolve_sequence(&store, "out/a.pcap", 1)
+            .await
+            .unwrap();
+        assert_eq!(resolved.requested, "out/a-1.pcap");
+        assert_eq!(resolved.key, "out/a-3.pcap");
+        assert_eq!(resolved.sequence, 3);
+    }
+
+    #[tokio::test]
+    async fn test_append_sequence_is_bounded() {
+        let store = store_with(&["a", "a-1", "a-2"]);
+        let err = KeyResolver::new(CollisionPolicy::AppendSequence)
+            .with_max_sequence(2)
+            .resolve(&store, "a")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::QuotaExceeded)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_policy_keeps_key() {
+        let store = store_with(&["out/a.pcap"]);
+        let resolved = KeyResolver::new(CollisionPolicy::Overwrite)
+            .resolve(&store, "out/a.pcap")
+            .await
+            .unwrap();
+        assert_eq!(resolved.key, "out/a.pcap");
+        assert!(resolved.collided);
+    }
+
+    #[tokio::test]
+    async fn test_resolved_key_is_recorded_in_manifest() {
+        let store = store_with(&["out/a.pcap"]);
+        let resolved = KeyResolver::default()
+            .resolve(&store, "out/a.pcap")
+            .await
+            .unwrap();
+
+        let mut manifest = OutputManifest::new("local");
+        manifest.record_object(resolved.clone());
+        let parsed = OutputManifest::from_json(&manifest.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.objects, vec![resolved]);
+    }
+
+    #[tokio::test]
+    async fn test_local_file_store_detects_existing_file() {
+        let dir = std::env::temp_dir().join(format!("sparktrap-naming-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("capture.pcap"), b"existing").unwrap();
+        let store = LocalFileStore::new(&dir);
+
+        let resolved = KeyResolver::default()
+            .resolve(&store, "capture.pcap")
+            .await
+            .unwrap();
+        assert_eq!(resolved.key, "capture-1.pcap");
+        // Resolving claimed the key, leaving the existing file untouched
+        assert_eq!(
+            std::fs::read(dir.join("capture.pcap")).unwrap(),
+            b"existing"
+        );
+        assert!(dir.join("capture-1.pcap").exists());
+        assert!(!store.create_if_absent("capture-1.pcap").await.unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_resolutions_claim_distinct_keys() {
+        let store = std::sync::Arc::new(store_with(&["out/a.pcap"]));
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    KeyResolver::default()
+                        .resolve(store.as_ref(), "out/a.pcap")
+                        .await
+                        .unwrap()
+                        .key
+                })
+            })
+            .collect();
+
+        let mut keys = HashSet::new();
+        for task in tasks {
+            assert!(keys.insert(task.await.unwrap()));
+        }
+        assert_eq!(keys.len(), 16);
+        assert!(!keys.contains("out/a.pcap"));
+    }
+
+    #[test]
+    fn test_blocking_resolution_matches_store_resolution() {
+        let mut taken = HashSet::from(["a.pcap".to_string(), "a-2.pcap".to_string()]);
+        let resolver = KeyResolver::new(CollisionPolicy::AppendSequence);
+        let first = resolver
+            .resolve_sequence_with("a.pcap", 0, |key| Ok(taken.insert(key.to_string())))
+            .unwrap();
+        assert_eq!((first.key.as_str(), first.sequence), ("a-1.pcap", 1));
+        let second = resolver
+            .resolve_sequence_with("a.pcap", 2, |key| Ok(taken.insert(key.to_string())))
+            .unwrap();
+        assert_eq!((second.key.as_str(), second.sequence), ("a-3.pcap", 3));
+        assert!(second.collided);
+    }
+}
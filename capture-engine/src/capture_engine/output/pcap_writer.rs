@@ -0,0 +1,913 @@
+// output/pcap_writer.rs
+//! Capture file output in libpcap and PCAPNG formats.
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, SystemErrorKind,
+};
+use crate::capture_engine::output::compression::CompressionAlgorithm;
+use crate::capture_engine::output::naming::KeyResolver;
+use crate::capture_engine::output::traits::{
+    DestinationType, OutputData, OutputDestinationConfig, OutputEvent, RotationConfig,
+    RotationTrigger,
+};
+use crate::capture_engine::output::verification::{
+    ContentDigest, ContentHasher, VerificationMethod, VerificationOutcome,
+};
+
+/// Largest snap length written; zero in the configuration selects it
+pub const MAX_SNAPLEN: u32 = 262_144;
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAP_HEADER_LEN: u64 = 24;
+const PCAP_RECORD_HEADER_LEN: u64 = 16;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const PCAPNG_OPT_ENDOFOPT: u16 = 0;
+const PCAPNG_OPT_IF_TSRESOL: u16 = 9;
+
+/// Resolution of record timestamps in a capture file
+///
+/// # Variants
+/// * `Microseconds` - Classic libpcap resolution, understood by every reader
+/// * `Nanoseconds` - Full resolution of capture timestamps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampResolution {
+    #[default]
+    Microseconds,
+    Nanoseconds,
+}
+
+impl TimestampResolution {
+    /// Converts a nanosecond timestamp to units of this resolution
+    fn units(&self, timestamp_ns: u64) -> u64 {
+        match self {
+            TimestampResolution::Microseconds => timestamp_ns / 1_000,
+            TimestampResolution::Nanoseconds => timestamp_ns,
+        }
+    }
+
+    /// Gets the number of units per second
+    fn per_second(&self) -> u64 {
+        match self {
+            TimestampResolution::Microseconds => 1_000_000,
+            TimestampResolution::Nanoseconds => 1_000_000_000,
+        }
+    }
+}
+
+/// Layout of a capture file
+///
+/// # Variants
+/// * `Pcap` - Classic libpcap format
+/// * `PcapNg` - PCAPNG with one section and one interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFileFormat {
+    Pcap,
+    PcapNg,
+}
+
+/// Writes output data as capture files that packet analyzers can open
+///
+/// Files are written little-endian; readers detect byte order from the magic number, so the
+/// output is portable. Each `OutputData` becomes one record stamped with
+/// `OutputMetadata::timestamp` (nanoseconds since the epoch) and cut to the snap length, with
+/// the untruncated length kept as the original length. Zero-length data is written as an empty
+/// record rather than skipped. Records must hold raw frames; compressed records are rejected.
+///
+/// The path of each file is resolved under the destination's collision policy. Files are created
+/// with `create_new`, so an existing file is only replaced under `Overwrite`, and concurrent
+/// writers never pick the same path.
+///
+/// With verification enabled, each file is read back when it is closed and compared with the
+/// hash of the bytes written to it; a mismatch queues a `WriteError` event.
+///
+/// When a `RotationConfig` limit is reached the current file is closed and the next record
+/// starts a new file named with a `-N` suffix, queuing a `RotationTriggered` event. Duration
+/// limits are measured on packet timestamps, so replayed captures rotate the same way as live
+/// ones.
+///
+/// # Fields
+/// * `destination_id` - Destination the events belong to
+/// * `path` - Path of the first file
+/// * `format` - Capture file layout
+/// * `snaplen` - Maximum bytes written per record
+/// * `link_type` - Link-layer header type of the records
+/// * `resolution` - Timestamp resolution of the records
+/// * `rotation` - Limits that start a new file
+/// * `resolver` - Collision policy applied to file paths
+/// * `verify` - Read back each file when it is closed
+/// * `file` - File being written, if one is open
+/// * `hasher` - Hash of the bytes written to the current file
+/// * `files` - Paths of every file opened, in order
+/// * `next_sequence` - Sequence suffix requested for the next file
+/// * `bytes` - Bytes written to the current file
+/// * `packets` - Records written to the current file
+/// * `first_timestamp` - Timestamp of the first record in the current file
+/// * `events` - Events not yet taken by the caller
+pub struct PcapWriter {
+    destination_id: String,
+    path: PathBuf,
+    format: CaptureFileFormat,
+    snaplen: u32,
+    link_type: u32,
+    resolution: TimestampResolution,
+    rotation: RotationConfig,
+    resolver: KeyResolver,
+    verify: bool,
+    file: Option<BufWriter<File>>,
+    hasher: ContentHasher,
+    files: Vec<PathBuf>,
+    next_sequence: u32,
+    bytes: u64,
+    packets: u64,
+    first_timestamp: Option<u64>,
+    events: Vec<OutputEvent>,
+}
+
+impl PcapWriter {
+    /// Creates a writer for a capture file destination
+    ///
+    /// # Arguments
+    /// * `destination` - A destination of type `Pcap` or `PcapNg`
+    /// * `rotation` - Limits that start a new file
+    ///
+    /// # Returns
+    /// A new PcapWriter instance, or an error for other destination types or if verification
+    /// asks for rewrites. No file is created until the first record is written or the writer
+    /// is flushed.
+    pub fn new(
+        destination: &OutputDestinationConfig,
+        rotation: RotationConfig,
+    ) -> Result<Self, CaptureError> {
+        let (format, path, snaplen, link_type, resolution) = match &destination.destination_type {
+            DestinationType::Pcap {
+                path,
+                snaplen,
+                link_type,
+                resolution,
+            } => (
+                CaptureFileFormat::Pcap,
+                path,
+                snaplen,
+                link_type,
+                resolution,
+            ),
+            DestinationType::PcapNg {
+                path,
+                snaplen,
+                link_type,
+                resolution,
+            } => (
+                CaptureFileFormat::PcapNg,
+                path,
+                snaplen,
+                link_type,
+                resolution,
+            ),
+            _ => {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    "Destination is not a capture file",
+                ))
+            }
+        };
+        if format == CaptureFileFormat::PcapNg && *link_type > u16::MAX as u32 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "PCAPNG link type must fit in 16 bits",
+            ));
+        }
+        if destination.verification.rewrite_on_mismatch {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Capture file output cannot rewrite files on verification mismatch",
+            ));
+        }
+
+        Ok(Self {
+            destination_id: destination.destination_id.clone(),
+            path: path.clone(),
+            format,
+            snaplen: match *snaplen {
+                0 => MAX_SNAPLEN,
+                len => len.min(MAX_SNAPLEN),
+            },
+            link_type: *link_type,
+            resolution: *resolution,
+            rotation,
+            resolver: KeyResolver::new(destination.collision_policy),
+            verify: destination.verification.enabled,
+            file: None,
+            hasher: ContentHasher::new(),
+            files: Vec::new(),
+            next_sequence: 0,
+            bytes: 0,
+            packets: 0,
+            first_timestamp: None,
+            events: Vec::new(),
+        })
+    }
+
+    /// Gets the snap length applied to records
+    pub fn snaplen(&self) -> u32 {
+        self.snaplen
+    }
+
+    /// Gets the paths of every file opened, in order
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Takes the events queued since the last call
+    pub fn take_events(&mut self) -> Vec<OutputEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Writes one record
+    ///
+    /// # Arguments
+    /// * `data` - Packet bytes and their capture timestamp
+    ///
+    /// # Returns
+    /// An error if the record is compressed, or the file could not be opened or written
+    pub fn write(&mut self, data: &OutputData) -> Result<(), CaptureError> {
+        if data.metadata.compression != CompressionAlgorithm::None {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                &format!(
+                    "Capture files hold raw frames, not {:?} compressed records",
+                    data.metadata.compression
+                ),
+            ));
+        }
+        let timestamp = data.metadata.timestamp;
+        if self.file.is_some() {
+            let span = Duration::from_nanos(
+                timestamp.saturating_sub(self.first_timestamp.unwrap_or(timestamp)),
+            );
+            if let Some(reason) = self
+                .rotation
+                .rotation_reason(self.bytes, self.packets, span)
+            {
+                self.close()?;
+                self.open_next()?;
+                let path = self.files.last().expect("a file was just opened");
+                self.events
+                    .push(OutputEvent::RotationTriggered(RotationTrigger {
+                        reason: format!("{}; now writing {}", reason, path.display()),
+                    }));
+            }
+        }
+        if self.file.is_none() {
+            self.open_next()?;
+        }
+
+        let captured = data.data.len().min(self.snaplen as usize);
+        let record = self.encode_record(timestamp, &data.data[..captured], data.wire_length());
+        self.emit(&record)?;
+        self.packets += 1;
+        self.first_timestamp.get_or_insert(timestamp);
+        Ok(())
+    }
+
+    /// Writes records in order
+    ///
+    /// # Arguments
+    /// * `batch` - Records to write
+    ///
+    /// # Returns
+    /// An error at the first record that could not be written
+    pub fn write_batch(&mut self, batch: &[OutputData]) -> Result<(), CaptureError> {
+        batch.iter().try_for_each(|data| self.write(data))
+    }
+
+    /// Flushes buffered records, creating the file with only its header if nothing was written
+    pub fn flush(&mut self) -> Result<(), CaptureError> {
+        if self.file.is_none() && self.files.is_empty() {
+            self.open_next()?;
+        }
+        match self.file.as_mut() {
+            Some(file) => file.flush().map_err(io_error),
+            None => Ok(()),
+        }
+    }
+
+    /// Flushes and closes the current file
+    ///
+    /// # Returns
+    /// The paths of every file written, or an error if a file failed verification and its
+    /// `WriteError` event was not taken
+    pub fn finish(mut self) -> Result<Vec<PathBuf>, CaptureError> {
+        self.flush()?;
+        self.close()?;
+        let failure = self.events.iter().find_map(|event| match event {
+            OutputEvent::WriteError(failure) => Some(failure),
+            _ => None,
+        });
+        if let Some(failure) = failure {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::System(SystemErrorKind::IoError),
+                &failure.error,
+            ));
+        }
+        Ok(self.files)
+    }
+
+    fn close(&mut self) -> Result<(), CaptureError> {
+        let Some(mut file) = self.file.take() else {
+            return Ok(());
+        };
+        file.flush().map_err(io_error)?;
+        drop(file);
+
+        let expected = std::mem::take(&mut self.hasher).finish();
+        if self.verify {
+            let path = self.files.last().expect("a closed file was opened");
+            if let Some(event) =
+                verify_file(path, expected)?.to_event(&self.destination_id, &path.to_string_lossy())
+            {
+                self.events.push(event);
+            }
+        }
+        Ok(())
+    }
+
+    fn open_next(&mut self) -> Result<(), CaptureError> {
+        let mut created = None;
+        let resolved = self.resolver.resolve_sequence_with(
+            &self.path.to_string_lossy(),
+            self.next_sequence,
+            |path| match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(file) => {
+                    created = Some(file);
+                    Ok(true)
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+                Err(e) => Err(io_error(e)),
+            },
+        )?;
+        let path = PathBuf::from(&resolved.key);
+        // Only `Overwrite` resolves to a file that already existed
+        let file = match created {
+            Some(file) => file,
+            None => OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .open(&path)
+                .map_err(io_error)?,
+        };
+        self.next_sequence = resolved.sequence.saturating_add(1);
+        self.file = Some(BufWriter::new(file));
+        self.files.push(path);
+        self.bytes = 0;
+        self.packets = 0;
+        self.first_timestamp = None;
+
+        let header = match self.format {
+            CaptureFileFormat::Pcap => self.pcap_header(),
+            CaptureFileFormat::PcapNg => self.pcapng_header(),
+        };
+        self.emit(&header)
+    }
+
+    fn emit(&mut self, bytes: &[u8]) -> Result<(), CaptureError> {
+        let file = self.file.as_mut().expect("emit requires an open file");
+        file.write_all(bytes).map_err(io_error)?;
+        self.hasher.update(bytes);
+        self.bytes += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn pcap_header(&self) -> Vec<u8> {
+        let magic = match self.resolution {
+            TimestampResolution::Microseconds => PCAP_MAGIC_MICROS,
+            TimestampResolution::Nanoseconds => PCAP_MAGIC_NANOS,
+        };
+        let mut header = Vec::with_capacity(PCAP_HEADER_LEN as usize);
+        header.extend_from_slice(&magic.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        // thiszone and sigfigs are always zero
+        header.extend_from_slice(&[0u8; 8]);
+        header.extend_from_slice(&self.snaplen.to_le_bytes());
+        header.extend_from_slice(&self.link_type.to_le_bytes());
+        header
+    }
+
+    fn pcapng_header(&self) -> Vec<u8> {
+        let mut section = Vec::new();
+        section.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+        section.extend_from_slice(&1u16.to_le_bytes());
+        section.extend_from_slice(&0u16.to_le_bytes());
+        // Section length is unspecified so the file can be written in one pass
+        section.extend_from_slice(&(-1i64).to_le_bytes());
+        let mut header = pcapng_block(PCAPNG_SECTION_HEADER, &section);
+
+        let mut interface = Vec::new();
+        interface.extend_from_slice(&(self.link_type as u16).to_le_bytes());
+        interface.extend_from_slice(&0u16.to_le_bytes());
+        interface.extend_from_slice(&self.snaplen.to_le_bytes());
+        if self.resolution == TimestampResolution::Nanoseconds {
+            interface.extend_from_slice(&PCAPNG_OPT_IF_TSRESOL.to_le_bytes());
+            interface.extend_from_slice(&1u16.to_le_bytes());
+            interface.extend_from_slice(&[9, 0, 0, 0]);
+            interface.extend_from_slice(&PCAPNG_OPT_ENDOFOPT.to_le_bytes());
+            interface.extend_from_slice(&0u16.to_le_bytes());
+        }
+        header.extend(pcapng_block(PCAPNG_INTERFACE_DESCRIPTION, &interface));
+        header
+    }
+
+    fn encode_record(&self, timestamp_ns: u64, captured: &[u8], original: usize) -> Vec<u8> {
+        let units = self.resolution.units(timestamp_ns);
+        let captured_len = captured.len() as u32;
+        let original_len = original.min(u32::MAX as usize) as u32;
+        match self.format {
+            CaptureFileFormat::Pcap => {
+                let per_second = self.resolution.per_second();
+                let mut record =
+                    Vec::with_capacity(PCAP_RECORD_HEADER_LEN as usize + captured.len());
+                record.extend_from_slice(&((units / per_second) as u32).to_le_bytes());
+                record.extend_from_slice(&((units % per_second) as u32).to_le_bytes());
+                record.extend_from_slice(&captured_len.to_le_bytes());
+                record.extend_from_slice(&original_len.to_le_bytes());
+                record.extend_from_slice(captured);
+                record
+            }
+            CaptureFileFormat::PcapNg => {
+                let mut body = Vec::with_capacity(20 + captured.len() + 3);
+                body.extend_from_slice(&0u32.to_le_bytes());
+                body.extend_from_slice(&((units >> 32) as u32).to_le_bytes());
+                body.extend_from_slice(&(units as u32).to_le_bytes());
+                body.extend_from_slice(&captured_len.to_le_bytes());
+                body.extend_from_slice(&original_len.to_le_bytes());
+                body.extend_from_slice(captured);
+                pcapng_block(PCAPNG_ENHANCED_PACKET, &body)
+            }
+        }
+    }
+}
+
+/// Frames a PCAPNG block body, padding it to 32 bits
+fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let padded = body.len().div_ceil(4) * 4;
+    let total = (12 + padded) as u32;
+    let mut block = Vec::with_capacity(total as usize);
+    block.extend_from_slice(&block_type.to_le_bytes());
+    block.extend_from_slice(&total.to_le_bytes());
+    block.extend_from_slice(body);
+    block.resize(8 + padded, 0);
+    block.extend_from_slice(&total.to_le_bytes());
+    block
+}
+
+/// Reads a closed file back, hashing it as it streams in
+fn verify_file(path: &Path, expected: ContentDigest) -> Result<VerificationOutcome, CaptureError> {
+    let mut hasher = ContentHasher::new();
+    File::open(path)
+        .and_then(|mut file| std::io::copy(&mut file, &mut hasher))
+        .map_err(io_error)?;
+    let actual = hasher.finish();
+    Ok(if actual == expected {
+        VerificationOutcome::Verified {
+            method: VerificationMethod::Readback,
+            rewrites: 0,
+        }
+    } else {
+        VerificationOutcome::Mismatch {
+            expected,
+            actual,
+            rewrites: 0,
+        }
+    })
+}
+
+fn io_error(e: std::io::Error) -> CaptureError {
+    CaptureError::new(
+        CaptureErrorKind::System(SystemErrorKind::IoError),
+        "Failed to write capture file",
+    )
+    .with_source(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::ResourceErrorKind;
+    use crate::capture_engine::interface::pcap_file::PcapFileSource;
+    use crate::capture_engine::interface::source::PacketSource;
+    use crate::capture_engine::output::compression::{CompressionAlgorithm, CompressionConfig};
+    use crate::capture_engine::output::naming::CollisionPolicy;
+    use crate::capture_engine::output::traits::{OutputMetadata, QualityOfService};
+    use crate::capture_engine::output::verification::VerificationConfig;
+    use crate::traits::{BufferId, Packet, PacketMetadata};
+    use bytes::Bytes;
+    use std::collections::HashMap;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sparktrap-pcap-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn output(data: &[u8], timestamp: u64) -> OutputData {
+        OutputData {
+            data: Bytes::copy_from_slice(data),
+            length: None,
+            metadata: OutputMetadata {
+                timestamp,
+                routing_info: None,
+                compression: CompressionAlgorithm::None,
+            },
+        }
+    }
+
+    fn destination(destination_type: DestinationType) -> OutputDestinationConfig {
+        OutputDestinationConfig {
+            destination_id: "pcap".to_string(),
+            destination_type,
+            settings: HashMap::new(),
+            compression: CompressionConfig::default(),
+            collision_policy: CollisionPolicy::default(),
+            verification: VerificationConfig::default(),
+            qos: QualityOfService::default(),
+        }
+    }
+
+    fn pcap(path: &Path, snaplen: u32, resolution: TimestampResolution) -> OutputDestinationConfig {
+        destination(DestinationType::Pcap {
+            path: path.to_path_buf(),
+            snaplen,
+            link_type: 1,
+            resolution,
+        })
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_pcap_readable_with_truncation() {
+        let dir = temp_dir();
+        let path = dir.join("capture.pcap");
+        let mut writer = PcapWriter::new(
+            &pcap(&path, 64, TimestampResolution::Nanoseconds),
+            RotationConfig::default(),
+        )
+        .unwrap();
+        writer
+            .write_batch(&[
+                output(&[1; 60], 1_000_000_123),
+                output(&[2; 1500], 1_000_000_456),
+                output(&[], 2_000_000_000),
+            ])
+            .unwrap();
+        assert_eq!(writer.finish().unwrap(), vec![path.clone()]);
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..4], &[0x4d, 0x3c, 0xb2, 0xa1]);
+        assert_eq!(u32_at(&bytes, 16), 64);
+
+        let mut source = PcapFileSource::new(&path);
+        source.open().unwrap();
+        assert_eq!(source.link_type(), Some(1));
+        let batch = source.poll_capture_batch(16).unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.truncated, 1);
+        assert_eq!(batch.packets[0].timestamp, 1_000_000_123);
+        assert_eq!(batch.packets[1].data, &[2u8; 64][..]);
+        assert!(batch.packets[2].data.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_packet_keeps_wire_length() {
+        let dir = temp_dir();
+        let path = dir.join("snaplen.pcap");
+        let mut writer = PcapWriter::new(
+            &pcap(&path, 0, TimestampResolution::Nanoseconds),
+            RotationConfig::default(),
+        )
+        .unwrap();
+        let packet = Packet {
+            timestamp: 5,
+            data: &[3; 64],
+            length: 1500,
+            metadata: PacketMetadata {
+                compact_data: 0,
+                additional_info: Default::default(),
+            },
+            buffer_id: BufferId::new(0),
+        };
+        writer.write(&OutputData::from_packet(&packet)).unwrap();
+        writer.finish().unwrap();
+
+        let mut source = PcapFileSource::new(&path);
+        source.open().unwrap();
+        let batch = source.poll_capture_batch(4).unwrap();
+        assert_eq!(batch.truncated, 1);
+        assert_eq!(batch.packets[0].data.len(), 64);
+        assert_eq!(batch.packets[0].length, 1500);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_microsecond_resolution() {
+        let dir = temp_dir();
+        let path = dir.join("micros.pcap");
+        let mut writer = PcapWriter::new(
+            &pcap(&path, 0, TimestampResolution::Microseconds),
+            RotationConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(writer.snaplen(), MAX_SNAPLEN);
+        writer.write(&output(&[7; 10], 3_250_000_999)).unwrap();
+        writer.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(u32_at(&bytes, 0), PCAP_MAGIC_MICROS);
+        assert_eq!(u32_at(&bytes, 24), 3);
+        assert_eq!(u32_at(&bytes, 28), 250_000);
+
+        let mut source = PcapFileSource::new(&path);
+        source.open().unwrap();
+        assert_eq!(source.poll_batch(4).unwrap()[0].timestamp, 3_250_000_000);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_empty_capture_has_header() {
+        let dir = temp_dir();
+        let path = dir.join("empty.pcap");
+        let writer = PcapWriter::new(
+            &pcap(&path, 65535, TimestampResolution::Microseconds),
+            RotationConfig::default(),
+        )
+        .unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), PCAP_HEADER_LEN);
+        let mut source = PcapFileSource::new(&path);
+        source.open().unwrap();
+        assert!(source.poll_batch(4).unwrap().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_opens_sequenced_files() {
+        let dir = temp_dir();
+        let path = dir.join("capture.pcap");
+        let rotation = RotationConfig {
+            max_packets: Some(2),
+            ..Default::default()
+        };
+        let mut writer = PcapWriter::new(
+            &pcap(&path, 65535, TimestampResolution::Microseconds),
+            rotation,
+        )
+        .unwrap();
+        for i in 0..5u8 {
+            writer.write(&output(&[i; 20], 1_000 * i as u64)).unwrap();
+        }
+
+        let events = writer.take_events();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0],
+            OutputEvent::RotationTriggered(trigger) if trigger.reason.contains("capture-1.pcap")
+        ));
+        let files = writer.finish().unwrap();
+        assert_eq!(
+            files,
+            vec![
+                path.clone(),
+                dir.join("capture-1.pcap"),
+                dir.join("capture-2.pcap")
+            ]
+        );
+
+        let counts: Vec<_> = files
+            .iter()
+            .map(|file| {
+                let mut source = PcapFileSource::new(file);
+                source.open().unwrap();
+                source.poll_batch(16).unwrap().len()
+            })
+            .collect();
+        assert_eq!(counts, vec![2, 2, 1]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_pcapng_blocks() {
+        let dir = temp_dir();
+        let path = dir.join("capture.pcapng");
+        let pcapng = destination(DestinationType::PcapNg {
+            path: path.clone(),
+            snaplen: 4,
+            link_type: 1,
+            resolution: TimestampResolution::Nanoseconds,
+        });
+        let mut writer = PcapWriter::new(&pcapng, RotationConfig::default()).unwrap();
+        let timestamp = 5_000_000_000_123;
+        writer
+            .write(&output(&[1, 2, 3, 4, 5, 6], timestamp))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(u32_at(&bytes, 0), PCAPNG_SECTION_HEADER);
+        assert_eq!(u32_at(&bytes, 8), PCAPNG_BYTE_ORDER_MAGIC);
+        let section_len = u32_at(&bytes, 4) as usize;
+        assert_eq!(u32_at(&bytes, section_len - 4) as usize, section_len);
+
+        let interface = &bytes[section_len..];
+        assert_eq!(u32_at(interface, 0), PCAPNG_INTERFACE_DESCRIPTION);
+        assert_eq!(u32_at(interface, 12), 4);
+        // if_tsresol option selecting nanoseconds
+        assert_eq!(&interface[16..21], &[9, 0, 1, 0, 9]);
+
+        let packet = &interface[u32_at(interface, 4) as usize..];
+        assert_eq!(u32_at(packet, 0), PCAPNG_ENHANCED_PACKET);
+        assert_eq!(u32_at(packet, 4), 36);
+        let high = u32_at(packet, 12) as u64;
+        let low = u32_at(packet, 16) as u64;
+        assert_eq!((high << 32) | low, timestamp);
+        assert_eq!((u32_at(packet, 20), u32_at(packet, 24)), (4, 6));
+        assert_eq!(&packet[28..32], &[1, 2, 3, 4]);
+        assert_eq!(u32_at(packet, 32), 36);
+        assert_eq!(packet.len(), 36);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_existing_file_not_truncated() {
+        let dir = temp_dir();
+        let path = dir.join("capture.pcap");
+        std::fs::write(dir.join("capture-1.pcap"), b"earlier capture").unwrap();
+        let mut config = pcap(&path, 0, TimestampResolution::Nanoseconds);
+        config.collision_policy = CollisionPolicy::Fail;
+        let mut writer = PcapWriter::new(
+            &config,
+            RotationConfig {
+                max_packets: Some(1),
+                ..RotationConfig::default()
+            },
+        )
+        .unwrap();
+        writer.write(&output(&[1; 10], 1)).unwrap();
+
+        // Rotating onto the existing capture-1.pcap fails instead of replacing it
+        let err = writer.write(&output(&[2; 10], 2)).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::InvalidState)
+        ));
+        assert_eq!(
+            std::fs::read(dir.join("capture-1.pcap")).unwrap(),
+            b"earlier capture"
+        );
+
+        let mut again = PcapWriter::new(&config, RotationConfig::default()).unwrap();
+        assert!(again.write(&output(&[3; 10], 3)).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_sequence_skips_existing_files() {
+        let dir = temp_dir();
+        let path = dir.join("capture.pcap");
+        std::fs::write(&path, b"earlier capture").unwrap();
+        std::fs::write(dir.join("capture-2.pcap"), b"earlier capture").unwrap();
+        let mut writer = PcapWriter::new(
+            &pcap(&path, 0, TimestampResolution::Nanoseconds),
+            RotationConfig {
+                max_packets: Some(1),
+                ..RotationConfig::default()
+            },
+        )
+        .unwrap();
+        writer.write(&output(&[1; 10], 1)).unwrap();
+        writer.write(&output(&[2; 10], 2)).unwrap();
+
+        let files = writer.finish().unwrap();
+        assert_eq!(
+            files,
+            vec![dir.join("capture-1.pcap"), dir.join("capture-3.pcap")]
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"earlier capture");
+        assert_eq!(
+            std::fs::read(dir.join("capture-2.pcap")).unwrap(),
+            b"earlier capture"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_compressed_records_rejected() {
+        let dir = temp_dir();
+        let mut writer = PcapWriter::new(
+            &pcap(
+                &dir.join("capture.pcap"),
+                0,
+                TimestampResolution::Nanoseconds,
+            ),
+            RotationConfig::default(),
+        )
+        .unwrap();
+        let mut record = output(&[1; 10], 1);
+        record.metadata.compression = CompressionAlgorithm::Zstd;
+        let err = writer.write(&record).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue)
+        ));
+        assert!(writer.files().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_other_destinations_rejected() {
+        assert!(
+            PcapWriter::new(&destination(DestinationType::S3), RotationConfig::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_verified_files_raise_no_events() {
+        let dir = temp_dir();
+        let mut config = pcap(
+            &dir.join("capture.pcap"),
+            0,
+            TimestampResolution::Nanoseconds,
+        );
+        config.verification.enabled = true;
+        let rotation = RotationConfig {
+            max_packets: Some(1),
+            ..RotationConfig::default()
+        };
+        let mut writer = PcapWriter::new(&config, rotation).unwrap();
+        writer.write(&output(&[1; 10], 1)).unwrap();
+        writer.write(&output(&[2; 10], 2)).unwrap();
+
+        let events = writer.take_events();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], OutputEvent::RotationTriggered(_)));
+        assert_eq!(writer.finish().unwrap().len(), 2);
+
+        config.verification.rewrite_on_mismatch = true;
+        assert!(PcapWriter::new(&config, RotationConfig::default()).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_file_fails_verification() {
+        let dir = temp_dir();
+        let path = dir.join("capture.pcap");
+        let mut config = pcap(&path, 0, TimestampResolution::Nanoseconds);
+        config.verification.enabled = true;
+        let rotation = RotationConfig {
+            max_packets: Some(1),
+            ..RotationConfig::default()
+        };
+        let corrupt = |path: &Path| {
+            let mut file = OpenOptions::new().write(true).open(path).unwrap();
+            file.write_all(&[0xff]).unwrap();
+        };
+        let mut writer = PcapWriter::new(&config, rotation).unwrap();
+        writer.write(&output(&[1; 10], 1)).unwrap();
+        writer.flush().unwrap();
+        corrupt(&path);
+
+        // Rotation closes the corrupted file and verifies it
+        writer.write(&output(&[2; 10], 2)).unwrap();
+        let failures: Vec<_> = writer
+            .take_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                OutputEvent::WriteError(failure) => Some(failure.error),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].contains("capture.pcap on pcap"));
+
+        // A mismatch in the last file has no later take_events, so finishing fails
+        writer.flush().unwrap();
+        corrupt(&dir.join("capture-1.pcap"));
+        let err = writer.finish().unwrap_err();
+        assert!(err.message().contains("capture-1.pcap"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
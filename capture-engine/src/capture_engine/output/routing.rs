@@ -0,0 +1,429 @@
+// output/routing.rs
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::output::traits::{OutputData, RoutingInfo};
+use crate::capture_engine::protocol::flow::{FlowKey, FlowTable};
+
+/// Default number of flows whose routing is cached
+pub const DEFAULT_ROUTE_CACHE_FLOWS: usize = 65_536;
+/// Default idle time after which a flow's cached routing is dropped
+pub const DEFAULT_ROUTE_CACHE_IDLE_NS: u64 = 120_000_000_000;
+
+/// Packet attribute a routing rule tests
+///
+/// # Variants
+/// * `FilterRule` - Id of the filter rule that matched the packet
+/// * `Protocol` - Transport protocol number
+/// * `Port` - Port on either side of the flow
+/// * `Address` - Address on either side of the flow
+/// * `SessionTag` - Tag of the capture session the packet belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteCondition {
+    FilterRule(String),
+    Protocol(u8),
+    Port(u16),
+    Address(IpAddr),
+    SessionTag(String),
+}
+
+/// Attributes of a packet used for routing
+///
+/// # Fields
+/// * `flow` - Flow the packet belongs to
+/// * `rule_id` - Id of the filter rule that matched the packet, if any
+/// * `session_tag` - Tag of the capture session, if any
+#[derive(Debug, Clone, Copy)]
+pub struct RouteContext<'a> {
+    pub flow: FlowKey,
+    pub rule_id: Option<&'a str>,
+    pub session_tag: Option<&'a str>,
+}
+
+impl RouteCondition {
+    fn matches(&self, context: &RouteContext<'_>) -> bool {
+        let flow = &context.flow;
+        match self {
+            RouteCondition::FilterRule(id) => context.rule_id == Some(id.as_str()),
+            RouteCondition::Protocol(protocol) => flow.protocol == *protocol,
+            RouteCondition::Port(port) => flow.port_a == *port || flow.port_b == *port,
+            RouteCondition::Address(addr) => flow.addr_a == *addr || flow.addr_b == *addr,
+            RouteCondition::SessionTag(tag) => context.session_tag == Some(tag.as_str()),
+        }
+    }
+}
+
+/// Maps packets matching every condition to a set of destinations
+///
+/// # Fields
+/// * `id` - Rule identifier
+/// * `priority` - Evaluation order; lower values are evaluated first
+/// * `conditions` - Conditions that must all match
+/// * `destination_ids` - Destinations matching packets are routed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingRule {
+    pub id: String,
+    pub priority: u32,
+    pub conditions: Vec<RouteCondition>,
+    pub destination_ids: Vec<String>,
+}
+
+impl RoutingRule {
+    /// Checks whether a packet matches the rule
+    ///
+    /// # Arguments
+    /// * `context` - Packet attributes
+    ///
+    /// # Returns
+    /// True if every condition matches
+    pub fn matches(&self, context: &RouteContext<'_>) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.matches(context))
+    }
+}
+
+/// Routing decision cached for a flow
+#[derive(Debug, Clone)]
+struct CachedRoute {
+    rule_id: Option<Arc<str>>,
+    destinations: Arc<[String]>,
+}
+
+/// Counters for output routing
+///
+/// # Fields
+/// * `evaluated` - Packets routed by evaluating the rules
+/// * `cached` - Packets routed from a flow's cached decision
+/// * `defaulted` - Packets that matched no rule and went to the default destinations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutingStats {
+    pub evaluated: u64,
+    pub cached: u64,
+    pub defaulted: u64,
+}
+
+/// Populates output routing from rules, caching each flow's decision
+///
+/// The first packet of a flow is evaluated against the rules in priority order and the first
+/// matching rule's destinations are cached for the flow; later packets of the flow reuse that
+/// decision, so every packet of a flow goes to the same destinations. Packets matching no rule
+/// go to the default destinations. Replacing the rules clears the cache.
+///
+/// # Fields
+/// * `rules` - Rules in evaluation order
+/// * `default_destinations` - Destinations for packets matching no rule
+/// * `cache` - Routing decision per flow
+/// * `stats` - Routing counters
+#[derive(Debug)]
+pub struct OutputRouter {
+    rules: Vec<RoutingRule>,
+    default_destinations: Arc<[String]>,
+    cache: FlowTable<CachedRoute>,
+    stats: RoutingStats,
+}
+
+impl OutputRouter {
+    /// Creates a router with the default cache size
+    ///
+    /// # Arguments
+    /// * `rules` - Routing rules
+    /// * `default_destinations` - Destinations for packets matching no rule
+    ///
+    /// # Returns
+    /// A new OutputRouter, or an error if the rules are invalid
+    pub fn new(
+        rules: Vec<RoutingRule>,
+        default_destinations: Vec<String>,
+    ) -> Result<Self, CaptureError> {
+        Self::with_cache(
+            rules,
+            default_destinations,
+            DEFAULT_ROUTE_CACHE_FLOWS,
+            DEFAULT_ROUTE_CACHE_IDLE_NS,
+        )
+    }
+
+    /// Creates a router with a custom flow cache
+    ///
+    /// # Arguments
+    /// * `rules` - Routing rules
+    /// * `default_destinations` - Destinations for packets matching no rule
+    /// * `cache_flows` - Number of flows whose routing is cached
+    /// * `cache_idle_ns` - Idle time after which a flow's cached routing is dropped
+    ///
+    /// # Returns
+    /// A new OutputRouter, or an error if the rules are invalid
+    pub fn with_cache(
+        rules: Vec<RoutingRule>,
+        default_destinations: Vec<String>,
+        cache_flows: usize,
+        cache_idle_ns: u64,
+    ) -> Result<Self, CaptureError> {
+        let mut router = Self {
+            rules: Vec::new(),
+            default_destinations: default_destinations.into(),
+            cache: FlowTable::new(cache_flows, cache_idle_ns),
+            stats: RoutingStats::default(),
+        };
+        router.set_rules(rules)?;
+        Ok(router)
+    }
+
+    /// Replaces the routing rules and clears cached flow decisions
+    ///
+    /// # Arguments
+    /// * `rules` - New routing rules
+    ///
+    /// # Returns
+    /// An error if a rule has no destinations or rule ids repeat
+    pub fn set_rules(&mut self, mut rules: Vec<RoutingRule>) -> Result<(), CaptureError> {
+        for (index, rule) in rules.iter().enumerate() {
+            if rule.destination_ids.is_empty() {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    &format!("Routing rule {} has no destinations", rule.id),
+                ));
+            }
+            if rules[..index].iter().any(|other| other.id == rule.id) {
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    &format!("Duplicate routing rule id {}", rule.id),
+                ));
+            }
+        }
+        rules.sort_by_key(|rule| rule.priority);
+        self.rules = rules;
+        self.cache = FlowTable::new(self.cache.capacity(), self.cache.idle_timeout_ns());
+        Ok(())
+    }
+
+    /// Gets the routing counters
+    pub fn stats(&self) -> &RoutingStats {
+        &self.stats
+    }
+
+    /// Gets the id of the rule cached for a flow
+    ///
+    /// # Arguments
+    /// * `flow` - Flow to look up
+    ///
+    /// # Returns
+    /// The matched rule id; None if the flow is not cached or went to the default destinations
+    pub fn cached_rule(&self, flow: &FlowKey) -> Option<&str> {
+        self.cache
+            .get(flow)
+            .and_then(|route| route.rule_id.as_deref())
+    }
+
+    /// Routes a packet
+    ///
+    /// # Arguments
+    /// * `context` - Packet attributes
+    /// * `now` - Packet timestamp in nanoseconds
+    ///
+    /// # Returns
+    /// The routing for the packet
+    pub fn route(&mut self, context: &RouteContext<'_>, now: u64) -> RoutingInfo {
+        if let Some(route) = self.cache.touch(&context.flow, now) {
+            self.stats.cached += 1;
+            return RoutingInfo {
+                destination_ids: route.destinations.to_vec(),
+            };
+        }
+
+        self.stats.evaluated += 1;
+        let route = match self.rules.iter().find(|rule| rule.matches(context)) {
+            Some(rule) => CachedRoute {
+                rule_id: Some(rule.id.as_str().into()),
+                destinations: rule.destination_ids.as_slice().into(),
+            },
+            None => {
+                self.stats.defaulted += 1;
+                CachedRoute {
+                    rule_id: None,
+                    destinations: Arc::clone(&self.default_destinations),
+                }
+            }
+        };
+        let destination_ids = route.destinations.to_vec();
+        self.cache.get_or_insert_with(context.flow, now, || route);
+        RoutingInfo { destination_ids }
+    }
+
+    /// Sets the routing of output data
+    ///
+    /// # Arguments
+    /// * `data` - Output data to route
+    /// * `context` - Attributes of the packet the data came from
+    pub fn apply(&mut self, data: &mut OutputData, context: &RouteContext<'_>) {
+        let routing = self.route(context, data.metadata.timestamp);
+        data.metadata.routing_info = Some(routing);
+    }
+
+    /// Drops cached decisions for flows idle past the cache timeout
+    ///
+    /// # Arguments
+    /// * `now` - Current capture time in nanoseconds
+    ///
+    /// # Returns
+    /// The number of flows dropped from the cache
+    pub fn evict_idle(&mut self, now: u64) -> usize {
+        self.cache.evict_idle(now).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::output::compression::CompressionAlgorithm;
+    use crate::capture_engine::output::traits::OutputMetadata;
+    use crate::capture_engine::protocol::flow::{IPPROTO_TCP, IPPROTO_UDP};
+    use bytes::Bytes;
+    use std::net::Ipv4Addr;
+
+    fn flow(port: u16, server_port: u16, protocol: u8) -> FlowKey {
+        FlowKey::new(
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            port,
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 53)),
+            server_port,
+            protocol,
+        )
+    }
+
+    fn rule(id: &str, priority: u32, conditions: Vec<RouteCondition>, to: &str) -> RoutingRule {
+        RoutingRule {
+            id: id.to_string(),
+            priority,
+            conditions,
+            destination_ids: vec![to.to_string()],
+        }
+    }
+
+    fn router() -> OutputRouter {
+        OutputRouter::new(
+            vec![
+                rule(
+                    "tls",
+                    20,
+                    vec![
+                        RouteCondition::Protocol(IPPROTO_TCP),
+                        RouteCondition::Port(443),
+                    ],
+                    "tls-archive",
+                ),
+                rule("dns", 10, vec![RouteCondition::Port(53)], "dns-bucket"),
+                rule(
+                    "suspicious",
+                    5,
+                    vec![RouteCondition::FilterRule("ioc-match".to_string())],
+                    "soc-stream",
+                ),
+            ],
+            vec!["default".to_string()],
+        )
+        .unwrap()
+    }
+
+    fn context(flow: FlowKey) -> RouteContext<'static> {
+        RouteContext {
+            flow,
+            rule_id: None,
+            session_tag: None,
+        }
+    }
+
+    #[test]
+    fn test_routes_by_protocol_and_rule_id() {
+        let mut router = router();
+
+        let dns = router.route(&context(flow(40000, 53, IPPROTO_UDP)), 0);
+        assert_eq!(dns.destination_ids, vec!["dns-bucket"]);
+
+        let tls = router.route(&context(flow(40001, 443, IPPROTO_TCP)), 0);
+        assert_eq!(tls.destination_ids, vec!["tls-archive"]);
+
+        let flagged = RouteContext {
+            rule_id: Some("ioc-match"),
+            ..context(flow(40002, 443, IPPROTO_TCP))
+        };
+        assert_eq!(
+            router.route(&flagged, 0).destination_ids,
+            vec!["soc-stream"]
+        );
+
+        let other = router.route(&context(flow(40003, 8080, IPPROTO_TCP)), 0);
+        assert_eq!(other.destination_ids, vec!["default"]);
+        assert_eq!(router.stats().defaulted, 1);
+    }
+
+    #[test]
+    fn test_flow_decision_is_cached() {
+        let mut router = router();
+        let key = flow(40000, 443, IPPROTO_TCP);
+
+        let first = router.route(&context(key), 0);
+        // A later packet of the flow matching a higher-priority rule keeps the flow's routing
+        let later = RouteContext {
+            rule_id: Some("ioc-match"),
+            ..context(key)
+        };
+        for now in 1..10 {
+            assert_eq!(router.route(&later, now), first);
+        }
+        assert_eq!(router.cached_rule(&key), Some("tls"));
+        assert_eq!(
+            router.stats(),
+            &RoutingStats {
+                evaluated: 1,
+                cached: 9,
+                defaulted: 0,
+            }
+        );
+
+        // Replacing the rules re-evaluates the flow
+        router
+            .set_rules(vec![rule(
+                "all-tcp",
+                1,
+                vec![RouteCondition::Protocol(IPPROTO_TCP)],
+                "tcp",
+            )])
+            .unwrap();
+        assert_eq!(router.route(&context(key), 10).destination_ids, vec!["tcp"]);
+    }
+
+    #[test]
+    fn test_apply_sets_output_routing() {
+        let mut router = router();
+        let mut data = OutputData {
+            data: Bytes::from_static(b"payload"),
+            length: None,
+            metadata: OutputMetadata {
+                timestamp: 5,
+                routing_info: None,
+                compression: CompressionAlgorithm::None,
+            },
+        };
+        router.apply(&mut data, &context(flow(40000, 53, IPPROTO_UDP)));
+        assert_eq!(
+            data.metadata.routing_info.unwrap().destination_ids,
+            vec!["dns-bucket"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules_rejected() {
+        let empty = RoutingRule {
+            destination_ids: vec![],
+            ..rule("empty", 1, vec![], "x")
+        };
+        assert!(OutputRouter::new(vec![empty], vec![]).is_err());
+        let duplicate = vec![rule("a", 1, vec![], "x"), rule("a", 2, vec![], "y")];
+        assert!(OutputRouter::new(duplicate, vec![]).is_err());
+    }
+}
@@ -0,0 +1,1224 @@
+// output/s3.rs
+//! S3 object output using multipart uploads.
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind, SystemErrorKind,
+};
+use crate::capture_engine::output::compression::{
+    CompressionAlgorithm, CompressionConfig, ZstdCodec,
+};
+use crate::capture_engine::output::naming::{CollisionPolicy, KeyResolver, ObjectStore};
+use crate::capture_engine::output::traits::{
+    DestinationType, FlushControl, FlushMode, OutputData, OutputDestinationConfig, OutputEvent,
+    RetryPolicy, RotationConfig, RotationTrigger, WriteFailure, WriteMetrics,
+};
+use crate::capture_engine::output::verification::{
+    ContentDigest, ContentHasher, VerificationConfig, VerificationMethod, VerificationOutcome,
+};
+use crate::traits::Error;
+
+/// Smallest part S3 accepts, except for the last part of an object
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Part size used when the destination does not set one
+pub const DEFAULT_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Most parts one multipart upload may have
+pub const MAX_PARTS: usize = 10_000;
+
+/// Storage class objects are written with
+///
+/// # Variants
+/// * `Standard` - Frequently accessed data
+/// * `IntelligentTiering` - Moved between access tiers by S3
+/// * `StandardIa` - Infrequently accessed data
+/// * `OneZoneIa` - Infrequently accessed data kept in one availability zone
+/// * `GlacierInstantRetrieval` - Archive data read within milliseconds
+/// * `GlacierFlexibleRetrieval` - Archive data restored within hours
+/// * `DeepArchive` - Long-term archive data restored within a day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum S3StorageClass {
+    #[default]
+    Standard,
+    IntelligentTiering,
+    StandardIa,
+    OneZoneIa,
+    GlacierInstantRetrieval,
+    GlacierFlexibleRetrieval,
+    DeepArchive,
+}
+
+impl S3StorageClass {
+    const ALL: [S3StorageClass; 7] = [
+        S3StorageClass::Standard,
+        S3StorageClass::IntelligentTiering,
+        S3StorageClass::StandardIa,
+        S3StorageClass::OneZoneIa,
+        S3StorageClass::GlacierInstantRetrieval,
+        S3StorageClass::GlacierFlexibleRetrieval,
+        S3StorageClass::DeepArchive,
+    ];
+
+    /// Returns the name S3 uses for the storage class
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            S3StorageClass::Standard => "STANDARD",
+            S3StorageClass::IntelligentTiering => "INTELLIGENT_TIERING",
+            S3StorageClass::StandardIa => "STANDARD_IA",
+            S3StorageClass::OneZoneIa => "ONEZONE_IA",
+            S3StorageClass::GlacierInstantRetrieval => "GLACIER_IR",
+            S3StorageClass::GlacierFlexibleRetrieval => "GLACIER",
+            S3StorageClass::DeepArchive => "DEEP_ARCHIVE",
+        }
+    }
+
+    /// Parses the name S3 uses for a storage class, ignoring case
+    pub fn parse(value: &str) -> Result<Self, CaptureError> {
+        Self::ALL
+            .into_iter()
+            .find(|class| class.as_str().eq_ignore_ascii_case(value))
+            .ok_or_else(|| {
+                *CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    &format!("Unknown S3 storage class '{}'", value),
+                )
+            })
+    }
+}
+
+/// A part uploaded to a multipart upload
+///
+/// # Fields
+/// * `part_number` - Position of the part in the object, starting at 1
+/// * `etag` - Entity tag S3 returned for the part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedPart {
+    pub part_number: u32,
+    pub etag: String,
+}
+
+/// Object calls of an S3 client
+///
+/// No implementation ships with this crate; deployments implement it over their S3 SDK, and
+/// tests use in-memory fakes.
+#[async_trait]
+pub trait S3Client: Send + Sync {
+    /// Writes an empty object at `key` with `If-None-Match: *`
+    ///
+    /// Returns false if S3 answered 412 Precondition Failed because the key was taken.
+    async fn put_object_if_absent(&self, bucket: &str, key: &str) -> Result<bool, CaptureError>;
+
+    /// Deletes an object; deleting a missing key succeeds
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), CaptureError>;
+
+    /// Reads an object as a stream of body chunks
+    async fn get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<BoxStream<'static, Result<Bytes, CaptureError>>, CaptureError>;
+
+    /// Starts an upload and returns its id
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        storage_class: S3StorageClass,
+    ) -> Result<String, CaptureError>;
+
+    /// Uploads one part and returns its entity tag
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        body: Bytes,
+    ) -> Result<String, CaptureError>;
+
+    /// Assembles the uploaded parts into the object
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+    ) -> Result<(), CaptureError>;
+
+    /// Discards an upload and the parts stored for it
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), CaptureError>;
+}
+
+/// Settings of an S3 destination
+///
+/// Read from the destination settings `bucket` and `key` (required), `part_size_bytes` and
+/// `storage_class`; the rest comes from the destination configuration.
+///
+/// # Fields
+/// * `bucket` - Bucket objects are written to
+/// * `key` - Key of the first object; rotated objects get a `-N` suffix before the extension
+/// * `part_size` - Bytes buffered before a part is uploaded
+/// * `storage_class` - Storage class of the objects
+/// * `compression` - Compression applied to object contents
+/// * `collision_policy` - What to do when an object already exists at a key
+/// * `verification` - Read-back verification of completed objects; objects are never rewritten
+/// * `retry` - Retries of a failed part upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3Config {
+    pub bucket: String,
+    pub key: String,
+    pub part_size: usize,
+    pub storage_class: S3StorageClass,
+    pub compression: CompressionConfig,
+    pub collision_policy: CollisionPolicy,
+    pub verification: VerificationConfig,
+    pub retry: RetryPolicy,
+}
+
+impl S3Config {
+    /// Reads the settings of an S3 destination
+    ///
+    /// # Arguments
+    /// * `destination` - An `S3` destination
+    ///
+    /// # Returns
+    /// The settings, or an error if the destination is not S3, a required setting is missing,
+    /// the part size is below the S3 minimum, the compression algorithm is not supported, or
+    /// verification asks for rewrites
+    pub fn from_destination(destination: &OutputDestinationConfig) -> Result<Self, CaptureError> {
+        if !matches!(destination.destination_type, DestinationType::S3) {
+            return Err(config_error(
+                ConfigErrorKind::InvalidValue,
+                "Destination is not S3",
+            ));
+        }
+        let settings = &destination.settings;
+        let required = |name: &str| {
+            settings
+                .get(name)
+                .filter(|value| !value.is_empty())
+                .cloned()
+                .ok_or_else(|| {
+                    config_error(
+                        ConfigErrorKind::MissingRequired,
+                        &format!("S3 destination needs a '{}' setting", name),
+                    )
+                })
+        };
+
+        let part_size = match settings.get("part_size_bytes") {
+            Some(value) => value.parse::<usize>().map_err(|_| {
+                config_error(
+                    ConfigErrorKind::ParseError,
+                    "S3 setting 'part_size_bytes' must be a whole number",
+                )
+            })?,
+            None => DEFAULT_PART_SIZE,
+        };
+        if part_size < MIN_PART_SIZE {
+            return Err(config_error(
+                ConfigErrorKind::InvalidValue,
+                &format!(
+                    "S3 part size {} is below the minimum of {} bytes",
+                    part_size, MIN_PART_SIZE
+                ),
+            ));
+        }
+        let storage_class = match settings.get("storage_class") {
+            Some(value) => S3StorageClass::parse(value)?,
+            None => S3StorageClass::default(),
+        };
+        if !matches!(
+            destination.compression.algorithm,
+            CompressionAlgorithm::None | CompressionAlgorithm::Zstd
+        ) {
+            return Err(config_error(
+                ConfigErrorKind::InvalidValue,
+                "S3 output supports only Zstd compression",
+            ));
+        }
+        if destination.verification.rewrite_on_mismatch {
+            return Err(config_error(
+                ConfigErrorKind::InvalidValue,
+                "S3 output cannot rewrite objects on verification mismatch",
+            ));
+        }
+
+        Ok(Self {
+            bucket: required("bucket")?,
+            key: required("key")?,
+            part_size,
+            storage_class,
+            compression: destination.compression.clone(),
+            collision_policy: destination.collision_policy,
+            verification: destination.verification,
+            retry: destination.qos.retry,
+        })
+    }
+}
+
+/// Key claims against one bucket
+struct BucketStore<'a, C: S3Client> {
+    client: &'a C,
+    bucket: &'a str,
+}
+
+#[async_trait]
+impl<C: S3Client> ObjectStore for BucketStore<'_, C> {
+    async fn create_if_absent(&self, key: &str) -> Result<bool, CaptureError> {
+        self.client.put_object_if_absent(self.bucket, key).await
+    }
+}
+
+fn config_error(kind: ConfigErrorKind, message: &str) -> CaptureError {
+    *CaptureError::new(CaptureErrorKind::Configuration(kind), message)
+}
+
+/// Object contents not yet uploaded, compressed as they are written when configured
+enum PartBuffer {
+    Plain(Vec<u8>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl PartBuffer {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match self {
+            PartBuffer::Plain(buffer) => {
+                buffer.extend_from_slice(data);
+                Ok(())
+            }
+            PartBuffer::Zstd(encoder) => encoder.write_all(data),
+        }
+    }
+
+    /// Bytes ready to upload
+    fn pending(&mut self) -> &mut Vec<u8> {
+        match self {
+            PartBuffer::Plain(buffer) => buffer,
+            PartBuffer::Zstd(encoder) => encoder.get_mut(),
+        }
+    }
+
+    /// Ends the object and returns the bytes left to upload
+    fn finish(self) -> std::io::Result<Vec<u8>> {
+        match self {
+            PartBuffer::Plain(buffer) => Ok(buffer),
+            PartBuffer::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+/// Object being uploaded
+struct Upload {
+    key: String,
+    upload_id: String,
+    sequence: u32,
+    parts: Vec<CompletedPart>,
+    buffer: PartBuffer,
+    hasher: ContentHasher,
+    bytes: u64,
+    packets: u64,
+    first_timestamp: u64,
+}
+
+/// Writes output data to S3 objects with multipart uploads
+///
+/// Payloads are appended to the current object in order, compressed as one Zstd frame per
+/// object when configured. Whenever `part_size` bytes are buffered they are uploaded as the
+/// next part, so memory stays bounded by the part size however large the object grows. The
+/// object is completed on rotation or flush; the next record starts a new object named with a
+/// `-N` suffix. Rotation limits apply to payload bytes before compression. An object that
+/// would need more than `MAX_PARTS` parts is completed early, whatever the rotation limits.
+///
+/// Keys are resolved against the bucket under the destination's collision policy. A key is
+/// claimed before its upload starts by writing an empty placeholder with `If-None-Match: *`,
+/// so concurrent writers never pick the same key; completing the upload replaces the
+/// placeholder. With verification enabled, each completed object is read back and compared
+/// with the hash of the parts uploaded; a mismatch queues a `WriteError` event, as the content
+/// is no longer held to rewrite it.
+///
+/// A failed part upload is retried under the destination's retry policy. If a call still
+/// fails the multipart upload is aborted, so no orphaned parts are left stored and billed, the
+/// placeholder is deleted so the key can be claimed again, and a `WriteError` event is queued.
+///
+/// # Fields
+/// * `config` - Destination settings
+/// * `client` - S3 client
+/// * `codec` - Compressor, if the destination compresses
+/// * `rotation` - Limits that complete the current object
+/// * `resolver` - Collision policy applied to object keys
+/// * `upload` - Object being uploaded, if one is open
+/// * `objects` - Keys of every object completed, in order
+/// * `next_sequence` - Sequence suffix requested for the next object
+/// * `max_parts` - Parts an object may have before it is completed
+/// * `metrics` - Volume and time spent writing
+/// * `events` - Events not yet taken by the caller
+pub struct S3MultipartWriter<C: S3Client> {
+    config: S3Config,
+    client: C,
+    codec: Option<ZstdCodec>,
+    rotation: RotationConfig,
+    resolver: KeyResolver,
+    upload: Option<Upload>,
+    objects: Vec<String>,
+    next_sequence: u32,
+    max_parts: usize,
+    metrics: WriteMetrics,
+    events: Vec<OutputEvent>,
+}
+
+impl<C: S3Client> S3MultipartWriter<C> {
+    /// Creates a writer for an S3 destination
+    ///
+    /// # Arguments
+    /// * `destination` - An `S3` destination
+    /// * `rotation` - Limits that complete the current object
+    /// * `client` - S3 client
+    ///
+    /// # Returns
+    /// A new S3MultipartWriter instance, or an error if the settings are invalid. No upload is
+    /// started until the first record is written.
+    pub fn new(
+        destination: &OutputDestinationConfig,
+        rotation: RotationConfig,
+        client: C,
+    ) -> Result<Self, CaptureError> {
+        let config = S3Config::from_destination(destination)?;
+        let codec = match config.compression.algorithm {
+            CompressionAlgorithm::Zstd => Some(ZstdCodec::from_config(&config.compression)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            resolver: KeyResolver::new(config.collision_policy),
+            config,
+            client,
+            codec,
+            rotation,
+            upload: None,
+            objects: Vec::new(),
+            next_sequence: 0,
+            max_parts: MAX_PARTS,
+            metrics: WriteMetrics {
+                destination_id: destination.destination_id.clone(),
+                ..WriteMetrics::default()
+            },
+            events: Vec::new(),
+        })
+    }
+
+    /// Gets the S3 client
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Gets the keys of every object completed, in order
+    pub fn objects(&self) -> &[String] {
+        &self.objects
+    }
+
+    /// Gets the volume and time spent writing
+    pub fn metrics(&self) -> &WriteMetrics {
+        &self.metrics
+    }
+
+    /// Takes the events queued since the last call
+    pub fn take_events(&mut self) -> Vec<OutputEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Writes one record, uploading any part it fills
+    ///
+    /// # Arguments
+    /// * `data` - Payload and its capture timestamp
+    ///
+    /// # Returns
+    /// An error if an S3 call failed; the upload has then been aborted
+    pub async fn write(&mut self, data: &OutputData) -> Result<(), CaptureError> {
+        let result = self.append(data).await;
+        self.abort_on_error(result).await
+    }
+
+    /// Writes records in order
+    ///
+    /// # Arguments
+    /// * `batch` - Records to write
+    ///
+    /// # Returns
+    /// An error at the first record that could not be written
+    pub async fn write_batch(&mut self, batch: &[OutputData]) -> Result<(), CaptureError> {
+        for data in batch {
+            self.write(data).await?;
+        }
+        Ok(())
+    }
+
+    /// Uploads the buffered tail and completes the current object, if one is open
+    pub async fn flush(&mut self) -> Result<(), CaptureError> {
+        let result = self.complete().await;
+        self.abort_on_error(result).await
+    }
+
+    async fn append(&mut self, data: &OutputData) -> Result<(), CaptureError> {
+        let timestamp = data.metadata.timestamp;
+        if let Some(upload) = &self.upload {
+            let span = Duration::from_nanos(timestamp.saturating_sub(upload.first_timestamp));
+            if let Some(reason) = self
+                .rotation
+                .rotation_reason(upload.bytes, upload.packets, span)
+            {
+                self.complete().await?;
+                self.start(timestamp).await?;
+                let key = &self
+                    .upload
+                    .as_ref()
+                    .expect("an upload was just started")
+                    .key;
+                self.events
+                    .push(OutputEvent::RotationTriggered(RotationTrigger {
+                        reason: format!("{}; now writing {}", reason, key),
+                    }));
+            }
+        }
+        if self.upload.is_none() {
+            self.start(timestamp).await?;
+        }
+
+        let upload = self.upload.as_mut().expect("an upload is open");
+        upload.buffer.write(&data.data).map_err(compression_error)?;
+        upload.bytes += data.data.len() as u64;
+        upload.packets += 1;
+        self.metrics.records_written += 1;
+        self.upload_full_parts().await
+    }
+
+    async fn start(&mut self, timestamp: u64) -> Result<(), CaptureError> {
+        let buffer = match &self.codec {
+            Some(codec) => PartBuffer::Zstd(codec.stream_encoder(Vec::new())?),
+            None => PartBuffer::Plain(Vec::new()),
+        };
+        let started = Instant::now();
+        let store = BucketStore {
+            client: &self.client,
+            bucket: &self.config.bucket,
+        };
+        let resolved = self
+            .resolver
+            .resolve_sequence(&store, &self.config.key, self.next_sequence)
+            .await;
+        self.metrics.write_time += started.elapsed();
+        let resolved = resolved?;
+        let key = resolved.key;
+
+        let started = Instant::now();
+        let upload_id = self
+            .client
+            .create_multipart_upload(&self.config.bucket, &key, self.config.storage_class)
+            .await;
+        self.metrics.write_time += started.elapsed();
+        let upload_id = match upload_id {
+            Ok(upload_id) => upload_id,
+            Err(error) => {
+                // Release the claimed key so the next object can take it
+                let deleted = self.client.delete_object(&self.config.bucket, &key).await;
+                if let Err(delete_error) = deleted {
+                    self.events.push(OutputEvent::WriteError(WriteFailure {
+                        error: format!(
+                            "S3 placeholder {} not deleted: {}",
+                            key,
+                            delete_error.message()
+                        ),
+                    }));
+                }
+                return Err(error);
+            }
+        };
+
+        self.upload = Some(Upload {
+            key,
+            upload_id,
+            sequence: resolved.sequence,
+            parts: Vec::new(),
+            buffer,
+            hasher: ContentHasher::new(),
+            bytes: 0,
+            packets: 0,
+            first_timestamp: timestamp,
+        });
+        Ok(())
+    }
+
+    async fn upload_full_parts(&mut self) -> Result<(), CaptureError> {
+        let part_size = self.config.part_size;
+        while let Some(upload) = self.upload.as_mut() {
+            if upload.buffer.pending().len() < part_size {
+                break;
+            }
+            if upload.parts.len() + 1 >= self.max_parts {
+                // Only the last part is left; it takes everything buffered
+                let reason = format!("{} reached {} parts", upload.key, self.max_parts);
+                self.complete().await?;
+                self.events
+                    .push(OutputEvent::RotationTriggered(RotationTrigger { reason }));
+                break;
+            }
+            let pending = upload.buffer.pending();
+            let rest = pending.split_off(part_size);
+            let body = std::mem::replace(pending, rest);
+            self.upload_part(body).await?;
+        }
+        Ok(())
+    }
+
+    async fn upload_part(&mut self, body: Vec<u8>) -> Result<(), CaptureError> {
+        let upload = self.upload.as_mut().expect("an upload is open");
+        let part_number = upload.parts.len() as u32 + 1;
+        let body = Bytes::from(body);
+        let mut retries = 0;
+        let etag = loop {
+            let started = Instant::now();
+            let etag = self
+                .client
+                .upload_part(
+                    &self.config.bucket,
+                    &upload.key,
+                    &upload.upload_id,
+                    part_number,
+                    body.clone(),
+                )
+                .await;
+            self.metrics.write_time += started.elapsed();
+            match etag {
+                Ok(etag) => break etag,
+                Err(error) => {
+                    retries += 1;
+                    match self.config.retry.backoff(retries) {
+                        Some(backoff) => tokio::time::sleep(backoff).await,
+                        None => return Err(error),
+                    }
+                }
+            }
+        };
+
+        upload.hasher.update(&body);
+        upload.parts.push(CompletedPart { part_number, etag });
+        self.metrics.bytes_written += body.len() as u64;
+        Ok(())
+    }
+
+    async fn complete(&mut self) -> Result<(), CaptureError> {
+        let Some(upload) = self.upload.as_mut() else {
+            return Ok(());
+        };
+        let buffer = std::mem::replace(&mut upload.buffer, PartBuffer::Plain(Vec::new()));
+        let tail = buffer.finish().map_err(compression_error)?;
+        // An object needs at least one part, even if it is empty
+        if !tail.is_empty() || upload.parts.is_empty() {
+            self.upload_part(tail).await?;
+        }
+
+        let upload = self.upload.as_ref().expect("an upload is open");
+        let started = Instant::now();
+        let completed = self
+            .client
+            .complete_multipart_upload(
+                &self.config.bucket,
+                &upload.key,
+                &upload.upload_id,
+                &upload.parts,
+            )
+            .await;
+        self.metrics.write_time += started.elapsed();
+        completed?;
+
+        let upload = self.upload.take().expect("an upload is open");
+        self.next_sequence = upload.sequence.saturating_add(1);
+        self.objects.push(upload.key.clone());
+        self.metrics.objects_completed += 1;
+        self.verify(&upload.key, upload.hasher.finish()).await
+    }
+
+    async fn verify(&mut self, key: &str, expected: ContentDigest) -> Result<(), CaptureError> {
+        if !self.config.verification.enabled {
+            return Ok(());
+        }
+        let started = Instant::now();
+        let actual = self.read_digest(key).await;
+        self.metrics.write_time += started.elapsed();
+
+        let actual = actual?;
+        let outcome = if actual == expected {
+            VerificationOutcome::Verified {
+                method: VerificationMethod::Readback,
+                rewrites: 0,
+            }
+        } else {
+            VerificationOutcome::Mismatch {
+                expected,
+                actual,
+                rewrites: 0,
+            }
+        };
+        if let Some(event) = outcome.to_event(&self.metrics.destination_id, key) {
+            self.events.push(event);
+        }
+        Ok(())
+    }
+
+    /// Hashes an object as its body streams in, without holding it in memory
+    async fn read_digest(&self, key: &str) -> Result<ContentDigest, CaptureError> {
+        let mut body = self.client.get_object(&self.config.bucket, key).await?;
+        let mut hasher = ContentHasher::new();
+        while let Some(chunk) = body.next().await {
+            hasher.update(&chunk?);
+        }
+        Ok(hasher.finish())
+    }
+
+    async fn abort_on_error(
+        &mut self,
+        result: Result<(), CaptureError>,
+    ) -> Result<(), CaptureError> {
+        let Err(error) = result else {
+            return Ok(());
+        };
+        let Some(upload) = self.upload.take() else {
+            self.events.push(OutputEvent::WriteError(WriteFailure {
+                error: error.message().to_string(),
+            }));
+            return Err(error);
+        };
+
+        let aborted = self
+            .client
+            .abort_multipart_upload(&self.config.bucket, &upload.key, &upload.upload_id)
+            .await;
+        let deleted = self
+            .client
+            .delete_object(&self.config.bucket, &upload.key)
+            .await;
+        let outcome = match (aborted, deleted) {
+            (Ok(()), Ok(())) => "upload aborted".to_string(),
+            (Err(abort_error), _) => format!("abort also failed: {}", abort_error.message()),
+            (Ok(()), Err(delete_error)) => format!(
+                "upload aborted, placeholder not deleted: {}",
+                delete_error.message()
+            ),
+        };
+        self.events.push(OutputEvent::WriteError(WriteFailure {
+            error: format!(
+                "S3 object {}: {} ({})",
+                upload.key,
+                error.message(),
+                outcome
+            ),
+        }));
+        Err(error)
+    }
+}
+
+fn compression_error(e: std::io::Error) -> CaptureError {
+    CaptureError::new(
+        CaptureErrorKind::System(SystemErrorKind::IoError),
+        "Zstd compression of S3 object failed",
+    )
+    .with_source(e)
+}
+
+#[async_trait]
+impl<C: S3Client> FlushControl for S3MultipartWriter<C> {
+    /// Every mode completes the current object; a multipart upload left open stores nothing.
+    async fn flush_with_mode(&mut self, _mode: FlushMode) -> Result<(), Error> {
+        self.flush()
+            .await
+            .map_err(|e| Error::Runtime(e.message().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::ResourceErrorKind;
+    use crate::capture_engine::output::naming::CollisionPolicy;
+    use crate::capture_engine::output::traits::{OutputMetadata, QualityOfService};
+    use crate::capture_engine::output::verification::VerificationConfig;
+    use parking_lot::Mutex;
+    use std::collections::{HashMap, HashSet};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct FakeState {
+        created: Vec<(String, S3StorageClass)>,
+        parts: HashMap<String, Vec<(u32, Bytes)>>,
+        completed: HashMap<String, Vec<u8>>,
+        aborted: Vec<String>,
+        placeholders: HashSet<String>,
+        fail_part: Option<u32>,
+        part_failures: u32,
+        corrupt_reads: bool,
+    }
+
+    /// In-memory S3 keeping parts per upload; the upload id is the key
+    #[derive(Default)]
+    struct FakeS3 {
+        state: Mutex<FakeState>,
+    }
+
+    #[async_trait]
+    impl S3Client for FakeS3 {
+        async fn put_object_if_absent(
+            &self,
+            _bucket: &str,
+            key: &str,
+        ) -> Result<bool, CaptureError> {
+            let mut state = self.state.lock();
+            if state.completed.contains_key(key) {
+                return Ok(false);
+            }
+            Ok(state.placeholders.insert(key.to_string()))
+        }
+
+        async fn delete_object(&self, _bucket: &str, key: &str) -> Result<(), CaptureError> {
+            let mut state = self.state.lock();
+            state.placeholders.remove(key);
+            state.completed.remove(key);
+            Ok(())
+        }
+
+        async fn get_object(
+            &self,
+            _bucket: &str,
+            key: &str,
+        ) -> Result<BoxStream<'static, Result<Bytes, CaptureError>>, CaptureError> {
+            let state = self.state.lock();
+            let mut object = state.completed[key].clone();
+            if state.corrupt_reads {
+                object[0] ^= 0xff;
+            }
+            let chunks: Vec<_> = object
+                .chunks(16)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect();
+            Ok(futures::stream::iter(chunks).boxed())
+        }
+
+        async fn create_multipart_upload(
+            &self,
+            _bucket: &str,
+            key: &str,
+            storage_class: S3StorageClass,
+        ) -> Result<String, CaptureError> {
+            let mut state = self.state.lock();
+            state.created.push((key.to_string(), storage_class));
+            state.parts.insert(key.to_string(), Vec::new());
+            Ok(key.to_string())
+        }
+
+        async fn upload_part(
+            &self,
+            _bucket: &str,
+            _key: &str,
+            upload_id: &str,
+            part_number: u32,
+            body: Bytes,
+        ) -> Result<String, CaptureError> {
+            let mut state = self.state.lock();
+            if state.fail_part == Some(part_number) || state.part_failures > 0 {
+                state.part_failures = state.part_failures.saturating_sub(1);
+                return Err(*CaptureError::new(
+                    CaptureErrorKind::System(SystemErrorKind::IoError),
+                    "connection reset",
+                ));
+            }
+            let parts = state.parts.get_mut(upload_id).unwrap();
+            parts.push((part_number, body));
+            Ok(format!("etag-{}", part_number))
+        }
+
+        async fn complete_multipart_upload(
+            &self,
+            _bucket: &str,
+            key: &str,
+            upload_id: &str,
+            parts: &[CompletedPart],
+        ) -> Result<(), CaptureError> {
+            let mut state = self.state.lock();
+            let uploaded = state.parts.remove(upload_id).unwrap();
+            assert_eq!(
+                parts.iter().map(|p| p.part_number).collect::<Vec<_>>(),
+                uploaded.iter().map(|(n, _)| *n).collect::<Vec<_>>()
+            );
+            let object = uploaded
+                .iter()
+                .flat_map(|(_, body)| body.to_vec())
+                .collect();
+            state.completed.insert(key.to_string(), object);
+            Ok(())
+        }
+
+        async fn abort_multipart_upload(
+            &self,
+            _bucket: &str,
+            key: &str,
+            upload_id: &str,
+        ) -> Result<(), CaptureError> {
+            let mut state = self.state.lock();
+            state.parts.remove(upload_id);
+            state.aborted.push(key.to_string());
+            Ok(())
+        }
+    }
+
+    fn destination(
+        settings: &[(&str, &str)],
+        compression: CompressionConfig,
+    ) -> OutputDestinationConfig {
+        let mut all = HashMap::from([
+            ("bucket".to_string(), "captures".to_string()),
+            ("key".to_string(), "run/capture.bin".to_string()),
+        ]);
+        for (name, value) in settings {
+            all.insert(name.to_string(), value.to_string());
+        }
+        OutputDestinationConfig {
+            destination_id: "s3-1".to_string(),
+            destination_type: DestinationType::S3,
+            settings: all,
+            compression,
+            collision_policy: CollisionPolicy::default(),
+            verification: VerificationConfig::default(),
+            qos: QualityOfService {
+                retry: RetryPolicy {
+                    max_retries: 2,
+                    initial_backoff: Duration::from_millis(1),
+                    max_backoff: Duration::from_millis(1),
+                },
+                ..QualityOfService::default()
+            },
+        }
+    }
+
+    fn record(fill: u8, len: usize, timestamp: u64) -> OutputData {
+        OutputData {
+            data: Bytes::from(vec![fill; len]),
+            length: None,
+            metadata: OutputMetadata {
+                timestamp,
+                routing_info: None,
+                compression: CompressionAlgorithm::None,
+            },
+        }
+    }
+
+    fn part_sizes(writer: &S3MultipartWriter<FakeS3>, key: &str) -> Vec<usize> {
+        writer.client().state.lock().parts[key]
+            .iter()
+            .map(|(_, body)| body.len())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_uploads_full_parts_and_completes_on_flush() {
+        let part_size = MIN_PART_SIZE.to_string();
+        let config = destination(
+            &[
+                ("storage_class", "standard_ia"),
+                ("part_size_bytes", &part_size),
+            ],
+            CompressionConfig::default(),
+        );
+        let mut writer =
+            S3MultipartWriter::new(&config, RotationConfig::default(), FakeS3::default()).unwrap();
+        let records: Vec<_> = (0..12u8).map(|i| record(i, 1024 * 1024, 0)).collect();
+        writer.write_batch(&records).await.unwrap();
+
+        // Two full parts are uploaded as they fill; the last 2 MiB wait for completion
+        assert_eq!(
+            part_sizes(&writer, "run/capture.bin"),
+            vec![MIN_PART_SIZE, MIN_PART_SIZE]
+        );
+        assert!(writer.client().state.lock().completed.is_empty());
+
+        writer.flush().await.unwrap();
+        {
+            let state = writer.client().state.lock();
+            assert_eq!(
+                state.created,
+                vec![("run/capture.bin".to_string(), S3StorageClass::StandardIa)]
+            );
+            let expected: Vec<u8> = records.iter().flat_map(|r| r.data.to_vec()).collect();
+            assert_eq!(state.completed["run/capture.bin"], expected);
+        }
+
+        assert_eq!(writer.objects(), ["run/capture.bin"]);
+        let metrics = writer.metrics();
+        assert_eq!(metrics.destination_id, "s3-1");
+        assert_eq!(metrics.bytes_written, 12 * 1024 * 1024);
+        assert_eq!(metrics.records_written, 12);
+        assert_eq!(metrics.objects_completed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rotation_completes_object_and_starts_next() {
+        let config = destination(&[], CompressionConfig::default());
+        let rotation = RotationConfig {
+            max_packets: Some(2),
+            ..RotationConfig::default()
+        };
+        let mut writer = S3MultipartWriter::new(&config, rotation, FakeS3::default()).unwrap();
+        for i in 0..3u8 {
+            writer.write(&record(i, 10, 0)).await.unwrap();
+        }
+        assert_eq!(writer.objects(), ["run/capture.bin"]);
+        assert!(matches!(
+            writer.take_events().as_slice(),
+            [OutputEvent::RotationTriggered(trigger)]
+                if trigger.reason.contains("run/capture-1.bin")
+        ));
+
+        writer.flush().await.unwrap();
+        assert_eq!(writer.objects(), ["run/capture.bin", "run/capture-1.bin"]);
+        let state = writer.client().state.lock();
+        assert_eq!(
+            state.completed["run/capture.bin"],
+            [[0u8; 10], [1u8; 10]].concat()
+        );
+        assert_eq!(state.completed["run/capture-1.bin"], vec![2u8; 10]);
+    }
+
+    #[tokio::test]
+    async fn test_part_limit_completes_object() {
+        let part_size = MIN_PART_SIZE.to_string();
+        let config = destination(
+            &[("part_size_bytes", &part_size)],
+            CompressionConfig::default(),
+        );
+        let mut writer =
+            S3MultipartWriter::new(&config, RotationConfig::default(), FakeS3::default()).unwrap();
+        writer.max_parts = 3;
+        let records: Vec<_> = (0..16u8).map(|i| record(i, 1024 * 1024, 0)).collect();
+        writer.write_batch(&records).await.unwrap();
+        writer.flush().await.unwrap();
+
+        // Two full parts, then the 15th MiB fills a third, which closes the object instead
+        assert_eq!(writer.objects(), ["run/capture.bin", "run/capture-1.bin"]);
+        assert!(matches!(
+            writer.take_events().as_slice(),
+            [OutputEvent::RotationTriggered(trigger)]
+                if trigger.reason == "run/capture.bin reached 3 parts"
+        ));
+        let state = writer.client().state.lock();
+        let expected: Vec<u8> = records.iter().flat_map(|r| r.data.to_vec()).collect();
+        let first = &state.completed["run/capture.bin"];
+        assert_eq!(first.len(), 15 * 1024 * 1024);
+        assert_eq!(
+            [first.as_slice(), &state.completed["run/capture-1.bin"]].concat(),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zstd_object_decompresses_to_payloads() {
+        let config = destination(&[], CompressionConfig::zstd(3));
+        let mut writer =
+            S3MultipartWriter::new(&config, RotationConfig::default(), FakeS3::default()).unwrap();
+        let records: Vec<_> = (0..4u8).map(|i| record(i, 4096, 0)).collect();
+        writer.write_batch(&records).await.unwrap();
+        writer.flush().await.unwrap();
+
+        let object = writer.client().state.lock().completed["run/capture.bin"].clone();
+        assert!(object.len() < 4 * 4096);
+        let codec = ZstdCodec::from_config(&CompressionConfig::zstd(3)).unwrap();
+        let expected: Vec<u8> = records.iter().flat_map(|r| r.data.to_vec()).collect();
+        assert_eq!(codec.decompress(&object, expected.len()).unwrap(), expected);
+        assert_eq!(writer.metrics().bytes_written, object.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_failed_part_aborts_upload() {
+        let part_size = MIN_PART_SIZE.to_string();
+        let config = destination(
+            &[("part_size_bytes", &part_size)],
+            CompressionConfig::default(),
+        );
+        let mut writer =
+            S3MultipartWriter::new(&config, RotationConfig::default(), FakeS3::default()).unwrap();
+        writer.client().state.lock().fail_part = Some(2);
+
+        let records: Vec<_> = (0..12u8).map(|i| record(i, 1024 * 1024, 0)).collect();
+        let error = writer.write_batch(&records).await.unwrap_err();
+        assert_eq!(error.message(), "connection reset");
+
+        {
+            let state = writer.client().state.lock();
+            assert_eq!(state.aborted, vec!["run/capture.bin".to_string()]);
+            assert!(state.parts.is_empty());
+            assert!(state.completed.is_empty());
+        }
+        assert!(writer.objects().is_empty());
+        assert!(matches!(
+            writer.take_events().as_slice(),
+            [OutputEvent::WriteError(failure)] if failure.error.contains("upload aborted")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_aborted_object_key_reused() {
+        for policy in [CollisionPolicy::Fail, CollisionPolicy::AppendSequence] {
+            let mut config = destination(&[], CompressionConfig::default());
+            config.collision_policy = policy;
+            let mut writer =
+                S3MultipartWriter::new(&config, RotationConfig::default(), FakeS3::default())
+                    .unwrap();
+            writer.client().state.lock().fail_part = Some(1);
+            writer.write(&record(1, 10, 0)).await.unwrap();
+            assert!(writer.flush().await.is_err());
+            assert!(writer.client().state.lock().placeholders.is_empty());
+
+            writer.client().state.lock().fail_part = None;
+            writer.write(&record(2, 10, 0)).await.unwrap();
+            writer.flush().await.unwrap();
+            assert_eq!(writer.objects(), ["run/capture.bin"], "{:?}", policy);
+            let state = writer.client().state.lock();
+            assert_eq!(state.completed.len(), 1);
+            assert_eq!(state.completed["run/capture.bin"], vec![2u8; 10]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_part_retried() {
+        let config = destination(&[], CompressionConfig::default());
+        let mut writer =
+            S3MultipartWriter::new(&config, RotationConfig::default(), FakeS3::default()).unwrap();
+        writer.client().state.lock().part_failures = 2;
+
+        writer.write(&record(7, 10, 0)).await.unwrap();
+        writer.flush().await.unwrap();
+
+        assert_eq!(writer.objects(), ["run/capture.bin"]);
+        let state = writer.client().state.lock();
+        assert_eq!(state.completed["run/capture.bin"], vec![7u8; 10]);
+        assert!(state.aborted.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_existing_objects_resolved_by_collision_policy() {
+        let rotation = RotationConfig {
+            max_packets: Some(1),
+            ..RotationConfig::default()
+        };
+        let existing = || {
+            let client = FakeS3::default();
+            client
+                .state
+                .lock()
+                .completed
+                .insert("run/capture.bin".to_string(), b"earlier run".to_vec());
+            client
+        };
+
+        let config = destination(&[], CompressionConfig::default());
+        let mut writer = S3MultipartWriter::new(&config, rotation, existing()).unwrap();
+        for i in 0..2u8 {
+            writer.write(&record(i, 10, 0)).await.unwrap();
+        }
+        writer.flush().await.unwrap();
+        assert_eq!(writer.objects(), ["run/capture-1.bin", "run/capture-2.bin"]);
+        assert_eq!(
+            writer.client().state.lock().completed["run/capture.bin"],
+            b"earlier run"
+        );
+
+        let mut config = destination(&[], CompressionConfig::default());
+        config.collision_policy = CollisionPolicy::Fail;
+        let mut writer = S3MultipartWriter::new(&config, rotation, existing()).unwrap();
+        let error = writer.write(&record(0, 10, 0)).await.unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            CaptureErrorKind::Resource(ResourceErrorKind::InvalidState)
+        ));
+        assert!(writer.client().state.lock().created.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verification_reports_mismatched_object() {
+        let mut config = destination(&[], CompressionConfig::zstd(3));
+        config.verification = VerificationConfig {
+            enabled: true,
+            ..VerificationConfig::default()
+        };
+        let mut writer =
+            S3MultipartWriter::new(&config, RotationConfig::default(), FakeS3::default()).unwrap();
+        writer.write(&record(1, 100, 0)).await.unwrap();
+        writer.flush().await.unwrap();
+        assert!(writer.take_events().is_empty());
+
+        writer.client().state.lock().corrupt_reads = true;
+        writer.write(&record(2, 100, 0)).await.unwrap();
+        writer.flush().await.unwrap();
+        assert!(matches!(
+            writer.take_events().as_slice(),
+            [OutputEvent::WriteError(failure)]
+                if failure.error.contains("Checksum mismatch for run/capture-1.bin")
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_settings() {
+        let small = (MIN_PART_SIZE - 1).to_string();
+        for (settings, compression) in [
+            (vec![("bucket", "")], CompressionConfig::default()),
+            (
+                vec![("part_size_bytes", small.as_str())],
+                CompressionConfig::default(),
+            ),
+            (
+                vec![("part_size_bytes", "big")],
+                CompressionConfig::default(),
+            ),
+            (
+                vec![("storage_class", "COLD")],
+                CompressionConfig::default(),
+            ),
+            (
+                vec![],
+                CompressionConfig {
+                    algorithm: CompressionAlgorithm::Gzip,
+                    ..CompressionConfig::default()
+                },
+            ),
+        ] {
+            let config = destination(&settings, compression);
+            assert!(
+                S3Config::from_destination(&config).is_err(),
+                "{:?}",
+                settings
+            );
+        }
+
+        let mut rewriting = destination(&[], CompressionConfig::default());
+        rewriting.verification = VerificationConfig::verify_and_rewrite();
+        assert!(S3Config::from_destination(&rewriting).is_err());
+
+        let mut not_s3 = destination(&[], CompressionConfig::default());
+        not_s3.destination_type = DestinationType::Kafka;
+        assert!(S3Config::from_destination(&not_s3).is_err());
+        assert_eq!(
+            S3StorageClass::parse("glacier_ir").unwrap(),
+            S3StorageClass::GlacierInstantRetrieval
+        );
+    }
+}
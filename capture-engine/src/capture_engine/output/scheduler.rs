@@ -0,0 +1,681 @@
+// output/scheduler.rs
+//! Priority-aware scheduling of writes across output destinations.
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, RuntimeErrorKind,
+};
+use crate::capture_engine::output::bandwidth::BandwidthLimiter;
+use crate::capture_engine::output::compression::CompressionStage;
+use crate::capture_engine::output::traits::{
+    BackpressureStatus, OutputData, OutputDestinationConfig, OutputEvent, Priority,
+    QualityOfService, WriteFailure, WriteMetrics,
+};
+
+/// A write handed to the caller
+///
+/// # Fields
+/// * `destination_id` - Destination to write to
+/// * `data` - Record to write
+/// * `attempt` - Earlier failed attempts of this record
+/// * `enqueued` - When the record was first queued, kept across retries
+#[derive(Debug, Clone)]
+pub struct ScheduledWrite {
+    pub destination_id: String,
+    pub data: OutputData,
+    pub attempt: u32,
+    pub enqueued: Instant,
+}
+
+/// A record waiting in a destination queue
+#[derive(Debug)]
+struct Queued {
+    data: OutputData,
+    enqueued: Instant,
+    not_before: Instant,
+    attempt: u32,
+}
+
+/// Pending records and bandwidth budget of one destination
+#[derive(Debug)]
+struct DestinationQueue {
+    qos: QualityOfService,
+    queue: VecDeque<Queued>,
+    queued_bytes: u64,
+    limiter: Option<BandwidthLimiter>,
+    compression: Option<CompressionStage>,
+    metrics: WriteMetrics,
+}
+
+impl DestinationQueue {
+    /// Whether more is queued than the limiter lets out in its burst plus one second
+    fn backlogged(&self) -> bool {
+        self.limiter
+            .as_ref()
+            .is_some_and(|limiter| self.queued_bytes > limiter.burst() + limiter.rate())
+    }
+
+    /// Whether the oldest record has waited longer than the latency target
+    fn starving(&self, now: Instant) -> bool {
+        match (self.qos.max_latency, self.queue.front()) {
+            (Some(max_latency), Some(head)) => {
+                now.saturating_duration_since(head.enqueued) > max_latency
+            }
+            _ => false,
+        }
+    }
+
+    /// Enqueue time of the oldest record, if it may be written now
+    fn ready(&mut self, now: Instant) -> Option<Instant> {
+        let head = self.queue.front()?;
+        let bytes = head.data.data.len() as u64;
+        let within_budget = self
+            .limiter
+            .as_mut()
+            .is_none_or(|limiter| limiter.wait_time(bytes, now).is_zero());
+        (head.not_before <= now && within_budget).then_some(head.enqueued)
+    }
+}
+
+/// Orders writes across destinations by their quality of service
+///
+/// The highest-priority destination with a record ready is written first; within a priority
+/// the oldest record goes first. A destination with a `bandwidth_limit` is paced by a
+/// `BandwidthLimiter` and waits for its budget to refill.
+///
+/// Backpressure is applied instead of buffering without bound or dropping records: a
+/// `BackpressureEvent` asks producers to slow down while a rate-limited destination has more
+/// queued than its burst plus one second of bandwidth, or while a destination starves. A
+/// destination starves when its oldest record has waited longer than its `max_latency`;
+/// lower-priority destinations are then held. Records are only dropped once their
+/// destination's `RetryPolicy` is exhausted, which queues a `WriteError` event.
+///
+/// Destinations added from their configuration compress records with their
+/// `CompressionStage` as they are queued, so bandwidth budgets apply to the bytes written.
+///
+/// # Fields
+/// * `destinations` - Queues by destination id
+/// * `held_below` - Priority under which writes are held while a destination starves
+/// * `backpressure` - Whether producers are asked to slow down
+/// * `events` - Events not yet taken by the caller
+#[derive(Debug, Default)]
+pub struct WriteScheduler {
+    destinations: BTreeMap<String, DestinationQueue>,
+    held_below: Option<Priority>,
+    backpressure: bool,
+    events: Vec<OutputEvent>,
+}
+
+impl WriteScheduler {
+    /// Creates a scheduler without destinations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a destination, replacing the quality of service of an existing one
+    ///
+    /// # Arguments
+    /// * `destination_id` - Destination identifier
+    /// * `qos` - Priority, latency target, bandwidth limit and retries of the destination
+    /// * `now` - Current time
+    pub fn add_destination(&mut self, destination_id: &str, qos: QualityOfService, now: Instant) {
+        let queue = self
+            .destinations
+            .entry(destination_id.to_string())
+            .or_insert_with(|| DestinationQueue {
+                qos,
+                queue: VecDeque::new(),
+                queued_bytes: 0,
+                limiter: None,
+                compression: None,
+                metrics: WriteMetrics {
+                    destination_id: destination_id.to_string(),
+                    ..WriteMetrics::default()
+                },
+            });
+        queue.qos = qos;
+        queue.limiter = BandwidthLimiter::from_qos(&qos, now);
+    }
+
+    /// Adds a destination from its configuration, compressing its records as they are queued
+    ///
+    /// # Arguments
+    /// * `config` - Destination configuration
+    /// * `now` - Current time
+    ///
+    /// # Returns
+    /// An error if the destination's compression configuration is invalid for it
+    pub fn add_configured_destination(
+        &mut self,
+        config: &OutputDestinationConfig,
+        now: Instant,
+    ) -> Result<(), CaptureError> {
+        let compression = CompressionStage::for_destination(config)?;
+        self.add_destination(&config.destination_id, config.qos, now);
+        if let Some(queue) = self.destinations.get_mut(&config.destination_id) {
+            queue.compression = compression;
+        }
+        Ok(())
+    }
+
+    /// Removes a destination
+    ///
+    /// # Returns
+    /// The records still queued for it, oldest first
+    pub fn remove_destination(&mut self, destination_id: &str) -> Vec<OutputData> {
+        self.destinations
+            .remove(destination_id)
+            .map(|queue| queue.queue.into_iter().map(|queued| queued.data).collect())
+            .unwrap_or_default()
+    }
+
+    /// Queues a record for a destination
+    ///
+    /// # Arguments
+    /// * `destination_id` - Destination to write to
+    /// * `data` - Record to write
+    /// * `now` - Current time
+    ///
+    /// # Returns
+    /// An error if the destination is unknown or compressing the record failed. The record is
+    /// queued even while backpressure is applied; producers are expected to pause on the
+    /// `BackpressureEvent`.
+    pub fn enqueue(
+        &mut self,
+        destination_id: &str,
+        data: OutputData,
+        now: Instant,
+    ) -> Result<(), CaptureError> {
+        let queue = self.destinations.get_mut(destination_id).ok_or_else(|| {
+            *CaptureError::new(
+                CaptureErrorKind::Runtime(RuntimeErrorKind::EntityNotFound),
+                &format!("Output destination {} is not scheduled", destination_id),
+            )
+        })?;
+        let data = match &queue.compression {
+            Some(stage) => stage
+                .apply(vec![data], &mut queue.metrics)?
+                .pop()
+                .expect("compression keeps every record"),
+            None => data,
+        };
+        queue.queued_bytes += data.data.len() as u64;
+        queue.queue.push_back(Queued {
+            data,
+            enqueued: now,
+            not_before: now,
+            attempt: 0,
+        });
+        self.update_backpressure(now);
+        Ok(())
+    }
+
+    /// Picks the next write
+    ///
+    /// # Arguments
+    /// * `now` - Current time
+    ///
+    /// # Returns
+    /// The write to perform, or None if no destination may be written now
+    pub fn next(&mut self, now: Instant) -> Option<ScheduledWrite> {
+        self.update_backpressure(now);
+
+        let held_below = self.held_below;
+        let (destination_id, queue) = self
+            .destinations
+            .iter_mut()
+            .filter(|(_, queue)| held_below.is_none_or(|floor| queue.qos.priority >= floor))
+            .filter_map(|(id, queue)| queue.ready(now).map(|enqueued| (id, queue, enqueued)))
+            .max_by(|(_, a, a_enqueued), (_, b, b_enqueued)| {
+                a.qos
+                    .priority
+                    .cmp(&b.qos.priority)
+                    .then(b_enqueued.cmp(a_enqueued))
+            })
+            .map(|(id, queue, _)| (id, queue))?;
+
+        let queued = queue.queue.pop_front()?;
+        let bytes = queued.data.data.len() as u64;
+        queue.queued_bytes -= bytes;
+        if let Some(limiter) = queue.limiter.as_mut() {
+            let _ = limiter.try_acquire(bytes, now);
+        }
+        let write = ScheduledWrite {
+            destination_id: destination_id.clone(),
+            data: queued.data,
+            attempt: queued.attempt,
+            enqueued: queued.enqueued,
+        };
+        self.update_backpressure(now);
+        Some(write)
+    }
+
+    /// Holds priorities below the highest starving destination and signals producers when
+    /// backpressure starts or ends
+    fn update_backpressure(&mut self, now: Instant) {
+        self.held_below = self
+            .destinations
+            .values()
+            .filter(|queue| queue.starving(now))
+            .map(|queue| queue.qos.priority)
+            .max()
+            .filter(|priority| *priority > Priority::Background);
+        let backpressure = self.held_below.is_some()
+            || self.destinations.values().any(DestinationQueue::backlogged);
+        if backpressure != self.backpressure {
+            self.backpressure = backpressure;
+            self.events
+                .push(OutputEvent::BackpressureEvent(BackpressureStatus {
+                    active: backpressure,
+                }));
+        }
+    }
+
+    /// Records a successful write
+    ///
+    /// # Arguments
+    /// * `write` - Write that succeeded
+    /// * `elapsed` - Time the destination took
+    pub fn completed(&mut self, write: ScheduledWrite, elapsed: Duration) {
+        if let Some(queue) = self.destinations.get_mut(&write.destination_id) {
+            queue.metrics.bytes_written += write.data.data.len() as u64;
+            queue.metrics.records_written += 1;
+            queue.metrics.write_time += elapsed;
+        }
+    }
+
+    /// Records a failed write, requeuing it ahead of newer records if retries remain
+    ///
+    /// # Arguments
+    /// * `write` - Write that failed
+    /// * `error` - Why it failed
+    /// * `now` - Current time
+    ///
+    /// # Returns
+    /// The delay before the retry, or None if the record was dropped
+    pub fn failed(&mut self, write: ScheduledWrite, error: &str, now: Instant) -> Option<Duration> {
+        let queue = self.destinations.get_mut(&write.destination_id)?;
+        let attempt = write.attempt + 1;
+        match queue.qos.retry.backoff(attempt) {
+            Some(backoff) => {
+                queue.queued_bytes += write.data.data.len() as u64;
+                // The retry keeps its original age, so it still counts towards the latency target
+                queue.queue.push_front(Queued {
+                    data: write.data,
+                    enqueued: write.enqueued,
+                    not_before: now + backoff,
+                    attempt,
+                });
+                Some(backoff)
+            }
+            None => {
+                self.events.push(OutputEvent::WriteError(WriteFailure {
+                    error: format!(
+                        "Dropped record for {} after {} attempts: {}",
+                        write.destination_id, attempt, error
+                    ),
+                }));
+                None
+            }
+        }
+    }
+
+    /// Gets whether writes at a priority are held for a starving destination
+    pub fn is_held(&self, priority: Priority) -> bool {
+        self.held_below.is_some_and(|floor| priority < floor)
+    }
+
+    /// Gets the backpressure currently asked of producers
+    pub fn backpressure_status(&self) -> BackpressureStatus {
+        BackpressureStatus {
+            active: self.backpressure,
+        }
+    }
+
+    /// Gets the records waiting, by destination priority
+    pub fn queue_depths(&self) -> BTreeMap<Priority, usize> {
+        let mut depths = BTreeMap::new();
+        for queue in self.destinations.values() {
+            *depths.entry(queue.qos.priority).or_insert(0) += queue.queue.len();
+        }
+        depths
+    }
+
+    /// Gets the write metrics of every destination, with its queue depth under its priority
+    pub fn metrics(&self) -> Vec<WriteMetrics> {
+        self.destinations
+            .values()
+            .map(|queue| WriteMetrics {
+                queue_depths: BTreeMap::from([(queue.qos.priority, queue.queue.len())]),
+                ..queue.metrics.clone()
+            })
+            .collect()
+    }
+
+    /// Takes the events queued since the last call
+    pub fn take_events(&mut self) -> Vec<OutputEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::capture::capture_error::ConfigErrorKind;
+    use crate::capture_engine::output::compression::{CompressionAlgorithm, CompressionConfig};
+    use crate::capture_engine::output::naming::CollisionPolicy;
+    use crate::capture_engine::output::pcap_writer::TimestampResolution;
+    use crate::capture_engine::output::traits::{DestinationType, OutputMetadata, RetryPolicy};
+    use crate::capture_engine::output::verification::VerificationConfig;
+    use bytes::Bytes;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn record(len: usize) -> OutputData {
+        OutputData {
+            data: Bytes::from(vec![0u8; len]),
+            length: None,
+            metadata: OutputMetadata {
+                timestamp: 0,
+                routing_info: None,
+                compression: CompressionAlgorithm::None,
+            },
+        }
+    }
+
+    fn destination(
+        destination_type: DestinationType,
+        compression: CompressionConfig,
+    ) -> OutputDestinationConfig {
+        OutputDestinationConfig {
+            destination_id: "out".to_string(),
+            destination_type,
+            settings: HashMap::new(),
+            compression,
+            collision_policy: CollisionPolicy::default(),
+            verification: VerificationConfig::default(),
+            qos: QualityOfService::default(),
+        }
+    }
+
+    #[test]
+    fn test_configured_destination_compresses_queued_records() {
+        let now = Instant::now();
+        let mut scheduler = WriteScheduler::new();
+        let gzip = CompressionConfig {
+            algorithm: CompressionAlgorithm::Gzip,
+            ..CompressionConfig::default()
+        };
+        scheduler
+            .add_configured_destination(&destination(DestinationType::LocalFile, gzip), now)
+            .unwrap();
+        scheduler.enqueue("out", record(4096), now).unwrap();
+
+        let write = scheduler.next(now).unwrap();
+        assert_eq!(write.data.metadata.compression, CompressionAlgorithm::Gzip);
+        assert!(write.data.data.len() < 4096);
+        let metrics = &scheduler.metrics()[0];
+        assert_eq!(metrics.uncompressed_bytes, 4096);
+        assert_eq!(metrics.compressed_bytes, write.data.data.len() as u64);
+    }
+
+    #[test]
+    fn test_natively_compressing_destinations_skip_the_stage() {
+        let now = Instant::now();
+        for destination_type in [DestinationType::Kafka, DestinationType::S3] {
+            let mut scheduler = WriteScheduler::new();
+            let config = destination(destination_type, CompressionConfig::zstd(3));
+            scheduler.add_configured_destination(&config, now).unwrap();
+            scheduler.enqueue("out", record(4096), now).unwrap();
+
+            let write = scheduler.next(now).unwrap();
+            assert_eq!(write.data.metadata.compression, CompressionAlgorithm::None);
+            assert_eq!(write.data.data.len(), 4096);
+        }
+    }
+
+    #[test]
+    fn test_pcap_destination_rejects_compression() {
+        let pcap = DestinationType::Pcap {
+            path: PathBuf::from("capture.pcap"),
+            snaplen: 65535,
+            link_type: 1,
+            resolution: TimestampResolution::Microseconds,
+        };
+        let mut scheduler = WriteScheduler::new();
+        let err = scheduler
+            .add_configured_destination(
+                &destination(pcap.clone(), CompressionConfig::zstd(3)),
+                Instant::now(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue)
+        ));
+        assert!(scheduler.metrics().is_empty());
+        scheduler
+            .add_configured_destination(
+                &destination(pcap, CompressionConfig::default()),
+                Instant::now(),
+            )
+            .unwrap();
+    }
+
+    fn qos(priority: Priority) -> QualityOfService {
+        QualityOfService {
+            priority,
+            ..QualityOfService::default()
+        }
+    }
+
+    fn drain(scheduler: &mut WriteScheduler, now: Instant) -> Vec<String> {
+        std::iter::from_fn(|| scheduler.next(now))
+            .map(|write| write.destination_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_realtime_drained_before_background() {
+        let now = Instant::now();
+        let mut scheduler = WriteScheduler::new();
+        scheduler.add_destination("archive", qos(Priority::Background), now);
+        scheduler.add_destination("alerts", qos(Priority::Realtime), now);
+        scheduler.add_destination("bulk", qos(Priority::Normal), now);
+        scheduler.enqueue("archive", record(10), now).unwrap();
+        scheduler.enqueue("bulk", record(10), now).unwrap();
+        scheduler.enqueue("alerts", record(10), now).unwrap();
+        scheduler.enqueue("alerts", record(10), now).unwrap();
+        assert!(scheduler.enqueue("missing", record(10), now).is_err());
+
+        assert_eq!(
+            scheduler.queue_depths(),
+            BTreeMap::from([
+                (Priority::Background, 1),
+                (Priority::Normal, 1),
+                (Priority::Realtime, 2)
+            ])
+        );
+        assert_eq!(
+            drain(&mut scheduler, now),
+            vec!["alerts", "alerts", "bulk", "archive"]
+        );
+    }
+
+    #[test]
+    fn test_bandwidth_limit_yields_to_lower_priorities() {
+        let now = Instant::now();
+        let mut scheduler = WriteScheduler::new();
+        let limited = QualityOfService {
+            bandwidth_limit: Some(100),
+            ..qos(Priority::Realtime)
+        };
+        scheduler.add_destination("alerts", limited, now);
+        scheduler.add_destination("archive", qos(Priority::Background), now);
+        for _ in 0..2 {
+            scheduler.enqueue("alerts", record(100), now).unwrap();
+            scheduler.enqueue("archive", record(100), now).unwrap();
+        }
+
+        assert_eq!(
+            drain(&mut scheduler, now),
+            vec!["alerts", "archive", "archive"]
+        );
+        let later = now + Duration::from_secs(1);
+        assert_eq!(drain(&mut scheduler, later), vec!["alerts"]);
+    }
+
+    #[test]
+    fn test_starving_destination_holds_lower_priorities() {
+        let now = Instant::now();
+        let mut scheduler = WriteScheduler::new();
+        let latency_bound = QualityOfService {
+            max_latency: Some(Duration::from_millis(10)),
+            bandwidth_limit: Some(100),
+            ..qos(Priority::High)
+        };
+        scheduler.add_destination("alerts", latency_bound, now);
+        scheduler.add_destination("archive", qos(Priority::Background), now);
+        for _ in 0..2 {
+            scheduler.enqueue("alerts", record(1000), now).unwrap();
+        }
+        scheduler.enqueue("archive", record(10), now).unwrap();
+
+        // The second alert waits for bandwidth and starves; the archive write is held
+        assert_eq!(scheduler.next(now).unwrap().destination_id, "alerts");
+        let starving = now + Duration::from_millis(20);
+        assert!(scheduler.next(starving).is_none());
+        assert!(scheduler.is_held(Priority::Background));
+        assert!(!scheduler.is_held(Priority::High));
+        assert!(scheduler.backpressure_status().active);
+        assert!(matches!(
+            scheduler.take_events().as_slice(),
+            [OutputEvent::BackpressureEvent(status)] if status.active
+        ));
+        assert_eq!(scheduler.queue_depths()[&Priority::Background], 1);
+
+        // Once the alert is written the hold is released and the archive record follows
+        let refilled = now + Duration::from_secs(10);
+        assert_eq!(drain(&mut scheduler, refilled), vec!["alerts", "archive"]);
+        assert!(!scheduler.backpressure_status().active);
+        assert!(matches!(
+            scheduler.take_events().as_slice(),
+            [OutputEvent::BackpressureEvent(status)] if !status.active
+        ));
+    }
+
+    #[test]
+    fn test_failed_writes_retry_then_drop() {
+        let now = Instant::now();
+        let mut scheduler = WriteScheduler::new();
+        let retrying = QualityOfService {
+            retry: RetryPolicy {
+                max_retries: 1,
+                initial_backoff: Duration::from_millis(50),
+                max_backoff: Duration::from_secs(1),
+            },
+            ..qos(Priority::Normal)
+        };
+        scheduler.add_destination("bulk", retrying, now);
+        scheduler.enqueue("bulk", record(10), now).unwrap();
+        scheduler.enqueue("bulk", record(20), now).unwrap();
+
+        let write = scheduler.next(now).unwrap();
+        assert_eq!(
+            scheduler.failed(write, "timeout", now),
+            Some(Duration::from_millis(50))
+        );
+        // The retried record keeps its place ahead of newer ones
+        assert!(scheduler.next(now).is_none());
+        let retry_at = now + Duration::from_millis(50);
+        let write = scheduler.next(retry_at).unwrap();
+        assert_eq!((write.attempt, write.data.data.len()), (1, 10));
+        assert_eq!(scheduler.failed(write, "timeout", retry_at), None);
+        assert!(matches!(
+            scheduler.take_events().as_slice(),
+            [OutputEvent::WriteError(failure)] if failure.error.contains("after 2 attempts")
+        ));
+
+        let write = scheduler.next(retry_at).unwrap();
+        scheduler.completed(write, Duration::from_millis(4));
+        let metrics = scheduler.metrics();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].bytes_written, 20);
+        assert_eq!(metrics[0].records_written, 1);
+        assert_eq!(
+            metrics[0].queue_depths,
+            BTreeMap::from([(Priority::Normal, 0)])
+        );
+    }
+
+    #[test]
+    fn test_retried_write_keeps_its_enqueue_time() {
+        let now = Instant::now();
+        let mut scheduler = WriteScheduler::new();
+        let latency_bound = QualityOfService {
+            max_latency: Some(Duration::from_millis(100)),
+            retry: RetryPolicy {
+                max_retries: 1,
+                initial_backoff: Duration::from_millis(50),
+                max_backoff: Duration::from_secs(1),
+            },
+            ..qos(Priority::High)
+        };
+        scheduler.add_destination("alerts", latency_bound, now);
+        scheduler.add_destination("archive", qos(Priority::Background), now);
+        scheduler.enqueue("alerts", record(10), now).unwrap();
+        scheduler.enqueue("archive", record(10), now).unwrap();
+
+        let write = scheduler.next(now).unwrap();
+        let failed_at = now + Duration::from_millis(80);
+        assert!(scheduler.failed(write, "timeout", failed_at).is_some());
+
+        // Counted from its first enqueue the alert is starving while it waits out the backoff
+        let waiting = now + Duration::from_millis(120);
+        assert!(scheduler.next(waiting).is_none());
+        assert!(scheduler.is_held(Priority::Background));
+
+        let write = scheduler
+            .next(failed_at + Duration::from_millis(50))
+            .unwrap();
+        assert_eq!(
+            (write.destination_id.as_str(), write.enqueued),
+            ("alerts", now)
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_backlog_applies_backpressure() {
+        let now = Instant::now();
+        let mut scheduler = WriteScheduler::new();
+        let limited = QualityOfService {
+            bandwidth_limit: Some(1000),
+            burst_bytes: Some(500),
+            ..qos(Priority::Normal)
+        };
+        scheduler.add_destination("bulk", limited, now);
+
+        // Burst plus one second of bandwidth may queue without backpressure
+        scheduler.enqueue("bulk", record(600), now).unwrap();
+        scheduler.enqueue("bulk", record(600), now).unwrap();
+        assert!(scheduler.take_events().is_empty());
+        scheduler.enqueue("bulk", record(600), now).unwrap();
+        assert!(scheduler.backpressure_status().active);
+        assert!(matches!(
+            scheduler.take_events().as_slice(),
+            [OutputEvent::BackpressureEvent(status)] if status.active
+        ));
+
+        // Writes are paced by the limiter and the backpressure ends as the backlog drains
+        assert!(scheduler.next(now).is_some());
+        assert!(!scheduler.backpressure_status().active);
+        assert!(scheduler.next(now).is_none());
+        assert!(scheduler.next(now + Duration::from_millis(599)).is_none());
+        assert!(scheduler.next(now + Duration::from_millis(600)).is_some());
+        assert!(matches!(
+            scheduler.take_events().as_slice(),
+            [OutputEvent::BackpressureEvent(status)] if !status.active
+        ));
+    }
+}
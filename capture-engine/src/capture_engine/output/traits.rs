@@ -2,11 +2,17 @@
 /// `OutputManager` sends processed packet data to destinations (e.g., S3 buckets, Kafka topics, local files).
 use async_trait::async_trait;
 use bytes::Bytes;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
 
+use crate::capture_engine::output::compression::{CompressionAlgorithm, CompressionConfig};
+use crate::capture_engine::output::naming::CollisionPolicy;
+use crate::capture_engine::output::pcap_writer::TimestampResolution;
+use crate::capture_engine::output::verification::VerificationConfig;
 use crate::traits::{
-    BackpressureControl, Cleanup, Error, EventHandler, Lifecycle, PressureAware, RateLimiter,
-    ResourceManager,
+    BackpressureControl, Cleanup, Error, EventHandler, Lifecycle, Packet, PressureAware,
+    RateLimiter, ResourceManager,
 };
 
 /// Events specific to output management.
@@ -29,6 +35,7 @@ pub trait OutputManager:
     + BackpressureControl
     + ResourceManager
     + Cleanup
+    + FlushControl
     + Send
     + Sync
 {
@@ -39,22 +46,66 @@ pub trait OutputManager:
     async fn flush(&mut self) -> Result<(), Error>;
 }
 
+/// How urgently buffered output is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushMode {
+    /// Regular flush, e.g. at a batch or rotation boundary.
+    #[default]
+    Normal,
+    /// Write everything buffered as fast as possible, skipping optional work such as
+    /// verification, because the instance is about to go away.
+    Emergency,
+    /// Write everything buffered and wait until the destination has acknowledged it.
+    Forced,
+}
+
+/// Flushing of buffered output.
+#[async_trait]
+pub trait FlushControl: Send + Sync {
+    /// Flushes buffered output in the given mode.
+    async fn flush_with_mode(&mut self, mode: FlushMode) -> Result<(), Error>;
+}
+
 /// Represents data to be sent to an output destination.
 #[derive(Debug, Clone)]
 pub struct OutputData {
     pub data: Bytes,
+    /// On-wire length of the packet `data` was captured from; `None` if `data` is complete.
+    pub length: Option<usize>,
     pub metadata: OutputMetadata,
 }
 
+impl OutputData {
+    /// Copies a captured packet into a record, keeping its on-wire length.
+    pub fn from_packet(packet: &Packet<'_>) -> Self {
+        Self {
+            data: Bytes::copy_from_slice(packet.data),
+            length: Some(packet.length),
+            metadata: OutputMetadata {
+                timestamp: packet.timestamp,
+                routing_info: None,
+                compression: CompressionAlgorithm::None,
+            },
+        }
+    }
+
+    /// Returns the on-wire length, which is the payload length unless the packet was truncated.
+    pub fn wire_length(&self) -> usize {
+        self.length.unwrap_or(0).max(self.data.len())
+    }
+}
+
 /// Metadata associated with output data.
 #[derive(Debug, Clone)]
 pub struct OutputMetadata {
     pub timestamp: u64,
     pub routing_info: Option<RoutingInfo>,
+    /// Algorithm the payload is compressed with; `None` if it is stored as captured.
+    pub compression: CompressionAlgorithm,
 }
 
 /// Information for routing output data.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RoutingInfo {
     pub destination_ids: Vec<String>,
 }
@@ -65,6 +116,34 @@ pub struct OutputDestinationConfig {
     pub destination_id: String,
     pub destination_type: DestinationType,
     pub settings: HashMap<String, String>,
+    pub compression: CompressionConfig,
+    pub collision_policy: CollisionPolicy,
+    /// Read-back check run as each object or capture file is finalized.
+    pub verification: VerificationConfig,
+    pub qos: QualityOfService,
+}
+
+/// Scheduling class of a destination; higher classes are written first under contention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    Background,
+    #[default]
+    Normal,
+    High,
+    Realtime,
+}
+
+/// Delivery guarantees requested for a destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QualityOfService {
+    pub priority: Priority,
+    /// Longest a record may wait to be written before the destination counts as starving.
+    pub max_latency: Option<Duration>,
+    /// Bytes per second written at most; unset is unlimited.
+    pub bandwidth_limit: Option<u64>,
+    /// Bytes that may be written at once above the limit; unset allows one second's worth.
+    pub burst_bytes: Option<u64>,
+    pub retry: RetryPolicy,
 }
 
 /// Types of output destinations.
@@ -74,16 +153,110 @@ pub enum DestinationType {
     LocalFile,
     NetworkStream,
     Kafka,
+    /// Classic libpcap file; rotated files get a `-N` suffix before the extension.
+    Pcap {
+        path: PathBuf,
+        snaplen: u32,
+        link_type: u32,
+        resolution: TimestampResolution,
+    },
+    /// PCAPNG file with a single interface.
+    PcapNg {
+        path: PathBuf,
+        snaplen: u32,
+        link_type: u32,
+        resolution: TimestampResolution,
+    },
 }
 
 /// Status of an output destination.
 #[derive(Debug, Clone)]
 pub struct DestinationStatus {
     pub destination_id: String,
-    pub status: String,
+    pub status: DestinationState,
     pub last_error: Option<String>,
 }
 
+/// Connectivity of an output destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DestinationState {
+    /// Writes are being acknowledged.
+    #[default]
+    Active,
+    /// Writes are failing but being retried.
+    Degraded,
+    /// Writes were given up on or the destination is unreachable.
+    Failed,
+}
+
+/// Retries of failed writes with exponential backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 never retries.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before retry `retry` (1-based), or None once retries are exhausted.
+    pub fn backoff(&self, retry: u32) -> Option<Duration> {
+        if retry == 0 || retry > self.max_retries {
+            return None;
+        }
+        let factor = 2u32.saturating_pow(retry - 1);
+        Some(
+            self.initial_backoff
+                .saturating_mul(factor)
+                .min(self.max_backoff),
+        )
+    }
+}
+
+/// Volume and time spent writing to one destination.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteMetrics {
+    pub destination_id: String,
+    pub bytes_written: u64,
+    pub records_written: u64,
+    pub objects_completed: u64,
+    /// Payload bytes before and after compression.
+    pub uncompressed_bytes: u64,
+    pub compressed_bytes: u64,
+    /// Time spent in destination calls, excluding time waiting for data.
+    pub write_time: Duration,
+    /// Records waiting to be written, by destination priority.
+    pub queue_depths: BTreeMap<Priority, usize>,
+}
+
+impl WriteMetrics {
+    /// Returns bytes written per second of write time, or 0 before anything was written.
+    pub fn throughput_bytes_per_sec(&self) -> f64 {
+        match self.write_time.as_secs_f64() {
+            secs if secs > 0.0 => self.bytes_written as f64 / secs,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns uncompressed over compressed bytes, or 1 before anything was compressed.
+    pub fn compression_ratio(&self) -> f64 {
+        match self.compressed_bytes {
+            0 => 1.0,
+            compressed => self.uncompressed_bytes as f64 / compressed as f64,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct BufferThresholdEvent {
     pub threshold_reached: bool,
@@ -94,6 +267,31 @@ pub struct RotationTrigger {
     pub reason: String,
 }
 
+/// Limits that close the current output file and start a new one; unset limits never rotate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RotationConfig {
+    pub max_bytes: Option<u64>,
+    pub max_packets: Option<u64>,
+    /// Span of packet timestamps covered by one file.
+    pub max_duration: Option<Duration>,
+}
+
+impl RotationConfig {
+    /// Returns why a file holding `bytes` and `packets` over `span` must rotate, if it must.
+    pub fn rotation_reason(&self, bytes: u64, packets: u64, span: Duration) -> Option<String> {
+        if let Some(max) = self.max_bytes.filter(|max| bytes >= *max) {
+            return Some(format!("size {} bytes reached limit {}", bytes, max));
+        }
+        if let Some(max) = self.max_packets.filter(|max| packets >= *max) {
+            return Some(format!("{} packets reached limit {}", packets, max));
+        }
+        if let Some(max) = self.max_duration.filter(|max| span >= *max) {
+            return Some(format!("span {:?} reached limit {:?}", span, max));
+        }
+        None
+    }
+}
+
 #[derive(Debug)]
 pub struct WriteFailure {
     pub error: String,
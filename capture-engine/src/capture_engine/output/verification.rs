@@ -0,0 +1,438 @@
+// output/verification.rs
+//! Optional read-back verification of finalized output objects against their streaming hash.
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io;
+
+use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::output::traits::{OutputEvent, WriteFailure};
+
+/// SHA-256 digest of an output object's content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentDigest(pub [u8; 32]);
+
+impl ContentDigest {
+    /// Hashes a complete buffer
+    ///
+    /// # Arguments
+    /// * `data` - Content to hash
+    ///
+    /// # Returns
+    /// The SHA-256 digest of the content
+    pub fn of(data: &[u8]) -> Self {
+        let mut hasher = ContentHasher::new();
+        hasher.update(data);
+        hasher.finish()
+    }
+
+    /// Formats the digest as lowercase hex
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+impl fmt::Display for ContentDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// Streaming content hash computed as an object is written
+#[derive(Debug, Clone, Default)]
+pub struct ContentHasher {
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl ContentHasher {
+    /// Creates a hasher with no content
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a chunk of written content
+    ///
+    /// # Arguments
+    /// * `chunk` - Bytes written to the object, in order
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        self.bytes += chunk.len() as u64;
+    }
+
+    /// Gets the number of bytes hashed so far
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Completes the hash
+    ///
+    /// # Returns
+    /// The digest of everything passed to `update`
+    pub fn finish(self) -> ContentDigest {
+        ContentDigest(self.hasher.finalize().into())
+    }
+}
+
+/// Hashes content copied in with `std::io::copy`, so read-back never holds a whole object
+impl io::Write for ContentHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Read-back verification settings for a destination
+///
+/// Verification costs a full read of every object unless the destination reports its own
+/// SHA-256, so it is disabled by default.
+///
+/// # Fields
+/// * `enabled` - Verify each object after it is finalized
+/// * `rewrite_on_mismatch` - Rewrite an object whose read-back does not match
+/// * `max_rewrites` - Rewrites attempted before the mismatch is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationConfig {
+    pub enabled: bool,
+    pub rewrite_on_mismatch: bool,
+    pub max_rewrites: u32,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rewrite_on_mismatch: false,
+            max_rewrites: 1,
+        }
+    }
+}
+
+impl VerificationConfig {
+    /// Creates a configuration that verifies and rewrites once on mismatch
+    pub fn verify_and_rewrite() -> Self {
+        Self {
+            enabled: true,
+            rewrite_on_mismatch: true,
+            max_rewrites: 1,
+        }
+    }
+}
+
+/// Storage holding finalized objects that can be verified
+pub trait VerifiableStore {
+    /// Gets the SHA-256 the storage computed for an object, if it records one
+    ///
+    /// # Arguments
+    /// * `key` - Object key
+    ///
+    /// # Returns
+    /// The stored checksum, or None if verification must read the object back
+    fn stored_sha256(&self, key: &str) -> Result<Option<ContentDigest>, CaptureError>;
+
+    /// Reads an object back in full
+    ///
+    /// # Arguments
+    /// * `key` - Object key
+    ///
+    /// # Returns
+    /// The object's content
+    fn read_back(&self, key: &str) -> Result<Vec<u8>, CaptureError>;
+
+    /// Replaces an object's content
+    ///
+    /// # Arguments
+    /// * `key` - Object key
+    /// * `data` - Content to write
+    fn rewrite(&mut self, key: &str, data: &[u8]) -> Result<(), CaptureError>;
+}
+
+/// How an object's content was checked
+///
+/// # Variants
+/// * `StoredChecksum` - The storage's own SHA-256 was compared
+/// * `Readback` - The object was read back and hashed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationMethod {
+    StoredChecksum,
+    Readback,
+}
+
+/// Result of verifying an object
+///
+/// # Variants
+/// * `Skipped` - Verification is disabled for the destination
+/// * `Verified` - The content matched, after `rewrites` rewrites
+/// * `Mismatch` - The content did not match and was not repaired
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationOutcome {
+    Skipped,
+    Verified {
+        method: VerificationMethod,
+        rewrites: u32,
+    },
+    Mismatch {
+        expected: ContentDigest,
+        actual: ContentDigest,
+        rewrites: u32,
+    },
+}
+
+impl VerificationOutcome {
+    /// Checks whether the object is known to hold the written content
+    pub fn is_verified(&self) -> bool {
+        matches!(self, VerificationOutcome::Verified { .. })
+    }
+
+    /// Builds the alert raised for an unrepaired mismatch
+    ///
+    /// # Arguments
+    /// * `destination_id` - Destination the object was written to
+    /// * `key` - Object key
+    ///
+    /// # Returns
+    /// A `WriteError` event for mismatches, None otherwise
+    pub fn to_event(&self, destination_id: &str, key: &str) -> Option<OutputEvent> {
+        match self {
+            VerificationOutcome::Mismatch {
+                expected,
+                actual,
+                rewrites,
+            } => Some(OutputEvent::WriteError(WriteFailure {
+                error: format!(
+                    "Checksum mismatch for {} on {}: expected {}, read back {} after {} rewrites",
+                    key, destination_id, expected, actual, rewrites
+                ),
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// Verifies finalized objects against their streaming content hash
+///
+/// # Fields
+/// * `config` - Verification settings of the destination
+#[derive(Debug, Clone, Default)]
+pub struct ObjectVerifier {
+    config: VerificationConfig,
+}
+
+impl ObjectVerifier {
+    /// Creates a verifier
+    ///
+    /// # Arguments
+    /// * `config` - Verification settings of the destination
+    ///
+    /// # Returns
+    /// A new ObjectVerifier instance
+    pub fn new(config: VerificationConfig) -> Self {
+        Self { config }
+    }
+
+    /// Verifies a finalized object
+    ///
+    /// A checksum recorded by the storage is trusted when present; otherwise the object is
+    /// read back and hashed. On mismatch the object is rewritten from `content` if rewriting is
+    /// enabled and content is available, then verified again.
+    ///
+    /// # Arguments
+    /// * `store` - Storage holding the object
+    /// * `key` - Object key
+    /// * `expected` - Streaming hash of the content written
+    /// * `content` - The written content, needed to rewrite on mismatch
+    ///
+    /// # Returns
+    /// The verification outcome, or an error if the storage could not be read
+    pub fn verify(
+        &self,
+        store: &mut dyn VerifiableStore,
+        key: &str,
+        expected: ContentDigest,
+        content: Option<&[u8]>,
+    ) -> Result<VerificationOutcome, CaptureError> {
+        if !self.config.enabled {
+            return Ok(VerificationOutcome::Skipped);
+        }
+
+        let mut rewrites = 0;
+        loop {
+            let (method, actual) = match store.stored_sha256(key)? {
+                Some(digest) => (VerificationMethod::StoredChecksum, digest),
+                None => (
+                    VerificationMethod::Readback,
+                    ContentDigest::of(&store.read_back(key)?),
+                ),
+            };
+            if actual == expected {
+                return Ok(VerificationOutcome::Verified { method, rewrites });
+            }
+
+            match content {
+                Some(data)
+                    if self.config.rewrite_on_mismatch && rewrites < self.config.max_rewrites =>
+                {
+                    store.rewrite(key, data)?;
+                    rewrites += 1;
+                }
+                _ => {
+                    return Ok(VerificationOutcome::Mismatch {
+                        expected,
+                        actual,
+                        rewrites,
+                    })
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Object store that can corrupt the next N writes and optionally report checksums
+    #[derive(Default)]
+    struct MemoryStore {
+        objects: HashMap<String, Vec<u8>>,
+        corrupt_writes: u32,
+        reports_checksum: bool,
+        rewrites: u32,
+    }
+
+    impl MemoryStore {
+        fn put(&mut self, key: &str, data: &[u8]) {
+            let mut stored = data.to_vec();
+            if self.corrupt_writes > 0 {
+                self.corrupt_writes -= 1;
+                stored[0] ^= 0xff;
+            }
+            self.objects.insert(key.to_string(), stored);
+        }
+    }
+
+    impl VerifiableStore for MemoryStore {
+        fn stored_sha256(&self, key: &str) -> Result<Option<ContentDigest>, CaptureError> {
+            Ok(self
+                .reports_checksum
+                .then(|| ContentDigest::of(&self.objects[key])))
+        }
+
+        fn read_back(&self, key: &str) -> Result<Vec<u8>, CaptureError> {
+            Ok(self.objects[key].clone())
+        }
+
+        fn rewrite(&mut self, key: &str, data: &[u8]) -> Result<(), CaptureError> {
+            self.rewrites += 1;
+            self.put(key, data);
+            Ok(())
+        }
+    }
+
+    fn written(store: &mut MemoryStore, key: &str, chunks: &[&[u8]]) -> (ContentDigest, Vec<u8>) {
+        let mut hasher = ContentHasher::new();
+        let mut content = Vec::new();
+        for chunk in chunks {
+            hasher.update(chunk);
+            content.extend_from_slice(chunk);
+        }
+        store.put(key, &content);
+        (hasher.finish(), content)
+    }
+
+    #[test]
+    fn test_matching_readback_verifies() {
+        let mut store = MemoryStore::default();
+        let (digest, _) = written(&mut store, "a.pcap", &[b"chunk one ", b"chunk two"]);
+        assert_eq!(digest, ContentDigest::of(b"chunk one chunk two"));
+
+        let verifier = ObjectVerifier::new(VerificationConfig {
+            enabled: true,
+            ..Default::default()
+        });
+        let outcome = verifier.verify(&mut store, "a.pcap", digest, None).unwrap();
+        assert_eq!(
+            outcome,
+            VerificationOutcome::Verified {
+                method: VerificationMethod::Readback,
+                rewrites: 0
+            }
+        );
+        assert!(outcome.to_event("s3", "a.pcap").is_none());
+
+        store.reports_checksum = true;
+        let outcome = verifier.verify(&mut store, "a.pcap", digest, None).unwrap();
+        assert!(matches!(
+            outcome,
+            VerificationOutcome::Verified {
+                method: VerificationMethod::StoredChecksum,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_corruption_raises_write_error() {
+        let mut store = MemoryStore {
+            corrupt_writes: 1,
+            ..Default::default()
+        };
+        let (digest, _) = written(&mut store, "b.pcap", &[b"payload"]);
+
+        let verifier = ObjectVerifier::new(VerificationConfig {
+            enabled: true,
+            ..Default::default()
+        });
+        let outcome = verifier.verify(&mut store, "b.pcap", digest, None).unwrap();
+        assert!(!outcome.is_verified());
+        let Some(OutputEvent::WriteError(failure)) = outcome.to_event("s3", "b.pcap") else {
+            panic!("expected a write error, got {:?}", outcome);
+        };
+        assert!(failure.error.contains(&digest.to_hex()));
+    }
+
+    #[test]
+    fn test_mismatch_rewritten_until_limit() {
+        let mut store = MemoryStore {
+            corrupt_writes: 2,
+            ..Default::default()
+        };
+        let (digest, content) = written(&mut store, "c.pcap", &[b"payload"]);
+        let verifier = ObjectVerifier::new(VerificationConfig::verify_and_rewrite());
+
+        // The rewrite is corrupted too, so the single allowed rewrite does not help
+        let outcome = verifier
+            .verify(&mut store, "c.pcap", digest, Some(&content))
+            .unwrap();
+        assert!(matches!(
+            outcome,
+            VerificationOutcome::Mismatch { rewrites: 1, .. }
+        ));
+
+        let outcome = verifier
+            .verify(&mut store, "c.pcap", digest, Some(&content))
+            .unwrap();
+        assert_eq!(
+            outcome,
+            VerificationOutcome::Verified {
+                method: VerificationMethod::Readback,
+                rewrites: 1
+            }
+        );
+        assert_eq!(store.rewrites, 2);
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let mut store = MemoryStore::default();
+        let (digest, _) = written(&mut store, "d.pcap", &[b"x"]);
+        let outcome = ObjectVerifier::default()
+            .verify(&mut store, "d.pcap", digest, None)
+            .unwrap();
+        assert_eq!(outcome, VerificationOutcome::Skipped);
+    }
+}
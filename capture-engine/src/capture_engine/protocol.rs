@@ -1 +1,7 @@
+pub mod checksum;
+pub mod flow;
+pub mod flow_guard;
+pub mod headers;
+pub mod reassembly;
+pub mod tcp_state;
 pub mod traits;
@@ -0,0 +1,332 @@
+// protocol/checksum.rs
+use crate::capture_engine::protocol::flow::{IPPROTO_TCP, IPPROTO_UDP};
+
+/// Shortest input summed with SIMD; below this the setup costs more than it saves.
+const SIMD_MIN_LEN: usize = 64;
+
+/// Sums `data` as big-endian 16-bit words, padding an odd trailing byte with zero.
+///
+/// The sum is left unfolded so sums of several parts can be added before `fold`. Each part
+/// other than the last must have an even length. Uses AVX2 when the CPU supports it.
+pub fn ones_complement_sum(data: &[u8]) -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if data.len() >= SIMD_MIN_LEN && std::arch::is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was checked above
+            return unsafe { avx2::sum(data) };
+        }
+    }
+    scalar_sum(data)
+}
+
+/// Folds a one's complement sum to 16 bits.
+pub fn fold(mut sum: u64) -> u16 {
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum as u16
+}
+
+/// Whether `packet` starts with an IPv4 header whose checksum is correct.
+///
+/// Returns None when the packet does not start with a complete IPv4 header.
+pub fn verify_ipv4_header(packet: &[u8]) -> Option<bool> {
+    if packet.first()? >> 4 != 4 {
+        return None;
+    }
+    let ihl = (packet[0] & 0x0f) as usize * 4;
+    if ihl < 20 {
+        return None;
+    }
+    let header = packet.get(..ihl)?;
+    Some(fold(ones_complement_sum(header)) == 0xffff)
+}
+
+/// Whether the TCP or UDP checksum of an IP packet is correct.
+///
+/// `packet` starts at the IPv4 or IPv6 header. Returns None when the packet does not carry
+/// `protocol`, is truncated, or is a fragment, whose checksum covers data in other packets.
+/// A zero UDP checksum means none was computed, which IPv4 allows and IPv6 does not.
+pub fn verify_transport(packet: &[u8], protocol: u8) -> Option<bool> {
+    let segment = match packet.first()? >> 4 {
+        4 => Segment::ipv4(packet)?,
+        6 => Segment::ipv6(packet)?,
+        _ => return None,
+    };
+    if segment.protocol != protocol {
+        return None;
+    }
+    let checksum_at = match protocol {
+        IPPROTO_TCP if segment.data.len() >= 20 => 16,
+        IPPROTO_UDP if segment.data.len() >= 8 => 6,
+        _ => return None,
+    };
+    if protocol == IPPROTO_UDP && segment.data[checksum_at..checksum_at + 2] == [0, 0] {
+        return Some(!segment.ipv6);
+    }
+    let sum = segment.pseudo_header_sum + ones_complement_sum(segment.data);
+    Some(fold(sum) == 0xffff)
+}
+
+/// A transport segment and the sum of its pseudo-header.
+struct Segment<'a> {
+    protocol: u8,
+    data: &'a [u8],
+    pseudo_header_sum: u64,
+    ipv6: bool,
+}
+
+impl<'a> Segment<'a> {
+    fn ipv4(packet: &'a [u8]) -> Option<Self> {
+        let ihl = (packet[0] & 0x0f) as usize * 4;
+        let header = packet.get(..ihl).filter(|_| ihl >= 20)?;
+        // More-fragments flag or a fragment offset
+        if u16::from_be_bytes([header[6], header[7]]) & 0x3fff != 0 {
+            return None;
+        }
+        let total_len = u16::from_be_bytes([header[2], header[3]]) as usize;
+        // Trailing link-layer padding lies past the total length
+        let data = packet.get(ihl..total_len)?;
+        let protocol = header[9];
+        Some(Self {
+            protocol,
+            data,
+            pseudo_header_sum: ones_complement_sum(&header[12..20])
+                + u64::from(protocol)
+                + data.len() as u64,
+            ipv6: false,
+        })
+    }
+
+    fn ipv6(packet: &'a [u8]) -> Option<Self> {
+        let header = packet.get(..40)?;
+        let payload_len = u16::from_be_bytes([header[4], header[5]]) as usize;
+        if payload_len == 0 {
+            // Jumbograms carry their length in a hop-by-hop option
+            return None;
+        }
+        let mut next = header[6];
+        let mut offset = 40;
+        loop {
+            let len = match next {
+                // Hop-by-hop and destination options
+                0 | 60 => (*packet.get(offset + 1)? as usize + 1) * 8,
+                // Routing; the pseudo-header uses the final destination, which is only in the
+                // fixed header once no segments are left
+                43 if *packet.get(offset + 3)? == 0 => (*packet.get(offset + 1)? as usize + 1) * 8,
+                // Authentication header
+                51 => (*packet.get(offset + 1)? as usize + 2) * 4,
+                // Fragments and routing headers with segments left
+                43 | 44 => return None,
+                _ => break,
+            };
+            next = *packet.get(offset)?;
+            offset += len;
+        }
+        let data = packet.get(offset..40 + payload_len)?;
+        Some(Self {
+            protocol: next,
+            data,
+            pseudo_header_sum: ones_complement_sum(&header[8..40])
+                + u64::from(next)
+                + data.len() as u64,
+            ipv6: true,
+        })
+    }
+}
+
+fn scalar_sum(data: &[u8]) -> u64 {
+    let mut words = data.chunks_exact(8);
+    let mut sum = 0u64;
+    for chunk in &mut words {
+        let word = u64::from_be_bytes(chunk.try_into().unwrap());
+        sum += (word >> 48) + ((word >> 32) & 0xffff) + ((word >> 16) & 0xffff) + (word & 0xffff);
+    }
+    let mut pairs = words.remainder().chunks_exact(2);
+    for pair in &mut pairs {
+        sum += u64::from(u16::from_be_bytes([pair[0], pair[1]]));
+    }
+    if let [last] = pairs.remainder() {
+        sum += u64::from(*last) << 8;
+    }
+    sum
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    /// 32-byte blocks summed before the 32-bit lanes are drained; each block adds at most
+    /// 2 * 0xffff to a lane.
+    const BLOCKS_PER_DRAIN: usize = 16384;
+
+    /// Sums `data` like `scalar_sum`.
+    ///
+    /// Words are loaded in native little-endian order and byte-swapped once after folding;
+    /// the one's complement sum is independent of byte order (RFC 1071).
+    ///
+    /// # Safety
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn sum(data: &[u8]) -> u64 {
+        let zero = _mm256_setzero_si256();
+        let mut blocks = data.chunks_exact(32);
+        let mut swapped = 0u64;
+        loop {
+            let mut lanes = zero;
+            let mut summed = 0;
+            for block in blocks.by_ref().take(BLOCKS_PER_DRAIN) {
+                let words = _mm256_loadu_si256(block.as_ptr() as *const __m256i);
+                lanes = _mm256_add_epi32(lanes, _mm256_unpacklo_epi16(words, zero));
+                lanes = _mm256_add_epi32(lanes, _mm256_unpackhi_epi16(words, zero));
+                summed += 1;
+            }
+            if summed == 0 {
+                break;
+            }
+            let mut drained = [0u32; 8];
+            _mm256_storeu_si256(drained.as_mut_ptr() as *mut __m256i, lanes);
+            swapped += drained.iter().map(|&lane| u64::from(lane)).sum::<u64>();
+        }
+        u64::from(super::fold(swapped).swap_bytes()) + super::scalar_sum(blocks.remainder())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // 192.168.0.1:12345 -> 192.168.0.199:53, payload "hello"
+    const IPV4_UDP: &str = "450000211c46400040119c6dc0a80001c0a800c7\
+                            30390035000d097b68656c6c6f";
+    // 192.168.0.1:443 -> 192.168.0.199:50000, payload "abc"
+    const IPV4_TCP: &str = "4500002b1c46400040069c6ec0a80001c0a800c7\
+                            01bbc350000000010000000250180400a03f0000616263";
+    // [2001:db8::1]:12345 -> [2001:db8::2]:53, payload "hello"
+    const IPV6_UDP: &str = "60000000000d1140\
+                            20010db8000000000000000000000001\
+                            20010db8000000000000000000000002\
+                            30390035000d301f68656c6c6f";
+    // [2001:db8::1]:443 -> [2001:db8::2]:50000, payload "abc"
+    const IPV6_TCP: &str = "6000000000170640\
+                            20010db8000000000000000000000001\
+                            20010db8000000000000000000000002\
+                            01bbc350000000010000000250180400c6e30000616263";
+
+    #[test]
+    fn test_ipv4_header_vector() {
+        let header = hex("45000073000040004011b861c0a80001c0a800c7");
+        assert_eq!(verify_ipv4_header(&header), Some(true));
+
+        let mut corrupted = header.clone();
+        corrupted[8] ^= 0x01;
+        assert_eq!(verify_ipv4_header(&corrupted), Some(false));
+        assert_eq!(verify_ipv4_header(&header[..19]), None);
+    }
+
+    #[test]
+    fn test_odd_length_segments_over_both_ip_versions() {
+        for (packet, protocol) in [
+            (IPV4_UDP, IPPROTO_UDP),
+            (IPV4_TCP, IPPROTO_TCP),
+            (IPV6_UDP, IPPROTO_UDP),
+            (IPV6_TCP, IPPROTO_TCP),
+        ] {
+            let mut packet = hex(packet);
+            assert_eq!(verify_transport(&packet, protocol), Some(true));
+
+            // Link-layer padding past the IP length is not checksummed
+            packet.extend_from_slice(&[0xaa; 6]);
+            assert_eq!(verify_transport(&packet, protocol), Some(true));
+
+            // The odd trailing byte is part of the sum
+            let last = packet.len() - 7;
+            packet[last] ^= 0x01;
+            assert_eq!(verify_transport(&packet, protocol), Some(false));
+        }
+    }
+
+    #[test]
+    fn test_pseudo_header_covers_addresses() {
+        let mut ipv4 = hex(IPV4_UDP);
+        // Rewrite the source address and fix only the IP header checksum
+        ipv4[15] = 0x02;
+        ipv4[10..12].copy_from_slice(&[0, 0]);
+        let header_checksum = !fold(ones_complement_sum(&ipv4[..20]));
+        ipv4[10..12].copy_from_slice(&header_checksum.to_be_bytes());
+        assert_eq!(verify_ipv4_header(&ipv4), Some(true));
+        assert_eq!(verify_transport(&ipv4, IPPROTO_UDP), Some(false));
+
+        let mut ipv6 = hex(IPV6_TCP);
+        ipv6[39] = 0x03;
+        assert_eq!(verify_transport(&ipv6, IPPROTO_TCP), Some(false));
+    }
+
+    #[test]
+    fn test_zero_udp_checksum() {
+        let mut ipv4 = hex(IPV4_UDP);
+        ipv4[26..28].copy_from_slice(&[0, 0]);
+        assert_eq!(verify_transport(&ipv4, IPPROTO_UDP), Some(true));
+
+        let mut ipv6 = hex(IPV6_UDP);
+        ipv6[46..48].copy_from_slice(&[0, 0]);
+        assert_eq!(verify_transport(&ipv6, IPPROTO_UDP), Some(false));
+    }
+
+    #[test]
+    fn test_unverifiable_packets() {
+        let packet = hex(IPV4_UDP);
+        assert_eq!(verify_transport(&packet, IPPROTO_TCP), None);
+        assert_eq!(verify_transport(&packet[..30], IPPROTO_UDP), None);
+
+        let mut fragment = packet.clone();
+        fragment[6] |= 0x20;
+        assert_eq!(verify_transport(&fragment, IPPROTO_UDP), None);
+
+        // IPv6 fragment header in front of the UDP segment
+        let udp = hex(IPV6_UDP);
+        let mut ipv6 = udp[..40].to_vec();
+        ipv6[4..6].copy_from_slice(&(udp.len() as u16 - 40 + 8).to_be_bytes());
+        ipv6[6] = 44;
+        ipv6.extend_from_slice(&[IPPROTO_UDP, 0, 0, 0, 0, 0, 0, 1]);
+        ipv6.extend_from_slice(&udp[40..]);
+        assert_eq!(verify_transport(&ipv6, IPPROTO_UDP), None);
+    }
+
+    #[test]
+    fn test_ipv6_extension_headers_are_skipped() {
+        let udp = hex(IPV6_UDP);
+        let mut packet = udp[..40].to_vec();
+        packet[4..6].copy_from_slice(&(udp.len() as u16 - 40 + 8).to_be_bytes());
+        packet[6] = 60;
+        packet.extend_from_slice(&[IPPROTO_UDP, 0, 1, 4, 0, 0, 0, 0]);
+        packet.extend_from_slice(&udp[40..]);
+        assert_eq!(verify_transport(&packet, IPPROTO_UDP), Some(true));
+    }
+
+    #[test]
+    fn test_simd_and_scalar_sums_agree() {
+        let data: Vec<u8> = (0..600_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        for len in (0..300).chain([1499, 1500, 9001, 65535, 600_000]) {
+            let data = &data[..len];
+            assert_eq!(
+                fold(ones_complement_sum(data)),
+                fold(scalar_sum(data)),
+                "length {}",
+                len
+            );
+        }
+        // All-ones words exercise carries out of every lane
+        let ones = vec![0xff; 600_001];
+        assert_eq!(fold(ones_complement_sum(&ones)), fold(scalar_sum(&ones)));
+    }
+}
@@ -0,0 +1,370 @@
+// protocol/flow.rs
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::net::IpAddr;
+
+/// Transport protocol number for TCP.
+pub const IPPROTO_TCP: u8 = 6;
+/// Transport protocol number for UDP.
+pub const IPPROTO_UDP: u8 = 17;
+/// Transport protocol number for SCTP.
+pub const IPPROTO_SCTP: u8 = 132;
+
+/// Direction-independent identity of a flow.
+///
+/// Both directions of a conversation map to the same key: the endpoint pair is stored in a
+/// canonical order, so lookups from either side find the same flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FlowKey {
+    pub addr_a: IpAddr,
+    pub port_a: u16,
+    pub addr_b: IpAddr,
+    pub port_b: u16,
+    pub protocol: u8,
+}
+
+impl FlowKey {
+    /// Builds the canonical key for a packet's 5-tuple.
+    pub fn new(src: IpAddr, src_port: u16, dst: IpAddr, dst_port: u16, protocol: u8) -> Self {
+        if (src, src_port) <= (dst, dst_port) {
+            Self {
+                addr_a: src,
+                port_a: src_port,
+                addr_b: dst,
+                port_b: dst_port,
+                protocol,
+            }
+        } else {
+            Self {
+                addr_a: dst,
+                port_a: dst_port,
+                addr_b: src,
+                port_b: src_port,
+                protocol,
+            }
+        }
+    }
+
+    /// Returns the direction of a packet sent from `(src, src_port)` within this flow.
+    pub fn direction_of(&self, src: IpAddr, src_port: u16) -> FlowDirection {
+        if (src, src_port) == (self.addr_a, self.port_a) {
+            FlowDirection::AToB
+        } else {
+            FlowDirection::BToA
+        }
+    }
+}
+
+/// Direction of a packet relative to a flow's canonical endpoint order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FlowDirection {
+    AToB,
+    BToA,
+}
+
+impl FlowDirection {
+    /// Returns the opposite direction.
+    pub fn reverse(self) -> Self {
+        match self {
+            FlowDirection::AToB => FlowDirection::BToA,
+            FlowDirection::BToA => FlowDirection::AToB,
+        }
+    }
+}
+
+/// Why a flow left the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionReason {
+    /// No packets were seen for longer than the idle timeout.
+    Idle,
+    /// The table was full and this was the least recently seen flow.
+    Capacity,
+    /// An incomplete entry was dropped early to relieve table pressure.
+    Pressure,
+    /// The flow ended, e.g. on a TCP FIN or RST.
+    Closed,
+}
+
+/// A flow removed from the table, with its final state.
+#[derive(Debug, Clone)]
+pub struct EvictedFlow<V, K = FlowKey> {
+    pub key: K,
+    pub value: V,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub reason: EvictionReason,
+}
+
+#[derive(Debug)]
+struct FlowEntry<V> {
+    value: V,
+    first_seen: u64,
+    last_seen: u64,
+    order: (u64, u64),
+}
+
+/// Bounded flow table with idle eviction.
+///
+/// Timestamps are caller-supplied nanoseconds, normally packet timestamps, so the table follows
+/// capture time rather than wall-clock time. Flows are ordered by last activity, which makes
+/// idle sweeps and capacity eviction O(log n) per flow. Keys default to `FlowKey`; other
+/// per-conversation tables such as fragment reassembly use their own key types.
+#[derive(Debug)]
+pub struct FlowTable<V, K = FlowKey> {
+    entries: HashMap<K, FlowEntry<V>>,
+    by_activity: BTreeMap<(u64, u64), K>,
+    capacity: usize,
+    idle_timeout_ns: u64,
+    sequence: u64,
+}
+
+impl<V, K: Copy + Eq + Hash> FlowTable<V, K> {
+    /// Creates a table holding at most `capacity` flows.
+    pub fn new(capacity: usize, idle_timeout_ns: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            by_activity: BTreeMap::new(),
+            capacity: capacity.max(1),
+            idle_timeout_ns,
+            sequence: 0,
+        }
+    }
+
+    /// Number of tracked flows.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the table tracks no flows.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Maximum number of flows.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Idle timeout in nanoseconds.
+    pub fn idle_timeout_ns(&self) -> u64 {
+        self.idle_timeout_ns
+    }
+
+    /// Looks up a flow without updating its activity.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    /// Returns when a flow was first seen.
+    pub fn first_seen(&self, key: &K) -> Option<u64> {
+        self.entries.get(key).map(|entry| entry.first_seen)
+    }
+
+    /// Returns when a flow was last seen.
+    pub fn last_seen(&self, key: &K) -> Option<u64> {
+        self.entries.get(key).map(|entry| entry.last_seen)
+    }
+
+    /// Looks up a flow and marks it active at `now`.
+    ///
+    /// A timestamp older than the flow's last activity leaves it at that activity, so packets
+    /// arriving out of order never make a flow look idle sooner.
+    pub fn touch(&mut self, key: &K, now: u64) -> Option<&mut V> {
+        let last_seen = self.entries.get(key)?.last_seen.max(now);
+        let order = self.next_order(last_seen);
+        let entry = self.entries.get_mut(key)?;
+        self.by_activity.remove(&entry.order);
+        self.by_activity.insert(order, *key);
+        entry.order = order;
+        entry.last_seen = last_seen;
+        Some(&mut entry.value)
+    }
+
+    /// Looks up a flow, creating it with `create` if it is new, and marks it active at `now`.
+    ///
+    /// Creating a flow in a full table evicts the least recently seen flow, which is returned.
+    pub fn get_or_insert_with<F>(
+        &mut self,
+        key: K,
+        now: u64,
+        create: F,
+    ) -> (&mut V, Option<EvictedFlow<V, K>>)
+    where
+        F: FnOnce() -> V,
+    {
+        let mut evicted = None;
+        if !self.entries.contains_key(&key) {
+            if self.entries.len() >= self.capacity {
+                evicted = self.evict_oldest(EvictionReason::Capacity);
+            }
+            let order = self.next_order(now);
+            self.by_activity.insert(order, key);
+            self.entries.insert(
+                key,
+                FlowEntry {
+                    value: create(),
+                    first_seen: now,
+                    last_seen: now,
+                    order,
+                },
+            );
+            return (&mut self.entries.get_mut(&key).unwrap().value, evicted);
+        }
+
+        (self.touch(&key, now).unwrap(), evicted)
+    }
+
+    /// Removes a flow.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.entries.remove(key)?;
+        self.by_activity.remove(&entry.order);
+        Some(entry.value)
+    }
+
+    /// Removes a flow, returning it with its activity timestamps.
+    pub fn evict(&mut self, key: &K, reason: EvictionReason) -> Option<EvictedFlow<V, K>> {
+        let entry = self.entries.remove(key)?;
+        self.by_activity.remove(&entry.order);
+        Some(EvictedFlow {
+            key: *key,
+            value: entry.value,
+            first_seen: entry.first_seen,
+            last_seen: entry.last_seen,
+            reason,
+        })
+    }
+
+    /// Removes and returns every flow idle for longer than the idle timeout at `now`.
+    pub fn evict_idle(&mut self, now: u64) -> Vec<EvictedFlow<V, K>> {
+        self.evict_idle_after(now, self.idle_timeout_ns)
+    }
+
+    /// Removes and returns every flow idle for longer than `idle_ns` at `now`.
+    pub fn evict_idle_after(&mut self, now: u64, idle_ns: u64) -> Vec<EvictedFlow<V, K>> {
+        let mut evicted = Vec::new();
+        while let Some((_, key)) = self.by_activity.first_key_value() {
+            let last_seen = self.entries[key].last_seen;
+            if now.saturating_sub(last_seen) <= idle_ns {
+                break;
+            }
+            evicted.extend(self.evict_oldest(EvictionReason::Idle));
+        }
+        evicted
+    }
+
+    /// Iterates over tracked flows in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, entry)| (key, &entry.value))
+    }
+
+    fn evict_oldest(&mut self, reason: EvictionReason) -> Option<EvictedFlow<V, K>> {
+        let (_, key) = self.by_activity.pop_first()?;
+        let entry = self.entries.remove(&key)?;
+        Some(EvictedFlow {
+            key,
+            value: entry.value,
+            first_seen: entry.first_seen,
+            last_seen: entry.last_seen,
+            reason,
+        })
+    }
+
+    fn next_order(&mut self, now: u64) -> (u64, u64) {
+        self.sequence += 1;
+        (now, self.sequence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn key(port: u16) -> FlowKey {
+        FlowKey::new(
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            port,
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            80,
+            IPPROTO_TCP,
+        )
+    }
+
+    #[test]
+    fn test_key_is_direction_independent() {
+        let a = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10));
+        let b = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let forward = FlowKey::new(a, 40000, b, 443, IPPROTO_TCP);
+        let reverse = FlowKey::new(b, 443, a, 40000, IPPROTO_TCP);
+
+        assert_eq!(forward, reverse);
+        assert_eq!(
+            forward.direction_of(a, 40000),
+            forward.direction_of(b, 443).reverse()
+        );
+    }
+
+    #[test]
+    fn test_capacity_evicts_least_recently_seen() {
+        let mut table = FlowTable::new(2, 1_000);
+        table.get_or_insert_with(key(1), 10, || 1);
+        table.get_or_insert_with(key(2), 20, || 2);
+        table.touch(&key(1), 30);
+
+        let (_, evicted) = table.get_or_insert_with(key(3), 40, || 3);
+        let evicted = evicted.unwrap();
+        assert_eq!(evicted.key, key(2));
+        assert_eq!(evicted.reason, EvictionReason::Capacity);
+        assert_eq!(table.len(), 2);
+        assert!(table.get(&key(1)).is_some());
+    }
+
+    #[test]
+    fn test_idle_eviction() {
+        let mut table = FlowTable::new(10, 100);
+        table.get_or_insert_with(key(1), 0, || 1);
+        table.get_or_insert_with(key(2), 50, || 2);
+        table.get_or_insert_with(key(3), 90, || 3);
+
+        let evicted = table.evict_idle(160);
+        let keys: Vec<_> = evicted.iter().map(|e| e.key).collect();
+        assert_eq!(keys, vec![key(1), key(2)]);
+        assert!(evicted.iter().all(|e| e.reason == EvictionReason::Idle));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_late_packet_keeps_flow_ordered_by_last_activity() {
+        let mut table = FlowTable::new(10, 100);
+        table.get_or_insert_with(key(1), 100, || 1);
+        table.get_or_insert_with(key(2), 50, || 2);
+        // A packet of flow 1 timestamped before its last activity must not move it behind flow 2
+        table.touch(&key(1), 10);
+        assert_eq!(table.last_seen(&key(1)), Some(100));
+
+        let evicted = table.evict_idle(180);
+        let keys: Vec<_> = evicted.iter().map(|e| e.key).collect();
+        assert_eq!(keys, vec![key(2)]);
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_existing_flow_is_updated_not_replaced() {
+        let mut table = FlowTable::new(10, 100);
+        *table.get_or_insert_with(key(1), 0, || 0).0 += 5;
+        let (value, evicted) = table.get_or_insert_with(key(1), 10, || 100);
+        assert_eq!(*value, 5);
+        assert!(evicted.is_none());
+        assert_eq!(table.first_seen(&key(1)), Some(0));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut table = FlowTable::new(10, 100);
+        table.get_or_insert_with(key(1), 0, || 7);
+        assert_eq!(table.remove(&key(1)), Some(7));
+        assert!(table.is_empty());
+        assert!(table.evict_idle(1_000).is_empty());
+    }
+}
@@ -0,0 +1,462 @@
+// protocol/flow_guard.rs
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::net::IpAddr;
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::protocol::flow::{EvictedFlow, EvictionReason, FlowKey, FlowTable};
+use crate::capture_engine::telemetry::traits::{
+    MetricType, MetricUnit, MetricValue, TelemetryData,
+};
+
+/// Telemetry name for new flows passed through untracked because the table was full.
+pub const PASS_THROUGH_TABLE_FULL_METRIC: &str = "protocol.flows.pass_through.table_full";
+/// Telemetry name for new flows passed through untracked because their source hit its cap.
+pub const PASS_THROUGH_SOURCE_LIMIT_METRIC: &str = "protocol.flows.pass_through.source_limit";
+/// Telemetry name for incomplete entries evicted early under pressure.
+pub const PRESSURE_EVICTIONS_METRIC: &str = "protocol.flows.evicted.pressure";
+/// Telemetry name for the number of tracked entries.
+pub const TRACKED_FLOWS_METRIC: &str = "protocol.flows.tracked";
+
+/// Incomplete entries examined per admission when relieving pressure.
+const PRESSURE_SCAN_LIMIT: usize = 64;
+
+/// State stored in a guarded table.
+pub trait TrackedFlow {
+    /// Whether the entry has proven itself legitimate (an established connection).
+    ///
+    /// Established entries are never evicted to make room; incomplete ones (half-open
+    /// handshakes, partial datagrams) are the first to go under pressure.
+    fn is_established(&self) -> bool;
+}
+
+/// Limits protecting a flow or reassembly table against floods.
+#[derive(Debug, Clone)]
+pub struct FlowLimits {
+    /// Maximum number of tracked entries.
+    pub max_flows: usize,
+    /// Maximum number of tracked entries opened by a single source address.
+    pub max_flows_per_source: usize,
+    /// Fill ratio above which incomplete entries are evicted early.
+    pub pressure_threshold: f64,
+    /// Idle time after which an incomplete entry is evicted while under pressure, in nanoseconds.
+    pub incomplete_timeout_ns: u64,
+}
+
+impl Default for FlowLimits {
+    fn default() -> Self {
+        Self {
+            max_flows: 262_144,
+            max_flows_per_source: 4_096,
+            pressure_threshold: 0.8,
+            incomplete_timeout_ns: 1_000_000_000,
+        }
+    }
+}
+
+impl FlowLimits {
+    /// Limits with a table size and defaults for everything else.
+    pub fn with_max_flows(max_flows: usize) -> Self {
+        Self {
+            max_flows,
+            max_flows_per_source: max_flows.min(FlowLimits::default().max_flows_per_source),
+            ..Default::default()
+        }
+    }
+
+    /// Checks that the limits are usable.
+    pub fn validate(&self) -> Result<(), CaptureError> {
+        if self.max_flows == 0 || self.max_flows_per_source == 0 {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Flow limits must be greater than zero",
+            ));
+        }
+        if !(self.pressure_threshold > 0.0 && self.pressure_threshold <= 1.0) {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "Flow table pressure threshold must be in (0, 1]",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Why a new entry was not tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassReason {
+    TableFull,
+    SourceLimit,
+}
+
+/// Result of offering a packet's flow to a guarded table.
+#[derive(Debug)]
+pub enum Admission<'a, V> {
+    /// The flow is tracked; its state can be updated.
+    Tracked(&'a mut V),
+    /// The flow is not tracked; the packet should be passed on without per-flow state.
+    PassThrough(PassReason),
+}
+
+/// Counters describing how a guarded table handled load.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowGuardStats {
+    pub pass_through_table_full: u64,
+    pub pass_through_source_limit: u64,
+    pub pressure_evictions: u64,
+}
+
+impl FlowGuardStats {
+    /// Exports the counters, plus the current table size, as telemetry.
+    pub fn telemetry(&self, tracked: usize, timestamp: u64) -> Vec<TelemetryData> {
+        let metric = |name: &str, description: &str, metric_type, value: u64| TelemetryData {
+            timestamp,
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type,
+            value: MetricValue::Integer(value as i64),
+            attributes: HashMap::new(),
+            resource: None,
+        };
+
+        vec![
+            metric(
+                PASS_THROUGH_TABLE_FULL_METRIC,
+                "New flows passed through untracked because the table was full",
+                MetricType::Counter,
+                self.pass_through_table_full,
+            ),
+            metric(
+                PASS_THROUGH_SOURCE_LIMIT_METRIC,
+                "New flows passed through untracked because their source reached its cap",
+                MetricType::Counter,
+                self.pass_through_source_limit,
+            ),
+            metric(
+                PRESSURE_EVICTIONS_METRIC,
+                "Incomplete entries evicted early under table pressure",
+                MetricType::Counter,
+                self.pressure_evictions,
+            ),
+            metric(
+                TRACKED_FLOWS_METRIC,
+                "Entries currently tracked",
+                MetricType::Gauge,
+                tracked as u64,
+            ),
+        ]
+    }
+}
+
+#[derive(Debug)]
+struct Guarded<V> {
+    value: V,
+    source: IpAddr,
+    created: (u64, u64),
+}
+
+/// Flow table that degrades to pass-through instead of growing without bound.
+///
+/// New entries are refused once their source has `max_flows_per_source` entries. Above the
+/// pressure threshold, incomplete entries idle past `incomplete_timeout_ns` are evicted, and
+/// if the table is still full the oldest incomplete entry makes room. When only established
+/// entries remain, new flows are passed through untracked, so a flood can never displace
+/// long-lived connections.
+#[derive(Debug)]
+pub struct GuardedFlowTable<V, K = FlowKey> {
+    table: FlowTable<Guarded<V>, K>,
+    per_source: HashMap<IpAddr, usize>,
+    incomplete: BTreeMap<(u64, u64), K>,
+    sequence: u64,
+    limits: FlowLimits,
+    stats: FlowGuardStats,
+}
+
+impl<V: TrackedFlow, K: Copy + Eq + Hash> GuardedFlowTable<V, K> {
+    /// Creates a guarded table.
+    pub fn new(limits: FlowLimits, idle_timeout_ns: u64) -> Result<Self, CaptureError> {
+        limits.validate()?;
+        Ok(Self {
+            table: FlowTable::new(limits.max_flows, idle_timeout_ns),
+            per_source: HashMap::new(),
+            incomplete: BTreeMap::new(),
+            sequence: 0,
+            limits,
+            stats: FlowGuardStats::default(),
+        })
+    }
+
+    /// Number of tracked entries.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Whether no entries are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Configured limits.
+    pub fn limits(&self) -> &FlowLimits {
+        &self.limits
+    }
+
+    /// Load-handling counters.
+    pub fn stats(&self) -> &FlowGuardStats {
+        &self.stats
+    }
+
+    /// Number of tracked entries opened by `source`.
+    pub fn source_count(&self, source: &IpAddr) -> usize {
+        self.per_source.get(source).copied().unwrap_or(0)
+    }
+
+    /// Looks up an entry without updating its activity.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.table.get(key).map(|guarded| &guarded.value)
+    }
+
+    /// Looks up an entry, tracking it as new if allowed, and marks it active at `now`.
+    ///
+    /// Returns the admission decision and any incomplete entries evicted to make room.
+    pub fn admit<F>(
+        &mut self,
+        key: K,
+        source: IpAddr,
+        now: u64,
+        create: F,
+    ) -> (Admission<'_, V>, Vec<EvictedFlow<V, K>>)
+    where
+        F: FnOnce() -> V,
+    {
+        if self.table.get(&key).is_some() {
+            let guarded = self.table.touch(&key, now).unwrap();
+            return (Admission::Tracked(&mut guarded.value), Vec::new());
+        }
+
+        if self.source_count(&source) >= self.limits.max_flows_per_source {
+            self.stats.pass_through_source_limit += 1;
+            return (Admission::PassThrough(PassReason::SourceLimit), Vec::new());
+        }
+
+        let evicted = self.relieve_pressure(now);
+        if self.table.len() >= self.limits.max_flows {
+            self.stats.pass_through_table_full += 1;
+            return (Admission::PassThrough(PassReason::TableFull), evicted);
+        }
+
+        self.sequence += 1;
+        let created = (now, self.sequence);
+        self.incomplete.insert(created, key);
+        *self.per_source.entry(source).or_default() += 1;
+        let (guarded, _) = self.table.get_or_insert_with(key, now, || Guarded {
+            value: create(),
+            source,
+            created,
+        });
+        (Admission::Tracked(&mut guarded.value), evicted)
+    }
+
+    /// Removes an entry.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let guarded = self.table.remove(key)?;
+        Some(self.forget(guarded))
+    }
+
+    /// Removes and returns every entry idle past the idle timeout at `now`.
+    pub fn evict_idle(&mut self, now: u64) -> Vec<EvictedFlow<V, K>> {
+        self.table
+            .evict_idle(now)
+            .into_iter()
+            .map(|evicted| self.unwrap_evicted(evicted))
+            .collect()
+    }
+
+    /// Evicts incomplete entries while the table is above its pressure threshold.
+    fn relieve_pressure(&mut self, now: u64) -> Vec<EvictedFlow<V, K>> {
+        let pressure_at = (self.limits.max_flows as f64 * self.limits.pressure_threshold) as usize;
+        if self.table.len() < pressure_at.max(1) {
+            return Vec::new();
+        }
+
+        let mut evicted = Vec::new();
+        let mut oldest_incomplete = None;
+        let candidates: Vec<((u64, u64), K)> = self
+            .incomplete
+            .iter()
+            .take(PRESSURE_SCAN_LIMIT)
+            .map(|(created, key)| (*created, *key))
+            .collect();
+        for (created, key) in candidates {
+            let Some(guarded) = self.table.get(&key) else {
+                self.incomplete.remove(&created);
+                continue;
+            };
+            if guarded.value.is_established() {
+                // Graduated since it was indexed; it is no longer an eviction candidate
+                self.incomplete.remove(&created);
+                continue;
+            }
+            let last_seen = self.table.last_seen(&key).unwrap_or(now);
+            if now.saturating_sub(last_seen) >= self.limits.incomplete_timeout_ns {
+                evicted.extend(self.evict_for_pressure(&key));
+            } else if oldest_incomplete.is_none() {
+                oldest_incomplete = Some(key);
+            }
+        }
+
+        if self.table.len() >= self.limits.max_flows {
+            if let Some(key) = oldest_incomplete {
+                evicted.extend(self.evict_for_pressure(&key));
+            }
+        }
+        evicted
+    }
+
+    fn evict_for_pressure(&mut self, key: &K) -> Option<EvictedFlow<V, K>> {
+        let evicted = self.table.evict(key, EvictionReason::Pressure)?;
+        self.stats.pressure_evictions += 1;
+        Some(self.unwrap_evicted(evicted))
+    }
+
+    fn unwrap_evicted(&mut self, evicted: EvictedFlow<Guarded<V>, K>) -> EvictedFlow<V, K> {
+        EvictedFlow {
+            key: evicted.key,
+            first_seen: evicted.first_seen,
+            last_seen: evicted.last_seen,
+            reason: evicted.reason,
+            value: self.forget(evicted.value),
+        }
+    }
+
+    /// Drops the bookkeeping for an entry leaving the table.
+    fn forget(&mut self, guarded: Guarded<V>) -> V {
+        self.incomplete.remove(&guarded.created);
+        if let Some(count) = self.per_source.get_mut(&guarded.source) {
+            *count -= 1;
+            if *count == 0 {
+                self.per_source.remove(&guarded.source);
+            }
+        }
+        guarded.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::protocol::flow::IPPROTO_UDP;
+    use std::net::Ipv4Addr;
+
+    #[derive(Debug)]
+    struct Entry {
+        established: bool,
+    }
+
+    impl TrackedFlow for Entry {
+        fn is_established(&self) -> bool {
+            self.established
+        }
+    }
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))
+    }
+
+    fn key(source: IpAddr, port: u16) -> FlowKey {
+        FlowKey::new(source, port, ip(254), 53, IPPROTO_UDP)
+    }
+
+    fn limits(max_flows: usize, per_source: usize) -> FlowLimits {
+        FlowLimits {
+            max_flows,
+            max_flows_per_source: per_source,
+            pressure_threshold: 0.5,
+            incomplete_timeout_ns: 100,
+        }
+    }
+
+    fn admit(table: &mut GuardedFlowTable<Entry>, key: FlowKey, source: IpAddr, now: u64) -> bool {
+        matches!(
+            table
+                .admit(key, source, now, || Entry { established: false })
+                .0,
+            Admission::Tracked(_)
+        )
+    }
+
+    #[test]
+    fn test_per_source_cap() {
+        let mut table = GuardedFlowTable::new(limits(100, 3), 10_000).unwrap();
+        for port in 0..3 {
+            assert!(admit(&mut table, key(ip(1), port), ip(1), 0));
+        }
+        assert!(!admit(&mut table, key(ip(1), 3), ip(1), 0));
+        assert!(admit(&mut table, key(ip(2), 3), ip(2), 0));
+        assert_eq!(table.stats().pass_through_source_limit, 1);
+
+        table.remove(&key(ip(1), 0));
+        assert_eq!(table.source_count(&ip(1)), 2);
+        assert!(admit(&mut table, key(ip(1), 3), ip(1), 0));
+    }
+
+    #[test]
+    fn test_full_table_of_established_flows_passes_new_flows_through() {
+        let mut table = GuardedFlowTable::new(limits(4, 100), 10_000).unwrap();
+        for port in 0..4 {
+            if let (Admission::Tracked(entry), _) =
+                table.admit(key(ip(1), port), ip(1), 0, || Entry { established: false })
+            {
+                entry.established = true;
+            }
+        }
+
+        assert!(!admit(&mut table, key(ip(2), 9), ip(2), 1_000));
+        assert_eq!(table.stats().pass_through_table_full, 1);
+        assert_eq!(table.stats().pressure_evictions, 0);
+        assert_eq!(table.len(), 4);
+    }
+
+    #[test]
+    fn test_stale_incomplete_entries_are_evicted_under_pressure() {
+        let mut table = GuardedFlowTable::new(limits(4, 100), 10_000).unwrap();
+        admit(&mut table, key(ip(1), 1), ip(1), 0);
+        admit(&mut table, key(ip(1), 2), ip(1), 0);
+
+        // Two entries reach the pressure threshold; both are idle past the incomplete timeout
+        let (admission, evicted) =
+            table.admit(key(ip(2), 3), ip(2), 500, || Entry { established: false });
+        assert!(matches!(admission, Admission::Tracked(_)));
+        assert_eq!(evicted.len(), 2);
+        assert!(evicted.iter().all(|e| e.reason == EvictionReason::Pressure));
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.source_count(&ip(1)), 0);
+    }
+
+    #[test]
+    fn test_limits_validation() {
+        assert!(GuardedFlowTable::<Entry>::new(limits(0, 1), 1).is_err());
+        let mut bad = limits(10, 1);
+        bad.pressure_threshold = 1.5;
+        assert!(GuardedFlowTable::<Entry>::new(bad, 1).is_err());
+    }
+
+    #[test]
+    fn test_telemetry() {
+        let table = GuardedFlowTable::<Entry>::new(limits(4, 4), 1).unwrap();
+        let telemetry = table.stats().telemetry(table.len(), 7);
+        let names: Vec<_> = telemetry.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                PASS_THROUGH_TABLE_FULL_METRIC,
+                PASS_THROUGH_SOURCE_LIMIT_METRIC,
+                PRESSURE_EVICTIONS_METRIC,
+                TRACKED_FLOWS_METRIC
+            ]
+        );
+    }
+}
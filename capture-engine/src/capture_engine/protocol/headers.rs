@@ -0,0 +1,520 @@
+// protocol/headers.rs
+use std::collections::HashMap;
+
+use crate::capture_engine::protocol::traits::{
+    HeaderInfo, ProtocolConfig, DEFAULT_MAX_PARSE_DEPTH,
+};
+use crate::traits::Packet;
+
+/// UDP port carrying VXLAN.
+pub const VXLAN_PORT: u16 = 4789;
+/// UDP port carrying Geneve.
+pub const GENEVE_PORT: u16 = 6081;
+
+/// Deepest tunnel nesting followed before the rest of the frame is treated as payload.
+const MAX_ENCAPSULATION_DEPTH: usize = 8;
+
+/// Packet metadata key set when parsing stopped at a depth limit.
+pub const PARSE_TRUNCATED: &str = "parse_truncated";
+
+/// EtherType of IPv4.
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+/// EtherType of IPv6.
+pub const ETHERTYPE_IPV6: u16 = 0x86dd;
+/// EtherType of an 802.1Q VLAN tag.
+pub const ETHERTYPE_VLAN: u16 = 0x8100;
+/// EtherType of an 802.1ad service tag.
+pub const ETHERTYPE_QINQ: u16 = 0x88a8;
+/// EtherType of a unicast MPLS label stack.
+pub const ETHERTYPE_MPLS: u16 = 0x8847;
+/// EtherType of transparent Ethernet bridging, as carried in GRE.
+pub const ETHERTYPE_TEB: u16 = 0x6558;
+
+/// A header found while walking a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderLayer {
+    Ethernet,
+    Vlan,
+    Mpls,
+    Ipv4,
+    Ipv6,
+    Tcp,
+    Udp,
+    Sctp,
+    Icmp,
+    Gre,
+    Vxlan,
+    Geneve,
+}
+
+/// Where a frame's headers end and its payload begins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderSplit {
+    /// Bytes of headers, including every tunnel and its inner headers.
+    pub header_len: usize,
+    /// Headers in the order they appear.
+    pub layers: Vec<HeaderLayer>,
+    /// Offset of each header in `layers`.
+    pub offsets: Vec<usize>,
+    /// Header the frame ended inside, and its offset.
+    pub cut: Option<(HeaderLayer, usize)>,
+    /// False when the frame ended inside a header; `header_len` then covers the whole frame.
+    pub complete: bool,
+    /// True when a depth limit stopped the walk; everything after `header_len` is payload.
+    pub truncated: bool,
+}
+
+impl HeaderSplit {
+    /// Payload bytes in a frame of `frame_len` bytes.
+    pub fn payload_len(&self, frame_len: usize) -> usize {
+        frame_len.saturating_sub(self.header_len)
+    }
+}
+
+/// Finds the payload boundary of an Ethernet frame.
+///
+/// Walks VLAN tags, MPLS labels, IPv4 options, IPv6 extension headers, TCP options and
+/// VXLAN/Geneve/GRE tunnels down to the innermost L4 header. An IPv4 header length below 20
+/// bytes ends the walk as malformed. Unrecognised protocols end the
+/// walk; everything after the last recognised header is payload. At most
+/// `DEFAULT_MAX_PARSE_DEPTH` headers are parsed.
+pub fn split_headers(frame: &[u8]) -> HeaderSplit {
+    split_headers_to_depth(frame, DEFAULT_MAX_PARSE_DEPTH)
+}
+
+/// Finds the payload boundary of an Ethernet frame, parsing at most `max_depth` headers.
+///
+/// A frame with more headers is split after the first `max_depth` and marked truncated.
+pub fn split_headers_to_depth(frame: &[u8], max_depth: usize) -> HeaderSplit {
+    let mut walker = Walker {
+        frame,
+        offset: 0,
+        layers: Vec::new(),
+        offsets: Vec::new(),
+        cut: None,
+        depth: 0,
+        max_layers: max_depth,
+        truncated: false,
+    };
+    let complete = walker.ethernet().is_some() || walker.truncated;
+    HeaderSplit {
+        header_len: if complete { walker.offset } else { frame.len() },
+        layers: walker.layers,
+        offsets: walker.offsets,
+        cut: walker.cut,
+        complete,
+        truncated: walker.truncated,
+    }
+}
+
+/// Parses a packet's headers up to the configured depth.
+///
+/// A packet that hits the limit is tagged `PARSE_TRUNCATED` in its metadata and passed through
+/// with the rest of the frame left unparsed.
+pub fn parse_headers(packet: &mut Packet<'_>, config: &ProtocolConfig) -> HeaderInfo {
+    let split = split_headers_to_depth(packet.data, config.max_parse_depth);
+    if split.truncated {
+        packet
+            .metadata
+            .additional_info
+            .insert(PARSE_TRUNCATED.to_string(), "true".to_string());
+    }
+    HeaderInfo {
+        protocols: split
+            .layers
+            .iter()
+            .map(|layer| format!("{:?}", layer))
+            .collect(),
+        fields: HashMap::new(),
+        layers_parsed: split.layers.len(),
+        parse_truncated: split.truncated,
+    }
+}
+
+struct Walker<'a> {
+    frame: &'a [u8],
+    offset: usize,
+    layers: Vec<HeaderLayer>,
+    offsets: Vec<usize>,
+    cut: Option<(HeaderLayer, usize)>,
+    depth: usize,
+    max_layers: usize,
+    truncated: bool,
+}
+
+impl<'a> Walker<'a> {
+    /// Consumes a header of `len` bytes, failing if the frame is too short or the depth limit
+    /// is reached.
+    fn take(&mut self, layer: HeaderLayer, len: usize) -> Option<&'a [u8]> {
+        if self.layers.len() >= self.max_layers {
+            self.truncated = true;
+            return None;
+        }
+        let Some(header) = self.frame.get(self.offset..self.offset.checked_add(len)?) else {
+            self.cut = Some((layer, self.offset));
+            return None;
+        };
+        self.layers.push(layer);
+        self.offsets.push(self.offset);
+        self.offset += len;
+        Some(header)
+    }
+
+    fn peek(&self, at: usize) -> Option<u8> {
+        self.frame.get(self.offset + at).copied()
+    }
+
+    /// Reads a byte of the next header, recording the cut if the frame ends before it.
+    fn peek_in(&mut self, layer: HeaderLayer, at: usize) -> Option<u8> {
+        let byte = self.peek(at);
+        if byte.is_none() {
+            self.cut = Some((layer, self.offset));
+        }
+        byte
+    }
+
+    fn ethernet(&mut self) -> Option<()> {
+        let header = self.take(HeaderLayer::Ethernet, 14)?;
+        let mut ethertype = u16::from_be_bytes([header[12], header[13]]);
+        while matches!(ethertype, ETHERTYPE_VLAN | ETHERTYPE_QINQ) {
+            let tag = self.take(HeaderLayer::Vlan, 4)?;
+            ethertype = u16::from_be_bytes([tag[2], tag[3]]);
+        }
+        self.ethertype(ethertype)
+    }
+
+    fn ethertype(&mut self, ethertype: u16) -> Option<()> {
+        match ethertype {
+            ETHERTYPE_IPV4 => self.ipv4(),
+            ETHERTYPE_IPV6 => self.ipv6(),
+            ETHERTYPE_MPLS => self.mpls(),
+            ETHERTYPE_TEB => self.encapsulated(Self::ethernet),
+            _ => Some(()),
+        }
+    }
+
+    fn mpls(&mut self) -> Option<()> {
+        loop {
+            let label = self.take(HeaderLayer::Mpls, 4)?;
+            if label[2] & 0x01 != 0 {
+                break;
+            }
+        }
+        // MPLS carries no next-protocol field; IP is recognised by its version nibble
+        match self.peek(0).map(|b| b >> 4) {
+            Some(4) => self.ipv4(),
+            Some(6) => self.ipv6(),
+            _ => Some(()),
+        }
+    }
+
+    fn ipv4(&mut self) -> Option<()> {
+        let ihl = (self.peek_in(HeaderLayer::Ipv4, 0)? & 0x0f) as usize * 4;
+        if ihl < 20 {
+            return None;
+        }
+        let header = self.take(HeaderLayer::Ipv4, ihl)?;
+        let protocol = header[9];
+        let fragment_offset = u16::from_be_bytes([header[6], header[7]]) & 0x1fff;
+        if fragment_offset != 0 {
+            // Later fragments carry no transport header
+            return Some(());
+        }
+        self.transport(protocol)
+    }
+
+    fn ipv6(&mut self) -> Option<()> {
+        let header = self.take(HeaderLayer::Ipv6, 40)?;
+        let mut next = header[6];
+        loop {
+            match next {
+                // Hop-by-hop, routing and destination options
+                0 | 43 | 60 => {
+                    let len = (self.peek_in(HeaderLayer::Ipv6, 1)? as usize + 1) * 8;
+                    next = self.take(HeaderLayer::Ipv6, len)?[0];
+                }
+                44 => {
+                    let fragment = self.take(HeaderLayer::Ipv6, 8)?;
+                    next = fragment[0];
+                    if u16::from_be_bytes([fragment[2], fragment[3]]) >> 3 != 0 {
+                        return Some(());
+                    }
+                }
+                // Authentication header
+                51 => {
+                    let len = (self.peek_in(HeaderLayer::Ipv6, 1)? as usize + 2) * 4;
+                    next = self.take(HeaderLayer::Ipv6, len)?[0];
+                }
+                _ => return self.transport(next),
+            }
+        }
+    }
+
+    fn transport(&mut self, protocol: u8) -> Option<()> {
+        match protocol {
+            1 | 58 => self.take(HeaderLayer::Icmp, 8).map(|_| ()),
+            4 => self.encapsulated(Self::ipv4),
+            6 => {
+                let data_offset = (self.peek_in(HeaderLayer::Tcp, 12)? >> 4) as usize * 4;
+                if data_offset < 20 {
+                    return None;
+                }
+                self.take(HeaderLayer::Tcp, data_offset).map(|_| ())
+            }
+            17 => {
+                let header = self.take(HeaderLayer::Udp, 8)?;
+                let (src, dst) = (
+                    u16::from_be_bytes([header[0], header[1]]),
+                    u16::from_be_bytes([header[2], header[3]]),
+                );
+                if dst == VXLAN_PORT || src == VXLAN_PORT {
+                    self.take(HeaderLayer::Vxlan, 8)?;
+                    self.encapsulated(Self::ethernet)
+                } else if dst == GENEVE_PORT || src == GENEVE_PORT {
+                    let options = (self.peek(0)? & 0x3f) as usize * 4;
+                    let header = self.take(HeaderLayer::Geneve, 8 + options)?;
+                    let inner = u16::from_be_bytes([header[2], header[3]]);
+                    self.encapsulated(|walker| walker.ethertype(inner))
+                } else {
+                    Some(())
+                }
+            }
+            41 => self.encapsulated(Self::ipv6),
+            132 => self.take(HeaderLayer::Sctp, 12).map(|_| ()),
+            47 => {
+                let flags = self.peek(0)?;
+                // Checksum, key and sequence number are each 4 optional bytes
+                let optional = [0x80u8, 0x20, 0x10]
+                    .iter()
+                    .filter(|&&bit| flags & bit != 0)
+                    .count();
+                let header = self.take(HeaderLayer::Gre, 4 + optional * 4)?;
+                let inner = u16::from_be_bytes([header[2], header[3]]);
+                self.encapsulated(|walker| walker.ethertype(inner))
+            }
+            _ => Some(()),
+        }
+    }
+
+    fn encapsulated(&mut self, inner: impl FnOnce(&mut Self) -> Option<()>) -> Option<()> {
+        if self.depth >= MAX_ENCAPSULATION_DEPTH {
+            self.truncated = true;
+            return Some(());
+        }
+        self.depth += 1;
+        inner(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ethernet(ethertype: u16) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame
+    }
+
+    /// IPv4 header with `options` bytes of options (a multiple of 4).
+    fn ipv4(protocol: u8, options: usize) -> Vec<u8> {
+        let mut header = vec![0u8; 20 + options];
+        header[0] = 0x40 | ((20 + options) / 4) as u8;
+        header[9] = protocol;
+        header
+    }
+
+    fn udp(dst_port: u16) -> Vec<u8> {
+        let mut header = vec![0u8; 8];
+        header[2..4].copy_from_slice(&dst_port.to_be_bytes());
+        header
+    }
+
+    /// TCP header with `options` bytes of options (a multiple of 4).
+    fn tcp(options: usize) -> Vec<u8> {
+        let mut header = vec![0u8; 20 + options];
+        header[12] = (((20 + options) / 4) as u8) << 4;
+        header
+    }
+
+    #[test]
+    fn test_vlan_ipv6_extension_headers() {
+        let mut frame = ethernet(ETHERTYPE_VLAN);
+        frame.extend_from_slice(&[0, 10, 0x86, 0xdd]);
+        let mut ipv6 = vec![0u8; 40];
+        ipv6[0] = 0x60;
+        ipv6[6] = 0; // hop-by-hop options
+        frame.extend_from_slice(&ipv6);
+        frame.extend_from_slice(&[6, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        frame.extend(tcp(12));
+        frame.extend_from_slice(b"payload");
+
+        let split = split_headers(&frame);
+        assert!(split.complete);
+        assert_eq!(split.header_len, 14 + 4 + 40 + 16 + 32);
+        assert_eq!(split.payload_len(frame.len()), 7);
+        assert_eq!(
+            split.layers,
+            vec![
+                HeaderLayer::Ethernet,
+                HeaderLayer::Vlan,
+                HeaderLayer::Ipv6,
+                HeaderLayer::Ipv6,
+                HeaderLayer::Tcp
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gre_with_key() {
+        let mut frame = ethernet(ETHERTYPE_IPV4);
+        frame.extend(ipv4(47, 0));
+        frame.extend_from_slice(&[0x20, 0, 0x08, 0x00, 0, 0, 0, 7]);
+        frame.extend(ipv4(17, 0));
+        frame.extend(udp(53));
+        frame.extend_from_slice(&[1, 2, 3]);
+
+        let split = split_headers(&frame);
+        assert_eq!(split.header_len, 14 + 20 + 8 + 20 + 8);
+        assert_eq!(split.layers[2], HeaderLayer::Gre);
+    }
+
+    #[test]
+    fn test_frame_ending_inside_header_is_all_header() {
+        let mut frame = ethernet(ETHERTYPE_IPV4);
+        frame.extend(ipv4(6, 8));
+        frame.extend_from_slice(&[0u8; 10]);
+
+        let split = split_headers(&frame);
+        assert!(!split.complete);
+        assert_eq!(split.header_len, frame.len());
+        assert_eq!(split.payload_len(frame.len()), 0);
+    }
+
+    #[test]
+    fn test_offsets_and_cut_layer_recorded() {
+        let mut frame = ethernet(ETHERTYPE_IPV4);
+        frame.extend(ipv4(6, 4));
+        frame.extend(tcp(0));
+        let split = split_headers(&frame);
+        assert_eq!(split.offsets, vec![0, 14, 38]);
+        assert_eq!(split.cut, None);
+
+        let split = split_headers(&frame[..40]);
+        assert_eq!(split.offsets, vec![0, 14]);
+        assert_eq!(split.cut, Some((HeaderLayer::Tcp, 38)));
+        assert_eq!(
+            split_headers(&frame[..14]).cut,
+            Some((HeaderLayer::Ipv4, 14))
+        );
+    }
+
+    #[test]
+    fn test_short_ipv4_header_length_is_malformed() {
+        let mut frame = ethernet(ETHERTYPE_IPV4);
+        let mut header = ipv4(6, 0);
+        header[0] = 0x44;
+        frame.extend(header);
+        frame.extend(tcp(0));
+        let split = split_headers(&frame);
+        assert!(!split.complete);
+        assert_eq!(split.layers, vec![HeaderLayer::Ethernet]);
+        assert_eq!(split.cut, None);
+    }
+
+    #[test]
+    fn test_unknown_ethertype_ends_walk() {
+        let mut frame = ethernet(0x0806);
+        frame.extend_from_slice(&[0u8; 28]);
+        let split = split_headers(&frame);
+        assert!(split.complete);
+        assert_eq!(split.header_len, 14);
+    }
+
+    /// Ethernet frame nesting `levels` VXLAN tunnels, each Ethernet/IPv4/UDP/VXLAN.
+    fn nested_vxlan(levels: usize) -> Vec<u8> {
+        let mut frame = Vec::new();
+        for _ in 0..levels {
+            frame.extend(ethernet(ETHERTYPE_IPV4));
+            frame.extend(ipv4(17, 0));
+            frame.extend(udp(VXLAN_PORT));
+            frame.extend_from_slice(&[0x08, 0, 0, 0, 0, 0, 1, 0]);
+        }
+        frame.extend(ethernet(ETHERTYPE_IPV4));
+        frame.extend(ipv4(17, 0));
+        frame.extend(udp(53));
+        frame
+    }
+
+    #[test]
+    fn test_pathological_nesting_stops_at_configured_depth() {
+        // QinQ stack, then MPLS labels, then thousands of nested tunnels
+        let mut frame = ethernet(ETHERTYPE_QINQ);
+        for _ in 0..1000 {
+            frame.extend_from_slice(&[0, 1, 0x88, 0xa8]);
+        }
+        frame.extend_from_slice(&[0, 1, 0x88, 0x47]);
+        for _ in 0..1000 {
+            frame.extend_from_slice(&[0, 0, 0, 64]);
+        }
+        frame.extend_from_slice(&[0, 0, 1, 64]);
+        frame.extend(ipv4(17, 0));
+        frame.extend(udp(VXLAN_PORT));
+        frame.extend_from_slice(&[0x08, 0, 0, 0, 0, 0, 1, 0]);
+        frame.extend(nested_vxlan(5000));
+
+        let mut packet = Packet {
+            timestamp: 0,
+            data: &frame,
+            length: frame.len(),
+            metadata: crate::traits::PacketMetadata {
+                compact_data: 0,
+                additional_info: HashMap::new(),
+            },
+            buffer_id: crate::traits::BufferId::new(0),
+        };
+        let config = ProtocolConfig {
+            max_parse_depth: 1500,
+        };
+        let info = parse_headers(&mut packet, &config);
+
+        assert_eq!(info.layers_parsed, 1500);
+        assert!(info.parse_truncated);
+        assert_eq!(info.protocols[0], "Ethernet");
+        assert_eq!(info.protocols[1499], "Mpls");
+        assert_eq!(
+            packet.metadata.additional_info.get(PARSE_TRUNCATED),
+            Some(&"true".to_string())
+        );
+
+        // The unparsed rest of the frame is passed through as payload
+        let split = split_headers_to_depth(&frame, 1500);
+        assert!(split.complete);
+        // Ethernet, 1001 VLAN tags and the first 498 MPLS labels
+        assert_eq!(split.header_len, 14 + 1001 * 4 + 498 * 4);
+    }
+
+    #[test]
+    fn test_tunnel_nesting_is_bounded_without_recursing_deeply() {
+        let frame = nested_vxlan(100_000);
+        let split = split_headers_to_depth(&frame, usize::MAX);
+        assert!(split.truncated);
+        assert!(split.complete);
+        // The outer tunnel and each nested one are Ethernet/IPv4/UDP/VXLAN
+        assert_eq!(split.layers.len(), (MAX_ENCAPSULATION_DEPTH + 1) * 4);
+
+        let split = split_headers_to_depth(&frame, 10);
+        assert!(split.truncated);
+        assert_eq!(split.layers.len(), 10);
+    }
+
+    #[test]
+    fn test_shallow_packet_is_not_truncated() {
+        let frame = nested_vxlan(1);
+        let split = split_headers_to_depth(&frame, 7);
+        assert!(!split.truncated);
+        assert_eq!(split.layers.len(), 7);
+        assert_eq!(split.header_len, frame.len());
+        assert!(split_headers_to_depth(&frame, 6).truncated);
+    }
+}
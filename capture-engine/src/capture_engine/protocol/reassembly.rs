@@ -0,0 +1,320 @@
+// protocol/reassembly.rs
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::protocol::flow_guard::{
+    Admission, FlowGuardStats, FlowLimits, GuardedFlowTable, PassReason, TrackedFlow,
+};
+
+/// Largest datagram an IP fragment train can reassemble to.
+pub const MAX_DATAGRAM_LEN: usize = 65_535;
+
+/// Identity of a fragmented IP datagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FragmentKey {
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub id: u32,
+    pub protocol: u8,
+}
+
+/// One IP fragment.
+#[derive(Debug, Clone, Copy)]
+pub struct Fragment<'a> {
+    pub key: FragmentKey,
+    /// Payload offset within the datagram, in bytes.
+    pub offset: usize,
+    pub more_fragments: bool,
+    pub payload: &'a [u8],
+    /// Capture timestamp in nanoseconds.
+    pub timestamp: u64,
+}
+
+/// Result of feeding a fragment to the reassembler.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReassemblyOutcome {
+    /// All fragments arrived; the reassembled payload.
+    Complete(Vec<u8>),
+    /// More fragments are needed.
+    Pending,
+    /// The datagram is not being reassembled; pass the fragment on as-is.
+    PassThrough(PassReason),
+    /// The fragment would extend the datagram past `MAX_DATAGRAM_LEN`, or the fragments stored
+    /// for its datagram would exceed it; the partial datagram is dropped.
+    Invalid,
+}
+
+/// Fragments received so far for one datagram.
+#[derive(Debug, Default)]
+struct PartialDatagram {
+    fragments: BTreeMap<usize, Vec<u8>>,
+    /// Disjoint byte ranges received so far, keyed by start, valued by end.
+    coverage: BTreeMap<usize, usize>,
+    /// Payload bytes held across all fragments, overlaps included.
+    stored: usize,
+    total_len: Option<usize>,
+}
+
+impl TrackedFlow for PartialDatagram {
+    fn is_established(&self) -> bool {
+        // Partial datagrams are always incomplete; they leave the table once reassembled
+        false
+    }
+}
+
+impl PartialDatagram {
+    /// Stores a fragment; false once the bytes stored exceed `MAX_DATAGRAM_LEN`.
+    fn insert(&mut self, fragment: &Fragment<'_>) -> bool {
+        if !fragment.more_fragments {
+            self.total_len = Some(fragment.offset + fragment.payload.len());
+        }
+        if let Entry::Vacant(entry) = self.fragments.entry(fragment.offset) {
+            entry.insert(fragment.payload.to_vec());
+            self.stored += fragment.payload.len();
+            self.cover(fragment.offset, fragment.offset + fragment.payload.len());
+        }
+        self.stored <= MAX_DATAGRAM_LEN
+    }
+
+    /// Adds a byte range to the coverage, merging it with the ranges it touches.
+    fn cover(&mut self, mut start: usize, mut end: usize) {
+        if let Some((&before, &before_end)) = self.coverage.range(..=start).next_back() {
+            if before_end >= start {
+                start = before;
+                end = end.max(before_end);
+            }
+        }
+        while let Some((&inner, &inner_end)) = self.coverage.range(start..=end).next() {
+            self.coverage.remove(&inner);
+            end = end.max(inner_end);
+        }
+        self.coverage.insert(start, end);
+    }
+
+    /// Whether every byte up to the final fragment has arrived.
+    fn is_complete(&self) -> bool {
+        match (self.total_len, self.coverage.first_key_value()) {
+            (Some(total_len), Some((&0, &end))) => end >= total_len,
+            _ => false,
+        }
+    }
+
+    /// Assembles a complete datagram.
+    fn assemble(&self) -> Vec<u8> {
+        let total_len = self.total_len.unwrap_or_default();
+        let mut datagram = Vec::with_capacity(total_len);
+        for (&offset, bytes) in &self.fragments {
+            // Overlapping data keeps the bytes at the lower offset
+            let skip = datagram.len() - offset.min(datagram.len());
+            if skip < bytes.len() {
+                datagram.extend_from_slice(&bytes[skip..]);
+            }
+        }
+        datagram.truncate(total_len);
+        datagram
+    }
+}
+
+/// Bounded IP fragment reassembler.
+///
+/// Partial datagrams live in a `GuardedFlowTable`, so a fragment flood evicts stale partial
+/// datagrams and then passes new fragments through unreassembled rather than exhausting memory.
+#[derive(Debug)]
+pub struct FragmentReassembler {
+    table: GuardedFlowTable<PartialDatagram, FragmentKey>,
+}
+
+impl FragmentReassembler {
+    /// Creates a reassembler; partial datagrams idle past `timeout_ns` are dropped by `expire`.
+    pub fn new(limits: FlowLimits, timeout_ns: u64) -> Result<Self, CaptureError> {
+        Ok(Self {
+            table: GuardedFlowTable::new(limits, timeout_ns)?,
+        })
+    }
+
+    /// Number of datagrams awaiting fragments.
+    pub fn pending(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Load-handling counters.
+    pub fn stats(&self) -> &FlowGuardStats {
+        self.table.stats()
+    }
+
+    /// Adds a fragment.
+    pub fn push(&mut self, fragment: &Fragment<'_>) -> ReassemblyOutcome {
+        if fragment.offset + fragment.payload.len() > MAX_DATAGRAM_LEN {
+            return ReassemblyOutcome::Invalid;
+        }
+
+        let (admission, _) = self.table.admit(
+            fragment.key,
+            fragment.key.src,
+            fragment.timestamp,
+            PartialDatagram::default,
+        );
+        let partial = match admission {
+            Admission::Tracked(partial) => partial,
+            Admission::PassThrough(reason) => return ReassemblyOutcome::PassThrough(reason),
+        };
+
+        if !partial.insert(fragment) {
+            self.table.remove(&fragment.key);
+            return ReassemblyOutcome::Invalid;
+        }
+        if !partial.is_complete() {
+            return ReassemblyOutcome::Pending;
+        }
+        let datagram = partial.assemble();
+        self.table.remove(&fragment.key);
+        ReassemblyOutcome::Complete(datagram)
+    }
+
+    /// Drops partial datagrams idle past the timeout, returning how many were dropped.
+    pub fn expire(&mut self, now: u64) -> usize {
+        self.table.evict_idle(now).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn key(src: u8, id: u32) -> FragmentKey {
+        FragmentKey {
+            src: IpAddr::V4(Ipv4Addr::new(192, 0, 2, src)),
+            dst: IpAddr::V4(Ipv4Addr::new(198, 51, 100, 1)),
+            id,
+            protocol: 17,
+        }
+    }
+
+    fn fragment(
+        key: FragmentKey,
+        offset: usize,
+        more: bool,
+        payload: &[u8],
+        ts: u64,
+    ) -> Fragment<'_> {
+        Fragment {
+            key,
+            offset,
+            more_fragments: more,
+            payload,
+            timestamp: ts,
+        }
+    }
+
+    #[test]
+    fn test_out_of_order_fragments_reassemble() {
+        let mut reassembler =
+            FragmentReassembler::new(FlowLimits::with_max_flows(16), 1_000).unwrap();
+        let k = key(1, 7);
+        assert_eq!(
+            reassembler.push(&fragment(k, 16, false, &[3; 4], 0)),
+            ReassemblyOutcome::Pending
+        );
+        assert_eq!(
+            reassembler.push(&fragment(k, 0, true, &[1; 8], 1)),
+            ReassemblyOutcome::Pending
+        );
+        let mut expected = vec![1; 8];
+        expected.extend([2; 8]);
+        expected.extend([3; 4]);
+        assert_eq!(
+            reassembler.push(&fragment(k, 8, true, &[2; 8], 2)),
+            ReassemblyOutcome::Complete(expected)
+        );
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_oversized_fragment_is_invalid() {
+        let mut reassembler =
+            FragmentReassembler::new(FlowLimits::with_max_flows(16), 1_000).unwrap();
+        assert_eq!(
+            reassembler.push(&fragment(key(1, 1), MAX_DATAGRAM_LEN, false, &[0; 8], 0)),
+            ReassemblyOutcome::Invalid
+        );
+    }
+
+    #[test]
+    fn test_overlapping_fragments_keep_lower_offset() {
+        let mut reassembler =
+            FragmentReassembler::new(FlowLimits::with_max_flows(16), 1_000).unwrap();
+        let k = key(1, 2);
+        assert_eq!(
+            reassembler.push(&fragment(k, 4, true, &[2; 8], 0)),
+            ReassemblyOutcome::Pending
+        );
+        assert_eq!(
+            reassembler.push(&fragment(k, 16, false, &[4; 4], 1)),
+            ReassemblyOutcome::Pending
+        );
+        // Bridges the gap at 12..16 but leaves 0..4 missing
+        assert_eq!(
+            reassembler.push(&fragment(k, 10, true, &[3; 6], 2)),
+            ReassemblyOutcome::Pending
+        );
+        let mut expected = vec![1; 8];
+        expected.extend([2; 4]);
+        expected.extend([3; 4]);
+        expected.extend([4; 4]);
+        assert_eq!(
+            reassembler.push(&fragment(k, 0, true, &[1; 8], 3)),
+            ReassemblyOutcome::Complete(expected)
+        );
+    }
+
+    #[test]
+    fn test_overlap_flood_past_datagram_limit_is_invalid() {
+        let mut reassembler =
+            FragmentReassembler::new(FlowLimits::with_max_flows(16), 1_000).unwrap();
+        let k = key(1, 3);
+        let payload = [0u8; 1_400];
+        let mut outcome = ReassemblyOutcome::Pending;
+        for offset in 1..100 {
+            outcome = reassembler.push(&fragment(k, offset * 8, true, &payload, 0));
+            if outcome != ReassemblyOutcome::Pending {
+                break;
+            }
+        }
+        assert_eq!(outcome, ReassemblyOutcome::Invalid);
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_fragment_flood_is_bounded() {
+        let limits = FlowLimits {
+            max_flows: 64,
+            max_flows_per_source: 32,
+            pressure_threshold: 0.75,
+            incomplete_timeout_ns: 1_000,
+        };
+        let mut reassembler = FragmentReassembler::new(limits, 60_000_000_000).unwrap();
+
+        // Attackers send first fragments that never complete
+        for i in 0..5_000u32 {
+            let attacker = key((i % 8) as u8, i);
+            reassembler.push(&fragment(attacker, 0, true, &[0; 8], i as u64 * 10));
+            assert!(reassembler.pending() <= 64);
+        }
+        assert!(reassembler.stats().pressure_evictions > 0);
+
+        // A legitimate datagram arriving during the flood still reassembles
+        let k = key(200, 1);
+        let now = 200_000;
+        assert_eq!(
+            reassembler.push(&fragment(k, 0, true, &[1; 8], now)),
+            ReassemblyOutcome::Pending
+        );
+        assert!(matches!(
+            reassembler.push(&fragment(k, 8, false, &[2; 8], now + 1)),
+            ReassemblyOutcome::Complete(_)
+        ));
+    }
+}
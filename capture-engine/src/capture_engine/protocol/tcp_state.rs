@@ -0,0 +1,622 @@
+// protocol/tcp_state.rs
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+use crate::capture_engine::capture::capture_error::CaptureError;
+use crate::capture_engine::protocol::flow::{EvictedFlow, FlowDirection, FlowKey, IPPROTO_TCP};
+use crate::capture_engine::protocol::flow_guard::{
+    Admission, FlowGuardStats, FlowLimits, GuardedFlowTable, TrackedFlow,
+};
+
+/// TCP header flags relevant to connection tracking.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TcpFlags {
+    pub syn: bool,
+    pub ack: bool,
+    pub fin: bool,
+    pub rst: bool,
+}
+
+impl TcpFlags {
+    pub const SYN: Self = Self {
+        syn: true,
+        ack: false,
+        fin: false,
+        rst: false,
+    };
+    pub const SYN_ACK: Self = Self {
+        syn: true,
+        ack: true,
+        fin: false,
+        rst: false,
+    };
+    pub const ACK: Self = Self {
+        syn: false,
+        ack: true,
+        fin: false,
+        rst: false,
+    };
+    pub const FIN_ACK: Self = Self {
+        syn: false,
+        ack: true,
+        fin: true,
+        rst: false,
+    };
+    pub const RST: Self = Self {
+        syn: false,
+        ack: false,
+        fin: false,
+        rst: true,
+    };
+
+    /// Decodes the flags byte of a TCP header.
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            fin: bits & 0x01 != 0,
+            syn: bits & 0x02 != 0,
+            rst: bits & 0x04 != 0,
+            ack: bits & 0x10 != 0,
+        }
+    }
+}
+
+/// The parts of a TCP segment the tracker needs.
+#[derive(Debug, Clone, Copy)]
+pub struct TcpSegment {
+    pub src: IpAddr,
+    pub src_port: u16,
+    pub dst: IpAddr,
+    pub dst_port: u16,
+    pub flags: TcpFlags,
+    pub seq: u32,
+    /// Capture timestamp in nanoseconds.
+    pub timestamp: u64,
+}
+
+/// Connection state as inferred from observed segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TcpState {
+    SynSent,
+    SynReceived,
+    Established,
+    /// One side has sent FIN.
+    FinWait,
+    /// Closed or reset; kept until idle eviction so trailing segments are not mistaken for a
+    /// new connection.
+    Closed,
+}
+
+/// Per-flow TCP connection tracking state.
+#[derive(Debug, Clone)]
+pub struct TcpConnection {
+    pub state: TcpState,
+    /// Direction of the side that opened the connection, or of the first segment seen.
+    pub initiator: FlowDirection,
+    /// Whether the three-way handshake was observed rather than inferred.
+    pub handshake_observed: bool,
+    /// Initial sequence number of the initiator, if its SYN was seen.
+    pub initiator_isn: Option<u32>,
+    fin_from: Option<FlowDirection>,
+    opened_at: u64,
+}
+
+impl TrackedFlow for TcpConnection {
+    fn is_established(&self) -> bool {
+        matches!(self.state, TcpState::Established | TcpState::FinWait)
+    }
+}
+
+/// Kinds of connection lifecycle events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionEventKind {
+    Opened,
+    Closed,
+    Reset,
+    /// The connection went idle or was evicted without being closed.
+    Expired,
+}
+
+/// A connection lifecycle event, suitable for output and telemetry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionEvent {
+    pub flow: FlowKey,
+    pub kind: ConnectionEventKind,
+    pub timestamp: u64,
+    /// Time since the connection was first seen, in nanoseconds.
+    pub duration_ns: u64,
+    pub handshake_observed: bool,
+}
+
+/// Tracks TCP connection state per flow and emits lifecycle events.
+///
+/// Capture is lossy, so the tracker never requires a complete handshake or teardown: a
+/// connection first seen mid-stream is treated as established with `handshake_observed` unset,
+/// a lost SYN-ACK or final ACK is inferred from later traffic, and connections that never close
+/// are expired by idle eviction.
+///
+/// Connections are held in a `GuardedFlowTable`, so a SYN flood fills the table with
+/// half-open entries that are evicted first, and established connections keep their state.
+#[derive(Debug)]
+pub struct TcpTracker {
+    flows: GuardedFlowTable<TcpConnection>,
+}
+
+impl TcpTracker {
+    /// Creates a tracker bounded to `capacity` connections with default flood limits.
+    pub fn new(capacity: usize, idle_timeout_ns: u64) -> Self {
+        Self::with_limits(FlowLimits::with_max_flows(capacity.max(1)), idle_timeout_ns)
+            .expect("default flow limits are valid")
+    }
+
+    /// Creates a tracker with explicit flood limits.
+    pub fn with_limits(limits: FlowLimits, idle_timeout_ns: u64) -> Result<Self, CaptureError> {
+        Ok(Self {
+            flows: GuardedFlowTable::new(limits, idle_timeout_ns)?,
+        })
+    }
+
+    /// Counters describing how the tracker handled load.
+    pub fn guard_stats(&self) -> &FlowGuardStats {
+        self.flows.stats()
+    }
+
+    /// Number of tracked connections.
+    pub fn len(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// Whether no connections are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+
+    /// Returns the tracked state of a connection.
+    pub fn connection(&self, key: &FlowKey) -> Option<&TcpConnection> {
+        self.flows.get(key)
+    }
+
+    /// Feeds a segment into the tracker, returning any lifecycle events it caused.
+    pub fn observe(&mut self, segment: &TcpSegment) -> Vec<ConnectionEvent> {
+        let key = FlowKey::new(
+            segment.src,
+            segment.src_port,
+            segment.dst,
+            segment.dst_port,
+            IPPROTO_TCP,
+        );
+        let direction = key.direction_of(segment.src, segment.src_port);
+        let now = segment.timestamp;
+        let mut events = Vec::new();
+
+        let is_new = self.flows.get(&key).is_none();
+        if is_new && segment.flags.rst {
+            // Reset for a connection we never tracked; nothing to report
+            return events;
+        }
+
+        // Attribute the flow to the side that opened it for per-source limits
+        let source = if segment.flags.syn && segment.flags.ack {
+            segment.dst
+        } else {
+            segment.src
+        };
+        let (admission, evicted) = self.flows.admit(key, source, now, || {
+            Self::new_connection(segment.flags, segment.seq, direction, now)
+        });
+        events.extend(evicted.into_iter().filter_map(Self::expired));
+        let connection = match admission {
+            Admission::Tracked(connection) => connection,
+            // Not tracked: the segment still flows through the pipeline, without lifecycle events
+            Admission::PassThrough(_) => return events,
+        };
+
+        if is_new {
+            if connection.state == TcpState::Established {
+                events.push(Self::event(
+                    &key,
+                    connection,
+                    ConnectionEventKind::Opened,
+                    now,
+                ));
+            }
+            return events;
+        }
+
+        let flags = segment.flags;
+        if connection.state == TcpState::Closed {
+            if flags.syn && !flags.ack {
+                // Port reuse: a fresh SYN starts a new connection on the same flow
+                *connection = Self::new_connection(flags, segment.seq, direction, now);
+            }
+            return events;
+        }
+
+        if flags.rst {
+            connection.state = TcpState::Closed;
+            events.push(Self::event(
+                &key,
+                connection,
+                ConnectionEventKind::Reset,
+                now,
+            ));
+            return events;
+        }
+
+        let from_initiator = direction == connection.initiator;
+        match connection.state {
+            TcpState::SynSent => {
+                if flags.syn && flags.ack && !from_initiator {
+                    connection.state = TcpState::SynReceived;
+                } else if flags.ack && from_initiator && !flags.syn {
+                    // SYN-ACK was not captured; the initiator acknowledging means it arrived
+                    connection.state = TcpState::Established;
+                    events.push(Self::event(
+                        &key,
+                        connection,
+                        ConnectionEventKind::Opened,
+                        now,
+                    ));
+                }
+            }
+            TcpState::SynReceived => {
+                if flags.ack && !flags.syn {
+                    connection.state = TcpState::Established;
+                    events.push(Self::event(
+                        &key,
+                        connection,
+                        ConnectionEventKind::Opened,
+                        now,
+                    ));
+                }
+            }
+            TcpState::Established | TcpState::FinWait | TcpState::Closed => {}
+        }
+
+        if flags.fin {
+            match connection.fin_from {
+                None => {
+                    if connection.state != TcpState::Established {
+                        // Handshake completion was not captured before teardown began
+                        connection.state = TcpState::Established;
+                        events.push(Self::event(
+                            &key,
+                            connection,
+                            ConnectionEventKind::Opened,
+                            now,
+                        ));
+                    }
+                    connection.fin_from = Some(direction);
+                    connection.state = TcpState::FinWait;
+                }
+                Some(first) if first != direction => {
+                    connection.state = TcpState::Closed;
+                    events.push(Self::event(
+                        &key,
+                        connection,
+                        ConnectionEventKind::Closed,
+                        now,
+                    ));
+                }
+                // Retransmitted FIN
+                Some(_) => {}
+            }
+        }
+
+        events
+    }
+
+    /// Expires connections idle past the timeout at `now`.
+    pub fn expire_idle(&mut self, now: u64) -> Vec<ConnectionEvent> {
+        self.flows
+            .evict_idle(now)
+            .into_iter()
+            .filter_map(Self::expired)
+            .collect()
+    }
+
+    fn new_connection(
+        flags: TcpFlags,
+        seq: u32,
+        direction: FlowDirection,
+        now: u64,
+    ) -> TcpConnection {
+        let (state, initiator, initiator_isn) = match (flags.syn, flags.ack) {
+            (true, false) => (TcpState::SynSent, direction, Some(seq)),
+            // First seen segment is the SYN-ACK, so the other side initiated
+            (true, true) => (TcpState::SynReceived, direction.reverse(), None),
+            _ => (TcpState::Established, direction, None),
+        };
+
+        TcpConnection {
+            state,
+            initiator,
+            handshake_observed: flags.syn,
+            initiator_isn,
+            fin_from: if flags.fin { Some(direction) } else { None },
+            opened_at: now,
+        }
+    }
+
+    fn expired(evicted: EvictedFlow<TcpConnection>) -> Option<ConnectionEvent> {
+        // Connections that never got past the handshake were never reported as opened
+        match evicted.value.state {
+            TcpState::Established | TcpState::FinWait => Some(ConnectionEvent {
+                flow: evicted.key,
+                kind: ConnectionEventKind::Expired,
+                timestamp: evicted.last_seen,
+                duration_ns: evicted.last_seen.saturating_sub(evicted.value.opened_at),
+                handshake_observed: evicted.value.handshake_observed,
+            }),
+            _ => None,
+        }
+    }
+
+    fn event(
+        key: &FlowKey,
+        connection: &TcpConnection,
+        kind: ConnectionEventKind,
+        now: u64,
+    ) -> ConnectionEvent {
+        ConnectionEvent {
+            flow: *key,
+            kind,
+            timestamp: now,
+            duration_ns: now.saturating_sub(connection.opened_at),
+            handshake_observed: connection.handshake_observed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const CLIENT: (IpAddr, u16) = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5)), 51000);
+    const SERVER: (IpAddr, u16) = (IpAddr::V4(Ipv4Addr::new(10, 0, 1, 9)), 443);
+
+    fn from_client(flags: TcpFlags, seq: u32, timestamp: u64) -> TcpSegment {
+        TcpSegment {
+            src: CLIENT.0,
+            src_port: CLIENT.1,
+            dst: SERVER.0,
+            dst_port: SERVER.1,
+            flags,
+            seq,
+            timestamp,
+        }
+    }
+
+    fn from_server(flags: TcpFlags, seq: u32, timestamp: u64) -> TcpSegment {
+        TcpSegment {
+            src: SERVER.0,
+            src_port: SERVER.1,
+            dst: CLIENT.0,
+            dst_port: CLIENT.1,
+            flags,
+            seq,
+            timestamp,
+        }
+    }
+
+    fn kinds(events: &[ConnectionEvent]) -> Vec<ConnectionEventKind> {
+        events.iter().map(|e| e.kind).collect()
+    }
+
+    fn run(tracker: &mut TcpTracker, segments: &[TcpSegment]) -> Vec<ConnectionEvent> {
+        segments.iter().flat_map(|s| tracker.observe(s)).collect()
+    }
+
+    #[test]
+    fn test_full_handshake_and_teardown() {
+        let mut tracker = TcpTracker::new(16, 60_000_000_000);
+        let events = run(
+            &mut tracker,
+            &[
+                from_client(TcpFlags::SYN, 100, 1_000),
+                from_server(TcpFlags::SYN_ACK, 900, 2_000),
+                from_client(TcpFlags::ACK, 101, 3_000),
+                from_client(TcpFlags::ACK, 101, 4_000),
+                from_client(TcpFlags::FIN_ACK, 500, 10_000),
+                from_server(TcpFlags::ACK, 901, 11_000),
+                from_server(TcpFlags::FIN_ACK, 901, 12_000),
+                from_client(TcpFlags::ACK, 501, 13_000),
+            ],
+        );
+
+        assert_eq!(
+            kinds(&events),
+            vec![ConnectionEventKind::Opened, ConnectionEventKind::Closed]
+        );
+        assert!(events[0].handshake_observed);
+        assert_eq!(events[0].duration_ns, 2_000);
+        assert_eq!(events[1].duration_ns, 11_000);
+        // The trailing ACK after close does not open a new connection
+        assert_eq!(
+            tracker.connection(&events[0].flow).unwrap().state,
+            TcpState::Closed
+        );
+    }
+
+    #[test]
+    fn test_reset_produces_reset_event() {
+        let mut tracker = TcpTracker::new(16, 60_000_000_000);
+        let events = run(
+            &mut tracker,
+            &[
+                from_client(TcpFlags::SYN, 100, 1_000),
+                from_server(TcpFlags::SYN_ACK, 900, 2_000),
+                from_client(TcpFlags::ACK, 101, 3_000),
+                from_server(TcpFlags::RST, 901, 8_000),
+            ],
+        );
+
+        assert_eq!(
+            kinds(&events),
+            vec![ConnectionEventKind::Opened, ConnectionEventKind::Reset]
+        );
+        assert_eq!(events[1].duration_ns, 7_000);
+        // Closed flows are not reported again when they age out
+        assert!(tracker.expire_idle(u64::MAX).is_empty());
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_missing_syn_ack_is_inferred() {
+        let mut tracker = TcpTracker::new(16, 60_000_000_000);
+        let events = run(
+            &mut tracker,
+            &[
+                from_client(TcpFlags::SYN, 100, 1_000),
+                from_client(TcpFlags::ACK, 101, 3_000),
+            ],
+        );
+        assert_eq!(kinds(&events), vec![ConnectionEventKind::Opened]);
+    }
+
+    #[test]
+    fn test_mid_stream_connection_is_not_claimed_as_handshake() {
+        let mut tracker = TcpTracker::new(16, 60_000_000_000);
+        let events = run(
+            &mut tracker,
+            &[
+                from_server(TcpFlags::ACK, 5_000, 1_000),
+                from_server(TcpFlags::FIN_ACK, 6_000, 2_000),
+                from_client(TcpFlags::FIN_ACK, 7_000, 3_000),
+            ],
+        );
+
+        assert_eq!(
+            kinds(&events),
+            vec![ConnectionEventKind::Opened, ConnectionEventKind::Closed]
+        );
+        assert!(events.iter().all(|e| !e.handshake_observed));
+    }
+
+    #[test]
+    fn test_syn_after_close_starts_new_connection() {
+        let mut tracker = TcpTracker::new(16, 60_000_000_000);
+        let events = run(
+            &mut tracker,
+            &[
+                from_client(TcpFlags::ACK, 1, 1_000),
+                from_client(TcpFlags::RST, 2, 2_000),
+                from_client(TcpFlags::SYN, 100, 3_000),
+                from_server(TcpFlags::SYN_ACK, 900, 4_000),
+                from_client(TcpFlags::ACK, 101, 5_000),
+            ],
+        );
+        assert_eq!(
+            kinds(&events),
+            vec![
+                ConnectionEventKind::Opened,
+                ConnectionEventKind::Reset,
+                ConnectionEventKind::Opened
+            ]
+        );
+        assert!(events[2].handshake_observed);
+        assert_eq!(events[2].duration_ns, 2_000);
+    }
+
+    #[test]
+    fn test_idle_connection_expires() {
+        let mut tracker = TcpTracker::new(16, 1_000);
+        run(
+            &mut tracker,
+            &[
+                from_client(TcpFlags::SYN, 100, 0),
+                from_server(TcpFlags::SYN_ACK, 900, 100),
+                from_client(TcpFlags::ACK, 101, 200),
+            ],
+        );
+
+        assert!(tracker.expire_idle(1_000).is_empty());
+        let events = tracker.expire_idle(5_000);
+        assert_eq!(kinds(&events), vec![ConnectionEventKind::Expired]);
+        assert_eq!(events[0].duration_ns, 200);
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_tracker_is_bounded() {
+        let mut tracker = TcpTracker::new(2, 60_000_000_000);
+        for port in 0..5u16 {
+            tracker.observe(&TcpSegment {
+                src_port: 1000 + port,
+                ..from_client(TcpFlags::ACK, 1, port as u64)
+            });
+        }
+        assert_eq!(tracker.len(), 2);
+    }
+
+    #[test]
+    fn test_syn_flood_keeps_established_connections() {
+        let limits = FlowLimits {
+            max_flows: 64,
+            max_flows_per_source: 16,
+            pressure_threshold: 0.75,
+            incomplete_timeout_ns: 1_000,
+        };
+        let mut tracker = TcpTracker::with_limits(limits, 60_000_000_000).unwrap();
+
+        let legit: Vec<u16> = (0..8).map(|i| 50_000 + i).collect();
+        for &port in &legit {
+            let syn = TcpSegment {
+                src_port: port,
+                ..from_client(TcpFlags::SYN, 1, 0)
+            };
+            let syn_ack = TcpSegment {
+                dst_port: port,
+                ..from_server(TcpFlags::SYN_ACK, 1, 1)
+            };
+            let ack = TcpSegment {
+                src_port: port,
+                ..from_client(TcpFlags::ACK, 2, 2)
+            };
+            run(&mut tracker, &[syn, syn_ack, ack]);
+        }
+
+        // Spoofed SYNs from many sources, plus one source opening as many flows as it can
+        for i in 0..5_000u32 {
+            let source = if i % 2 == 0 {
+                IpAddr::V4(Ipv4Addr::new(203, 0, (i >> 8) as u8, i as u8))
+            } else {
+                IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7))
+            };
+            tracker.observe(&TcpSegment {
+                src: source,
+                src_port: (i % 60_000) as u16,
+                ..from_client(TcpFlags::SYN, i, 10 + i as u64 * 10)
+            });
+            assert!(tracker.len() <= 64);
+        }
+
+        let stats = tracker.guard_stats();
+        assert!(stats.pressure_evictions > 0);
+        assert!(stats.pass_through_source_limit > 0);
+
+        // Every legitimate connection kept its state and still closes cleanly
+        let now = 1_000_000;
+        for &port in &legit {
+            let fin = TcpSegment {
+                src_port: port,
+                ..from_client(TcpFlags::FIN_ACK, 3, now)
+            };
+            let fin_back = TcpSegment {
+                dst_port: port,
+                ..from_server(TcpFlags::FIN_ACK, 3, now + 1)
+            };
+            let events = run(&mut tracker, &[fin, fin_back]);
+            assert_eq!(kinds(&events), vec![ConnectionEventKind::Closed]);
+            assert!(events[0].handshake_observed);
+        }
+    }
+
+    #[test]
+    fn test_flags_from_bits() {
+        assert_eq!(TcpFlags::from_bits(0x12), TcpFlags::SYN_ACK);
+        assert_eq!(TcpFlags::from_bits(0x11), TcpFlags::FIN_ACK);
+        assert_eq!(TcpFlags::from_bits(0x04), TcpFlags::RST);
+    }
+}
@@ -23,11 +23,34 @@ pub trait ProtocolManager: Lifecycle + PacketProcessor + HealthCheck + Send + Sy
         -> Result<(), Error>;
 }
 
+/// Headers parsed before the rest of a packet is passed through unparsed, by default.
+pub const DEFAULT_MAX_PARSE_DEPTH: usize = 32;
+
+/// Protocol parsing settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolConfig {
+    /// Most headers parsed per packet, counting every VLAN tag, MPLS label, extension header
+    /// and tunnel header; bounds the work spent on deeply nested encapsulation.
+    pub max_parse_depth: usize,
+}
+
+impl Default for ProtocolConfig {
+    fn default() -> Self {
+        Self {
+            max_parse_depth: DEFAULT_MAX_PARSE_DEPTH,
+        }
+    }
+}
+
 /// Information extracted from packet headers.
 #[derive(Debug, Clone)]
 pub struct HeaderInfo {
     pub protocols: Vec<String>,
     pub fields: HashMap<String, String>,
+    /// Headers parsed.
+    pub layers_parsed: usize,
+    /// Whether a depth limit stopped parsing before the innermost header.
+    pub parse_truncated: bool,
 }
 
 /// Result of a deep packet inspection.
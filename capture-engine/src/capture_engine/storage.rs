@@ -1 +1,2 @@
+pub mod durable_writer;
 pub mod traits;
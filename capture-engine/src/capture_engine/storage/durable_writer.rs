@@ -0,0 +1,458 @@
+// storage/durable_writer.rs
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom, Write};
+
+use crate::capture_engine::output::verification::ContentHasher;
+use crate::capture_engine::storage::traits::StorageId;
+use crate::traits::Error;
+
+/// Append-only file a `DurableWriter` writes records to.
+pub trait RecordFile: Write + Send {
+    /// Flushes written data to stable storage.
+    fn sync(&mut self) -> io::Result<()>;
+
+    /// Discards everything past `len` bytes and positions writes at the new end.
+    fn truncate(&mut self, len: u64) -> io::Result<()>;
+
+    /// Copies everything from `offset` to the end into `sink`, leaving writes at the end.
+    fn read_from(&mut self, offset: u64, sink: &mut dyn Write) -> io::Result<u64>;
+}
+
+impl RecordFile for File {
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_data()
+    }
+
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.set_len(len)?;
+        self.seek(SeekFrom::Start(len)).map(|_| ())
+    }
+
+    /// Needs the file opened for reading as well as writing.
+    fn read_from(&mut self, offset: u64, sink: &mut dyn Write) -> io::Result<u64> {
+        self.seek(SeekFrom::Start(offset))?;
+        let copied = io::copy(self, sink);
+        self.seek(SeekFrom::End(0))?;
+        copied
+    }
+}
+
+/// When written records become durable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurabilityPolicy {
+    /// Records are durable only after an explicit `sync`.
+    Buffered,
+    /// Every record is synced before it is acknowledged.
+    SyncEachWrite,
+}
+
+/// Acknowledgement of a completely written record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteAck {
+    pub id: StorageId,
+    pub offset: u64,
+    pub len: u64,
+    /// Whether the record has been synced; false under `Buffered` until `sync` succeeds.
+    pub durable: bool,
+}
+
+/// A record that could not be written in full.
+#[derive(Debug)]
+pub struct PartialWriteError {
+    pub written: usize,
+    pub expected: usize,
+    /// Whether the partial bytes were removed; if not the file ends in an incomplete record.
+    pub rolled_back: bool,
+    pub source: io::Error,
+}
+
+impl fmt::Display for PartialWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "wrote {} of {} bytes ({}): {}",
+            self.written,
+            self.expected,
+            if self.rolled_back {
+                "rolled back"
+            } else {
+                "incomplete record left in place"
+            },
+            self.source
+        )
+    }
+}
+
+impl std::error::Error for PartialWriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<PartialWriteError> for Error {
+    fn from(err: PartialWriteError) -> Self {
+        Error::IO(io::Error::new(err.source.kind(), err))
+    }
+}
+
+/// Appends records to a file, handling short writes and tracking durability.
+///
+/// A record is written by looping until every byte is accepted. If a write fails part-way the
+/// partial bytes are truncated away, so the file only ever holds complete records; if that
+/// rollback also fails the writer refuses further records rather than appending after a
+/// corrupt one. A failed sync likewise stops the writer, since data the kernel failed to flush
+/// cannot be assumed written.
+///
+/// With read-back verification, each sync reads the newly synced records back and compares
+/// them with the hash of what was written; a mismatch fails the sync and stops the writer.
+pub struct DurableWriter<F: RecordFile> {
+    file: F,
+    policy: DurabilityPolicy,
+    name: String,
+    /// Bytes of complete records.
+    written_len: u64,
+    /// Bytes known to be on stable storage.
+    synced_len: u64,
+    /// Whether synced records are read back and checked.
+    verify: bool,
+    /// Hash of the records written since the last verified sync.
+    hasher: ContentHasher,
+    failed: Option<String>,
+}
+
+impl<F: RecordFile> DurableWriter<F> {
+    /// Writes to `file`, which holds `existing_len` bytes of complete, synced records.
+    pub fn new(name: &str, file: F, existing_len: u64, policy: DurabilityPolicy) -> Self {
+        Self {
+            file,
+            policy,
+            name: name.to_string(),
+            written_len: existing_len,
+            synced_len: existing_len,
+            verify: false,
+            hasher: ContentHasher::new(),
+            failed: None,
+        }
+    }
+
+    /// Reads records back after each sync and checks them against what was written.
+    pub fn with_readback_verification(mut self) -> Self {
+        self.verify = true;
+        self
+    }
+
+    /// Bytes of complete records in the file.
+    pub fn written_len(&self) -> u64 {
+        self.written_len
+    }
+
+    /// Bytes known to be durable.
+    pub fn synced_len(&self) -> u64 {
+        self.synced_len
+    }
+
+    /// Whether an acknowledged record has since been synced.
+    pub fn is_durable(&self, ack: &WriteAck) -> bool {
+        ack.offset + ack.len <= self.synced_len
+    }
+
+    /// Why the writer stopped accepting records, if it has.
+    pub fn failure(&self) -> Option<&str> {
+        self.failed.as_deref()
+    }
+
+    /// Appends one record.
+    pub fn write_record(&mut self, data: &[u8]) -> Result<WriteAck, Error> {
+        self.check_usable()?;
+        let offset = self.written_len;
+
+        let mut written = 0;
+        while written < data.len() {
+            match self.file.write(&data[written..]) {
+                Ok(0) => {
+                    return Err(self.rollback(
+                        offset,
+                        written,
+                        data.len(),
+                        io::Error::new(io::ErrorKind::WriteZero, "storage accepted no bytes"),
+                    ))
+                }
+                Ok(n) => written += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.rollback(offset, written, data.len(), e)),
+            }
+        }
+        if let Err(e) = self.file.flush() {
+            return Err(self.rollback(offset, written, data.len(), e));
+        }
+        self.written_len += data.len() as u64;
+        self.hasher.update(data);
+
+        if self.policy == DurabilityPolicy::SyncEachWrite {
+            self.sync()?;
+        }
+        Ok(WriteAck {
+            id: StorageId::new(format!("{}@{}", self.name, offset)),
+            offset,
+            len: data.len() as u64,
+            durable: self.synced_len >= self.written_len,
+        })
+    }
+
+    /// Makes every written record durable.
+    pub fn sync(&mut self) -> Result<(), Error> {
+        self.check_usable()?;
+        if self.synced_len == self.written_len {
+            return Ok(());
+        }
+        if let Err(e) = self.file.sync() {
+            self.failed = Some(format!(
+                "sync failed with {} bytes unsynced: {}",
+                self.written_len - self.synced_len,
+                e
+            ));
+            return Err(Error::IO(e));
+        }
+        let offset = self.synced_len;
+        self.synced_len = self.written_len;
+        if self.verify {
+            self.verify_from(offset)?;
+        }
+        Ok(())
+    }
+
+    /// Consumes the writer, returning the file.
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+
+    /// Compares the records from `offset` to the end with the hash of what was written.
+    fn verify_from(&mut self, offset: u64) -> Result<(), Error> {
+        let expected = std::mem::take(&mut self.hasher).finish();
+        let mut read = ContentHasher::new();
+        if let Err(e) = self.file.read_from(offset, &mut read) {
+            self.failed = Some(format!("read-back from offset {} failed: {}", offset, e));
+            return Err(Error::IO(e));
+        }
+        let actual = read.finish();
+        if actual != expected {
+            self.failed = Some(format!(
+                "checksum mismatch from offset {}: expected {}, read back {}",
+                offset, expected, actual
+            ));
+            return Err(self.check_usable().unwrap_err());
+        }
+        Ok(())
+    }
+
+    fn check_usable(&self) -> Result<(), Error> {
+        match &self.failed {
+            Some(reason) => Err(Error::Runtime(format!(
+                "Storage writer {} is unusable: {}",
+                self.name, reason
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn rollback(
+        &mut self,
+        offset: u64,
+        written: usize,
+        expected: usize,
+        source: io::Error,
+    ) -> Error {
+        let rolled_back = written == 0 || self.file.truncate(offset).is_ok();
+        if !rolled_back {
+            self.failed = Some(format!("incomplete record at offset {}", offset));
+        }
+        PartialWriteError {
+            written,
+            expected,
+            rolled_back,
+            source,
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory file accepting at most `chunk` bytes per write and failing after `fail_after`.
+    #[derive(Default)]
+    struct MockFile {
+        contents: Vec<u8>,
+        chunk: usize,
+        fail_after: Option<usize>,
+        fail_truncate: bool,
+        fail_sync: bool,
+        corrupt: bool,
+        syncs: usize,
+        writes: usize,
+    }
+
+    impl Write for MockFile {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes += 1;
+            let mut n = buf.len().min(self.chunk);
+            if let Some(limit) = self.fail_after {
+                let remaining = limit.saturating_sub(self.contents.len());
+                if remaining == 0 {
+                    return Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"));
+                }
+                n = n.min(remaining);
+            }
+            let start = self.contents.len();
+            self.contents.extend_from_slice(&buf[..n]);
+            if self.corrupt {
+                self.contents[start] ^= 0xff;
+            }
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl RecordFile for MockFile {
+        fn sync(&mut self) -> io::Result<()> {
+            if self.fail_sync {
+                return Err(io::Error::other("EIO"));
+            }
+            self.syncs += 1;
+            Ok(())
+        }
+
+        fn truncate(&mut self, len: u64) -> io::Result<()> {
+            if self.fail_truncate {
+                return Err(io::Error::other("read-only"));
+            }
+            self.contents.truncate(len as usize);
+            Ok(())
+        }
+
+        fn read_from(&mut self, offset: u64, sink: &mut dyn Write) -> io::Result<u64> {
+            let tail = &self.contents[offset as usize..];
+            sink.write_all(tail)?;
+            Ok(tail.len() as u64)
+        }
+    }
+
+    fn mock(chunk: usize) -> MockFile {
+        MockFile {
+            chunk,
+            ..Default::default()
+        }
+    }
+
+    fn partial(err: &Error) -> &PartialWriteError {
+        let Error::IO(io) = err else {
+            panic!("expected an IO error, got {:?}", err);
+        };
+        io.get_ref().unwrap().downcast_ref().unwrap()
+    }
+
+    #[test]
+    fn test_short_writes_retried_to_completion() {
+        let mut writer = DurableWriter::new("seg", mock(3), 0, DurabilityPolicy::SyncEachWrite);
+        let first = writer.write_record(b"hello world").unwrap();
+        let second = writer.write_record(b"again").unwrap();
+
+        assert_eq!(first.id, StorageId::new("seg@0"));
+        assert!(first.durable && second.durable);
+        assert_eq!(second.offset, 11);
+        let file = writer.into_inner();
+        assert_eq!(file.contents, b"hello worldagain");
+        assert_eq!(file.writes, 4 + 2);
+        assert_eq!(file.syncs, 2);
+    }
+
+    #[test]
+    fn test_failed_write_rolled_back_without_ack() {
+        let mut file = mock(4);
+        file.fail_after = Some(10);
+        let mut writer = DurableWriter::new("seg", file, 0, DurabilityPolicy::SyncEachWrite);
+        writer.write_record(b"ok").unwrap();
+
+        let err = writer.write_record(b"too long to fit").unwrap_err();
+        let partial = partial(&err);
+        assert_eq!((partial.written, partial.expected), (8, 15));
+        assert!(partial.rolled_back);
+        assert_eq!(writer.written_len(), 2);
+        assert_eq!(writer.synced_len(), 2);
+        assert!(writer.failure().is_none());
+
+        // The writer stays usable and the file holds only the complete record
+        writer.write_record(b"fits").unwrap();
+        assert_eq!(writer.into_inner().contents, b"okfits");
+    }
+
+    #[test]
+    fn test_failed_rollback_marks_writer_unusable() {
+        let mut file = mock(4);
+        file.fail_after = Some(6);
+        file.fail_truncate = true;
+        let mut writer = DurableWriter::new("seg", file, 0, DurabilityPolicy::Buffered);
+
+        let err = writer.write_record(b"0123456789").unwrap_err();
+        assert!(!partial(&err).rolled_back);
+        assert!(writer.failure().unwrap().contains("offset 0"));
+        assert!(writer.write_record(b"x").is_err());
+        assert!(writer.sync().is_err());
+    }
+
+    #[test]
+    fn test_buffered_records_not_durable_until_synced() {
+        let mut writer = DurableWriter::new("seg", mock(64), 0, DurabilityPolicy::Buffered);
+        let ack = writer.write_record(b"record").unwrap();
+        assert!(!ack.durable);
+        assert!(!writer.is_durable(&ack));
+
+        writer.sync().unwrap();
+        assert!(writer.is_durable(&ack));
+    }
+
+    #[test]
+    fn test_sync_failure_gives_no_durable_ack() {
+        let mut file = mock(64);
+        file.fail_sync = true;
+        let mut writer = DurableWriter::new("seg", file, 0, DurabilityPolicy::SyncEachWrite);
+
+        assert!(writer.write_record(b"record").is_err());
+        assert_eq!(writer.synced_len(), 0);
+        assert!(writer.failure().unwrap().contains("sync failed"));
+        assert!(writer.write_record(b"next").is_err());
+    }
+
+    #[test]
+    fn test_verified_sync_checks_only_new_records() {
+        let mut writer = DurableWriter::new("seg", mock(64), 0, DurabilityPolicy::Buffered)
+            .with_readback_verification();
+        writer.write_record(b"first").unwrap();
+        writer.write_record(b"second").unwrap();
+        writer.sync().unwrap();
+
+        writer.file.corrupt = true;
+        let ack = writer.write_record(b"third").unwrap();
+        assert_eq!(ack.offset, 11);
+        assert!(writer.sync().is_err());
+        assert!(writer
+            .failure()
+            .unwrap()
+            .contains("checksum mismatch from offset 11"));
+        assert!(writer.write_record(b"fourth").is_err());
+    }
+
+    #[test]
+    fn test_unverified_writer_ignores_corruption() {
+        let mut file = mock(64);
+        file.corrupt = true;
+        let mut writer = DurableWriter::new("seg", file, 0, DurabilityPolicy::SyncEachWrite);
+        assert!(writer.write_record(b"record").unwrap().durable);
+        assert!(writer.failure().is_none());
+    }
+}
@@ -23,6 +23,11 @@ pub trait StorageManager:
     Lifecycle + EventHandler<StorageEvent> + PressureAware + Send + Sync
 {
     /// Writes data to storage.
+    ///
+    /// The id is returned only once the data is fully written and synced as the durability
+    /// policy requires; `DurableWriter` provides a write path with those guarantees. With
+    /// verification enabled, synced data must also read back matching what was written, which
+    /// `DurableWriter::with_readback_verification` checks.
     async fn write_data(&mut self, data: StorageData) -> Result<StorageId, Error>;
 
     /// Reads data from storage.
@@ -59,6 +64,18 @@ pub struct StorageMetadata {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct StorageId(String);
 
+impl StorageId {
+    /// Creates a storage identifier.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    /// Returns the identifier as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 /// Event when space thresholds are crossed.
 #[derive(Debug)]
 pub struct SpaceThresholdEvent {
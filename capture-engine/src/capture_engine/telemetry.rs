@@ -1 +1,4 @@
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod prometheus;
 pub mod traits;
@@ -0,0 +1,991 @@
+// telemetry/otlp.rs
+//! OpenTelemetry (OTLP/gRPC) metrics exporter.
+//!
+//! `TelemetryData` points are converted to OTLP metrics and sent with the unary
+//! `opentelemetry.proto.collector.metrics.v1.MetricsService/Export` call. Only the parts of the
+//! OTLP protocol the engine emits are modelled. Timestamps are taken as nanoseconds since the
+//! Unix epoch.
+//!
+//! Points are recorded into a bounded queue and exported in batches every `metrics_interval`.
+//! Exports that fail because the collector is unreachable or overloaded are retried with
+//! exponential backoff; any export failure surfaces as `Error::Communication`. Points a
+//! collector rejects in a partial success are counted as dropped and never resent, as the
+//! OTLP specification requires.
+//!
+//! `OtlpExporter` is a `TelemetryManager`: collected metrics are queued, `report_metrics`
+//! exports them, and `export_metrics` encodes the queue as `ExportFormat::OpenTelemetry`
+//! (a protobuf `ExportMetricsServiceRequest`) or `ExportFormat::Prometheus` text.
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use prost::Message;
+use tonic::client::Grpc;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Status};
+
+use crate::capture_engine::capture::capture_error::{
+    CaptureError, CaptureErrorKind, ConfigErrorKind,
+};
+use crate::capture_engine::control::grpc_reporter::TlsConfig;
+use crate::capture_engine::telemetry::prometheus;
+use crate::capture_engine::telemetry::traits::{
+    ExportFormat, MetricType, MetricUnit, MetricValue, TelemetryData, TelemetryManager,
+};
+use crate::traits::{Error, HealthCheck, HealthStatus, Lifecycle};
+
+/// gRPC service that receives metrics.
+pub const METRICS_SERVICE: &str = "opentelemetry.proto.collector.metrics.v1.MetricsService";
+/// Path of the unary export call.
+pub const EXPORT_METRICS_PATH: &str =
+    "/opentelemetry.proto.collector.metrics.v1.MetricsService/Export";
+/// Instrumentation scope the engine's metrics are reported under.
+pub const SCOPE_NAME: &str = "sparktrap.capture_engine";
+
+/// `AggregationTemporality::AGGREGATION_TEMPORALITY_CUMULATIVE`.
+const CUMULATIVE: i32 = 2;
+
+/// `ExportMetricsServiceRequest`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExportMetricsServiceRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub resource_metrics: Vec<ResourceMetrics>,
+}
+
+/// `ExportMetricsServiceResponse`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExportMetricsServiceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub partial_success: Option<ExportMetricsPartialSuccess>,
+}
+
+/// `ExportMetricsPartialSuccess`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExportMetricsPartialSuccess {
+    #[prost(int64, tag = "1")]
+    pub rejected_data_points: i64,
+    #[prost(string, tag = "2")]
+    pub error_message: String,
+}
+
+/// `ResourceMetrics`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResourceMetrics {
+    #[prost(message, optional, tag = "1")]
+    pub resource: Option<Resource>,
+    #[prost(message, repeated, tag = "2")]
+    pub scope_metrics: Vec<ScopeMetrics>,
+}
+
+/// `Resource`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Resource {
+    #[prost(message, repeated, tag = "1")]
+    pub attributes: Vec<KeyValue>,
+}
+
+/// `ScopeMetrics`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ScopeMetrics {
+    #[prost(message, optional, tag = "1")]
+    pub scope: Option<InstrumentationScope>,
+    #[prost(message, repeated, tag = "2")]
+    pub metrics: Vec<Metric>,
+}
+
+/// `InstrumentationScope`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct InstrumentationScope {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub version: String,
+}
+
+/// `Metric`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Metric {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub description: String,
+    #[prost(string, tag = "3")]
+    pub unit: String,
+    #[prost(oneof = "MetricData", tags = "5, 7, 9")]
+    pub data: Option<MetricData>,
+}
+
+/// Data of a `Metric`.
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum MetricData {
+    #[prost(message, tag = "5")]
+    Gauge(Gauge),
+    #[prost(message, tag = "7")]
+    Sum(Sum),
+    #[prost(message, tag = "9")]
+    Histogram(Histogram),
+}
+
+/// `Gauge`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Gauge {
+    #[prost(message, repeated, tag = "1")]
+    pub data_points: Vec<NumberDataPoint>,
+}
+
+/// `Sum`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Sum {
+    #[prost(message, repeated, tag = "1")]
+    pub data_points: Vec<NumberDataPoint>,
+    #[prost(int32, tag = "2")]
+    pub aggregation_temporality: i32,
+    #[prost(bool, tag = "3")]
+    pub is_monotonic: bool,
+}
+
+/// `Histogram`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Histogram {
+    #[prost(message, repeated, tag = "1")]
+    pub data_points: Vec<HistogramDataPoint>,
+    #[prost(int32, tag = "2")]
+    pub aggregation_temporality: i32,
+}
+
+/// `NumberDataPoint`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NumberDataPoint {
+    #[prost(message, repeated, tag = "7")]
+    pub attributes: Vec<KeyValue>,
+    #[prost(fixed64, tag = "3")]
+    pub time_unix_nano: u64,
+    #[prost(oneof = "NumberValue", tags = "4, 6")]
+    pub value: Option<NumberValue>,
+}
+
+/// Value of a `NumberDataPoint`.
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum NumberValue {
+    #[prost(double, tag = "4")]
+    AsDouble(f64),
+    #[prost(sfixed64, tag = "6")]
+    AsInt(i64),
+}
+
+/// `HistogramDataPoint`; `bucket_counts` has one more entry than `explicit_bounds`, the last
+/// counting values above every bound.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HistogramDataPoint {
+    #[prost(message, repeated, tag = "9")]
+    pub attributes: Vec<KeyValue>,
+    #[prost(fixed64, tag = "3")]
+    pub time_unix_nano: u64,
+    #[prost(fixed64, tag = "4")]
+    pub count: u64,
+    #[prost(double, optional, tag = "5")]
+    pub sum: Option<f64>,
+    #[prost(fixed64, repeated, tag = "6")]
+    pub bucket_counts: Vec<u64>,
+    #[prost(double, repeated, tag = "7")]
+    pub explicit_bounds: Vec<f64>,
+}
+
+/// `KeyValue` with a string value.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct KeyValue {
+    #[prost(string, tag = "1")]
+    pub key: String,
+    #[prost(message, optional, tag = "2")]
+    pub value: Option<AnyValue>,
+}
+
+/// `AnyValue`, of which only strings are emitted.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AnyValue {
+    #[prost(string, tag = "1")]
+    pub string_value: String,
+}
+
+/// UCUM unit OTLP expects for a metric unit.
+pub fn otlp_unit(unit: &MetricUnit) -> &'static str {
+    match unit {
+        MetricUnit::Nanoseconds => "ns",
+        MetricUnit::Microseconds => "us",
+        MetricUnit::Milliseconds => "ms",
+        MetricUnit::Seconds => "s",
+        MetricUnit::Bytes => "By",
+        MetricUnit::Kilobytes => "kBy",
+        MetricUnit::Megabytes => "MBy",
+        MetricUnit::Gigabytes => "GBy",
+        MetricUnit::PacketsPerSecond => "{packet}/s",
+        MetricUnit::BytesPerSecond => "By/s",
+        MetricUnit::Percent => "%",
+        MetricUnit::Count => "1",
+    }
+}
+
+fn attributes(map: &HashMap<String, String>) -> Vec<KeyValue> {
+    let sorted: BTreeMap<_, _> = map.iter().collect();
+    sorted
+        .into_iter()
+        .map(|(key, value)| KeyValue {
+            key: key.clone(),
+            value: Some(AnyValue {
+                string_value: value.clone(),
+            }),
+        })
+        .collect()
+}
+
+/// Empty metric of the OTLP shape a point's type and value call for.
+///
+/// Counters become monotonic cumulative sums and up-down counters non-monotonic ones; a
+/// histogram value is always sent as a histogram.
+fn empty_data(point: &TelemetryData) -> MetricData {
+    match (&point.metric_type, &point.value) {
+        (_, MetricValue::Histogram { .. }) | (MetricType::Histogram, _) => {
+            MetricData::Histogram(Histogram {
+                data_points: Vec::new(),
+                aggregation_temporality: CUMULATIVE,
+            })
+        }
+        (MetricType::Counter, _) => MetricData::Sum(Sum {
+            data_points: Vec::new(),
+            aggregation_temporality: CUMULATIVE,
+            is_monotonic: true,
+        }),
+        (MetricType::UpDownCounter, _) => MetricData::Sum(Sum {
+            data_points: Vec::new(),
+            aggregation_temporality: CUMULATIVE,
+            is_monotonic: false,
+        }),
+        (MetricType::Gauge, _) => MetricData::Gauge(Gauge {
+            data_points: Vec::new(),
+        }),
+    }
+}
+
+/// Converts cumulative `(bound, count)` buckets into OTLP per-bucket counts.
+fn histogram_point(
+    point: &TelemetryData,
+    count: u64,
+    sum: f64,
+    buckets: &[(f64, u64)],
+) -> HistogramDataPoint {
+    let mut bucket_counts = Vec::with_capacity(buckets.len() + 1);
+    let mut below = 0;
+    for (_, cumulative) in buckets {
+        bucket_counts.push(cumulative.saturating_sub(below));
+        below = below.max(*cumulative);
+    }
+    bucket_counts.push(count.saturating_sub(below));
+    HistogramDataPoint {
+        attributes: attributes(&point.attributes),
+        time_unix_nano: point.timestamp,
+        count,
+        sum: Some(sum),
+        bucket_counts,
+        explicit_bounds: buckets.iter().map(|(bound, _)| *bound).collect(),
+    }
+}
+
+fn add_point(data: &mut MetricData, point: &TelemetryData) {
+    let number = |value| NumberDataPoint {
+        attributes: attributes(&point.attributes),
+        time_unix_nano: point.timestamp,
+        value: Some(value),
+    };
+    match (data, &point.value) {
+        (
+            MetricData::Histogram(histogram),
+            MetricValue::Histogram {
+                count,
+                sum,
+                buckets,
+            },
+        ) => histogram
+            .data_points
+            .push(histogram_point(point, *count, *sum, buckets)),
+        (MetricData::Histogram(histogram), MetricValue::Integer(value)) => histogram
+            .data_points
+            .push(histogram_point(point, 1, *value as f64, &[])),
+        (MetricData::Histogram(histogram), MetricValue::Float(value)) => histogram
+            .data_points
+            .push(histogram_point(point, 1, *value, &[])),
+        (MetricData::Gauge(Gauge { data_points }), value)
+        | (MetricData::Sum(Sum { data_points, .. }), value) => match value {
+            MetricValue::Integer(value) => data_points.push(number(NumberValue::AsInt(*value))),
+            MetricValue::Float(value) => data_points.push(number(NumberValue::AsDouble(*value))),
+            MetricValue::Histogram { .. } => {}
+        },
+    }
+}
+
+/// Builds an export request, grouping points by resource and then by metric name.
+pub fn export_request(points: &[TelemetryData]) -> ExportMetricsServiceRequest {
+    let mut resources: BTreeMap<Vec<(String, String)>, BTreeMap<String, Metric>> = BTreeMap::new();
+    for point in points {
+        let resource: Vec<(String, String)> = point
+            .resource
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect();
+        let metric = resources
+            .entry(resource)
+            .or_default()
+            .entry(point.name.clone())
+            .or_insert_with(|| Metric {
+                name: point.name.clone(),
+                description: point.description.clone().unwrap_or_default(),
+                unit: point.unit.as_ref().map(otlp_unit).unwrap_or("").to_string(),
+                data: Some(empty_data(point)),
+            });
+        if let Some(data) = metric.data.as_mut() {
+            add_point(data, point);
+        }
+    }
+
+    ExportMetricsServiceRequest {
+        resource_metrics: resources
+            .into_iter()
+            .map(|(resource, metrics)| ResourceMetrics {
+                resource: Some(Resource {
+                    attributes: attributes(&resource.into_iter().collect()),
+                }),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: Some(InstrumentationScope {
+                        name: SCOPE_NAME.to_string(),
+                        version: env!("CARGO_PKG_VERSION").to_string(),
+                    }),
+                    metrics: metrics.into_values().collect(),
+                }],
+            })
+            .collect(),
+    }
+}
+
+/// OTLP exporter settings.
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Collector URL, e.g. `http://otel-collector:4317`.
+    pub endpoint: String,
+    /// TLS settings; plaintext when None.
+    pub tls: Option<TlsConfig>,
+    /// Time between scheduled exports.
+    pub metrics_interval: Duration,
+    /// Most points sent in one request.
+    pub max_batch: usize,
+    /// Most points queued between exports; the oldest are dropped beyond it.
+    pub max_queued: usize,
+    /// Time allowed for each request.
+    pub request_timeout: Duration,
+    /// Retries of an export after the first attempt.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl OtlpConfig {
+    /// Settings for a collector with default interval, batching and retries.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            tls: None,
+            metrics_interval: Duration::from_secs(60),
+            max_batch: 1000,
+            max_queued: 10_000,
+            request_timeout: Duration::from_secs(10),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Exports telemetry to an OpenTelemetry collector.
+pub struct OtlpExporter {
+    endpoint: Endpoint,
+    config: OtlpConfig,
+    channel: Mutex<Option<Channel>>,
+    queued: Mutex<VecDeque<TelemetryData>>,
+    dropped: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl OtlpExporter {
+    /// Creates an exporter; the channel is opened on the first export.
+    ///
+    /// Fails with a configuration error if the endpoint URL, TLS settings or batch size are
+    /// invalid.
+    pub fn new(config: OtlpConfig) -> Result<Self, CaptureError> {
+        if config.max_batch == 0 || config.metrics_interval.is_zero() {
+            return Err(*CaptureError::new(
+                CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                "OTLP batch size and metrics interval must be positive",
+            ));
+        }
+        let mut endpoint = Endpoint::from_shared(config.endpoint.clone())
+            .map_err(|e| {
+                CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    &format!("Invalid OTLP endpoint '{}'", config.endpoint),
+                )
+                .with_source(e)
+            })?
+            .timeout(config.request_timeout);
+        if let Some(tls) = &config.tls {
+            endpoint = endpoint.tls_config(tls.to_client_config()?).map_err(|e| {
+                CaptureError::new(
+                    CaptureErrorKind::Configuration(ConfigErrorKind::InvalidValue),
+                    "Invalid OTLP TLS configuration",
+                )
+                .with_source(e)
+            })?;
+        }
+        Ok(Self {
+            endpoint,
+            config,
+            channel: Mutex::new(None),
+            queued: Mutex::new(VecDeque::new()),
+            dropped: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+        })
+    }
+
+    /// Queues a point for the next export, dropping the oldest if the queue is full.
+    pub fn record(&self, point: TelemetryData) {
+        let mut queued = self.queued.lock();
+        if queued.len() >= self.config.max_queued {
+            queued.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        queued.push_back(point);
+    }
+
+    /// Points waiting for the next export.
+    pub fn queued(&self) -> usize {
+        self.queued.lock().len()
+    }
+
+    /// Points dropped because the queue was full, their export failed or the collector
+    /// rejected them.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Exports every queued point in batches of `max_batch`.
+    ///
+    /// Points of a batch that could not be exported are dropped; the first failure is returned.
+    pub async fn flush(&self) -> Result<(), Error> {
+        let points: Vec<_> = self.queued.lock().drain(..).collect();
+        let mut first_error = None;
+        for batch in points.chunks(self.config.max_batch) {
+            if let Err(error) = self.export(batch).await {
+                self.dropped
+                    .fetch_add(batch.len() as u64, Ordering::Relaxed);
+                first_error.get_or_insert(error);
+            }
+        }
+        *self.last_error.lock() = first_error.as_ref().map(ToString::to_string);
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Exports points in one request, retrying while the collector is unavailable.
+    ///
+    /// Points rejected in a partial success are added to `dropped`; the accepted rest of the
+    /// request counts as exported.
+    pub async fn export(&self, points: &[TelemetryData]) -> Result<(), Error> {
+        let request = export_request(points);
+        let mut backoff = self.config.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            match self.send(request.clone()).await {
+                Ok(rejected) => {
+                    self.dropped.fetch_add(rejected, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err((retryable, message)) => {
+                    if !retryable || attempt >= self.config.max_retries {
+                        return Err(Error::Communication(format!(
+                            "OTLP export to {} failed after {} attempts: {}",
+                            self.config.endpoint,
+                            attempt + 1,
+                            message
+                        )));
+                    }
+                }
+            }
+            attempt += 1;
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(self.config.max_backoff);
+        }
+    }
+
+    /// Exports queued points every `metrics_interval` until the task is aborted.
+    pub fn spawn(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.metrics_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                // Failed batches are counted as dropped; the next interval exports new points
+                let _ = self.flush().await;
+            }
+        })
+    }
+
+    /// Sends one request, returning the number of points the collector rejected; errors carry
+    /// whether retrying may help.
+    async fn send(&self, request: ExportMetricsServiceRequest) -> Result<u64, (bool, String)> {
+        let channel = self.channel();
+        let mut client = Grpc::new(channel);
+        client.ready().await.map_err(|e| {
+            self.disconnect();
+            (true, format!("channel not ready: {}", e))
+        })?;
+        let response = client
+            .unary::<_, ExportMetricsServiceResponse, _>(
+                Request::new(request),
+                PathAndQuery::from_static(EXPORT_METRICS_PATH),
+                ProstCodec::default(),
+            )
+            .await
+            .map_err(|status| {
+                let retryable = is_retryable(&status);
+                if retryable {
+                    self.disconnect();
+                }
+                (
+                    retryable,
+                    format!("{:?}: {}", status.code(), status.message()),
+                )
+            })?;
+        Ok(response
+            .into_inner()
+            .partial_success
+            .map_or(0, |partial| partial.rejected_data_points.max(0) as u64))
+    }
+
+    fn channel(&self) -> Channel {
+        self.channel
+            .lock()
+            .get_or_insert_with(|| self.endpoint.connect_lazy())
+            .clone()
+    }
+
+    /// Drops a broken channel so the next export reconnects.
+    fn disconnect(&self) {
+        *self.channel.lock() = None;
+    }
+}
+
+#[async_trait]
+impl Lifecycle for OtlpExporter {
+    async fn initialize(&mut self) -> Result<(), Error> {
+        // The channel connects lazily on the first export
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> Result<(), Error> {
+        self.flush().await
+    }
+}
+
+impl HealthCheck for OtlpExporter {
+    fn health_check(&self) -> HealthStatus {
+        match self.last_error.lock().as_ref() {
+            Some(error) => HealthStatus::Degraded(error.clone()),
+            None => HealthStatus::Healthy,
+        }
+    }
+}
+
+#[async_trait]
+impl TelemetryManager for OtlpExporter {
+    fn collect_metric(&mut self, data: TelemetryData) -> Result<(), Error> {
+        self.record(data);
+        Ok(())
+    }
+
+    async fn report_metrics(&self) -> Result<(), Error> {
+        self.flush().await
+    }
+
+    /// Encodes the queued points without exporting them.
+    fn export_metrics(&self, format: ExportFormat) -> Result<Vec<u8>, Error> {
+        let points: Vec<_> = self.queued.lock().iter().cloned().collect();
+        match format {
+            ExportFormat::OpenTelemetry => Ok(export_request(&points).encode_to_vec()),
+            ExportFormat::Prometheus => Ok(prometheus::render(&points).into_bytes()),
+            ExportFormat::JSON => Err(Error::Configuration(
+                "The OTLP exporter does not encode metrics as JSON".to_string(),
+            )),
+        }
+    }
+}
+
+/// Statuses the OTLP specification marks as retryable.
+fn is_retryable(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable
+            | Code::DeadlineExceeded
+            | Code::Cancelled
+            | Code::Aborted
+            | Code::OutOfRange
+            | Code::ResourceExhausted
+            | Code::DataLoss
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::net::TcpListener;
+    use tonic::codegen::{http, Body, BoxFuture, Context, Poll, Service, StdError};
+    use tonic::server::{Grpc as ServerGrpc, NamedService, UnaryService};
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+
+    fn point(name: &str, metric_type: MetricType, value: MetricValue) -> TelemetryData {
+        TelemetryData {
+            timestamp: 1_700_000_000_000_000_000,
+            name: name.to_string(),
+            description: Some("Test metric".to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type,
+            value,
+            attributes: HashMap::from([("interface".to_string(), "eth0".to_string())]),
+            resource: Some(HashMap::from([(
+                "service.name".to_string(),
+                "sparktrap".to_string(),
+            )])),
+        }
+    }
+
+    fn metrics(request: &ExportMetricsServiceRequest) -> &[Metric] {
+        &request.resource_metrics[0].scope_metrics[0].metrics
+    }
+
+    #[test]
+    fn test_unit_and_type_mapping() {
+        let units = [
+            (MetricUnit::Nanoseconds, "ns"),
+            (MetricUnit::Microseconds, "us"),
+            (MetricUnit::Milliseconds, "ms"),
+            (MetricUnit::Seconds, "s"),
+            (MetricUnit::Bytes, "By"),
+            (MetricUnit::Kilobytes, "kBy"),
+            (MetricUnit::Megabytes, "MBy"),
+            (MetricUnit::Gigabytes, "GBy"),
+            (MetricUnit::PacketsPerSecond, "{packet}/s"),
+            (MetricUnit::BytesPerSecond, "By/s"),
+            (MetricUnit::Percent, "%"),
+            (MetricUnit::Count, "1"),
+        ];
+        let types = [
+            MetricType::Counter,
+            MetricType::UpDownCounter,
+            MetricType::Gauge,
+            MetricType::Histogram,
+        ];
+        for (unit, expected_unit) in units {
+            for metric_type in types.clone() {
+                let mut data = point("m", metric_type.clone(), MetricValue::Integer(5));
+                data.unit = Some(unit.clone());
+                let request = export_request(&[data]);
+                let metric = &metrics(&request)[0];
+                assert_eq!(metric.unit, expected_unit, "{:?}", unit);
+
+                match (metric_type, metric.data.as_ref().unwrap()) {
+                    (MetricType::Counter, MetricData::Sum(sum)) => {
+                        assert!(sum.is_monotonic);
+                        assert_eq!(sum.aggregation_temporality, CUMULATIVE);
+                        assert_eq!(sum.data_points[0].value, Some(NumberValue::AsInt(5)));
+                    }
+                    (MetricType::UpDownCounter, MetricData::Sum(sum)) => {
+                        assert!(!sum.is_monotonic)
+                    }
+                    (MetricType::Gauge, MetricData::Gauge(gauge)) => {
+                        assert_eq!(gauge.data_points[0].value, Some(NumberValue::AsInt(5)))
+                    }
+                    (MetricType::Histogram, MetricData::Histogram(histogram)) => {
+                        assert_eq!(histogram.data_points[0].count, 1)
+                    }
+                    (metric_type, data) => panic!("{:?} mapped to {:?}", metric_type, data),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_histogram_preserves_buckets() {
+        let buckets = vec![(0.5, 1), (1.0, 3), (5.0, 3)];
+        let request = export_request(&[point(
+            "latency",
+            MetricType::Histogram,
+            MetricValue::Histogram {
+                count: 4,
+                sum: 7.5,
+                buckets: buckets.clone(),
+            },
+        )]);
+        let Some(MetricData::Histogram(histogram)) = &metrics(&request)[0].data else {
+            panic!("not a histogram");
+        };
+        let data_point = &histogram.data_points[0];
+        assert_eq!(data_point.explicit_bounds, vec![0.5, 1.0, 5.0]);
+        assert_eq!(data_point.bucket_counts, vec![1, 2, 0, 1]);
+        assert_eq!((data_point.count, data_point.sum), (4, Some(7.5)));
+
+        // Summing the OTLP counts gives back the cumulative buckets
+        let cumulative: Vec<_> = data_point
+            .explicit_bounds
+            .iter()
+            .zip(data_point.bucket_counts.iter().scan(0, |total, count| {
+                *total += count;
+                Some(*total)
+            }))
+            .map(|(bound, count)| (*bound, count))
+            .collect();
+        assert_eq!(cumulative, buckets);
+    }
+
+    #[test]
+    fn test_points_grouped_by_resource_and_name() {
+        let mut other = point("drops", MetricType::Counter, MetricValue::Integer(2));
+        other.resource = None;
+        let request = export_request(&[
+            point("drops", MetricType::Counter, MetricValue::Integer(1)),
+            point("drops", MetricType::Counter, MetricValue::Float(1.5)),
+            point("depth", MetricType::Gauge, MetricValue::Integer(3)),
+            other,
+        ]);
+
+        assert_eq!(request.resource_metrics.len(), 2);
+        let tagged = request
+            .resource_metrics
+            .iter()
+            .find(|resource| !resource.resource.as_ref().unwrap().attributes.is_empty())
+            .unwrap();
+        let attribute = &tagged.resource.as_ref().unwrap().attributes[0];
+        assert_eq!(attribute.key, "service.name");
+        let metrics = &tagged.scope_metrics[0].metrics;
+        assert_eq!(metrics.len(), 2);
+        let Some(MetricData::Sum(sum)) = &metrics[1].data else {
+            panic!("not a sum");
+        };
+        assert_eq!(sum.data_points.len(), 2);
+        assert_eq!(sum.data_points[0].attributes[0].key, "interface");
+    }
+
+    /// In-process collector that fails the first `failures` exports with `code` and reports
+    /// `rejected` points of every accepted export as a partial success.
+    #[derive(Clone)]
+    struct MockCollector {
+        received: Arc<Mutex<Vec<ExportMetricsServiceRequest>>>,
+        failures: Arc<AtomicUsize>,
+        rejected: Arc<AtomicUsize>,
+        code: Code,
+    }
+
+    impl NamedService for MockCollector {
+        const NAME: &'static str = METRICS_SERVICE;
+    }
+
+    struct ExportSvc(MockCollector);
+
+    impl UnaryService<ExportMetricsServiceRequest> for ExportSvc {
+        type Response = ExportMetricsServiceResponse;
+        type Future = BoxFuture<tonic::Response<ExportMetricsServiceResponse>, Status>;
+
+        fn call(&mut self, request: Request<ExportMetricsServiceRequest>) -> Self::Future {
+            let collector = self.0.clone();
+            Box::pin(async move {
+                if collector
+                    .failures
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok()
+                {
+                    return Err(Status::new(collector.code, "collector busy"));
+                }
+                collector.received.lock().push(request.into_inner());
+                let rejected = collector.rejected.load(Ordering::SeqCst);
+                Ok(tonic::Response::new(ExportMetricsServiceResponse {
+                    partial_success: (rejected > 0).then(|| ExportMetricsPartialSuccess {
+                        rejected_data_points: rejected as i64,
+                        error_message: "out of range".to_string(),
+                    }),
+                }))
+            })
+        }
+    }
+
+    impl<B> Service<http::Request<B>> for MockCollector
+    where
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<B>) -> Self::Future {
+            let svc = ExportSvc(self.clone());
+            Box::pin(async move {
+                let mut grpc = ServerGrpc::new(ProstCodec::default());
+                Ok(grpc.unary(svc, request).await)
+            })
+        }
+    }
+
+    async fn exporter(failures: usize, code: Code) -> (MockCollector, OtlpExporter) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let collector = MockCollector {
+            received: Arc::default(),
+            failures: Arc::new(AtomicUsize::new(failures)),
+            rejected: Arc::default(),
+            code,
+        };
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        let router = Server::builder().add_service(collector.clone());
+        tokio::spawn(router.serve_with_incoming(incoming));
+
+        let mut config = OtlpConfig::new(format!("http://{}", addr));
+        config.max_batch = 2;
+        config.max_retries = 2;
+        config.initial_backoff = Duration::from_millis(10);
+        (collector, OtlpExporter::new(config).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_flush_batches_and_retries_unavailable() {
+        let (collector, exporter) = exporter(1, Code::Unavailable).await;
+        for value in 0..3 {
+            exporter.record(point(
+                "drops",
+                MetricType::Counter,
+                MetricValue::Integer(value),
+            ));
+        }
+        exporter.flush().await.unwrap();
+
+        let received = collector.received.lock();
+        assert_eq!(received.len(), 2);
+        let Some(MetricData::Sum(sum)) = &metrics(&received[0])[0].data else {
+            panic!("not a sum");
+        };
+        assert_eq!(sum.data_points.len(), 2);
+        assert_eq!(exporter.queued(), 0);
+        assert_eq!(exporter.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_export_is_communication_error() {
+        let (collector, exporter) = exporter(1, Code::PermissionDenied).await;
+        exporter.record(point("drops", MetricType::Counter, MetricValue::Integer(1)));
+        let error = exporter.flush().await.unwrap_err();
+        assert!(
+            matches!(&error, Error::Communication(message) if message.contains("after 1 attempts")),
+            "{:?}",
+            error
+        );
+        assert_eq!(exporter.dropped(), 1);
+        assert!(collector.received.lock().is_empty());
+
+        let exporter = exporter_unreachable().await;
+        let error = exporter
+            .export(&[point("drops", MetricType::Counter, MetricValue::Integer(1))])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Communication(_)));
+    }
+
+    #[tokio::test]
+    async fn test_partial_success_drops_only_rejected_points() {
+        let (collector, exporter) = exporter(0, Code::Ok).await;
+        collector.rejected.store(1, Ordering::SeqCst);
+        for value in 0..2 {
+            exporter.record(point(
+                "drops",
+                MetricType::Counter,
+                MetricValue::Integer(value),
+            ));
+        }
+        exporter.flush().await.unwrap();
+
+        // Sent once, without retrying the accepted point
+        assert_eq!(collector.received.lock().len(), 1);
+        assert_eq!(exporter.dropped(), 1);
+        assert!(matches!(exporter.health_check(), HealthStatus::Healthy));
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_manager_exports_and_reports() {
+        let (collector, mut exporter) = exporter(1, Code::PermissionDenied).await;
+        exporter
+            .collect_metric(point("depth", MetricType::Gauge, MetricValue::Integer(3)))
+            .unwrap();
+
+        let encoded = exporter
+            .export_metrics(ExportFormat::OpenTelemetry)
+            .unwrap();
+        let decoded = ExportMetricsServiceRequest::decode(encoded.as_slice()).unwrap();
+        assert_eq!(
+            decoded,
+            export_request(&[point("depth", MetricType::Gauge, MetricValue::Integer(3))])
+        );
+        let text = exporter.export_metrics(ExportFormat::Prometheus).unwrap();
+        assert!(String::from_utf8(text)
+            .unwrap()
+            .contains("depth{interface=\"eth0\"} 3"));
+        assert!(exporter.export_metrics(ExportFormat::JSON).is_err());
+        assert_eq!(exporter.queued(), 1);
+
+        assert!(exporter.report_metrics().await.is_err());
+        assert!(matches!(exporter.health_check(), HealthStatus::Degraded(_)));
+
+        exporter
+            .collect_metric(point("depth", MetricType::Gauge, MetricValue::Integer(4)))
+            .unwrap();
+        exporter.shutdown().await.unwrap();
+        assert_eq!(collector.received.lock().len(), 1);
+        assert!(matches!(exporter.health_check(), HealthStatus::Healthy));
+    }
+
+    async fn exporter_unreachable() -> OtlpExporter {
+        // Reserve an address with nothing listening on it
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let mut config = OtlpConfig::new(format!("http://{}", addr));
+        config.max_retries = 1;
+        config.initial_backoff = Duration::from_millis(10);
+        OtlpExporter::new(config).unwrap()
+    }
+
+    #[test]
+    fn test_invalid_configuration_rejected() {
+        assert!(OtlpExporter::new(OtlpConfig::new("not a url")).is_err());
+        let mut config = OtlpConfig::new("http://localhost:4317");
+        config.max_batch = 0;
+        assert!(OtlpExporter::new(config).is_err());
+    }
+}
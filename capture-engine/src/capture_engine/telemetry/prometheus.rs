@@ -0,0 +1,247 @@
+// telemetry/prometheus.rs
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::capture_engine::telemetry::traits::{MetricType, MetricValue, TelemetryData};
+
+/// Renders telemetry in the Prometheus text exposition format.
+///
+/// Metric names and label keys are sanitized to the Prometheus character set, so
+/// `capture.drops.policy` becomes `capture_drops_policy`. Data points sharing a name are grouped under one `HELP`/`TYPE` header.
+pub fn render(metrics: &[TelemetryData]) -> String {
+    let mut families: BTreeMap<String, Vec<&TelemetryData>> = BTreeMap::new();
+    for metric in metrics {
+        families
+            .entry(metric_name(&metric.name))
+            .or_default()
+            .push(metric);
+    }
+
+    let mut out = String::new();
+    for (name, points) in families {
+        let first = points[0];
+        if let Some(description) = &first.description {
+            let _ = writeln!(out, "# HELP {} {}", name, escape_help(description));
+        }
+        let _ = writeln!(out, "# TYPE {} {}", name, type_name(first));
+        for point in points {
+            write_point(&mut out, &name, point);
+        }
+    }
+    out
+}
+
+fn write_point(out: &mut String, name: &str, point: &TelemetryData) {
+    let labels: BTreeMap<String, &str> = point
+        .attributes
+        .iter()
+        .map(|(key, value)| (label_name(key), value.as_str()))
+        .collect();
+
+    match &point.value {
+        MetricValue::Integer(value) => {
+            let _ = writeln!(out, "{}{} {}", name, label_set(&labels, None), value);
+        }
+        MetricValue::Float(value) => {
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                name,
+                label_set(&labels, None),
+                float(*value)
+            );
+        }
+        MetricValue::Histogram {
+            count,
+            sum,
+            buckets,
+        } => {
+            for (bound, bucket_count) in buckets {
+                let le = float(*bound);
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {}",
+                    name,
+                    label_set(&labels, Some(&le)),
+                    bucket_count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{} {}",
+                name,
+                label_set(&labels, Some("+Inf")),
+                count
+            );
+            let _ = writeln!(
+                out,
+                "{}_sum{} {}",
+                name,
+                label_set(&labels, None),
+                float(*sum)
+            );
+            let _ = writeln!(out, "{}_count{} {}", name, label_set(&labels, None), count);
+        }
+    }
+}
+
+/// Type the point is exposed as, following its value: a histogram-typed point holding a single
+/// number has no buckets to expose, so it is a gauge.
+fn type_name(point: &TelemetryData) -> &'static str {
+    if matches!(point.value, MetricValue::Histogram { .. }) {
+        return "histogram";
+    }
+    match point.metric_type {
+        MetricType::Counter => "counter",
+        MetricType::UpDownCounter | MetricType::Gauge | MetricType::Histogram => "gauge",
+    }
+}
+
+fn label_set(labels: &BTreeMap<String, &str>, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn metric_name(name: &str) -> String {
+    sanitize(name, true)
+}
+
+/// Label keys follow the metric name rules except that colons are reserved for metric names.
+fn label_name(key: &str) -> String {
+    sanitize(key, false)
+}
+
+fn sanitize(name: &str, allow_colon: bool) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || (allow_colon && c == ':') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+fn float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_help(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_label(text: &str) -> String {
+    escape_help(text).replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_engine::telemetry::traits::MetricUnit;
+    use std::collections::HashMap;
+
+    fn metric(name: &str, metric_type: MetricType, value: MetricValue) -> TelemetryData {
+        TelemetryData {
+            timestamp: 0,
+            name: name.to_string(),
+            description: Some("Test metric".to_string()),
+            unit: Some(MetricUnit::Count),
+            metric_type,
+            value,
+            attributes: HashMap::new(),
+            resource: None,
+        }
+    }
+
+    #[test]
+    fn test_counters_and_gauges() {
+        let mut labelled = metric(
+            "capture.drops",
+            MetricType::Counter,
+            MetricValue::Integer(3),
+        );
+        labelled
+            .attributes
+            .insert("reason".to_string(), "po\"licy".to_string());
+        let text = render(&[
+            metric(
+                "capture.drops",
+                MetricType::Counter,
+                MetricValue::Integer(7),
+            ),
+            labelled,
+            metric("queue.depth", MetricType::Gauge, MetricValue::Float(1.5)),
+        ]);
+
+        assert_eq!(text.matches("# TYPE capture_drops counter").count(), 1);
+        assert!(text.contains("capture_drops 7\n"));
+        assert!(text.contains("capture_drops{reason=\"po\\\"licy\"} 3\n"));
+        assert!(text.contains("# TYPE queue_depth gauge\nqueue_depth 1.5\n"));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let text = render(&[metric(
+            "latency",
+            MetricType::Histogram,
+            MetricValue::Histogram {
+                count: 4,
+                sum: 2.5,
+                buckets: vec![(0.5, 1), (1.0, 3)],
+            },
+        )]);
+
+        assert!(text.contains("latency_bucket{le=\"0.5\"} 1\n"));
+        assert!(text.contains("latency_bucket{le=\"+Inf\"} 4\n"));
+        assert!(text.contains("latency_sum 2.5\nlatency_count 4\n"));
+    }
+
+    #[test]
+    fn test_histogram_typed_scalar_is_a_gauge() {
+        let text = render(&[metric(
+            "latency.last",
+            MetricType::Histogram,
+            MetricValue::Float(0.25),
+        )]);
+        assert!(text.contains("# TYPE latency_last gauge\nlatency_last 0.25\n"));
+    }
+
+    #[test]
+    fn test_label_keys_are_sanitized() {
+        let mut point = metric("drops", MetricType::Counter, MetricValue::Integer(1));
+        point
+            .attributes
+            .insert("net:iface.name".to_string(), "eth0".to_string());
+        point
+            .attributes
+            .insert("0queue".to_string(), "1".to_string());
+        let text = render(&[point]);
+        assert!(
+            text.contains("drops{_0queue=\"1\",net_iface_name=\"eth0\"} 1\n"),
+            "{}",
+            text
+        );
+    }
+}
@@ -6,6 +6,7 @@
 //! control plane.
 
 pub mod capture_engine;
+pub mod pressure;
 pub mod traits;
 
 // Version and build information
@@ -0,0 +1,210 @@
+// pressure.rs
+//! Shared pressure-level tracking for `PressureAware` components.
+use crate::traits::{Error, PressureLevel, PressureThresholds, ValidationErrorKind};
+
+/// Default margin utilization must fall below a threshold before the level drops.
+pub const DEFAULT_HYSTERESIS: f32 = 0.05;
+
+/// Default thresholds for components that do not configure their own.
+pub fn default_thresholds() -> PressureThresholds {
+    PressureThresholds {
+        elevated: 0.7,
+        critical: 0.85,
+        overflow: 0.95,
+    }
+}
+
+/// Checks that thresholds are ascending and within (0, 1].
+pub fn validate_thresholds(thresholds: &PressureThresholds) -> Result<(), Error> {
+    let ordered = 0.0 < thresholds.elevated
+        && thresholds.elevated <= thresholds.critical
+        && thresholds.critical <= thresholds.overflow
+        && thresholds.overflow <= 1.0;
+    if ordered {
+        Ok(())
+    } else {
+        Err(Error::Validation(ValidationErrorKind::ConstraintViolation))
+    }
+}
+
+/// Maps utilization to a pressure level without hysteresis.
+pub fn level_for(thresholds: &PressureThresholds, utilization: f32) -> PressureLevel {
+    if utilization >= thresholds.overflow {
+        PressureLevel::Overflow
+    } else if utilization >= thresholds.critical {
+        PressureLevel::Critical
+    } else if utilization >= thresholds.elevated {
+        PressureLevel::Elevated
+    } else {
+        PressureLevel::Normal
+    }
+}
+
+fn rank(level: &PressureLevel) -> u8 {
+    match level {
+        PressureLevel::Normal => 0,
+        PressureLevel::Elevated => 1,
+        PressureLevel::Critical => 2,
+        PressureLevel::Overflow => 3,
+    }
+}
+
+/// Tracks a component's pressure level with hysteresis.
+///
+/// Rising pressure is reported as soon as a threshold is crossed. Falling pressure is only
+/// reported once utilization drops `hysteresis` below the threshold of the current level, so
+/// utilization hovering around a boundary does not make the level flap.
+#[derive(Debug, Clone)]
+pub struct PressureTracker {
+    thresholds: PressureThresholds,
+    hysteresis: f32,
+    level: PressureLevel,
+}
+
+impl PressureTracker {
+    /// Creates a tracker starting at `Normal`.
+    pub fn new(thresholds: PressureThresholds, hysteresis: f32) -> Result<Self, Error> {
+        validate_thresholds(&thresholds)?;
+        if !(0.0..1.0).contains(&hysteresis) {
+            return Err(Error::Validation(ValidationErrorKind::ConstraintViolation));
+        }
+
+        Ok(Self {
+            thresholds,
+            hysteresis,
+            level: PressureLevel::Normal,
+        })
+    }
+
+    /// Updates the tracker with the latest utilization and returns the resulting level.
+    pub fn update(&mut self, utilization: f32) -> PressureLevel {
+        let raw = level_for(&self.thresholds, utilization);
+        if rank(&raw) >= rank(&self.level) {
+            self.level = raw;
+            return self.level.clone();
+        }
+
+        while let Some(threshold) = self.threshold(&self.level) {
+            if utilization >= threshold - self.hysteresis {
+                break;
+            }
+            self.level = match self.level {
+                PressureLevel::Overflow => PressureLevel::Critical,
+                PressureLevel::Critical => PressureLevel::Elevated,
+                _ => PressureLevel::Normal,
+            };
+        }
+        self.level.clone()
+    }
+
+    /// Returns the current level.
+    pub fn level(&self) -> &PressureLevel {
+        &self.level
+    }
+
+    /// Returns the configured thresholds.
+    pub fn thresholds(&self) -> &PressureThresholds {
+        &self.thresholds
+    }
+
+    /// Replaces the thresholds, keeping the current level until the next update.
+    pub fn set_thresholds(&mut self, thresholds: PressureThresholds) -> Result<(), Error> {
+        validate_thresholds(&thresholds)?;
+        self.thresholds = thresholds;
+        Ok(())
+    }
+
+    fn threshold(&self, level: &PressureLevel) -> Option<f32> {
+        match level {
+            PressureLevel::Normal => None,
+            PressureLevel::Elevated => Some(self.thresholds.elevated),
+            PressureLevel::Critical => Some(self.thresholds.critical),
+            PressureLevel::Overflow => Some(self.thresholds.overflow),
+        }
+    }
+}
+
+impl Default for PressureTracker {
+    fn default() -> Self {
+        Self {
+            thresholds: default_thresholds(),
+            hysteresis: DEFAULT_HYSTERESIS,
+            level: PressureLevel::Normal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level_changes(tracker: &mut PressureTracker, samples: &[f32]) -> usize {
+        let mut previous = tracker.level().clone();
+        let mut changes = 0;
+        for &sample in samples {
+            let level = tracker.update(sample);
+            if level != previous {
+                changes += 1;
+                previous = level;
+            }
+        }
+        changes
+    }
+
+    #[test]
+    fn test_noise_around_threshold_does_not_flap() {
+        let mut tracker = PressureTracker::default();
+        // Utilization jitters +/- 0.02 around the elevated threshold of 0.7
+        let noise: Vec<f32> = (0..200)
+            .map(|i| 0.7 + if i % 2 == 0 { 0.02 } else { -0.02 })
+            .collect();
+
+        assert_eq!(level_changes(&mut tracker, &noise), 1);
+        assert_eq!(*tracker.level(), PressureLevel::Elevated);
+    }
+
+    #[test]
+    fn test_without_hysteresis_noise_flaps() {
+        let mut tracker = PressureTracker::new(default_thresholds(), 0.0).unwrap();
+        let noise: Vec<f32> = (0..10)
+            .map(|i| 0.7 + if i % 2 == 0 { 0.02 } else { -0.02 })
+            .collect();
+
+        assert_eq!(level_changes(&mut tracker, &noise), 10);
+    }
+
+    #[test]
+    fn test_level_drops_after_margin() {
+        let mut tracker = PressureTracker::default();
+        assert_eq!(tracker.update(0.9), PressureLevel::Critical);
+        assert_eq!(tracker.update(0.81), PressureLevel::Critical);
+        assert_eq!(tracker.update(0.79), PressureLevel::Elevated);
+        assert_eq!(tracker.update(0.66), PressureLevel::Elevated);
+        assert_eq!(tracker.update(0.64), PressureLevel::Normal);
+    }
+
+    #[test]
+    fn test_rising_pressure_is_immediate() {
+        let mut tracker = PressureTracker::default();
+        assert_eq!(tracker.update(0.5), PressureLevel::Normal);
+        assert_eq!(tracker.update(0.96), PressureLevel::Overflow);
+    }
+
+    #[test]
+    fn test_large_drop_skips_levels() {
+        let mut tracker = PressureTracker::default();
+        tracker.update(1.0);
+        assert_eq!(tracker.update(0.1), PressureLevel::Normal);
+    }
+
+    #[test]
+    fn test_invalid_configuration() {
+        let inverted = PressureThresholds {
+            elevated: 0.9,
+            critical: 0.5,
+            overflow: 1.0,
+        };
+        assert!(PressureTracker::new(inverted, 0.05).is_err());
+        assert!(PressureTracker::new(default_thresholds(), 1.5).is_err());
+    }
+}
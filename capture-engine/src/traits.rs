@@ -107,7 +107,10 @@ pub enum PerformanceIssue {
 #[derive(Debug, Clone)]
 pub struct Packet<'a> {
     pub timestamp: u64,
+    /// Captured bytes; shorter than `length` when the packet was truncated.
     pub data: &'a [u8],
+    /// Length of the packet on the wire.
+    pub length: usize,
     pub metadata: PacketMetadata,
     pub buffer_id: BufferId,
 }
@@ -123,6 +126,18 @@ pub struct PacketMetadata {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BufferId(u64);
 
+impl BufferId {
+    /// Creates a buffer identifier.
+    pub fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// Returns the raw identifier value.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
 /// Represents the pressure status of a resource.
 #[derive(Debug, Clone)]
 pub struct PressureStatus {